@@ -0,0 +1,91 @@
+//! Benchmarks for request and response bodies.
+//!
+//! `echo_1mb` routes a request whose handler echoes a 1 MB body back. With `Bytes`
+//! bodies, the request body is handed to the handler and returned in the response
+//! without being copied. The `response_*` cases compare building a response with
+//! a static body against an owned one. The `json_*` cases compare three consumers
+//! of a JSON body sharing the request's parsed value against each parsing it.
+
+use bytes::Bytes;
+use criterion::{Criterion, criterion_group, criterion_main};
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Request, Response, StatusCode};
+use std::hint::black_box;
+
+struct EchoHandler;
+impl HttpHandler for EchoHandler {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        HttpResponse::new(StatusCode::OK, req.body)
+    }
+}
+
+fn build_router() -> Router {
+    let config = r#"{"endpoints": [{"method": "POST", "path": "/echo", "controller": "echo", "description": ""}]}"#;
+    let config_path = std::env::temp_dir().join("generic_http_router_bench_body.json");
+    std::fs::write(&config_path, config).expect("Failed to write the benchmark config");
+
+    let mut router = Router::new(&config_path).expect("Failed to load the benchmark config");
+    router.register_handler("echo", EchoHandler);
+    router
+}
+
+fn echo(c: &mut Criterion) {
+    let router = build_router();
+    let body = Bytes::from(vec![b'x'; 1024 * 1024]);
+
+    c.bench_function("echo_1mb", |b| {
+        b.iter(|| {
+            let req = Request::post("/echo").body(body.clone()).unwrap();
+            router.route(black_box(req))
+        })
+    });
+}
+
+fn responses(c: &mut Criterion) {
+    c.bench_function("response_static", |b| {
+        b.iter(|| {
+            let res = HttpResponse::new_static(StatusCode::OK, black_box(b"{\"status\":\"ok\"}"));
+            Response::<Bytes>::from(res)
+        })
+    });
+    c.bench_function("response_owned", |b| {
+        b.iter(|| {
+            let res = HttpResponse::new(StatusCode::OK, black_box(b"{\"status\":\"ok\"}").to_vec());
+            Response::<Bytes>::from(res)
+        })
+    });
+}
+
+fn json(c: &mut Criterion) {
+    let items: Vec<String> = (0..1000)
+        .map(|i| format!(r#"{{"id":{i},"name":"item {i}","tags":["a","b"]}}"#))
+        .collect();
+    let body = Bytes::from(format!("[{}]", items.join(",")));
+    let request = || {
+        HttpRequest::builder()
+            .method("POST")
+            .body(body.clone())
+            .build()
+            .unwrap()
+    };
+
+    c.bench_function("json_shared_3_consumers", |b| {
+        b.iter(|| {
+            let req = request();
+            for _ in 0..3 {
+                black_box(req.json_value().unwrap());
+            }
+        })
+    });
+    c.bench_function("json_separate_3_consumers", |b| {
+        b.iter(|| {
+            let req = request();
+            for _ in 0..3 {
+                black_box(serde_json::from_slice::<serde_json::Value>(&req.body).unwrap());
+            }
+        })
+    });
+}
+
+criterion_group!(benches, echo, responses, json);
+criterion_main!(benches);
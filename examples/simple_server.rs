@@ -1,7 +1,11 @@
 //! An example of how to use the `generic-http-router` library with `hyper`.
 
-use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use bytes::Bytes;
+use generic_http_router::{
+    Body as RouterBody, HttpHandler, HttpRequest, HttpResponse, RequestContext, Router,
+};
 use http::StatusCode;
+use hyper::server::conn::AddrStream;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Body, Request, Response, Server};
 use std::convert::Infallible;
@@ -13,7 +17,11 @@ use std::sync::Arc;
 /// A handler for fetching all users.
 struct GetAllUsersHandler;
 impl HttpHandler for GetAllUsersHandler {
-    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        // The server passes the client's address in the request context.
+        if let Some(addr) = req.context().and_then(|ctx| ctx.remote_addr) {
+            println!("Listing users for {}", addr);
+        }
         HttpResponse::new(StatusCode::OK, b"Returning all users".to_vec())
     }
 }
@@ -22,7 +30,7 @@ impl HttpHandler for GetAllUsersHandler {
 struct GetUserByIdHandler;
 impl HttpHandler for GetUserByIdHandler {
     fn handle(&self, req: HttpRequest) -> HttpResponse {
-        // Extract the 'id' parameter that `matchit` parsed for us.
+        // Extract the 'id' parameter that the router parsed for us.
         if let Some(id) = req.params.get("id") {
             let body = format!("Fetching user with id: {}", id);
             HttpResponse::new(StatusCode::OK, body.into_bytes())
@@ -46,25 +54,81 @@ impl HttpHandler for CreateUserHandler {
     }
 }
 
+/// A handler exporting all users as CSV, streamed chunk by chunk.
+struct ExportUsersHandler;
+impl HttpHandler for ExportUsersHandler {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        // In a real application, the rows would come from a database cursor.
+        let header = std::iter::once(Ok(Bytes::from_static(b"id,name\n")));
+        let rows = (1..=1000).map(|id| Ok(Bytes::from(format!("{id},user{id}\n"))));
+        let mut response = HttpResponse::streaming(StatusCode::OK, header.chain(rows));
+        response.add_header(http::header::CONTENT_TYPE, "text/csv");
+        response
+    }
+}
+
+/// Converts our router's response body into a Hyper body.
+///
+/// Buffered bodies are passed through as is. Streamed bodies are sent with
+/// chunked transfer encoding: since the chunks come from a blocking iterator,
+/// they are pulled on a blocking thread and forwarded through a channel.
+fn into_hyper_body(body: RouterBody) -> Body {
+    let chunks = match body {
+        RouterBody::Full(bytes) => return Body::from(bytes),
+        RouterBody::Stream(chunks) => chunks,
+    };
+
+    let (mut sender, hyper_body) = Body::channel();
+    let runtime = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || {
+        for chunk in chunks {
+            match chunk {
+                Ok(chunk) => {
+                    if runtime.block_on(sender.send_data(chunk)).is_err() {
+                        // The client went away.
+                        return;
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Response stream failed: {e}");
+                    // Aborting makes Hyper close the connection instead of
+                    // ending the chunked body, so the client sees the failure.
+                    sender.abort();
+                    return;
+                }
+            }
+        }
+    });
+    hyper_body
+}
+
 /// The main service function that processes each incoming request.
 async fn handle_request(
     hyper_req: Request<Body>,
     router: Arc<Router>,
+    remote_addr: SocketAddr,
 ) -> Result<Response<Body>, Infallible> {
-    // Convert Hyper's request body into a Vec<u8> for our router.
+    // Collect Hyper's request body into `Bytes` for our router.
     // Extract the body while preserving the request parts
-    let (parts, body) = hyper_req.into_parts();
+    let (mut parts, body) = hyper_req.into_parts();
+
+    // Tell the handlers who the client is, through `req.context()`.
+    parts
+        .extensions
+        .insert(RequestContext::new().with_remote_addr(remote_addr));
     let body_bytes = hyper::body::to_bytes(body).await.unwrap();
 
-    // Create the request for our router using the original parts
-    let req_for_router = Request::from_parts(parts, body_bytes.to_vec());
+    // Create the request for our router using the original parts. The body
+    // is passed through as `Bytes`, without copying.
+    let req_for_router = Request::from_parts(parts, body_bytes);
 
-    // Use the router to handle the request.
-    let response = router.route(req_for_router);
+    // Use the router to handle the request, passing streamed bodies through.
+    let response = router.route_streaming(req_for_router);
 
-    // Convert our router's response back into a Hyper response.
+    // Convert our router's response back into a Hyper response, again without
+    // copying the body.
     let (parts, body) = response.into_parts();
-    Ok(Response::from_parts(parts, Body::from(body)))
+    Ok(Response::from_parts(parts, into_hyper_body(body)))
 }
 
 #[tokio::main]
@@ -80,20 +144,37 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 
     // 2. Register all your handler implementations.
     //    The string key MUST match the `controller` value in `routes.json`.
-    router.register("users_controller::get_all", Box::new(GetAllUsersHandler));
-    router.register("users_controller::get_by_id", Box::new(GetUserByIdHandler));
-    router.register("users_controller::create", Box::new(CreateUserHandler));
+    router.register_handler("users_controller::get_all", GetAllUsersHandler);
+    router.register_handler("users_controller::get_by_id", GetUserByIdHandler);
+    router.register_handler("users_controller::create", CreateUserHandler);
+    router.register_handler("users_controller::export", ExportUsersHandler);
+
+    // Print the routes, now that their handlers are registered.
+    print!("{}", router.routes_summary());
+    #[cfg(feature = "logging")]
+    router.log_routes();
+
+    // Build what the router otherwise builds on first use, and check every
+    // route, before taking traffic.
+    let report = router.warm_up();
+    for problem in &report.problems {
+        eprintln!(
+            "Warning: {} {}: {}",
+            problem.method, problem.pattern, problem.problem
+        );
+    }
 
     // Wrap the router in an Arc to share it safely across threads.
     let shared_router = Arc::new(router);
 
     // --- Hyper Server Setup ---
     // Create a service that uses our `handle_request` function.
-    let make_svc = make_service_fn(move |_conn| {
+    let make_svc = make_service_fn(move |conn: &AddrStream| {
         let router_clone = shared_router.clone();
-        async {
+        let remote_addr = conn.remote_addr();
+        async move {
             Ok::<_, Infallible>(service_fn(move |req| {
-                handle_request(req, router_clone.clone())
+                handle_request(req, router_clone.clone(), remote_addr)
             }))
         }
     });
@@ -104,6 +185,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     println!("Try running:");
     println!("  curl http://{}/users", addr);
     println!("  curl http://{}/users/123", addr);
+    println!("  curl http://{}/users/export", addr);
     println!(
         "  curl -X POST -d '{{\"name\":\"test\"}}' http://{}/users",
         addr
@@ -0,0 +1,15 @@
+//! Parses arbitrary bytes as a configuration: `Config` and `Router` must
+//! reject invalid ones with an error, never a panic.
+
+#![no_main]
+
+use generic_http_router::Router;
+use generic_http_router::config::Config;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = serde_json::from_slice::<Config>(data);
+    if let Ok(json) = std::str::from_utf8(data) {
+        let _ = Router::from_json_str(json);
+    }
+});
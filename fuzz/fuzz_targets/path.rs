@@ -0,0 +1,16 @@
+//! Normalizes and percent-decodes arbitrary request paths.
+
+#![no_main]
+
+use generic_http_router::path::{self, PathDecoding};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    if let Some(normalized) = path::normalize(input) {
+        // A normalized path is normal.
+        assert_eq!(path::normalize(&normalized).as_deref(), Some(&*normalized));
+    }
+    let _ = path::percent_decode(input, PathDecoding::Reject);
+    // Lossy decoding accepts every path.
+    assert!(path::percent_decode(input, PathDecoding::Lossy).is_some());
+});
@@ -0,0 +1,87 @@
+//! Routes arbitrary requests, with any method, path, headers and body, through
+//! a router with a configuration exercising most routing features, including
+//! signed webhooks, response caching and circuit breakers.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use bytes::Bytes;
+use generic_http_router::signature::SecretProvider;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Request, StatusCode};
+use libfuzzer_sys::fuzz_target;
+use std::sync::OnceLock;
+
+#[derive(Arbitrary, Debug)]
+struct Input<'a> {
+    method: &'a str,
+    path: &'a str,
+    headers: Vec<(&'a str, &'a [u8])>,
+    body: &'a [u8],
+}
+
+/// Echoes the parameters and the body of the request.
+struct Echo;
+impl HttpHandler for Echo {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let params: Vec<String> = req.params.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        let _ = req.json::<serde_json::Value>();
+        HttpResponse::text(StatusCode::OK, params.join("&"))
+    }
+}
+
+/// Fails with a 500 Internal Server Error for a body starting with `!`, to trip
+/// the circuit breaker.
+struct Flaky;
+impl HttpHandler for Flaky {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        match req.body.first() {
+            Some(b'!') => HttpResponse::internal_error("failed"),
+            _ => HttpResponse::ok("ok"),
+        }
+    }
+}
+
+/// Returns the same secret for every signed route.
+struct Secret;
+impl SecretProvider for Secret {
+    fn secret(&self, _name: &str) -> Option<Vec<u8>> {
+        Some(b"fuzz".to_vec())
+    }
+}
+
+fn router() -> &'static Router {
+    static ROUTER: OnceLock<Router> = OnceLock::new();
+    ROUTER.get_or_init(|| {
+        let mut router = Router::from_json_str(include_str!("../routes.json"))
+            .expect("The fuzz configuration is valid");
+        router.register_handler("echo", Echo);
+        router.register_handler("flaky", Flaky);
+        router.set_secret_provider(Secret);
+        router.set_cache_max_bytes(64 * 1024);
+        router.register_guard(
+            "token",
+            Box::new(|req: &HttpRequest| match req.headers.get("x-token") {
+                Some(_) => Ok(()),
+                None => Err(HttpResponse::text(StatusCode::FORBIDDEN, "")),
+            }),
+        );
+        router.set_auto_options(true);
+        router.use_problem_responses(true);
+        router.enable_404_suggestions(true);
+        router.enable_health_endpoints(Default::default());
+        router
+    })
+}
+
+fuzz_target!(|input: Input<'_>| {
+    let mut builder = Request::builder().method(input.method).uri(input.path);
+    for (name, value) in input.headers {
+        builder = builder.header(name, value);
+    }
+    // Requests `http` can't represent never reach a router.
+    let Ok(req) = builder.body(Bytes::copy_from_slice(input.body)) else {
+        return;
+    };
+    let _ = router().route(req);
+});
@@ -0,0 +1,174 @@
+//! Defines `Accept` parsing and media type negotiation.
+
+use crate::language::parse_quality;
+use crate::request::HttpRequest;
+use http::HeaderValue;
+use http::header::ACCEPT;
+
+/// A media range of an `Accept` header, e.g. `text/html;level=1;q=0.8`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaRange {
+    /// The type, lowercased, e.g. `text`, or `*` for any type.
+    pub media_type: String,
+    /// The subtype, lowercased, e.g. `html`, or `*` for any subtype.
+    pub subtype: String,
+    /// The parameters other than the quality, with lowercased names, e.g.
+    /// `level=1`.
+    pub params: Vec<(String, String)>,
+    /// The quality value, from 0 to 1 (1 if not specified). A range with a
+    /// quality of 0 marks the media types it matches as not acceptable.
+    pub quality: f32,
+}
+
+impl MediaRange {
+    /// How specific the range is: 0 for `*/*`, 1 for `type/*`, 2 for a media
+    /// type and 3 for a media type with parameters.
+    fn specificity(&self) -> u8 {
+        match (self.media_type.as_str(), self.subtype.as_str()) {
+            ("*", _) => 0,
+            (_, "*") => 1,
+            _ if self.params.is_empty() => 2,
+            _ => 3,
+        }
+    }
+
+    /// Returns `true` if the range matches `media_type`, e.g. `text/*` matches
+    /// `text/html`, ignoring case. A range with parameters only matches media
+    /// types with the same parameters.
+    pub fn matches(&self, media_type: &str) -> bool {
+        let Some(offered) = parse_media_type(media_type) else {
+            return false;
+        };
+        (self.media_type == "*" || self.media_type == offered.media_type)
+            && (self.subtype == "*" || self.subtype == offered.subtype)
+            && self
+                .params
+                .iter()
+                .all(|param| offered.params.contains(param))
+    }
+}
+
+impl HttpRequest {
+    /// Returns the media ranges of the `Accept` headers, sorted by decreasing
+    /// quality, then from the most to the least specific (`text/html` before
+    /// `text/*` before `*/*`); ranges tied on both keep the order they were
+    /// sent in. Malformed ranges are skipped, and so are parameters without a
+    /// value.
+    ///
+    /// A request without an `Accept` header accepts any media type, so it
+    /// gets a single `*/*` range.
+    pub fn accepts(&self) -> Vec<MediaRange> {
+        parse_accept(self.headers.get_all(ACCEPT))
+    }
+
+    /// Picks the media type of `offered` to respond with: the one with the
+    /// highest quality, taken from the most specific range of `Accept`
+    /// matching it, so `text/*;q=0.5, text/html` prefers `text/html` to
+    /// `text/plain`. Ties go to the type offered first. Types only matched by
+    /// ranges with `q=0`, or by none, are never picked.
+    ///
+    /// Returns `None` if no offered type is acceptable; respond with a 406 Not
+    /// Acceptable, or a default type, then. Either way, add `Accept` to the
+    /// response's `Vary` header with
+    /// [`HttpResponse::add_vary`](crate::HttpResponse::add_vary).
+    pub fn preferred_type<'a>(&self, offered: &[&'a str]) -> Option<&'a str> {
+        negotiate(&self.accepts(), offered)
+    }
+}
+
+/// Parses the values of the `Accept` headers of a request into sorted media
+/// ranges; see `HttpRequest::accepts`.
+pub(crate) fn parse_accept<'v, I>(values: I) -> Vec<MediaRange>
+where
+    I: IntoIterator<Item = &'v HeaderValue>,
+{
+    let mut values = values.into_iter().peekable();
+    if values.peek().is_none() {
+        return vec![MediaRange {
+            media_type: "*".to_string(),
+            subtype: "*".to_string(),
+            params: Vec::new(),
+            quality: 1.0,
+        }];
+    }
+    let mut ranges: Vec<MediaRange> = values
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(parse_range)
+        .collect();
+    // A stable sort, so that ties stay in header order.
+    ranges.sort_by(|a, b| {
+        b.quality
+            .total_cmp(&a.quality)
+            .then_with(|| b.specificity().cmp(&a.specificity()))
+    });
+    ranges
+}
+
+/// Picks the media type of `offered` that `ranges` prefer; see
+/// `HttpRequest::preferred_type`.
+pub(crate) fn negotiate<'a>(ranges: &[MediaRange], offered: &[&'a str]) -> Option<&'a str> {
+    let mut best: Option<(&'a str, f32)> = None;
+    for &media_type in offered {
+        let quality = ranges
+            .iter()
+            .filter(|range| range.matches(media_type))
+            .max_by_key(|range| range.specificity())
+            .map_or(0.0, |range| range.quality);
+        if quality > 0.0 && best.is_none_or(|(_, best)| quality > best) {
+            best = Some((media_type, quality));
+        }
+    }
+    best.map(|(media_type, _)| media_type)
+}
+
+/// Parses one comma-separated element of `Accept`, e.g. `text/html;q=0.8`.
+fn parse_range(element: &str) -> Option<MediaRange> {
+    let mut range = parse_media_type(element)?;
+    if range.media_type == "*" && range.subtype != "*" {
+        return None;
+    }
+    // Parameters after `q` are extensions, not part of the range.
+    if let Some(q) = range.params.iter().position(|(name, _)| name == "q") {
+        range.quality = parse_quality(&range.params[q].1)?;
+        range.params.truncate(q);
+    }
+    Some(range)
+}
+
+/// Parses a media type or range with its parameters, e.g. `text/html;level=1`,
+/// with a quality of 1.
+pub(crate) fn parse_media_type(value: &str) -> Option<MediaRange> {
+    let mut parts = value.split(';');
+    let (media_type, subtype) = parts.next()?.trim().split_once('/')?;
+    if !is_token(media_type) || !is_token(subtype) {
+        return None;
+    }
+    let params = parts
+        .filter_map(|param| {
+            let (name, value) = param.split_once('=')?;
+            let name = name.trim();
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+            (is_token(name) && !value.is_empty())
+                .then(|| (name.to_ascii_lowercase(), value.to_string()))
+        })
+        .collect();
+    Some(MediaRange {
+        media_type: media_type.to_ascii_lowercase(),
+        subtype: subtype.to_ascii_lowercase(),
+        params,
+        quality: 1.0,
+    })
+}
+
+/// Returns `true` if `value` is a non-empty token of RFC 9110.
+fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
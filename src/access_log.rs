@@ -0,0 +1,287 @@
+//! Defines `AccessLogger`, which writes a line per request from the summaries
+//! of the `Router::on_response` hook.
+
+use crate::clock::{Clock, SystemClock};
+use crate::hooks::RequestSummary;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The format of the lines an `AccessLogger` writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessLogFormat {
+    /// The Apache combined log format, e.g.
+    /// `10.0.0.1 - - [14/Oct/2026:09:30:00 +0000] "GET /users/1 HTTP/1.1" 200 42 "-" "curl/8.5.0"`.
+    Combined,
+    /// A JSON object per line, with the fields `ts` (RFC 3339, in UTC),
+    /// `method`, `path`, `pattern`, `status`, `bytes`, `duration_ms`,
+    /// `request_id` and `client_ip`; unknown values are `null`.
+    JsonLines,
+}
+
+/// Where an `AccessLogger` sends its lines.
+enum Sink {
+    Writer(Box<dyn Write + Send>),
+    Callback(Box<dyn Fn(&str) + Send + Sync>),
+}
+
+/// What an `AccessLogger` reuses from one line to the next.
+struct State {
+    sink: Sink,
+    /// The line being written.
+    line: Vec<u8>,
+    /// The second `timestamp` is formatted for, since the Unix epoch.
+    second: Option<u64>,
+    /// The timestamp of `second`, to the second.
+    timestamp: String,
+}
+
+/// Writes an access log line for each request, in the Apache combined log
+/// format or as JSON lines, to an `io::Write` or a callback.
+///
+/// Lines are built in a buffer reused across requests, and timestamps are
+/// formatted once per second, so logging doesn't allocate. The time logged is
+/// when the request was received. Write errors are ignored.
+///
+/// # Examples
+///
+/// ```no_run
+/// use generic_http_router::{AccessLogFormat, AccessLogger, Router};
+///
+/// let logger = AccessLogger::new(AccessLogFormat::JsonLines, std::io::stdout());
+/// let mut router = Router::new("routes.json").unwrap();
+/// router.on_response(move |summary| logger.log(summary));
+/// ```
+pub struct AccessLogger {
+    format: AccessLogFormat,
+    clock: Arc<dyn Clock + Send + Sync>,
+    state: Mutex<State>,
+}
+
+impl AccessLogger {
+    /// Creates a logger writing each line, ending with a newline, to `writer`,
+    /// e.g. `io::stdout()` or a `BufWriter` of a file.
+    pub fn new<W: Write + Send + 'static>(format: AccessLogFormat, writer: W) -> Self {
+        Self::with_sink(format, Sink::Writer(Box::new(writer)))
+    }
+
+    /// Creates a logger calling `callback` with each line, without a newline.
+    pub fn with_callback<F>(format: AccessLogFormat, callback: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        Self::with_sink(format, Sink::Callback(Box::new(callback)))
+    }
+
+    fn with_sink(format: AccessLogFormat, sink: Sink) -> Self {
+        Self {
+            format,
+            clock: Arc::new(SystemClock),
+            state: Mutex::new(State {
+                sink,
+                line: Vec::with_capacity(256),
+                second: None,
+                timestamp: String::new(),
+            }),
+        }
+    }
+
+    /// Sets the clock the time of requests is told with, e.g. a
+    /// [`testing::MockClock`](crate::testing::MockClock) in tests.
+    pub fn with_clock<C: Clock + Send + Sync + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Writes the line of a request.
+    pub fn log(&self, summary: &RequestSummary) {
+        let received = self
+            .clock
+            .system_time()
+            .checked_sub(summary.elapsed)
+            .unwrap_or(UNIX_EPOCH);
+        let mut state = self.lock();
+        let state = &mut *state;
+        state.line.clear();
+        let since_epoch = received.duration_since(UNIX_EPOCH).unwrap_or_default();
+        if state.second != Some(since_epoch.as_secs()) {
+            state.second = Some(since_epoch.as_secs());
+            state.timestamp = format_timestamp(self.format, received);
+        }
+        // Writing to a `Vec` can't fail.
+        let _ = match self.format {
+            AccessLogFormat::Combined => write_combined(&mut state.line, &state.timestamp, summary),
+            AccessLogFormat::JsonLines => write_json(
+                &mut state.line,
+                &state.timestamp,
+                since_epoch.subsec_millis(),
+                summary,
+            ),
+        };
+        match &mut state.sink {
+            Sink::Writer(writer) => {
+                state.line.push(b'\n');
+                let _ = writer.write_all(&state.line);
+            }
+            Sink::Callback(callback) => {
+                if let Ok(line) = std::str::from_utf8(&state.line) {
+                    callback(line);
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fmt::Debug for AccessLogger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessLogger")
+            .field("format", &self.format)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Writes a line in the combined log format, without the newline.
+fn write_combined(line: &mut Vec<u8>, timestamp: &str, summary: &RequestSummary) -> io::Result<()> {
+    match summary.client_ip {
+        Some(ip) => write!(line, "{ip}")?,
+        None => line.push(b'-'),
+    }
+    write!(line, " - - [{timestamp}] \"")?;
+    write_escaped(line, summary.method.as_str());
+    line.push(b' ');
+    write_escaped(line, &summary.path);
+    write!(
+        line,
+        " {:?}\" {} ",
+        summary.version,
+        summary.status.as_u16()
+    )?;
+    match summary.bytes {
+        Some(bytes) if bytes > 0 => write!(line, "{bytes}")?,
+        _ => line.push(b'-'),
+    }
+    for header in [&summary.referer, &summary.user_agent] {
+        line.extend_from_slice(b" \"");
+        write_escaped(line, header.as_deref().unwrap_or("-"));
+        line.push(b'"');
+    }
+    Ok(())
+}
+
+/// Writes a JSON line, without the newline.
+fn write_json(
+    line: &mut Vec<u8>,
+    timestamp: &str,
+    millis: u32,
+    summary: &RequestSummary,
+) -> io::Result<()> {
+    write!(line, "{{\"ts\":\"{timestamp}.{millis:03}Z\",\"method\":")?;
+    write_json_str(line, Some(summary.method.as_str()));
+    line.extend_from_slice(b",\"path\":");
+    write_json_str(line, Some(&summary.path));
+    line.extend_from_slice(b",\"pattern\":");
+    write_json_str(line, summary.pattern.as_deref());
+    write!(line, ",\"status\":{},\"bytes\":", summary.status.as_u16())?;
+    match summary.bytes {
+        Some(bytes) => write!(line, "{bytes}")?,
+        None => line.extend_from_slice(b"null"),
+    }
+    write!(
+        line,
+        ",\"duration_ms\":{:.3},\"request_id\":",
+        summary.elapsed.as_secs_f64() * 1000.0
+    )?;
+    write_json_str(line, summary.request_id.as_deref());
+    line.extend_from_slice(b",\"client_ip\":");
+    match summary.client_ip {
+        Some(ip) => write!(line, "\"{ip}\"")?,
+        None => line.extend_from_slice(b"null"),
+    }
+    line.push(b'}');
+    Ok(())
+}
+
+/// Writes `value` for a quoted field of the combined log format, escaping `"`
+/// and `\` with a backslash, and other bytes that aren't printable ASCII as
+/// `\xhh`, as Apache does.
+fn write_escaped(line: &mut Vec<u8>, value: &str) {
+    for &b in value.as_bytes() {
+        match b {
+            b'"' | b'\\' => line.extend_from_slice(&[b'\\', b]),
+            b' '..=b'~' => line.push(b),
+            _ => {
+                let _ = write!(line, "\\x{b:02x}");
+            }
+        }
+    }
+}
+
+/// Writes `value` as a JSON string, or `null`.
+fn write_json_str(line: &mut Vec<u8>, value: Option<&str>) {
+    let Some(value) = value else {
+        line.extend_from_slice(b"null");
+        return;
+    };
+    line.push(b'"');
+    for c in value.chars() {
+        match c {
+            '"' => line.extend_from_slice(b"\\\""),
+            '\\' => line.extend_from_slice(b"\\\\"),
+            '\n' => line.extend_from_slice(b"\\n"),
+            '\r' => line.extend_from_slice(b"\\r"),
+            '\t' => line.extend_from_slice(b"\\t"),
+            c if c < ' ' || c == '\u{7f}' => {
+                let _ = write!(line, "\\u{:04x}", u32::from(c));
+            }
+            c => {
+                let mut utf8 = [0; 4];
+                line.extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+            }
+        }
+    }
+    line.push(b'"');
+}
+
+/// Formats `time`, to the second, for `format`: `14/Oct/2026:09:30:00 +0000`
+/// for the combined log format, and `2026-10-14T09:30:00` for JSON lines, which
+/// adds the milliseconds.
+fn format_timestamp(format: AccessLogFormat, time: SystemTime) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let (hour, minute, second) = (secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    match format {
+        AccessLogFormat::Combined => format!(
+            "{day:02}/{}/{year}:{hour:02}:{minute:02}:{second:02} +0000",
+            MONTHS[month as usize - 1]
+        ),
+        AccessLogFormat::JsonLines => {
+            format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}")
+        }
+    }
+}
+
+/// Returns the year, month and day of the date `days` days after 1970-01-01,
+/// in the proleptic Gregorian calendar (Howard Hinnant's `civil_from_days`).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,86 @@
+//! Defines the `AsyncHttpHandler` trait for handlers used by `Router::route_async`
+//! (enabled by the `async` feature).
+
+pub use crate::handler::BoxFuture;
+use crate::handler::{Handler, HandlerFuture};
+use crate::request::HttpRequest;
+use crate::response::HttpResponse;
+use std::future::Future;
+
+/// A trait for handling HTTP requests asynchronously.
+///
+/// Register implementations with `Router::register_handler`, wrapped in
+/// [`Async`], and route requests with `Router::route_async`. If the route has a timeout, the returned future is
+/// dropped once `req.deadline` passes.
+///
+/// # Examples
+///
+/// ```
+/// use generic_http_router::async_handler::BoxFuture;
+/// use generic_http_router::{AsyncHttpHandler, HttpRequest, HttpResponse};
+///
+/// struct GetUserHandler;
+///
+/// impl AsyncHttpHandler for GetUserHandler {
+///     fn handle(&self, req: HttpRequest) -> BoxFuture<'_, HttpResponse> {
+///         Box::pin(async move {
+///             let id = req.params.get("id").unwrap_or_default().to_string();
+///             HttpResponse::ok(format!("Fetching user with id: {id}"))
+///         })
+///     }
+/// }
+/// ```
+pub trait AsyncHttpHandler {
+    /// Handles an incoming request, returning a future of the response.
+    fn handle(&self, req: HttpRequest) -> BoxFuture<'_, HttpResponse>;
+}
+
+/// A boxed asynchronous handler handles requests with the handler it holds.
+impl<H: AsyncHttpHandler + ?Sized> AsyncHttpHandler for Box<H> {
+    fn handle(&self, req: HttpRequest) -> BoxFuture<'_, HttpResponse> {
+        (**self).handle(req)
+    }
+}
+
+/// Adapts an `AsyncHttpHandler` into a [`Handler`] answering with
+/// `HandlerFuture::Pending`, e.g. `router.register_handler("users",
+/// Async(GetUserHandler))`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Async<H>(pub H);
+
+impl<H: AsyncHttpHandler> Handler for Async<H> {
+    fn call(&self, req: HttpRequest) -> HandlerFuture<'_> {
+        HandlerFuture::Pending(self.0.handle(req))
+    }
+}
+
+/// Adapts an async closure, or any function returning a future of the
+/// response, into a [`Handler`].
+///
+/// # Examples
+///
+/// ```
+/// use generic_http_router::async_handler::AsyncFn;
+/// use generic_http_router::{HttpRequest, HttpResponse, Router};
+///
+/// let mut router = Router::new("./examples/routes.json").unwrap();
+/// router.register_handler(
+///     "users_controller::get_by_id",
+///     AsyncFn(|req: HttpRequest| async move {
+///         let id = req.params.get("id").unwrap_or_default().to_string();
+///         HttpResponse::ok(format!("Fetching user with id: {id}"))
+///     }),
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsyncFn<F>(pub F);
+
+impl<F, Fut> Handler for AsyncFn<F>
+where
+    F: Fn(HttpRequest) -> Fut,
+    Fut: Future<Output = HttpResponse> + Send + 'static,
+{
+    fn call(&self, req: HttpRequest) -> HandlerFuture<'_> {
+        HandlerFuture::Pending(Box::pin((self.0)(req)))
+    }
+}
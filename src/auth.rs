@@ -0,0 +1,208 @@
+//! Defines the authenticated `Identity` of a request, and API-key
+//! authentication for routes with `"auth": "api_key"`.
+
+use crate::config::ApiKeys;
+use crate::crypto;
+use crate::error::RouterError;
+use crate::path::{self, PathDecoding};
+use crate::request::HttpRequest;
+use http::header::HeaderName;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// The authenticated caller of a request, available to handlers with
+/// [`HttpRequest::identity`]: the claims of a JWT bearer token (with the `jwt`
+/// feature), or the ID of an API key as its subject.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Identity {
+    claims: Map<String, Value>,
+}
+
+impl Identity {
+    /// Creates an identity with `subject` as its only claim, `sub`.
+    pub fn new<S: Into<String>>(subject: S) -> Self {
+        let mut claims = Map::new();
+        claims.insert("sub".to_string(), Value::String(subject.into()));
+        Self { claims }
+    }
+
+    /// Creates an identity with the claims of a token.
+    pub fn from_claims(claims: Map<String, Value>) -> Self {
+        Self { claims }
+    }
+
+    /// Returns all the claims.
+    pub fn claims(&self) -> &Map<String, Value> {
+        &self.claims
+    }
+
+    /// Returns the claim `name`, or `None` if there's none or it doesn't
+    /// deserialize into `T`.
+    pub fn claim<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        serde_json::from_value(self.claims.get(name)?.clone()).ok()
+    }
+
+    /// Returns the `sub` claim, e.g. the user ID, or the ID of the API key.
+    pub fn subject(&self) -> Option<&str> {
+        self.claims.get("sub").and_then(Value::as_str)
+    }
+
+    /// Returns the scopes granted, from the space-separated `scope` claim or
+    /// the `scp` claim (a string or an array).
+    pub fn scopes(&self) -> Vec<&str> {
+        match self.claims.get("scope").or_else(|| self.claims.get("scp")) {
+            Some(Value::String(scopes)) => scopes.split_whitespace().collect(),
+            Some(Value::Array(scopes)) => scopes.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns `true` if `scope` is granted.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes().contains(&scope)
+    }
+}
+
+impl HttpRequest {
+    /// Returns the authenticated caller of the request, if its route requires
+    /// an API key, or if JWT validation is enabled with `Router::use_jwt_auth`
+    /// and the request has a valid bearer token.
+    pub fn identity(&self) -> Option<&Identity> {
+        self.extensions.get()
+    }
+}
+
+/// Authenticates requests to routes with `"auth": "api_key"` by a static key,
+/// built from the `api_keys` configuration section or set with
+/// `Router::set_api_keys`.
+///
+/// Keys are only stored as SHA-256 hashes, and compared in constant time. A
+/// request's key is read from the configured header, or else the query
+/// parameter if one is configured; the router adds both to the redacted ones
+/// of [`redact::debug_options`](crate::redact::debug_options). The `Debug`
+/// output only lists the key IDs.
+#[derive(Clone)]
+pub struct ApiKeyProvider {
+    header: HeaderName,
+    query_param: Option<String>,
+    keys: Vec<(String, [u8; 32])>,
+}
+
+impl ApiKeyProvider {
+    /// Creates a provider reading keys from the header `header`, without keys.
+    pub fn new(header: HeaderName) -> Self {
+        Self {
+            header,
+            query_param: None,
+            keys: Vec::new(),
+        }
+    }
+
+    /// Also reads keys from the query parameter `name`, for clients that can't
+    /// set headers.
+    pub fn with_query_param<S: Into<String>>(mut self, name: S) -> Self {
+        self.query_param = Some(name.into());
+        self
+    }
+
+    /// Adds the key `id`, by the SHA-256 hash of the key.
+    pub fn with_key_hash<S: Into<String>>(mut self, id: S, hash: [u8; 32]) -> Self {
+        self.keys.push((id.into(), hash));
+        self
+    }
+
+    /// Adds the key `id`, hashing `key`.
+    pub fn with_key<S: Into<String>>(self, id: S, key: &str) -> Self {
+        self.with_key_hash(id, crypto::sha256(key.as_bytes()))
+    }
+
+    /// Builds a provider from the `api_keys` configuration section.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError::ApiKey` for an invalid header name, or a hash
+    /// that isn't 64 hexadecimal digits.
+    pub fn from_config(config: &ApiKeys) -> Result<Self, RouterError> {
+        let header =
+            HeaderName::from_bytes(config.header.as_bytes()).map_err(|e| RouterError::ApiKey {
+                name: config.header.clone(),
+                reason: e.to_string(),
+            })?;
+        let mut provider = Self::new(header);
+        provider.query_param = config.query_param.clone();
+        for (id, hash) in &config.keys {
+            let hash = decode_hash(hash).ok_or_else(|| RouterError::ApiKey {
+                name: id.clone(),
+                reason: "the hash must be 64 hexadecimal digits".to_string(),
+            })?;
+            provider.keys.push((id.clone(), hash));
+        }
+        Ok(provider)
+    }
+
+    /// Returns the header keys are read from.
+    pub fn header(&self) -> &HeaderName {
+        &self.header
+    }
+
+    /// Returns the query parameter keys are also read from, if any.
+    pub fn query_param(&self) -> Option<&str> {
+        self.query_param.as_deref()
+    }
+
+    /// Returns the identity of the request's key, with the key ID as subject,
+    /// or `None` if it has no key or an unknown one.
+    pub fn authenticate(&self, req: &HttpRequest) -> Option<Identity> {
+        let key = match req.headers.get(&self.header) {
+            Some(value) => value.as_bytes().to_vec(),
+            None => self.query_key(req)?.into_bytes(),
+        };
+        let hash = crypto::sha256(&key);
+        // Every key is compared, so the time taken doesn't tell which matched.
+        let mut matched = None;
+        for (id, expected) in &self.keys {
+            if crypto::constant_time_eq(expected, &hash) {
+                matched = Some(id);
+            }
+        }
+        matched.map(Identity::new)
+    }
+
+    fn query_key(&self, req: &HttpRequest) -> Option<String> {
+        let name = self.query_param.as_deref()?;
+        let (_, value) = req
+            .uri
+            .query()?
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)?;
+        path::percent_decode(&value.replace('+', " "), PathDecoding::Reject)
+            .map(|value| value.into_owned())
+    }
+}
+
+impl fmt::Debug for ApiKeyProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ids: Vec<&str> = self.keys.iter().map(|(id, _)| id.as_str()).collect();
+        f.debug_struct("ApiKeyProvider")
+            .field("header", &self.header)
+            .field("query_param", &self.query_param)
+            .field("keys", &ids)
+            .finish()
+    }
+}
+
+/// Decodes a hex-encoded SHA-256 hash.
+fn decode_hash(hex: &str) -> Option<[u8; 32]> {
+    let hex = hex.as_bytes();
+    if hex.len() != 64 || !hex.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    let mut hash = [0u8; 32];
+    for (byte, digits) in hash.iter_mut().zip(hex.chunks_exact(2)) {
+        let digits = std::str::from_utf8(digits).ok()?;
+        *byte = u8::from_str_radix(digits, 16).ok()?;
+    }
+    Some(hash)
+}
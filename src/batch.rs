@@ -0,0 +1,222 @@
+//! Defines the built-in batch endpoint, enabled with `Router::enable_batching`:
+//! a `POST` of a JSON array of sub-requests, each routed as a request of its
+//! own, answered with the array of their responses.
+
+use crate::accept::parse_media_type;
+use crate::response::{HttpResponse, IntoHttpResponse};
+use bytes::Bytes;
+use http::header::{CONTENT_TYPE, HeaderName, HeaderValue};
+use http::{Method, Request, Response, StatusCode, Uri};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// The standard base64 alphabet (RFC 4648 §4).
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The limits on batch requests, set with `Router::enable_batching`.
+///
+/// A batch over a limit gets a 413 Content Too Large, before any of its
+/// sub-requests is routed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchLimits {
+    /// The most sub-requests a batch can have. Defaults to 20.
+    pub max_requests: usize,
+    /// The largest body a batch request can have, in bytes. Defaults to 1 MiB.
+    pub max_body_bytes: usize,
+}
+
+impl Default for BatchLimits {
+    fn default() -> Self {
+        Self {
+            max_requests: 20,
+            max_body_bytes: 1024 * 1024,
+        }
+    }
+}
+
+/// A sub-request, as sent in a batch, e.g.
+/// `{"method": "POST", "path": "/orders", "headers": {...}, "body": {...}}`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SubRequest {
+    method: String,
+    path: String,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    /// A JSON body, sent with `Content-Type: application/json` unless the
+    /// sub-request has a `Content-Type` of its own.
+    #[serde(default)]
+    body: Option<Value>,
+    /// Any other body, base64-encoded.
+    #[serde(default)]
+    body_base64: Option<String>,
+}
+
+/// Marks a sub-request of a batch, in its extensions, so that it can't be a
+/// batch itself.
+pub(crate) struct BatchItem;
+
+/// Parses the body of a batch request into its sub-requests.
+///
+/// # Errors
+///
+/// Returns the status and the problem detail of the response to an invalid
+/// batch: a 413 Content Too Large over `limits`, or a 400 Bad Request if it
+/// isn't an array of valid sub-requests.
+pub(crate) fn parse(
+    body: &[u8],
+    limits: &BatchLimits,
+) -> Result<Vec<Request<Bytes>>, (StatusCode, String)> {
+    let invalid = |detail: String| (StatusCode::BAD_REQUEST, detail);
+    if body.len() > limits.max_body_bytes {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("The batch is larger than {} bytes.", limits.max_body_bytes),
+        ));
+    }
+    let items: Vec<Value> = serde_json::from_slice(body)
+        .map_err(|e| invalid(format!("The batch isn't a JSON array: {e}")))?;
+    if items.len() > limits.max_requests {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("The batch has more than {} requests.", limits.max_requests),
+        ));
+    }
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| {
+            sub_request(item).map_err(|e| invalid(format!("Request {index} of the batch {e}.")))
+        })
+        .collect()
+}
+
+/// Builds the request of a sub-request, or returns why it's invalid.
+fn sub_request(item: Value) -> Result<Request<Bytes>, String> {
+    let item = SubRequest::deserialize(item).map_err(|e| format!("is invalid: {e}"))?;
+    let method = Method::from_bytes(item.method.as_bytes())
+        .map_err(|_| format!("has an invalid method '{}'", item.method))?;
+    if !item.path.starts_with('/') {
+        return Err(format!("has a path '{}' not starting with '/'", item.path));
+    }
+    let uri: Uri = item
+        .path
+        .parse()
+        .map_err(|_| format!("has an invalid path '{}'", item.path))?;
+
+    let mut req = Request::builder().method(method).uri(uri);
+    for (name, value) in &item.headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("has an invalid header name '{name}'"))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|_| format!("has an invalid value for the header '{name}'"))?;
+        req = req.header(name, value);
+    }
+    let body = match (item.body, item.body_base64) {
+        (Some(_), Some(_)) => return Err("has both a body and a body_base64".to_string()),
+        (Some(json), None) => {
+            if !item
+                .headers
+                .keys()
+                .any(|name| name.eq_ignore_ascii_case("content-type"))
+            {
+                req = req.header(CONTENT_TYPE, "application/json");
+            }
+            Bytes::from(json.to_string())
+        }
+        (None, Some(encoded)) => base64_decode(&encoded)
+            .map(Bytes::from)
+            .ok_or("has a body_base64 that isn't valid base64")?,
+        (None, None) => Bytes::new(),
+    };
+    let mut req = req.body(body).map_err(|e| format!("is invalid: {e}"))?;
+    req.extensions_mut().insert(BatchItem);
+    Ok(req)
+}
+
+/// Builds the response to a batch from the responses to its sub-requests, in
+/// order: each one's status, its headers, with the values of a repeated header
+/// joined by commas, and its body, as JSON for a JSON `Content-Type` and
+/// base64-encoded otherwise, e.g.
+/// `[{"status":200,"headers":{"content-type":"application/json"},"body":{"id":1}}]`.
+pub(crate) fn response(responses: &[Response<Bytes>]) -> HttpResponse {
+    Value::Array(responses.iter().map(item).collect()).into_http_response()
+}
+
+/// Returns a sub-response, as listed in the batch response.
+fn item(response: &Response<Bytes>) -> Value {
+    let mut headers = Map::new();
+    for (name, value) in response.headers() {
+        let value = String::from_utf8_lossy(value.as_bytes());
+        match headers.get_mut(name.as_str()) {
+            Some(Value::String(values)) => {
+                values.push_str(", ");
+                values.push_str(&value);
+            }
+            _ => {
+                headers.insert(name.to_string(), Value::String(value.into_owned()));
+            }
+        }
+    }
+    let mut item = Map::new();
+    item.insert("status".to_string(), response.status().as_u16().into());
+    item.insert("headers".to_string(), Value::Object(headers));
+
+    let body = response.body();
+    if !body.is_empty() {
+        let is_json = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_media_type)
+            .is_some_and(|media_type| {
+                media_type.subtype == "json" || media_type.subtype.ends_with("+json")
+            });
+        match is_json.then(|| serde_json::from_slice(body).ok()).flatten() {
+            Some(json) => item.insert("body".to_string(), json),
+            None => item.insert("body_base64".to_string(), base64_encode(body).into()),
+        };
+    }
+    Value::Object(item)
+}
+
+/// Encodes `input` in padded base64 (RFC 4648 §4).
+fn base64_encode(input: &[u8]) -> String {
+    let mut encoded = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let buffer = chunk.iter().enumerate().fold(0u32, |buffer, (i, &byte)| {
+            buffer | u32::from(byte) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(char::from(BASE64[(buffer >> (18 - 6 * i)) as usize & 63]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Decodes base64 (RFC 4648 §4), padded or not, or returns `None` if `input`
+/// isn't.
+pub(crate) fn base64_decode(input: &str) -> Option<Vec<u8>> {
+    let input = input.trim_end_matches('=');
+    if input.len() % 4 == 1 {
+        return None;
+    }
+    let mut decoded = Vec::with_capacity(input.len() * 3 / 4);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for byte in input.bytes() {
+        let value = BASE64.iter().position(|&c| c == byte)? as u32;
+        buffer = (buffer << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(decoded)
+}
@@ -0,0 +1,122 @@
+//! Defines the `Body` type for response bodies, which can be buffered or streamed.
+
+use bytes::{Bytes, BytesMut};
+use std::fmt;
+use std::io;
+
+/// The iterator type backing a streamed body. Each item is a chunk of the body,
+/// and an error ends the stream.
+pub type BodyStream = Box<dyn Iterator<Item = Result<Bytes, io::Error>> + Send>;
+
+/// The body of an `HttpResponse`.
+pub enum Body {
+    /// A body that is fully in memory.
+    Full(Bytes),
+    /// A body produced chunk by chunk, for responses too large to buffer.
+    Stream(BodyStream),
+}
+
+impl Body {
+    /// Creates an empty body.
+    pub fn empty() -> Self {
+        Body::Full(Bytes::new())
+    }
+
+    /// Creates a streamed body from an iterator of chunks.
+    pub fn stream<I>(chunks: I) -> Self
+    where
+        I: IntoIterator<Item = Result<Bytes, io::Error>>,
+        I::IntoIter: Send + 'static,
+    {
+        Body::Stream(Box::new(chunks.into_iter()))
+    }
+
+    /// Returns the body's bytes, or `None` if the body is streamed.
+    pub fn as_bytes(&self) -> Option<&Bytes> {
+        match self {
+            Body::Full(bytes) => Some(bytes),
+            Body::Stream(_) => None,
+        }
+    }
+
+    /// Returns `true` if the body is streamed.
+    pub fn is_stream(&self) -> bool {
+        matches!(self, Body::Stream(_))
+    }
+
+    /// Buffers the whole body into memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns the stream's error if it fails, or an error of kind
+    /// `io::ErrorKind::OutOfMemory` if the body grows larger than `limit` bytes.
+    pub fn collect(self, limit: usize) -> Result<Bytes, io::Error> {
+        let chunks = match self {
+            Body::Full(bytes) if bytes.len() <= limit => return Ok(bytes),
+            Body::Full(_) => return Err(too_large(limit)),
+            Body::Stream(chunks) => chunks,
+        };
+
+        let mut buffer = BytesMut::new();
+        for chunk in chunks {
+            let chunk = chunk?;
+            if buffer.len() + chunk.len() > limit {
+                return Err(too_large(limit));
+            }
+            buffer.extend_from_slice(&chunk);
+        }
+        Ok(buffer.freeze())
+    }
+}
+
+fn too_large(limit: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::OutOfMemory,
+        format!("body exceeds the buffering limit of {limit} bytes"),
+    )
+}
+
+impl Default for Body {
+    fn default() -> Self {
+        Body::empty()
+    }
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Body::Full(bytes) => f.debug_tuple("Full").field(bytes).finish(),
+            Body::Stream(_) => f.write_str("Stream(..)"),
+        }
+    }
+}
+
+impl From<Bytes> for Body {
+    fn from(bytes: Bytes) -> Self {
+        Body::Full(bytes)
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(bytes: Vec<u8>) -> Self {
+        Body::Full(bytes.into())
+    }
+}
+
+impl From<String> for Body {
+    fn from(text: String) -> Self {
+        Body::Full(text.into())
+    }
+}
+
+impl From<&'static [u8]> for Body {
+    fn from(bytes: &'static [u8]) -> Self {
+        Body::Full(Bytes::from_static(bytes))
+    }
+}
+
+impl From<&'static str> for Body {
+    fn from(text: &'static str) -> Self {
+        Body::Full(Bytes::from_static(text.as_bytes()))
+    }
+}
@@ -0,0 +1,376 @@
+//! Defines the in-memory response cache used for routes with a
+//! `cache_ttl_seconds` (enabled by the `cache` feature).
+
+use crate::body::Body;
+use crate::response::HttpResponse;
+use bytes::Bytes;
+use http::header::{CACHE_CONTROL, HeaderName, HeaderValue, VARY};
+use http::{HeaderMap, Method, StatusCode};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The default maximum total size of the cached responses: 16 MiB.
+pub(crate) const DEFAULT_MAX_BYTES: usize = 16 * 1024 * 1024;
+
+/// Identifies a cached response: the method, the request target (path and
+/// query), and the request's values of the headers the response varies on.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Key {
+    method: Method,
+    target: String,
+    vary_values: Vec<Option<HeaderValue>>,
+}
+
+struct Entry {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    expires: Instant,
+    size: usize,
+    /// The position of the entry in the LRU order.
+    tick: u64,
+}
+
+/// The `Vary` header names of the latest cached response for a method and
+/// request target, kept while any response for them is cached.
+struct Vary {
+    names: Vec<HeaderName>,
+    /// The cached responses for the method and target.
+    variants: usize,
+    /// The bytes counted for the entry itself.
+    size: usize,
+}
+
+struct Inner {
+    entries: HashMap<Key, Entry>,
+    vary: HashMap<(Method, String), Vary>,
+    /// The entries by last use, least recently used first.
+    lru: BTreeMap<u64, Key>,
+    next_tick: u64,
+    size: usize,
+    max_bytes: usize,
+}
+
+impl Inner {
+    fn remove(&mut self, key: &Key) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.lru.remove(&entry.tick);
+            self.forget(key, entry.size);
+        }
+    }
+
+    /// Accounts for the removal of the entry of `key`, of `size` bytes,
+    /// removing the `Vary` names of its method and target with their last
+    /// entry.
+    fn forget(&mut self, key: &Key, size: usize) {
+        self.size -= size;
+        let target = (key.method.clone(), key.target.clone());
+        if let Some(vary) = self.vary.get_mut(&target) {
+            vary.variants -= 1;
+            if vary.variants == 0 {
+                self.size -= vary.size;
+                self.vary.remove(&target);
+            }
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.next_tick += 1;
+        self.next_tick
+    }
+
+    fn evict_to(&mut self, max_bytes: usize) {
+        while self.size > max_bytes {
+            let Some((_, key)) = self.lru.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&key) {
+                self.forget(&key, entry.size);
+            }
+        }
+    }
+}
+
+/// A size-bounded, least-recently-used cache of 200 OK responses.
+pub(crate) struct ResponseCache {
+    inner: Mutex<Inner>,
+}
+
+impl ResponseCache {
+    pub(crate) fn new() -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                entries: HashMap::new(),
+                vary: HashMap::new(),
+                lru: BTreeMap::new(),
+                next_tick: 0,
+                size: 0,
+                max_bytes: DEFAULT_MAX_BYTES,
+            }),
+        }
+    }
+
+    /// Returns an empty cache with the same maximum size.
+    pub(crate) fn empty_copy(&self) -> Self {
+        let cache = Self::new();
+        cache.set_max_bytes(self.lock().max_bytes);
+        cache
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Sets the maximum total size of the cached responses, evicting the least
+    /// recently used ones as needed.
+    pub(crate) fn set_max_bytes(&self, max_bytes: usize) {
+        let mut inner = self.lock();
+        inner.max_bytes = max_bytes;
+        inner.evict_to(max_bytes);
+    }
+
+    /// Returns a copy of the cached response for a request, if there's one that
+    /// hasn't expired.
+    pub(crate) fn get(
+        &self,
+        method: &Method,
+        target: &str,
+        headers: &HeaderMap,
+        now: Instant,
+    ) -> Option<HttpResponse> {
+        let mut inner = self.lock();
+        let vary = inner.vary.get(&(method.clone(), target.to_string()))?;
+        let key = Key {
+            method: method.clone(),
+            target: target.to_string(),
+            vary_values: vary_values(&vary.names, headers),
+        };
+
+        let entry = inner.entries.get(&key)?;
+        if entry.expires <= now {
+            inner.remove(&key);
+            return None;
+        }
+        let old_tick = entry.tick;
+        let tick = inner.tick();
+        inner.lru.remove(&old_tick);
+        inner.lru.insert(tick, key.clone());
+        let entry = inner.entries.get_mut(&key)?;
+        entry.tick = tick;
+        Some(HttpResponse::with_headers(
+            entry.status,
+            entry.headers.clone(),
+            entry.body.clone(),
+        ))
+    }
+
+    /// Caches `response` for `ttl` from `now` if it's cacheable: a 200 OK with
+    /// a buffered body, without `Vary: *` or `Cache-Control: no-store` /
+    /// `private`. A `ttl` past what an `Instant` can represent isn't cached.
+    pub(crate) fn insert(
+        &self,
+        method: Method,
+        target: String,
+        request_headers: &HeaderMap,
+        response: &HttpResponse,
+        ttl: Duration,
+        now: Instant,
+    ) {
+        let Body::Full(body) = &response.body else {
+            return;
+        };
+        if response.status != StatusCode::OK {
+            return;
+        }
+        let forbidden = response
+            .headers
+            .get_all(CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|directive| {
+                let directive = directive.trim();
+                directive.eq_ignore_ascii_case("no-store")
+                    || directive.eq_ignore_ascii_case("private")
+            });
+        let Some(vary) = vary_names(&response.headers) else {
+            return;
+        };
+        if forbidden {
+            return;
+        }
+        let Some(expires) = now.checked_add(ttl) else {
+            return;
+        };
+
+        let size = target.len()
+            + body.len()
+            + response
+                .headers
+                .iter()
+                .map(|(name, value)| name.as_str().len() + value.len())
+                .sum::<usize>();
+        let mut inner = self.lock();
+        if size > inner.max_bytes {
+            return;
+        }
+
+        let key = Key {
+            method: method.clone(),
+            target: target.clone(),
+            vary_values: vary_values(&vary, request_headers),
+        };
+        inner.remove(&key);
+        let vary_size = target.len() + vary.iter().map(|name| name.as_str().len()).sum::<usize>();
+        let inner = &mut *inner;
+        match inner.vary.get_mut(&(method.clone(), target.clone())) {
+            Some(entry) => {
+                entry.variants += 1;
+                inner.size = inner.size - entry.size + vary_size;
+                entry.names = vary;
+                entry.size = vary_size;
+            }
+            None => {
+                inner.size += vary_size;
+                let entry = Vary {
+                    names: vary,
+                    variants: 1,
+                    size: vary_size,
+                };
+                inner.vary.insert((method, target), entry);
+            }
+        }
+        let tick = inner.tick();
+        inner.lru.insert(tick, key.clone());
+        inner.size += size;
+        inner.entries.insert(
+            key,
+            Entry {
+                status: response.status,
+                headers: response.headers.clone(),
+                body: body.clone(),
+                expires,
+                size,
+                tick,
+            },
+        );
+        let max_bytes = inner.max_bytes;
+        inner.evict_to(max_bytes);
+    }
+
+    /// Removes the cached responses whose request path starts with `path_prefix`.
+    pub(crate) fn invalidate(&self, path_prefix: &str) {
+        let matches = |target: &str| {
+            let path = target.split('?').next().unwrap_or(target);
+            path.starts_with(path_prefix)
+        };
+        let mut inner = self.lock();
+        let keys: Vec<Key> = inner
+            .entries
+            .keys()
+            .filter(|key| matches(&key.target))
+            .cloned()
+            .collect();
+        for key in &keys {
+            inner.remove(key);
+        }
+    }
+}
+
+/// Returns the header names listed in the `Vary` headers, or `None` for
+/// `Vary: *`, which can't be cached.
+fn vary_names(headers: &HeaderMap) -> Option<Vec<HeaderName>> {
+    let mut names = Vec::new();
+    for value in headers.get_all(VARY) {
+        let value = value.to_str().ok()?;
+        for name in value
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            if name == "*" {
+                return None;
+            }
+            names.push(HeaderName::from_bytes(name.as_bytes()).ok()?);
+        }
+    }
+    Some(names)
+}
+
+fn vary_values(names: &[HeaderName], headers: &HeaderMap) -> Vec<Option<HeaderValue>> {
+    names
+        .iter()
+        .map(|name| headers.get(name).cloned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(cache: &ResponseCache, target: &str, now: Instant) {
+        let mut response = HttpResponse::ok("report");
+        response.add_header(VARY, "Accept");
+        cache.insert(
+            Method::GET,
+            target.to_string(),
+            &HeaderMap::new(),
+            &response,
+            Duration::from_secs(30),
+            now,
+        );
+    }
+
+    #[test]
+    fn vary_names_are_evicted_with_their_last_response() {
+        let cache = ResponseCache::new();
+        cache.set_max_bytes(1000);
+        let now = Instant::now();
+        for nonce in 0..1000 {
+            insert(&cache, &format!("/reports?nonce={nonce}"), now);
+        }
+        let inner = cache.lock();
+        assert!(inner.size <= 1000);
+        assert_eq!(inner.vary.len(), inner.entries.len());
+        assert!(
+            inner
+                .vary
+                .contains_key(&(Method::GET, "/reports?nonce=999".to_string()))
+        );
+        assert!(
+            !inner
+                .vary
+                .contains_key(&(Method::GET, "/reports?nonce=0".to_string()))
+        );
+    }
+
+    #[test]
+    fn vary_names_are_removed_with_an_expired_response() {
+        let cache = ResponseCache::new();
+        let now = Instant::now();
+        insert(&cache, "/reports", now);
+        let later = now + Duration::from_secs(30);
+        assert!(
+            cache
+                .get(&Method::GET, "/reports", &HeaderMap::new(), later)
+                .is_none()
+        );
+        let inner = cache.lock();
+        assert!(inner.vary.is_empty());
+        assert_eq!(inner.size, 0);
+    }
+
+    #[test]
+    fn vary_names_are_removed_by_invalidation() {
+        let cache = ResponseCache::new();
+        let now = Instant::now();
+        insert(&cache, "/reports/1", now);
+        insert(&cache, "/reports/1", now);
+        insert(&cache, "/reports/2", now);
+        cache.invalidate("/reports/");
+        let inner = cache.lock();
+        assert!(inner.vary.is_empty());
+        assert_eq!(inner.size, 0);
+    }
+}
@@ -0,0 +1,69 @@
+//! Assigns requests to canary routes to their stable or candidate controller.
+
+use crate::config::{Canary, CanaryKey};
+use crate::rng::Rng;
+use http::HeaderMap;
+use http::header::COOKIE;
+
+/// The controller of a canary route a request was assigned to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Variant {
+    Stable,
+    Candidate,
+}
+
+impl Variant {
+    /// The value of the `X-Canary` response header.
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Variant::Stable => "stable",
+            Variant::Candidate => "candidate",
+        }
+    }
+}
+
+/// Assigns a request with `headers` to a controller of `canary`, drawing from
+/// `rng` for a random split.
+pub(crate) fn select(canary: &Canary, headers: &HeaderMap, rng: &dyn Rng) -> Variant {
+    match canary.percent {
+        0 => return Variant::Stable,
+        100.. => return Variant::Candidate,
+        _ => {}
+    }
+
+    let bucket = match &canary.key {
+        CanaryKey::Header(name) => match headers.get(name.as_str()) {
+            Some(value) => fnv1a(value.as_bytes()) % 100,
+            None => return Variant::Stable,
+        },
+        CanaryKey::Cookie(name) => match cookie(headers, name) {
+            Some(value) => fnv1a(value.as_bytes()) % 100,
+            None => return Variant::Stable,
+        },
+        CanaryKey::Random => rng.next_u64() % 100,
+    };
+    if bucket < u64::from(canary.percent) {
+        Variant::Candidate
+    } else {
+        Variant::Stable
+    }
+}
+
+/// Returns the value of the cookie `name` from the `Cookie` headers.
+pub(crate) fn cookie<'h>(headers: &'h HeaderMap, name: &str) -> Option<&'h str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find_map(|(cookie, value)| (cookie == name).then_some(value))
+}
+
+/// The 64-bit FNV-1a hash of `bytes`, which is stable across runs and builds,
+/// unlike the standard library's hashers.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
@@ -0,0 +1,97 @@
+//! Defines canonical redirects, set with `Router::set_canonicalization`: to
+//! HTTPS, and to a canonical host.
+
+use crate::Router;
+use crate::forwarded::{request_host, request_scheme};
+use crate::response::HttpResponse;
+use http::header::LOCATION;
+use http::{HeaderValue, Method, Request, StatusCode};
+
+/// The canonical scheme and host requests are redirected to.
+///
+/// The scheme and host a request was sent to are taken from the `Forwarded`,
+/// `X-Forwarded-Proto` and `X-Forwarded-Host` headers of the proxy in front
+/// of the router, or from the request itself; see
+/// [`HttpRequest::forwarded_proto`](crate::HttpRequest::forwarded_proto).
+/// A request whose scheme can't be told isn't redirected to HTTPS, so that a
+/// server terminating TLS without saying so can't loop.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CanonicalizeConfig {
+    /// Whether requests over plain HTTP are redirected to HTTPS.
+    pub https: bool,
+    /// The host requests for another host are redirected to, e.g.
+    /// `www.example.com`, with the port if it isn't the scheme's default.
+    pub host: Option<String>,
+    /// Whether redirects are permanent: 301 Moved Permanently for `GET` and
+    /// `HEAD` requests, and 308 Permanent Redirect for the others, which keeps
+    /// the method and body. Otherwise they're 302 Found and 307 Temporary
+    /// Redirect.
+    pub permanent: bool,
+    /// The paths never redirected, e.g. `["/healthz"]` for load balancer
+    /// health checks. The built-in health endpoints are always exempt.
+    pub exempt_paths: Vec<String>,
+}
+
+impl CanonicalizeConfig {
+    /// Returns the redirect to the canonical URL of `req`, with the same path
+    /// and query, or `None` if it's already canonical.
+    pub(crate) fn redirect<B>(&self, req: &Request<B>) -> Option<HttpResponse> {
+        if self
+            .exempt_paths
+            .iter()
+            .any(|path| path == req.uri().path())
+        {
+            return None;
+        }
+        let scheme = request_scheme(req);
+        let host = request_host(req);
+        let to_https = self.https && scheme.as_deref() == Some("http");
+        let to_host = self.host.as_deref().filter(|canonical| {
+            !host
+                .as_deref()
+                .is_some_and(|h| h.eq_ignore_ascii_case(canonical))
+        });
+        if !to_https && to_host.is_none() {
+            return None;
+        }
+
+        let scheme = if self.https {
+            "https"
+        } else {
+            scheme.as_deref().unwrap_or("http")
+        };
+        let host = to_host.map(str::to_string).or(host)?;
+        let path = req.uri().path_and_query().map_or("/", |p| p.as_str());
+        let location = HeaderValue::from_str(&format!("{scheme}://{host}{path}")).ok()?;
+        let safe = req.method() == Method::GET || req.method() == Method::HEAD;
+        let status = match (self.permanent, safe) {
+            (true, true) => StatusCode::MOVED_PERMANENTLY,
+            (true, false) => StatusCode::PERMANENT_REDIRECT,
+            (false, true) => StatusCode::FOUND,
+            (false, false) => StatusCode::TEMPORARY_REDIRECT,
+        };
+        let mut response = HttpResponse::new_static(status, b"");
+        response.headers.insert(LOCATION, location);
+        Some(response)
+    }
+}
+
+impl Router {
+    /// Redirects requests to the canonical scheme and host of `config`, e.g.
+    /// plain HTTP requests to HTTPS, keeping their path and query, before
+    /// they're routed. Requests already canonical, and to the exempt paths or
+    /// the built-in health endpoints, are routed normally.
+    pub fn set_canonicalization(&mut self, config: CanonicalizeConfig) {
+        self.canonicalization = Some(config);
+    }
+
+    /// Redirects a request to the canonical scheme and host, unless it's
+    /// already canonical or to a built-in health endpoint.
+    pub(crate) fn canonical_redirect<B>(&self, req: &Request<B>) -> Option<HttpResponse> {
+        let canonical = self.canonicalization.as_ref()?;
+        if self.is_health_path(req.uri().path()) {
+            return None;
+        }
+        canonical.redirect(req)
+    }
+}
@@ -0,0 +1,228 @@
+//! Defines the decoding of request bodies to text, following the `charset` of
+//! their `Content-Type`. The `encoding` feature adds the legacy single-byte
+//! encodings windows-1252 and ISO-8859-15, and UTF-16.
+
+use crate::accept::parse_media_type;
+use crate::error::BodyError;
+use crate::request::HttpRequest;
+use http::header::CONTENT_TYPE;
+
+/// A character encoding `HttpRequest::text` decodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Charset {
+    Utf8,
+    Latin1,
+    Ascii,
+    #[cfg(feature = "encoding")]
+    Windows1252,
+    #[cfg(feature = "encoding")]
+    Latin9,
+    /// UTF-16 in the byte order of its byte order mark, or else big-endian
+    /// (RFC 2781, section 4.3).
+    #[cfg(feature = "encoding")]
+    Utf16,
+    #[cfg(feature = "encoding")]
+    Utf16Le,
+    #[cfg(feature = "encoding")]
+    Utf16Be,
+}
+
+/// The characters of windows-1252 bytes 0x80 to 0x9F, which ISO-8859-1 has
+/// as C1 controls. The five bytes windows-1252 leaves undefined are the
+/// controls, as in the WHATWG Encoding Standard.
+#[cfg(feature = "encoding")]
+const WINDOWS_1252: [char; 32] = [
+    '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}', '\u{8f}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}', '\u{178}',
+];
+
+impl Charset {
+    /// Returns the encoding named `label`, ignoring case, from its IANA name
+    /// or one of its aliases.
+    fn from_label(label: &str) -> Option<Self> {
+        let label = label.to_ascii_lowercase();
+        match label.as_str() {
+            "utf-8" | "utf8" => Some(Self::Utf8),
+            "iso-8859-1" | "iso8859-1" | "iso_8859-1" | "iso_8859-1:1987" | "latin1" | "l1"
+            | "cp819" | "ibm819" => Some(Self::Latin1),
+            "us-ascii" | "ascii" | "iso646-us" => Some(Self::Ascii),
+            #[cfg(feature = "encoding")]
+            "windows-1252" | "cp1252" | "x-cp1252" => Some(Self::Windows1252),
+            #[cfg(feature = "encoding")]
+            "iso-8859-15" | "iso8859-15" | "iso_8859-15" | "latin-9" | "latin9" | "l9" => {
+                Some(Self::Latin9)
+            }
+            #[cfg(feature = "encoding")]
+            "utf-16" | "utf16" => Some(Self::Utf16),
+            #[cfg(feature = "encoding")]
+            "utf-16le" => Some(Self::Utf16Le),
+            #[cfg(feature = "encoding")]
+            "utf-16be" => Some(Self::Utf16Be),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Latin1 => "ISO-8859-1",
+            Self::Ascii => "US-ASCII",
+            #[cfg(feature = "encoding")]
+            Self::Windows1252 => "windows-1252",
+            #[cfg(feature = "encoding")]
+            Self::Latin9 => "ISO-8859-15",
+            #[cfg(feature = "encoding")]
+            Self::Utf16 => "UTF-16",
+            #[cfg(feature = "encoding")]
+            Self::Utf16Le => "UTF-16LE",
+            #[cfg(feature = "encoding")]
+            Self::Utf16Be => "UTF-16BE",
+        }
+    }
+
+    /// Decodes `bytes`, replacing invalid sequences with U+FFFD if `lossy`.
+    fn decode(self, bytes: &[u8], lossy: bool) -> Result<String, BodyError> {
+        let invalid = |offset| BodyError::InvalidEncoding {
+            charset: self.name(),
+            offset,
+        };
+        match self {
+            Self::Utf8 if lossy => Ok(String::from_utf8_lossy(bytes).into_owned()),
+            Self::Utf8 => std::str::from_utf8(bytes)
+                .map(str::to_string)
+                .map_err(|e| invalid(e.valid_up_to())),
+            // Each byte is the code point of the same value.
+            Self::Latin1 => Ok(bytes.iter().copied().map(char::from).collect()),
+            Self::Ascii => match bytes.iter().position(|b| !b.is_ascii()) {
+                Some(offset) if !lossy => Err(invalid(offset)),
+                _ => Ok(bytes
+                    .iter()
+                    .map(|&b| {
+                        if b.is_ascii() {
+                            char::from(b)
+                        } else {
+                            '\u{fffd}'
+                        }
+                    })
+                    .collect()),
+            },
+            #[cfg(feature = "encoding")]
+            Self::Windows1252 => Ok(bytes
+                .iter()
+                .map(|&b| match b {
+                    0x80..=0x9f => WINDOWS_1252[usize::from(b - 0x80)],
+                    _ => char::from(b),
+                })
+                .collect()),
+            #[cfg(feature = "encoding")]
+            Self::Latin9 => Ok(bytes
+                .iter()
+                .map(|&b| match b {
+                    0xa4 => '\u{20ac}',
+                    0xa6 => '\u{160}',
+                    0xa8 => '\u{161}',
+                    0xb4 => '\u{17d}',
+                    0xb8 => '\u{17e}',
+                    0xbc => '\u{152}',
+                    0xbd => '\u{153}',
+                    0xbe => '\u{178}',
+                    _ => char::from(b),
+                })
+                .collect()),
+            #[cfg(feature = "encoding")]
+            Self::Utf16 => match bytes {
+                [0xff, 0xfe, rest @ ..] => decode_utf16(rest, u16::from_le_bytes, lossy)
+                    .map_err(|offset| invalid(offset + 2)),
+                [0xfe, 0xff, rest @ ..] => decode_utf16(rest, u16::from_be_bytes, lossy)
+                    .map_err(|offset| invalid(offset + 2)),
+                _ => decode_utf16(bytes, u16::from_be_bytes, lossy).map_err(invalid),
+            },
+            #[cfg(feature = "encoding")]
+            Self::Utf16Le => decode_utf16(bytes, u16::from_le_bytes, lossy).map_err(invalid),
+            #[cfg(feature = "encoding")]
+            Self::Utf16Be => decode_utf16(bytes, u16::from_be_bytes, lossy).map_err(invalid),
+        }
+    }
+}
+
+/// Decodes UTF-16 code units read from pairs of bytes with `unit`, replacing
+/// unpaired surrogates and a trailing odd byte with U+FFFD if `lossy`, or
+/// else returning the offset of the first.
+#[cfg(feature = "encoding")]
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16, lossy: bool) -> Result<String, usize> {
+    let units = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+    let mut text = String::with_capacity(bytes.len() / 2);
+    let mut offset = 0;
+    for decoded in char::decode_utf16(units) {
+        match decoded {
+            Ok(c) => {
+                text.push(c);
+                offset += c.len_utf16() * 2;
+            }
+            Err(_) if lossy => {
+                text.push('\u{fffd}');
+                offset += 2;
+            }
+            Err(_) => return Err(offset),
+        }
+    }
+    if bytes.len() % 2 == 1 {
+        if !lossy {
+            return Err(bytes.len() - 1);
+        }
+        text.push('\u{fffd}');
+    }
+    Ok(text)
+}
+
+impl HttpRequest {
+    /// Decodes the body to text, in the `charset` of the `Content-Type`. Without
+    /// one, `text/*` bodies are ISO-8859-1, as HTTP/1.1 long specified, and
+    /// other bodies, such as JSON, UTF-8. UTF-8, ISO-8859-1 and US-ASCII, and
+    /// their common aliases, are supported, and with the `encoding` feature
+    /// windows-1252, ISO-8859-15, UTF-16LE, UTF-16BE and UTF-16 (with a byte
+    /// order mark, or else big-endian) too.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BodyError::UnsupportedCharset` for another charset, to answer
+    /// with a 415 Unsupported Media Type, and `BodyError::InvalidEncoding` if
+    /// the body isn't valid in its charset.
+    pub fn text(&self) -> Result<String, BodyError> {
+        self.charset()?.decode(&self.body, false)
+    }
+
+    /// Decodes the body to text like [`HttpRequest::text`], replacing invalid
+    /// sequences with U+FFFD instead of failing.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BodyError::UnsupportedCharset` for an unsupported charset.
+    pub fn text_lossy(&self) -> Result<String, BodyError> {
+        self.charset()?.decode(&self.body, true)
+    }
+
+    /// Returns the charset the body is encoded in; see `HttpRequest::text`.
+    fn charset(&self) -> Result<Charset, BodyError> {
+        let Some(content_type) = self
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_media_type)
+        else {
+            return Ok(Charset::Utf8);
+        };
+        match content_type
+            .params
+            .iter()
+            .find(|(name, _)| name == "charset")
+        {
+            Some((_, label)) => Charset::from_label(label)
+                .ok_or_else(|| BodyError::UnsupportedCharset(label.clone())),
+            None if content_type.media_type == "text" => Ok(Charset::Latin1),
+            None => Ok(Charset::Utf8),
+        }
+    }
+}
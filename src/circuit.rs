@@ -0,0 +1,280 @@
+//! Defines the circuit breakers of the controllers whose routes have a
+//! `circuit_breaker`: a rolling count of each controller's requests and
+//! failures, opening its circuit when too many fail.
+
+use crate::config::CircuitBreaker;
+use http::StatusCode;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// The rolling window of a breaker without a `window_ms`.
+const DEFAULT_WINDOW: Duration = Duration::from_secs(10);
+/// The number of buckets the window is counted in.
+const BUCKETS: u32 = 10;
+
+/// The state of a controller's circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests get to the controller.
+    Closed,
+    /// Requests get a 503 Service Unavailable without reaching the controller.
+    Open,
+    /// A limited number of trial requests get to the controller.
+    HalfOpen,
+}
+
+/// The circuit of a controller, as returned by `Router::stats`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitStats {
+    pub controller: String,
+    pub state: CircuitState,
+    /// The requests counted in the rolling window while closed.
+    pub requests: usize,
+    /// The failures among `requests`.
+    pub failures: usize,
+}
+
+#[derive(Debug)]
+enum Phase {
+    Closed,
+    Open { until: Instant },
+    HalfOpen { trials: u32, succeeded: u32 },
+}
+
+/// The requests and failures counted from `start`.
+#[derive(Debug)]
+struct Bucket {
+    start: Instant,
+    requests: usize,
+    failures: usize,
+}
+
+#[derive(Debug)]
+struct Breaker {
+    config: CircuitBreaker,
+    phase: Phase,
+    buckets: VecDeque<Bucket>,
+    /// Changed with each change of phase, so that requests admitted before
+    /// it aren't counted after it.
+    generation: u64,
+}
+
+/// The breakers of a router, by controller.
+#[derive(Debug, Default)]
+pub(crate) struct Circuits {
+    breakers: Mutex<HashMap<String, Breaker>>,
+}
+
+/// A request admitted to a controller, counted as a failure if it's dropped
+/// while unwinding from a panicking handler, and as never sent if it's
+/// dropped otherwise without being finished.
+pub(crate) struct Attempt<'a> {
+    circuits: &'a Circuits,
+    controller: String,
+    generation: u64,
+    trial: bool,
+    started: Instant,
+    finished: bool,
+}
+
+impl Circuits {
+    /// Admits a request to `controller` at `now`, or returns how long its
+    /// circuit stays open.
+    pub(crate) fn admit(
+        &self,
+        controller: &str,
+        config: CircuitBreaker,
+        now: Instant,
+    ) -> Result<Attempt<'_>, Duration> {
+        let mut breakers = self.lock();
+        let breaker = breakers
+            .entry(controller.to_string())
+            .or_insert_with(|| Breaker {
+                config,
+                phase: Phase::Closed,
+                buckets: VecDeque::new(),
+                generation: 0,
+            });
+        breaker.config = config;
+        if let Phase::Open { until } = breaker.phase {
+            if now < until {
+                return Err(until - now);
+            }
+            breaker.transition(Phase::HalfOpen {
+                trials: 0,
+                succeeded: 0,
+            });
+        }
+        let trial = match &mut breaker.phase {
+            Phase::HalfOpen { trials, .. } => {
+                if *trials >= config.half_open_requests.unwrap_or(1) {
+                    // The trials are still running; wait for them at least
+                    // a second.
+                    return Err(Duration::from_secs(1));
+                }
+                *trials += 1;
+                true
+            }
+            _ => false,
+        };
+        Ok(Attempt {
+            circuits: self,
+            controller: controller.to_string(),
+            generation: breaker.generation,
+            trial,
+            started: now,
+            finished: false,
+        })
+    }
+
+    /// Closes the circuit of `controller` and forgets its requests. Returns
+    /// `false` if it has no breaker.
+    pub(crate) fn reset(&self, controller: &str) -> bool {
+        let mut breakers = self.lock();
+        let Some(breaker) = breakers.get_mut(controller) else {
+            return false;
+        };
+        breaker.transition(Phase::Closed);
+        true
+    }
+
+    /// Returns the circuits at `now`, by controller.
+    pub(crate) fn stats(&self, now: Instant) -> Vec<CircuitStats> {
+        let breakers = self.lock();
+        let mut stats: Vec<CircuitStats> = breakers
+            .iter()
+            .map(|(controller, breaker)| {
+                let (requests, failures) = breaker.counts(now);
+                CircuitStats {
+                    controller: controller.clone(),
+                    state: match breaker.phase {
+                        Phase::Open { until } if now >= until => CircuitState::HalfOpen,
+                        Phase::Open { .. } => CircuitState::Open,
+                        Phase::HalfOpen { .. } => CircuitState::HalfOpen,
+                        Phase::Closed => CircuitState::Closed,
+                    },
+                    requests,
+                    failures,
+                }
+            })
+            .collect();
+        stats.sort_by(|a, b| a.controller.cmp(&b.controller));
+        stats
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Breaker>> {
+        self.breakers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Breaker {
+    fn transition(&mut self, phase: Phase) {
+        self.phase = phase;
+        self.buckets.clear();
+        self.generation += 1;
+    }
+
+    fn window(&self) -> Duration {
+        self.config
+            .window_ms
+            .map_or(DEFAULT_WINDOW, Duration::from_millis)
+    }
+
+    /// Returns the requests and failures in the window at `now`.
+    fn counts(&self, now: Instant) -> (usize, usize) {
+        let window = self.window();
+        self.buckets
+            .iter()
+            .filter(|bucket| now.saturating_duration_since(bucket.start) < window)
+            .fold((0, 0), |(requests, failures), bucket| {
+                (requests + bucket.requests, failures + bucket.failures)
+            })
+    }
+
+    /// Counts a request of the current generation that completed at `now`.
+    fn record(&mut self, trial: bool, failed: bool, now: Instant) {
+        let open = Phase::Open {
+            until: now + Duration::from_millis(self.config.open_for_ms),
+        };
+        match &mut self.phase {
+            Phase::Open { .. } => {}
+            Phase::HalfOpen { .. } if !trial => {}
+            Phase::HalfOpen { .. } if failed => self.transition(open),
+            Phase::HalfOpen { succeeded, .. } => {
+                *succeeded += 1;
+                if *succeeded >= self.config.half_open_requests.unwrap_or(1) {
+                    self.transition(Phase::Closed);
+                }
+            }
+            Phase::Closed => {
+                let window = self.window();
+                while self
+                    .buckets
+                    .front()
+                    .is_some_and(|bucket| now.saturating_duration_since(bucket.start) >= window)
+                {
+                    self.buckets.pop_front();
+                }
+                let width = window / BUCKETS;
+                match self.buckets.back_mut() {
+                    Some(bucket) if now.saturating_duration_since(bucket.start) < width => {
+                        bucket.requests += 1;
+                        bucket.failures += usize::from(failed);
+                    }
+                    _ => self.buckets.push_back(Bucket {
+                        start: now,
+                        requests: 1,
+                        failures: usize::from(failed),
+                    }),
+                }
+                let (requests, failures) = self.counts(now);
+                if requests >= self.config.min_requests as usize
+                    && failures as f64 >= self.config.failure_threshold * requests as f64
+                {
+                    self.transition(open);
+                }
+            }
+        }
+    }
+}
+
+impl Attempt<'_> {
+    /// Counts the request with the status of its response, a failure if it's
+    /// 5xx, completed at `now`.
+    pub(crate) fn finish(mut self, status: StatusCode, now: Instant) {
+        self.finished = true;
+        self.complete(Some(status.is_server_error()), now);
+    }
+
+    /// Counts the request, as failed or not, or as never sent if `failed` is
+    /// `None`.
+    fn complete(&self, failed: Option<bool>, now: Instant) {
+        let mut breakers = self.circuits.lock();
+        let Some(breaker) = breakers.get_mut(&self.controller) else {
+            return;
+        };
+        if breaker.generation != self.generation {
+            return;
+        }
+        match failed {
+            Some(failed) => breaker.record(self.trial, failed, now),
+            None => {
+                if let Phase::HalfOpen { trials, .. } = &mut breaker.phase
+                    && self.trial
+                {
+                    *trials -= 1;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Attempt<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            let failed = std::thread::panicking().then_some(true);
+            self.complete(failed, self.started);
+        }
+    }
+}
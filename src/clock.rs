@@ -0,0 +1,36 @@
+//! Defines the clock the router reads the time from, which tests can replace
+//! with `Router::set_clock`, e.g. with a `testing::MockClock`.
+
+use std::time::{Instant, SystemTime};
+
+/// A source of the current time, for the elapsed time of `RequestSummary`,
+/// the `X-Response-Time-Ms` header, `Router::on_slow_request`, the expiry of
+/// cached responses and idempotency records, the `exp` and `nbf` claims of
+/// JWTs, the timestamps of signed requests, deadlines sent as Unix times and
+/// the windows of circuit breakers. A `session::MemoryStore` takes one with
+/// `with_clock`.
+///
+/// Timeouts, and the deadlines handlers get, are enforced by timers running on
+/// the system clock, so they're always measured from it: a mock clock standing
+/// still would otherwise time requests out early.
+pub trait Clock {
+    /// Returns the current time, for measuring durations.
+    fn now(&self) -> Instant;
+
+    /// Returns the current wall-clock time, for comparing with timestamps.
+    fn system_time(&self) -> SystemTime;
+}
+
+/// The system's monotonic clock, used by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
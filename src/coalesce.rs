@@ -0,0 +1,243 @@
+//! Defines the coalescing of identical concurrent requests to routes with
+//! `"coalesce": true` by `Router::route_async`: the first runs the handler,
+//! and the others wait for its response and get a copy of it.
+
+use crate::hooks::Outcome;
+use crate::request::HttpRequest;
+use crate::response::HttpResponse;
+use http::header::{AUTHORIZATION, HeaderName, HeaderValue, SET_COOKIE, VARY};
+use http::{HeaderMap, Method};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// The settings of request coalescing, set with `Router::set_coalescing`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoalesceOptions {
+    /// The longest a request waits for the response of the identical request
+    /// running the handler, after which it runs the handler itself. Defaults
+    /// to 10 seconds.
+    pub max_wait: Duration,
+    /// Whether requests with an `Authorization` header are coalesced, only
+    /// with requests with the same header. `false` by default: they always
+    /// run the handler.
+    pub allow_authorization: bool,
+}
+
+impl Default for CoalesceOptions {
+    fn default() -> Self {
+        Self {
+            max_wait: Duration::from_secs(10),
+            allow_authorization: false,
+        }
+    }
+}
+
+/// Identifies identical requests: the method, the controller, the request
+/// target (path and query) and the credentials, if they're coalesced.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Key {
+    method: Method,
+    controller: Box<str>,
+    target: String,
+    authorization: Option<HeaderValue>,
+}
+
+enum State {
+    Running,
+    /// The handler answered, with the response to share, or `None` if it
+    /// can't be shared, e.g. a streamed one.
+    Landed(Option<HttpResponse>),
+    /// The handler panicked, timed out or was cancelled: a waiting request
+    /// runs it again.
+    Abandoned,
+}
+
+/// A request running the handler, waited for by identical ones.
+struct Flight {
+    state: Mutex<State>,
+    /// Wakes the waiting requests when the flight lands or is abandoned.
+    done: Notify,
+    /// The headers of the request, compared with those of the waiting ones
+    /// for the response's `Vary` header.
+    headers: HeaderMap,
+}
+
+impl Flight {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// The requests running the handler of a route with `coalesce`, by key.
+#[derive(Default)]
+pub(crate) struct Flights {
+    running: Mutex<HashMap<Key, Arc<Flight>>>,
+}
+
+/// How a request takes part in coalescing.
+pub(crate) enum Joined<'a> {
+    /// The copy of the response to an identical request.
+    Shared(HttpResponse),
+    /// The request runs the handler, and shares its response through the
+    /// leader, if any.
+    Lead(Option<Leader<'a>>),
+}
+
+/// A request running the handler, for identical ones to wait for. Dropped
+/// without landing, e.g. when its handler panics or its future is dropped,
+/// the flight is abandoned, and a waiting request runs the handler instead.
+pub(crate) struct Leader<'a> {
+    flights: &'a Flights,
+    key: Key,
+    flight: Arc<Flight>,
+}
+
+impl Flights {
+    /// Joins the flight of an identical request to `controller`, waiting for
+    /// its response, or starts one. The request runs the handler itself if it
+    /// has an `Authorization` header that `options` doesn't allow, the
+    /// response can't be shared or its `Vary` headers differ, or it waits
+    /// past `options.max_wait` or its deadline.
+    pub(crate) async fn join(
+        &self,
+        options: &CoalesceOptions,
+        controller: &str,
+        req: &HttpRequest,
+    ) -> Joined<'_> {
+        let authorization = req.headers.get(AUTHORIZATION).cloned();
+        if authorization.is_some() && !options.allow_authorization {
+            return Joined::Lead(None);
+        }
+        let key = Key {
+            method: req.method.clone(),
+            controller: controller.into(),
+            target: req
+                .uri
+                .path_and_query()
+                .map_or_else(|| req.uri.path().to_string(), ToString::to_string),
+            authorization,
+        };
+        // A `max_wait` too large to add to the current time is no limit.
+        let give_up = match (Instant::now().checked_add(options.max_wait), req.deadline) {
+            (Some(max_wait), Some(deadline)) => Some(max_wait.min(deadline)),
+            (max_wait, deadline) => max_wait.or(deadline),
+        };
+
+        loop {
+            let flight = {
+                let mut running = self.lock();
+                match running.get(&key) {
+                    Some(flight) => Arc::clone(flight),
+                    None => {
+                        let flight = Arc::new(Flight {
+                            state: Mutex::new(State::Running),
+                            done: Notify::new(),
+                            headers: req.headers.clone(),
+                        });
+                        running.insert(key.clone(), Arc::clone(&flight));
+                        return Joined::Lead(Some(Leader {
+                            flights: self,
+                            key,
+                            flight,
+                        }));
+                    }
+                }
+            };
+            loop {
+                // Created before the state is checked, so a landing in
+                // between isn't missed.
+                let done = flight.done.notified();
+                match &*flight.lock() {
+                    State::Running => {}
+                    State::Landed(Some(response))
+                        if varies_alike(&response.headers, &flight.headers, &req.headers) =>
+                    {
+                        return Joined::Shared(response.buffered_copy());
+                    }
+                    State::Landed(_) => return Joined::Lead(None),
+                    State::Abandoned => break,
+                }
+                match give_up {
+                    Some(give_up) => {
+                        if tokio::time::timeout_at(give_up.into(), done).await.is_err() {
+                            return Joined::Lead(None);
+                        }
+                    }
+                    None => done.await,
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<Key, Arc<Flight>>> {
+        self.running.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Leader<'_> {
+    /// Shares `response` with the requests waiting for it, unless it can't
+    /// be shared: a streamed body, a `Set-Cookie` header or `Vary: *`. A
+    /// response to a timed out handler isn't shared, and another request runs
+    /// the handler instead.
+    pub(crate) fn land(self, response: &HttpResponse, outcome: Outcome) {
+        if outcome != Outcome::Completed {
+            return;
+        }
+        let shareable = response.body.as_bytes().is_some()
+            && !response.headers.contains_key(SET_COOKIE)
+            && vary_names(&response.headers).is_some();
+        *self.flight.lock() = State::Landed(shareable.then(|| response.buffered_copy()));
+        self.flight.done.notify_waiters();
+    }
+}
+
+impl Drop for Leader<'_> {
+    fn drop(&mut self) {
+        {
+            let mut state = self.flight.lock();
+            if matches!(*state, State::Running) {
+                *state = State::Abandoned;
+            }
+        }
+        self.flight.done.notify_waiters();
+        let mut running = self.flights.lock();
+        if running
+            .get(&self.key)
+            .is_some_and(|flight| Arc::ptr_eq(flight, &self.flight))
+        {
+            running.remove(&self.key);
+        }
+    }
+}
+
+/// Returns whether two requests have the same values of the headers the
+/// response varies on.
+fn varies_alike(response: &HeaderMap, first: &HeaderMap, second: &HeaderMap) -> bool {
+    vary_names(response).is_some_and(|names| {
+        names
+            .iter()
+            .all(|name| first.get_all(name).iter().eq(second.get_all(name).iter()))
+    })
+}
+
+/// Returns the header names listed in the `Vary` headers, or `None` for
+/// `Vary: *`, which can't be shared.
+fn vary_names(headers: &HeaderMap) -> Option<Vec<HeaderName>> {
+    let mut names = Vec::new();
+    for value in headers.get_all(VARY) {
+        let value = value.to_str().ok()?;
+        for name in value
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            if name == "*" {
+                return None;
+            }
+            names.push(HeaderName::from_bytes(name.as_bytes()).ok()?);
+        }
+    }
+    Some(names)
+}
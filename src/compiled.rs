@@ -0,0 +1,238 @@
+//! Defines `CompiledRoutes`, the routes and rewrite rules of a configuration
+//! ready for matching, which can be used without a `Router` or any handlers,
+//! e.g. to check in CI that a configuration change doesn't alter how recorded
+//! paths are routed.
+
+use crate::config::{self, Config, Endpoint};
+use crate::error::{LoadWarning, RouterError};
+use crate::path::{self, PathDecoding};
+use crate::rewrite::{self, RewriteRule};
+use crate::table::{Lookup, RouteTable};
+use http::Method;
+use http::header::HeaderName;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// The compiled routing table and rewrite rules of a configuration. A `Router`
+/// matches requests with the same type, so both resolve paths identically.
+#[derive(Clone)]
+pub struct CompiledRoutes {
+    pub(crate) table: RouteTable,
+    pub(crate) rewrites: Vec<RewriteRule>,
+    /// The controller aliases of the configuration's `controllers` section.
+    pub(crate) controllers: BTreeMap<String, String>,
+    /// The headers every request must have.
+    pub(crate) required_headers: Vec<HeaderName>,
+}
+
+/// How a request is resolved by [`CompiledRoutes::resolve`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MatchOutcome {
+    /// A route matched.
+    Matched {
+        /// The pattern of the route, e.g. `/users/:id`.
+        pattern: String,
+        /// The controller of the route; the stable one for canary routes.
+        controller: String,
+        /// The percent-decoded path parameters, in path order.
+        params: Vec<(String, String)>,
+    },
+    /// The path matched routes for other methods only, listed sorted.
+    MethodNotAllowed { allowed: Vec<Method> },
+    /// No route matched the path.
+    NotFound,
+    /// The path was rejected, e.g. because it escapes the root or has invalid
+    /// percent-encoding.
+    InvalidPath,
+}
+
+/// A sample path that two `CompiledRoutes` resolve differently for a method,
+/// returned by [`CompiledRoutes::diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutingChange {
+    pub method: Method,
+    pub path: String,
+    pub before: MatchOutcome,
+    pub after: MatchOutcome,
+}
+
+impl CompiledRoutes {
+    /// Compiles the routes and rewrite rules of `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError` if an endpoint or rewrite rule is invalid, or if
+    /// routes conflict, as `Router::new` does.
+    pub fn new(config: &Config) -> Result<Self, RouterError> {
+        Self::new_with_report(config.clone()).map(|(routes, _)| routes)
+    }
+
+    /// Compiles `config` like `new`, also returning the load warnings.
+    pub(crate) fn new_with_report(config: Config) -> Result<(Self, Vec<LoadWarning>), RouterError> {
+        let rewrites = config
+            .rewrites
+            .into_iter()
+            .map(|rule| RewriteRule::new(rule.pattern, rule.to))
+            .collect::<Result<Vec<_>, _>>()?;
+        let required_headers = config
+            .required_headers
+            .iter()
+            .map(|name| {
+                HeaderName::from_bytes(name.as_bytes()).map_err(|e| RouterError::Header {
+                    name: name.clone(),
+                    reason: e.to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let controllers = config.controllers;
+        let endpoints = config
+            .endpoints
+            .into_iter()
+            .map(|endpoint| expand_aliases(&controllers, endpoint))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (table, warnings) = RouteTable::new_with_report(endpoints)?;
+        Ok((
+            Self {
+                table,
+                rewrites,
+                controllers,
+                required_headers,
+            },
+            warnings,
+        ))
+    }
+
+    /// Returns the canonical form of a controller name, the one routes store,
+    /// by expanding the alias it starts with, if any.
+    pub(crate) fn controller_name(&self, name: String) -> String {
+        match name.split_once("::") {
+            Some((prefix, _)) if self.controllers.contains_key(prefix) => {
+                config::expand_controller(&self.controllers, &name).unwrap_or(name)
+            }
+            _ => name,
+        }
+    }
+
+    /// Expands the controller aliases of an endpoint added at runtime, as for
+    /// those of the configuration.
+    pub(crate) fn expand_aliases(&self, endpoint: Endpoint) -> Result<Endpoint, RouterError> {
+        expand_aliases(&self.controllers, endpoint)
+    }
+
+    /// Resolves a request for `method` and `path` as a `Router` with the default
+    /// settings does: the path is normalized and rewritten before matching, and
+    /// parameters are percent-decoded, rejecting invalid encodings.
+    ///
+    /// The methods the router answers itself, such as `HEAD` for `GET` routes,
+    /// aren't taken into account.
+    pub fn resolve(&self, method: &Method, path: &str) -> MatchOutcome {
+        if path::percent_decode(path, PathDecoding::Reject).is_none() {
+            return MatchOutcome::InvalidPath;
+        }
+        let Some(normalized) = path::normalize(path) else {
+            return MatchOutcome::InvalidPath;
+        };
+        let path = match rewrite::apply_all(&self.rewrites, &normalized) {
+            Some(rewritten) => Cow::Owned(rewritten),
+            None => normalized,
+        };
+
+        match self.table.lookup(method, &path) {
+            Lookup::Found { entry, params } => {
+                let mut decoded = Vec::with_capacity(params.len());
+                for (name, value) in params.iter() {
+                    match path::percent_decode(value, PathDecoding::Reject) {
+                        Some(value) => decoded.push((name.to_string(), value.into_owned())),
+                        None => return MatchOutcome::InvalidPath,
+                    }
+                }
+                MatchOutcome::Matched {
+                    pattern: entry.pattern.to_string(),
+                    controller: entry.controller.to_string(),
+                    params: decoded,
+                }
+            }
+            Lookup::MethodNotAllowed(allowed) => MatchOutcome::MethodNotAllowed {
+                allowed: allowed.methods.clone(),
+            },
+            Lookup::NotFound => MatchOutcome::NotFound,
+        }
+    }
+
+    /// Resolves each of `sample_paths` with both `self` and `other`, for every
+    /// method with a route in either, and returns the requests resolved
+    /// differently, in the order of `sample_paths`.
+    pub fn diff(&self, other: &CompiledRoutes, sample_paths: &[String]) -> Vec<RoutingChange> {
+        let mut methods: Vec<Method> = self
+            .table
+            .routes()
+            .chain(other.table.routes())
+            .map(|(method, _)| method.clone())
+            .collect();
+        methods.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        methods.dedup();
+
+        let mut changes = Vec::new();
+        for path in sample_paths {
+            for method in &methods {
+                let before = self.resolve(method, path);
+                let after = other.resolve(method, path);
+                if before != after {
+                    changes.push(RoutingChange {
+                        method: method.clone(),
+                        path: path.clone(),
+                        before,
+                        after,
+                    });
+                }
+            }
+        }
+        changes
+    }
+}
+
+/// Expands the aliases of the controller names of an endpoint: its
+/// `controller`, the controllers of its `canary` and its `mirror_to`.
+///
+/// # Errors
+///
+/// Returns a `RouterError::Endpoint` for a name starting with an unknown
+/// alias.
+fn expand_aliases(
+    aliases: &BTreeMap<String, String>,
+    mut endpoint: Endpoint,
+) -> Result<Endpoint, RouterError> {
+    if aliases.is_empty() {
+        return Ok(endpoint);
+    }
+    let (method, path) = (endpoint.method.clone(), endpoint.path.clone());
+    let expand = |name: &mut String| {
+        if name.is_empty() {
+            return Ok(());
+        }
+        match config::expand_controller(aliases, name) {
+            Some(expanded) => {
+                *name = expanded;
+                Ok(())
+            }
+            None => Err(RouterError::Endpoint {
+                method: method.clone(),
+                path: path.clone(),
+                reason: format!(
+                    "unknown controller alias '{}' in '{name}'",
+                    name.split("::").next().unwrap_or_default()
+                ),
+            }),
+        }
+    };
+    expand(&mut endpoint.controller)?;
+    if let Some(canary) = &mut endpoint.canary {
+        expand(&mut canary.stable)?;
+        expand(&mut canary.candidate)?;
+    }
+    if let Some(mirror_to) = &mut endpoint.mirror_to {
+        expand(mirror_to)?;
+    }
+    Ok(endpoint)
+}
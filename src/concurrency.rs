@@ -0,0 +1,196 @@
+//! Defines the concurrency limits of routes with a `max_concurrency`: a
+//! counting semaphore per route, shared by `Router::route` and
+//! `Router::route_async`, with a bounded queue of waiting requests. Blocked
+//! threads wait on a condition variable and futures on a FIFO semaphore,
+//! woken when a permit is released.
+
+use http::{Method, StatusCode};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// The concurrency of a route with a `max_concurrency`, as returned by
+/// `Router::stats`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteStats {
+    pub method: Method,
+    /// The path pattern of the route, as written in the configuration.
+    pub path: String,
+    /// The requests whose handler is running.
+    pub in_flight: usize,
+    /// The requests waiting for the handler.
+    pub queued: usize,
+    pub max_concurrency: usize,
+}
+
+/// What the router is doing, returned by `Router::stats`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouterStats {
+    /// The requests being routed, as counted for graceful shutdown.
+    pub in_flight: usize,
+    /// The routes with a `max_concurrency`, by path and method.
+    pub routes: Vec<RouteStats>,
+    /// The circuits of controllers, by name.
+    pub circuits: Vec<crate::circuit::CircuitStats>,
+}
+
+/// The response to requests turned away by a concurrency limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LimitResponse {
+    pub(crate) status: StatusCode,
+    pub(crate) retry_after: Duration,
+}
+
+impl Default for LimitResponse {
+    fn default() -> Self {
+        Self {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            retry_after: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    active: usize,
+    queued: usize,
+}
+
+/// The semaphore of a route.
+#[derive(Debug)]
+pub(crate) struct ConcurrencyLimit {
+    max: usize,
+    max_queue: usize,
+    state: Mutex<State>,
+    released: Condvar,
+    /// The permits not taken, which the futures of `acquire_async` wait for
+    /// in turn. Threads only take one if no future waits.
+    #[cfg(feature = "async")]
+    permits: tokio::sync::Semaphore,
+}
+
+/// The permit of a request to run the handler, released when it's dropped,
+/// including while unwinding from a panicking handler.
+pub(crate) struct Permit<'a>(&'a ConcurrencyLimit);
+
+/// A request in the queue, leaving it when dropped, e.g. with the future of a
+/// cancelled request.
+struct Queued<'a>(&'a ConcurrencyLimit);
+
+impl ConcurrencyLimit {
+    pub(crate) fn new(max: u32, max_queue: u32) -> Self {
+        Self {
+            max: max as usize,
+            max_queue: max_queue as usize,
+            state: Mutex::default(),
+            released: Condvar::new(),
+            #[cfg(feature = "async")]
+            permits: tokio::sync::Semaphore::new(max as usize),
+        }
+    }
+
+    /// Returns a permit, waiting in the queue if the route is saturated, or
+    /// `None` if the queue is full or `deadline` passes first.
+    pub(crate) fn acquire(&self, deadline: Option<Instant>) -> Option<Permit<'_>> {
+        let mut state = self.lock();
+        if let Some(permit) = self.try_take(&mut state) {
+            return Some(permit);
+        }
+        let queued = self.enqueue(&mut state)?;
+        let permit = loop {
+            state = match deadline {
+                Some(deadline) => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    if left.is_zero() {
+                        break None;
+                    }
+                    match self.released.wait_timeout(state, left) {
+                        Ok((state, _)) => state,
+                        Err(e) => e.into_inner().0,
+                    }
+                }
+                None => self.released.wait(state).unwrap_or_else(|e| e.into_inner()),
+            };
+            if let Some(permit) = self.try_take(&mut state) {
+                break Some(permit);
+            }
+        };
+        // Leaving the queue takes the lock again.
+        drop(state);
+        drop(queued);
+        permit
+    }
+
+    /// Returns a permit like `acquire`, without blocking the thread.
+    #[cfg(feature = "async")]
+    pub(crate) async fn acquire_async(&self, deadline: Option<Instant>) -> Option<Permit<'_>> {
+        let _queued = {
+            let mut state = self.lock();
+            if let Some(permit) = self.try_take(&mut state) {
+                return Some(permit);
+            }
+            self.enqueue(&mut state)?
+        };
+        let acquire = self.permits.acquire();
+        let permit = match deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline.into(), acquire)
+                .await
+                .ok()?,
+            None => acquire.await,
+        };
+        // The semaphore is never closed.
+        permit.ok()?.forget();
+        self.lock().active += 1;
+        Some(Permit(self))
+    }
+
+    /// Returns the number of requests running the handler, and waiting for it.
+    pub(crate) fn counts(&self) -> (usize, usize) {
+        let state = self.lock();
+        (state.active, state.queued)
+    }
+
+    pub(crate) fn max(&self) -> usize {
+        self.max
+    }
+
+    fn try_take(&self, state: &mut State) -> Option<Permit<'_>> {
+        #[cfg(feature = "async")]
+        let available = self
+            .permits
+            .try_acquire()
+            .map(|permit| permit.forget())
+            .is_ok();
+        #[cfg(not(feature = "async"))]
+        let available = state.active < self.max;
+        available.then(|| {
+            state.active += 1;
+            Permit(self)
+        })
+    }
+
+    fn enqueue(&self, state: &mut State) -> Option<Queued<'_>> {
+        (state.queued < self.max_queue).then(|| {
+            state.queued += 1;
+            Queued(self)
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.0.lock().active -= 1;
+        #[cfg(feature = "async")]
+        self.0.permits.add_permits(1);
+        self.0.released.notify_one();
+    }
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.lock().queued -= 1;
+    }
+}
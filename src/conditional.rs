@@ -0,0 +1,72 @@
+//! Defines helpers for conditional requests with time-based validators,
+//! `If-Modified-Since` and `If-Unmodified-Since`.
+
+use crate::request::HttpRequest;
+use crate::response::HttpResponse;
+use http::Method;
+use http::header::{
+    HeaderName, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_UNMODIFIED_SINCE, LAST_MODIFIED,
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+impl HttpRequest {
+    /// Returns the date of the `If-Modified-Since` header, or `None` if it's
+    /// missing or not a valid HTTP-date. All three formats of RFC 9110 are
+    /// accepted: `Sun, 06 Nov 1994 08:49:37 GMT`, the obsolete
+    /// `Sunday, 06-Nov-94 08:49:37 GMT` and `Sun Nov  6 08:49:37 1994`.
+    pub fn if_modified_since(&self) -> Option<SystemTime> {
+        self.date_header(IF_MODIFIED_SINCE)
+    }
+
+    /// Returns the date of the `If-Unmodified-Since` header, parsed like
+    /// [`HttpRequest::if_modified_since`].
+    pub fn if_unmodified_since(&self) -> Option<SystemTime> {
+        self.date_header(IF_UNMODIFIED_SINCE)
+    }
+
+    fn date_header(&self, name: HeaderName) -> Option<SystemTime> {
+        let value = self.headers.get(name)?.to_str().ok()?;
+        httpdate::parse_http_date(value.trim()).ok()
+    }
+}
+
+impl HttpResponse {
+    /// Sets the `Last-Modified` header, formatted as an HTTP-date. The date is
+    /// truncated to whole seconds.
+    pub fn set_last_modified(&mut self, last_modified: SystemTime) -> &mut Self {
+        self.add_header(LAST_MODIFIED, &httpdate::fmt_http_date(last_modified))
+    }
+}
+
+/// Returns `true` if `req` can be answered with a 304 Not Modified for a
+/// resource last modified at `last_modified`: it's a `GET` or `HEAD` request
+/// with an `If-Modified-Since` date no earlier than `last_modified`, and without
+/// an `If-None-Match` header, which takes precedence.
+///
+/// As `Last-Modified` has a one-second resolution, `last_modified` is truncated
+/// to whole seconds before comparing, so a client sending back the
+/// `Last-Modified` it got isn't sent the resource again.
+pub fn not_modified_since(req: &HttpRequest, last_modified: SystemTime) -> bool {
+    if (req.method != Method::GET && req.method != Method::HEAD)
+        || req.headers.contains_key(IF_NONE_MATCH)
+    {
+        return false;
+    }
+    req.if_modified_since()
+        .is_some_and(|since| whole_seconds(last_modified) <= since)
+}
+
+/// Returns `true` if `req` has an `If-Unmodified-Since` date earlier than
+/// `last_modified` (truncated to whole seconds), so it must be answered with a
+/// 412 Precondition Failed.
+pub(crate) fn modified_since_precondition(req: &HttpRequest, last_modified: SystemTime) -> bool {
+    req.if_unmodified_since()
+        .is_some_and(|since| whole_seconds(last_modified) > since)
+}
+
+fn whole_seconds(time: SystemTime) -> SystemTime {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => UNIX_EPOCH + Duration::from_secs(elapsed.as_secs()),
+        Err(_) => time,
+    }
+}
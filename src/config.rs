@@ -1,22 +1,1143 @@
 //! Defines the data structures for parsing the JSON configuration.
 
+use crate::compiled::{CompiledRoutes, MatchOutcome};
+use crate::error::{LoadWarning, RouterError};
+use crate::limits::Limits;
+use crate::method::ANY_METHODS;
 use http::Method;
-use serde::{Deserialize, Deserializer};
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
 
 /// Represents the top-level structure of the `routes.json` file.
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct Config {
+    /// The endpoints, those with a localized `path` expanded into one
+    /// endpoint per locale.
+    #[serde(deserialize_with = "deserialize_endpoints")]
     pub endpoints: Vec<Endpoint>,
+    /// Optional, ordered path rewrite rules applied before route matching.
+    #[serde(default)]
+    pub rewrites: Vec<Rewrite>,
+    /// Headers added to every response that doesn't already have them, e.g.
+    /// `{ "X-Content-Type-Options": "nosniff" }`.
+    #[serde(default)]
+    pub default_response_headers: BTreeMap<String, String>,
+    /// The API keys accepted by routes with `"auth": "api_key"`.
+    #[serde(default)]
+    pub api_keys: Option<ApiKeys>,
+    /// Shorthands for controller name prefixes, e.g. `{ "users":
+    /// "com.acme.users_controller" }` makes `"users::get_by_id"` stand for
+    /// `"com.acme.users_controller::get_by_id"`. Once aliases are defined,
+    /// every controller name with a `::` must start with an alias or the
+    /// prefix it stands for.
+    #[serde(default)]
+    pub controllers: BTreeMap<String, String>,
+    /// Headers every request must have, e.g. `["X-Tenant-Id"]`, in addition to
+    /// the `required_headers` of its endpoint.
+    #[serde(default)]
+    pub required_headers: Vec<String>,
+    /// The bodies of the router's own error responses, such as its 404 Not
+    /// Found, by status code or `"default"` for the other statuses, e.g.
+    /// `{ "404": { "content_type": "text/html", "file": "errors/404.html" } }`.
+    #[serde(default)]
+    pub error_pages: BTreeMap<String, ErrorPages>,
+    /// The limits on the size of requests' heads, e.g. `{ "max_headers": 50 }`,
+    /// the fields left out keeping their defaults. The router's are kept
+    /// without it.
+    #[serde(default)]
+    pub limits: Option<Limits>,
+}
+
+/// The `api_keys` section of the configuration, e.g.
+/// `{ "header": "X-Api-Key", "query_param": "api_key", "keys": { "partner-a": "<sha256 hex>" } }`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApiKeys {
+    /// The header requests send their key in, `X-Api-Key` by default.
+    #[serde(default = "api_key_header")]
+    pub header: String,
+    /// A query parameter also accepted, for clients that can't set headers.
+    #[serde(default)]
+    pub query_param: Option<String>,
+    /// The hex-encoded SHA-256 hashes of the keys, by key ID. The key itself
+    /// is never stored.
+    pub keys: BTreeMap<String, String>,
+}
+
+fn api_key_header() -> String {
+    "X-Api-Key".to_string()
+}
+
+/// The pages of a status in the `error_pages` section: one, or several of
+/// different content types, e.g. HTML for browsers and JSON for API clients,
+/// picked from by the request's `Accept` header.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum ErrorPages {
+    One(ErrorPage),
+    Variants(Vec<ErrorPage>),
+}
+
+/// A page of the `error_pages` section: the template of an error response's
+/// body, inline or in a file read when loading, relative to the configuration
+/// file. `{{status}}`, `{{path}}` and `{{request_id}}` are replaced with the
+/// status code, the path requested and its `X-Request-Id` header.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ErrorPage {
+    /// The `Content-Type` of the page, e.g. `text/html; charset=utf-8`.
+    pub content_type: String,
+    /// The template itself; either `body` or `file` must be set.
+    #[serde(default)]
+    pub body: Option<String>,
+    /// The file holding the template.
+    #[serde(default)]
+    pub file: Option<String>,
 }
 
 /// Represents a single endpoint definition in the configuration.
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 pub struct Endpoint {
-    #[serde(deserialize_with = "deserialize_method")]
+    /// The method of the route. In the configuration, it can also be `ANY`,
+    /// standing for one endpoint per method but `TRACE` and `CONNECT`.
+    #[serde(
+        deserialize_with = "deserialize_method",
+        serialize_with = "serialize_method"
+    )]
     pub method: Method,
+    /// The path pattern, e.g. `/users/:id`. In the configuration, it can also
+    /// be a map of locale to path, e.g. `{ "en": "/pricing", "de": "/preise" }`,
+    /// standing for one endpoint per locale with its `locale` set.
     pub path: String,
+    /// The locale of the path, available to the handler as
+    /// `HttpRequest::locale`.
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// The controller handling the route. Left empty for canary routes. In the
+    /// configuration, it can also be an object with the controller's `name`
+    /// and the `args` of its handler factory, e.g. `{ "name": "static_json",
+    /// "args": { "file": "plans.json" } }`; see `Router::register_factory`.
+    #[serde(default)]
     pub controller: String,
+    /// The arguments the controller's handler factory builds the route's
+    /// handler from, from the object form of `controller`.
+    #[serde(skip)]
+    pub controller_args: Option<serde_json::Value>,
+    /// A template of the route's response body, in place of `controller`: the
+    /// router answers with a 200 OK rendering it, e.g. `"{\"id\":
+    /// \"{params.id}\"}"`. Placeholders are `{params.name}`, `{query.name}`
+    /// and `{headers.name}`, with an optional default, e.g. `{query.page|1}`;
+    /// their values are escaped for `content_type`.
+    #[serde(default)]
+    pub body_template: Option<String>,
+    /// The `Content-Type` of the `body_template` responses, which also
+    /// chooses how values are escaped: as JSON strings for JSON types, for
+    /// HTML for HTML, and not at all otherwise. Defaults to
+    /// `text/plain; charset=utf-8`.
+    #[serde(default)]
+    pub content_type: Option<String>,
+    /// Serves the files of a directory, in place of `controller`, with the
+    /// `files` feature; see [`StaticFiles`].
+    #[serde(default)]
+    pub static_files: Option<StaticFiles>,
     pub description: String,
+    /// Splits the route's traffic between two controllers, in place of `controller`.
+    #[serde(default)]
+    pub canary: Option<Canary>,
+    /// Resolves conflicts with other routes for the same method: when two routes
+    /// with priorities conflict, the higher-priority one is kept and the other is
+    /// reported as shadowed. Conflicts involving a route without a priority, or
+    /// between equal priorities, are errors.
+    #[serde(default)]
+    pub priority: Option<i32>,
+    /// Caches 200 OK responses to `GET` and `HEAD` requests for this many seconds.
+    /// Only takes effect with the `cache` feature.
+    #[serde(default)]
+    pub cache_ttl_seconds: Option<u64>,
+    /// The time the handler has to respond, in milliseconds. Overrides the
+    /// router's default timeout; enforced by `Router::route_async`.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// The most requests the route's handler runs at once. Requests beyond
+    /// that wait in the route's queue, if `max_queue` leaves room, or get a
+    /// 503 Service Unavailable (see `Router::set_concurrency_limit_response`).
+    #[serde(default)]
+    pub max_concurrency: Option<u32>,
+    /// The most requests waiting for the handler when `max_concurrency` are
+    /// running, none by default. They wait until their deadline, if any.
+    #[serde(default)]
+    pub max_queue: Option<u32>,
+    /// The largest request body the route accepts, in bytes. Larger requests
+    /// get a 413 Content Too Large before the handler, or any check reading
+    /// the body, runs.
+    #[serde(default)]
+    pub max_request_size: Option<u64>,
+    /// The largest response body the route's handler can send, in bytes.
+    /// Overrides the router's limit (see `Router::set_max_response_size`).
+    #[serde(default)]
+    pub max_response_size: Option<u64>,
+    /// Fails requests to the route's controller fast with a 503 Service
+    /// Unavailable once too many of them fail; see [`CircuitBreaker`].
+    #[serde(default)]
+    pub circuit_breaker: Option<CircuitBreaker>,
+    /// Keeps the route available in maintenance mode, e.g. for health checks.
+    #[serde(default)]
+    pub maintenance_exempt: bool,
+    /// A controller that also gets a copy of each request, e.g. a rewritten
+    /// handler being compared against this one. Its responses are discarded.
+    #[serde(default)]
+    pub mirror_to: Option<String>,
+    /// The guards checked, in order, before the handler is called, by the names
+    /// they're registered under with `Router::register_guard`. The response of
+    /// the first that fails is sent instead.
+    #[serde(default)]
+    pub guards: Vec<String>,
+    /// Whether unsafe requests to the route must carry the CSRF token, if CSRF
+    /// protection is enabled with `Router::use_csrf`. Set to `false` for routes
+    /// called by other servers, such as webhooks.
+    #[serde(default = "enabled")]
+    pub csrf: bool,
+    /// Whether requests to the route with an `Idempotency-Key` header are
+    /// deduplicated, if enabled with `Router::use_idempotency`: a retry gets
+    /// the response to the first request instead of running the handler again.
+    #[serde(default)]
+    pub idempotent: bool,
+    /// Whether identical concurrent `GET` requests to the route share one run
+    /// of the handler, its response being copied to the others, enforced by
+    /// `Router::route_async`; see `Router::set_coalescing`. Requests with an
+    /// `Authorization` header aren't coalesced unless it allows them.
+    #[serde(default)]
+    pub coalesce: bool,
+    /// Requires requests to the route to be authenticated, if JWT validation is
+    /// enabled with `Router::use_jwt_auth`, and their token to grant these
+    /// scopes, e.g. `["orders:write"]`. An empty list only requires a valid
+    /// token.
+    #[serde(default)]
+    pub auth_scopes: Option<Vec<String>>,
+    /// The authentication the route requires, checked before its guards.
+    #[serde(default)]
+    pub auth: Option<Auth>,
+    /// The signature requests to the route must carry, e.g. webhooks from a
+    /// provider signing them with a shared secret. Requests without a valid
+    /// one get a 401 Unauthorized before the guards and handler run.
+    #[serde(default)]
+    pub signature: Option<Signature>,
+    /// A JSON Schema that `POST`, `PUT` and `PATCH` request bodies must match,
+    /// inline or as a file path relative to the configuration file. Only takes
+    /// effect with the `schema` feature; see the `schema` module for the
+    /// supported keywords.
+    #[serde(default)]
+    pub request_schema: Option<serde_json::Value>,
+    /// A JSON Schema that the route's JSON responses must match when response
+    /// validation is enabled with `Router::set_response_validation`, given like
+    /// `request_schema`.
+    #[serde(default)]
+    pub response_schema: Option<serde_json::Value>,
+    /// Labels grouping the route, e.g. `["public", "admin"]`, to load subsets
+    /// of the configuration with `Router::new_filtered` or group documentation.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Headers requests to the route must have, matched case-insensitively.
+    /// Requests missing any get a 400 Bad Request (or the status set with
+    /// `Router::set_required_header_status`) before the guards and handler run.
+    #[serde(default)]
+    pub required_headers: Vec<String>,
+    /// Extension fields, whose names start with `x-`, e.g. `"x-owner":
+    /// "payments"`, kept for tools reading the configuration; the router
+    /// ignores them. Other unknown fields are reported when loading, and left
+    /// out.
+    #[serde(flatten)]
+    pub metadata: BTreeMap<String, serde_json::Value>,
+}
+
+impl Endpoint {
+    /// Creates an endpoint with an empty description, e.g. for routes added at
+    /// runtime with `SharedRouter::add_route`.
+    pub fn new<P: Into<String>, C: Into<String>>(method: Method, path: P, controller: C) -> Self {
+        Self {
+            method,
+            path: path.into(),
+            locale: None,
+            controller: controller.into(),
+            controller_args: None,
+            body_template: None,
+            content_type: None,
+            static_files: None,
+            description: String::new(),
+            canary: None,
+            priority: None,
+            cache_ttl_seconds: None,
+            timeout_ms: None,
+            max_concurrency: None,
+            max_queue: None,
+            max_request_size: None,
+            max_response_size: None,
+            circuit_breaker: None,
+            maintenance_exempt: false,
+            mirror_to: None,
+            guards: Vec::new(),
+            csrf: true,
+            idempotent: false,
+            coalesce: false,
+            auth_scopes: None,
+            auth: None,
+            signature: None,
+            request_schema: None,
+            response_schema: None,
+            tags: Vec::new(),
+            required_headers: Vec::new(),
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the arguments of the controller's handler factory; see
+    /// `controller_args`.
+    pub fn with_controller_args(mut self, args: serde_json::Value) -> Self {
+        self.controller_args = Some(args);
+        self
+    }
+
+    /// Sets the description.
+    pub fn with_description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Sets the time 200 OK responses are cached for; see `cache_ttl_seconds`.
+    pub fn with_cache_ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.cache_ttl_seconds = Some(ttl.as_secs());
+        self
+    }
+
+    /// Sets the handler timeout; see `timeout_ms`.
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout_ms = Some(timeout.as_millis().try_into().unwrap_or(u64::MAX));
+        self
+    }
+
+    /// Sets the most requests the handler runs at once; see
+    /// `max_concurrency`.
+    pub fn with_max_concurrency(mut self, max: u32) -> Self {
+        self.max_concurrency = Some(max);
+        self
+    }
+
+    /// Sets the most requests waiting for the handler; see `max_queue`.
+    pub fn with_max_queue(mut self, max: u32) -> Self {
+        self.max_queue = Some(max);
+        self
+    }
+
+    /// Sets the largest request body the route accepts; see
+    /// `max_request_size`.
+    pub fn with_max_request_size(mut self, max: u64) -> Self {
+        self.max_request_size = Some(max);
+        self
+    }
+
+    /// Sets the circuit breaker of the route's controller; see
+    /// [`CircuitBreaker`].
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Keeps the route available in maintenance mode.
+    pub fn maintenance_exempt(mut self) -> Self {
+        self.maintenance_exempt = true;
+        self
+    }
+
+    /// Mirrors requests to `controller`; see `mirror_to`.
+    pub fn with_mirror<S: Into<String>>(mut self, controller: S) -> Self {
+        self.mirror_to = Some(controller.into());
+        self
+    }
+
+    /// Splits the route's traffic between two controllers; see [`Canary`].
+    /// The endpoint's `controller` must be empty.
+    pub fn with_canary(mut self, canary: Canary) -> Self {
+        self.canary = Some(canary);
+        self
+    }
+
+    /// Adds a guard; see `guards`.
+    pub fn with_guard<S: Into<String>>(mut self, guard: S) -> Self {
+        self.guards.push(guard.into());
+        self
+    }
+
+    /// Adds a tag; see `tags`.
+    pub fn with_tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Adds a header requests must have; see `required_headers`.
+    pub fn with_required_header<S: Into<String>>(mut self, name: S) -> Self {
+        self.required_headers.push(name.into());
+        self
+    }
+
+    /// Exempts the route from CSRF protection; see `csrf`.
+    pub fn without_csrf(mut self) -> Self {
+        self.csrf = false;
+        self
+    }
+
+    /// Deduplicates retried requests to the route; see `idempotent`.
+    pub fn idempotent(mut self) -> Self {
+        self.idempotent = true;
+        self
+    }
+
+    /// Coalesces identical concurrent requests to the route; see `coalesce`.
+    pub fn coalesced(mut self) -> Self {
+        self.coalesce = true;
+        self
+    }
+
+    /// Requires authentication with these scopes; see `auth_scopes`.
+    pub fn with_auth_scopes<S: Into<String>, I: IntoIterator<Item = S>>(
+        mut self,
+        scopes: I,
+    ) -> Self {
+        self.auth_scopes = Some(scopes.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Requires authentication; see `auth`.
+    pub fn with_auth(mut self, auth: Auth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Sets the schema of request bodies; see `request_schema`.
+    pub fn with_request_schema(mut self, schema: serde_json::Value) -> Self {
+        self.request_schema = Some(schema);
+        self
+    }
+
+    /// Sets the schema of responses; see `response_schema`.
+    pub fn with_response_schema(mut self, schema: serde_json::Value) -> Self {
+        self.response_schema = Some(schema);
+        self
+    }
+
+    /// Adds an extension field; see `metadata`.
+    pub fn with_metadata<K: Into<String>>(mut self, key: K, value: serde_json::Value) -> Self {
+        self.metadata.insert(key.into(), value);
+        self
+    }
+
+    /// Sets the priority.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Requires requests to be signed; see [`Signature`].
+    pub fn with_signature(mut self, signature: Signature) -> Self {
+        self.signature = Some(signature);
+        self
+    }
+}
+
+/// The signature of an endpoint's requests, an HMAC-SHA256 of their body with
+/// a shared secret, e.g.
+/// `{ "header": "X-Hub-Signature-256", "algorithm": "hmac-sha256", "secret_env": "GITHUB_WEBHOOK_SECRET" }`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    /// The header carrying the signature.
+    pub header: String,
+    pub algorithm: SignatureAlgorithm,
+    /// The name of the secret, read from the environment variable of that
+    /// name, or from the `SecretProvider` set with
+    /// `Router::set_secret_provider`.
+    pub secret_env: String,
+    /// How old a signature's timestamp can be, or how far in the future, for
+    /// the algorithms that sign one. Defaults to 300 seconds.
+    #[serde(default)]
+    pub tolerance_seconds: Option<u64>,
+}
+
+impl Signature {
+    /// Creates a signature in `header`, with the secret named `secret_env`.
+    pub fn new<H: Into<String>, S: Into<String>>(
+        header: H,
+        algorithm: SignatureAlgorithm,
+        secret_env: S,
+    ) -> Self {
+        Self {
+            header: header.into(),
+            algorithm,
+            secret_env: secret_env.into(),
+            tolerance_seconds: None,
+        }
+    }
+
+    /// Sets how old or early a signature's timestamp can be.
+    pub fn with_tolerance(mut self, tolerance: std::time::Duration) -> Self {
+        self.tolerance_seconds = Some(tolerance.as_secs());
+        self
+    }
+}
+
+/// The circuit breaker of a controller, e.g.
+/// `{ "failure_threshold": 0.5, "min_requests": 20, "open_for_ms": 30000 }`.
+///
+/// Failures are responses with a 5xx status, including handler timeouts, and
+/// panicking handlers. Once at least `min_requests` requests in the rolling
+/// window got to the controller, and the share of failures among them reaches
+/// `failure_threshold`, the circuit opens: requests get a 503 Service
+/// Unavailable with `Retry-After` without reaching the controller. After
+/// `open_for_ms`, it's half-open: `half_open_requests` trial requests are let
+/// through, closing the circuit if they all succeed, and opening it again if
+/// one fails.
+///
+/// The breaker is the controller's, shared by its routes; it takes the
+/// settings of the route a request matched.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+pub struct CircuitBreaker {
+    /// The share of failed requests opening the circuit, above 0 and up to
+    /// 1.
+    pub failure_threshold: f64,
+    /// The fewest requests in the window for the circuit to open.
+    pub min_requests: u32,
+    /// How long the circuit stays open, in milliseconds, up to a year.
+    pub open_for_ms: u64,
+    /// The length of the rolling window requests are counted in, in
+    /// milliseconds. Defaults to 10 seconds.
+    #[serde(default)]
+    pub window_ms: Option<u64>,
+    /// The trial requests let through while half-open. Defaults to 1.
+    #[serde(default)]
+    pub half_open_requests: Option<u32>,
+}
+
+impl CircuitBreaker {
+    /// Creates a breaker opening for `open_for` once `failure_threshold` of at
+    /// least `min_requests` requests fail.
+    pub fn new(failure_threshold: f64, min_requests: u32, open_for: std::time::Duration) -> Self {
+        Self {
+            failure_threshold,
+            min_requests,
+            open_for_ms: open_for.as_millis().try_into().unwrap_or(u64::MAX),
+            window_ms: None,
+            half_open_requests: None,
+        }
+    }
+
+    /// Sets the length of the rolling window.
+    pub fn with_window(mut self, window: std::time::Duration) -> Self {
+        self.window_ms = Some(window.as_millis().try_into().unwrap_or(u64::MAX));
+        self
+    }
+
+    /// Sets the number of trial requests let through while half-open.
+    pub fn with_half_open_requests(mut self, requests: u32) -> Self {
+        self.half_open_requests = Some(requests);
+        self
+    }
+}
+
+// Thresholds are compared bit for bit, so that the equality is total.
+impl PartialEq for CircuitBreaker {
+    fn eq(&self, other: &Self) -> bool {
+        self.failure_threshold.to_bits() == other.failure_threshold.to_bits()
+            && self.min_requests == other.min_requests
+            && self.open_for_ms == other.open_for_ms
+            && self.window_ms == other.window_ms
+            && self.half_open_requests == other.half_open_requests
+    }
+}
+
+impl Eq for CircuitBreaker {}
+
+/// How a request's signature is computed and sent.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureAlgorithm {
+    /// The hex-encoded HMAC-SHA256 of the body, optionally prefixed with
+    /// `sha256=`, as GitHub sends it.
+    #[serde(rename = "hmac-sha256")]
+    HmacSha256,
+    /// Stripe's scheme, e.g. `t=1492774577,v1=5257a869...`: the hex-encoded
+    /// HMAC-SHA256 of the timestamp, a `.` and the body, in one or more `v1`
+    /// elements. The timestamp must be within the tolerance.
+    #[serde(rename = "stripe-v1")]
+    StripeV1,
+}
+
+/// Serves the files of a directory for a route whose path ends with a
+/// catch-all parameter naming the file, e.g. `/assets/*file` with
+/// `{ "dir": "public" }` (with the `files` feature).
+///
+/// Files outside the directory, through `..` segments or symbolic links,
+/// are never served. Directories get a 404 Not Found, unless
+/// `directory_listing` is on.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct StaticFiles {
+    /// The directory, relative to the configuration file's directory.
+    pub dir: String,
+    /// Whether responses have an `ETag` made from the file's size and
+    /// modification time, and requests whose `If-None-Match` matches it get a
+    /// 304 Not Modified. `true` by default.
+    #[serde(default = "enabled")]
+    pub etag: bool,
+    /// Whether responses have a `Last-Modified` header, and requests with an
+    /// `If-Modified-Since` no earlier than it get a 304 Not Modified. `true`
+    /// by default.
+    #[serde(default = "enabled")]
+    pub last_modified: bool,
+    /// Whether requests accepting gzip get the `.gz` sibling of a file, if
+    /// there's one, e.g. `app.js.gz` for `app.js`, with `Content-Encoding:
+    /// gzip`. `true` by default.
+    #[serde(default = "enabled")]
+    pub precompressed: bool,
+    /// Whether directories get an HTML page listing their entries. `false` by
+    /// default.
+    #[serde(default)]
+    pub directory_listing: bool,
+}
+
+/// Sends a percentage of a route's traffic to a candidate controller, and the
+/// rest to the stable one, e.g.
+/// `{ "stable": "orders::create", "candidate": "orders::create_v2", "percent": 5 }`.
+///
+/// Requests are assigned by hashing the `key`, so the same key always gets the
+/// same controller for a given percentage. Responses carry an `X-Canary: stable`
+/// or `X-Canary: candidate` header, and aren't cached.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Canary {
+    pub stable: String,
+    pub candidate: String,
+    /// The percentage of requests sent to the candidate, from 0 to 100.
+    pub percent: u8,
+    #[serde(default)]
+    pub key: CanaryKey,
+}
+
+impl Canary {
+    /// Creates a canary sending `percent` percent of requests, picked at random,
+    /// to `candidate`.
+    pub fn new<S: Into<String>, C: Into<String>>(stable: S, candidate: C, percent: u8) -> Self {
+        Self {
+            stable: stable.into(),
+            candidate: candidate.into(),
+            percent,
+            key: CanaryKey::default(),
+        }
+    }
+
+    /// Sets the key requests are assigned by.
+    pub fn with_key(mut self, key: CanaryKey) -> Self {
+        self.key = key;
+        self
+    }
+}
+
+/// The authentication an endpoint requires, e.g. `"auth": "api_key"`.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Auth {
+    /// A key from the `api_keys` section, or set with `Router::set_api_keys`.
+    /// Requests without a valid key get a 401 Unauthorized.
+    ApiKey,
+}
+
+/// What a canary route hashes to assign a request to a controller.
+///
+/// In the configuration: `{ "header": "X-User-Id" }`, `{ "cookie": "uid" }` or
+/// `"random"`.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CanaryKey {
+    /// The value of a request header; requests without it go to the stable
+    /// controller.
+    Header(String),
+    /// The value of a cookie; requests without it go to the stable controller.
+    Cookie(String),
+    /// Nothing: each request is assigned at random.
+    #[default]
+    Random,
+}
+
+impl Config {
+    /// Parses `default_response_headers` into a `HeaderMap`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RouterError::Header` for an invalid header name or value.
+    pub fn default_headers(&self) -> Result<HeaderMap, RouterError> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.default_response_headers {
+            let invalid = |reason: String| RouterError::Header {
+                name: name.clone(),
+                reason,
+            };
+            let header_name =
+                HeaderName::from_bytes(name.as_bytes()).map_err(|e| invalid(e.to_string()))?;
+            let header_value = HeaderValue::from_str(value).map_err(|e| invalid(e.to_string()))?;
+            headers.append(header_name, header_value);
+        }
+        Ok(headers)
+    }
+
+    /// Replaces the `request_schema` and `response_schema` file paths of the
+    /// endpoints with the schemas they contain, reading them relative to `base_dir`, the directory of the
+    /// configuration file. `Router::new` does this when loading.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError::Endpoint` naming the endpoint whose schema file
+    /// can't be read or isn't JSON.
+    pub fn resolve_schemas(&mut self, base_dir: &Path) -> Result<(), RouterError> {
+        for endpoint in &mut self.endpoints {
+            let schemas = [
+                ("request_schema", &mut endpoint.request_schema),
+                ("response_schema", &mut endpoint.response_schema),
+            ];
+            for (field, schema) in schemas {
+                let Some(serde_json::Value::String(file)) = schema else {
+                    continue;
+                };
+                let invalid = |reason: String| RouterError::Endpoint {
+                    method: endpoint.method.clone(),
+                    path: endpoint.path.clone(),
+                    reason: format!("can't load {field} '{file}': {reason}"),
+                };
+                let json =
+                    std::fs::read(base_dir.join(&*file)).map_err(|e| invalid(e.to_string()))?;
+                *schema = Some(serde_json::from_slice(&json).map_err(|e| invalid(e.to_string()))?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes the `static_files` directories of the endpoints relative to
+    /// `base_dir`, the directory of the configuration file.
+    pub(crate) fn resolve_static_dirs(&mut self, base_dir: &Path) {
+        for files in self
+            .endpoints
+            .iter_mut()
+            .filter_map(|endpoint| endpoint.static_files.as_mut())
+        {
+            files.dir = base_dir.join(&files.dir).to_string_lossy().into_owned();
+        }
+    }
+
+    /// Checks that the endpoints sharing a controller agree on the fields
+    /// securing it: `auth`, `auth_scopes`, `guards`, `required_headers`,
+    /// `signature`, `csrf` and `max_request_size`. One route of a controller requiring
+    /// authentication while another doesn't is usually a mistake, letting
+    /// requests reach the controller unauthenticated. `Router::new` does this
+    /// when loading.
+    ///
+    /// Endpoints are numbered by their position in `endpoints`, with an
+    /// endpoint with a localized `path` counted once per locale, and one with
+    /// the method `ANY` once per method. Controller
+    /// aliases are expanded, and the controllers of canary routes included.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError::InconsistentController` for the first
+    /// controller whose endpoints disagree if `strict`, and otherwise one
+    /// `LoadWarning::InconsistentController` for each.
+    pub fn validate(&self, strict: bool) -> Result<Vec<LoadWarning>, RouterError> {
+        let mut controllers = BTreeMap::<String, Vec<usize>>::new();
+        for (index, endpoint) in self.endpoints.iter().enumerate() {
+            let names = match &endpoint.canary {
+                Some(canary) => vec![canary.stable.as_str(), canary.candidate.as_str()],
+                None if endpoint.controller.is_empty() => Vec::new(),
+                None => vec![endpoint.controller.as_str()],
+            };
+            for name in names {
+                let name =
+                    expand_controller(&self.controllers, name).unwrap_or_else(|| name.to_string());
+                controllers.entry(name).or_default().push(index);
+            }
+        }
+
+        let mut warnings = Vec::new();
+        for (controller, indices) in controllers {
+            let first = &self.endpoints[indices[0]];
+            let fields: Vec<&'static str> = SECURITY_FIELDS
+                .iter()
+                .filter(|(_, same)| {
+                    indices[1..]
+                        .iter()
+                        .any(|&index| !same(first, &self.endpoints[index]))
+                })
+                .map(|(field, _)| *field)
+                .collect();
+            if fields.is_empty() {
+                continue;
+            }
+            let endpoints = indices
+                .into_iter()
+                .map(|index| {
+                    let endpoint = &self.endpoints[index];
+                    (index, endpoint.method.clone(), endpoint.path.clone())
+                })
+                .collect();
+            if strict {
+                return Err(RouterError::InconsistentController {
+                    controller,
+                    fields,
+                    endpoints,
+                });
+            }
+            warnings.push(LoadWarning::InconsistentController {
+                controller,
+                fields,
+                endpoints,
+            });
+        }
+        Ok(warnings)
+    }
+
+    /// Compares the endpoints of two configurations, matching them by method and
+    /// path: endpoints only in `other` are added, those only in `self` removed,
+    /// and those in both but with any other field different are modified.
+    /// Reordering endpoints isn't a change.
+    ///
+    /// Rewrite rules and default response headers aren't compared.
+    pub fn diff(&self, other: &Config) -> ConfigDiff {
+        let find = |endpoints: &[Endpoint], endpoint: &Endpoint| {
+            endpoints
+                .iter()
+                .find(|e| e.method == endpoint.method && e.path == endpoint.path)
+                .cloned()
+        };
+
+        let mut diff = ConfigDiff::default();
+        for old in &self.endpoints {
+            match find(&other.endpoints, old) {
+                Some(new) if new != *old => diff.modified.push((old.clone(), new)),
+                Some(_) => {}
+                None => diff.removed.push(old.clone()),
+            }
+        }
+        for new in &other.endpoints {
+            if find(&self.endpoints, new).is_none() {
+                diff.added.push(new.clone());
+            }
+        }
+        diff
+    }
+}
+
+/// The differences between the endpoints of two configurations, returned by
+/// [`Config::diff`].
+///
+/// It serializes to JSON for tooling, and its `Display` output is a summary with
+/// one line per endpoint, e.g.:
+///
+/// ```text
+/// + POST /orders (orders::create)
+/// - DELETE /users/:id (users::delete)
+/// ~ GET /users/:id: controller
+/// ```
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigDiff {
+    pub added: Vec<Endpoint>,
+    pub removed: Vec<Endpoint>,
+    /// The endpoints as they were and as they are.
+    pub modified: Vec<(Endpoint, Endpoint)>,
+}
+
+impl ConfigDiff {
+    /// Returns `true` if the configurations have the same endpoints.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+impl fmt::Display for ConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No changes");
+        }
+        for endpoint in &self.added {
+            writeln!(
+                f,
+                "+ {} {} ({})",
+                endpoint.method,
+                endpoint.path,
+                handled_by(endpoint)
+            )?;
+        }
+        for endpoint in &self.removed {
+            writeln!(
+                f,
+                "- {} {} ({})",
+                endpoint.method,
+                endpoint.path,
+                handled_by(endpoint)
+            )?;
+        }
+        for (old, new) in &self.modified {
+            writeln!(
+                f,
+                "~ {} {}: {}",
+                old.method,
+                old.path,
+                changed_fields(old, new).join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Describes the controller of an endpoint, or both controllers of a canary.
+fn handled_by(endpoint: &Endpoint) -> String {
+    match &endpoint.canary {
+        Some(canary) => format!(
+            "{}, {} ({}%)",
+            canary.stable, canary.candidate, canary.percent
+        ),
+        None => endpoint.controller.clone(),
+    }
+}
+
+/// Returns the names of the fields that differ between two endpoints with the
+/// same method and path.
+fn changed_fields(old: &Endpoint, new: &Endpoint) -> Vec<&'static str> {
+    // Destructured so that a new field can't be forgotten here.
+    let Endpoint {
+        method: _,
+        path: _,
+        locale,
+        controller,
+        controller_args,
+        body_template,
+        content_type,
+        static_files,
+        description,
+        canary,
+        priority,
+        cache_ttl_seconds,
+        timeout_ms,
+        max_concurrency,
+        max_queue,
+        max_request_size,
+        max_response_size,
+        circuit_breaker,
+        maintenance_exempt,
+        mirror_to,
+        guards,
+        csrf,
+        idempotent,
+        coalesce,
+        auth_scopes,
+        auth,
+        signature,
+        request_schema,
+        response_schema,
+        tags,
+        required_headers,
+        metadata,
+    } = old;
+    let fields = [
+        ("locale", *locale != new.locale),
+        ("controller", *controller != new.controller),
+        ("controller_args", *controller_args != new.controller_args),
+        ("body_template", *body_template != new.body_template),
+        ("content_type", *content_type != new.content_type),
+        ("static_files", *static_files != new.static_files),
+        ("description", *description != new.description),
+        ("canary", *canary != new.canary),
+        ("priority", *priority != new.priority),
+        (
+            "cache_ttl_seconds",
+            *cache_ttl_seconds != new.cache_ttl_seconds,
+        ),
+        ("timeout_ms", *timeout_ms != new.timeout_ms),
+        ("max_concurrency", *max_concurrency != new.max_concurrency),
+        ("max_queue", *max_queue != new.max_queue),
+        (
+            "max_request_size",
+            *max_request_size != new.max_request_size,
+        ),
+        (
+            "max_response_size",
+            *max_response_size != new.max_response_size,
+        ),
+        ("circuit_breaker", *circuit_breaker != new.circuit_breaker),
+        (
+            "maintenance_exempt",
+            *maintenance_exempt != new.maintenance_exempt,
+        ),
+        ("mirror_to", *mirror_to != new.mirror_to),
+        ("guards", *guards != new.guards),
+        ("csrf", *csrf != new.csrf),
+        ("idempotent", *idempotent != new.idempotent),
+        ("coalesce", *coalesce != new.coalesce),
+        ("auth_scopes", *auth_scopes != new.auth_scopes),
+        ("auth", *auth != new.auth),
+        ("signature", *signature != new.signature),
+        ("request_schema", *request_schema != new.request_schema),
+        ("response_schema", *response_schema != new.response_schema),
+        ("tags", *tags != new.tags),
+        (
+            "required_headers",
+            *required_headers != new.required_headers,
+        ),
+        ("metadata", *metadata != new.metadata),
+    ];
+    fields
+        .into_iter()
+        .filter_map(|(name, changed)| changed.then_some(name))
+        .collect()
+}
+
+/// Represents a single path rewrite rule in the configuration.
+///
+/// Both `match` and `to` use the same syntax as endpoint paths, e.g.
+/// `{ "match": "/legacy/*rest", "to": "/v2/*rest" }`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Rewrite {
+    #[serde(rename = "match")]
+    pub pattern: String,
+    pub to: String,
+}
+
+/// Resolves a request for `method` and `path` against the routes of `config`,
+/// without building a `Router`; see [`CompiledRoutes::resolve`].
+///
+/// This compiles the routes on every call: to resolve many paths, build a
+/// `CompiledRoutes` once instead.
+///
+/// # Errors
+///
+/// Returns a `RouterError` if the configuration is invalid.
+pub fn resolve(config: &Config, method: &Method, path: &str) -> Result<MatchOutcome, RouterError> {
+    Ok(CompiledRoutes::new(config)?.resolve(method, path))
+}
+
+/// Tells whether two endpoints agree on a field.
+type SameField = fn(&Endpoint, &Endpoint) -> bool;
+
+/// The fields securing a controller that `Config::validate` compares, with
+/// whether two endpoints agree on each. Scopes and required headers are sets,
+/// the latter case-insensitive; guards run in order, so their order counts.
+const SECURITY_FIELDS: [(&str, SameField); 7] = [
+    ("auth", |a, b| a.auth == b.auth),
+    ("auth_scopes", |a, b| {
+        let scopes = |e: &Endpoint| {
+            e.auth_scopes
+                .as_ref()
+                .map(|scopes| scopes.iter().cloned().collect::<BTreeSet<_>>())
+        };
+        scopes(a) == scopes(b)
+    }),
+    ("guards", |a, b| a.guards == b.guards),
+    ("required_headers", |a, b| {
+        let headers = |e: &Endpoint| {
+            e.required_headers
+                .iter()
+                .map(|header| header.to_ascii_lowercase())
+                .collect::<BTreeSet<_>>()
+        };
+        headers(a) == headers(b)
+    }),
+    ("signature", |a, b| a.signature == b.signature),
+    ("csrf", |a, b| a.csrf == b.csrf),
+    ("max_request_size", |a, b| {
+        a.max_request_size == b.max_request_size
+    }),
+];
+
+/// Expands the alias `name` starts with, before its first `::`, per the
+/// `controllers` section `aliases`. Names that already start with the prefix
+/// an alias stands for, or without a `::`, are kept as is.
+///
+/// Returns `None` for a name starting with neither, if there are aliases.
+pub(crate) fn expand_controller(aliases: &BTreeMap<String, String>, name: &str) -> Option<String> {
+    let Some((prefix, rest)) = name.split_once("::") else {
+        return Some(name.to_string());
+    };
+    if let Some(expanded) = aliases.get(prefix) {
+        return Some(format!("{expanded}::{rest}"));
+    }
+    let expanded = aliases.is_empty()
+        || aliases.values().any(|expanded| {
+            name.strip_prefix(expanded.as_str())
+                .is_some_and(|rest| rest.starts_with("::"))
+        });
+    expanded.then(|| name.to_string())
+}
+
+/// Deserializes the endpoints of the configuration, expanding each one with
+/// the method `ANY` into an endpoint per method, and each one whose `path` is
+/// a map of locale to path into an endpoint per locale, in the order of the
+/// locales.
+fn deserialize_endpoints<'de, D>(deserializer: D) -> Result<Vec<Endpoint>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+    use serde_json::Value;
+
+    let values: Vec<Value> = Deserialize::deserialize(deserializer)?;
+    let mut endpoints = Vec::with_capacity(values.len());
+    for (index, mut value) in values.into_iter().enumerate() {
+        let invalid = |e: &dyn fmt::Display| D::Error::custom(format!("endpoint {index}: {e}"));
+        let args = match value.get_mut("controller") {
+            Some(Value::Object(controller)) => {
+                let Some(Value::String(name)) = controller.remove("name") else {
+                    return Err(invalid(&"a controller object needs a string `name`"));
+                };
+                let args = controller.remove("args").unwrap_or(Value::Null);
+                value["controller"] = Value::String(name);
+                Some(args)
+            }
+            _ => None,
+        };
+        let first = endpoints.len();
+        let any = value
+            .get("method")
+            .and_then(Value::as_str)
+            .is_some_and(|method| method.eq_ignore_ascii_case("ANY"));
+        let values = if any {
+            ANY_METHODS
+                .iter()
+                .map(|method| {
+                    let mut value = value.clone();
+                    value["method"] = Value::String(method.to_string());
+                    value
+                })
+                .collect()
+        } else {
+            vec![value]
+        };
+        for mut value in values {
+            let paths = match value.get_mut("path") {
+                Some(Value::Object(paths)) => std::mem::take(paths),
+                _ => {
+                    endpoints.push(Endpoint::deserialize(value).map_err(|e| invalid(&e))?);
+                    continue;
+                }
+            };
+            if paths.is_empty() {
+                return Err(invalid(&"a localized path needs at least one locale"));
+            }
+            if value.get("locale").is_some() {
+                return Err(invalid(&"a localized path sets the locales itself"));
+            }
+            for (locale, path) in paths {
+                let mut value = value.clone();
+                value["path"] = path;
+                value["locale"] = Value::String(locale);
+                endpoints.push(Endpoint::deserialize(value).map_err(|e| invalid(&e))?);
+            }
+        }
+        for endpoint in &mut endpoints[first..] {
+            endpoint.controller_args.clone_from(&args);
+        }
+    }
+    Ok(endpoints)
 }
 
 /// Custom deserializer for `http::Method`.
@@ -43,3 +1164,16 @@ where
     };
     Ok(method)
 }
+
+/// Custom serializer for `http::Method`, the counterpart of `deserialize_method`.
+fn serialize_method<S>(method: &Method, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(method.as_str())
+}
+
+/// The default of flags that are on unless the configuration turns them off.
+fn enabled() -> bool {
+    true
+}
@@ -0,0 +1,477 @@
+//! Defines the JSON Schema of the configuration format, and the check for
+//! unknown fields run before a configuration file is parsed.
+
+use crate::config::{
+    ApiKeys, Canary, CircuitBreaker, Config, Endpoint, ErrorPage, Rewrite, Signature, StaticFiles,
+};
+use crate::error::{LoadWarning, RouterError};
+use crate::limits::Limits;
+use serde_json::{Value, json};
+
+impl Config {
+    /// Returns the JSON Schema (2020-12) of the configuration format, e.g. to
+    /// write it to a file that editors validate `routes.json` against through
+    /// its `"$schema"` field.
+    ///
+    /// The schema describes the fields and their types; rules spanning fields,
+    /// such as a canary route having no `controller`, are checked when loading.
+    pub fn schema() -> Value {
+        // Adding a field to one of these structs fails to compile here, as a
+        // reminder to add it to the schema too.
+        let _ = |config: Config,
+                 endpoint: Endpoint,
+                 canary: Canary,
+                 keys: ApiKeys,
+                 rewrite: Rewrite,
+                 page: ErrorPage,
+                 signature: Signature,
+                 breaker: CircuitBreaker,
+                 limits: Limits,
+                 files: StaticFiles| {
+            let Config {
+                endpoints: _,
+                rewrites: _,
+                default_response_headers: _,
+                api_keys: _,
+                controllers: _,
+                required_headers: _,
+                error_pages: _,
+                limits: _,
+            } = config;
+            let Endpoint {
+                method: _,
+                path: _,
+                locale: _,
+                controller: _,
+                controller_args: _,
+                body_template: _,
+                content_type: _,
+                static_files: _,
+                description: _,
+                canary: _,
+                priority: _,
+                cache_ttl_seconds: _,
+                timeout_ms: _,
+                max_concurrency: _,
+                max_queue: _,
+                max_request_size: _,
+                max_response_size: _,
+                circuit_breaker: _,
+                maintenance_exempt: _,
+                mirror_to: _,
+                guards: _,
+                csrf: _,
+                idempotent: _,
+                coalesce: _,
+                auth_scopes: _,
+                auth: _,
+                signature: _,
+                request_schema: _,
+                response_schema: _,
+                tags: _,
+                required_headers: _,
+                metadata: _,
+            } = endpoint;
+            let Canary {
+                stable: _,
+                candidate: _,
+                percent: _,
+                key: _,
+            } = canary;
+            let ApiKeys {
+                header: _,
+                query_param: _,
+                keys: _,
+            } = keys;
+            let Rewrite { pattern: _, to: _ } = rewrite;
+            let Signature {
+                header: _,
+                algorithm: _,
+                secret_env: _,
+                tolerance_seconds: _,
+            } = signature;
+            let CircuitBreaker {
+                failure_threshold: _,
+                min_requests: _,
+                open_for_ms: _,
+                window_ms: _,
+                half_open_requests: _,
+            } = breaker;
+            let ErrorPage {
+                content_type: _,
+                body: _,
+                file: _,
+            } = page;
+            let Limits {
+                max_headers: _,
+                max_header_bytes: _,
+                max_uri_bytes: _,
+                max_query_params: _,
+            } = limits;
+            let StaticFiles {
+                dir: _,
+                etag: _,
+                last_modified: _,
+                precompressed: _,
+                directory_listing: _,
+            } = files;
+        };
+
+        let string = json!({ "type": "string" });
+        let strings = json!({ "type": "array", "items": { "type": "string" } });
+        let count = json!({ "type": "integer", "minimum": 0 });
+        let schema = json!({ "type": ["object", "string"] });
+        // Defined apart, since the whole schema in one `json!` reaches the
+        // macro's recursion limit.
+        let endpoint = json!({
+            "type": "object",
+            "required": ["method", "path", "description"],
+            "additionalProperties": false,
+            "patternProperties": { "^x-": {} },
+            "properties": {
+                "method": string,
+                "path": {
+                    "anyOf": [
+                        string,
+                        {
+                            "type": "object",
+                            "minProperties": 1,
+                            "additionalProperties": string
+                        }
+                    ]
+                },
+                "locale": string,
+                "controller": {
+                    "anyOf": [
+                        string,
+                        {
+                            "type": "object",
+                            "required": ["name"],
+                            "additionalProperties": false,
+                            "properties": { "name": string, "args": {} }
+                        }
+                    ]
+                },
+                "body_template": string,
+                "content_type": string,
+                "description": string,
+                "static_files": { "$ref": "#/$defs/static_files" },
+                "canary": { "$ref": "#/$defs/canary" },
+                "priority": { "type": "integer" },
+                "cache_ttl_seconds": count,
+                "timeout_ms": count,
+                "max_concurrency": { "type": "integer", "minimum": 1 },
+                "max_queue": count,
+                "max_request_size": count,
+                "max_response_size": count,
+                "circuit_breaker": { "$ref": "#/$defs/circuit_breaker" },
+                "maintenance_exempt": { "type": "boolean" },
+                "mirror_to": string,
+                "guards": strings,
+                "csrf": { "type": "boolean" },
+                "idempotent": { "type": "boolean" },
+                "coalesce": { "type": "boolean" },
+                "auth_scopes": strings,
+                "auth": { "enum": ["api_key"] },
+                "signature": { "$ref": "#/$defs/signature" },
+                "request_schema": schema,
+                "response_schema": schema,
+                "tags": strings,
+                "required_headers": strings
+            }
+        });
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "generic-http-router configuration",
+            "type": "object",
+            "required": ["endpoints"],
+            "additionalProperties": false,
+            "properties": {
+                "$schema": string,
+                "endpoints": { "type": "array", "items": { "$ref": "#/$defs/endpoint" } },
+                "rewrites": { "type": "array", "items": { "$ref": "#/$defs/rewrite" } },
+                "default_response_headers": {
+                    "type": "object",
+                    "additionalProperties": string
+                },
+                "api_keys": { "$ref": "#/$defs/api_keys" },
+                "controllers": { "type": "object", "additionalProperties": string },
+                "required_headers": strings,
+                "error_pages": {
+                    "type": "object",
+                    "additionalProperties": {
+                        "anyOf": [
+                            { "$ref": "#/$defs/error_page" },
+                            { "type": "array", "items": { "$ref": "#/$defs/error_page" } }
+                        ]
+                    }
+                },
+                "limits": { "$ref": "#/$defs/limits" }
+            },
+            "$defs": {
+                "endpoint": endpoint,
+                "static_files": {
+                    "type": "object",
+                    "required": ["dir"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "dir": string,
+                        "etag": { "type": "boolean" },
+                        "last_modified": { "type": "boolean" },
+                        "precompressed": { "type": "boolean" },
+                        "directory_listing": { "type": "boolean" }
+                    }
+                },
+                "canary": {
+                    "type": "object",
+                    "required": ["stable", "candidate", "percent"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "stable": string,
+                        "candidate": string,
+                        "percent": { "type": "integer", "minimum": 0, "maximum": 100 },
+                        "key": {
+                            "anyOf": [
+                                { "const": "random" },
+                                {
+                                    "type": "object",
+                                    "minProperties": 1,
+                                    "maxProperties": 1,
+                                    "additionalProperties": false,
+                                    "properties": { "header": string, "cookie": string }
+                                }
+                            ]
+                        }
+                    }
+                },
+                "signature": {
+                    "type": "object",
+                    "required": ["header", "algorithm", "secret_env"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "header": string,
+                        "algorithm": { "enum": ["hmac-sha256", "stripe-v1"] },
+                        "secret_env": string,
+                        "tolerance_seconds": count
+                    }
+                },
+                "circuit_breaker": {
+                    "type": "object",
+                    "required": ["failure_threshold", "min_requests", "open_for_ms"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "failure_threshold": {
+                            "type": "number",
+                            "exclusiveMinimum": 0,
+                            "maximum": 1
+                        },
+                        "min_requests": count,
+                        "open_for_ms": count,
+                        "window_ms": { "type": "integer", "minimum": 1 },
+                        "half_open_requests": { "type": "integer", "minimum": 1 }
+                    }
+                },
+                "rewrite": {
+                    "type": "object",
+                    "required": ["match", "to"],
+                    "additionalProperties": false,
+                    "properties": { "match": string, "to": string }
+                },
+                "error_page": {
+                    "type": "object",
+                    "required": ["content_type"],
+                    "additionalProperties": false,
+                    "properties": { "content_type": string, "body": string, "file": string }
+                },
+                "limits": {
+                    "type": "object",
+                    "additionalProperties": false,
+                    "properties": {
+                        "max_headers": count,
+                        "max_header_bytes": count,
+                        "max_uri_bytes": count,
+                        "max_query_params": count
+                    }
+                },
+                "api_keys": {
+                    "type": "object",
+                    "required": ["keys"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "header": string,
+                        "query_param": string,
+                        "keys": { "type": "object", "additionalProperties": string }
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// An unknown field found by `check`.
+struct UnknownField {
+    /// The JSON pointer of the object holding the field.
+    pointer: String,
+    field: String,
+    suggestion: Option<String>,
+}
+
+/// Checks a configuration file's JSON for fields its schema doesn't have,
+/// which serde would ignore, e.g. a misspelled `"controler"`. Unknown fields
+/// of endpoints are returned as warnings, unless `strict`; those of the other
+/// sections are always errors.
+///
+/// # Errors
+///
+/// Returns a `RouterError::UnknownField` for the first unknown field that
+/// isn't a warning.
+pub(crate) fn check_fields(config: &Value, strict: bool) -> Result<Vec<LoadWarning>, RouterError> {
+    let schema = Config::schema();
+    let mut unknown = Vec::new();
+    check(&schema, &schema, config, &mut String::new(), &mut unknown);
+
+    let mut warnings = Vec::new();
+    for UnknownField {
+        pointer,
+        field,
+        suggestion,
+    } in unknown
+    {
+        let index = pointer
+            .strip_prefix("/endpoints/")
+            .and_then(|index| index.parse().ok());
+        match index {
+            Some(index) if !strict => {
+                let path = config
+                    .pointer(&format!("{pointer}/path"))
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                warnings.push(LoadWarning::UnknownField {
+                    index,
+                    path: path.to_string(),
+                    field,
+                    suggestion,
+                });
+            }
+            _ => {
+                return Err(RouterError::UnknownField {
+                    pointer,
+                    field,
+                    suggestion,
+                });
+            }
+        }
+    }
+    Ok(warnings)
+}
+
+/// Checks `value`, at `pointer`, against the object and array keywords of
+/// `schema`, adding the fields it doesn't allow to `unknown`. The other
+/// keywords are left to the parser, and only the `^prefix` patterns of
+/// `patternProperties` are understood.
+fn check(
+    root: &Value,
+    schema: &Value,
+    value: &Value,
+    pointer: &mut String,
+    unknown: &mut Vec<UnknownField>,
+) {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        if let Some(target) = reference.strip_prefix('#').and_then(|p| root.pointer(p)) {
+            check(root, target, value, pointer, unknown);
+        }
+        return;
+    }
+    if let (Some(branches), Value::Object(_)) =
+        (schema.get("anyOf").and_then(Value::as_array), value)
+    {
+        for branch in branches
+            .iter()
+            .filter(|b| b.get("type") == Some(&json!("object")))
+        {
+            check(root, branch, value, pointer, unknown);
+        }
+        return;
+    }
+
+    let len = pointer.len();
+    match value {
+        Value::Object(fields) => {
+            let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+                return;
+            };
+            let closed = schema.get("additionalProperties") == Some(&Value::Bool(false));
+            let prefixes: Vec<&str> = schema
+                .get("patternProperties")
+                .and_then(Value::as_object)
+                .map(|patterns| {
+                    patterns
+                        .keys()
+                        .filter_map(|pattern| pattern.strip_prefix('^'))
+                        .collect()
+                })
+                .unwrap_or_default();
+            for (name, field) in fields {
+                match properties.get(name) {
+                    Some(property) => {
+                        pointer.push('/');
+                        pointer.push_str(&name.replace('~', "~0").replace('/', "~1"));
+                        check(root, property, field, pointer, unknown);
+                        pointer.truncate(len);
+                    }
+                    None if closed && !prefixes.iter().any(|p| name.starts_with(p)) => {
+                        unknown.push(UnknownField {
+                            pointer: pointer.clone(),
+                            field: name.clone(),
+                            suggestion: suggest(name, properties.keys()),
+                        });
+                    }
+                    None => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            let Some(schema) = schema.get("items") else {
+                return;
+            };
+            for (index, item) in items.iter().enumerate() {
+                pointer.push('/');
+                pointer.push_str(&index.to_string());
+                check(root, schema, item, pointer, unknown);
+                pointer.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Returns the known field closest to a misspelled one, if any is close
+/// enough: within two edits, or a third of its length for longer names, or
+/// extending it with words, e.g. `cache_ttl_seconds` for `cache_ttl`.
+fn suggest<'a>(name: &str, known: impl Iterator<Item = &'a String>) -> Option<String> {
+    let name = name.to_lowercase();
+    let max = (name.chars().count() / 3).max(2);
+    let prefix = format!("{name}_");
+    known
+        .filter(|known| !known.starts_with('$'))
+        .map(|known| (edit_distance(&name, known), known))
+        .filter(|(distance, known)| *distance <= max || known.starts_with(&prefix))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known)| known.clone())
+}
+
+/// Returns the Levenshtein distance between two strings, in characters.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != *b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
@@ -0,0 +1,95 @@
+//! Defines `RequestContext`, what the embedding server knows about a request
+//! beyond the request itself, such as the peer's address.
+
+use crate::request::HttpRequest;
+use http::Extensions;
+use std::net::SocketAddr;
+use std::time::Instant;
+
+/// The connection and server details of a request, passed to
+/// `Router::route_with_context` and read by handlers with
+/// [`HttpRequest::context`].
+///
+/// The router gives requests routed without one a default context, with no
+/// addresses and received when they were routed.
+#[derive(Debug)]
+pub struct RequestContext {
+    /// The address of the client, or of the last proxy.
+    pub remote_addr: Option<SocketAddr>,
+    /// The local address the request was received on.
+    pub local_addr: Option<SocketAddr>,
+    /// The TLS session details, for requests received over TLS.
+    pub tls_info: Option<TlsInfo>,
+    /// When the server received the request, e.g. when the connection was
+    /// accepted.
+    pub received_at: Instant,
+    /// Anything else the server wants handlers to know, e.g. the name of the
+    /// listener.
+    pub extensions: Extensions,
+}
+
+impl RequestContext {
+    /// Creates a context with no addresses or TLS details, received now.
+    pub fn new() -> Self {
+        Self {
+            remote_addr: None,
+            local_addr: None,
+            tls_info: None,
+            received_at: Instant::now(),
+            extensions: Extensions::new(),
+        }
+    }
+
+    /// Sets the remote address.
+    pub fn with_remote_addr(mut self, addr: SocketAddr) -> Self {
+        self.remote_addr = Some(addr);
+        self
+    }
+
+    /// Sets the local address.
+    pub fn with_local_addr(mut self, addr: SocketAddr) -> Self {
+        self.local_addr = Some(addr);
+        self
+    }
+
+    /// Sets the TLS session details.
+    pub fn with_tls_info(mut self, tls_info: TlsInfo) -> Self {
+        self.tls_info = Some(tls_info);
+        self
+    }
+
+    /// Sets when the request was received.
+    pub fn with_received_at(mut self, received_at: Instant) -> Self {
+        self.received_at = received_at;
+        self
+    }
+}
+
+impl Default for RequestContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The details of the TLS session a request was received over.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsInfo {
+    /// The server name the client asked for with SNI.
+    pub server_name: Option<String>,
+    /// The negotiated ALPN protocol, e.g. `h2`.
+    pub alpn_protocol: Option<String>,
+    /// The client's identity from its certificate, e.g. the subject's common
+    /// name, for mutual TLS.
+    pub peer_identity: Option<String>,
+    /// The client's certificate chain, DER-encoded, leaf first.
+    pub peer_certificates: Vec<Vec<u8>>,
+}
+
+impl HttpRequest {
+    /// Returns the context the request was routed with, or `None` for requests
+    /// that weren't routed, e.g. built with [`HttpRequest::builder`] without
+    /// one.
+    pub fn context(&self) -> Option<&RequestContext> {
+        self.extensions.get()
+    }
+}
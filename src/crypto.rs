@@ -0,0 +1,318 @@
+//! Implements the cryptography of API keys, sessions, CSRF protection, JWT
+//! validation, request signatures and idempotency fingerprints: SHA-256 (FIPS 180-4), HMAC-SHA256
+//! (RFC 2104), constant-time comparison and random tokens.
+
+#[cfg(all(unix, any(feature = "csrf", feature = "session")))]
+use std::fs::File;
+#[cfg(any(feature = "csrf", feature = "session"))]
+use std::io;
+#[cfg(all(unix, any(feature = "csrf", feature = "session")))]
+use std::io::Read;
+
+/// The block size of SHA-256, in bytes.
+const BLOCK_SIZE: usize = 64;
+
+/// Returns the hex-encoded HMAC-SHA256 of `message` with `key`.
+#[cfg(feature = "session")]
+pub(crate) fn hmac_sign(key: &[u8], message: &[u8]) -> String {
+    hex(&hmac_sha256(key, message))
+}
+
+/// Returns `true` if `signature` is the hex-encoded HMAC-SHA256 of
+/// `message` with `key`, comparing in constant time.
+#[cfg(feature = "session")]
+pub(crate) fn hmac_verify(key: &[u8], message: &[u8], signature: &str) -> bool {
+    constant_time_eq(hmac_sign(key, message).as_bytes(), signature.as_bytes())
+}
+
+/// Compares two byte strings in time depending only on their lengths, so that
+/// comparing a secret doesn't reveal how much of it was guessed right.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Returns `len` random bytes from the operating system's CSPRNG, hex-encoded.
+#[cfg(any(feature = "csrf", feature = "session"))]
+pub(crate) fn random_token(len: usize) -> io::Result<String> {
+    let mut bytes = vec![0u8; len];
+    random_bytes(&mut bytes)?;
+    Ok(hex(&bytes))
+}
+
+/// Fills `bytes` from the operating system's CSPRNG: `/dev/urandom` on Unix,
+/// and `BCryptGenRandom` on Windows. Other targets don't compile with the
+/// features using it.
+#[cfg(all(unix, any(feature = "csrf", feature = "session")))]
+pub(crate) fn random_bytes(bytes: &mut [u8]) -> io::Result<()> {
+    File::open("/dev/urandom")?.read_exact(bytes)
+}
+
+#[cfg(all(windows, any(feature = "csrf", feature = "session")))]
+pub(crate) fn random_bytes(bytes: &mut [u8]) -> io::Result<()> {
+    use std::ffi::c_void;
+
+    /// Uses the system's preferred generator, without an algorithm handle.
+    const BCRYPT_USE_SYSTEM_PREFERRED_RNG: u32 = 2;
+
+    #[link(name = "bcrypt")]
+    unsafe extern "system" {
+        fn BCryptGenRandom(algorithm: *mut c_void, buffer: *mut u8, len: u32, flags: u32) -> i32;
+    }
+
+    for chunk in bytes.chunks_mut(u32::MAX as usize) {
+        // SAFETY: `chunk` is valid for writes of its length, which fits in a
+        // `u32`, and the flag makes the null algorithm handle valid.
+        let status = unsafe {
+            BCryptGenRandom(
+                std::ptr::null_mut(),
+                chunk.as_mut_ptr(),
+                chunk.len() as u32,
+                BCRYPT_USE_SYSTEM_PREFERRED_RNG,
+            )
+        };
+        if status != 0 {
+            return Err(io::Error::other(format!(
+                "BCryptGenRandom failed with status {status:#x}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(not(any(unix, windows)), any(feature = "csrf", feature = "session")))]
+compile_error!(
+    "the `csrf` and `session` features need the random generator of a Unix or Windows system"
+);
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Returns the HMAC-SHA256 of `message` with `key`.
+pub(crate) fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&sha256(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.map(|b| b ^ byte);
+
+    let mut inner = pad(0x36).to_vec();
+    inner.extend_from_slice(message);
+    let mut outer = pad(0x5c).to_vec();
+    outer.extend_from_slice(&sha256(&inner));
+    sha256(&outer)
+}
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Returns the SHA-256 digest of `message`.
+pub(crate) fn sha256(message: &[u8]) -> [u8; 32] {
+    let mut h: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((message.len() as u64) * 8).to_be_bytes());
+
+    for chunk in padded.chunks_exact(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in chunk.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = hh
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 32];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(h) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sha256_hex(message: &[u8]) -> String {
+        hex(&sha256(message))
+    }
+
+    #[test]
+    fn sha256_fips_180_vectors() {
+        assert_eq!(
+            sha256_hex(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            sha256_hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        assert_eq!(
+            sha256_hex(&[b'a'; 1_000_000]),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+
+    #[test]
+    fn sha256_around_the_padding_boundaries() {
+        let expected = [
+            (
+                55,
+                "9f4390f8d30c2dd92ec9f095b65e2b9ae9b0a925a5258e241c9f1e910f734318",
+            ),
+            (
+                56,
+                "b35439a4ac6f0948b6d6f9e3c6af0f5f590ce20f1bde7090ef7970686ec6738a",
+            ),
+            (
+                63,
+                "7d3e74a05d7db15bce4ad9ec0658ea98e3f06eeecf16b4c6fff2da457ddc2f34",
+            ),
+            (
+                64,
+                "ffe054fe7ae0cb6dc65c3af9b61d5209f439851db43d0ba5997337df154668eb",
+            ),
+            (
+                65,
+                "635361c48bb9eab14198e76ea8ab7f1a41685d6ad62aa9146d301d4f17eb0ae0",
+            ),
+        ];
+        for (len, digest) in expected {
+            assert_eq!(sha256_hex(&vec![b'a'; len]), digest, "{len} bytes");
+        }
+    }
+
+    #[test]
+    fn hmac_sha256_rfc_4231_vectors() {
+        let cases: [(&[u8], &[u8], &str); 6] = [
+            (
+                &[0x0b; 20],
+                b"Hi There",
+                "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            ),
+            (
+                b"Jefe",
+                b"what do ya want for nothing?",
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            ),
+            (
+                &[0xaa; 20],
+                &[0xdd; 50],
+                "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+            ),
+            (
+                &[
+                    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22,
+                    23, 24, 25,
+                ],
+                &[0xcd; 50],
+                "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b",
+            ),
+            (
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First",
+                "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            ),
+            (
+                &[0xaa; 131],
+                b"This is a test using a larger than block-size key and a larger than \
+                  block-size data. The key needs to be hashed before being used by the HMAC \
+                  algorithm.",
+                "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2",
+            ),
+        ];
+        for (i, (key, message, mac)) in cases.into_iter().enumerate() {
+            assert_eq!(hex(&hmac_sha256(key, message)), mac, "test case {}", i + 1);
+        }
+    }
+
+    #[test]
+    fn constant_time_eq_compares_lengths_and_bytes() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret!"));
+    }
+
+    #[cfg(feature = "session")]
+    #[test]
+    fn hmac_verify_checks_the_hex_signature() {
+        let signature = hmac_sign(b"Jefe", b"what do ya want for nothing?");
+        assert!(hmac_verify(
+            b"Jefe",
+            b"what do ya want for nothing?",
+            &signature
+        ));
+        assert!(!hmac_verify(
+            b"Jefe",
+            b"what do ya want for something?",
+            &signature
+        ));
+        assert!(!hmac_verify(
+            b"Jefe",
+            b"what do ya want for nothing?",
+            &signature[1..]
+        ));
+    }
+
+    #[cfg(any(feature = "csrf", feature = "session"))]
+    #[test]
+    fn random_tokens_are_hex_and_distinct() {
+        let first = random_token(16).unwrap();
+        assert_eq!(first.len(), 32);
+        assert!(first.bytes().all(|b| b.is_ascii_hexdigit()));
+        assert_ne!(first, random_token(16).unwrap());
+    }
+}
@@ -0,0 +1,196 @@
+//! Defines CSRF protection with the double-submit cookie pattern: requests
+//! changing state must repeat the token of a cookie only the site can read.
+
+use crate::canary;
+use crate::crypto;
+use crate::request::HttpRequest;
+use http::header::{CONTENT_TYPE, HeaderName};
+use http::{HeaderValue, Method};
+use std::io;
+
+/// The length of tokens in bytes, before hex encoding.
+const TOKEN_BYTES: usize = 32;
+
+/// Protects routes against cross-site request forgery, enabled with
+/// `Router::use_csrf`.
+///
+/// Requests with a safe method (`GET`, `HEAD`, `OPTIONS` or `TRACE`) get a
+/// random token in a cookie if they don't have one yet, and handlers read it
+/// with [`HttpRequest::csrf_token`], e.g. to embed it in a form. Requests with
+/// any other method must send the cookie's token back in the `X-CSRF-Token`
+/// header or, for `application/x-www-form-urlencoded` bodies, a `_csrf` field;
+/// others get a 403 Forbidden. Endpoints with `"csrf": false`, such as
+/// webhooks, aren't checked.
+///
+/// The cookie is `Path=/; SameSite=Strict`, and not `HttpOnly` so that scripts
+/// can copy it into the header. Tokens come from the operating system's random
+/// generator; a safe request for which it fails gets a 500 Internal Server
+/// Error rather than a page without a token.
+#[derive(Debug, Clone)]
+pub struct CsrfMiddleware {
+    cookie_name: String,
+    header_name: HeaderName,
+    form_field: String,
+    secure: bool,
+}
+
+/// Why `CsrfMiddleware::protect` turned a request away.
+#[derive(Debug)]
+pub(crate) enum Rejection {
+    /// An unsafe request's token is missing or doesn't match its cookie's.
+    Mismatch,
+    /// No token could be generated for a safe request without one.
+    NoToken(io::Error),
+}
+
+/// The CSRF token of a request, in its extensions.
+struct CsrfToken {
+    token: String,
+    /// The `Set-Cookie` value for the response, if the token is new.
+    set_cookie: Option<HeaderValue>,
+}
+
+impl CsrfMiddleware {
+    /// Creates the middleware with a `Secure` cookie named `csrf_token`, the
+    /// `X-CSRF-Token` header and the `_csrf` form field.
+    pub fn new() -> Self {
+        Self {
+            cookie_name: "csrf_token".to_string(),
+            header_name: HeaderName::from_static("x-csrf-token"),
+            form_field: "_csrf".to_string(),
+            secure: true,
+        }
+    }
+
+    /// Sets the name of the cookie.
+    pub fn with_cookie_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.cookie_name = name.into();
+        self
+    }
+
+    /// Sets the header unsafe requests send the token in.
+    pub fn with_header_name(mut self, name: HeaderName) -> Self {
+        self.header_name = name;
+        self
+    }
+
+    /// Sets the form field unsafe requests can send the token in.
+    pub fn with_form_field<S: Into<String>>(mut self, name: S) -> Self {
+        self.form_field = name.into();
+        self
+    }
+
+    /// Sets whether the cookie is `Secure`, i.e. only sent over HTTPS.
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Checks the token of a request, making it available to the handler, or
+    /// issuing one if a safe request has none. Returns why the request must
+    /// be rejected otherwise.
+    pub(crate) fn protect(&self, req: &mut HttpRequest) -> Result<(), Rejection> {
+        let cookie = canary::cookie(&req.headers, &self.cookie_name)
+            .filter(|token| is_token(token))
+            .map(str::to_string);
+
+        if is_safe(&req.method) {
+            match cookie {
+                Some(token) => {
+                    req.extensions.insert(CsrfToken {
+                        token,
+                        set_cookie: None,
+                    });
+                }
+                None => self.issue(req).map_err(Rejection::NoToken)?,
+            }
+            return Ok(());
+        }
+
+        let (Some(expected), Some(submitted)) = (cookie, self.submitted_token(req)) else {
+            return Err(Rejection::Mismatch);
+        };
+        if !crypto::constant_time_eq(expected.as_bytes(), submitted.as_bytes()) {
+            return Err(Rejection::Mismatch);
+        }
+        req.extensions.insert(CsrfToken {
+            token: expected,
+            set_cookie: None,
+        });
+        Ok(())
+    }
+
+    /// Generates a token for a request, or fails if no random bytes are
+    /// available.
+    fn issue(&self, req: &mut HttpRequest) -> io::Result<()> {
+        let token = crypto::random_token(TOKEN_BYTES)?;
+        let mut cookie = format!("{}={token}; Path=/; SameSite=Strict", self.cookie_name);
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+        let set_cookie = HeaderValue::from_str(&cookie).ok();
+        req.extensions.insert(CsrfToken { token, set_cookie });
+        Ok(())
+    }
+
+    /// Returns the token an unsafe request sent back, from the header or else
+    /// the form field.
+    fn submitted_token(&self, req: &HttpRequest) -> Option<String> {
+        if let Some(value) = req.headers.get(&self.header_name) {
+            return value.to_str().ok().map(|token| token.trim().to_string());
+        }
+        let is_form = req
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .is_some_and(|mime| {
+                mime.trim()
+                    .eq_ignore_ascii_case("application/x-www-form-urlencoded")
+            });
+        if !is_form {
+            return None;
+        }
+        req.form_pairs()
+            .ok()?
+            .iter()
+            .find(|(name, _)| *name == self.form_field)
+            .map(|(_, value)| value.clone())
+    }
+}
+
+impl Default for CsrfMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpRequest {
+    /// Returns the CSRF token of the request, to embed in forms or pages, if
+    /// CSRF protection is enabled with `Router::use_csrf` and the route isn't
+    /// exempt.
+    pub fn csrf_token(&self) -> Option<&str> {
+        self.extensions
+            .get::<CsrfToken>()
+            .map(|token| token.token.as_str())
+    }
+}
+
+/// Returns the `Set-Cookie` value issuing the token of a request, if it's new.
+pub(crate) fn new_cookie(req: &HttpRequest) -> Option<HeaderValue> {
+    req.extensions.get::<CsrfToken>()?.set_cookie.clone()
+}
+
+/// Returns `true` for methods that mustn't change state, which need no token.
+fn is_safe(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    )
+}
+
+/// Returns `true` if a cookie value has the shape of a token, so that a
+/// truncated or forged one is replaced rather than echoed back.
+fn is_token(value: &str) -> bool {
+    value.len() == TOKEN_BYTES * 2 && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
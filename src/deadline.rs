@@ -0,0 +1,91 @@
+//! Defines the deadlines callers send in a request header, set with
+//! `Router::set_deadline_header`.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How the deadline header tells the deadline.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeadlineFormat {
+    /// The milliseconds left, e.g. `X-Request-Deadline-Ms: 250`.
+    #[default]
+    RemainingMillis,
+    /// The time as a Unix timestamp in milliseconds, e.g.
+    /// `X-Request-Deadline-Ms: 1700000000250`. It's compared with the
+    /// router's clock (`Router::set_clock`), the system's by default, so the
+    /// caller's clock must be in sync with it.
+    UnixMillis,
+}
+
+/// The deadline a request's caller sent, in its extensions when the router
+/// has a deadline header.
+///
+/// `HttpRequest::deadline` is the earlier of this one and the route's
+/// timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline(pub Instant);
+
+impl DeadlineFormat {
+    /// Parses the value of a deadline header, received at `now` (and at
+    /// `system_now` by the router's wall clock). Returns `None` if it isn't a number
+    /// of milliseconds, or one too large for a time.
+    pub(crate) fn parse(
+        self,
+        value: &str,
+        now: Instant,
+        system_now: SystemTime,
+    ) -> Option<Instant> {
+        let millis = value.trim().parse::<u64>().ok()?;
+        let remaining = match self {
+            DeadlineFormat::RemainingMillis => Duration::from_millis(millis),
+            // A deadline already passed leaves no time at all.
+            DeadlineFormat::UnixMillis => UNIX_EPOCH
+                .checked_add(Duration::from_millis(millis))?
+                .duration_since(system_now)
+                .unwrap_or_default(),
+        };
+        now.checked_add(remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remaining_millis() {
+        let now = Instant::now();
+        let format = DeadlineFormat::RemainingMillis;
+        assert_eq!(
+            format.parse(" 250 ", now, SystemTime::now()),
+            Some(now + Duration::from_millis(250))
+        );
+        assert_eq!(format.parse("-1", now, SystemTime::now()), None);
+        assert_eq!(format.parse("soon", now, SystemTime::now()), None);
+    }
+
+    #[test]
+    fn unix_millis() {
+        let now = Instant::now();
+        let system_now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let format = DeadlineFormat::UnixMillis;
+        assert_eq!(
+            format.parse("1700000000250", now, system_now),
+            Some(now + Duration::from_millis(250))
+        );
+        // A deadline already passed leaves no time at all.
+        assert_eq!(format.parse("1600000000000", now, system_now), Some(now));
+    }
+
+    #[test]
+    fn values_too_large_for_a_time_dont_panic() {
+        let now = Instant::now();
+        let max = u64::MAX.to_string();
+        // Whether the system's times go up to 2^64 ms after the epoch depends
+        // on the platform: they do on Linux, not on Windows.
+        for format in [DeadlineFormat::RemainingMillis, DeadlineFormat::UnixMillis] {
+            if let Some(deadline) = format.parse(&max, now, SystemTime::now()) {
+                assert!(deadline > now + Duration::from_secs(365 * 24 * 60 * 60));
+            }
+        }
+    }
+}
@@ -1,19 +1,910 @@
 //! Defines the custom error type for the router library.
 
+use http::Method;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 /// Represents all possible errors that can occur in this library.
+///
+/// Each error has a stable code, returned by [`code`](Self::code) and
+/// starting its message, e.g. `config.parse: routes.json: EOF while parsing
+/// an object at line 1 column 1`. New variants may be added, so matching on
+/// the code or on [`is_config_error`](Self::is_config_error) is more robust
+/// than matching on every variant.
+///
+/// # Examples
+///
+/// ```
+/// use generic_http_router::Router;
+///
+/// let error = Router::from_json_str("{").unwrap_err();
+/// assert_eq!(error.code(), "config.parse");
+/// assert!(error.is_config_error());
+/// assert!(error.to_string().starts_with("config.parse: "));
+/// ```
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum RouterError {
-    /// Error originating from I/O operations (e.g., reading the config file).
-    #[error("I/O error: {0}")]
-    Io(#[from] std::io::Error),
+    /// A file can't be read, e.g. the configuration; `path` is the file, if
+    /// known.
+    #[error("config.io: {}{source}", file(.path))]
+    Io {
+        path: Option<PathBuf>,
+        #[source]
+        source: std::io::Error,
+    },
 
-    /// Error originating from JSON deserialization.
-    #[error("JSON parsing error: {0}")]
-    Json(#[from] serde_json::Error),
+    /// A JSON document isn't valid, e.g. the configuration; `path` is its
+    /// file, if known.
+    #[error("config.parse: {}{source}", file(.path))]
+    Json {
+        path: Option<PathBuf>,
+        #[source]
+        source: serde_json::Error,
+    },
 
-    /// Error from the `matchit` router, e.g., inserting a conflicting route.
-    #[error("Routing error: {0}")]
-    MatchIt(#[from] matchit::InsertError),
+    /// Error from the `http` crate, e.g., an invalid URI or header passed to a builder.
+    #[error("request.invalid: {0}")]
+    Http(#[from] http::Error),
+
+    /// The pattern of the route for `method` and `path` is invalid, or
+    /// conflicts with the route for the same method with the pattern
+    /// `conflicts_with`, and priorities don't resolve the conflict.
+    #[error("{}: route {method} {path}: {source}", route_code(.source))]
+    Route {
+        method: Method,
+        path: String,
+        conflicts_with: Option<String>,
+        #[source]
+        source: matchit::InsertError,
+    },
+
+    /// A rewrite rule in the configuration is invalid.
+    #[error("config.rewrite: rule '{pattern}': {reason}")]
+    Rewrite { pattern: String, reason: String },
+
+    /// An endpoint in the configuration is invalid, e.g. a canary route that also
+    /// has a `controller`.
+    #[error("config.endpoint: route {method} {path}: {reason}")]
+    Endpoint {
+        method: Method,
+        path: String,
+        reason: String,
+    },
+
+    /// A header of the configuration's `default_response_headers` or global
+    /// `required_headers` has an invalid name or value.
+    #[error("config.header: header '{name}': {reason}")]
+    Header { name: String, reason: String },
+
+    /// The `api_keys` section of the configuration has an invalid header name
+    /// or key hash; `name` is the header or the key ID.
+    #[error("config.api_key: '{name}': {reason}")]
+    ApiKey { name: String, reason: String },
+
+    /// An entry of the configuration's `error_pages` section is invalid, e.g.
+    /// its template file can't be read; `key` is its status code or
+    /// `default`.
+    #[error("config.error_page: page '{key}': {reason}")]
+    ErrorPage { key: String, reason: String },
+
+    /// The configuration has a field its format doesn't define, e.g. a
+    /// misspelled `"controler"`. `pointer` is the JSON pointer of the object
+    /// holding it, empty for the top level, and `suggestion` the known field
+    /// it's closest to, if any.
+    #[error(
+        "config.unknown_field: field '{field}' at {}{}",
+        location(.pointer),
+        hint(.suggestion)
+    )]
+    UnknownField {
+        pointer: String,
+        field: String,
+        suggestion: Option<String>,
+    },
+
+    /// The endpoints sharing `controller` disagree on the fields securing it,
+    /// listed in `fields`, e.g. `auth`; see `Config::validate`. `endpoints`
+    /// has the index, method and path of each endpoint of the controller.
+    #[error(
+        "config.controller: controller '{controller}' has endpoints with different {}: {}",
+        fields.join(", "),
+        list_endpoints(.endpoints)
+    )]
+    InconsistentController {
+        controller: String,
+        fields: Vec<&'static str>,
+        endpoints: Vec<(usize, Method, String)>,
+    },
+
+    /// No handler is registered for the controller of the route for `method`
+    /// and `path`, or no handler factory if the route has `controller_args`,
+    /// found by `Router::validate_handlers`.
+    #[error("handler.missing: route {method} {path}: no handler for controller '{controller}'")]
+    MissingHandler {
+        method: Method,
+        path: String,
+        controller: String,
+    },
+
+    /// A request fixture, loaded with `testing::load_fixture`, is malformed at
+    /// `line`, from 1; `path` is its file, if known.
+    #[error("fixture.parse: {}line {line}: {reason}", file(.path))]
+    Fixture {
+        path: Option<PathBuf>,
+        line: usize,
+        reason: String,
+    },
+}
+
+impl RouterError {
+    /// Returns the error's stable code, e.g. `config.parse` or
+    /// `route.conflict`, which its message starts with. A code never changes
+    /// meaning, so it can be matched on or logged.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "config.io",
+            Self::Json { .. } => "config.parse",
+            Self::Http(_) => "request.invalid",
+            Self::Route { source, .. } => route_code(source),
+            Self::Rewrite { .. } => "config.rewrite",
+            Self::Endpoint { .. } => "config.endpoint",
+            Self::Header { .. } => "config.header",
+            Self::ApiKey { .. } => "config.api_key",
+            Self::ErrorPage { .. } => "config.error_page",
+            Self::UnknownField { .. } => "config.unknown_field",
+            Self::InconsistentController { .. } => "config.controller",
+            Self::MissingHandler { .. } => "handler.missing",
+            Self::Fixture { .. } => "fixture.parse",
+        }
+    }
+
+    /// Returns `true` for an error in a configuration or the files it refers
+    /// to, i.e. one with a `config.*` or `route.*` code, fixed by changing the
+    /// configuration.
+    pub fn is_config_error(&self) -> bool {
+        let code = self.code();
+        code.starts_with("config.") || code.starts_with("route.")
+    }
+
+    /// Returns `true` for an error in how the router is used rather than in
+    /// its configuration, e.g. an invalid request built in a test or a
+    /// controller without a handler.
+    pub fn is_runtime_error(&self) -> bool {
+        !self.is_config_error()
+    }
+
+    /// Sets the file of an `Io`, `Json` or `Fixture` error without one to
+    /// `path`.
+    pub(crate) fn in_file(mut self, path: &Path) -> Self {
+        if let Self::Io { path: file, .. }
+        | Self::Json { path: file, .. }
+        | Self::Fixture { path: file, .. } = &mut self
+            && file.is_none()
+        {
+            *file = Some(path.to_path_buf());
+        }
+        self
+    }
+}
+
+impl From<std::io::Error> for RouterError {
+    fn from(source: std::io::Error) -> Self {
+        Self::Io { path: None, source }
+    }
+}
+
+impl From<serde_json::Error> for RouterError {
+    fn from(source: serde_json::Error) -> Self {
+        Self::Json { path: None, source }
+    }
+}
+
+/// Names the file of an `Io` or `Json` error, if known.
+fn file(path: &Option<PathBuf>) -> String {
+    path.as_ref()
+        .map(|path| format!("{}: ", path.display()))
+        .unwrap_or_default()
+}
+
+/// Lists the endpoints of an inconsistent controller, e.g. `endpoint 0 (GET
+/// /users/:id), endpoint 3 (DELETE /users/:id)`.
+fn list_endpoints(endpoints: &[(usize, Method, String)]) -> String {
+    endpoints
+        .iter()
+        .map(|(index, method, path)| format!("endpoint {index} ({method} {path})"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Lists the routes of a `ParamNamesDiffer` warning, e.g. `GET /users/:id,
+/// DELETE /users/:user_id`.
+fn list_routes(routes: &[(Method, String)]) -> String {
+    routes
+        .iter()
+        .map(|(method, path)| format!("{method} {path}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns the code of a `Route` error: `route.conflict` for a conflict, and
+/// `route.pattern` for an invalid pattern.
+fn route_code(source: &matchit::InsertError) -> &'static str {
+    match source {
+        matchit::InsertError::Conflict { .. } => "route.conflict",
+        _ => "route.pattern",
+    }
+}
+
+/// Describes where an unknown field is, for `RouterError::UnknownField`.
+fn location(pointer: &str) -> String {
+    if pointer.is_empty() {
+        "the top level of the configuration".to_string()
+    } else {
+        format!("'{pointer}'")
+    }
+}
+
+/// Suggests the field meant, for the `UnknownField` errors and warnings.
+fn hint(suggestion: &Option<String>) -> String {
+    suggestion
+        .as_ref()
+        .map(|field| format!(", did you mean '{field}'?"))
+        .unwrap_or_default()
+}
+
+/// Why an extractor rejected a request, e.g. a path parameter that isn't a
+/// number. Responding with it gives a 400 Bad Request, or a 415 Unsupported
+/// Media Type for a body of the wrong type.
+#[cfg(feature = "extractors")]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ExtractError {
+    /// The path parameters don't deserialize into the requested type.
+    #[error("Invalid path parameters: {0}")]
+    Path(String),
+
+    /// The query string doesn't deserialize into the requested type.
+    #[error("Invalid query string: {0}")]
+    Query(String),
+
+    /// The request body doesn't have the expected `Content-Type`.
+    #[error("Expected a request body of type {expected}")]
+    UnsupportedMediaType { expected: &'static str },
+
+    /// The request body isn't valid JSON for the requested type.
+    #[error("Invalid JSON body: {0}")]
+    Json(String),
+}
+
+#[cfg(feature = "extractors")]
+impl ExtractError {
+    /// Returns the status of the response to the rejected request.
+    pub fn status(&self) -> http::StatusCode {
+        match self {
+            ExtractError::UnsupportedMediaType { .. } => http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            _ => http::StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Represents the reasons `JwtAuth` rejects a bearer token. The message is
+/// sent as the `error_description` of the 401 Unauthorized response.
+#[cfg(feature = "jwt")]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum JwtError {
+    /// The token isn't three base64url-encoded parts, or its header or claims
+    /// aren't JSON objects.
+    #[error("malformed token")]
+    Malformed,
+
+    /// The token is signed with another algorithm than HS256, RS256 or
+    /// ES256, e.g. `none`.
+    #[error("unsupported algorithm {0}")]
+    UnsupportedAlgorithm(String),
+
+    /// No key has the token's `kid`, or the key is for another algorithm.
+    #[error("unknown signing key")]
+    UnknownKey,
+
+    /// The signature doesn't match the token.
+    #[error("invalid signature")]
+    InvalidSignature,
+
+    /// The token has no `exp` claim, which is required.
+    #[error("missing exp claim")]
+    MissingExpiry,
+
+    /// The token's `exp` claim is in the past.
+    #[error("token expired")]
+    Expired,
+
+    /// The token's `nbf` claim is in the future.
+    #[error("token not yet valid")]
+    NotYetValid,
+
+    /// The token's `iss` claim isn't the expected issuer.
+    #[error("invalid issuer")]
+    InvalidIssuer,
+
+    /// The token's `aud` claim doesn't include the expected audience.
+    #[error("invalid audience")]
+    InvalidAudience,
+}
+
+/// Represents the reasons a request body can't be decoded with
+/// `HttpRequest::text` or parsed with `HttpRequest::json` or
+/// `HttpRequest::form_pairs`.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BodyError {
+    /// The `charset` of the `Content-Type` isn't supported.
+    #[error("unsupported charset {0}")]
+    UnsupportedCharset(String),
+
+    /// The body isn't valid in its charset, from the byte at `offset`.
+    #[error("body isn't valid {charset} at byte {offset}")]
+    InvalidEncoding {
+        charset: &'static str,
+        offset: usize,
+    },
+
+    /// The body isn't valid JSON, or not for the requested type.
+    #[error("invalid JSON body: {0}")]
+    Json(String),
+
+    /// The body isn't a valid URL-encoded form.
+    #[error("invalid form body: {0}")]
+    Form(String),
+}
+
+/// Represents the reasons `HttpRequest::route_config` can't read a route's
+/// metadata.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MetadataError {
+    /// The metadata field isn't of the type asked for, e.g. a string read as
+    /// a number, or an object missing a field.
+    #[error("route metadata '{key}' has the wrong type: {reason}")]
+    InvalidType { key: String, reason: String },
+}
+
+/// Represents the reasons `Router::url_for` can't build the URL of a route.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum UrlError {
+    /// No route dispatches to the controller.
+    #[error("no route for controller '{0}'")]
+    UnknownController(String),
+
+    /// The controller's paths are localized, and none is for the locale, or
+    /// no locale was given.
+    #[error("controller '{controller}' has no route for locale '{}'", .locale.as_deref().unwrap_or("none"))]
+    MissingLocale {
+        controller: String,
+        locale: Option<String>,
+    },
+
+    /// The route's path has a parameter without a value.
+    #[error("missing path parameter '{0}'")]
+    MissingParam(String),
+}
+
+/// Represents the reasons `HttpRequest::range` can't return the byte ranges of
+/// a `Range` header.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RangeError {
+    /// The header isn't a list of byte ranges, e.g. `bytes=5-3` or `bytes=a-`.
+    #[error("malformed range")]
+    Malformed,
+
+    /// The header uses another unit than `bytes`.
+    #[error("unsupported range unit {0}")]
+    UnsupportedUnit(String),
+
+    /// Two of the ranges overlap, e.g. `bytes=0-99,50-149`.
+    #[error("overlapping ranges")]
+    Overlapping,
+
+    /// None of the ranges starts before the end of the representation.
+    #[error("range not satisfiable")]
+    Unsatisfiable,
+}
+
+/// Represents the errors that can occur when parsing a raw HTTP/1.1 request
+/// with `wire::parse_request`.
+#[cfg(feature = "wire")]
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireError {
+    /// The buffer doesn't contain a whole request yet; read more and retry.
+    #[error("incomplete request")]
+    Incomplete,
+
+    /// The request line or headers are malformed, including obsolete header
+    /// line folding.
+    #[error("malformed request: {0}")]
+    Malformed(httparse::Error),
+
+    /// The header section is larger than the configured limit.
+    #[error("request header section exceeds {limit} bytes")]
+    HeadersTooLarge { limit: usize },
+
+    /// The request body is larger than the configured limit.
+    #[error("request body exceeds {limit} bytes")]
+    BodyTooLarge { limit: usize },
+
+    /// The request target isn't valid for the method, e.g. `*` for a `GET`.
+    #[error("invalid request target")]
+    InvalidTarget,
+
+    /// The `Content-Length` header is invalid, or repeated with different values.
+    #[error("invalid Content-Length")]
+    InvalidContentLength,
+
+    /// The request has both `Content-Length` and `Transfer-Encoding` headers.
+    #[error("request has both Content-Length and Transfer-Encoding")]
+    ConflictingLength,
+
+    /// The request uses `Transfer-Encoding`, which isn't supported.
+    #[error("unsupported Transfer-Encoding")]
+    UnsupportedTransferEncoding,
+}
+
+/// A problem found while loading a configuration that doesn't prevent the
+/// router from being built. Returned by `Router::new_with_report`,
+/// `Router::reload`, `Router::enable_health_endpoints` and
+/// `Router::enable_introspection`.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LoadWarning {
+    /// A route conflicts with a higher-priority route for the same method, and
+    /// was left out of the routing table.
+    #[error("Route {method} {path} is shadowed by higher-priority route {method} {by}")]
+    Shadowed {
+        method: Method,
+        /// The pattern of the route that was left out.
+        path: String,
+        /// The pattern of the route that was kept.
+        by: String,
+    },
+
+    /// A configured route matches the path of a built-in health endpoint, which
+    /// is left unreachable.
+    #[error("Health endpoint {path} is shadowed by a configured route")]
+    HealthEndpointShadowed { path: String },
+
+    /// A configured route matches the path of the built-in route listing, which
+    /// is left unreachable.
+    #[error("Introspection endpoint {path} is shadowed by a configured route")]
+    IntrospectionEndpointShadowed { path: String },
+
+    /// A configured route matches the path of the built-in batch endpoint,
+    /// which is left unreachable.
+    #[error("Batch endpoint {path} is shadowed by a configured route")]
+    BatchEndpointShadowed { path: String },
+
+    /// The endpoint at `index` in the configuration, with the pattern `path`,
+    /// has a field the format doesn't define, which is ignored. `suggestion`
+    /// is the known field it's closest to, if any. With
+    /// `Router::set_strict_config`, it's a `RouterError::UnknownField` instead.
+    #[error(
+        "Unknown field '{field}' of endpoint {index} ({path}) is ignored{}",
+        hint(.suggestion)
+    )]
+    UnknownField {
+        index: usize,
+        path: String,
+        field: String,
+        suggestion: Option<String>,
+    },
+
+    /// Routes for the same path name its parameters differently, e.g. `GET
+    /// /users/:id` and `DELETE /users/:user_id`. The path is matched the same
+    /// way for all of them, and each route's handler gets the parameters
+    /// under the names of its own pattern, which is also the one listed and
+    /// exported for it, and the one `Router::url_for` takes values by.
+    #[error(
+        "Routes for the same path name its parameters differently: {}",
+        list_routes(.routes)
+    )]
+    ParamNamesDiffer {
+        /// The method and pattern of each route for the path.
+        routes: Vec<(Method, String)>,
+    },
+
+    /// The endpoints sharing `controller` disagree on the fields securing it,
+    /// listed in `fields`; see `Config::validate`. With
+    /// `Router::set_strict_config`, it's a
+    /// `RouterError::InconsistentController` instead.
+    #[error(
+        "Controller '{controller}' has endpoints with different {}: {}",
+        fields.join(", "),
+        list_endpoints(.endpoints)
+    )]
+    InconsistentController {
+        controller: String,
+        fields: Vec<&'static str>,
+        /// The index, method and path of each endpoint of the controller.
+        endpoints: Vec<(usize, Method, String)>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoints() -> Vec<(usize, Method, String)> {
+        vec![
+            (0, Method::GET, "/users/:id".to_string()),
+            (3, Method::DELETE, "/users/:id".to_string()),
+        ]
+    }
+
+    #[test]
+    fn router_error_messages() {
+        let io = || std::io::Error::new(std::io::ErrorKind::NotFound, "not found");
+        let cases = [
+            (
+                RouterError::from(io()),
+                "config.io",
+                "config.io: not found".to_string(),
+            ),
+            (
+                RouterError::from(io()).in_file(Path::new("routes.json")),
+                "config.io",
+                "config.io: routes.json: not found".to_string(),
+            ),
+            (
+                RouterError::from(serde_json::from_str::<serde_json::Value>("{").unwrap_err())
+                    .in_file(Path::new("routes.json")),
+                "config.parse",
+                "config.parse: routes.json: EOF while parsing an object at line 1 column 1"
+                    .to_string(),
+            ),
+            (
+                RouterError::Route {
+                    method: Method::GET,
+                    path: "/users/:name".to_string(),
+                    conflicts_with: Some("/users/:id".to_string()),
+                    source: matchit::InsertError::Conflict {
+                        with: "/users/:id".to_string(),
+                    },
+                },
+                "route.conflict",
+                "route.conflict: route GET /users/:name: insertion failed due to conflict \
+                 with previously registered route: /users/:id"
+                    .to_string(),
+            ),
+            (
+                RouterError::Route {
+                    method: Method::GET,
+                    path: "/files/*path/raw".to_string(),
+                    conflicts_with: None,
+                    source: matchit::InsertError::InvalidCatchAll,
+                },
+                "route.pattern",
+                "route.pattern: route GET /files/*path/raw: catch-all parameters are only \
+                 allowed at the end of a route"
+                    .to_string(),
+            ),
+            (
+                RouterError::Rewrite {
+                    pattern: "/old/*".to_string(),
+                    reason: "no target".to_string(),
+                },
+                "config.rewrite",
+                "config.rewrite: rule '/old/*': no target".to_string(),
+            ),
+            (
+                RouterError::Endpoint {
+                    method: Method::POST,
+                    path: "/users".to_string(),
+                    reason: "a canary route can't have a controller".to_string(),
+                },
+                "config.endpoint",
+                "config.endpoint: route POST /users: a canary route can't have a controller"
+                    .to_string(),
+            ),
+            (
+                RouterError::Header {
+                    name: "x-bad header".to_string(),
+                    reason: "invalid header name".to_string(),
+                },
+                "config.header",
+                "config.header: header 'x-bad header': invalid header name".to_string(),
+            ),
+            (
+                RouterError::ApiKey {
+                    name: "partner-a".to_string(),
+                    reason: "the hash isn't 64 hex digits".to_string(),
+                },
+                "config.api_key",
+                "config.api_key: 'partner-a': the hash isn't 64 hex digits".to_string(),
+            ),
+            (
+                RouterError::ErrorPage {
+                    key: "404".to_string(),
+                    reason: "the template can't be read".to_string(),
+                },
+                "config.error_page",
+                "config.error_page: page '404': the template can't be read".to_string(),
+            ),
+            (
+                RouterError::UnknownField {
+                    pointer: "/endpoints/2".to_string(),
+                    field: "controler".to_string(),
+                    suggestion: Some("controller".to_string()),
+                },
+                "config.unknown_field",
+                "config.unknown_field: field 'controler' at '/endpoints/2', did you mean \
+                 'controller'?"
+                    .to_string(),
+            ),
+            (
+                RouterError::UnknownField {
+                    pointer: String::new(),
+                    field: "extra".to_string(),
+                    suggestion: None,
+                },
+                "config.unknown_field",
+                "config.unknown_field: field 'extra' at the top level of the configuration"
+                    .to_string(),
+            ),
+            (
+                RouterError::InconsistentController {
+                    controller: "users".to_string(),
+                    fields: vec!["auth", "max_request_size"],
+                    endpoints: endpoints(),
+                },
+                "config.controller",
+                "config.controller: controller 'users' has endpoints with different auth, \
+                 max_request_size: endpoint 0 (GET /users/:id), endpoint 3 (DELETE /users/:id)"
+                    .to_string(),
+            ),
+            (
+                RouterError::MissingHandler {
+                    method: Method::GET,
+                    path: "/users".to_string(),
+                    controller: "users::list".to_string(),
+                },
+                "handler.missing",
+                "handler.missing: route GET /users: no handler for controller 'users::list'"
+                    .to_string(),
+            ),
+            (
+                RouterError::Fixture {
+                    path: Some(PathBuf::from("requests.http")),
+                    line: 4,
+                    reason: "missing request line".to_string(),
+                },
+                "fixture.parse",
+                "fixture.parse: requests.http: line 4: missing request line".to_string(),
+            ),
+        ];
+        for (error, code, message) in cases {
+            assert_eq!(error.code(), code);
+            assert_eq!(error.to_string(), message);
+        }
+
+        let source = http::Request::builder().uri("\n").body(()).unwrap_err();
+        let message = format!("request.invalid: {source}");
+        let error = RouterError::from(source);
+        assert_eq!(error.code(), "request.invalid");
+        assert_eq!(error.to_string(), message);
+        assert!(error.is_runtime_error());
+    }
+
+    #[test]
+    fn load_warning_messages() {
+        let cases = [
+            (
+                LoadWarning::Shadowed {
+                    method: Method::GET,
+                    path: "/users/:name".to_string(),
+                    by: "/users/:id".to_string(),
+                },
+                "Route GET /users/:name is shadowed by higher-priority route GET /users/:id",
+            ),
+            (
+                LoadWarning::HealthEndpointShadowed {
+                    path: "/healthz".to_string(),
+                },
+                "Health endpoint /healthz is shadowed by a configured route",
+            ),
+            (
+                LoadWarning::IntrospectionEndpointShadowed {
+                    path: "/_routes".to_string(),
+                },
+                "Introspection endpoint /_routes is shadowed by a configured route",
+            ),
+            (
+                LoadWarning::BatchEndpointShadowed {
+                    path: "/_batch".to_string(),
+                },
+                "Batch endpoint /_batch is shadowed by a configured route",
+            ),
+            (
+                LoadWarning::UnknownField {
+                    index: 2,
+                    path: "/users".to_string(),
+                    field: "controler".to_string(),
+                    suggestion: Some("controller".to_string()),
+                },
+                "Unknown field 'controler' of endpoint 2 (/users) is ignored, did you mean \
+                 'controller'?",
+            ),
+            (
+                LoadWarning::ParamNamesDiffer {
+                    routes: vec![
+                        (Method::GET, "/users/:id".to_string()),
+                        (Method::DELETE, "/users/:user_id".to_string()),
+                    ],
+                },
+                "Routes for the same path name its parameters differently: GET /users/:id, \
+                 DELETE /users/:user_id",
+            ),
+            (
+                LoadWarning::InconsistentController {
+                    controller: "users".to_string(),
+                    fields: vec!["auth"],
+                    endpoints: endpoints(),
+                },
+                "Controller 'users' has endpoints with different auth: endpoint 0 (GET \
+                 /users/:id), endpoint 3 (DELETE /users/:id)",
+            ),
+        ];
+        for (warning, message) in cases {
+            assert_eq!(warning.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn request_error_messages() {
+        let cases = [
+            (
+                BodyError::UnsupportedCharset("klingon-8".to_string()).to_string(),
+                "unsupported charset klingon-8",
+            ),
+            (
+                BodyError::InvalidEncoding {
+                    charset: "UTF-8",
+                    offset: 2,
+                }
+                .to_string(),
+                "body isn't valid UTF-8 at byte 2",
+            ),
+            (
+                BodyError::Json("expected value".to_string()).to_string(),
+                "invalid JSON body: expected value",
+            ),
+            (
+                BodyError::Form("invalid escape".to_string()).to_string(),
+                "invalid form body: invalid escape",
+            ),
+            (
+                MetadataError::InvalidType {
+                    key: "owner".to_string(),
+                    reason: "expected a string".to_string(),
+                }
+                .to_string(),
+                "route metadata 'owner' has the wrong type: expected a string",
+            ),
+            (
+                UrlError::UnknownController("users".to_string()).to_string(),
+                "no route for controller 'users'",
+            ),
+            (
+                UrlError::MissingLocale {
+                    controller: "users".to_string(),
+                    locale: Some("fr".to_string()),
+                }
+                .to_string(),
+                "controller 'users' has no route for locale 'fr'",
+            ),
+            (
+                UrlError::MissingLocale {
+                    controller: "users".to_string(),
+                    locale: None,
+                }
+                .to_string(),
+                "controller 'users' has no route for locale 'none'",
+            ),
+            (
+                UrlError::MissingParam("id".to_string()).to_string(),
+                "missing path parameter 'id'",
+            ),
+            (RangeError::Malformed.to_string(), "malformed range"),
+            (
+                RangeError::UnsupportedUnit("items".to_string()).to_string(),
+                "unsupported range unit items",
+            ),
+            (RangeError::Overlapping.to_string(), "overlapping ranges"),
+            (
+                RangeError::Unsatisfiable.to_string(),
+                "range not satisfiable",
+            ),
+        ];
+        for (message, expected) in cases {
+            assert_eq!(message, expected);
+        }
+    }
+
+    #[cfg(feature = "extractors")]
+    #[test]
+    fn extract_error_messages() {
+        let cases = [
+            (
+                ExtractError::Path("invalid digit".to_string()),
+                "Invalid path parameters: invalid digit",
+            ),
+            (
+                ExtractError::Query("missing field `page`".to_string()),
+                "Invalid query string: missing field `page`",
+            ),
+            (
+                ExtractError::UnsupportedMediaType {
+                    expected: "application/json",
+                },
+                "Expected a request body of type application/json",
+            ),
+            (
+                ExtractError::Json("expected value".to_string()),
+                "Invalid JSON body: expected value",
+            ),
+        ];
+        for (error, message) in cases {
+            assert_eq!(error.to_string(), message);
+        }
+    }
+
+    #[cfg(feature = "jwt")]
+    #[test]
+    fn jwt_error_messages() {
+        let cases = [
+            (JwtError::Malformed, "malformed token"),
+            (
+                JwtError::UnsupportedAlgorithm("none".to_string()),
+                "unsupported algorithm none",
+            ),
+            (JwtError::UnknownKey, "unknown signing key"),
+            (JwtError::InvalidSignature, "invalid signature"),
+            (JwtError::MissingExpiry, "missing exp claim"),
+            (JwtError::Expired, "token expired"),
+            (JwtError::NotYetValid, "token not yet valid"),
+            (JwtError::InvalidIssuer, "invalid issuer"),
+            (JwtError::InvalidAudience, "invalid audience"),
+        ];
+        for (error, message) in cases {
+            assert_eq!(error.to_string(), message);
+        }
+    }
+
+    #[cfg(feature = "wire")]
+    #[test]
+    fn wire_error_messages() {
+        let cases = [
+            (WireError::Incomplete, "incomplete request"),
+            (
+                WireError::Malformed(httparse::Error::Version),
+                "malformed request: invalid HTTP version",
+            ),
+            (
+                WireError::HeadersTooLarge { limit: 8192 },
+                "request header section exceeds 8192 bytes",
+            ),
+            (
+                WireError::BodyTooLarge { limit: 1024 },
+                "request body exceeds 1024 bytes",
+            ),
+            (WireError::InvalidTarget, "invalid request target"),
+            (WireError::InvalidContentLength, "invalid Content-Length"),
+            (
+                WireError::ConflictingLength,
+                "request has both Content-Length and Transfer-Encoding",
+            ),
+            (
+                WireError::UnsupportedTransferEncoding,
+                "unsupported Transfer-Encoding",
+            ),
+        ];
+        for (error, message) in cases {
+            assert_eq!(error.to_string(), message);
+        }
+    }
 }
@@ -0,0 +1,189 @@
+//! Defines the templates of the configuration's `error_pages` section, which
+//! replace the bodies of the router's own error responses.
+
+use crate::accept::{self, parse_media_type};
+use crate::config::{ErrorPage, ErrorPages};
+use crate::error::RouterError;
+use crate::response::HttpResponse;
+use crate::template::Escape;
+use http::header::{ACCEPT, CONTENT_TYPE};
+use http::{HeaderValue, Request, StatusCode};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Marks a built-in error response, the only kind the templates apply to.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BuiltInError;
+
+/// A template of an error page, loaded from the configuration.
+#[derive(Debug)]
+struct Template {
+    /// The media type matched against `Accept`, e.g. `text/html`.
+    media_type: String,
+    content_type: HeaderValue,
+    escape: Escape,
+    body: String,
+}
+
+/// The error page templates of a configuration, by status code.
+#[derive(Debug, Default)]
+pub(crate) struct ErrorTemplates {
+    by_status: HashMap<u16, Vec<Template>>,
+    default: Vec<Template>,
+}
+
+/// What the templates need to know about a request, kept before it's routed.
+#[derive(Debug)]
+pub(crate) struct PageRequest {
+    accept: Vec<HeaderValue>,
+    path: String,
+    request_id: Option<String>,
+}
+
+impl PageRequest {
+    pub(crate) fn new<B>(req: &Request<B>) -> Self {
+        Self {
+            accept: req.headers().get_all(ACCEPT).iter().cloned().collect(),
+            path: req.uri().path().to_string(),
+            request_id: req
+                .headers()
+                .get(crate::X_REQUEST_ID)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+        }
+    }
+}
+
+impl ErrorTemplates {
+    /// Loads the templates of the `error_pages` section, reading template
+    /// files relative to `base_dir`.
+    ///
+    /// Returns `None` if the section is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RouterError::ErrorPage` for a key that isn't `default` or an
+    /// error status code, a page with both or neither of `body` and `file`, an
+    /// invalid `content_type`, or a template file that can't be read.
+    pub(crate) fn load(
+        pages: &BTreeMap<String, ErrorPages>,
+        base_dir: &Path,
+    ) -> Result<Option<Self>, RouterError> {
+        if pages.is_empty() {
+            return Ok(None);
+        }
+        let mut templates = Self::default();
+        for (key, pages) in pages {
+            let invalid = |reason: String| RouterError::ErrorPage {
+                key: key.clone(),
+                reason,
+            };
+            let pages = match pages {
+                ErrorPages::One(page) => std::slice::from_ref(page),
+                ErrorPages::Variants(pages) if pages.is_empty() => {
+                    return Err(invalid("no page is given".to_string()));
+                }
+                ErrorPages::Variants(pages) => pages.as_slice(),
+            };
+            let variants = pages
+                .iter()
+                .map(|page| Template::load(page, base_dir).map_err(invalid))
+                .collect::<Result<Vec<_>, _>>()?;
+            if key == "default" {
+                templates.default = variants;
+            } else {
+                let status = key
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|status| (400..600).contains(status))
+                    .ok_or_else(|| {
+                        invalid("expected an error status code or \"default\"".to_string())
+                    })?;
+                templates.by_status.insert(status, variants);
+            }
+        }
+        Ok(Some(templates))
+    }
+
+    /// Replaces the body of `response` with its template, if it's a built-in
+    /// error response and a template is configured for its status or by
+    /// default. Of several variants, the one `Accept` prefers is used, or the
+    /// first if it accepts none.
+    pub(crate) fn apply(&self, req: &PageRequest, response: &mut HttpResponse) {
+        if response.extensions.remove::<BuiltInError>().is_none() {
+            return;
+        }
+        let variants = match self.by_status.get(&response.status.as_u16()) {
+            Some(variants) => variants,
+            None if !self.default.is_empty() => &self.default,
+            None => return,
+        };
+        let template = match variants.as_slice() {
+            [template] => template,
+            _ => {
+                let offered: Vec<&str> = variants.iter().map(|t| t.media_type.as_str()).collect();
+                let preferred = accept::negotiate(&accept::parse_accept(&req.accept), &offered);
+                response.add_vary(ACCEPT);
+                preferred
+                    .and_then(|media_type| variants.iter().find(|t| t.media_type == media_type))
+                    .unwrap_or(&variants[0])
+            }
+        };
+        response.body = template.render(response.status, req).into();
+        response
+            .headers
+            .insert(CONTENT_TYPE, template.content_type.clone());
+    }
+}
+
+impl Template {
+    fn load(page: &ErrorPage, base_dir: &Path) -> Result<Self, String> {
+        let media_type = parse_media_type(&page.content_type)
+            .ok_or_else(|| format!("invalid content_type '{}'", page.content_type))?;
+        let content_type = HeaderValue::from_str(&page.content_type)
+            .map_err(|_| format!("invalid content_type '{}'", page.content_type))?;
+        let body = match (&page.body, &page.file) {
+            (Some(body), None) => body.clone(),
+            (None, Some(file)) => std::fs::read_to_string(base_dir.join(file))
+                .map_err(|e| format!("can't read '{file}': {e}"))?,
+            _ => return Err("exactly one of body and file must be set".to_string()),
+        };
+        let escape = Escape::for_subtype(&media_type.subtype);
+        Ok(Self {
+            media_type: format!("{}/{}", media_type.media_type, media_type.subtype),
+            content_type,
+            escape,
+            body,
+        })
+    }
+
+    /// Substitutes the placeholders of the template for `req`, escaping the
+    /// values for the content type. Unknown placeholders are left as they are.
+    fn render(&self, status: StatusCode, req: &PageRequest) -> String {
+        let mut out = String::with_capacity(self.body.len() + 32);
+        let mut rest = self.body.as_str();
+        while let Some(start) = rest.find("{{") {
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let value = [
+                ("{{status}}", status.as_str()),
+                ("{{path}}", req.path.as_str()),
+                ("{{request_id}}", req.request_id.as_deref().unwrap_or("")),
+            ]
+            .into_iter()
+            .find(|(placeholder, _)| rest.starts_with(placeholder));
+            match value {
+                Some((placeholder, value)) => {
+                    self.escape.push(&mut out, value);
+                    rest = &rest[placeholder.len()..];
+                }
+                None => {
+                    out.push_str("{{");
+                    rest = &rest[2..];
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+}
@@ -0,0 +1,258 @@
+//! Defines helpers for responses streaming a sequence of records, as NDJSON or,
+//! with the `csv` feature, as CSV.
+
+use crate::body::Body;
+use crate::response::HttpResponse;
+use bytes::Bytes;
+use http::StatusCode;
+use http::header::CONTENT_TYPE;
+use serde::Serialize;
+use std::io;
+
+impl HttpResponse {
+    /// Creates an `application/x-ndjson` response with one JSON document per
+    /// line, one per item.
+    ///
+    /// The items are serialized as the body is streamed, so a large export is
+    /// never held in memory. An item that fails to serialize ends the stream.
+    pub fn ndjson<I, T>(status: StatusCode, items: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: Send + 'static,
+        T: Serialize,
+    {
+        let lines = items.into_iter().map(|item| {
+            let mut line = serde_json::to_vec(&item)?;
+            line.push(b'\n');
+            Ok::<_, io::Error>(Bytes::from(line))
+        });
+        let mut response = HttpResponse::new(status, Body::stream(lines));
+        response.add_header(CONTENT_TYPE, "application/x-ndjson");
+        response
+    }
+
+    /// Creates a `text/csv; charset=utf-8` response with one record per item,
+    /// after a header record with the field names of the first item. Lines end
+    /// with CRLF, and fields containing commas, quotes or line breaks are quoted.
+    ///
+    /// The items must be structs whose fields are strings, numbers, booleans or
+    /// options of those (`None` gives an empty field). They're serialized as the
+    /// body is streamed, like [`HttpResponse::ndjson`]; an item that fails to
+    /// serialize ends the stream. No items give an empty body.
+    #[cfg(feature = "csv")]
+    pub fn csv<I, T>(status: StatusCode, items: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: Send + 'static,
+        T: Serialize,
+    {
+        let mut first = true;
+        let lines = items.into_iter().map(move |item| {
+            let record = item.serialize(csv::RecordSerializer)?;
+            let mut lines = String::new();
+            if first {
+                first = false;
+                csv::write_record(&mut lines, record.iter().map(|(name, _)| *name));
+            }
+            csv::write_record(&mut lines, record.iter().map(|(_, value)| value.as_str()));
+            Ok::<_, io::Error>(Bytes::from(lines))
+        });
+        let mut response = HttpResponse::new(status, Body::stream(lines));
+        response.add_header(CONTENT_TYPE, "text/csv; charset=utf-8");
+        response
+    }
+}
+
+#[cfg(feature = "csv")]
+mod csv {
+    use serde::ser::{self, Error as _, Impossible, Serialize};
+    use serde_json::{Error, Value};
+
+    /// A record: the field names and values of a struct, in declaration order.
+    pub(super) type Record = Vec<(&'static str, String)>;
+
+    /// Appends `fields` to `out` as a CSV line.
+    pub(super) fn write_record<'a>(out: &mut String, fields: impl Iterator<Item = &'a str>) {
+        for (i, field) in fields.enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            if field.contains([',', '"', '\n', '\r']) {
+                out.push('"');
+                out.push_str(&field.replace('"', "\"\""));
+                out.push('"');
+            } else {
+                out.push_str(field);
+            }
+        }
+        out.push_str("\r\n");
+    }
+
+    fn not_a_struct<T>() -> Result<T, Error> {
+        Err(Error::custom("a CSV record must be a struct"))
+    }
+
+    /// Serializes a struct into a `Record`.
+    pub(super) struct RecordSerializer;
+
+    pub(super) struct RecordFields(Record);
+
+    impl ser::SerializeStruct for RecordFields {
+        type Ok = Record;
+        type Error = Error;
+
+        fn serialize_field<T: Serialize + ?Sized>(
+            &mut self,
+            key: &'static str,
+            value: &T,
+        ) -> Result<(), Error> {
+            let field = match serde_json::to_value(value)? {
+                Value::Null => String::new(),
+                Value::String(s) => s,
+                value @ (Value::Bool(_) | Value::Number(_)) => value.to_string(),
+                Value::Array(_) | Value::Object(_) => {
+                    return Err(Error::custom(format!(
+                        "the CSV field `{key}` must be a string, a number or a boolean"
+                    )));
+                }
+            };
+            self.0.push((key, field));
+            Ok(())
+        }
+
+        fn end(self) -> Result<Record, Error> {
+            Ok(self.0)
+        }
+    }
+
+    impl ser::Serializer for RecordSerializer {
+        type Ok = Record;
+        type Error = Error;
+        type SerializeSeq = Impossible<Record, Error>;
+        type SerializeTuple = Impossible<Record, Error>;
+        type SerializeTupleStruct = Impossible<Record, Error>;
+        type SerializeTupleVariant = Impossible<Record, Error>;
+        type SerializeMap = Impossible<Record, Error>;
+        type SerializeStruct = RecordFields;
+        type SerializeStructVariant = Impossible<Record, Error>;
+
+        fn serialize_struct(self, _: &'static str, len: usize) -> Result<RecordFields, Error> {
+            Ok(RecordFields(Vec::with_capacity(len)))
+        }
+
+        fn serialize_newtype_struct<T: Serialize + ?Sized>(
+            self,
+            _: &'static str,
+            value: &T,
+        ) -> Result<Record, Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Record, Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_bool(self, _: bool) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_i8(self, _: i8) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_i16(self, _: i16) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_i32(self, _: i32) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_i64(self, _: i64) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_u8(self, _: u8) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_u16(self, _: u16) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_u32(self, _: u32) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_u64(self, _: u64) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_f32(self, _: f32) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_f64(self, _: f64) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_char(self, _: char) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_str(self, _: &str) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_bytes(self, _: &[u8]) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_none(self) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_unit(self) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_unit_struct(self, _: &'static str) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_unit_variant(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+        ) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_newtype_variant<T: Serialize + ?Sized>(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: &T,
+        ) -> Result<Record, Error> {
+            not_a_struct()
+        }
+        fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+            not_a_struct()
+        }
+        fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, Error> {
+            not_a_struct()
+        }
+        fn serialize_tuple_struct(
+            self,
+            _: &'static str,
+            _: usize,
+        ) -> Result<Self::SerializeTupleStruct, Error> {
+            not_a_struct()
+        }
+        fn serialize_tuple_variant(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: usize,
+        ) -> Result<Self::SerializeTupleVariant, Error> {
+            not_a_struct()
+        }
+        fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Error> {
+            not_a_struct()
+        }
+        fn serialize_struct_variant(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: usize,
+        ) -> Result<Self::SerializeStructVariant, Error> {
+            not_a_struct()
+        }
+    }
+}
@@ -0,0 +1,396 @@
+//! Defines typed extractors, which let handlers be plain functions of the parts
+//! of the request they need, e.g. `fn(Path<u64>, Json<CreateUser>) -> String`,
+//! registered with `Router::register_extracting`.
+
+use crate::error::{BodyError, ExtractError};
+use crate::handler::HttpHandler;
+use crate::problem::Problem;
+use crate::request::HttpRequest;
+use crate::response::{HttpResponse, IntoHttpResponse};
+use bytes::Bytes;
+use http::HeaderMap;
+use http::header::CONTENT_TYPE;
+use serde::de::value::{Error as DeError, MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, Error as _, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use std::marker::PhantomData;
+
+/// A value that can be extracted from a request, as an argument of a handler
+/// function registered with `Router::register_extracting`.
+pub trait FromRequest: Sized {
+    /// Extracts the value, or returns why the request is rejected.
+    fn from_request(req: &HttpRequest) -> Result<Self, ExtractError>;
+}
+
+/// The path parameters, deserialized into `T`: a single value (`Path<u64>` for
+/// `/users/:id`), a tuple of values in path order, or a struct or map keyed by
+/// parameter name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<T>(pub T);
+
+/// The query string, deserialized into `T`, typically a struct whose optional
+/// fields are `Option`s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query<T>(pub T);
+
+/// The JSON request body, deserialized into `T`. The request must have an
+/// `application/json` (or `+json`) `Content-Type`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Json<T>(pub T);
+
+/// A copy of the request headers.
+#[derive(Debug, Clone)]
+pub struct Headers(pub HeaderMap);
+
+/// The request body, as is. Named so as not to clash with the response
+/// [`Body`](crate::Body).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawBody(pub Bytes);
+
+impl<T: DeserializeOwned> FromRequest for Path<T> {
+    fn from_request(req: &HttpRequest) -> Result<Self, ExtractError> {
+        let params = req
+            .params
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        T::deserialize(ParamsDeserializer(params))
+            .map(Path)
+            .map_err(|e| ExtractError::Path(e.to_string()))
+    }
+}
+
+impl<T: DeserializeOwned> FromRequest for Query<T> {
+    fn from_request(req: &HttpRequest) -> Result<Self, ExtractError> {
+        let pairs = req
+            .query_result()
+            .map_err(|reason| ExtractError::Query(reason.to_string()))?
+            .iter()
+            .map(|(name, value)| (name.clone(), StrValue(value.clone())));
+        T::deserialize(MapDeserializer::<_, DeError>::new(pairs))
+            .map(Query)
+            .map_err(|e| ExtractError::Query(e.to_string()))
+    }
+}
+
+impl<T: DeserializeOwned> FromRequest for Json<T> {
+    fn from_request(req: &HttpRequest) -> Result<Self, ExtractError> {
+        let is_json = req
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .is_some_and(|mime| {
+                let mime = mime.trim();
+                mime.eq_ignore_ascii_case("application/json")
+                    || (mime.len() > 5
+                        && mime[mime.len() - 5..].eq_ignore_ascii_case("+json")
+                        && mime[..mime.len() - 5].contains('/'))
+            });
+        if !is_json {
+            return Err(ExtractError::UnsupportedMediaType {
+                expected: "application/json",
+            });
+        }
+        match req.json() {
+            Ok(value) => Ok(Json(value)),
+            Err(BodyError::Json(reason)) => Err(ExtractError::Json(reason)),
+            Err(e) => Err(ExtractError::Json(e.to_string())),
+        }
+    }
+}
+
+impl FromRequest for Headers {
+    fn from_request(req: &HttpRequest) -> Result<Self, ExtractError> {
+        Ok(Headers(req.headers.clone()))
+    }
+}
+
+impl FromRequest for RawBody {
+    fn from_request(req: &HttpRequest) -> Result<Self, ExtractError> {
+        Ok(RawBody(req.body.clone()))
+    }
+}
+
+impl<T: FromRequest> FromRequest for Option<T> {
+    fn from_request(req: &HttpRequest) -> Result<Self, ExtractError> {
+        Ok(T::from_request(req).ok())
+    }
+}
+
+/// Marks the response of a rejected request, so that the router can replace
+/// it with a problem document when problem responses are enabled.
+struct Rejection(ExtractError);
+
+/// Responds with a 400 Bad Request, or a 415 Unsupported Media Type for a body
+/// of the wrong type, with the reason as a `text/plain` body.
+impl IntoHttpResponse for ExtractError {
+    fn into_http_response(self) -> HttpResponse {
+        let mut response = HttpResponse::text(self.status(), self.to_string());
+        response.extensions.insert(Rejection(self));
+        response
+    }
+}
+
+/// Replaces the response of a rejected request with a problem document.
+pub(crate) fn rejection_to_problem(mut response: HttpResponse) -> HttpResponse {
+    match response.extensions.remove::<Rejection>() {
+        Some(Rejection(error)) => {
+            let mut problem =
+                HttpResponse::problem(Problem::new(error.status()).with_detail(error.to_string()));
+            problem.extensions = response.extensions;
+            problem
+        }
+        None => response,
+    }
+}
+
+/// A function taking extractors as arguments, up to four, and returning
+/// anything convertible into a response. Implemented for `Fn(A, B, ...) -> R`;
+/// `Args` is the tuple of argument types.
+pub trait ExtractorHandler<Args>: Send + Sync + 'static {
+    /// Extracts the arguments from `req` and calls the function, or responds
+    /// with the first extraction error.
+    fn call(&self, req: &HttpRequest) -> HttpResponse;
+}
+
+macro_rules! impl_extractor_handler {
+    ($($arg:ident),*) => {
+        impl<F, R, $($arg,)*> ExtractorHandler<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R + Send + Sync + 'static,
+            R: IntoHttpResponse,
+            $($arg: FromRequest,)*
+        {
+            #[allow(non_snake_case, unused_variables)]
+            fn call(&self, req: &HttpRequest) -> HttpResponse {
+                $(
+                    let $arg = match $arg::from_request(req) {
+                        Ok(value) => value,
+                        Err(error) => return error.into_http_response(),
+                    };
+                )*
+                (self)($($arg),*).into_http_response()
+            }
+        }
+    };
+}
+
+impl_extractor_handler!();
+impl_extractor_handler!(A);
+impl_extractor_handler!(A, B);
+impl_extractor_handler!(A, B, C);
+impl_extractor_handler!(A, B, C, D);
+
+/// Adapts an `ExtractorHandler` into an `HttpHandler`.
+pub(crate) struct Extracting<H, Args> {
+    handler: H,
+    args: PhantomData<fn() -> Args>,
+}
+
+impl<H, Args> Extracting<H, Args> {
+    pub(crate) fn new(handler: H) -> Self {
+        Self {
+            handler,
+            args: PhantomData,
+        }
+    }
+}
+
+impl<H: ExtractorHandler<Args>, Args> HttpHandler for Extracting<H, Args> {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        self.handler.call(&req)
+    }
+}
+
+/// A path parameter or query string value, parsed into whatever type is asked
+/// for.
+struct StrValue(String);
+
+macro_rules! parse_value {
+    ($($method:ident => $visit:ident),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+                match self.0.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(DeError::custom(format!("invalid value '{}'", self.0))),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for StrValue {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_string(self.0)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    parse_value!(
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64
+    );
+
+    forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl IntoDeserializer<'_, DeError> for StrValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// The path parameters, deserialized as a map or struct by name, a sequence or
+/// tuple in path order, or a single value if there's exactly one.
+struct ParamsDeserializer(Vec<(String, String)>);
+
+impl ParamsDeserializer {
+    fn single(self) -> Result<StrValue, DeError> {
+        let count = self.0.len();
+        match <[_; 1]>::try_from(self.0) {
+            Ok([(_, value)]) => Ok(StrValue(value)),
+            Err(_) => Err(DeError::custom(format!(
+                "expected 1 path parameter, found {count}"
+            ))),
+        }
+    }
+}
+
+macro_rules! single_param {
+    ($($method:ident),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+                self.single()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ParamsDeserializer {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        if self.0.len() == 1 {
+            self.single()?.deserialize_any(visitor)
+        } else {
+            self.deserialize_map(visitor)
+        }
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let entries = self
+            .0
+            .into_iter()
+            .map(|(name, value)| (name, StrValue(value)));
+        visitor.visit_map(MapDeserializer::new(entries))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let values = self.0.into_iter().map(|(_, value)| StrValue(value));
+        visitor.visit_seq(SeqDeserializer::new(values))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        if self.0.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    single_param!(
+        deserialize_bool,
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32,
+        deserialize_u64,
+        deserialize_f32,
+        deserialize_f64,
+        deserialize_char,
+        deserialize_str,
+        deserialize_string
+    );
+
+    forward_to_deserialize_any! {
+        i128 u128 bytes byte_buf unit unit_struct identifier ignored_any
+    }
+}
@@ -0,0 +1,472 @@
+//! Defines helpers for responses serving a file from disk, and the handler of
+//! `static_files` routes.
+
+use crate::body::Body;
+use crate::conditional::{self, not_modified_since};
+use crate::config::StaticFiles;
+use crate::error::RangeError;
+use crate::handler::HttpHandler;
+use crate::range::ByteRange;
+use crate::request::HttpRequest;
+use crate::response::HttpResponse;
+use crate::template::Escape;
+use bytes::Bytes;
+use http::header::{
+    ACCEPT_ENCODING, ACCEPT_RANGES, CONTENT_DISPOSITION, CONTENT_ENCODING, CONTENT_LENGTH,
+    CONTENT_RANGE, CONTENT_TYPE, ETAG, HeaderMap, IF_NONE_MATCH,
+};
+use http::{Method, StatusCode};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The size of the chunks a file body is streamed in.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+impl HttpResponse {
+    /// Creates a response streaming the file at `path`.
+    ///
+    /// Sets `Content-Type` from the file extension, `Content-Length`,
+    /// `Last-Modified` and `Accept-Ranges: bytes`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the file can't be opened or its metadata read.
+    pub fn file<P: AsRef<Path>>(path: P) -> Result<HttpResponse, io::Error> {
+        FileResponse::open(path.as_ref())?.full()
+    }
+
+    /// Creates a response streaming the file at `path` as a download named `name`,
+    /// like [`HttpResponse::file`] plus a `Content-Disposition: attachment` header.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the file can't be opened or its metadata read.
+    pub fn file_attachment<P: AsRef<Path>>(path: P, name: &str) -> Result<HttpResponse, io::Error> {
+        let mut response = Self::file(path)?;
+        response.add_header(CONTENT_DISPOSITION, &content_disposition(name));
+        Ok(response)
+    }
+
+    /// Creates a response streaming the file at `path`, honoring the `Range`
+    /// and time-based conditional headers of `req`.
+    ///
+    /// A request whose `If-Unmodified-Since` is earlier than the file's
+    /// modification time gets a 412 Precondition Failed, and one that
+    /// [`not_modified_since`](crate::conditional::not_modified_since) accepts a
+    /// 304 Not Modified, both without a body.
+    /// A satisfiable single byte range, as parsed by [`HttpRequest::range`],
+    /// produces a 206 Partial Content with `Content-Range`, and an
+    /// unsatisfiable one a 416 Range Not Satisfiable. Requests without a
+    /// `Range` header, with an otherwise invalid one, or asking for multiple
+    /// ranges get the whole file, as [`HttpResponse::file`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the file can't be opened, its metadata read, or
+    /// the range seeked to.
+    pub fn file_ranged<P: AsRef<Path>>(
+        path: P,
+        req: &HttpRequest,
+    ) -> Result<HttpResponse, io::Error> {
+        FileResponse::open(path.as_ref())?.ranged(req)
+    }
+}
+
+/// An opened file with the metadata needed to build a response.
+struct FileResponse {
+    file: File,
+    len: u64,
+    content_type: &'static str,
+    last_modified: Option<SystemTime>,
+}
+
+impl FileResponse {
+    fn open(path: &Path) -> Result<Self, io::Error> {
+        let file = File::open(path)?;
+        let metadata = file.metadata()?;
+        if !metadata.is_file() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a file"));
+        }
+        Ok(Self {
+            file,
+            len: metadata.len(),
+            content_type: content_type(path),
+            last_modified: metadata.modified().ok(),
+        })
+    }
+
+    /// Builds the response to `req`, honoring its `Range` and time-based
+    /// conditional headers; see `HttpResponse::file_ranged`.
+    fn ranged(self, req: &HttpRequest) -> Result<HttpResponse, io::Error> {
+        let range = req.range(self.len);
+
+        if let Some(last_modified) = self.last_modified {
+            if conditional::modified_since_precondition(req, last_modified) {
+                return Ok(HttpResponse::new_static(
+                    StatusCode::PRECONDITION_FAILED,
+                    b"",
+                ));
+            }
+            if not_modified_since(req, last_modified) {
+                let mut response = HttpResponse::new_static(StatusCode::NOT_MODIFIED, b"");
+                response.set_last_modified(last_modified);
+                return Ok(response);
+            }
+        }
+
+        match range {
+            Some(Ok(ranges)) if ranges.len() == 1 => self.partial(ranges[0]),
+            Some(Err(RangeError::Unsatisfiable)) => {
+                let mut response = HttpResponse::new_static(StatusCode::RANGE_NOT_SATISFIABLE, b"");
+                response.add_header(CONTENT_RANGE, &format!("bytes */{}", self.len));
+                Ok(response)
+            }
+            _ => self.full(),
+        }
+    }
+
+    /// Returns the strong `ETag` of the file, from its modification time and
+    /// size, e.g. `"65f1c2a0-1b3"`.
+    fn etag(&self) -> String {
+        let modified = self
+            .last_modified
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |elapsed| elapsed.as_secs());
+        format!("\"{modified:x}-{:x}\"", self.len)
+    }
+
+    fn full(self) -> Result<HttpResponse, io::Error> {
+        let len = self.len;
+        let mut response = self.response(StatusCode::OK, 0, len)?;
+        response.add_header(CONTENT_LENGTH, &len.to_string());
+        Ok(response)
+    }
+
+    /// Builds a 206 response for `range`.
+    fn partial(self, range: ByteRange) -> Result<HttpResponse, io::Error> {
+        let total = self.len;
+        let mut response = self.response(StatusCode::PARTIAL_CONTENT, range.start, range.len())?;
+        response.add_header(CONTENT_LENGTH, &range.len().to_string());
+        response.content_range(range, total);
+        Ok(response)
+    }
+
+    fn response(
+        mut self,
+        status: StatusCode,
+        start: u64,
+        len: u64,
+    ) -> Result<HttpResponse, io::Error> {
+        if start > 0 {
+            self.file.seek(SeekFrom::Start(start))?;
+        }
+        let chunks = FileChunks {
+            file: self.file,
+            remaining: len,
+        };
+
+        let mut response = HttpResponse::new(status, Body::stream(chunks));
+        response.add_header(CONTENT_TYPE, self.content_type);
+        response.add_header(ACCEPT_RANGES, "bytes");
+        if let Some(last_modified) = self.last_modified {
+            response.set_last_modified(last_modified);
+        }
+        Ok(response)
+    }
+}
+
+/// Reads `remaining` bytes of a file, chunk by chunk.
+struct FileChunks {
+    file: File,
+    remaining: u64,
+}
+
+impl Iterator for FileChunks {
+    type Item = Result<Bytes, io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let size = self.remaining.min(CHUNK_SIZE as u64) as usize;
+        let mut chunk = vec![0; size];
+        match self.file.read(&mut chunk) {
+            Ok(0) => {
+                self.remaining = 0;
+                Some(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "file ended before its reported length",
+                )))
+            }
+            Ok(read) => {
+                self.remaining -= read as u64;
+                chunk.truncate(read);
+                Some(Ok(Bytes::from(chunk)))
+            }
+            Err(e) => {
+                self.remaining = 0;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Builds a `Content-Disposition: attachment` value for `name`.
+///
+/// The `filename` parameter is a quoted string with `"` and `\` escaped and any
+/// non-ASCII or control character replaced with `_`; names that aren't plain
+/// ASCII are also sent in full as a percent-encoded UTF-8 `filename*` (RFC 6266).
+fn content_disposition(name: &str) -> String {
+    let mut fallback = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '"' | '\\' => {
+                fallback.push('\\');
+                fallback.push(c);
+            }
+            ' '..='~' => fallback.push(c),
+            _ => fallback.push('_'),
+        }
+    }
+
+    let mut value = format!("attachment; filename=\"{fallback}\"");
+    if name.chars().any(|c| !(' '..='~').contains(&c)) {
+        value.push_str("; filename*=UTF-8''");
+        for byte in name.bytes() {
+            if byte.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&byte) {
+                value.push(byte as char);
+            } else {
+                value.push_str(&format!("%{byte:02X}"));
+            }
+        }
+    }
+    value
+}
+
+/// Guesses the `Content-Type` of a file from its extension.
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js" | "mjs") => "text/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("csv") => "text/csv; charset=utf-8",
+        Some("xml") => "application/xml",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("pdf") => "application/pdf",
+        Some("zip") => "application/zip",
+        Some("gz") => "application/gzip",
+        Some("wasm") => "application/wasm",
+        Some("mp3") => "audio/mpeg",
+        Some("mp4") => "video/mp4",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+/// The handler of a `static_files` route, serving the file named by the
+/// route's catch-all parameter.
+pub(crate) struct StaticDir {
+    root: PathBuf,
+    /// The name of the catch-all parameter, e.g. `file` for `/assets/*file`.
+    param: String,
+    files: StaticFiles,
+}
+
+impl StaticDir {
+    /// Creates the handler of the route with the path pattern `pattern`.
+    ///
+    /// # Errors
+    ///
+    /// Returns why the route can't serve files: its pattern doesn't end with
+    /// a catch-all parameter.
+    pub(crate) fn new(files: StaticFiles, pattern: &str) -> Result<Self, String> {
+        let param = pattern
+            .rsplit('/')
+            .next()
+            .and_then(|segment| segment.strip_prefix('*'))
+            .filter(|name| !name.is_empty())
+            .ok_or("static_files routes need a path ending with a catch-all parameter, e.g. /assets/*file")?;
+        Ok(Self {
+            root: PathBuf::from(&files.dir),
+            param: param.to_string(),
+            files,
+        })
+    }
+
+    /// Returns the path of `relative` in the directory, or `None` if it
+    /// doesn't exist or is outside of it, through a `..` segment or a
+    /// symbolic link.
+    fn resolve(&self, relative: &str) -> Option<PathBuf> {
+        let mut path = self.root.clone();
+        for segment in relative.split('/') {
+            match segment {
+                "" | "." => {}
+                ".." => return None,
+                segment if segment.contains(['\\', '\0']) => return None,
+                segment => path.push(segment),
+            }
+        }
+        let path = path.canonicalize().ok()?;
+        let root = self.root.canonicalize().ok()?;
+        path.starts_with(&root).then_some(path)
+    }
+
+    /// Serves the file at `path`, or its precompressed sibling at `encoded`.
+    fn serve(
+        &self,
+        path: &Path,
+        encoded: Option<PathBuf>,
+        req: &HttpRequest,
+    ) -> Result<HttpResponse, io::Error> {
+        let mut file = FileResponse::open(encoded.as_deref().unwrap_or(path))?;
+        file.content_type = content_type(path);
+        let etag = self.files.etag.then(|| file.etag());
+        if !self.files.last_modified {
+            file.last_modified = None;
+        }
+
+        let mut response = match &etag {
+            Some(etag) if none_match(req, etag) => {
+                let mut response = HttpResponse::new_static(StatusCode::NOT_MODIFIED, b"");
+                if let Some(last_modified) = file.last_modified {
+                    response.set_last_modified(last_modified);
+                }
+                response
+            }
+            _ => file.ranged(req)?,
+        };
+        if let Some(etag) = &etag {
+            response.add_header(ETAG, etag);
+        }
+        if encoded.is_some() {
+            response.add_header(CONTENT_ENCODING, "gzip");
+        }
+        if self.files.precompressed {
+            response.add_vary(ACCEPT_ENCODING);
+        }
+        Ok(response)
+    }
+}
+
+impl HttpHandler for StaticDir {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let relative = req.params.get(&self.param).unwrap_or_default();
+        let Some(path) = self.resolve(relative) else {
+            return HttpResponse::not_found();
+        };
+        if path.is_dir() {
+            return match self.files.directory_listing {
+                true => listing(&path, req.uri.path()),
+                false => HttpResponse::not_found(),
+            };
+        }
+        let encoded = (self.files.precompressed && accepts_gzip(&req.headers))
+            .then(|| self.resolve(&format!("{relative}.gz")))
+            .flatten()
+            .filter(|encoded| encoded.is_file());
+        self.serve(&path, encoded, &req)
+            .unwrap_or_else(|_| HttpResponse::not_found())
+    }
+}
+
+/// Returns `true` if `req` is a `GET` or `HEAD` request whose `If-None-Match`
+/// matches `etag`, with the weak comparison of RFC 9110.
+fn none_match(req: &HttpRequest, etag: &str) -> bool {
+    if req.method != Method::GET && req.method != Method::HEAD {
+        return false;
+    }
+    req.headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// Returns `true` if the `Accept-Encoding` headers accept gzip, by name or
+/// with `*`.
+fn accepts_gzip(headers: &HeaderMap) -> bool {
+    let (mut gzip, mut any) = (None, None);
+    for coding in headers
+        .get_all(ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+    {
+        let mut params = coding.split(';');
+        let name = params.next().unwrap_or_default().trim();
+        let quality = params
+            .filter_map(|param| param.trim().strip_prefix("q="))
+            .find_map(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if name.eq_ignore_ascii_case("gzip") || name.eq_ignore_ascii_case("x-gzip") {
+            gzip = Some(quality);
+        } else if name == "*" {
+            any = Some(quality);
+        }
+    }
+    gzip.or(any).is_some_and(|quality| quality > 0.0)
+}
+
+/// Builds the HTML page listing the entries of the directory `dir`, requested
+/// at `request_path`. Hidden entries, whose names start with `.`, are left
+/// out.
+fn listing(dir: &Path, request_path: &str) -> HttpResponse {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return HttpResponse::not_found();
+    };
+    let mut entries: Vec<(String, bool)> = entries
+        .filter_map(Result::ok)
+        .map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            (name, entry.file_type().is_ok_and(|kind| kind.is_dir()))
+        })
+        .filter(|(name, _)| !name.starts_with('.'))
+        .collect();
+    entries.sort();
+
+    let base = request_path.trim_end_matches('/');
+    let mut title = String::new();
+    Escape::Html.push(&mut title, &format!("Index of {base}/"));
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{title}</title></head>\n<body><h1>{title}</h1>\n<ul>\n"
+    );
+    for (name, is_dir) in entries {
+        let slash = if is_dir { "/" } else { "" };
+        let mut href = format!("{base}/");
+        for byte in name.bytes() {
+            if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+                href.push(byte as char);
+            } else {
+                href.push_str(&format!("%{byte:02X}"));
+            }
+        }
+        html.push_str("<li><a href=\"");
+        Escape::Html.push(&mut html, &href);
+        html.push_str(slash);
+        html.push_str("\">");
+        Escape::Html.push(&mut html, &name);
+        html.push_str(slash);
+        html.push_str("</a></li>\n");
+    }
+    html.push_str("</ul></body></html>\n");
+
+    let mut response = HttpResponse::new(StatusCode::OK, html);
+    response.add_header(CONTENT_TYPE, "text/html; charset=utf-8");
+    response
+}
@@ -0,0 +1,87 @@
+//! Defines `RouteFilter`, which selects the endpoints of a configuration a
+//! router loads, e.g. by their `tags`.
+
+use crate::config::Endpoint;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+
+/// A custom rule of a `RouteFilter`.
+type Predicate = Arc<dyn Fn(&Endpoint) -> bool + Send + Sync>;
+
+/// Selects the endpoints a router loads, for `Router::new_filtered`, so that
+/// binaries sharing a configuration each serve their own routes.
+///
+/// An endpoint is loaded if it has one of the included tags (or any tags, if
+/// none are included), none of the excluded tags, and matches the predicate,
+/// if set. Endpoints left out are ignored entirely: they can't conflict with
+/// other routes, nor appear in the `Allow` header of a 405 Method Not Allowed.
+///
+/// # Examples
+///
+/// ```
+/// use generic_http_router::RouteFilter;
+///
+/// let filter = RouteFilter::new()
+///     .include_tag("admin")
+///     .exclude_tag("deprecated")
+///     .with_predicate(|endpoint| !endpoint.path.starts_with("/internal"));
+/// ```
+#[derive(Clone, Default)]
+pub struct RouteFilter {
+    include: BTreeSet<String>,
+    exclude: BTreeSet<String>,
+    predicate: Option<Predicate>,
+}
+
+impl RouteFilter {
+    /// Creates a filter loading every endpoint.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads only endpoints with `tag`, or another included tag.
+    pub fn include_tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.include.insert(tag.into());
+        self
+    }
+
+    /// Leaves out endpoints with `tag`, even if they have an included tag.
+    pub fn exclude_tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.exclude.insert(tag.into());
+        self
+    }
+
+    /// Loads only endpoints for which `predicate` returns `true`, in addition
+    /// to the tag rules.
+    pub fn with_predicate<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Endpoint) -> bool + Send + Sync + 'static,
+    {
+        self.predicate = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Returns `true` if the filter loads `endpoint`.
+    pub fn matches(&self, endpoint: &Endpoint) -> bool {
+        let included =
+            self.include.is_empty() || endpoint.tags.iter().any(|tag| self.include.contains(tag));
+        let excluded = endpoint.tags.iter().any(|tag| self.exclude.contains(tag));
+        included
+            && !excluded
+            && self
+                .predicate
+                .as_ref()
+                .is_none_or(|predicate| predicate(endpoint))
+    }
+}
+
+impl fmt::Debug for RouteFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RouteFilter")
+            .field("include", &self.include)
+            .field("exclude", &self.exclude)
+            .field("predicate", &self.predicate.is_some())
+            .finish()
+    }
+}
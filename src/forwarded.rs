@@ -0,0 +1,186 @@
+//! Defines the parsing of the headers proxies add to tell what a request was
+//! like before reaching them: `Forwarded` (RFC 7239), and the older
+//! `X-Forwarded-Proto` and `X-Forwarded-Host`.
+//!
+//! Any client can send these headers: only rely on them behind a proxy that
+//! sets or overwrites them.
+
+use crate::context::RequestContext;
+use crate::request::HttpRequest;
+use http::header::{FORWARDED, HOST};
+use http::{HeaderMap, Request, Uri};
+
+/// The `X-Forwarded-Proto` header, the scheme the client used.
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+/// The `X-Forwarded-Host` header, the `Host` the client sent.
+const X_FORWARDED_HOST: &str = "x-forwarded-host";
+
+/// An element of a `Forwarded` header, added by one proxy, e.g.
+/// `for=192.0.2.60;proto=http;by=203.0.113.43`. Quoted values are unquoted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForwardedElement {
+    /// The interface the proxy received the request on (`by`).
+    pub by: Option<String>,
+    /// The client or previous proxy the request came from (`for`), e.g.
+    /// `192.0.2.60` or `[2001:db8::1]:4711`.
+    pub r#for: Option<String>,
+    /// The `Host` header the proxy received (`host`).
+    pub host: Option<String>,
+    /// The scheme the proxy received the request over (`proto`), lowercased.
+    pub proto: Option<String>,
+}
+
+/// Parses the value of a `Forwarded` header into its elements, in order, the
+/// first added by the proxy closest to the client. Parameters other than
+/// `by`, `for`, `host` and `proto` are ignored.
+///
+/// Returns `None` if the value is malformed, e.g. a parameter without a value
+/// or an unterminated quoted string.
+pub fn parse_forwarded(value: &str) -> Option<Vec<ForwardedElement>> {
+    let mut elements = Vec::new();
+    for element in split_unquoted(value, ',')? {
+        let mut parsed = ForwardedElement::default();
+        for pair in split_unquoted(element, ';')? {
+            let pair = pair.trim();
+            if pair.is_empty() {
+                continue;
+            }
+            let (name, value) = pair.split_once('=')?;
+            let value = unquote(value.trim())?;
+            match name.trim().to_ascii_lowercase().as_str() {
+                "by" => parsed.by = Some(value),
+                "for" => parsed.r#for = Some(value),
+                "host" => parsed.host = Some(value),
+                "proto" => parsed.proto = Some(value.to_ascii_lowercase()),
+                _ => {}
+            }
+        }
+        if parsed != ForwardedElement::default() {
+            elements.push(parsed);
+        }
+    }
+    Some(elements)
+}
+
+impl HttpRequest {
+    /// Returns the elements of the `Forwarded` headers, in order, or none if
+    /// any of them is malformed; see [`parse_forwarded`].
+    pub fn forwarded(&self) -> Vec<ForwardedElement> {
+        forwarded_elements(&self.headers)
+    }
+
+    /// Returns the scheme the client used, lowercased, from the first element
+    /// of `Forwarded` with a `proto`, or `X-Forwarded-Proto`, or the request
+    /// itself: its URI's scheme, or whether its
+    /// [`RequestContext`] has TLS details. Returns `None` if nothing tells.
+    pub fn forwarded_proto(&self) -> Option<String> {
+        scheme(&self.headers, &self.uri, self.context())
+    }
+
+    /// Returns the host the client asked for, from the first element of
+    /// `Forwarded` with a `host`, or `X-Forwarded-Host`, or the request's
+    /// `Host` header or URI. It includes the port, if any.
+    pub fn forwarded_host(&self) -> Option<String> {
+        host(&self.headers, &self.uri)
+    }
+}
+
+/// Returns the scheme of a request, as `HttpRequest::forwarded_proto` does.
+pub(crate) fn request_scheme<B>(req: &Request<B>) -> Option<String> {
+    scheme(req.headers(), req.uri(), req.extensions().get())
+}
+
+/// Returns the host of a request, as `HttpRequest::forwarded_host` does.
+pub(crate) fn request_host<B>(req: &Request<B>) -> Option<String> {
+    host(req.headers(), req.uri())
+}
+
+fn forwarded_elements(headers: &HeaderMap) -> Vec<ForwardedElement> {
+    let mut elements = Vec::new();
+    for value in headers.get_all(FORWARDED) {
+        match value.to_str().ok().and_then(parse_forwarded) {
+            Some(parsed) => elements.extend(parsed),
+            None => return Vec::new(),
+        }
+    }
+    elements
+}
+
+fn scheme(headers: &HeaderMap, uri: &Uri, context: Option<&RequestContext>) -> Option<String> {
+    forwarded_elements(headers)
+        .into_iter()
+        .find_map(|element| element.proto)
+        .or_else(|| first_value(headers, X_FORWARDED_PROTO).map(|p| p.to_ascii_lowercase()))
+        .or_else(|| uri.scheme_str().map(str::to_ascii_lowercase))
+        .or_else(|| {
+            context.map(|ctx| {
+                let scheme = if ctx.tls_info.is_some() {
+                    "https"
+                } else {
+                    "http"
+                };
+                scheme.to_string()
+            })
+        })
+}
+
+fn host(headers: &HeaderMap, uri: &Uri) -> Option<String> {
+    forwarded_elements(headers)
+        .into_iter()
+        .find_map(|element| element.host)
+        .or_else(|| first_value(headers, X_FORWARDED_HOST))
+        .or_else(|| first_value(headers, HOST.as_str()))
+        .or_else(|| uri.authority().map(|authority| authority.to_string()))
+        .filter(|host| !host.is_empty())
+}
+
+/// Returns the first comma-separated value of a header, trimmed.
+fn first_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    let value = headers.get(name)?.to_str().ok()?;
+    let first = value.split(',').next()?.trim();
+    (!first.is_empty()).then(|| first.to_string())
+}
+
+/// Splits `value` at each `separator` outside of quoted strings, or returns
+/// `None` if a quoted string isn't terminated.
+fn split_unquoted(value: &str, separator: char) -> Option<Vec<&str>> {
+    let mut parts = Vec::new();
+    let (mut start, mut quoted, mut escaped) = (0, false, false);
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                parts.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if quoted {
+        return None;
+    }
+    parts.push(&value[start..]);
+    Some(parts)
+}
+
+/// Returns a parameter value, unquoting and unescaping a quoted string, or
+/// `None` if it's empty or a token with characters tokens can't have. Colons
+/// are allowed, as some proxies send `for=192.0.2.60:8080` unquoted.
+fn unquote(value: &str) -> Option<String> {
+    if let Some(inner) = value.strip_prefix('"') {
+        let inner = inner.strip_suffix('"')?;
+        let mut unquoted = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            unquoted.push(if c == '\\' { chars.next()? } else { c });
+        }
+        return Some(unquoted);
+    }
+    let is_token = !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~:".contains(&b));
+    is_token.then(|| value.to_string())
+}
@@ -0,0 +1,86 @@
+//! Defines guards, preconditions checked before a route's handler is called,
+//! such as a required header.
+
+use crate::request::HttpRequest;
+use crate::response::HttpResponse;
+use http::StatusCode;
+use http::header::{CONTENT_TYPE, HeaderName};
+
+/// A precondition of the routes listing it in their `guards`, registered with
+/// `Router::register_guard`.
+///
+/// Any `Fn(&HttpRequest) -> Result<(), HttpResponse>` is a guard.
+pub trait Guard {
+    /// Returns `Ok(())` to let the request through, or the response to send
+    /// instead of calling the handler.
+    // The error is the response to send, only built when the check fails.
+    #[allow(clippy::result_large_err)]
+    fn check(&self, req: &HttpRequest) -> Result<(), HttpResponse>;
+}
+
+impl<F: Fn(&HttpRequest) -> Result<(), HttpResponse>> Guard for F {
+    fn check(&self, req: &HttpRequest) -> Result<(), HttpResponse> {
+        self(req)
+    }
+}
+
+/// Rejects requests without a header with a 400 Bad Request.
+#[derive(Debug, Clone)]
+pub struct RequireHeader {
+    name: HeaderName,
+}
+
+impl RequireHeader {
+    /// Creates a guard requiring the header `name`.
+    pub fn new(name: HeaderName) -> Self {
+        Self { name }
+    }
+}
+
+impl Guard for RequireHeader {
+    fn check(&self, req: &HttpRequest) -> Result<(), HttpResponse> {
+        if req.headers.contains_key(&self.name) {
+            Ok(())
+        } else {
+            Err(HttpResponse::bad_request(format!(
+                "Missing required header {}",
+                self.name
+            )))
+        }
+    }
+}
+
+/// Rejects requests whose `Content-Type`, ignoring parameters such as
+/// `charset`, isn't the given media type with a 415 Unsupported Media Type.
+#[derive(Debug, Clone)]
+pub struct RequireContentType {
+    media_type: String,
+}
+
+impl RequireContentType {
+    /// Creates a guard requiring `media_type`, e.g. `application/json`.
+    pub fn new<S: Into<String>>(media_type: S) -> Self {
+        Self {
+            media_type: media_type.into(),
+        }
+    }
+}
+
+impl Guard for RequireContentType {
+    fn check(&self, req: &HttpRequest) -> Result<(), HttpResponse> {
+        let matches = req
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case(&self.media_type));
+        if matches {
+            Ok(())
+        } else {
+            Err(HttpResponse::text(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!("Expected a request body of type {}", self.media_type),
+            ))
+        }
+    }
+}
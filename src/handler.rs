@@ -1,13 +1,117 @@
-//! Defines the `HttpHandler` trait that all route handlers must implement.
+//! Defines the `Handler` trait the router stores every route handler as, and
+//! the `HttpHandler` trait for synchronous handlers, which are all handlers.
 
+use crate::error::RouterError;
 use crate::request::HttpRequest;
 use crate::response::HttpResponse;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A boxed future that can be sent between threads.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// The response of a [`Handler`]: either ready, or a future of it.
+pub enum HandlerFuture<'a> {
+    /// The handler answered synchronously.
+    Ready(HttpResponse),
+    /// The handler answers asynchronously, once the future completes.
+    Pending(BoxFuture<'a, HttpResponse>),
+}
+
+impl HandlerFuture<'_> {
+    /// Returns the response, awaiting it if it's pending.
+    pub async fn response(self) -> HttpResponse {
+        match self {
+            Self::Ready(response) => response,
+            Self::Pending(future) => future.await,
+        }
+    }
+}
+
+impl From<HttpResponse> for HandlerFuture<'_> {
+    fn from(response: HttpResponse) -> Self {
+        Self::Ready(response)
+    }
+}
+
+/// A handler of requests, synchronous or not: the form the router stores every
+/// handler in, registered with `Router::register_handler`.
+///
+/// The trait is object safe, and stays so. Every [`HttpHandler`] is a
+/// `Handler` answering with `HandlerFuture::Ready`; with the `async` feature,
+/// `async_handler::Async` and `async_handler::AsyncFn` adapt an
+/// `AsyncHttpHandler` and an async closure.
+///
+/// Requests routed by `Router::route` or `Router::route_streaming` to a
+/// handler answering with `HandlerFuture::Pending` get a 501 Not Implemented;
+/// `Router::route_async` awaits it.
+///
+/// # Examples
+///
+/// ```
+/// use generic_http_router::{Handler, HandlerFuture, HttpHandler, HttpRequest, HttpResponse};
+///
+/// struct Hello;
+///
+/// impl HttpHandler for Hello {
+///     fn handle(&self, _req: HttpRequest) -> HttpResponse {
+///         HttpResponse::ok("Hello")
+///     }
+/// }
+///
+/// let handlers: Vec<Box<dyn Handler + Send + Sync>> = vec![Box::new(Hello)];
+/// let req = HttpRequest::builder().uri("/").build().unwrap();
+/// match handlers[0].call(req) {
+///     HandlerFuture::Ready(res) => assert_eq!(res.body_as_slice(), Some(&b"Hello"[..])),
+///     HandlerFuture::Pending(_) => unreachable!(),
+/// }
+/// ```
+pub trait Handler {
+    /// Handles an incoming request, returning its response or a future of it.
+    fn call(&self, req: HttpRequest) -> HandlerFuture<'_>;
+}
+
+impl<H: HttpHandler + ?Sized> Handler for H {
+    fn call(&self, req: HttpRequest) -> HandlerFuture<'_> {
+        HandlerFuture::Ready(self.handle(req))
+    }
+}
+
+// `Handler` is part of the stable API as a trait object: this stops
+// compiling if a change makes it not object safe.
+const _: Option<&dyn Handler> = None;
 
 /// A trait for handling HTTP requests.
 ///
 /// Any struct that implements this trait can be registered as a handler
 /// in the `Router`. The `Send` and `Sync` bounds are required to allow
 //  the handler to be shared safely across threads in a concurrent server.
+///
+/// # Examples
+///
+/// A handler can be called directly with a request from [`HttpRequest::builder`]:
+///
+/// ```
+/// use generic_http_router::{HttpHandler, HttpRequest, HttpResponse};
+/// use http::StatusCode;
+///
+/// struct GetUserByIdHandler;
+///
+/// impl HttpHandler for GetUserByIdHandler {
+///     fn handle(&self, req: HttpRequest) -> HttpResponse {
+///         match req.params.get("id") {
+///             Some(id) => HttpResponse::ok(format!("Fetching user with id: {id}")),
+///             None => HttpResponse::bad_request("User ID missing"),
+///         }
+///     }
+/// }
+///
+/// let req = HttpRequest::builder().uri("/users/42").param("id", "42").build().unwrap();
+/// let res = GetUserByIdHandler.handle(req);
+/// assert_eq!(res.status, StatusCode::OK);
+/// assert_eq!(res.body_as_slice(), Some(&b"Fetching user with id: 42"[..]));
+/// ```
 pub trait HttpHandler {
     /// Handles an incoming request and returns a response.
     ///
@@ -21,3 +125,75 @@ pub trait HttpHandler {
     /// An `HttpResponse` to be sent back to the client.
     fn handle(&self, req: HttpRequest) -> HttpResponse;
 }
+
+/// A handler that can be registered with the deprecated `Router::register`: a
+/// `Box` or an `Arc` of an `HttpHandler`, either of a concrete type or already
+/// a trait object. An `Arc` registered under several names is shared, not
+/// copied (see also `Router::register_shared`).
+pub trait IntoHandler {
+    /// Converts the handler into a reference-counted one.
+    fn into_handler(self) -> Arc<dyn HttpHandler + Send + Sync>;
+}
+
+impl<H: HttpHandler + Send + Sync + 'static> IntoHandler for Box<H> {
+    fn into_handler(self) -> Arc<dyn HttpHandler + Send + Sync> {
+        Arc::<H>::from(self)
+    }
+}
+
+impl IntoHandler for Box<dyn HttpHandler + Send + Sync> {
+    fn into_handler(self) -> Arc<dyn HttpHandler + Send + Sync> {
+        Arc::from(self)
+    }
+}
+
+impl<H: HttpHandler + Send + Sync + 'static> IntoHandler for Arc<H> {
+    fn into_handler(self) -> Arc<dyn HttpHandler + Send + Sync> {
+        self
+    }
+}
+
+impl IntoHandler for Arc<dyn HttpHandler + Send + Sync> {
+    fn into_handler(self) -> Arc<dyn HttpHandler + Send + Sync> {
+        self
+    }
+}
+
+/// Builds the handler of a route from the `args` of its controller, e.g. a
+/// static JSON responder from the file to serve. Registered with
+/// `Router::register_factory`.
+pub type HandlerFactory = dyn Fn(&serde_json::Value) -> Result<Box<dyn HttpHandler + Send + Sync>, RouterError>
+    + Send
+    + Sync;
+
+/// What the router does with requests to a route whose controller has no
+/// registered handler. Set with `Router::set_missing_handler_policy`.
+///
+/// Whichever is chosen, the controller name isn't sent to the client: it's
+/// logged (with the `logging` feature) and passed to the `on_response` hook as
+/// `RequestSummary::detail`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissingHandlerPolicy {
+    /// Answer with a 501 Not Implemented.
+    #[default]
+    NotImplemented,
+    /// Answer with a 404 Not Found, as if the route didn't exist.
+    NotFound,
+    /// Call the handler set with `Router::set_fallback_handler`, or answer with
+    /// a 501 Not Implemented if there's none.
+    Fallback,
+}
+
+/// A shared handler handles requests with the handler it points to.
+impl<H: HttpHandler + ?Sized> HttpHandler for Arc<H> {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        (**self).handle(req)
+    }
+}
+
+/// A boxed handler handles requests with the handler it holds.
+impl<H: HttpHandler + ?Sized> HttpHandler for Box<H> {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        (**self).handle(req)
+    }
+}
@@ -0,0 +1,125 @@
+//! Defines the built-in liveness and readiness endpoints enabled with
+//! `Router::enable_health_endpoints`.
+
+use crate::Router;
+use crate::error::LoadWarning;
+use crate::response::{HttpResponse, IntoHttpResponse};
+use http::{Method, StatusCode};
+use serde_json::json;
+use std::sync::Arc;
+
+/// The paths of the built-in health endpoints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthConfig {
+    /// Answered with 200 OK and the body `ok`, as long as the process can serve
+    /// requests. Defaults to `/healthz`.
+    pub liveness_path: String,
+    /// Answered with 200 OK if every `ReadinessCheck` passes, and 503 Service
+    /// Unavailable otherwise, or once the router is shutting down. Defaults to
+    /// `/readyz`.
+    pub readiness_path: String,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            liveness_path: "/healthz".to_string(),
+            readiness_path: "/readyz".to_string(),
+        }
+    }
+}
+
+/// A check the readiness endpoint runs, e.g. whether the database is reachable.
+///
+/// Register implementations with `Router::add_readiness_check`. Closures
+/// returning `Result<(), String>` implement it too.
+pub trait ReadinessCheck {
+    /// Returns `Ok(())` if the service is ready as far as this check is
+    /// concerned, or the reason it isn't.
+    fn ready(&self) -> Result<(), String>;
+}
+
+impl<F: Fn() -> Result<(), String>> ReadinessCheck for F {
+    fn ready(&self) -> Result<(), String> {
+        self()
+    }
+}
+
+/// A named readiness check.
+pub(crate) type NamedCheck = (String, Arc<dyn ReadinessCheck + Send + Sync>);
+
+/// Builds the response of the readiness endpoint by running every check, e.g.
+/// `{"status":"unavailable","checks":[{"name":"db","status":"error","error":"timeout"}]}`.
+/// While the router is shutting down, the status is `shutting_down`.
+pub(crate) fn readiness_response(checks: &[NamedCheck], shutting_down: bool) -> HttpResponse {
+    let mut ready = true;
+    let results: Vec<_> = checks
+        .iter()
+        .map(|(name, check)| match check.ready() {
+            Ok(()) => json!({ "name": name, "status": "ok" }),
+            Err(error) => {
+                ready = false;
+                json!({ "name": name, "status": "error", "error": error })
+            }
+        })
+        .collect();
+
+    let (status, label) = if shutting_down {
+        (StatusCode::SERVICE_UNAVAILABLE, "shutting_down")
+    } else if ready {
+        (StatusCode::OK, "ok")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    };
+    let mut response = json!({ "status": label, "checks": results }).into_http_response();
+    response.status = status;
+    response
+}
+
+impl Router {
+    /// Answers `GET` and `HEAD` requests to the liveness and readiness paths of
+    /// `config` with built-in handlers, which also bypass maintenance mode.
+    ///
+    /// Configured routes take precedence: the built-in endpoints only answer
+    /// paths that don't match any route, and a warning is returned for each one
+    /// that's shadowed.
+    pub fn enable_health_endpoints(&mut self, config: HealthConfig) -> Vec<LoadWarning> {
+        self.health = Some(config);
+        self.builtin_warnings()
+    }
+
+    /// Adds a check run by the readiness endpoint, reported under `name`. The
+    /// endpoint answers 503 Service Unavailable if any check fails.
+    pub fn add_readiness_check<S: Into<String>>(
+        &mut self,
+        name: S,
+        check: Box<dyn ReadinessCheck + Send + Sync>,
+    ) {
+        self.readiness_checks.push((name.into(), Arc::from(check)));
+    }
+
+    /// Returns `true` if `path` is one of the built-in health endpoints.
+    pub(crate) fn is_health_path(&self, path: &str) -> bool {
+        self.health
+            .as_ref()
+            .is_some_and(|health| path == health.liveness_path || path == health.readiness_path)
+    }
+
+    /// Answers a request to a built-in health endpoint, if it is one.
+    pub(crate) fn health_response(&self, method: &Method, path: &str) -> Option<HttpResponse> {
+        let health = self.health.as_ref()?;
+        if method != Method::GET && method != Method::HEAD {
+            return None;
+        }
+        if path == health.liveness_path {
+            Some(HttpResponse::text(StatusCode::OK, "ok"))
+        } else if path == health.readiness_path {
+            Some(readiness_response(
+                &self.readiness_checks,
+                self.shutdown.is_shutting_down(),
+            ))
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Defines the types passed to the router's `on_response` and
+//! `on_mirror_result` hooks, and its response transformers.
+
+use crate::request::HttpRequest;
+use crate::response::HttpResponse;
+use crate::response_limit::OversizedResponse;
+use http::{Extensions, Method, StatusCode, Version};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A summary of a routed request, passed to the hook set with
+/// `Router::on_response`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestSummary {
+    pub method: Method,
+    /// The request path as received, before normalization and rewriting.
+    pub path: String,
+    /// The status of the response sent.
+    pub status: StatusCode,
+    /// The time spent routing the request and producing the response.
+    pub elapsed: Duration,
+    pub outcome: Outcome,
+    /// Details the response doesn't carry, e.g. the name of the controller when
+    /// the route has no registered handler, or the limit a response replaced
+    /// for being too large was over.
+    pub detail: Option<String>,
+    /// The path pattern of the matched route, as written in the configuration,
+    /// e.g. `/users/:id`, or `None` if no route matched.
+    pub pattern: Option<String>,
+    /// The size of the response body, or `None` if it was streamed.
+    pub bytes: Option<u64>,
+    pub version: Version,
+    /// The `X-Request-Id` request header.
+    pub request_id: Option<String>,
+    /// The IP address of the client, from the request's `RequestContext`.
+    pub client_ip: Option<IpAddr>,
+    /// The `Referer` request header.
+    pub referer: Option<String>,
+    /// The `User-Agent` request header.
+    pub user_agent: Option<String>,
+    /// The route patterns suggested by a 404 Not Found response, with
+    /// `Router::enable_404_suggestions`.
+    pub suggestions: Vec<String>,
+}
+
+/// How the handling of a request ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Outcome {
+    /// The router or the handler produced a response.
+    Completed,
+    /// The handler exceeded its timeout, and the request got a 504 Gateway Timeout.
+    TimedOut,
+}
+
+/// Recorded in the extensions of a response to a route without a registered
+/// handler for `RequestSummary::detail`, and removed before the response is
+/// returned.
+pub(crate) struct MissingHandler(pub(crate) String);
+
+impl MissingHandler {
+    /// Returns the `RequestSummary::detail` of the response.
+    pub(crate) fn detail(&self) -> String {
+        format!("No handler is registered for controller '{}'.", self.0)
+    }
+}
+
+/// Recorded in the extensions of a response to a request matching a route for
+/// `RequestSummary::pattern`, and removed before the response is returned.
+pub(crate) struct MatchedRoute(pub(crate) Arc<str>);
+
+/// Recorded in the extensions of a 404 Not Found response with suggestions for
+/// `RequestSummary::suggestions`, and removed before the response is returned.
+pub(crate) struct Suggestions(pub(crate) Vec<String>);
+
+/// What a `RequestSummary` needs to know about a request, recorded before
+/// it's routed.
+pub(crate) struct Started {
+    pub(crate) at: Instant,
+    pub(crate) method: Method,
+    pub(crate) path: String,
+    pub(crate) version: Version,
+    pub(crate) request_id: Option<String>,
+    pub(crate) client_ip: Option<IpAddr>,
+    pub(crate) referer: Option<String>,
+    pub(crate) user_agent: Option<String>,
+}
+
+impl Started {
+    /// Returns the summary of the request, with its response so far, taking
+    /// the router's details from the response's `extensions`.
+    pub(crate) fn summary(
+        &self,
+        status: StatusCode,
+        elapsed: Duration,
+        outcome: Outcome,
+        extensions: &Extensions,
+        bytes: Option<u64>,
+    ) -> RequestSummary {
+        RequestSummary {
+            method: self.method.clone(),
+            path: self.path.clone(),
+            status,
+            elapsed,
+            outcome,
+            detail: extensions
+                .get::<MissingHandler>()
+                .map(MissingHandler::detail)
+                .or_else(|| {
+                    extensions
+                        .get::<OversizedResponse>()
+                        .map(|oversized| oversized.0.clone())
+                }),
+            pattern: extensions
+                .get::<MatchedRoute>()
+                .map(|route| route.0.to_string()),
+            bytes,
+            version: self.version,
+            request_id: self.request_id.clone(),
+            client_ip: self.client_ip,
+            referer: self.referer.clone(),
+            user_agent: self.user_agent.clone(),
+            suggestions: extensions
+                .get::<Suggestions>()
+                .map(|suggestions| suggestions.0.clone())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Changes the responses of the router, added with
+/// `Router::add_response_transformer`, e.g. to add a header to every one.
+///
+/// Transformers get every response: the handlers' and the router's own, such
+/// as its 404 Not Found, once the middleware and the `error_pages` templates
+/// are done with it. Its summary tells the request's method, path and matched
+/// route, and the elapsed time so far.
+///
+/// Any `Fn(&RequestSummary, HttpResponse) -> HttpResponse` is a transformer.
+pub trait ResponseTransformer {
+    fn transform(&self, summary: &RequestSummary, response: HttpResponse) -> HttpResponse;
+}
+
+impl<F: Fn(&RequestSummary, HttpResponse) -> HttpResponse> ResponseTransformer for F {
+    fn transform(&self, summary: &RequestSummary, response: HttpResponse) -> HttpResponse {
+        self(summary, response)
+    }
+}
+
+/// The hook set with `Router::on_response`.
+pub(crate) type ResponseHook = Arc<dyn Fn(&RequestSummary) + Send + Sync>;
+
+/// The hook set with `Router::on_mirror_result`, called with the request, the
+/// primary response and the mirror response.
+pub(crate) type MirrorHook = Arc<dyn Fn(&HttpRequest, &HttpResponse, &HttpResponse) + Send + Sync>;
@@ -0,0 +1,341 @@
+//! Defines idempotency keys: a retry of a request to an `idempotent` route
+//! with the same `Idempotency-Key` header gets the response to the first
+//! request, recorded in an `IdempotencyStore`, instead of running the handler
+//! again.
+
+use crate::clock::Clock;
+use crate::crypto;
+use crate::request::HttpRequest;
+use crate::response::HttpResponse;
+use bytes::Bytes;
+use http::header::AUTHORIZATION;
+use http::{HeaderMap, HeaderValue, StatusCode};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime};
+
+/// The request header carrying the idempotency key.
+const IDEMPOTENCY_KEY: &str = "idempotency-key";
+/// The response header marking a replayed response.
+const IDEMPOTENT_REPLAYED: &str = "idempotent-replayed";
+/// The longest idempotency key accepted, in bytes.
+const MAX_KEY_LEN: usize = 255;
+/// How often a request waiting for an in-flight duplicate checks the store.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// The longest records are kept, whatever the TTL: a hundred years.
+const MAX_TTL: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+
+/// A response recorded for an idempotency key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+/// What an `IdempotencyStore` keeps for an idempotency key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdempotencyRecord {
+    /// The hex-encoded SHA-256 of the first request's body.
+    pub fingerprint: String,
+    /// The response to the first request, or `None` while it's in flight.
+    pub response: Option<StoredResponse>,
+}
+
+/// Where the responses to requests with an idempotency key are kept, by key.
+/// The keys passed in combine the request's method, path, caller and
+/// `Idempotency-Key` header.
+///
+/// Errors are reported as messages, and get the request a 500 Internal Server
+/// Error.
+pub trait IdempotencyStore {
+    /// Records `key` as in flight for a request with `fingerprint`, until
+    /// `expires`, unless a record for it that hasn't expired at `now` exists.
+    /// Returns that record, or `None` if the key was claimed. This must be
+    /// atomic, so that only one of concurrent duplicates claims the key.
+    fn claim(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        now: SystemTime,
+        expires: SystemTime,
+    ) -> Result<Option<IdempotencyRecord>, String>;
+
+    /// Records the response to the request that claimed `key`, to be kept
+    /// until `expires`.
+    fn complete(
+        &self,
+        key: &str,
+        response: &StoredResponse,
+        expires: SystemTime,
+    ) -> Result<(), String>;
+
+    /// Deletes the record of `key`, so that the next request with it runs
+    /// the handler.
+    fn release(&self, key: &str) -> Result<(), String>;
+}
+
+/// An `IdempotencyStore` keeping records in memory, e.g. for tests or a single
+/// server instance. Expired records are dropped as keys are claimed.
+#[derive(Default)]
+pub struct MemoryStore {
+    records: Mutex<HashMap<String, (IdempotencyRecord, SystemTime)>>,
+    claims: AtomicU64,
+}
+
+impl MemoryStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, (IdempotencyRecord, SystemTime)>> {
+        self.records.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl IdempotencyStore for MemoryStore {
+    fn claim(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        now: SystemTime,
+        expires: SystemTime,
+    ) -> Result<Option<IdempotencyRecord>, String> {
+        let mut records = self.lock();
+        // Sweeping now and then keeps keys that are never retried from
+        // piling up.
+        if self.claims.fetch_add(1, Ordering::Relaxed) % 1024 == 1023 {
+            records.retain(|_, (_, expires)| *expires > now);
+        }
+        if let Some((record, record_expires)) = records.get(key)
+            && *record_expires > now
+        {
+            return Ok(Some(record.clone()));
+        }
+        let record = IdempotencyRecord {
+            fingerprint: fingerprint.to_string(),
+            response: None,
+        };
+        records.insert(key.to_string(), (record, expires));
+        Ok(None)
+    }
+
+    fn complete(
+        &self,
+        key: &str,
+        response: &StoredResponse,
+        expires: SystemTime,
+    ) -> Result<(), String> {
+        if let Some((record, record_expires)) = self.lock().get_mut(key) {
+            record.response = Some(response.clone());
+            *record_expires = expires;
+        }
+        Ok(())
+    }
+
+    fn release(&self, key: &str) -> Result<(), String> {
+        self.lock().remove(key);
+        Ok(())
+    }
+}
+
+/// What a request with an idempotency key gets while the first request with
+/// it is still in flight.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InFlightPolicy {
+    /// A 409 Conflict, telling the client to retry later.
+    #[default]
+    Conflict,
+    /// The response to the first request, once it's recorded, or a 409
+    /// Conflict if that takes longer than the duration. Waiting blocks the
+    /// thread routing the request, including in `Router::route_async`.
+    Wait(Duration),
+}
+
+/// Deduplicates requests to `idempotent` routes by their `Idempotency-Key`
+/// header, enabled with `Router::use_idempotency`.
+///
+/// The first request with a key runs the handler, and its response is
+/// recorded; retries with the same key, method, path and body from the same
+/// caller get that response again, with an `Idempotent-Replayed: true` header. A retry with
+/// another body gets a 422 Unprocessable Entity. Responses with a 5xx status,
+/// and streamed ones, aren't recorded, so a retry runs the handler again.
+/// Requests without the header are handled normally, unless the key is
+/// required.
+pub struct IdempotencyMiddleware {
+    store: Arc<dyn IdempotencyStore + Send + Sync>,
+    ttl: Duration,
+    in_flight: InFlightPolicy,
+    require_key: bool,
+}
+
+/// Why the middleware turned a request away.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Rejection {
+    /// The key is required but missing, or it's empty or too long.
+    InvalidKey,
+    /// The key was used for a request with another body.
+    Mismatch,
+    /// The first request with the key is still in flight.
+    InFlight,
+    /// The store failed.
+    Store(String),
+}
+
+/// What to do with a request, decided by `IdempotencyMiddleware::begin`.
+pub(crate) enum Begin {
+    /// Run the handler, recording its response with the claim, if any.
+    Run(Option<Claim>),
+    /// Send the recorded response instead.
+    Replay(HttpResponse),
+    Reject(Rejection),
+}
+
+/// The claim of a request on its idempotency key. Dropping it without
+/// `finish`, e.g. because the request was rejected or its handler panicked,
+/// releases the key.
+pub(crate) struct Claim {
+    store: Arc<dyn IdempotencyStore + Send + Sync>,
+    key: String,
+    ttl: Duration,
+    finished: bool,
+}
+
+impl IdempotencyMiddleware {
+    /// Creates the middleware, keeping records in `store` for 24 hours, and
+    /// answering duplicates of in-flight requests with a 409 Conflict.
+    pub fn new(store: Box<dyn IdempotencyStore + Send + Sync>) -> Self {
+        Self {
+            store: Arc::from(store),
+            ttl: Duration::from_secs(24 * 60 * 60),
+            in_flight: InFlightPolicy::default(),
+            require_key: false,
+        }
+    }
+
+    /// Sets how long responses are replayed for, and how long a request that
+    /// never finished keeps its key, at most a hundred years.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets what duplicates of in-flight requests get.
+    pub fn on_in_flight(mut self, policy: InFlightPolicy) -> Self {
+        self.in_flight = policy;
+        self
+    }
+
+    /// Sets whether requests to `idempotent` routes must have an
+    /// `Idempotency-Key` header (not by default); those without one get a 400
+    /// Bad Request.
+    pub fn require_key(mut self, required: bool) -> Self {
+        self.require_key = required;
+        self
+    }
+
+    /// Claims the idempotency key of a request, or returns its recorded
+    /// response or why it's rejected.
+    pub(crate) fn begin(&self, req: &HttpRequest, clock: &(dyn Clock + Send + Sync)) -> Begin {
+        let header = match req.headers.get(IDEMPOTENCY_KEY) {
+            Some(value) => value.to_str().ok(),
+            None if self.require_key => None,
+            None => return Begin::Run(None),
+        };
+        let Some(header) = header.filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LEN) else {
+            return Begin::Reject(Rejection::InvalidKey);
+        };
+        let key = format!("{} {} {} {header}", req.method, req.uri.path(), caller(req));
+        let fingerprint = crypto::hex(&crypto::sha256(&req.body));
+
+        // Waiting takes real time whatever the clock tells, so it is timed
+        // with `Instant` alone.
+        let started = Instant::now();
+        loop {
+            let now = clock.system_time();
+            let record = match self
+                .store
+                .claim(&key, &fingerprint, now, expiry(now, self.ttl))
+            {
+                Ok(None) => {
+                    return Begin::Run(Some(Claim {
+                        store: Arc::clone(&self.store),
+                        key,
+                        ttl: self.ttl,
+                        finished: false,
+                    }));
+                }
+                Ok(Some(record)) => record,
+                Err(error) => return Begin::Reject(Rejection::Store(error)),
+            };
+            if !crypto::constant_time_eq(record.fingerprint.as_bytes(), fingerprint.as_bytes()) {
+                return Begin::Reject(Rejection::Mismatch);
+            }
+            if let Some(stored) = record.response {
+                let mut response =
+                    HttpResponse::with_headers(stored.status, stored.headers, stored.body);
+                response
+                    .headers
+                    .insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+                return Begin::Replay(response);
+            }
+            match self.in_flight {
+                InFlightPolicy::Wait(timeout) if started.elapsed() < timeout => {
+                    std::thread::sleep(POLL_INTERVAL);
+                }
+                _ => return Begin::Reject(Rejection::InFlight),
+            }
+        }
+    }
+}
+
+/// Returns when a record made at `now` and kept for `ttl` expires. The TTL is
+/// capped at `MAX_TTL`, so that adding it can't overflow; a clock too close to
+/// the end of representable time for that keeps records no time at all.
+fn expiry(now: SystemTime, ttl: Duration) -> SystemTime {
+    now.checked_add(ttl.min(MAX_TTL)).unwrap_or(now)
+}
+
+/// Identifies the caller of a request, so that callers can't replay each
+/// other's responses by reusing a key: the hash of its identity's subject, or
+/// else of its `Authorization` header, or `-` for anonymous requests.
+fn caller(req: &HttpRequest) -> String {
+    if let Some(subject) = req.identity().and_then(|identity| identity.subject()) {
+        return format!("sub:{}", crypto::hex(&crypto::sha256(subject.as_bytes())));
+    }
+    match req.headers.get(AUTHORIZATION) {
+        Some(value) => format!("auth:{}", crypto::hex(&crypto::sha256(value.as_bytes()))),
+        None => "-".to_string(),
+    }
+}
+
+impl Claim {
+    /// Records `response` for the key, unless it has a 5xx status or a
+    /// streamed body, in which case the key is released. Returns the error of
+    /// the store, if any.
+    pub(crate) fn finish(mut self, response: &HttpResponse, now: SystemTime) -> Result<(), String> {
+        self.finished = true;
+        match response.body.as_bytes() {
+            Some(body) if !response.status.is_server_error() => {
+                let stored = StoredResponse {
+                    status: response.status,
+                    headers: response.headers.clone(),
+                    body: body.clone(),
+                };
+                self.store
+                    .complete(&self.key, &stored, expiry(now, self.ttl))
+            }
+            _ => self.store.release(&self.key),
+        }
+    }
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.store.release(&self.key);
+        }
+    }
+}
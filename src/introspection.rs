@@ -0,0 +1,128 @@
+//! Defines the built-in endpoint listing the routes, enabled with
+//! `Router::enable_introspection`.
+
+use crate::Router;
+use crate::config::{Auth, Endpoint};
+use crate::error::LoadWarning;
+use crate::params::Params;
+use crate::request::HttpRequest;
+use crate::response::{HttpResponse, IntoHttpResponse};
+use bytes::Bytes;
+use http::{Method, Request};
+use serde::Serialize;
+
+/// The version of the endpoint's format, in its `version` field. It changes
+/// only when a field is removed or changes meaning.
+pub const INTROSPECTION_VERSION: u32 = 1;
+
+/// The settings of the built-in route listing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntrospectionConfig {
+    /// The path answered with the routes. Defaults to `/_routes`.
+    pub path: String,
+    /// The routes with any of these tags are left out. Defaults to
+    /// `["internal"]`.
+    pub excluded_tags: Vec<String>,
+    /// The guard a request must pass to get the routes, by the name it's
+    /// registered under with `Router::register_guard`, e.g. one checking an
+    /// admin token. It sees the request's method, URI and headers, but not
+    /// its body or extensions. None by default.
+    pub guard: Option<String>,
+}
+
+impl Default for IntrospectionConfig {
+    fn default() -> Self {
+        Self {
+            path: "/_routes".to_string(),
+            excluded_tags: vec!["internal".to_string()],
+            guard: None,
+        }
+    }
+}
+
+/// A route, as listed by the endpoint.
+#[derive(Serialize)]
+struct RouteInfo<'a> {
+    method: &'a str,
+    path: &'a str,
+    description: &'a str,
+    /// Whether the route has the `deprecated` tag.
+    deprecated: bool,
+    auth: Option<Auth>,
+    auth_scopes: Option<&'a [String]>,
+    tags: &'a [String],
+}
+
+/// Builds the response listing `endpoints`, sorted by path and method, e.g.
+/// `{"version":1,"routes":[{"method":"GET","path":"/users/:id",...}]}`.
+pub(crate) fn routes_response<'a>(
+    endpoints: impl Iterator<Item = &'a Endpoint>,
+    config: &IntrospectionConfig,
+) -> HttpResponse {
+    let mut routes: Vec<RouteInfo<'_>> = endpoints
+        .filter(|endpoint| {
+            !endpoint
+                .tags
+                .iter()
+                .any(|tag| config.excluded_tags.contains(tag))
+        })
+        .map(|endpoint| RouteInfo {
+            method: endpoint.method.as_str(),
+            path: &endpoint.path,
+            description: &endpoint.description,
+            deprecated: endpoint.tags.iter().any(|tag| tag == "deprecated"),
+            auth: endpoint.auth,
+            auth_scopes: endpoint.auth_scopes.as_deref(),
+            tags: &endpoint.tags,
+        })
+        .collect();
+    routes.sort_by(|a, b| (a.path, a.method).cmp(&(b.path, b.method)));
+    serde_json::json!({ "version": INTROSPECTION_VERSION, "routes": routes }).into_http_response()
+}
+
+impl Router {
+    /// Answers `GET` and `HEAD` requests to `config.path` with the routing
+    /// table as JSON, e.g. for a developer portal or to check a deployment:
+    /// each route's method, path pattern, description, authentication, scopes
+    /// and tags, and whether it's deprecated, i.e. has the `deprecated` tag.
+    /// The format is versioned by [`INTROSPECTION_VERSION`].
+    ///
+    /// Routes with one of `config.excluded_tags` are left out. If
+    /// `config.guard` is set, requests must pass that registered guard, and
+    /// get its response otherwise. Like the health endpoints, the listing
+    /// bypasses maintenance mode and only answers a path that doesn't match
+    /// any route; a warning is returned if it's shadowed.
+    pub fn enable_introspection(&mut self, config: IntrospectionConfig) -> Vec<LoadWarning> {
+        self.introspection = Some(config);
+        self.builtin_warnings()
+    }
+
+    /// Answers a request to the built-in route listing, if it is one.
+    pub(crate) fn introspection_response<B>(
+        &self,
+        req: &Request<B>,
+        path: &str,
+    ) -> Option<HttpResponse> {
+        let config = self.introspection.as_ref()?;
+        if path != config.path || (req.method() != Method::GET && req.method() != Method::HEAD) {
+            return None;
+        }
+        if let Some(guard) = &config.guard {
+            let guard_req = HttpRequest {
+                uri: req.uri().clone(),
+                original_uri: req.uri().clone(),
+                method: req.method().clone(),
+                headers: req.headers().clone(),
+                params: Params::default(),
+                body: Bytes::new(),
+                extensions: http::Extensions::new(),
+                deadline: None,
+                parsed: Default::default(),
+            };
+            if let Some(response) = self.check_guard(guard, &guard_req) {
+                return Some(response);
+            }
+        }
+        Some(routes_response(self.routes.table.endpoints(), config))
+    }
+}
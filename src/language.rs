@@ -0,0 +1,161 @@
+//! Defines `Accept-Language` parsing and language negotiation, and the locale
+//! of localized routes.
+
+use crate::request::HttpRequest;
+use http::header::ACCEPT_LANGUAGE;
+
+/// A language range of an `Accept-Language` header, e.g. `en-GB;q=0.8`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageTag {
+    /// The tag as sent, e.g. `en-GB`, or `*` for any language.
+    pub tag: String,
+    /// The quality value, from 0 to 1 (1 if not specified). A range with a
+    /// quality of 0 marks the language as not acceptable.
+    pub quality: f32,
+}
+
+/// The locale of the localized route a request matched, e.g. `de` for the
+/// `/preise` path of `"path": { "en": "/pricing", "de": "/preise" }`, in its
+/// extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteLocale(pub String);
+
+impl LanguageTag {
+    fn is_wildcard(&self) -> bool {
+        self.tag == "*"
+    }
+
+    /// Returns `true` if the range matches `tag`: it's the same tag or a prefix
+    /// of it ending at a subtag, ignoring case, e.g. `en` matches `en-GB`.
+    fn matches(&self, tag: &str) -> bool {
+        self.is_wildcard()
+            || tag.eq_ignore_ascii_case(&self.tag)
+            || (tag.len() > self.tag.len()
+                && tag.as_bytes()[self.tag.len()] == b'-'
+                && tag[..self.tag.len()].eq_ignore_ascii_case(&self.tag))
+    }
+}
+
+impl HttpRequest {
+    /// Returns the locale of the localized route the request matched, or
+    /// `None` if its path isn't localized.
+    pub fn locale(&self) -> Option<&str> {
+        self.extensions
+            .get::<RouteLocale>()
+            .map(|locale| locale.0.as_str())
+    }
+
+    /// Returns the language ranges of the `Accept-Language` headers, sorted by
+    /// decreasing quality; ranges with the same quality keep the order they were
+    /// sent in. Malformed ranges are skipped.
+    pub fn accept_languages(&self) -> Vec<LanguageTag> {
+        let mut languages: Vec<LanguageTag> = self
+            .headers
+            .get_all(ACCEPT_LANGUAGE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(parse_range)
+            .collect();
+        // A stable sort, so that ties stay in header order.
+        languages.sort_by(|a, b| b.quality.total_cmp(&a.quality));
+        languages
+    }
+
+    /// Picks the language of `supported` to respond in, following the lookup
+    /// scheme of RFC 4647: for each range of `Accept-Language`, by decreasing
+    /// quality, the first supported tag equal to the range is picked, falling
+    /// back to the range with its last subtag removed until only the primary
+    /// subtag is left, so `en-GB` picks `en` if `en-GB` isn't supported. `*`
+    /// picks the first supported language. Languages sent with `q=0` are never
+    /// picked.
+    ///
+    /// Returns `None` if no supported language is acceptable, or if the request
+    /// has no `Accept-Language` header; respond in a default language then.
+    /// Either way, add `Accept-Language` to the response's `Vary` header with
+    /// [`HttpResponse::add_vary`](crate::HttpResponse::add_vary), so that shared
+    /// caches keep one response per language.
+    pub fn negotiate_language<'a>(&self, supported: &[&'a str]) -> Option<&'a str> {
+        let languages = self.accept_languages();
+        let (acceptable, excluded): (Vec<_>, Vec<_>) = languages
+            .iter()
+            .partition(|language| language.quality > 0.0);
+        let allowed = |tag: &&str| !excluded.iter().any(|language| language.matches(tag));
+
+        for language in acceptable {
+            if language.is_wildcard() {
+                if let Some(tag) = supported.iter().copied().find(allowed) {
+                    return Some(tag);
+                }
+                continue;
+            }
+            let mut range = language.tag.as_str();
+            loop {
+                if let Some(tag) = supported
+                    .iter()
+                    .copied()
+                    .filter(allowed)
+                    .find(|tag| tag.eq_ignore_ascii_case(range))
+                {
+                    return Some(tag);
+                }
+                match range.rsplit_once('-') {
+                    Some((prefix, _)) => range = prefix,
+                    None => break,
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Parses one comma-separated element of `Accept-Language`, e.g. `en-GB;q=0.8`.
+fn parse_range(element: &str) -> Option<LanguageTag> {
+    let mut parts = element.split(';');
+    let tag = parts.next()?.trim();
+    if !is_language_range(tag) {
+        return None;
+    }
+    let mut quality = 1.0;
+    for param in parts {
+        let (name, value) = param.split_once('=')?;
+        if name.trim().eq_ignore_ascii_case("q") {
+            quality = parse_quality(value.trim())?;
+        }
+    }
+    Some(LanguageTag {
+        tag: tag.to_string(),
+        quality,
+    })
+}
+
+/// Returns `true` if `tag` is `*` or subtags of 1 to 8 letters or digits
+/// separated by `-`, the first made of letters only.
+fn is_language_range(tag: &str) -> bool {
+    if tag == "*" {
+        return true;
+    }
+    tag.split('-').enumerate().all(|(i, subtag)| {
+        (1..=8).contains(&subtag.len())
+            && subtag.bytes().all(|b| {
+                if i == 0 {
+                    b.is_ascii_alphabetic()
+                } else {
+                    b.is_ascii_alphanumeric()
+                }
+            })
+    })
+}
+
+/// Parses a quality value, `0` to `1` with up to three decimals.
+pub(crate) fn parse_quality(value: &str) -> Option<f32> {
+    let (whole, decimals) = value.split_once('.').unwrap_or((value, ""));
+    if !matches!(whole, "0" | "1")
+        || decimals.len() > 3
+        || !decimals.bytes().all(|b| b.is_ascii_digit())
+        || (whole == "1" && decimals.bytes().any(|b| b != b'0'))
+    {
+        return None;
+    }
+    value.parse().ok()
+}
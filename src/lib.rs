@@ -4,41 +4,536 @@
 //! from a JSON file. This library allows you to decouple your routing logic
 //! from your application code.
 
-use http::{Method, Request, Response, StatusCode};
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::BufReader;
+use bytes::Bytes;
+use http::{HeaderMap, HeaderValue, Method, Request, Response, StatusCode};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 // Publicly export modules and key types for easy access by library users.
+pub mod accept;
+pub mod access_log;
+#[cfg(feature = "async")]
+pub mod async_handler;
+pub mod auth;
+pub mod batch;
+pub mod body;
+#[cfg(feature = "cache")]
+mod cache;
+mod canary;
+pub mod canonical;
+mod charset;
+pub mod circuit;
+pub mod clock;
+#[cfg(feature = "async")]
+pub mod coalesce;
+pub mod compiled;
+pub mod concurrency;
+#[cfg(feature = "files")]
+pub mod conditional;
 pub mod config;
+mod config_schema;
+pub mod context;
+mod crypto;
+#[cfg(feature = "csrf")]
+pub mod csrf;
+pub mod deadline;
 pub mod error;
+mod error_page;
+pub mod export;
+#[cfg(feature = "extractors")]
+pub mod extract;
+#[cfg(feature = "files")]
+pub mod file;
+pub mod filter;
+pub mod forwarded;
+pub mod guard;
 pub mod handler;
+pub mod health;
+pub mod hooks;
+#[cfg(feature = "idempotency")]
+pub mod idempotency;
+pub mod introspection;
+#[cfg(feature = "jwt")]
+pub mod jwt;
+pub mod language;
+pub mod limits;
+pub mod maintenance;
+pub mod method;
+pub mod params;
+mod parsed;
+pub mod path;
+pub mod postman;
+pub mod problem;
+#[cfg(feature = "jwt-public-key")]
+mod public_key;
+pub mod range;
+#[cfg(feature = "record")]
+pub mod record;
+pub mod redact;
 pub mod request;
+pub mod resolution;
 pub mod response;
+mod response_limit;
+pub mod rewrite;
+pub mod rng;
+pub mod route_config;
+#[cfg(feature = "schema")]
+pub mod schema;
+#[cfg(feature = "session")]
+pub mod session;
+#[cfg(feature = "shared")]
+pub mod shared;
+pub mod shutdown;
+pub mod signature;
+pub mod sse;
+pub mod suggestions;
+mod table;
+mod template;
+#[cfg(feature = "shared")]
+pub mod tenant;
+pub mod testing;
+pub mod trace;
+pub mod warmup;
+#[cfg(feature = "wire")]
+pub mod wire;
 
-use crate::config::{Config, Endpoint};
-pub use crate::error::RouterError;
-pub use crate::handler::HttpHandler;
-pub use crate::request::HttpRequest;
-pub use crate::response::HttpResponse;
+pub use crate::accept::MediaRange;
+pub use crate::access_log::{AccessLogFormat, AccessLogger};
+#[cfg(feature = "async")]
+pub use crate::async_handler::AsyncHttpHandler;
+pub use crate::auth::{ApiKeyProvider, Identity};
+use crate::batch::BatchItem;
+pub use crate::batch::BatchLimits;
+pub use crate::body::Body;
+use crate::canary::Variant;
+pub use crate::canonical::CanonicalizeConfig;
+use crate::circuit::{Attempt, Circuits};
+pub use crate::circuit::{CircuitState, CircuitStats};
+pub use crate::clock::{Clock, SystemClock};
+#[cfg(feature = "async")]
+pub use crate::coalesce::CoalesceOptions;
+#[cfg(feature = "async")]
+use crate::coalesce::{Flights, Joined};
+pub use crate::compiled::CompiledRoutes;
+use crate::concurrency::{LimitResponse, Permit};
+pub use crate::concurrency::{RouteStats, RouterStats};
+use crate::config::{Auth, Config, Endpoint};
+pub use crate::context::{RequestContext, TlsInfo};
+#[cfg(feature = "csrf")]
+pub use crate::csrf::CsrfMiddleware;
+pub use crate::deadline::{Deadline, DeadlineFormat};
+#[cfg(feature = "extractors")]
+pub use crate::error::ExtractError;
+#[cfg(feature = "jwt")]
+pub use crate::error::JwtError;
+pub use crate::error::{BodyError, LoadWarning, MetadataError, RangeError, RouterError, UrlError};
+use crate::error_page::{BuiltInError, ErrorTemplates, PageRequest};
+pub use crate::filter::RouteFilter;
+pub use crate::forwarded::{ForwardedElement, parse_forwarded};
+pub use crate::guard::Guard;
+use crate::handler::MissingHandlerPolicy;
+pub use crate::handler::{Handler, HandlerFactory, HandlerFuture, HttpHandler, IntoHandler};
+use crate::health::{HealthConfig, NamedCheck};
+use crate::hooks::{MatchedRoute, MirrorHook, MissingHandler, ResponseHook, Started, Suggestions};
+pub use crate::hooks::{Outcome, RequestSummary, ResponseTransformer};
+#[cfg(feature = "idempotency")]
+use crate::idempotency::{Begin, Claim, Rejection};
+#[cfg(feature = "idempotency")]
+pub use crate::idempotency::{IdempotencyMiddleware, IdempotencyStore};
+pub use crate::introspection::IntrospectionConfig;
+#[cfg(feature = "jwt")]
+pub use crate::jwt::JwtAuth;
+pub use crate::language::{LanguageTag, RouteLocale};
+pub use crate::limits::Limits;
+use crate::maintenance::Maintenance;
+use crate::method::TracePolicy;
+pub use crate::params::Params;
+use crate::path::PathDecoding;
+pub use crate::problem::Problem;
+pub use crate::range::ByteRange;
+#[cfg(feature = "record")]
+pub use crate::record::{JsonFileSink, RecordingSink};
+#[cfg(feature = "record")]
+use crate::record::{PendingRecording, RecordedController};
+pub use crate::request::{HttpRequest, HttpRequestBuilder};
+pub use crate::resolution::Resolution;
+use crate::resolution::Resolved;
+pub use crate::response::{HttpResponse, IntoHttpResponse};
+use crate::response_limit::{OversizedResponse, ResponseTooLarge, RouteResponseLimit};
+pub use crate::rewrite::RewriteRule;
+pub use crate::rng::{Rng, SystemRng};
+pub use crate::route_config::{RouteController, RouteEndpoint};
+#[cfg(feature = "schema")]
+use crate::schema::{ResponseValidation, SkipValidation};
+#[cfg(feature = "session")]
+pub use crate::session::{Session, SessionMiddleware, SessionStore};
+#[cfg(feature = "shared")]
+pub use crate::shared::SharedRouter;
+use crate::shutdown::Shutdown;
+pub use crate::shutdown::ShutdownHandle;
+use crate::signature::{EnvSecretProvider, RouteSignature};
+pub use crate::signature::{SecretProvider, SignatureGuard};
+pub use crate::sse::{SseEvent, SseSender};
+pub use crate::suggestions::SuggestionTrigger;
+use crate::table::{AllowedMethods, Lookup, RouteEntry, RouteTable};
+use crate::template::BodyTemplate;
+#[cfg(feature = "shared")]
+pub use crate::tenant::{
+    HeaderTenant, MultiTenantRouter, PathSegmentTenant, Tenant, TenantExtractor,
+};
+pub use crate::trace::TraceContext;
+pub use crate::warmup::{WarmedRoute, WarmupProblem, WarmupReport};
+
+/// The default maximum size of a streamed response body that `Router::route`
+/// buffers into memory: 64 MiB.
+pub const DEFAULT_MAX_BUFFERED_BODY_SIZE: usize = 64 * 1024 * 1024;
+
+/// The response header telling which controller of a canary route answered.
+const X_CANARY: &str = "x-canary";
+
+/// The response header telling how long the router took to answer, in
+/// milliseconds, when enabled with `Router::enable_timing_header`.
+const X_RESPONSE_TIME_MS: &str = "x-response-time-ms";
+
+/// The request header identifying a request, for `RequestSummary::request_id`.
+const X_REQUEST_ID: &str = "x-request-id";
+
+/// The outcome of `Router::prepare`.
+// Matched requests are the common case, so they aren't boxed.
+#[allow(clippy::large_enum_variant)]
+enum Dispatch<'r> {
+    /// The router answers the request itself, e.g. with a 404 Not Found.
+    Respond(HttpResponse),
+    /// The request matched `entry`, and `req` is ready for the handler of
+    /// `controller`, which `canary` tells apart for canary routes.
+    Handle {
+        entry: &'r RouteEntry,
+        controller: &'r str,
+        canary: Option<Variant>,
+        req: HttpRequest,
+    },
+}
+
+/// What `Router::after_handler` needs from a request taken by
+/// `Router::before_handler`, since the handler consumes the request.
+struct Handling {
+    #[cfg(feature = "idempotency")]
+    claim: Option<Claim>,
+    #[cfg(feature = "session")]
+    session: Option<Session>,
+    #[cfg(feature = "csrf")]
+    csrf_cookie: Option<HeaderValue>,
+}
+
+/// What `Router::complete` needs from a request taken by
+/// `Router::completion`, before the request is routed.
+struct Completion {
+    started: Option<Started>,
+    page_request: Option<PageRequest>,
+    head: bool,
+    #[cfg(feature = "record")]
+    recording: Option<PendingRecording>,
+}
+
+/// Creates the span a request is routed in, with its method and path, and the
+/// trace ID of its `traceparent` header, if any.
+#[cfg(feature = "logging")]
+fn request_span<B>(req: &Request<B>) -> tracing::Span {
+    let span = tracing::info_span!(
+        "request",
+        method = req.method().as_str(),
+        path = req.uri().path(),
+        trace_id = tracing::field::Empty,
+    );
+    if let Some(context) = TraceContext::from_headers(req.headers()) {
+        span.record("trace_id", context.trace_id_hex().as_str());
+    }
+    span
+}
+
+/// Adds the header and query parameter of API keys to the redacted ones, so
+/// that keys never appear in `Debug` output.
+fn redacted(api_keys: ApiKeyProvider) -> Arc<ApiKeyProvider> {
+    redact::redact_header(api_keys.header().clone());
+    if let Some(name) = api_keys.query_param() {
+        redact::redact_query_param(name);
+    }
+    Arc::new(api_keys)
+}
 
 /// The main router struct.
 ///
 /// It holds the routing tables and the registered handlers. It is the primary
 /// entry point for the library.
 pub struct Router {
-    /// A map from HTTP methods to a radix tree (`matchit::Router`) for routing.
-    /// Each tree stores paths for a specific method and maps them to a controller name.
-    trees: HashMap<Method, matchit::Router<String>>,
+    /// The routing table, a single radix tree (`matchit::Router`) mapping each path
+    /// to the controller names registered for it under every method, and the
+    /// ordered rewrite rules applied to the request path before matching.
+    routes: CompiledRoutes,
+    /// Headers added to every response that doesn't already have them.
+    default_headers: HeaderMap,
+    /// The API keys of routes with `"auth": "api_key"`.
+    api_keys: Option<Arc<ApiKeyProvider>>,
+    /// Where the secrets of endpoint signatures are read from.
+    secrets: Arc<dyn SecretProvider + Send + Sync>,
+    /// The templates of built-in error responses, from `error_pages`.
+    error_pages: Option<Arc<ErrorTemplates>>,
+    /// The limits on the size of requests' heads.
+    limits: Limits,
     /// A map from controller names (from the JSON config) to actual handler implementations.
-    /// This allows for dynamic dispatch to the correct handler at runtime.
-    handlers: HashMap<String, Box<dyn HttpHandler + Send + Sync>>,
+    /// This allows for dynamic dispatch to the correct handler at runtime. Handlers
+    /// are reference-counted so that `SharedRouter` can copy the router cheaply.
+    handlers: HashMap<String, Arc<dyn Handler + Send + Sync>>,
+    /// The handlers of controller name prefixes, longest prefix first, for
+    /// controllers without a handler of their own.
+    prefix_handlers: Vec<(String, Arc<dyn Handler + Send + Sync>)>,
+    /// The handler factories, by controller name.
+    factories: HashMap<String, Arc<HandlerFactory>>,
+    /// The handlers the factories built for routes with `controller_args`, by
+    /// their `RouteEntry::instance`.
+    instances: HashMap<Arc<str>, Arc<dyn Handler + Send + Sync>>,
+    /// The guards routes can list in their `guards`, by name.
+    guards: HashMap<String, Arc<dyn Guard + Send + Sync>>,
+    /// What to do with requests to routes without a handler.
+    missing_handler_policy: MissingHandlerPolicy,
+    /// The handler used by `MissingHandlerPolicy::Fallback`.
+    fallback: Option<Arc<dyn HttpHandler + Send + Sync>>,
+    /// The handler of `OPTIONS *` requests, if any.
+    server_options: Option<Arc<dyn HttpHandler + Send + Sync>>,
+    /// Whether unknown endpoint fields fail a `reload`, rather than being
+    /// reported as warnings.
+    strict_config: bool,
+    /// The endpoints loaded from the configuration, if not all of them.
+    route_filter: Option<Arc<RouteFilter>>,
+    /// Whether `HEAD` requests are answered by `GET` routes without a `HEAD` route.
+    auto_head: bool,
+    /// Whether `OPTIONS` requests to paths without an `OPTIONS` route are
+    /// answered with the allowed methods.
+    auto_options: bool,
+    /// How `TRACE` requests are answered.
+    trace_policy: TracePolicy,
+    /// Whether request paths are normalized before rewriting and matching.
+    normalize_paths: bool,
+    /// How percent-encoded bytes in request paths are handled.
+    path_decoding: PathDecoding,
+    /// The maximum size of a streamed response body that `route` buffers.
+    max_buffered_body_size: usize,
+    /// The largest response body of routes without their own
+    /// `max_response_size`, if limited.
+    max_response_size: Option<usize>,
+    /// Whether built-in error responses are formatted as problem documents.
+    problem_responses: bool,
+    /// The status of responses to requests missing a required header.
+    required_header_status: StatusCode,
+    /// The handler timeout for routes without their own `timeout_ms`.
+    default_timeout: Option<Duration>,
+    /// The settings of request coalescing, for routes with `coalesce`.
+    #[cfg(feature = "async")]
+    coalescing: CoalesceOptions,
+    /// The requests running the handler of a route with `coalesce`, shared by
+    /// copies of the router.
+    #[cfg(feature = "async")]
+    flights: Arc<Flights>,
+    /// The response to requests turned away by a route's `max_concurrency`.
+    concurrency_limit_response: LimitResponse,
+    /// The header callers send their deadline in, and its format.
+    deadline_header: Option<(http::header::HeaderName, DeadlineFormat)>,
+    /// Called after each request is routed.
+    on_response: Option<ResponseHook>,
+    /// Called after each request taking at least the threshold to route.
+    on_slow_request: Option<(Duration, ResponseHook)>,
+    /// The response transformers, in the order they were added.
+    response_transformers: Vec<Arc<dyn ResponseTransformer + Send + Sync>>,
+    /// Whether responses get an `X-Response-Time-Ms` header.
+    timing_header: bool,
+    /// Tells the time, e.g. for the time spent routing requests.
+    clock: Arc<dyn Clock + Send + Sync>,
+    /// Draws the random canary splits.
+    rng: Arc<dyn Rng + Send + Sync>,
+    /// Whether `route` and `route_streaming` call mirror handlers inline.
+    sync_mirroring: bool,
+    /// Called with the responses of the primary and mirror handlers.
+    on_mirror: Option<MirrorHook>,
+    /// The paths of the built-in health endpoints, if enabled.
+    health: Option<HealthConfig>,
+    /// The settings of the built-in route listing, if enabled.
+    introspection: Option<IntrospectionConfig>,
+    /// The path and limits of the built-in batch endpoint, if enabled.
+    batch: Option<(String, BatchLimits)>,
+    /// The canonical scheme and host requests are redirected to.
+    canonicalization: Option<CanonicalizeConfig>,
+    /// The checks run by the readiness endpoint.
+    readiness_checks: Vec<NamedCheck>,
+    /// The maintenance mode, shared by copies of the router.
+    maintenance: Arc<Maintenance>,
+    /// The shutdown state, shared by copies of the router and its handles.
+    shutdown: Arc<Shutdown>,
+    /// The paths still routed while shutting down.
+    shutdown_exempt_paths: Vec<String>,
+    /// Whether 404 Not Found responses suggest the closest routes.
+    suggestions: bool,
+    /// The requests that get suggestions.
+    suggestion_trigger: SuggestionTrigger,
+    /// The circuit breakers of controllers, shared by copies of the router.
+    circuits: Arc<Circuits>,
+    /// Where the requests and their responses are recorded, if enabled.
+    #[cfg(feature = "record")]
+    recording: Option<Arc<dyn RecordingSink + Send + Sync>>,
+    /// The response cache for routes with a cache TTL, shared by copies of the router.
+    #[cfg(feature = "cache")]
+    cache: Arc<cache::ResponseCache>,
+    /// The session middleware, if enabled with `use_sessions`.
+    #[cfg(feature = "session")]
+    sessions: Option<Arc<SessionMiddleware>>,
+    /// The idempotency key middleware, if enabled with `use_idempotency`.
+    #[cfg(feature = "idempotency")]
+    idempotency: Option<Arc<IdempotencyMiddleware>>,
+    /// The CSRF protection, if enabled with `use_csrf`.
+    #[cfg(feature = "csrf")]
+    csrf: Option<Arc<CsrfMiddleware>>,
+    /// The bearer token validation, if enabled with `use_jwt_auth`.
+    #[cfg(feature = "jwt")]
+    jwt: Option<Arc<JwtAuth>>,
+    /// Whether responses are validated against their route's `response_schema`.
+    #[cfg(feature = "schema")]
+    response_validation: ResponseValidation,
+}
+
+/// Copies the routes and settings, sharing the handler instances. Unlike
+/// `SharedRouter`'s copies, the clone's maintenance mode, shutdown state and
+/// circuit breakers are independent of the original's, its circuits start
+/// closed, and it starts with an empty response cache of the same size.
+impl Clone for Router {
+    fn clone(&self) -> Self {
+        let mut router = self.snapshot();
+        router.maintenance = Arc::new(self.maintenance.copy());
+        router.shutdown = Arc::new(self.shutdown.copy());
+        router.circuits = Arc::default();
+        #[cfg(feature = "async")]
+        {
+            router.flights = Arc::default();
+        }
+        #[cfg(feature = "cache")]
+        {
+            router.cache = Arc::new(self.cache.empty_copy());
+        }
+        router
+    }
+}
+
+/// Summarizes the router: the number of routes per method, the names of the
+/// registered controllers (sorted) and the main settings. Handlers themselves
+/// aren't shown.
+impl fmt::Debug for Router {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut routes: BTreeMap<&str, usize> = BTreeMap::new();
+        for (method, _) in self.routes.table.routes() {
+            *routes.entry(method.as_str()).or_default() += 1;
+        }
+
+        let mut debug = f.debug_struct("Router");
+        debug
+            .field("routes", &routes)
+            .field("rewrites", &self.routes.rewrites.len())
+            .field("handlers", &sorted_names(&self.handlers))
+            .field(
+                "prefix_handlers",
+                &self
+                    .prefix_handlers
+                    .iter()
+                    .map(|(prefix, _)| prefix.as_str())
+                    .collect::<Vec<_>>(),
+            )
+            .field("factories", &sorted_names(&self.factories))
+            .field("guards", &sorted_names(&self.guards))
+            .field("api_keys", &self.api_keys);
+        debug
+            .field("missing_handler_policy", &self.missing_handler_policy)
+            .field("maintenance", &self.maintenance.mode())
+            .finish_non_exhaustive()
+    }
+}
+
+/// What `Router::load` reads from a configuration file.
+struct Loaded {
+    routes: CompiledRoutes,
+    default_headers: HeaderMap,
+    api_keys: Option<ApiKeyProvider>,
+    error_pages: Option<Arc<ErrorTemplates>>,
+    limits: Option<Limits>,
+    warnings: Vec<LoadWarning>,
+}
+
+/// Drops the body of the response to a `HEAD` request, whether a handler or
+/// the router built it, keeping its length as the `Content-Length` unless the
+/// response has one or an empty or streamed body: a handler of an explicit
+/// `HEAD` route may have set the length of the `GET` response itself.
+fn strip_body(response: &mut HttpResponse) {
+    if !response.headers.contains_key(http::header::CONTENT_LENGTH)
+        && let Some(body) = response.body.as_bytes()
+        && !body.is_empty()
+    {
+        response.add_header(http::header::CONTENT_LENGTH, &body.len().to_string());
+    }
+    response.body = Body::empty();
+}
+
+/// Builds the handlers of the routes of `table` with `controller_args`, with
+/// the factories of their controllers, reusing those in `built`. Routes whose
+/// controller has no factory are left without one.
+fn build_instances(
+    table: &RouteTable,
+    factories: &HashMap<String, Arc<HandlerFactory>>,
+    built: &HashMap<Arc<str>, Arc<dyn Handler + Send + Sync>>,
+) -> Result<HashMap<Arc<str>, Arc<dyn Handler + Send + Sync>>, RouterError> {
+    let mut instances = HashMap::new();
+    for (method, entry) in table.routes() {
+        let (Some(instance), Some(args)) = (&entry.instance, &entry.endpoint.controller_args)
+        else {
+            continue;
+        };
+        if instances.contains_key(instance) {
+            continue;
+        }
+        let handler = match (built.get(instance), factories.get(&*entry.controller)) {
+            (Some(handler), _) => Arc::clone(handler),
+            (None, Some(factory)) => {
+                let handler = factory(args).map_err(|e| RouterError::Endpoint {
+                    method: method.clone(),
+                    path: entry.pattern.to_string(),
+                    reason: format!(
+                        "controller '{}' failed to build its handler: {e}",
+                        entry.controller
+                    ),
+                })?;
+                Arc::new(handler)
+            }
+            (None, None) => continue,
+        };
+        instances.insert(Arc::clone(instance), handler);
+    }
+    Ok(instances)
+}
+
+/// Returns the controller names of a handler map, sorted.
+fn sorted_names<H: ?Sized>(handlers: &HashMap<String, Arc<H>>) -> Vec<&str> {
+    let mut names: Vec<&str> = handlers.keys().map(String::as_str).collect();
+    names.sort_unstable();
+    names
 }
 
 impl Router {
     /// Creates a new `Router` by loading and parsing a JSON configuration file.
     ///
+    /// Routes shadowed by a higher-priority route are left out silently; use
+    /// [`Router::new_with_report`] to find out about them.
+    ///
     /// # Arguments
     ///
     /// * `config_path` - A path to the JSON file containing the route definitions.
@@ -48,144 +543,3339 @@ impl Router {
     /// Returns a `RouterError` if the file cannot be opened, read, or parsed, or if
     /// there's an issue inserting a route into the routing tree.
     pub fn new<P: AsRef<Path>>(config_path: P) -> Result<Self, RouterError> {
-        // Open and parse the JSON configuration file.
-        let file = File::open(config_path)?;
-        let reader = BufReader::new(file);
-        let config: Config = serde_json::from_reader(reader)?;
-
-        let mut trees = HashMap::<Method, matchit::Router<String>>::new();
-
-        // Populate the routing trees from the parsed configuration.
-        for endpoint in config.endpoints {
-            let Endpoint {
-                method,
-                path,
-                controller,
-                ..
-            } = endpoint;
+        Self::new_with_report(config_path).map(|(router, _)| router)
+    }
+
+    /// Creates a new `Router` like [`Router::new`], also returning the warnings
+    /// found while loading the configuration, such as routes left out because a
+    /// higher-priority route conflicts with them.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError` if the file cannot be opened, read, or parsed, or if
+    /// there's an issue inserting a route into the routing tree.
+    pub fn new_with_report<P: AsRef<Path>>(
+        config_path: P,
+    ) -> Result<(Self, Vec<LoadWarning>), RouterError> {
+        Self::build_from(config_path, None)
+    }
 
-            // Get the tree for the current HTTP method, or create it if it doesn't exist.
-            let method_str = method.as_str().to_string();
-            // let tree = trees.entry(method).or_insert_with(matchit::Router::new);
-            let tree = trees.entry(method).or_default();
+    /// Creates a new `Router` like [`Router::new`], loading only the endpoints
+    /// `filter` matches, e.g. those tagged `"admin"`. The filter is applied
+    /// again by `reload`, but not to routes added with `add_route`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError` as [`Router::new`] does, for the endpoints
+    /// loaded: those left out can't make loading fail by conflicting with
+    /// others, although they must still be valid JSON of the right shape.
+    pub fn new_filtered<P: AsRef<Path>>(
+        config_path: P,
+        filter: RouteFilter,
+    ) -> Result<Self, RouterError> {
+        Self::build_from(config_path, Some(Arc::new(filter))).map(|(router, _)| router)
+    }
 
-            // Insert the route into the tree. The path is the key, and the controller
-            // name is the value.
-            tree.insert(path.clone(), controller.clone())?;
-            eprintln!("Registered route: {method_str} {path} -> {controller}");
-        }
+    /// Creates a new `Router` like [`Router::new`], from a JSON configuration
+    /// held in memory, e.g. embedded with `include_str!`. The files it refers
+    /// to, such as `error_pages` with a `file`, are relative to the current
+    /// directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError` if the configuration can't be parsed, or if
+    /// there's an issue inserting a route into the routing tree.
+    pub fn from_json_str(json: &str) -> Result<Self, RouterError> {
+        Self::from_json_str_with_report(json).map(|(router, _)| router)
+    }
+
+    /// Creates a new `Router` like [`Router::from_json_str`], also returning
+    /// the warnings found while loading the configuration, as
+    /// [`Router::new_with_report`] does, such as unknown endpoint fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError` if the configuration can't be parsed, or if
+    /// there's an issue inserting a route into the routing tree.
+    pub fn from_json_str_with_report(json: &str) -> Result<(Self, Vec<LoadWarning>), RouterError> {
+        let loaded = Self::load_str(json, Path::new(""), false, None)?;
+        Ok(Self::build(loaded, None))
+    }
+
+    /// Creates a new `Router` with the endpoints of a configuration file that
+    /// `route_filter` matches, or all of them.
+    fn build_from<P: AsRef<Path>>(
+        config_path: P,
+        route_filter: Option<Arc<RouteFilter>>,
+    ) -> Result<(Self, Vec<LoadWarning>), RouterError> {
+        let loaded = Self::load(config_path, false, route_filter.as_deref())?;
+        Ok(Self::build(loaded, route_filter))
+    }
 
-        Ok(Self {
-            trees,
+    /// Creates a new `Router` with a loaded configuration, loaded with
+    /// `route_filter`.
+    fn build(loaded: Loaded, route_filter: Option<Arc<RouteFilter>>) -> (Self, Vec<LoadWarning>) {
+        let Loaded {
+            routes,
+            default_headers,
+            api_keys,
+            error_pages,
+            limits,
+            warnings,
+        } = loaded;
+
+        let router = Self {
+            routes,
+            default_headers,
+            api_keys: api_keys.map(redacted),
+            secrets: Arc::new(EnvSecretProvider),
+            error_pages,
+            limits: limits.unwrap_or_default(),
             handlers: HashMap::new(),
+            prefix_handlers: Vec::new(),
+            factories: HashMap::new(),
+            instances: HashMap::new(),
+            guards: HashMap::new(),
+            missing_handler_policy: MissingHandlerPolicy::default(),
+            fallback: None,
+            server_options: None,
+            strict_config: false,
+            route_filter,
+            auto_head: true,
+            auto_options: false,
+            trace_policy: TracePolicy::default(),
+            normalize_paths: true,
+            path_decoding: PathDecoding::default(),
+            max_buffered_body_size: DEFAULT_MAX_BUFFERED_BODY_SIZE,
+            max_response_size: None,
+            problem_responses: false,
+            required_header_status: StatusCode::BAD_REQUEST,
+            default_timeout: None,
+            #[cfg(feature = "async")]
+            coalescing: CoalesceOptions::default(),
+            #[cfg(feature = "async")]
+            flights: Arc::default(),
+            concurrency_limit_response: LimitResponse::default(),
+            deadline_header: None,
+            on_response: None,
+            on_slow_request: None,
+            response_transformers: Vec::new(),
+            timing_header: false,
+            clock: Arc::new(SystemClock),
+            rng: Arc::new(SystemRng),
+            sync_mirroring: false,
+            on_mirror: None,
+            health: None,
+            introspection: None,
+            batch: None,
+            canonicalization: None,
+            readiness_checks: Vec::new(),
+            maintenance: Arc::default(),
+            #[cfg(feature = "record")]
+            recording: None,
+            shutdown: Arc::default(),
+            circuits: Arc::default(),
+            shutdown_exempt_paths: Vec::new(),
+            suggestions: false,
+            suggestion_trigger: SuggestionTrigger::default(),
+            #[cfg(feature = "cache")]
+            cache: Arc::new(cache::ResponseCache::new()),
+            #[cfg(feature = "session")]
+            sessions: None,
+            #[cfg(feature = "idempotency")]
+            idempotency: None,
+            #[cfg(feature = "csrf")]
+            csrf: None,
+            #[cfg(feature = "jwt")]
+            jwt: None,
+            #[cfg(feature = "schema")]
+            response_validation: ResponseValidation::default(),
+        };
+        (router, warnings)
+    }
+
+    /// Reloads the routes and rewrite rules from a JSON configuration file,
+    /// keeping the registered handlers and the other settings. Use it e.g. to
+    /// change the percentage of a canary route; with a `SharedRouter`, use
+    /// `SharedRouter::reload`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError` as [`Router::new_with_report`] does. The router is
+    /// left unchanged in that case.
+    pub fn reload<P: AsRef<Path>>(
+        &mut self,
+        config_path: P,
+    ) -> Result<Vec<LoadWarning>, RouterError> {
+        let Loaded {
+            routes,
+            default_headers,
+            api_keys,
+            error_pages,
+            limits,
+            mut warnings,
+        } = Self::load(
+            config_path,
+            self.strict_config,
+            self.route_filter.as_deref(),
+        )?;
+        self.instances = build_instances(&routes.table, &self.factories, &self.instances)?;
+        self.routes = routes;
+        self.default_headers = default_headers;
+        self.api_keys = api_keys.map(redacted);
+        self.error_pages = error_pages;
+        if let Some(limits) = limits {
+            self.limits = limits;
+        }
+        warnings.extend(self.builtin_warnings());
+        Ok(warnings)
+    }
+
+    /// Answers `POST` requests to `path` with the responses to a batch of
+    /// requests, saving clients round trips: the body is a JSON array of
+    /// sub-requests, e.g. `[{"method": "GET", "path": "/users/1"}, {"method":
+    /// "POST", "path": "/orders", "headers": {"authorization": "Bearer ..."},
+    /// "body": {"item": 42}}]`, with a `body_base64` for bodies that aren't
+    /// JSON. The response is the array of their responses, in order, each
+    /// with its status, headers and body, as JSON for a JSON `Content-Type`
+    /// and base64-encoded otherwise, e.g. `[{"status": 200, "headers":
+    /// {"content-type": "application/json"}, "body": {"id": 1}}, {"status":
+    /// 404, "headers": {}}]`.
+    ///
+    /// Each sub-request is routed like a request of its own, e.g. with
+    /// [`Router::route`], through its route's checks, authentication and
+    /// middleware, with only its own headers: the batch's, such as its
+    /// `Authorization`, aren't passed on. A sub-request to `path` gets a 400
+    /// Bad Request, since batches can't be nested, and a batch over `limits`
+    /// gets a 413 Content Too Large. Like the health endpoints, the batch
+    /// endpoint only answers a path that doesn't match any route; a warning
+    /// is returned if it's shadowed.
+    pub fn enable_batching<S: Into<String>>(
+        &mut self,
+        path: S,
+        limits: BatchLimits,
+    ) -> Vec<LoadWarning> {
+        self.batch = Some((path.into(), limits));
+        self.builtin_warnings()
+    }
+
+    /// Returns a warning for each built-in endpoint a route shadows.
+    fn builtin_warnings(&self) -> Vec<LoadWarning> {
+        let mut warnings: Vec<LoadWarning> = self
+            .health
+            .iter()
+            .flat_map(|health| [&health.liveness_path, &health.readiness_path])
+            .filter(|path| self.routes.table.allowed(path).is_some())
+            .map(|path| LoadWarning::HealthEndpointShadowed { path: path.clone() })
+            .collect();
+        if let Some(introspection) = &self.introspection
+            && self.routes.table.allowed(&introspection.path).is_some()
+        {
+            warnings.push(LoadWarning::IntrospectionEndpointShadowed {
+                path: introspection.path.clone(),
+            });
+        }
+        if let Some((path, _)) = &self.batch
+            && self.routes.table.allowed(path).is_some()
+        {
+            warnings.push(LoadWarning::BatchEndpointShadowed { path: path.clone() });
+        }
+        warnings
+    }
+
+    /// Loads the routing table and rewrite rules from a JSON configuration file,
+    /// failing on unknown endpoint fields if `strict`, and leaving out the
+    /// endpoints `filter` doesn't match.
+    fn load<P: AsRef<Path>>(
+        config_path: P,
+        strict: bool,
+        filter: Option<&RouteFilter>,
+    ) -> Result<Loaded, RouterError> {
+        let config_path = config_path.as_ref();
+        let json = fs::read_to_string(config_path)
+            .map_err(|e| RouterError::from(e).in_file(config_path))?;
+        let base_dir = config_path.parent().unwrap_or(Path::new(""));
+        Self::load_str(&json, base_dir, strict, filter).map_err(|e| e.in_file(config_path))
+    }
+
+    /// Loads the routing table and rewrite rules from a JSON configuration like
+    /// `load`, with the files it refers to relative to `base_dir`.
+    fn load_str(
+        json: &str,
+        base_dir: &Path,
+        strict: bool,
+        filter: Option<&RouteFilter>,
+    ) -> Result<Loaded, RouterError> {
+        // Parse the JSON configuration, checking for unknown fields first:
+        // serde ignores them, so a misspelled field would be lost.
+        let mut warnings = config_schema::check_fields(&serde_json::from_str(json)?, strict)?;
+        let mut config: Config = serde_json::from_str(json)?;
+        // Only `x-` fields are metadata; `check_fields` reported the others.
+        for endpoint in &mut config.endpoints {
+            endpoint.metadata.retain(|name, _| name.starts_with("x-"));
+        }
+        warnings.extend(config.validate(strict)?);
+        if let Some(filter) = filter {
+            config.endpoints.retain(|endpoint| filter.matches(endpoint));
+        }
+        #[cfg(feature = "schema")]
+        config.resolve_schemas(base_dir)?;
+        config.resolve_static_dirs(base_dir);
+        let default_headers = config.default_headers()?;
+        let error_pages = ErrorTemplates::load(&config.error_pages, base_dir)?.map(Arc::new);
+        let api_keys = config
+            .api_keys
+            .as_ref()
+            .map(ApiKeyProvider::from_config)
+            .transpose()?;
+        let limits = config.limits;
+        let (routes, shadowed) = CompiledRoutes::new_with_report(config)?;
+        warnings.extend(shadowed);
+        Ok(Loaded {
+            routes,
+            default_headers,
+            api_keys,
+            error_pages,
+            limits,
+            warnings,
         })
     }
 
-    /// Registers a handler for a given controller name.
+    /// Returns the endpoints of the routes, in configuration order and then
+    /// the order they were added in, e.g. to generate documentation grouped by
+    /// their `tags`. Controller aliases are expanded, and routes shadowed by a
+    /// higher-priority route left out.
+    pub fn routes(&self) -> impl Iterator<Item = &Endpoint> {
+        self.routes.table.endpoints()
+    }
+
+    /// Builds the path of a route to `controller`, with the values of its
+    /// path parameters from `params`, percent-encoded. For a controller with
+    /// localized paths, `locale` picks the path; unlocalized paths are for
+    /// every locale. The first route, in the order of `routes`, whose
+    /// parameters all have a value is used, and other values are ignored.
+    /// Parameters are named as in the route's own pattern, even if another
+    /// route for the same path names them differently (see
+    /// `LoadWarning::ParamNamesDiffer`).
+    ///
+    /// ```
+    /// use generic_http_router::Router;
+    ///
+    /// let router = Router::from_json_str(r#"{"endpoints": [
+    ///     {"method": "GET", "path": {"en": "/pricing/:plan", "de": "/preise/:plan"},
+    ///      "description": "", "controller": "pricing"}
+    /// ]}"#).unwrap();
+    /// let url = router.url_for("pricing", Some("de"), &[("plan", "großes Team")]);
+    /// assert_eq!(url.unwrap(), "/preise/gro%C3%9Fes%20Team");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns a `UrlError` if no route dispatches to `controller`, none is
+    /// for `locale`, or a parameter has no value.
+    pub fn url_for(
+        &self,
+        controller: &str,
+        locale: Option<&str>,
+        params: &[(&str, &str)],
+    ) -> Result<String, UrlError> {
+        let routes: Vec<&Endpoint> = self
+            .routes()
+            .filter(|endpoint| endpoint.controller == controller)
+            .collect();
+        if routes.is_empty() {
+            return Err(UrlError::UnknownController(controller.to_string()));
+        }
+        let localized: Vec<&Endpoint> = routes
+            .iter()
+            .copied()
+            .filter(|endpoint| match &endpoint.locale {
+                Some(route_locale) => Some(route_locale.as_str()) == locale,
+                None => true,
+            })
+            .collect();
+        if localized.is_empty() {
+            return Err(UrlError::MissingLocale {
+                controller: controller.to_string(),
+                locale: locale.map(str::to_string),
+            });
+        }
+
+        let value = |name: &str| {
+            params
+                .iter()
+                .find(|(param, _)| *param == name)
+                .map(|(_, value)| *value)
+        };
+        let mut missing = None;
+        'routes: for endpoint in localized {
+            let mut url = Vec::new();
+            for segment in endpoint.path.split('/') {
+                let Some(start) = segment.find([':', '*']) else {
+                    url.push(Cow::Borrowed(segment));
+                    continue;
+                };
+                let name = &segment[start + 1..];
+                let Some(value) = value(name) else {
+                    missing.get_or_insert(name);
+                    continue 'routes;
+                };
+                let catch_all = segment.as_bytes()[start] == b'*';
+                url.push(Cow::Owned(format!(
+                    "{}{}",
+                    &segment[..start],
+                    path::percent_encode(value, catch_all)
+                )));
+            }
+            return Ok(url.join("/"));
+        }
+        Err(UrlError::MissingParam(
+            missing.unwrap_or_default().to_string(),
+        ))
+    }
+
+    /// Returns the compiled routes and rewrite rules the router matches requests
+    /// with, e.g. to compare them with another configuration's using
+    /// [`CompiledRoutes::diff`].
+    pub fn compiled_routes(&self) -> &CompiledRoutes {
+        &self.routes
+    }
+
+    /// Registers a handler for a given controller name, replacing the one
+    /// registered before, if any.
     ///
     /// The `controller_name` must exactly match the `controller` string specified
-    /// in the `routes.json` file.
+    /// in the `routes.json` file, or the name it stands for with the aliases of
+    /// the `controllers` section: either form registers the same handler.
     ///
     /// # Arguments
     ///
     /// * `controller_name` - The name of the controller to register.
-    /// * `handler` - A boxed, dynamically-dispatchable `HttpHandler` implementation.
-    pub fn register<S: Into<String>>(
+    /// * `handler` - The [`Handler`]: any `HttpHandler`, or with the `async`
+    ///   feature an `AsyncHttpHandler` or an async closure in
+    ///   `async_handler::Async` or `async_handler::AsyncFn`.
+    pub fn register_handler<S, H>(&mut self, controller_name: S, handler: H)
+    where
+        S: Into<String>,
+        H: Handler + Send + Sync + 'static,
+    {
+        let name = self.routes.controller_name(controller_name.into());
+        self.handlers.insert(name, Arc::new(handler));
+    }
+
+    /// Registers a handler for every controller whose name starts with
+    /// `prefix`, e.g. `admin::` for `admin::users::list` and
+    /// `admin::audit::export`, replacing the one registered before for the
+    /// same prefix, if any. A handler registered for the exact name, with
+    /// `register_handler` or `register_factory`, takes precedence, and
+    /// otherwise the longest matching prefix does.
+    ///
+    /// The handler gets the full controller name from
+    /// `HttpRequest::route_controller`, for dispatching on it. Routes covered
+    /// by a prefix count as having a handler, e.g. for `validate_handlers`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+    ///
+    /// struct Admin;
+    ///
+    /// impl HttpHandler for Admin {
+    ///     fn handle(&self, req: HttpRequest) -> HttpResponse {
+    ///         HttpResponse::ok(req.route_controller().unwrap_or_default().to_string())
+    ///     }
+    /// }
+    ///
+    /// let mut router = Router::from_json_str(
+    ///     r#"{"endpoints": [
+    ///         {"method": "GET", "path": "/admin/users", "description": "",
+    ///          "controller": "admin::users::list"}
+    ///     ]}"#,
+    /// )
+    /// .unwrap();
+    /// router.register_prefix("admin::", Admin);
+    /// assert!(router.validate_handlers().is_ok());
+    ///
+    /// let res = router.route(http::Request::get("/admin/users").body(Vec::new()).unwrap());
+    /// assert_eq!(res.body().as_ref(), b"admin::users::list");
+    /// ```
+    pub fn register_prefix<S, H>(&mut self, prefix: S, handler: H)
+    where
+        S: Into<String>,
+        H: Handler + Send + Sync + 'static,
+    {
+        let prefix = prefix.into();
+        self.prefix_handlers
+            .retain(|(registered, _)| *registered != prefix);
+        let index = self
+            .prefix_handlers
+            .partition_point(|(registered, _)| registered.len() >= prefix.len());
+        self.prefix_handlers
+            .insert(index, (prefix, Arc::new(handler)));
+    }
+
+    /// Registers a handler factory for a given controller name, building the
+    /// handler of each route whose `controller` gives the factory's `args`,
+    /// e.g. two routes serving two files with one static JSON responder:
+    ///
+    /// ```json
+    /// { "method": "GET", "path": "/plans", "description": "",
+    ///   "controller": { "name": "static_json", "args": { "file": "plans.json" } } }
+    /// ```
+    ///
+    /// The routes with the same arguments share one handler. Handlers are
+    /// built now, and again for new routes when the configuration is reloaded
+    /// or a route is added. Routes of the controller without arguments use the
+    /// handler registered with `register_handler`, if any; routes with
+    /// arguments need the factory, which `validate_handlers` checks.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError::Endpoint` naming the first route the factory
+    /// fails for, with its error. No handler is registered in that case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+    ///
+    /// struct Greeter(String);
+    ///
+    /// impl HttpHandler for Greeter {
+    ///     fn handle(&self, _req: HttpRequest) -> HttpResponse {
+    ///         HttpResponse::ok(format!("Hello, {}!", self.0))
+    ///     }
+    /// }
+    ///
+    /// let mut router = Router::from_json_str(
+    ///     r#"{"endpoints": [
+    ///         {"method": "GET", "path": "/hello", "description": "",
+    ///          "controller": {"name": "greet", "args": {"name": "world"}}},
+    ///         {"method": "GET", "path": "/hi", "description": "",
+    ///          "controller": {"name": "greet", "args": {"name": "you"}}}
+    ///     ]}"#,
+    /// )
+    /// .unwrap();
+    /// router
+    ///     .register_factory(
+    ///         "greet",
+    ///         Box::new(|args| {
+    ///             let name = args["name"].as_str().unwrap_or_default().to_string();
+    ///             Ok(Box::new(Greeter(name)))
+    ///         }),
+    ///     )
+    ///     .unwrap();
+    ///
+    /// let res = router.route(http::Request::get("/hello").body(Vec::new()).unwrap());
+    /// assert_eq!(res.body().as_ref(), b"Hello, world!");
+    /// let res = router.route(http::Request::get("/hi").body(Vec::new()).unwrap());
+    /// assert_eq!(res.body().as_ref(), b"Hello, you!");
+    /// ```
+    pub fn register_factory<S: Into<String>>(
         &mut self,
         controller_name: S,
-        handler: Box<dyn HttpHandler + Send + Sync>,
+        factory: Box<HandlerFactory>,
+    ) -> Result<(), RouterError> {
+        let name = self.routes.controller_name(controller_name.into());
+        let mut factories = self.factories.clone();
+        factories.insert(name, Arc::from(factory));
+        self.instances = build_instances(&self.routes.table, &factories, &HashMap::new())?;
+        self.factories = factories;
+        Ok(())
+    }
+
+    /// Registers an `HttpHandler`, in a `Box` or an `Arc`, for a given
+    /// controller name.
+    ///
+    /// # Examples
+    ///
+    /// Existing callers keep compiling, with a deprecation warning:
+    ///
+    /// ```
+    /// # use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+    /// # struct Hello;
+    /// # impl HttpHandler for Hello {
+    /// #     fn handle(&self, _req: HttpRequest) -> HttpResponse {
+    /// #         HttpResponse::ok("hello")
+    /// #     }
+    /// # }
+    /// # let mut router = Router::from_json_str(r#"{"endpoints": []}"#).unwrap();
+    /// #[allow(deprecated)]
+    /// router.register("hello", Box::new(Hello));
+    /// ```
+    ///
+    /// The same call is an error where deprecations are denied:
+    ///
+    /// ```compile_fail
+    /// #![deny(deprecated)]
+    /// # use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+    /// # struct Hello;
+    /// # impl HttpHandler for Hello {
+    /// #     fn handle(&self, _req: HttpRequest) -> HttpResponse {
+    /// #         HttpResponse::ok("hello")
+    /// #     }
+    /// # }
+    /// # let mut router = Router::from_json_str(r#"{"endpoints": []}"#).unwrap();
+    /// router.register("hello", Box::new(Hello));
+    /// ```
+    ///
+    /// Migrate by passing the handler itself:
+    ///
+    /// ```
+    /// #![deny(deprecated)]
+    /// # use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+    /// # struct Hello;
+    /// # impl HttpHandler for Hello {
+    /// #     fn handle(&self, _req: HttpRequest) -> HttpResponse {
+    /// #         HttpResponse::ok("hello")
+    /// #     }
+    /// # }
+    /// # let mut router = Router::from_json_str(r#"{"endpoints": []}"#).unwrap();
+    /// router.register_handler("hello", Hello);
+    /// ```
+    #[deprecated(note = "use `register_handler`, which takes the handler itself")]
+    pub fn register<S: Into<String>, H: IntoHandler>(&mut self, controller_name: S, handler: H) {
+        self.register_handler(controller_name, handler.into_handler());
+    }
+
+    /// Registers a function taking up to four extractors as arguments, such as
+    /// [`extract::Path`] and [`extract::Json`], for a given controller name. A
+    /// request that an extractor rejects gets a 400 Bad Request (or a 415
+    /// Unsupported Media Type for a body of the wrong type) without calling the
+    /// function.
+    ///
+    /// # Examples
+    ///
+    /// The handlers of the example server, as functions:
+    ///
+    /// ```
+    /// use generic_http_router::extract::{Json, Path};
+    /// use generic_http_router::{HttpResponse, Router};
+    /// use http::StatusCode;
+    ///
+    /// #[derive(serde::Deserialize)]
+    /// struct CreateUser {
+    ///     name: String,
+    /// }
+    ///
+    /// fn get_by_id(Path(id): Path<u64>) -> String {
+    ///     format!("Fetching user with id: {id}")
+    /// }
+    ///
+    /// fn create(Json(user): Json<CreateUser>) -> (StatusCode, String) {
+    ///     (StatusCode::CREATED, format!("User {} created", user.name))
+    /// }
+    ///
+    /// let mut router = Router::new("./examples/routes.json").unwrap();
+    /// router.register_extracting("users_controller::get_all", || "Returning all users");
+    /// router.register_extracting("users_controller::get_by_id", get_by_id);
+    /// router.register_extracting("users_controller::create", create);
+    ///
+    /// let res = router.route(http::Request::get("/users/42").body(Vec::new()).unwrap());
+    /// assert_eq!(res.body().as_ref(), b"Fetching user with id: 42");
+    /// let res = router.route(http::Request::get("/users/me").body(Vec::new()).unwrap());
+    /// assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    /// ```
+    #[cfg(feature = "extractors")]
+    pub fn register_extracting<S, H, Args>(&mut self, controller_name: S, handler: H)
+    where
+        S: Into<String>,
+        H: extract::ExtractorHandler<Args>,
+        Args: 'static,
+    {
+        self.register_handler(controller_name, extract::Extracting::new(handler));
+    }
+
+    /// Registers a guard under `name`, for the routes listing it in their
+    /// `guards`. Built-in guards such as [`guard::RequireHeader`] are
+    /// registered like any other, e.g. as `"require_json"`.
+    pub fn register_guard<S: Into<String>>(
+        &mut self,
+        name: S,
+        guard: Box<dyn Guard + Send + Sync>,
     ) {
-        self.handlers.insert(controller_name.into(), handler);
+        self.guards.insert(name.into(), Arc::from(guard));
     }
 
-    /// Routes an incoming HTTP request to the appropriate handler.
+    /// Checks that every guard listed by a route is registered, and that API
+    /// keys are configured if a route requires one, e.g. at startup once the
+    /// guards are. A request to a route with an unregistered guard, or requiring
+    /// an API key without any configured, gets a 500 Internal Server Error.
     ///
-    /// This is the main method that performs the routing logic.
+    /// # Errors
     ///
-    /// # Arguments
+    /// Returns a `RouterError::Endpoint` for the first route listing an unknown
+    /// guard or requiring missing API keys.
+    pub fn validate(&self) -> Result<(), RouterError> {
+        for (method, entry) in self.routes.table.routes() {
+            if entry.auth == Some(Auth::ApiKey) && self.api_keys.is_none() {
+                return Err(RouterError::Endpoint {
+                    method: method.clone(),
+                    path: entry.pattern.to_string(),
+                    reason: "requires an API key, but no api_keys are configured".to_string(),
+                });
+            }
+            if let Some(name) = entry
+                .guards
+                .iter()
+                .find(|name| !self.guards.contains_key(name.as_str()))
+            {
+                return Err(RouterError::Endpoint {
+                    method: method.clone(),
+                    path: entry.pattern.to_string(),
+                    reason: format!("unknown guard '{name}'"),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that a handler is registered for the controller of every route,
+    /// both controllers for canary routes, e.g. at startup once the handlers
+    /// are. A request to a route without one is answered following the
+    /// `MissingHandlerPolicy`.
     ///
-    /// * `req` - The incoming `http::Request`. The body is expected to be `Vec<u8>`.
+    /// # Errors
     ///
-    /// # Returns
+    /// Returns a `RouterError::MissingHandler` for the first route without a
+    /// handler.
+    pub fn validate_handlers(&self) -> Result<(), RouterError> {
+        for (method, entry) in self.routes.table.routes() {
+            let controllers = match &entry.canary {
+                Some(canary) => vec![canary.stable.as_str(), canary.candidate.as_str()],
+                None => vec![&*entry.controller],
+            };
+            if let Some(controller) = controllers
+                .into_iter()
+                .find(|c| self.handler(entry, c).is_none())
+            {
+                return Err(RouterError::MissingHandler {
+                    method: method.clone(),
+                    path: entry.pattern.to_string(),
+                    controller: controller.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Prepares the router for traffic, e.g. before the server binds its
+    /// socket, so that the first requests aren't slower than the rest: builds
+    /// the `Allow` values of 405 Method Not Allowed responses, otherwise built
+    /// by the first one of each path, and matches a synthetic request to every
+    /// route, with each path parameter set to `warmup`.
     ///
-    /// An `http::Response` with a `Vec<u8>` body, produced by the matched handler
-    /// or an appropriate HTTP error response.
-    pub fn route(&self, req: Request<Vec<u8>>) -> Response<Vec<u8>> {
-        let path = req.uri().path();
-        let method = req.method();
-        eprintln!("Processing request: {method} {path}");
-        eprintln!(
-            "Available methods: {:?}",
-            self.trees.keys().collect::<Vec<_>>()
-        );
+    /// The report lists every route, and the problems found along the way:
+    /// a synthetic request matching no route, a `body_template`
+    /// using a path parameter its route doesn't have, and, with the `schema`
+    /// feature, a `request_schema` or `response_schema` rejecting every value.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use generic_http_router::Router;
+    ///
+    /// let router = Router::new("routes.json").unwrap();
+    /// let report = router.warm_up();
+    /// for problem in &report.problems {
+    ///     eprintln!("{} {}: {}", problem.method, problem.pattern, problem.problem);
+    /// }
+    /// ```
+    pub fn warm_up(&self) -> WarmupReport {
+        let mut report = WarmupReport::default();
+        let mut problem = |method: &Method, entry: &RouteEntry, problem: String| {
+            report.problems.push(WarmupProblem {
+                method: method.clone(),
+                pattern: entry.pattern.to_string(),
+                problem,
+            });
+        };
+        let mut routes: Vec<_> = self.routes.table.routes().collect();
+        routes.sort_by(|(a_method, a), (b_method, b)| {
+            (&*a.pattern, a_method.as_str()).cmp(&(&*b.pattern, b_method.as_str()))
+        });
+        let mut timings = Vec::new();
+
+        let started = self.clock.now();
+        self.routes
+            .table
+            .warm_up_allowed(self.auto_head, self.auto_options);
+        timings.push(("allowed_methods", self.clock.now() - started));
+
+        let started = self.clock.now();
+        let mut warmed = Vec::with_capacity(routes.len());
+        for &(method, entry) in &routes {
+            let sample_path = warmup::sample_path(&entry.pattern);
+            let matched = match self.routes.table.lookup(method, &sample_path) {
+                Lookup::Found { entry: found, .. } if std::ptr::eq(found, entry) => true,
+                // A more specific route with the sample's literal segments,
+                // e.g. `/users/warmup` for `/users/:id`, isn't a problem.
+                Lookup::Found { .. } => false,
+                _ => {
+                    problem(
+                        method,
+                        entry,
+                        format!("its synthetic request to {sample_path} matched no route"),
+                    );
+                    false
+                }
+            };
+            warmed.push(WarmedRoute {
+                method: method.clone(),
+                pattern: entry.pattern.to_string(),
+                sample_path,
+                matched,
+            });
+        }
+        timings.push(("routes", self.clock.now() - started));
 
-        // First check if the path exists in any method's tree
-        let mut allowed_methods = Vec::new();
-        for (tree_method, tree) in &self.trees {
-            if tree.at(path).is_ok() {
-                allowed_methods.push(tree_method);
+        let started = self.clock.now();
+        for &(method, entry) in &routes {
+            let Some(template) = &entry.endpoint.body_template else {
+                continue;
+            };
+            let Ok(template) =
+                BodyTemplate::compile(template, entry.endpoint.content_type.as_deref())
+            else {
+                continue;
+            };
+            for (name, _) in template.params() {
+                if !entry.param_names.iter().any(|param| **param == *name) {
+                    problem(
+                        method,
+                        entry,
+                        format!(
+                            "the body_template uses {{params.{name}}}, which the path doesn't have"
+                        ),
+                    );
+                }
             }
         }
+        timings.push(("templates", self.clock.now() - started));
 
-        // If we found the path in some trees but not for this method, return 405
-        if !allowed_methods.is_empty() && !allowed_methods.contains(&method) {
-            let mut response = HttpResponse::new(StatusCode::METHOD_NOT_ALLOWED, Vec::new());
-            let allow_header = allowed_methods
-                .iter()
-                .map(|m| m.as_str())
-                .collect::<Vec<_>>()
-                .join(", ");
-            response.add_header(http::header::ALLOW, &allow_header);
-            return response.into();
-        }
-
-        // Get the tree for the current method
-        let tree = match self.trees.get(method) {
-            Some(tree) => tree,
-            // If no routes are defined for this method and we didn't find the path
-            // in any other method's tree, it's a 404 Not Found
-            None => return HttpResponse::new(StatusCode::NOT_FOUND, Vec::new()).into(),
-        };
-
-        // Attempt to match the request's path against the tree.
-        match tree.at(path) {
-            // A route was successfully matched.
-            Ok(match_result) => {
-                let controller_name = match_result.value;
-
-                // Check if a handler has been registered for this controller name.
-                match self.handlers.get(controller_name) {
-                    Some(handler) => {
-                        // A handler exists. Extract path parameters and create our custom HttpRequest.
-                        let params: HashMap<String, String> = match_result
-                            .params
-                            .iter()
-                            .map(|(k, v)| (k.to_string(), v.to_string()))
-                            .collect();
-
-                        let (parts, body) = req.into_parts();
-                        let custom_req = HttpRequest {
-                            uri: parts.uri,
-                            method: parts.method,
-                            headers: parts.headers,
-                            params,
-                            body,
-                        };
-
-                        // Invoke the handler and return its response.
-                        handler.handle(custom_req).into()
-                    }
-                    // The route is in the JSON, but no handler was registered.
-                    // This is a server misconfiguration.
-                    None => {
-                        let body =
-                            format!("Error: Handler for '{controller_name}' is not implemented.");
-                        HttpResponse::new(StatusCode::NOT_IMPLEMENTED, body.into_bytes()).into()
+        #[cfg(feature = "schema")]
+        {
+            let started = self.clock.now();
+            for &(method, entry) in &routes {
+                let schemas = [
+                    ("request_schema", &entry.schema),
+                    ("response_schema", &entry.response_schema),
+                ];
+                for (field, schema) in schemas {
+                    if schema
+                        .as_ref()
+                        .is_some_and(|schema| schema.rejects_everything())
+                    {
+                        problem(method, entry, format!("the {field} rejects every value"));
                     }
                 }
             }
-            // No route matched the path.
-            Err(_) => HttpResponse::new(StatusCode::NOT_FOUND, Vec::new()).into(),
+            timings.push(("schemas", self.clock.now() - started));
+        }
+
+        report.timings = timings;
+        report.routes = warmed;
+        report
+    }
+
+    /// Registers one handler instance for several controllers, e.g. a generic
+    /// CRUD handler that dispatches on the route pattern. The instance is shared,
+    /// so any state it holds, such as a connection pool, is too.
+    pub fn register_shared(
+        &mut self,
+        controller_names: &[&str],
+        handler: Arc<dyn HttpHandler + Send + Sync>,
+    ) {
+        let handler: Arc<dyn Handler + Send + Sync> = Arc::new(handler);
+        for name in controller_names {
+            let name = self.routes.controller_name(name.to_string());
+            self.handlers.insert(name, Arc::clone(&handler));
+        }
+    }
+
+    /// Registers an asynchronous handler for a given controller name, used by
+    /// [`Router::route_async`].
+    #[cfg(feature = "async")]
+    #[deprecated(note = "use `register_handler` with `async_handler::Async`")]
+    pub fn register_async<S: Into<String>>(
+        &mut self,
+        controller_name: S,
+        handler: Box<dyn AsyncHttpHandler + Send + Sync>,
+    ) {
+        self.register_handler(controller_name, async_handler::Async(handler));
+    }
+
+    /// Sets whether 404 Not Found responses suggest the route patterns closest
+    /// to the request's path, up to three, e.g. `/users/:id/orders` for
+    /// `/users/123/order` (off by default). Only requests matching the
+    /// [`SuggestionTrigger`] get them, those accepting JSON or with an
+    /// `X-Debug` header by default.
+    ///
+    /// The suggestions are in the `suggestions` array of the problem document,
+    /// if problem responses are enabled, and in the
+    /// [`RequestSummary::suggestions`] of the `on_response` hook.
+    pub fn enable_404_suggestions(&mut self, enabled: bool) {
+        self.suggestions = enabled;
+    }
+
+    /// Sets which requests without a route get suggestions, with
+    /// `enable_404_suggestions`.
+    pub fn set_404_suggestion_trigger(&mut self, trigger: SuggestionTrigger) {
+        self.suggestion_trigger = trigger;
+    }
+
+    /// Sets what the router does with requests to routes whose controller has
+    /// no registered handler (a 501 Not Implemented by default).
+    pub fn set_missing_handler_policy(&mut self, policy: MissingHandlerPolicy) {
+        self.missing_handler_policy = policy;
+    }
+
+    /// Sets the handler called for routes without a registered handler, with
+    /// `MissingHandlerPolicy::Fallback`.
+    pub fn set_fallback_handler(&mut self, handler: Box<dyn HttpHandler + Send + Sync>) {
+        self.fallback = Some(Arc::from(handler));
+    }
+
+    /// Sets the handler of `OPTIONS *` requests, asking about the server's
+    /// capabilities rather than a resource's. It takes precedence over the
+    /// automatic response of [`Router::set_auto_options`].
+    pub fn register_server_options(&mut self, handler: Box<dyn HttpHandler + Send + Sync>) {
+        self.server_options = Some(Arc::from(handler));
+    }
+
+    /// Sets the handler timeout for routes without their own `timeout_ms`
+    /// (none by default).
+    ///
+    /// The deadline is available to handlers as `HttpRequest::deadline`, and is
+    /// enforced by [`Router::route_async`] for asynchronous handlers.
+    pub fn set_default_timeout(&mut self, timeout: Option<Duration>) {
+        self.default_timeout = timeout;
+    }
+
+    /// Sets how [`Router::route_async`] coalesces identical concurrent `GET`
+    /// requests to routes with `coalesce`: how long they wait for the first
+    /// one's response, 10 seconds by default, and whether requests with an
+    /// `Authorization` header are coalesced, only with those with the same
+    /// one, which they aren't by default.
+    ///
+    /// Identical requests have the same method, path and query, and the same
+    /// values of the headers the response lists in `Vary`. A response that is
+    /// streamed, sets a cookie or has `Vary: *` isn't shared, nor the 504
+    /// Gateway Timeout of a handler exceeding its timeout: the waiting
+    /// requests run the handler themselves. They skip the route's
+    /// `max_concurrency` and circuit breaker, but not its guards or
+    /// authentication.
+    #[cfg(feature = "async")]
+    pub fn set_coalescing(&mut self, options: CoalesceOptions) {
+        self.coalescing = options;
+    }
+
+    /// Sets the status and `Retry-After` (in whole seconds) of requests turned
+    /// away by a route's `max_concurrency`: 503 Service Unavailable and 1
+    /// second by default, or e.g. 429 Too Many Requests.
+    pub fn set_concurrency_limit_response(&mut self, status: StatusCode, retry_after: Duration) {
+        self.concurrency_limit_response = LimitResponse {
+            status,
+            retry_after,
+        };
+    }
+
+    /// Returns the requests being routed, those running and waiting for the
+    /// handler of each route with a `max_concurrency`, and the circuit of
+    /// each controller with a `circuit_breaker` that got a request.
+    ///
+    /// Each routing table has its own limits: after a `reload` or a route
+    /// change, requests still running under the previous table aren't
+    /// counted. Circuits are kept across reloads.
+    pub fn stats(&self) -> RouterStats {
+        let mut routes: Vec<RouteStats> = self
+            .routes
+            .table
+            .routes()
+            .filter_map(|(method, entry)| {
+                let limit = entry.concurrency.as_ref()?;
+                let (in_flight, queued) = limit.counts();
+                Some(RouteStats {
+                    method: method.clone(),
+                    path: entry.pattern.to_string(),
+                    in_flight,
+                    queued,
+                    max_concurrency: limit.max(),
+                })
+            })
+            .collect();
+        routes.sort_by(|a, b| (&a.path, a.method.as_str()).cmp(&(&b.path, b.method.as_str())));
+        RouterStats {
+            in_flight: self.shutdown_controller().in_flight(),
+            routes,
+            circuits: self.circuits.stats(self.clock.now()),
+        }
+    }
+
+    /// Closes the circuit of `controller`, forgetting its failures. Returns
+    /// `false` if it has no circuit, i.e. no route with a `circuit_breaker`
+    /// sent it a request yet.
+    pub fn reset_circuit(&self, controller: &str) -> bool {
+        self.circuits.reset(controller)
+    }
+
+    /// Reads the deadline of requests from the header `name`, e.g.
+    /// `X-Request-Deadline-Ms`, in `format`. The deadline is added to the
+    /// request's extensions as a [`Deadline`], and `HttpRequest::deadline` is
+    /// the earlier of it and the route's timeout, enforced like the timeout
+    /// by [`Router::route_async`]. Values that aren't a number of
+    /// milliseconds are ignored.
+    pub fn set_deadline_header(&mut self, name: http::header::HeaderName, format: DeadlineFormat) {
+        self.deadline_header = Some((name, format));
+    }
+
+    /// Sets a hook called with a [`RequestSummary`] after each request is routed,
+    /// e.g. for access logs or metrics.
+    pub fn on_response<F>(&mut self, hook: F)
+    where
+        F: Fn(&RequestSummary) + Send + Sync + 'static,
+    {
+        self.on_response = Some(Arc::new(hook));
+    }
+
+    /// Adds a transformer changing every response, e.g. to add a header or
+    /// inject a snippet into HTML bodies; see [`ResponseTransformer`].
+    /// Transformers run in the order they were added, each getting the
+    /// response of the previous one.
+    ///
+    /// A response's `Content-Length` header, if it has one, is updated after
+    /// the last transformer, and the router's default headers are added
+    /// after it too. The `on_response` hook sees the transformed response.
+    pub fn add_response_transformer(
+        &mut self,
+        transformer: Box<dyn ResponseTransformer + Send + Sync>,
+    ) {
+        self.response_transformers.push(Arc::from(transformer));
+    }
+
+    /// Sets a callback called with a [`RequestSummary`] after each request that
+    /// took at least `threshold` to route, e.g. to log slow requests.
+    ///
+    /// The time is measured around the whole of routing, including rejected
+    /// requests and built-in error responses, like the `on_response` hook's.
+    pub fn on_slow_request<F>(&mut self, threshold: Duration, callback: F)
+    where
+        F: Fn(&RequestSummary) + Send + Sync + 'static,
+    {
+        self.on_slow_request = Some((threshold, Arc::new(callback)));
+    }
+
+    /// Enables or disables the `X-Response-Time-Ms` response header (disabled
+    /// by default), telling how long the router took to answer, in
+    /// milliseconds with a fractional part, e.g. `12.345`.
+    ///
+    /// A header set by the handler is left as is.
+    pub fn enable_timing_header(&mut self, enabled: bool) {
+        self.timing_header = enabled;
+    }
+
+    /// Replaces the clock the router tells the time with (the system's by
+    /// default), e.g. with a [`testing::MockClock`] in tests. See [`Clock`] for
+    /// what it's used for.
+    pub fn set_clock<C>(&mut self, clock: C)
+    where
+        C: Clock + Send + Sync + 'static,
+    {
+        self.clock = Arc::new(clock);
+    }
+
+    /// Replaces the random number generator drawing the random canary splits
+    /// ([`SystemRng`] by default), e.g. with a [`testing::SeededRng`] in tests.
+    pub fn set_rng<R>(&mut self, rng: R)
+    where
+        R: Rng + Send + Sync + 'static,
+    {
+        self.rng = Arc::new(rng);
+    }
+
+    /// Returns the router with the clock `clock`, as set with
+    /// [`Router::set_clock`], e.g.
+    /// `Router::from_json_str(config)?.with_clock(MockClock::new())`.
+    pub fn with_clock<C>(mut self, clock: C) -> Self
+    where
+        C: Clock + Send + Sync + 'static,
+    {
+        self.set_clock(clock);
+        self
+    }
+
+    /// Returns the router with the random number generator `rng`, as set with
+    /// [`Router::set_rng`].
+    pub fn with_rng<R>(mut self, rng: R) -> Self
+    where
+        R: Rng + Send + Sync + 'static,
+    {
+        self.set_rng(rng);
+        self
+    }
+
+    /// Sets a callback called with the request, the primary handler's response
+    /// and the mirror handler's response for each request to an endpoint with a
+    /// `mirror_to` controller, e.g. to log differences between the two.
+    ///
+    /// The request passed to the callback has no extensions, and the body of a
+    /// streamed primary response isn't available to it.
+    pub fn on_mirror_result<F>(&mut self, callback: F)
+    where
+        F: Fn(&HttpRequest, &HttpResponse, &HttpResponse) + Send + Sync + 'static,
+    {
+        self.on_mirror = Some(Arc::new(callback));
+    }
+
+    /// Enables or disables calling mirror handlers in `route` and
+    /// `route_streaming` (disabled by default).
+    ///
+    /// These can't respond before the mirror handler has run, so it's called
+    /// inline after the primary handler, adding its latency to every mirrored
+    /// request. [`Router::route_async`] always mirrors, in a spawned task.
+    pub fn set_sync_mirroring(&mut self, enabled: bool) {
+        self.sync_mirroring = enabled;
+    }
+
+    /// Records each request the router handles and its response with `sink`,
+    /// e.g. a [`JsonFileSink`](record::JsonFileSink) whose file
+    /// [`testing::replay`] replays. The headers and query parameters listed in
+    /// [`redact::debug_options`] are recorded as `<redacted>`.
+    #[cfg(feature = "record")]
+    pub fn enable_recording(&mut self, sink: Box<dyn RecordingSink + Send + Sync>) {
+        self.recording = Some(Arc::from(sink));
+    }
+
+    /// Starts recording a request, if recording is enabled.
+    #[cfg(feature = "record")]
+    fn start_recording(&self, req: &Request<Bytes>) -> Option<PendingRecording> {
+        let sink = self.recording.as_ref()?;
+        Some(PendingRecording::new(sink, req, self.clock.now()))
+    }
+
+    /// Returns the handle shutting the router down gracefully: turning new
+    /// requests away, and waiting for those in flight to finish.
+    pub fn shutdown_controller(&self) -> ShutdownHandle {
+        ShutdownHandle::new(Arc::clone(&self.shutdown))
+    }
+
+    /// Sets the paths still routed while shutting down, e.g. those of health
+    /// checks served by handlers. The built-in health endpoints always are.
+    pub fn set_shutdown_exempt_paths(&mut self, paths: Vec<String>) {
+        self.shutdown_exempt_paths = paths;
+    }
+
+    /// Removes the handler registered for `controller_name`, returning it. Its routes
+    /// get a 501 Not Implemented until another handler is registered.
+    ///
+    /// If a copy of the router (such as an older `SharedRouter` version) still
+    /// uses the handler, both refer to the same instance.
+    pub fn unregister(&mut self, controller_name: &str) -> Option<Arc<dyn Handler + Send + Sync>> {
+        let name = self.routes.controller_name(controller_name.to_string());
+        self.handlers.remove(&name)
+    }
+
+    /// Adds a route at runtime, rebuilding the routing table.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError` if the endpoint's path is not a valid pattern or
+    /// conflicts with a route registered for the same method. The router is left
+    /// unchanged in that case.
+    pub fn add_route(&mut self, endpoint: Endpoint) -> Result<(), RouterError> {
+        let endpoint = self.routes.expand_aliases(endpoint)?;
+        let table = self.routes.table.with_endpoint(endpoint)?;
+        self.instances = build_instances(&table, &self.factories, &self.instances)?;
+        self.routes.table = table;
+        Ok(())
+    }
+
+    /// Removes the route registered for `method` with exactly the pattern
+    /// `path_pattern` (e.g. `"/users/:id"`), rebuilding the routing table.
+    /// Returns `false` if there's no such route.
+    ///
+    /// Once removed, the path gets a 404 Not Found, or a 405 Method Not Allowed
+    /// listing the remaining methods if routes for other methods match it.
+    pub fn remove_route(&mut self, method: &Method, path_pattern: &str) -> bool {
+        match self.routes.table.without_route(method, path_pattern) {
+            Some(table) => {
+                self.routes.table = table;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns a copy of the router, sharing its handlers, maintenance state and
+    /// response cache.
+    pub(crate) fn snapshot(&self) -> Self {
+        Self {
+            routes: self.routes.clone(),
+            default_headers: self.default_headers.clone(),
+            api_keys: self.api_keys.clone(),
+            secrets: Arc::clone(&self.secrets),
+            error_pages: self.error_pages.clone(),
+            limits: self.limits,
+            handlers: self.handlers.clone(),
+            prefix_handlers: self.prefix_handlers.clone(),
+            factories: self.factories.clone(),
+            instances: self.instances.clone(),
+            guards: self.guards.clone(),
+            missing_handler_policy: self.missing_handler_policy,
+            fallback: self.fallback.clone(),
+            server_options: self.server_options.clone(),
+            strict_config: self.strict_config,
+            route_filter: self.route_filter.clone(),
+            auto_head: self.auto_head,
+            auto_options: self.auto_options,
+            trace_policy: self.trace_policy,
+            normalize_paths: self.normalize_paths,
+            path_decoding: self.path_decoding,
+            max_buffered_body_size: self.max_buffered_body_size,
+            max_response_size: self.max_response_size,
+            problem_responses: self.problem_responses,
+            required_header_status: self.required_header_status,
+            default_timeout: self.default_timeout,
+            #[cfg(feature = "async")]
+            coalescing: self.coalescing,
+            #[cfg(feature = "async")]
+            flights: Arc::clone(&self.flights),
+            concurrency_limit_response: self.concurrency_limit_response,
+            deadline_header: self.deadline_header.clone(),
+            on_response: self.on_response.clone(),
+            on_slow_request: self.on_slow_request.clone(),
+            response_transformers: self.response_transformers.clone(),
+            timing_header: self.timing_header,
+            clock: Arc::clone(&self.clock),
+            rng: Arc::clone(&self.rng),
+            sync_mirroring: self.sync_mirroring,
+            on_mirror: self.on_mirror.clone(),
+            health: self.health.clone(),
+            introspection: self.introspection.clone(),
+            batch: self.batch.clone(),
+            canonicalization: self.canonicalization.clone(),
+            readiness_checks: self.readiness_checks.clone(),
+            maintenance: Arc::clone(&self.maintenance),
+            #[cfg(feature = "record")]
+            recording: self.recording.clone(),
+            shutdown: Arc::clone(&self.shutdown),
+            shutdown_exempt_paths: self.shutdown_exempt_paths.clone(),
+            suggestions: self.suggestions,
+            suggestion_trigger: self.suggestion_trigger.clone(),
+            circuits: Arc::clone(&self.circuits),
+            #[cfg(feature = "cache")]
+            cache: Arc::clone(&self.cache),
+            #[cfg(feature = "session")]
+            sessions: self.sessions.clone(),
+            #[cfg(feature = "idempotency")]
+            idempotency: self.idempotency.clone(),
+            #[cfg(feature = "csrf")]
+            csrf: self.csrf.clone(),
+            #[cfg(feature = "jwt")]
+            jwt: self.jwt.clone(),
+            #[cfg(feature = "schema")]
+            response_validation: self.response_validation,
+        }
+    }
+
+    /// Sets the headers added to every response, including the router's own
+    /// error responses, replacing those of the configuration's
+    /// `default_response_headers` (until the next `reload`). A header the
+    /// response already has, e.g. set by the handler, is kept as is.
+    pub fn set_default_headers(&mut self, headers: HeaderMap) {
+        self.default_headers = headers;
+    }
+
+    /// Sets the API keys of routes with `"auth": "api_key"`, replacing those of
+    /// the configuration's `api_keys` section (until the next `reload`). Their
+    /// header and query parameter are added to the redacted ones of
+    /// [`redact::debug_options`].
+    pub fn set_api_keys(&mut self, api_keys: ApiKeyProvider) {
+        self.api_keys = Some(redacted(api_keys));
+    }
+
+    /// Replaces where the secrets of endpoint signatures are read from (the
+    /// environment by default), e.g. to read them from a vault.
+    pub fn set_secret_provider<P>(&mut self, secrets: P)
+    where
+        P: SecretProvider + Send + Sync + 'static,
+    {
+        self.secrets = Arc::new(secrets);
+    }
+
+    /// Sets the status of responses to requests missing a header of the
+    /// `required_headers`, a 400 Bad Request by default, e.g. to a 403
+    /// Forbidden for headers an internal gateway adds.
+    pub fn set_required_header_status(&mut self, status: StatusCode) {
+        self.required_header_status = status;
+    }
+
+    /// Sets whether `reload` fails with a `RouterError::UnknownField` for an
+    /// unknown endpoint field, instead of returning a
+    /// `LoadWarning::UnknownField`, and with a
+    /// `RouterError::InconsistentController` for endpoints of a controller
+    /// secured differently (disabled by default). Fields starting with `x-`
+    /// are always allowed, and kept in `Endpoint::metadata`.
+    ///
+    /// `Router::new` can't be strict, since it loads the configuration before
+    /// this is set: treat the warnings of
+    /// [`Router::new_with_report`] as errors instead.
+    pub fn set_strict_config(&mut self, strict: bool) {
+        self.strict_config = strict;
+    }
+
+    /// Enables or disables answering `HEAD` requests with the handler of the
+    /// `GET` route for the path, if there's no `HEAD` route (enabled by default).
+    /// The handler sees the `HEAD` method, and the router drops the body of its
+    /// response.
+    pub fn set_auto_head(&mut self, enabled: bool) {
+        self.auto_head = enabled;
+    }
+
+    /// Enables or disables answering `OPTIONS` requests to paths without an
+    /// `OPTIONS` route with a 204 No Content listing the allowed methods in its
+    /// `Allow` header (disabled by default).
+    ///
+    /// `OPTIONS *` requests then get the methods with any route. Without
+    /// automatic responses or a handler set with
+    /// [`Router::register_server_options`], they get a 501 Not Implemented.
+    pub fn set_auto_options(&mut self, enabled: bool) {
+        self.auto_options = enabled;
+    }
+
+    /// Sets how `TRACE` requests are answered, [`TracePolicy::Reject`] by
+    /// default: with a 405 Method Not Allowed even if a `TRACE` route matches.
+    pub fn set_trace_policy(&mut self, policy: TracePolicy) {
+        self.trace_policy = policy;
+    }
+
+    /// Enables or disables request path normalization (enabled by default).
+    ///
+    /// When enabled, repeated slashes are collapsed and `.` / `..` segments are
+    /// resolved before matching, and any path escaping the root is rejected with
+    /// a 400 Bad Request. Handlers can still read the path as received from
+    /// `HttpRequest::original_uri`.
+    pub fn set_normalize_paths(&mut self, enabled: bool) {
+        self.normalize_paths = enabled;
+    }
+
+    /// Sets how percent-encoded bytes in request paths are handled. Defaults to
+    /// [`PathDecoding::Reject`].
+    ///
+    /// Routes are matched against the still-encoded path, and path parameter
+    /// values are percent-decoded as UTF-8 before reaching the handler. With
+    /// `Reject`, requests whose path has invalid percent-encoding, or whose
+    /// encoded bytes aren't valid UTF-8, get a 400 Bad Request; with `Lossy`,
+    /// invalid escapes are kept as is and invalid UTF-8 is replaced.
+    pub fn set_path_decoding(&mut self, decoding: PathDecoding) {
+        self.path_decoding = decoding;
+    }
+
+    /// Computes the path used for matching, applying normalization and the
+    /// rewrite rules in that order.
+    ///
+    /// Returns `Ok(None)` if the path is used as received, or `Err` with the
+    /// status code to reply with if the path is rejected.
+    fn effective_path(&self, path: &str) -> Result<Option<String>, StatusCode> {
+        let normalized = if self.normalize_paths {
+            path::normalize(path).ok_or(StatusCode::BAD_REQUEST)?
+        } else {
+            Cow::Borrowed(path)
+        };
+
+        match rewrite::apply_all(&self.routes.rewrites, &normalized) {
+            Some(rewritten) => Ok(Some(rewritten)),
+            None => match normalized {
+                Cow::Owned(normalized) => Ok(Some(normalized)),
+                Cow::Borrowed(_) => Ok(None),
+            },
+        }
+    }
+
+    /// Sets the maximum size of a streamed response body that `route` buffers
+    /// into memory. Defaults to [`DEFAULT_MAX_BUFFERED_BODY_SIZE`].
+    ///
+    /// Responses whose streamed body grows past the limit, or whose stream fails,
+    /// are replaced with an empty 500 Internal Server Error. `route_streaming`
+    /// isn't affected by this limit.
+    pub fn set_max_buffered_body_size(&mut self, limit: usize) {
+        self.max_buffered_body_size = limit;
+    }
+
+    /// Sets the largest response body, in bytes, of routes without their own
+    /// `max_response_size` (unlimited by default), e.g. to stop a handler
+    /// serializing an unbounded query result from exhausting memory.
+    ///
+    /// The limit is checked once the response transformers are done. A
+    /// response over it is replaced with a 500 Internal Server Error
+    /// explaining why, reported in the `RequestSummary::detail` of the
+    /// `on_response` hook and, with the `logging` feature, in an error log
+    /// naming the route's controller. A streamed body is ended with an error
+    /// once it grows past the limit: `route` answers it with the same 500,
+    /// while `route_streaming` has already sent the head, so the stream is
+    /// cut short and only the log reports it.
+    pub fn set_max_response_size(&mut self, limit: Option<usize>) {
+        self.max_response_size = limit;
+    }
+
+    /// Enables or disables formatting the router's built-in error responses as
+    /// RFC 7807 `application/problem+json` documents (disabled by default).
+    ///
+    /// This applies to the 400 Bad Request for rejected paths, 404 Not Found,
+    /// 405 Method Not Allowed (with the allowed methods as an `allowed` array),
+    /// 501 Not Implemented for missing handlers and the 500 Internal Server Error
+    /// for streamed bodies `route` fails to buffer. Responses returned by
+    /// handlers are never changed.
+    pub fn use_problem_responses(&mut self, enabled: bool) {
+        self.problem_responses = enabled;
+    }
+
+    /// Enables sessions: each matched request gets its [`Session`], loaded
+    /// before the route's guards and handler run and saved once the handler
+    /// returns. See [`SessionMiddleware`] for the cookie it uses.
+    ///
+    /// A session that the store fails to save, or that no random ID can be
+    /// generated for, gets the request a 500 Internal Server Error instead of
+    /// the handler's response.
+    #[cfg(feature = "session")]
+    pub fn use_sessions(&mut self, sessions: SessionMiddleware) {
+        self.sessions = Some(Arc::new(sessions));
+    }
+
+    /// Enables idempotency keys for the routes with `"idempotent": true`: a
+    /// retried request with the same `Idempotency-Key` header gets the response
+    /// to the first one, checked after the route's guards and validation. See
+    /// [`IdempotencyMiddleware`] for what's recorded and rejected.
+    #[cfg(feature = "idempotency")]
+    pub fn use_idempotency(&mut self, idempotency: IdempotencyMiddleware) {
+        self.idempotency = Some(Arc::new(idempotency));
+    }
+
+    /// Enables CSRF protection for the routes without `"csrf": false`: requests
+    /// with an unsafe method must send back the token of the CSRF cookie, or
+    /// get a 403 Forbidden before the route's guards and handler run. See
+    /// [`CsrfMiddleware`].
+    #[cfg(feature = "csrf")]
+    pub fn use_csrf(&mut self, csrf: CsrfMiddleware) {
+        self.csrf = Some(Arc::new(csrf));
+    }
+
+    /// Enables bearer token authentication: routes with `auth_scopes` get a
+    /// 401 Unauthorized without a valid token, or a 403 Forbidden if it lacks a
+    /// scope, before their guards and handler run. Handlers read the caller
+    /// with [`HttpRequest::identity`]. See [`JwtAuth`].
+    #[cfg(feature = "jwt")]
+    pub fn use_jwt_auth(&mut self, jwt: JwtAuth) {
+        self.jwt = Some(Arc::new(jwt));
+    }
+
+    /// Sets whether handlers' responses are validated against their route's
+    /// `response_schema`, e.g. to catch contract drift in tests (off by
+    /// default). See [`ResponseValidation`].
+    #[cfg(feature = "schema")]
+    pub fn set_response_validation(&mut self, mode: ResponseValidation) {
+        self.response_validation = mode;
+    }
+
+    /// Builds a built-in error response: a problem document created by `problem`
+    /// if problem responses are enabled, or a plain response with `body` otherwise.
+    /// With `error_pages`, it's marked for its template to replace the body.
+    fn error_response<B, F>(&self, status: StatusCode, body: B, problem: F) -> HttpResponse
+    where
+        B: Into<Body>,
+        F: FnOnce(Problem) -> Problem,
+    {
+        let mut response = if self.problem_responses {
+            HttpResponse::problem(problem(Problem::new(status)))
+        } else {
+            HttpResponse::new(status, body)
+        };
+        if self.error_pages.is_some() {
+            response.extensions.insert(BuiltInError);
+        }
+        response
+    }
+
+    /// Replaces the body of a built-in error response with its `error_pages`
+    /// template, if any.
+    fn apply_error_page(&self, req: Option<PageRequest>, response: &mut HttpResponse) {
+        if let (Some(templates), Some(req)) = (&self.error_pages, req) {
+            templates.apply(&req, response);
+        }
+    }
+
+    /// Routes an incoming HTTP request to the appropriate handler.
+    ///
+    /// This is the main method that performs the routing logic. Streamed response
+    /// bodies are buffered into memory, up to the limit set with
+    /// [`Router::set_max_buffered_body_size`]; use [`Router::route_streaming`]
+    /// to pass them through instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `req` - The incoming `http::Request`. The body can be anything convertible
+    ///   into `Bytes`, such as `Bytes` itself or a `Vec<u8>`, without copying.
+    ///
+    /// # Returns
+    ///
+    /// An `http::Response` with a `Bytes` body, produced by the matched handler
+    /// or an appropriate HTTP error response.
+    pub fn route<B: Into<Bytes>>(&self, req: Request<B>) -> Response<Bytes> {
+        let resolution = self.resolve(&req);
+        self.dispatch(resolution, req)
+    }
+
+    /// Resolves a request without running its route's checks, middleware or
+    /// handler: matches it to a route, or decides the response the router
+    /// answers it with itself, e.g. a 404 Not Found or a 405 Method Not
+    /// Allowed. `route` is `resolve` followed by [`Router::dispatch`].
+    ///
+    /// This lets a server look at the matched controller, pattern, parameters
+    /// and endpoint before the handler runs, e.g. to make its own decisions.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use bytes::Bytes;
+    /// use generic_http_router::Router;
+    /// use http::Request;
+    ///
+    /// let router = Router::new("routes.json").unwrap();
+    /// let req = Request::get("/users/1").body(Bytes::new()).unwrap();
+    /// let resolution = router.resolve(&req);
+    /// if resolution.controller() == Some("users::get_by_id") {
+    ///     // ...
+    /// }
+    /// let response = router.dispatch(resolution, req);
+    /// ```
+    pub fn resolve<B>(&self, req: &Request<B>) -> Resolution<'_> {
+        #[cfg(feature = "logging")]
+        {
+            let span = request_span(req);
+            let mut resolution = span.in_scope(|| self.start(req));
+            resolution.span = span;
+            resolution
+        }
+        #[cfg(not(feature = "logging"))]
+        self.start(req)
+    }
+
+    /// Routes a request resolved with [`Router::resolve`], which must be the
+    /// request given to it, like `route`: runs the route's checks, middleware
+    /// and handler if it matched one, and returns the response.
+    pub fn dispatch<B: Into<Bytes>>(
+        &self,
+        resolution: Resolution<'_>,
+        req: Request<B>,
+    ) -> Response<Bytes> {
+        self.route_resolved(resolution, req, true).into()
+    }
+
+    /// Routes an incoming HTTP request like `route`, with the connection details
+    /// the server knows about it, which handlers read with
+    /// [`HttpRequest::context`].
+    ///
+    /// Inserting the context into the request's extensions before calling
+    /// `route_streaming` or `route_async` has the same effect.
+    pub fn route_with_context<B: Into<Bytes>>(
+        &self,
+        mut req: Request<B>,
+        ctx: RequestContext,
+    ) -> Response<Bytes> {
+        req.extensions_mut().insert(ctx);
+        self.route(req)
+    }
+
+    /// Routes an incoming HTTP request to the appropriate handler, like `route`,
+    /// but passes streamed response bodies through without buffering them.
+    pub fn route_streaming<B: Into<Bytes>>(&self, req: Request<B>) -> Response<Body> {
+        let resolution = self.resolve(&req);
+        self.route_resolved(resolution, req, false).into()
+    }
+
+    /// Routes a resolved request, calling its handler inline, for `dispatch`
+    /// and `route_streaming`; a streamed response body is buffered if
+    /// `buffered`.
+    fn route_resolved<B: Into<Bytes>>(
+        &self,
+        resolution: Resolution<'_>,
+        req: Request<B>,
+        buffered: bool,
+    ) -> HttpResponse {
+        let Resolution {
+            resolved,
+            started,
+            page_request,
+            in_flight: _in_flight,
+            #[cfg(feature = "logging")]
+            span,
+            ..
+        } = resolution;
+        #[cfg(feature = "logging")]
+        let _span = span.entered();
+        let req = req.map(Into::into);
+        let completion = self.completion(&req, started, page_request);
+        let response = self.respond(resolved, req);
+        self.complete(completion, Outcome::Completed, response, buffered)
+    }
+
+    /// Routes an incoming HTTP request like `route`, awaiting the handler if it
+    /// answers with `HandlerFuture::Pending`, e.g. an `AsyncHttpHandler`.
+    ///
+    /// The handler future is dropped once the route's timeout (`timeout_ms`, or
+    /// the default set with [`Router::set_default_timeout`]) elapses, and the
+    /// request gets a 504 Gateway Timeout with a problem document body.
+    /// Synchronous handlers are called inline, and can't be timed out.
+    #[cfg(feature = "async")]
+    pub async fn route_async<B: Into<Bytes>>(&self, req: Request<B>) -> Response<Bytes> {
+        #[cfg(feature = "logging")]
+        {
+            use tracing::Instrument;
+            let span = request_span(&req);
+            self.route_async_in_span(req).instrument(span).await
+        }
+        #[cfg(not(feature = "logging"))]
+        self.route_async_in_span(req).await
+    }
+
+    /// Performs `route_async`, within its request span with the `logging`
+    /// feature.
+    #[cfg(feature = "async")]
+    async fn route_async_in_span<B: Into<Bytes>>(&self, req: Request<B>) -> Response<Bytes> {
+        let Resolution {
+            resolved,
+            started,
+            page_request,
+            in_flight: _in_flight,
+            ..
+        } = self.start(&req);
+        let req = req.map(Into::into);
+        let completion = self.completion(&req, started, page_request);
+        let dispatch = match resolved {
+            Resolved::Batch(limits) => Dispatch::Respond(match batch::parse(req.body(), limits) {
+                Ok(requests) => {
+                    let mut responses = Vec::with_capacity(requests.len());
+                    for req in requests {
+                        // Boxed, since the sub-requests are routed by this
+                        // function too.
+                        responses.push(Box::pin(self.route_async(req)).await);
+                    }
+                    batch::response(&responses)
+                }
+                Err((status, detail)) => self.batch_error(status, &detail),
+            }),
+            resolved => self.prepare(resolved, req),
+        };
+        let (response, outcome) = match dispatch {
+            Dispatch::Respond(response) => (response, Outcome::Completed),
+            Dispatch::Handle {
+                entry,
+                controller,
+                canary,
+                mut req,
+            } => {
+                let handling = self.before_handler(&mut req);
+                let joined = if entry.coalesce && matches!(req.method, Method::GET | Method::HEAD) {
+                    self.flights.join(&self.coalescing, controller, &req).await
+                } else {
+                    Joined::Lead(None)
+                };
+                let handled = match joined {
+                    Joined::Shared(response) => Ok((response, Outcome::Completed)),
+                    Joined::Lead(leader) => {
+                        match self.admit_async(entry, controller, req.deadline).await {
+                            Err(response) => Err(response),
+                            Ok((attempt, _permit)) => {
+                                let mirror = entry
+                                    .mirror_to
+                                    .as_deref()
+                                    .map(|name| (name, req.mirror_copy()));
+                                let (response, outcome) =
+                                    self.call_async(entry, controller, req).await;
+                                if let Some((name, req)) = mirror {
+                                    self.spawn_mirror(name, req, &response);
+                                }
+                                let response = self.handled(entry, attempt, response);
+                                if let Some(leader) = leader {
+                                    leader.land(&response, outcome);
+                                }
+                                Ok((response, outcome))
+                            }
+                        }
+                    }
+                };
+                match handled {
+                    Err(response) => (response, Outcome::Completed),
+                    Ok((response, outcome)) => (
+                        self.after_handler(handling, entry, canary, response),
+                        outcome,
+                    ),
+                }
+            }
+        };
+        self.complete(completion, outcome, response, true).into()
+    }
+
+    /// Calls the handler registered for a matched route, awaiting its response
+    /// within the request's deadline if it's pending.
+    #[cfg(feature = "async")]
+    async fn call_async(
+        &self,
+        entry: &RouteEntry,
+        controller: &str,
+        req: HttpRequest,
+    ) -> (HttpResponse, Outcome) {
+        let deadline = req.deadline;
+        match self.call_handler(entry, controller, req) {
+            HandlerFuture::Ready(response) => (response, Outcome::Completed),
+            HandlerFuture::Pending(future) => match deadline {
+                Some(deadline) => {
+                    let deadline = tokio::time::Instant::from_std(deadline);
+                    match tokio::time::timeout_at(deadline, future).await {
+                        Ok(response) => (response, Outcome::Completed),
+                        Err(_) => (self.timeout_response(), Outcome::TimedOut),
+                    }
+                }
+                None => (future.await, Outcome::Completed),
+            },
+        }
+    }
+
+    /// Spawns a task calling the mirror handler registered under `name`, and
+    /// then the `on_mirror_result` callback. The mirror handler is dropped if it
+    /// exceeds the request's deadline; a panic only ends the task.
+    #[cfg(feature = "async")]
+    fn spawn_mirror(&self, name: &str, req: HttpRequest, primary: &HttpResponse) {
+        let callback = self
+            .on_mirror
+            .clone()
+            .map(|callback| (callback, req.mirror_copy(), primary.buffered_copy()));
+        let report = move |mirror: HttpResponse| {
+            if let Some((callback, req, primary)) = callback {
+                callback(&req, &primary, &mirror);
+            }
+        };
+
+        let Some(handler) = self.registered(name).cloned() else {
+            return;
+        };
+        // The handler is called on a blocking thread, in case it's synchronous;
+        // a pending response is awaited there too.
+        let runtime = tokio::runtime::Handle::current();
+        tokio::task::spawn_blocking(move || {
+            let deadline = req.deadline;
+            let mirror = match handler.call(req) {
+                HandlerFuture::Ready(mirror) => mirror,
+                HandlerFuture::Pending(future) => {
+                    let mirror = runtime.block_on(async {
+                        match deadline {
+                            Some(deadline) => {
+                                let deadline = tokio::time::Instant::from_std(deadline);
+                                tokio::time::timeout_at(deadline, future).await.ok()
+                            }
+                            None => Some(future.await),
+                        }
+                    });
+                    match mirror {
+                        Some(mirror) => mirror,
+                        None => return,
+                    }
+                }
+            };
+            report(mirror);
+        });
+    }
+
+    /// Builds the 504 Gateway Timeout for a handler that exceeded its timeout.
+    #[cfg(feature = "async")]
+    fn timeout_response(&self) -> HttpResponse {
+        HttpResponse::problem(
+            Problem::new(StatusCode::GATEWAY_TIMEOUT)
+                .with_detail("The request handler did not complete in time."),
+        )
+    }
+
+    /// Saves or destroys the session of a handled request, setting its cookie on
+    /// the handler's response, or returns a 500 Internal Server Error if the
+    /// store fails.
+    #[cfg(feature = "session")]
+    fn finish_session(&self, session: &Session, mut response: HttpResponse) -> HttpResponse {
+        let Some(sessions) = &self.sessions else {
+            return response;
+        };
+        match sessions.finish(session, &mut response) {
+            Ok(()) => response,
+            Err(_error) => {
+                #[cfg(feature = "logging")]
+                tracing::error!(error = _error.as_str(), "failed to save the session");
+                self.error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal Server Error",
+                    |p| p.with_detail("The session could not be saved."),
+                )
+            }
+        }
+    }
+
+    /// Records the response to a request with an idempotency key, or releases
+    /// the key. A store failure is only logged: the handler has run, so its
+    /// response is sent anyway.
+    #[cfg(feature = "idempotency")]
+    fn finish_idempotency(&self, claim: Claim, response: &HttpResponse) {
+        if let Err(_error) = claim.finish(response, self.clock.system_time()) {
+            #[cfg(feature = "logging")]
+            tracing::error!(
+                error = _error.as_str(),
+                "failed to record the idempotent response"
+            );
+        }
+    }
+
+    /// Builds the response to a request turned away by the idempotency
+    /// middleware.
+    #[cfg(feature = "idempotency")]
+    fn idempotency_rejection(&self, rejection: Rejection) -> HttpResponse {
+        let (status, detail) = match rejection {
+            Rejection::InvalidKey => (
+                StatusCode::BAD_REQUEST,
+                "The request requires an Idempotency-Key header of 1 to 255 characters.",
+            ),
+            Rejection::Mismatch => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "The Idempotency-Key was already used for a request with another body.",
+            ),
+            Rejection::InFlight => (
+                StatusCode::CONFLICT,
+                "A request with the same Idempotency-Key is in progress.",
+            ),
+            Rejection::Store(_error) => {
+                #[cfg(feature = "logging")]
+                tracing::error!(
+                    error = _error.as_str(),
+                    "failed to check the idempotency key"
+                );
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "The idempotency key could not be checked.",
+                )
+            }
+        };
+        self.error_response(status, status.canonical_reason().unwrap_or_default(), |p| {
+            p.with_detail(detail)
+        })
+    }
+
+    /// Adds the default headers a response doesn't have.
+    fn add_default_headers(&self, headers: &mut HeaderMap) {
+        for name in self.default_headers.keys() {
+            if !headers.contains_key(name) {
+                for value in self.default_headers.get_all(name) {
+                    headers.append(name.clone(), value.clone());
+                }
+            }
+        }
+    }
+
+    /// Buffers a streamed response body, up to the configured limit.
+    fn buffer(&self, mut response: HttpResponse) -> HttpResponse {
+        match std::mem::take(&mut response.body).collect(self.max_buffered_body_size) {
+            Ok(bytes) => {
+                response.body = Body::Full(bytes);
+                response
+            }
+            Err(ref e) if let Some(too_large) = ResponseTooLarge::of(e) => {
+                self.oversized_response(too_large.limit, response.extensions)
+            }
+            Err(_) => {
+                let mut error =
+                    self.error_response(StatusCode::INTERNAL_SERVER_ERROR, Bytes::new(), |p| {
+                        p.with_detail("The response body could not be buffered.")
+                    });
+                error.extensions = response.extensions;
+                error
+            }
+        }
+    }
+
+    /// Replaces a response whose body is over its route's `max_response_size`,
+    /// or the router's, with a 500 Internal Server Error, and ends a streamed
+    /// body once it grows past it.
+    fn cap_response(&self, mut response: HttpResponse) -> HttpResponse {
+        let route = response.extensions.remove::<RouteResponseLimit>();
+        let Some(limit) = route
+            .as_ref()
+            .and_then(|route| route.limit)
+            .or(self.max_response_size)
+        else {
+            return response;
+        };
+        let controller = route.map(|route| route.controller);
+        let body = std::mem::take(&mut response.body);
+        match response_limit::cap(body, limit, controller.clone()) {
+            Ok(body) => {
+                response.body = body;
+                response
+            }
+            Err(_body) => {
+                #[cfg(feature = "logging")]
+                tracing::error!(
+                    controller = controller.as_deref(),
+                    limit,
+                    bytes = _body.as_bytes().map(Bytes::len),
+                    "response body exceeds max_response_size"
+                );
+                self.oversized_response(limit, response.extensions)
+            }
+        }
+    }
+
+    /// Returns the 500 Internal Server Error replacing a response over the
+    /// body size `limit`, with the extensions of the response it replaces.
+    fn oversized_response(&self, limit: usize, extensions: http::Extensions) -> HttpResponse {
+        let detail = response_limit::detail(limit);
+        let mut response = self.error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal Server Error",
+            |p| p.with_detail(detail.clone()),
+        );
+        response.extensions = extensions;
+        response.extensions.insert(OversizedResponse(detail));
+        response
+    }
+
+    /// Records what the `on_response` hook, the slow request callback and the
+    /// timing header need to know about a request before it's routed, if any
+    /// of them is enabled.
+    fn start_summary<B>(&self, req: &Request<B>) -> Option<Started> {
+        if !self.summary_needed() && !self.timing_header {
+            return None;
+        }
+        let header = |name| {
+            req.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Some(Started {
+            at: self.clock.now(),
+            method: req.method().clone(),
+            path: req.uri().path().to_string(),
+            version: req.version(),
+            request_id: header(X_REQUEST_ID),
+            client_ip: req
+                .extensions()
+                .get::<RequestContext>()
+                .and_then(|ctx| ctx.remote_addr)
+                .map(|addr| addr.ip()),
+            referer: header(http::header::REFERER.as_str()),
+            user_agent: header(http::header::USER_AGENT.as_str()),
+        })
+    }
+
+    /// Returns `true` if routed requests are summarized, for the `on_response`
+    /// hook or the slow request callback.
+    fn summary_needed(&self) -> bool {
+        self.on_response.is_some()
+            || self.on_slow_request.is_some()
+            || !self.response_transformers.is_empty()
+    }
+
+    /// Passes a response through the response transformers, in the order they
+    /// were added, and then updates its `Content-Length`, if it has one.
+    fn transform(
+        &self,
+        started: Option<&Started>,
+        outcome: Outcome,
+        mut response: HttpResponse,
+    ) -> HttpResponse {
+        let Some(started) = started.filter(|_| !self.response_transformers.is_empty()) else {
+            return response;
+        };
+        for transformer in &self.response_transformers {
+            let summary = started.summary(
+                response.status,
+                self.clock.now().saturating_duration_since(started.at),
+                outcome,
+                &response.extensions,
+                response.body.as_bytes().map(|body| body.len() as u64),
+            );
+            response = transformer.transform(&summary, response);
+        }
+        // The length of a `HEAD` response is the one of the `GET` response.
+        if started.method != Method::HEAD
+            && response.headers.contains_key(http::header::CONTENT_LENGTH)
+            && let Some(body) = response.body.as_bytes()
+        {
+            let len = body.len().to_string();
+            response.add_header(http::header::CONTENT_LENGTH, &len);
+        }
+        response
+    }
+
+    /// Records the matched route in a response's extensions, for the summary.
+    fn record_route(&self, response: &mut HttpResponse, entry: &RouteEntry) {
+        if self.max_response_size.is_some() || entry.max_response_size.is_some() {
+            response.extensions.insert(RouteResponseLimit {
+                limit: entry.max_response_size,
+                controller: Arc::clone(&entry.controller),
+            });
+        }
+        if self.summary_needed() {
+            response
+                .extensions
+                .insert(MatchedRoute(Arc::clone(&entry.pattern)));
+        }
+        #[cfg(feature = "record")]
+        if self.recording.is_some() {
+            response
+                .extensions
+                .insert(RecordedController(Arc::clone(&entry.controller)));
+        }
+    }
+
+    /// Calls the `on_response` hook, and the slow request callback if the
+    /// request took long enough, for a routed request whose response body is
+    /// `bytes` long, taking the router's details out of the response's
+    /// `extensions`.
+    ///
+    /// Returns the time spent routing the request, if measured.
+    fn finish_summary(
+        &self,
+        started: Option<Started>,
+        status: StatusCode,
+        outcome: Outcome,
+        extensions: &mut http::Extensions,
+        bytes: Option<u64>,
+    ) -> Option<Duration> {
+        let elapsed = started.map(|started| {
+            let elapsed = self.clock.now().saturating_duration_since(started.at);
+            let slow = self
+                .on_slow_request
+                .as_ref()
+                .filter(|(threshold, _)| elapsed >= *threshold);
+            if self.on_response.is_some() || slow.is_some() {
+                let summary = started.summary(status, elapsed, outcome, extensions, bytes);
+                if let Some(hook) = &self.on_response {
+                    hook(&summary);
+                }
+                if let Some((_, callback)) = slow {
+                    callback(&summary);
+                }
+            }
+            elapsed
+        });
+        extensions.remove::<MissingHandler>();
+        extensions.remove::<MatchedRoute>();
+        extensions.remove::<Suggestions>();
+        extensions.remove::<OversizedResponse>();
+        elapsed
+    }
+
+    /// Adds the `X-Response-Time-Ms` header, if enabled, unless the handler
+    /// set it.
+    fn add_timing_header(&self, headers: &mut HeaderMap, elapsed: Option<Duration>) {
+        if let Some(elapsed) = elapsed
+            && self.timing_header
+            && !headers.contains_key(X_RESPONSE_TIME_MS)
+        {
+            let millis = format!("{:.3}", elapsed.as_secs_f64() * 1000.0);
+            if let Ok(value) = HeaderValue::from_str(&millis) {
+                headers.insert(X_RESPONSE_TIME_MS, value);
+            }
+        }
+    }
+
+    /// Starts routing a request: records what its summary needs, counts it in
+    /// flight and resolves it.
+    fn start<B>(&self, req: &Request<B>) -> Resolution<'_> {
+        let started = self.start_summary(req);
+        let in_flight = self.shutdown.enter();
+        let page_request = self.error_pages.as_ref().map(|_| PageRequest::new(req));
+        let resolved = self.resolve_route(req);
+        let has_handler = match &resolved {
+            Resolved::Matched { entry, .. } => self.handler(entry, &entry.controller).is_some(),
+            Resolved::ServerOptions(_) | Resolved::Batch(_) => true,
+            Resolved::Respond(_) => false,
+        };
+        Resolution {
+            resolved,
+            has_handler,
+            started,
+            page_request,
+            in_flight,
+            #[cfg(feature = "logging")]
+            span: tracing::Span::none(),
+        }
+    }
+
+    /// Performs the routing logic of a resolved request shared by `dispatch`
+    /// and `route_streaming`, calling the handler inline.
+    fn respond(&self, resolved: Resolved<'_>, req: Request<Bytes>) -> HttpResponse {
+        match self.prepare(resolved, req) {
+            Dispatch::Respond(response) => response,
+            Dispatch::Handle {
+                entry,
+                controller,
+                canary,
+                mut req,
+            } => match self.admit(entry, controller, req.deadline) {
+                Err(response) => response,
+                Ok((attempt, _permit)) => {
+                    let handling = self.before_handler(&mut req);
+                    let response = match &entry.mirror_to {
+                        Some(name) if self.sync_mirroring => {
+                            let mirror_req = req.mirror_copy();
+                            let response = self.call(entry, controller, req);
+                            self.mirror(name, mirror_req, &response);
+                            response
+                        }
+                        _ => self.call(entry, controller, req),
+                    };
+                    let response = self.handled(entry, attempt, response);
+                    self.after_handler(handling, entry, canary, response)
+                }
+            },
+        }
+    }
+
+    /// Takes what finishing the response needs from a request about to be
+    /// handled: its idempotency claim, session and new CSRF cookie.
+    #[cfg_attr(
+        not(any(feature = "idempotency", feature = "session", feature = "csrf")),
+        allow(unused_variables)
+    )]
+    fn before_handler(&self, req: &mut HttpRequest) -> Handling {
+        Handling {
+            #[cfg(feature = "idempotency")]
+            claim: req.extensions.remove::<Claim>(),
+            #[cfg(feature = "session")]
+            session: req.session().cloned(),
+            #[cfg(feature = "csrf")]
+            csrf_cookie: csrf::new_cookie(req),
+        }
+    }
+
+    /// Reports the handler's response to the circuit breaker, and validates it
+    /// against the route's response schema, before coalesced requests share
+    /// it.
+    #[cfg_attr(not(feature = "schema"), allow(unused_variables))]
+    fn handled(
+        &self,
+        entry: &RouteEntry,
+        attempt: Option<Attempt<'_>>,
+        response: HttpResponse,
+    ) -> HttpResponse {
+        if let Some(attempt) = attempt {
+            attempt.finish(response.status, self.clock.now());
+        }
+        #[cfg(feature = "schema")]
+        let response = self.check_response(entry, response);
+        response
+    }
+
+    /// Finishes the handler's response for its caller: records it for
+    /// idempotent retries, saves the session, and adds the cookies and the
+    /// `X-Canary` header.
+    fn after_handler(
+        &self,
+        handling: Handling,
+        entry: &RouteEntry,
+        canary: Option<Variant>,
+        mut response: HttpResponse,
+    ) -> HttpResponse {
+        #[cfg(not(any(feature = "idempotency", feature = "session", feature = "csrf")))]
+        let Handling {} = handling;
+        // Recorded before the cookies of the caller's session and CSRF token
+        // are set, so retries don't get them.
+        #[cfg(feature = "idempotency")]
+        if let Some(claim) = handling.claim {
+            self.finish_idempotency(claim, &response);
+        }
+        #[cfg(feature = "session")]
+        if let Some(session) = handling.session {
+            response = self.finish_session(&session, response);
+        }
+        #[cfg(feature = "csrf")]
+        if let Some(cookie) = handling.csrf_cookie {
+            response.headers.append(http::header::SET_COOKIE, cookie);
+        }
+        if let Some(variant) = canary {
+            response.add_header(X_CANARY, variant.as_str());
+        }
+        self.record_route(&mut response, entry);
+        response
+    }
+
+    /// Takes what completing the response needs from a request before it's
+    /// routed.
+    fn completion(
+        &self,
+        req: &Request<Bytes>,
+        started: Option<Started>,
+        page_request: Option<PageRequest>,
+    ) -> Completion {
+        Completion {
+            started,
+            page_request,
+            head: req.method() == Method::HEAD,
+            #[cfg(feature = "record")]
+            recording: self.start_recording(req),
+        }
+    }
+
+    /// Completes the response to a request, shared by `dispatch`,
+    /// `route_streaming` and `route_async`: applies the error page, the
+    /// response transformers and the size cap, drops the body of a `HEAD`
+    /// response, buffers a streamed body if `buffered`, adds the default and
+    /// timing headers, and reports the request summary and recording.
+    fn complete(
+        &self,
+        completion: Completion,
+        outcome: Outcome,
+        mut response: HttpResponse,
+        buffered: bool,
+    ) -> HttpResponse {
+        let Completion {
+            started,
+            page_request,
+            head,
+            #[cfg(feature = "record")]
+            recording,
+        } = completion;
+        self.apply_error_page(page_request, &mut response);
+        let response = self.transform(started.as_ref(), outcome, response);
+        let mut response = self.cap_response(response);
+        if head {
+            strip_body(&mut response);
+        }
+        if buffered {
+            response = self.buffer(response);
+        }
+        self.add_default_headers(&mut response.headers);
+        let status = response.status;
+        let bytes = response.body.as_bytes().map(|body| body.len() as u64);
+        let elapsed =
+            self.finish_summary(started, status, outcome, &mut response.extensions, bytes);
+        self.add_timing_header(&mut response.headers, elapsed);
+        #[cfg(feature = "record")]
+        if let Some(recording) = recording {
+            recording.finish(
+                status,
+                &response.headers,
+                response.body.as_bytes().map(|body| &body[..]),
+                &mut response.extensions,
+                self.clock.now(),
+            );
+        }
+        response
+    }
+
+    /// Calls the mirror handler registered under `name`, and then the
+    /// `on_mirror_result` callback. A panic in either is caught, so it can't
+    /// affect the primary response.
+    fn mirror(&self, name: &str, req: HttpRequest, primary: &HttpResponse) {
+        let Some(handler) = self.registered(name) else {
+            return;
+        };
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+            let callback_req = self.on_mirror.as_ref().map(|_| req.mirror_copy());
+            let HandlerFuture::Ready(mirror) = handler.call(req) else {
+                return;
+            };
+            if let (Some(callback), Some(req)) = (&self.on_mirror, callback_req) {
+                callback(&req, primary, &mirror);
+            }
+        }));
+    }
+
+    /// Matches a request to a route, or returns the response if the router
+    /// answers the request itself.
+    fn resolve_route<B>(&self, req: &Request<B>) -> Resolved<'_> {
+        if let Some(response) = self.limits_response(req) {
+            return Resolved::Respond(response);
+        }
+
+        if self.shutdown.is_shutting_down() {
+            let path = req.uri().path();
+            if !self.is_health_path(path) && !self.shutdown_exempt_paths.iter().any(|p| p == path) {
+                return Resolved::Respond(self.shutdown_response());
+            }
+        }
+
+        // The asterisk-form target of `OPTIONS *` names the server, not a path.
+        if req.uri() == "*" {
+            return self.server_options(req.method());
+        }
+
+        if let Some(redirect) = self.canonical_redirect(req) {
+            return Resolved::Respond(redirect);
+        }
+
+        // Normalize and rewrite the path, if needed. Handlers see the resulting URI,
+        // while the URI as received stays available as `HttpRequest::original_uri`.
+        let mut uri = req.uri().clone();
+        if self.path_decoding == PathDecoding::Reject
+            && path::percent_decode(uri.path(), PathDecoding::Reject).is_none()
+        {
+            return Resolved::Respond(self.invalid_path_response());
+        }
+        match self.effective_path(uri.path()) {
+            Ok(None) => {}
+            Ok(Some(effective)) => match path::replace_path(&uri, &effective) {
+                Some(effective) => uri = effective,
+                None => return Resolved::Respond(self.invalid_path_response()),
+            },
+            Err(_) => return Resolved::Respond(self.invalid_path_response()),
+        }
+
+        let path = uri.path();
+
+        // A single lookup yields the route for the method, the `GET` route of
+        // a `HEAD` request without a route of its own or, failing that, the
+        // methods that are allowed for the path.
+        let fallback = (self.auto_head && req.method() == Method::HEAD).then_some(&Method::GET);
+        let mut lookup = self.routes.table.lookup_or(req.method(), fallback, path);
+        // Rejected `TRACE` routes are left out, as if they didn't exist.
+        if self.trace_policy == TracePolicy::Reject {
+            lookup = match lookup {
+                Lookup::Found { .. } if req.method() == Method::TRACE => self.without_trace(path),
+                Lookup::MethodNotAllowed(ref allowed)
+                    if allowed.methods.contains(&Method::TRACE) =>
+                {
+                    self.without_trace(path)
+                }
+                lookup => lookup,
+            };
+        }
+        if req.method() == Method::CONNECT && !matches!(lookup, Lookup::Found { .. }) {
+            return Resolved::Respond(self.error_response(
+                StatusCode::NOT_IMPLEMENTED,
+                Bytes::new(),
+                |p| p,
+            ));
+        }
+        let (entry, mut params) = match lookup {
+            Lookup::Found { entry, params } => (entry, params),
+            Lookup::MethodNotAllowed(allowed) => {
+                let allowed = self.with_implicit_methods(allowed);
+                if self.auto_options && req.method() == Method::OPTIONS {
+                    let mut response = HttpResponse::no_content();
+                    response.add_header(http::header::ALLOW, &allowed.header);
+                    return Resolved::Respond(response);
+                }
+                let mut response =
+                    self.error_response(StatusCode::METHOD_NOT_ALLOWED, Bytes::new(), |p| {
+                        let methods: Vec<_> = allowed.methods.iter().map(Method::as_str).collect();
+                        p.with_extension("allowed", methods)
+                    });
+                response.add_header(http::header::ALLOW, &allowed.header);
+                return Resolved::Respond(response);
+            }
+            Lookup::NotFound => {
+                if let Some(resolved) = self.batch_resolution(req, path) {
+                    return resolved;
+                }
+                if let Some(response) = self
+                    .health_response(req.method(), path)
+                    .or_else(|| self.introspection_response(req, path))
+                {
+                    return Resolved::Respond(response);
+                }
+                let suggestions = (self.suggestions
+                    && self.suggestion_trigger.applies(req.headers()))
+                .then(|| {
+                    suggestions::suggest(
+                        path,
+                        self.routes.table.endpoints().map(|e| e.path.as_str()),
+                    )
+                })
+                .filter(|suggestions| !suggestions.is_empty());
+                let Some(suggestions) = suggestions else {
+                    return Resolved::Respond(self.error_response(
+                        StatusCode::NOT_FOUND,
+                        Bytes::new(),
+                        |p| p,
+                    ));
+                };
+                let mut response = self.error_response(StatusCode::NOT_FOUND, Bytes::new(), |p| {
+                    p.with_extension("suggestions", suggestions.clone())
+                });
+                if self.summary_needed() {
+                    response.extensions.insert(Suggestions(suggestions));
+                }
+                return Resolved::Respond(response);
+            }
+        };
+
+        if let Some(response) = self.maintenance_check(entry, path) {
+            return Resolved::Respond(response);
+        }
+
+        // Parameters are matched still percent-encoded, and decoded for the handler.
+        for value in params.values_mut() {
+            match path::percent_decode(value, self.path_decoding) {
+                Some(Cow::Borrowed(_)) => {}
+                Some(Cow::Owned(decoded)) => *value = decoded.into_boxed_str(),
+                None => {
+                    let mut response = self.invalid_path_response();
+                    self.record_route(&mut response, entry);
+                    return Resolved::Respond(response);
+                }
+            }
+        }
+
+        Resolved::Matched { entry, uri, params }
+    }
+
+    /// Resolves a request to the built-in batch endpoint, if it is one: a
+    /// `POST` that isn't itself a sub-request of a batch.
+    fn batch_resolution<B>(&self, req: &Request<B>, path: &str) -> Option<Resolved<'_>> {
+        let (batch_path, limits) = self.batch.as_ref()?;
+        if path != batch_path {
+            return None;
+        }
+        if req.extensions().get::<BatchItem>().is_some() {
+            return Some(Resolved::Respond(self.batch_error(
+                StatusCode::BAD_REQUEST,
+                "A batch can't contain requests to the batch endpoint.",
+            )));
+        }
+        if req.method() != Method::POST {
+            let mut response =
+                self.error_response(StatusCode::METHOD_NOT_ALLOWED, Bytes::new(), |p| {
+                    p.with_extension("allowed", ["POST"])
+                });
+            response.add_header(http::header::ALLOW, "POST");
+            return Some(Resolved::Respond(response));
+        }
+        Some(Resolved::Batch(limits))
+    }
+
+    /// Returns the response to an invalid batch request.
+    fn batch_error(&self, status: StatusCode, detail: &str) -> HttpResponse {
+        self.error_response(status, format!("Error: {detail}"), |p| {
+            p.with_detail(detail)
+        })
+    }
+
+    /// Answers a request with the asterisk-form target, which only `OPTIONS`
+    /// may have: with the handler set with `register_server_options`, the
+    /// methods with any route if automatic `OPTIONS` responses are enabled,
+    /// or a 501 Not Implemented.
+    fn server_options(&self, method: &Method) -> Resolved<'_> {
+        if method != Method::OPTIONS {
+            let detail = "Only OPTIONS requests can target the whole server with '*'.";
+            return Resolved::Respond(self.error_response(
+                StatusCode::BAD_REQUEST,
+                format!("Error: {detail}"),
+                |p| p.with_detail(detail),
+            ));
+        }
+        if let Some(handler) = &self.server_options {
+            return Resolved::ServerOptions(&**handler);
+        }
+        if !self.auto_options {
+            return Resolved::Respond(self.error_response(
+                StatusCode::NOT_IMPLEMENTED,
+                Bytes::new(),
+                |p| p,
+            ));
+        }
+        let methods = self
+            .routes
+            .table
+            .routes()
+            .map(|(method, _)| method.clone())
+            .filter(|method| self.trace_policy == TracePolicy::Allow || method != Method::TRACE)
+            .collect();
+        let allowed = AllowedMethods::new(methods);
+        let allowed = self.with_implicit_methods(Cow::Owned(allowed));
+        let mut response = HttpResponse::no_content();
+        response.add_header(http::header::ALLOW, &allowed.header);
+        Resolved::Respond(response)
+    }
+
+    /// Looks up `path` as if it had no `TRACE` route, for a request without a
+    /// route of its own: the path's other methods are allowed, if any.
+    fn without_trace(&self, path: &str) -> Lookup<'_> {
+        let mut methods = self
+            .routes
+            .table
+            .allowed(path)
+            .map(|allowed| allowed.methods.clone())
+            .unwrap_or_default();
+        methods.retain(|method| method != Method::TRACE);
+        if methods.is_empty() {
+            Lookup::NotFound
+        } else {
+            Lookup::MethodNotAllowed(Cow::Owned(AllowedMethods::new(methods)))
+        }
+    }
+
+    /// Builds the `HttpRequest` for the handler of a resolved request's route,
+    /// or returns the response if the router answers the request itself.
+    fn prepare<'r>(&'r self, resolved: Resolved<'r>, req: Request<Bytes>) -> Dispatch<'r> {
+        let (entry, uri, params) = match resolved {
+            Resolved::Respond(response) => return Dispatch::Respond(response),
+            Resolved::ServerOptions(handler) => {
+                return Dispatch::Respond(handler.handle(HttpRequest::from(req)));
+            }
+            Resolved::Batch(limits) => {
+                return Dispatch::Respond(match batch::parse(req.body(), limits) {
+                    Ok(requests) => {
+                        let responses: Vec<_> =
+                            requests.into_iter().map(|req| self.route(req)).collect();
+                        batch::response(&responses)
+                    }
+                    Err((status, detail)) => self.batch_error(status, &detail),
+                });
+            }
+            Resolved::Matched { entry, uri, params } => (entry, uri, params),
+        };
+        let (mut parts, body) = req.into_parts();
+        let original_uri = std::mem::replace(&mut parts.uri, uri);
+        let req = Request::from_parts(parts, body);
+        match self.dispatch_route(req, original_uri, entry, params) {
+            Dispatch::Respond(mut response) => {
+                self.record_route(&mut response, entry);
+                Dispatch::Respond(response)
+            }
+            handle => handle,
+        }
+    }
+
+    /// Continues `prepare` for a request matching `entry`, with the path
+    /// parameters `params`.
+    fn dispatch_route<'r>(
+        &'r self,
+        req: Request<Bytes>,
+        original_uri: http::Uri,
+        entry: &'r RouteEntry,
+        params: Params,
+    ) -> Dispatch<'r> {
+        // Create our custom HttpRequest with the path parameters.
+        let now = Instant::now();
+        let timeout_deadline = entry
+            .timeout
+            .or(self.default_timeout)
+            // A timeout too large to add to the current time is no timeout.
+            .and_then(|timeout| now.checked_add(timeout));
+        let sent_deadline = self.deadline_header.as_ref().and_then(|(name, format)| {
+            let value = req.headers().get(name)?.to_str().ok()?;
+            // A Unix time is compared with the router's clock, like JWT
+            // expiry, while the deadline itself is kept on the system's.
+            format.parse(value, now, self.clock.system_time())
+        });
+        let deadline = match (timeout_deadline, sent_deadline) {
+            (Some(timeout), Some(sent)) => Some(timeout.min(sent)),
+            (timeout, sent) => timeout.or(sent),
+        };
+        let (mut parts, body) = req.into_parts();
+        if let Some(sent) = sent_deadline {
+            parts.extensions.insert(Deadline(sent));
+        }
+        if let Some(locale) = &entry.locale {
+            parts.extensions.insert(RouteLocale(locale.clone()));
+        }
+        parts
+            .extensions
+            .insert(RouteEndpoint(Arc::clone(&entry.endpoint)));
+        if parts.extensions.get::<RequestContext>().is_none() {
+            parts.extensions.insert(RequestContext::new());
+        }
+        let mut req = HttpRequest {
+            uri: parts.uri,
+            original_uri,
+            method: parts.method,
+            headers: parts.headers,
+            params,
+            body,
+            extensions: parts.extensions,
+            deadline,
+            parsed: Default::default(),
+        };
+
+        if let Some(max) = entry.max_request_size
+            && req.body.len() > max
+        {
+            return Dispatch::Respond(self.error_response(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "Content Too Large",
+                |p| p.with_detail(format!("The request body is larger than {max} bytes.")),
+            ));
+        }
+
+        #[cfg(feature = "session")]
+        if let Some(sessions) = &self.sessions {
+            let session = sessions.load(&req);
+            req.extensions.insert(session);
+        }
+
+        #[cfg(feature = "csrf")]
+        if let Some(csrf) = &self.csrf
+            && entry.csrf
+            && let Err(rejection) = csrf.protect(&mut req)
+        {
+            return Dispatch::Respond(match rejection {
+                csrf::Rejection::Mismatch => {
+                    self.error_response(StatusCode::FORBIDDEN, "Forbidden", |p| {
+                        p.with_detail("The request's CSRF token is missing or doesn't match.")
+                    })
+                }
+                csrf::Rejection::NoToken(_error) => {
+                    #[cfg(feature = "logging")]
+                    tracing::error!(error = %_error, "failed to generate a CSRF token");
+                    self.error_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Internal Server Error",
+                        |p| p.with_detail("No CSRF token could be generated."),
+                    )
+                }
+            });
+        }
+
+        #[cfg(feature = "jwt")]
+        if let Some(jwt) = &self.jwt
+            && let Err(denial) = jwt.authenticate(
+                &mut req,
+                entry.auth_scopes.as_deref(),
+                self.clock.system_time(),
+            )
+        {
+            let mut response = self.error_response(
+                denial.status,
+                denial.status.canonical_reason().unwrap_or_default(),
+                |p| p.with_detail(denial.detail),
+            );
+            if let Ok(challenge) = http::HeaderValue::from_str(&denial.challenge) {
+                response
+                    .headers
+                    .insert(http::header::WWW_AUTHENTICATE, challenge);
+            }
+            return Dispatch::Respond(response);
+        }
+
+        if let Some(Auth::ApiKey) = entry.auth {
+            match self
+                .api_keys
+                .as_ref()
+                .map(|api_keys| api_keys.authenticate(&req))
+            {
+                Some(Some(identity)) => {
+                    req.extensions.insert(identity);
+                }
+                Some(None) => {
+                    let mut response =
+                        self.error_response(StatusCode::UNAUTHORIZED, "Unauthorized", |p| {
+                            p.with_detail("The request requires a valid API key.")
+                        });
+                    response.add_header(http::header::WWW_AUTHENTICATE, "ApiKey");
+                    return Dispatch::Respond(response);
+                }
+                None => {
+                    #[cfg(feature = "logging")]
+                    tracing::error!(path = req.uri.path(), "no API keys are configured");
+                    let detail = "The authentication of this route is not configured.";
+                    return Dispatch::Respond(self.error_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Error: {detail}"),
+                        |p| p.with_detail(detail),
+                    ));
+                }
+            }
+        }
+
+        if let Some(signature) = &entry.signature
+            && let Some(response) = self.check_signature(signature, &req)
+        {
+            return Dispatch::Respond(response);
+        }
+
+        if let Some(response) = self.check_required_headers(entry, &req) {
+            return Dispatch::Respond(response);
+        }
+
+        if let Some(response) = self.check_guards(entry, &req) {
+            return Dispatch::Respond(response);
+        }
+
+        #[cfg(feature = "schema")]
+        if let Some(schema) = &entry.schema
+            && let Some(response) = self.check_schema(schema, &req)
+        {
+            return Dispatch::Respond(response);
+        }
+
+        #[cfg(feature = "idempotency")]
+        if let Some(idempotency) = &self.idempotency
+            && entry.idempotent
+        {
+            match idempotency.begin(&req, &*self.clock) {
+                Begin::Run(Some(claim)) => {
+                    req.extensions.insert(claim);
+                }
+                Begin::Run(None) => {}
+                Begin::Replay(response) => return Dispatch::Respond(response),
+                Begin::Reject(rejection) => {
+                    return Dispatch::Respond(self.idempotency_rejection(rejection));
+                }
+            }
+        }
+
+        let (controller, canary) = match &entry.canary {
+            Some(canary) => match canary::select(canary, &req.headers, &*self.rng) {
+                Variant::Stable => (canary.stable.as_str(), Some(Variant::Stable)),
+                Variant::Candidate => (canary.candidate.as_str(), Some(Variant::Candidate)),
+            },
+            None => (&*entry.controller, None),
+        };
+        Dispatch::Handle {
+            entry,
+            controller,
+            canary,
+            req,
+        }
+    }
+
+    /// Validates the body of a `POST`, `PUT` or `PATCH` request against its
+    /// route's schema, returning the response rejecting it: a 415 Unsupported
+    /// Media Type if it isn't JSON, or a 400 Bad Request if it's invalid JSON
+    /// or doesn't match, with the violations as the `errors` of a problem
+    /// document.
+    #[cfg(feature = "schema")]
+    fn check_schema(&self, schema: &schema::Schema, req: &HttpRequest) -> Option<HttpResponse> {
+        if !matches!(req.method, Method::POST | Method::PUT | Method::PATCH) {
+            return None;
+        }
+        if !schema::is_json(&req.headers) {
+            let detail = "Expected a request body of type application/json";
+            return Some(
+                self.error_response(StatusCode::UNSUPPORTED_MEDIA_TYPE, detail, |p| {
+                    p.with_detail(detail)
+                }),
+            );
+        }
+        let body = match req.json_value() {
+            Ok(body) => body,
+            Err(e) => {
+                let detail = match e {
+                    BodyError::Json(reason) => format!("Invalid JSON body: {reason}"),
+                    e => e.to_string(),
+                };
+                return Some(
+                    self.error_response(StatusCode::BAD_REQUEST, detail.clone(), |p| {
+                        p.with_detail(detail)
+                    }),
+                );
+            }
+        };
+        let violations = schema.validate(body);
+        if violations.is_empty() {
+            return None;
+        }
+        let errors = serde_json::to_value(violations).unwrap_or_default();
+        Some(HttpResponse::problem(
+            Problem::new(StatusCode::BAD_REQUEST)
+                .with_detail("The request body doesn't match the schema of the route.")
+                .with_extension("errors", errors),
+        ))
+    }
+
+    /// Validates a handler's response against its route's `response_schema`,
+    /// if response validation is enabled, returning the response to send: the
+    /// handler's, or a 500 Internal Server Error in enforce mode if it doesn't
+    /// match.
+    #[cfg(feature = "schema")]
+    fn check_response(&self, entry: &RouteEntry, response: HttpResponse) -> HttpResponse {
+        let Some(schema) = &entry.response_schema else {
+            return response;
+        };
+        if self.response_validation == ResponseValidation::Off
+            || !response.status.is_success()
+            || !schema::is_json(&response.headers)
+            || response.extensions.get::<SkipValidation>().is_some()
+        {
+            return response;
+        }
+        let Some(body) = response.body.as_bytes() else {
+            return response;
+        };
+        let violations = match serde_json::from_slice(body) {
+            Ok(body) => schema.validate(&body),
+            Err(e) => vec![schema::Violation {
+                pointer: String::new(),
+                message: format!("invalid JSON: {e}"),
+            }],
+        };
+        if violations.is_empty() {
+            return response;
+        }
+        #[cfg(feature = "logging")]
+        for violation in &violations {
+            tracing::warn!(
+                controller = &*entry.controller,
+                pointer = violation.pointer.as_str(),
+                "response doesn't match the schema: {}",
+                violation.message
+            );
+        }
+        if self.response_validation == ResponseValidation::Warn {
+            return response;
+        }
+        let errors = serde_json::to_value(violations).unwrap_or_default();
+        self.error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal Server Error",
+            |p| {
+                p.with_detail("The response doesn't match the schema of the route.")
+                    .with_extension("errors", errors)
+            },
+        )
+    }
+
+    /// Checks that a request has the headers of the configuration's and its
+    /// route's `required_headers`, returning the response rejecting it
+    /// otherwise: a problem document listing the missing headers.
+    fn check_required_headers(
+        &self,
+        entry: &RouteEntry,
+        req: &HttpRequest,
+    ) -> Option<HttpResponse> {
+        let mut missing: Vec<&str> = self
+            .routes
+            .required_headers
+            .iter()
+            .chain(&entry.required_headers)
+            .filter(|name| !req.headers.contains_key(*name))
+            .map(http::HeaderName::as_str)
+            .collect();
+        if missing.is_empty() {
+            return None;
+        }
+        missing.sort_unstable();
+        missing.dedup();
+        Some(HttpResponse::problem(
+            Problem::new(self.required_header_status)
+                .with_detail(format!(
+                    "The request is missing the required headers: {}.",
+                    missing.join(", ")
+                ))
+                .with_extension("missing_headers", missing),
+        ))
+    }
+
+    /// Returns the response to a request without a valid signature, if it
+    /// has none.
+    fn check_signature(
+        &self,
+        signature: &RouteSignature,
+        req: &HttpRequest,
+    ) -> Option<HttpResponse> {
+        let Some(secret) = self.secrets.secret(&signature.secret_name) else {
+            #[cfg(feature = "logging")]
+            tracing::error!(
+                path = req.uri.path(),
+                secret = signature.secret_name,
+                "the signature secret is not set"
+            );
+            let detail = "The signature secret of this route is not configured.";
+            return Some(self.error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Error: {detail}"),
+                |p| p.with_detail(detail),
+            ));
+        };
+        let valid = signature::verify(
+            signature.algorithm,
+            &req.headers,
+            &signature.header,
+            &req.body,
+            &secret,
+            self.clock.system_time(),
+            signature.tolerance,
+        );
+        (!valid).then(|| {
+            self.error_response(StatusCode::UNAUTHORIZED, "Unauthorized", |p| {
+                p.with_detail("The request's signature is missing or invalid.")
+            })
+        })
+    }
+
+    /// Checks the guards of a matched route in order, returning the response of
+    /// the first that fails.
+    fn check_guards(&self, entry: &RouteEntry, req: &HttpRequest) -> Option<HttpResponse> {
+        entry
+            .guards
+            .iter()
+            .find_map(|name| self.check_guard(name, req))
+    }
+
+    /// Runs the guard registered under `name`, returning its rejection, or a
+    /// 500 Internal Server Error if there's no such guard.
+    fn check_guard(&self, name: &str, req: &HttpRequest) -> Option<HttpResponse> {
+        match self.guards.get(name) {
+            Some(guard) => guard.check(req).err(),
+            None => {
+                #[cfg(feature = "logging")]
+                tracing::error!(guard = name, path = req.uri.path(), "unknown guard");
+                let detail = "A precondition of this route is not configured.";
+                Some(self.error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Error: {detail}"),
+                    |p| p.with_detail(detail),
+                ))
+            }
+        }
+    }
+
+    /// Calls the handler registered under `controller_name` for a matched route.
+    /// A request to a handler answering asynchronously gets a 501 Not
+    /// Implemented, since it can't be awaited here.
+    fn call(&self, entry: &RouteEntry, controller_name: &str, req: HttpRequest) -> HttpResponse {
+        match self.call_handler(entry, controller_name, req) {
+            HandlerFuture::Ready(response) => response,
+            HandlerFuture::Pending(_) => {
+                #[cfg(feature = "logging")]
+                tracing::warn!(
+                    controller = controller_name,
+                    "the route's handler is asynchronous, but the request isn't routed with route_async"
+                );
+                let detail = "This route's handler is asynchronous.";
+                let body = format!("Error: {detail}");
+                self.error_response(StatusCode::NOT_IMPLEMENTED, body, |p| p.with_detail(detail))
+            }
+        }
+    }
+
+    /// Calls the handler registered under `controller_name` for a matched
+    /// route, returning its response or a future of it.
+    fn call_handler<'a>(
+        &'a self,
+        entry: &'a RouteEntry,
+        controller_name: &str,
+        req: HttpRequest,
+    ) -> HandlerFuture<'a> {
+        // Check if a handler has been registered for this controller name.
+        match self.handler(entry, controller_name) {
+            Some(handler) => {
+                let mut req = req;
+                let controller = if *entry.controller == *controller_name {
+                    Arc::clone(&entry.controller)
+                } else {
+                    Arc::from(controller_name)
+                };
+                req.extensions.insert(RouteController(controller));
+                #[cfg(feature = "cache")]
+                if let Some(ttl) = entry.cache_ttl
+                    && entry.canary.is_none()
+                    && (req.method == Method::GET || req.method == Method::HEAD)
+                {
+                    return self.respond_cached(&**handler, req, ttl);
+                }
+
+                // Invoke the handler and return its response.
+                self.invoke(&**handler, req)
+            }
+            // The route is in the JSON, but no handler was registered.
+            // This is a server misconfiguration.
+            None => HandlerFuture::Ready(self.missing_handler(controller_name, req)),
+        }
+    }
+
+    /// Calls a registered handler. With the `extractors` feature, requests
+    /// rejected by an extractor get a problem document instead of a plain body
+    /// if problem responses are enabled.
+    fn invoke<'a>(
+        &self,
+        handler: &'a (dyn Handler + Send + Sync),
+        req: HttpRequest,
+    ) -> HandlerFuture<'a> {
+        match handler.call(req) {
+            #[cfg(feature = "extractors")]
+            HandlerFuture::Ready(response) if self.problem_responses => {
+                HandlerFuture::Ready(extract::rejection_to_problem(response))
+            }
+            future => future,
+        }
+    }
+
+    /// Answers a request to a route without a registered handler, following the
+    /// `MissingHandlerPolicy`. The controller name is only logged and recorded
+    /// for the `on_response` hook, never sent to the client.
+    fn missing_handler(&self, controller_name: &str, req: HttpRequest) -> HttpResponse {
+        #[cfg(feature = "logging")]
+        tracing::warn!(
+            controller = controller_name,
+            method = %req.method,
+            path = req.uri.path(),
+            "no handler is registered for the route's controller"
+        );
+
+        let mut response = match (self.missing_handler_policy, &self.fallback) {
+            (MissingHandlerPolicy::Fallback, Some(fallback)) => fallback.handle(req),
+            (MissingHandlerPolicy::NotFound, _) => {
+                self.error_response(StatusCode::NOT_FOUND, Bytes::new(), |p| p)
+            }
+            _ => {
+                let detail = "No handler is registered for this route.";
+                let body = format!("Error: {detail}");
+                self.error_response(StatusCode::NOT_IMPLEMENTED, body, |p| p.with_detail(detail))
+            }
+        };
+        response
+            .extensions
+            .insert(MissingHandler(controller_name.to_string()));
+        response
+    }
+
+    /// Serves a request for a cached route from the cache, or calls the handler
+    /// and caches its response. Adds an `X-Cache: HIT` or `X-Cache: MISS` header.
+    /// Responses of handlers answering asynchronously aren't cached.
+    #[cfg(feature = "cache")]
+    fn respond_cached<'a>(
+        &self,
+        handler: &'a (dyn Handler + Send + Sync),
+        req: HttpRequest,
+        ttl: std::time::Duration,
+    ) -> HandlerFuture<'a> {
+        let target = req
+            .uri
+            .path_and_query()
+            .map_or_else(|| req.uri.path().to_string(), |p| p.as_str().to_string());
+        if let Some(mut hit) = self
+            .cache
+            .get(&req.method, &target, &req.headers, self.clock.now())
+        {
+            hit.add_header("x-cache", "HIT");
+            return HandlerFuture::Ready(hit);
+        }
+
+        let method = req.method.clone();
+        let headers = req.headers.clone();
+        let mut response = match self.invoke(handler, req) {
+            HandlerFuture::Ready(response) => response,
+            pending => return pending,
+        };
+        self.cache
+            .insert(method, target, &headers, &response, ttl, self.clock.now());
+        response.add_header("x-cache", "MISS");
+        HandlerFuture::Ready(response)
+    }
+
+    /// Removes the cached responses whose request path starts with `path_prefix`,
+    /// e.g. `"/users/"` after a user changes. Copies of the router, such as the
+    /// versions of a `SharedRouter`, share the cache.
+    #[cfg(feature = "cache")]
+    pub fn invalidate_cache(&self, path_prefix: &str) {
+        self.cache.invalidate(path_prefix);
+    }
+
+    /// Sets the maximum total size of the cached responses, in bytes (16 MiB by
+    /// default). The least recently used responses are evicted to stay within it.
+    #[cfg(feature = "cache")]
+    pub fn set_cache_max_bytes(&self, max_bytes: usize) {
+        self.cache.set_max_bytes(max_bytes);
+    }
+
+    /// Admits a request to the handler of `entry`, registered under
+    /// `controller`: returns its attempt through the controller's circuit
+    /// breaker and its concurrency permit, or else the response turning it
+    /// away.
+    // The error is the response to send, only built when the request is
+    // turned away.
+    #[allow(clippy::result_large_err)]
+    fn admit<'e>(
+        &self,
+        entry: &'e RouteEntry,
+        controller: &str,
+        deadline: Option<Instant>,
+    ) -> Result<(Option<Attempt<'_>>, Option<Permit<'e>>), HttpResponse> {
+        let attempt = self.circuit_attempt(entry, controller)?;
+        Ok((attempt, self.concurrency_permit(entry, deadline)?))
+    }
+
+    /// Admits a request like `admit`, without blocking the thread.
+    #[cfg(feature = "async")]
+    // The error is the response to send, only built when the request is
+    // turned away.
+    #[allow(clippy::result_large_err)]
+    async fn admit_async<'e>(
+        &self,
+        entry: &'e RouteEntry,
+        controller: &str,
+        deadline: Option<Instant>,
+    ) -> Result<(Option<Attempt<'_>>, Option<Permit<'e>>), HttpResponse> {
+        let attempt = self.circuit_attempt(entry, controller)?;
+        Ok((
+            attempt,
+            self.concurrency_permit_async(entry, deadline).await?,
+        ))
+    }
+
+    /// Returns the attempt of a request through the circuit breaker of
+    /// `controller`, or else the 503 Service Unavailable of an open circuit.
+    /// Routes without a `circuit_breaker` need no attempt.
+    // The error is the response to send, only built when the circuit is open.
+    #[allow(clippy::result_large_err)]
+    fn circuit_attempt(
+        &self,
+        entry: &RouteEntry,
+        controller: &str,
+    ) -> Result<Option<Attempt<'_>>, HttpResponse> {
+        let Some(config) = entry.circuit_breaker else {
+            return Ok(None);
+        };
+        match self.circuits.admit(controller, config, self.clock.now()) {
+            Ok(attempt) => Ok(Some(attempt)),
+            Err(open_for) => {
+                let mut response =
+                    self.error_response(StatusCode::SERVICE_UNAVAILABLE, Bytes::new(), |p| {
+                        p.with_detail("The service is temporarily unavailable.")
+                    });
+                // Whole seconds, rounded up so that retries find it half-open.
+                let retry_after = open_for.as_secs() + u64::from(open_for.subsec_nanos() > 0);
+                response.add_header(http::header::RETRY_AFTER, &retry_after.to_string());
+                self.record_route(&mut response, entry);
+                Err(response)
+            }
+        }
+    }
+
+    /// Returns the permit of a request to run the handler of `entry`, waiting
+    /// in its queue until `deadline` if it's saturated, or else the response
+    /// turning it away. Routes without a `max_concurrency` need no permit.
+    // The error is the response to send, only built when the limit is hit.
+    #[allow(clippy::result_large_err)]
+    fn concurrency_permit<'e>(
+        &self,
+        entry: &'e RouteEntry,
+        deadline: Option<Instant>,
+    ) -> Result<Option<Permit<'e>>, HttpResponse> {
+        let Some(limit) = &entry.concurrency else {
+            return Ok(None);
+        };
+        limit
+            .acquire(deadline)
+            .map(Some)
+            .ok_or_else(|| self.concurrency_limit_response(entry))
+    }
+
+    /// Returns the permit of a request like `concurrency_permit`, without
+    /// blocking the thread.
+    #[cfg(feature = "async")]
+    // The error is the response to send, only built when the limit is hit.
+    #[allow(clippy::result_large_err)]
+    async fn concurrency_permit_async<'e>(
+        &self,
+        entry: &'e RouteEntry,
+        deadline: Option<Instant>,
+    ) -> Result<Option<Permit<'e>>, HttpResponse> {
+        let Some(limit) = &entry.concurrency else {
+            return Ok(None);
+        };
+        match limit.acquire_async(deadline).await {
+            Some(permit) => Ok(Some(permit)),
+            None => Err(self.concurrency_limit_response(entry)),
+        }
+    }
+
+    /// Builds the response to a request turned away by the `max_concurrency`
+    /// of `entry`.
+    fn concurrency_limit_response(&self, entry: &RouteEntry) -> HttpResponse {
+        let LimitResponse {
+            status,
+            retry_after,
+        } = self.concurrency_limit_response;
+        let mut response =
+            self.error_response(status, status.canonical_reason().unwrap_or_default(), |p| {
+                p.with_detail("The route is handling too many requests.")
+            });
+        response.add_header(
+            http::header::RETRY_AFTER,
+            &retry_after.as_secs().to_string(),
+        );
+        self.record_route(&mut response, entry);
+        response
+    }
+
+    /// Builds the 503 Service Unavailable for a request turned away while
+    /// shutting down.
+    fn shutdown_response(&self) -> HttpResponse {
+        let mut response =
+            self.error_response(StatusCode::SERVICE_UNAVAILABLE, Bytes::new(), |p| {
+                p.with_detail("The server is shutting down.")
+            });
+        response.add_header(http::header::CONNECTION, "close");
+        response.add_header(
+            http::header::RETRY_AFTER,
+            &self.shutdown.retry_after().as_secs().to_string(),
+        );
+        response
+    }
+
+    /// Builds the 400 Bad Request for a path that is rejected by normalization or
+    /// can't be rewritten into a valid URI.
+    fn invalid_path_response(&self) -> HttpResponse {
+        self.error_response(StatusCode::BAD_REQUEST, Bytes::new(), |p| {
+            p.with_detail("The request path is invalid.")
+        })
+    }
+
+    /// Returns a table of the routes, sorted by path and method, with the
+    /// controller of each route and whether its handler is registered, e.g. for
+    /// a startup banner:
+    ///
+    /// ```text
+    /// METHOD  PATH        CONTROLLER                   HANDLER
+    /// GET     /users      users_controller::get_all    registered
+    /// GET     /users/:id  users_controller::get_by_id  missing
+    /// ```
+    ///
+    /// Canary routes list both controllers, e.g. `orders::create, orders::create_v2 (5%)`,
+    /// and their handler is only `registered` if both are.
+    pub fn routes_summary(&self) -> String {
+        let mut rows: Vec<[String; 4]> = self
+            .route_rows()
+            .map(|(method, entry, controller, registered)| {
+                let handler = if registered { "registered" } else { "missing" };
+                [
+                    method.to_string(),
+                    entry.pattern.to_string(),
+                    controller,
+                    handler.to_string(),
+                ]
+            })
+            .collect();
+        rows.sort_by(|a, b| (&a[1], &a[0]).cmp(&(&b[1], &b[0])));
+
+        let header = ["METHOD", "PATH", "CONTROLLER", "HANDLER"].map(String::from);
+        let mut widths = header.each_ref().map(String::len);
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+
+        let mut summary = String::new();
+        for row in std::iter::once(&header).chain(&rows) {
+            let mut line = String::new();
+            for (cell, width) in row.iter().zip(widths) {
+                line.push_str(&format!("{cell:width$}  "));
+            }
+            summary.push_str(line.trim_end());
+            summary.push('\n');
+        }
+        summary
+    }
+
+    /// Logs an `info` event for each route, with its method, path, controller
+    /// and whether its handler is registered. Call it after registering the
+    /// handlers, e.g. at startup.
+    #[cfg(feature = "logging")]
+    pub fn log_routes(&self) {
+        for (method, entry, controller, registered) in self.route_rows() {
+            tracing::info!(
+                method = %method,
+                path = &*entry.pattern,
+                controller = controller,
+                registered = registered,
+                "route"
+            );
+        }
+    }
+
+    /// Returns each route with its controller description and whether its
+    /// handlers are registered.
+    fn route_rows(&self) -> impl Iterator<Item = (&Method, &RouteEntry, String, bool)> {
+        self.routes
+            .table
+            .routes()
+            .map(|(method, entry)| match &entry.canary {
+                Some(canary) => (
+                    method,
+                    entry,
+                    format!(
+                        "{}, {} ({}%)",
+                        canary.stable, canary.candidate, canary.percent
+                    ),
+                    self.has_handler(&canary.stable) && self.has_handler(&canary.candidate),
+                ),
+                None => (
+                    method,
+                    entry,
+                    entry.controller.to_string(),
+                    self.handler(entry, &entry.controller).is_some(),
+                ),
+            })
+    }
+
+    /// Returns `true` if a handler, synchronous or not, is registered for
+    /// `controller_name`.
+    fn has_handler(&self, controller_name: &str) -> bool {
+        self.registered(controller_name).is_some()
+    }
+
+    /// Returns the handler registered for `controller_name`, or else the one
+    /// of the longest prefix of it registered with `register_prefix`.
+    fn registered(&self, controller_name: &str) -> Option<&Arc<dyn Handler + Send + Sync>> {
+        self.handlers.get(controller_name).or_else(|| {
+            self.prefix_handlers
+                .iter()
+                .find(|(prefix, _)| controller_name.starts_with(prefix.as_str()))
+                .map(|(_, handler)| handler)
+        })
+    }
+
+    /// Returns the handler of `controller` for a matched route: the router's
+    /// own, for its `body_template` or `static_files`, the one its factory built for the route's `controller_args`,
+    /// if it has any, and otherwise the one registered for the controller or
+    /// a prefix of its name.
+    fn handler<'a>(
+        &'a self,
+        entry: &'a RouteEntry,
+        controller: &str,
+    ) -> Option<&'a Arc<dyn Handler + Send + Sync>> {
+        if let Some(builtin) = &entry.builtin {
+            return Some(builtin);
+        }
+        match &entry.instance {
+            Some(instance) if *entry.controller == *controller => self.instances.get(instance),
+            _ => self.registered(controller),
+        }
+    }
+
+    /// Returns the methods that have a route matching `path`, or an empty list if
+    /// the path doesn't match any route. The list is sorted alphabetically, and
+    /// includes `HEAD` for `GET` routes (see [`Router::set_auto_head`]) and
+    /// `OPTIONS` if [`Router::set_auto_options`] is enabled, as in the `Allow`
+    /// header of a 405 Method Not Allowed.
+    ///
+    /// The `path` is matched as given, without normalization or rewriting.
+    pub fn allowed_methods(&self, path: &str) -> Vec<Method> {
+        self.routes
+            .table
+            .allowed(path)
+            .map(|allowed| self.with_implicit_methods(allowed).methods.clone())
+            .unwrap_or_default()
+    }
+
+    /// Adds the methods the router answers itself (`HEAD` for `GET` routes and
+    /// `OPTIONS`, if enabled) to the methods with a route. This is the list used
+    /// by 405 responses, automatic `OPTIONS` responses and `allowed_methods`.
+    fn with_implicit_methods<'a>(
+        &self,
+        allowed: Cow<'a, AllowedMethods>,
+    ) -> Cow<'a, AllowedMethods> {
+        match allowed {
+            Cow::Borrowed(allowed) => {
+                Cow::Borrowed(allowed.with_implicit(self.auto_head, self.auto_options))
+            }
+            Cow::Owned(allowed) => Cow::Owned(
+                allowed
+                    .with_implicit(self.auto_head, self.auto_options)
+                    .clone(),
+            ),
         }
     }
 }
@@ -0,0 +1,107 @@
+//! Defines the limits on the size of a request's head, checked before it's
+//! matched: the number and length of its headers, the length of its URI and
+//! the number of its query parameters.
+
+use crate::Router;
+use crate::response::HttpResponse;
+use bytes::Bytes;
+use http::{Request, StatusCode};
+use serde::Deserialize;
+
+/// The limits on a request's head, set with `Router::set_limits` or the
+/// `limits` section of the configuration, e.g. `{ "max_headers": 50 }`.
+///
+/// A request over a header limit gets a 431 Request Header Fields Too Large,
+/// and one over a URI limit a 414 URI Too Long, before it's matched. The
+/// server in front of the router usually enforces limits of its own, which
+/// these can only tighten.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Limits {
+    /// The most headers a request can have, counting each value of a repeated
+    /// header. Defaults to 100.
+    pub max_headers: usize,
+    /// The longest header a request can have, in bytes of its name and
+    /// value. Defaults to 8 KiB.
+    pub max_header_bytes: usize,
+    /// The longest URI a request can have, in bytes of its path and query,
+    /// with its scheme and authority if it has them. Defaults to 8 KiB.
+    pub max_uri_bytes: usize,
+    /// The most parameters the query string can have, e.g. 2 for `?a=1&b=2`.
+    /// Defaults to 256.
+    ///
+    /// A request over it gets a 414 URI Too Long: the query is part of the
+    /// target URI, and RFC 9110 defines 414 for a target the server refuses
+    /// to interpret. 431 is only for header fields, and a 400 wouldn't tell
+    /// the client the URI is what to shorten.
+    pub max_query_params: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_headers: 100,
+            max_header_bytes: 8 * 1024,
+            max_uri_bytes: 8 * 1024,
+            max_query_params: 256,
+        }
+    }
+}
+
+impl Limits {
+    /// Returns the status and the problem detail of the response to a request
+    /// over a limit, or `None` if it's within them. Only lengths and counts
+    /// are checked, nothing is parsed.
+    pub(crate) fn check<B>(&self, req: &Request<B>) -> Option<(StatusCode, &'static str)> {
+        let headers = req.headers();
+        if headers.len() > self.max_headers {
+            return Some((
+                StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+                "The request has too many headers.",
+            ));
+        }
+        if headers
+            .iter()
+            .any(|(name, value)| name.as_str().len() + value.len() > self.max_header_bytes)
+        {
+            return Some((
+                StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+                "A request header is too large.",
+            ));
+        }
+        let uri = req.uri();
+        let uri_bytes = uri.scheme_str().map_or(0, |scheme| scheme.len() + 3)
+            + uri
+                .authority()
+                .map_or(0, |authority| authority.as_str().len())
+            + uri.path_and_query().map_or(0, |pq| pq.as_str().len());
+        if uri_bytes > self.max_uri_bytes {
+            return Some((StatusCode::URI_TOO_LONG, "The request URI is too long."));
+        }
+        let query_params = uri.query().map_or(0, |query| {
+            query.split('&').filter(|pair| !pair.is_empty()).count()
+        });
+        if query_params > self.max_query_params {
+            return Some((
+                StatusCode::URI_TOO_LONG,
+                "The request URI has too many query parameters.",
+            ));
+        }
+        None
+    }
+}
+
+impl Router {
+    /// Sets the limits on the size of requests' heads, replacing those of the
+    /// configuration's `limits` section (until the next `reload` of a
+    /// configuration with one). See [`Limits`] for the defaults.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Answers a request over the limits, before it's matched.
+    pub(crate) fn limits_response<B>(&self, req: &Request<B>) -> Option<HttpResponse> {
+        let (status, detail) = self.limits.check(req)?;
+        Some(self.error_response(status, Bytes::new(), |p| p.with_detail(detail)))
+    }
+}
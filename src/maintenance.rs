@@ -0,0 +1,168 @@
+//! Defines maintenance mode, which answers selected routes with a 503 Service
+//! Unavailable instead of calling their handlers.
+
+use crate::Router;
+use crate::response::HttpResponse;
+use crate::table::RouteEntry;
+use bytes::Bytes;
+use http::StatusCode;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Which routes are in maintenance. Set with `Router::set_maintenance`.
+///
+/// Routes whose endpoint has `"maintenance_exempt": true` are never affected.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MaintenanceMode {
+    /// Requests are routed normally.
+    #[default]
+    Off,
+    /// Every route is in maintenance.
+    All,
+    /// Routes whose request path starts with one of the prefixes are in
+    /// maintenance, e.g. `["/billing/"]`.
+    Prefixes(Vec<String>),
+}
+
+impl MaintenanceMode {
+    fn applies_to(&self, path: &str) -> bool {
+        match self {
+            MaintenanceMode::Off => false,
+            MaintenanceMode::All => true,
+            MaintenanceMode::Prefixes(prefixes) => prefixes
+                .iter()
+                .any(|prefix| path.starts_with(prefix.as_str())),
+        }
+    }
+}
+
+/// The response sent for routes in maintenance.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MaintenanceResponse {
+    pub(crate) retry_after: Option<Duration>,
+    /// The body, or `None` for the router's built-in 503 response.
+    pub(crate) body: Option<Bytes>,
+}
+
+/// The maintenance state of a router, shared by its copies so that it can be
+/// changed through a shared reference without rebuilding the routes.
+#[derive(Debug, Default)]
+pub(crate) struct Maintenance {
+    /// Whether the mode is anything but `Off`, checked first so that routing
+    /// outside maintenance doesn't take the lock.
+    active: AtomicBool,
+    mode: RwLock<MaintenanceMode>,
+    response: RwLock<MaintenanceResponse>,
+}
+
+impl Maintenance {
+    /// Returns an independent state with the same mode and response.
+    pub(crate) fn copy(&self) -> Self {
+        let copy = Self::default();
+        copy.set_mode(self.mode());
+        copy.set_response(
+            self.response
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+        );
+        copy
+    }
+
+    pub(crate) fn set_mode(&self, mode: MaintenanceMode) {
+        let mut current = self.mode.write().unwrap_or_else(|e| e.into_inner());
+        self.active
+            .store(mode != MaintenanceMode::Off, Ordering::Release);
+        *current = mode;
+    }
+
+    pub(crate) fn mode(&self) -> MaintenanceMode {
+        self.mode.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub(crate) fn set_response(&self, response: MaintenanceResponse) {
+        *self.response.write().unwrap_or_else(|e| e.into_inner()) = response;
+    }
+
+    /// Returns the response to send if `path` is in maintenance.
+    pub(crate) fn check(&self, path: &str) -> Option<MaintenanceResponse> {
+        if !self.active.load(Ordering::Acquire) {
+            return None;
+        }
+        if !self
+            .mode
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .applies_to(path)
+        {
+            return None;
+        }
+        Some(
+            self.response
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+        )
+    }
+}
+
+impl Router {
+    /// Puts the routes selected by `mode` into maintenance, or takes them out with
+    /// `MaintenanceMode::Off`.
+    ///
+    /// Requests matching a route in maintenance get a 503 Service Unavailable
+    /// (see [`Router::set_maintenance_response`]) instead of reaching the handler,
+    /// unless the endpoint is `maintenance_exempt`. Prefixes are matched against
+    /// the path after normalization and rewriting. This takes effect immediately,
+    /// without rebuilding the routes, and applies to every copy of the router,
+    /// such as every version of a `SharedRouter`.
+    pub fn set_maintenance(&self, mode: MaintenanceMode) {
+        self.maintenance.set_mode(mode);
+    }
+
+    /// Returns the current maintenance mode.
+    pub fn maintenance(&self) -> MaintenanceMode {
+        self.maintenance.mode()
+    }
+
+    /// Sets the 503 response sent for routes in maintenance: an optional
+    /// `Retry-After` (in whole seconds) and a body. An empty body leaves the
+    /// router's built-in response, a problem document if
+    /// [`Router::use_problem_responses`] is enabled.
+    pub fn set_maintenance_response<B: Into<Bytes>>(&self, retry_after: Option<Duration>, body: B) {
+        let body = body.into();
+        self.maintenance.set_response(MaintenanceResponse {
+            retry_after,
+            body: (!body.is_empty()).then_some(body),
+        });
+    }
+
+    /// Answers a request matching `entry` with a 503 Service Unavailable if
+    /// `path` is in maintenance, unless the route is `maintenance_exempt`.
+    pub(crate) fn maintenance_check(&self, entry: &RouteEntry, path: &str) -> Option<HttpResponse> {
+        if entry.maintenance_exempt {
+            return None;
+        }
+        let mut response = self.maintenance_response(self.maintenance.check(path)?);
+        self.record_route(&mut response, entry);
+        Some(response)
+    }
+
+    /// Builds the 503 Service Unavailable for a route in maintenance.
+    fn maintenance_response(&self, maintenance: MaintenanceResponse) -> HttpResponse {
+        let mut response = match maintenance.body {
+            Some(body) => HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE, body),
+            None => self.error_response(StatusCode::SERVICE_UNAVAILABLE, Bytes::new(), |p| {
+                p.with_detail("The service is undergoing maintenance.")
+            }),
+        };
+        if let Some(retry_after) = maintenance.retry_after {
+            response.add_header(
+                http::header::RETRY_AFTER,
+                &retry_after.as_secs().to_string(),
+            );
+        }
+        response
+    }
+}
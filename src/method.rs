@@ -0,0 +1,34 @@
+//! Defines how the router treats the `TRACE` and `CONNECT` methods, which
+//! security scanners probe for, and the methods an `"ANY"` endpoint stands for.
+
+use http::Method;
+
+/// The methods of an endpoint with `"method": "ANY"` in the configuration,
+/// one endpoint for each. `TRACE` and `CONNECT` are left out: a route for
+/// them must be declared on its own.
+pub(crate) const ANY_METHODS: [Method; 7] = [
+    Method::GET,
+    Method::HEAD,
+    Method::POST,
+    Method::PUT,
+    Method::DELETE,
+    Method::PATCH,
+    Method::OPTIONS,
+];
+
+/// How the router answers `TRACE` requests, set with
+/// `Router::set_trace_policy`.
+///
+/// Whichever is chosen, a `CONNECT` request gets a 501 Not Implemented unless
+/// a `CONNECT` route matches it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TracePolicy {
+    /// Answer with a 405 Method Not Allowed, listing the path's other methods
+    /// in its `Allow` header, even if a `TRACE` route matches. `TRACE` routes
+    /// are left out of the `Allow` header of other methods too, and a path
+    /// without any other route gets a 404 Not Found.
+    #[default]
+    Reject,
+    /// Route `TRACE` requests like any other method.
+    Allow,
+}
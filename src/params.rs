@@ -0,0 +1,104 @@
+//! Defines the `Params` type holding the path parameters extracted by the router.
+
+use std::collections::HashMap;
+use std::ops::Index;
+
+/// The path parameters of a request, in path order.
+///
+/// Most routes have a handful of parameters at most, so with the `smallvec`
+/// feature they are stored inline without a separate allocation. Either way,
+/// parameterless routes don't allocate at all.
+/// The accessors mirror the commonly used parts of `HashMap<String, String>`;
+/// use [`Params::to_hashmap`] where an actual map is needed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Params {
+    inner: Pairs,
+}
+
+#[cfg(feature = "smallvec")]
+type Pairs = smallvec::SmallVec<[(Box<str>, Box<str>); 4]>;
+#[cfg(not(feature = "smallvec"))]
+type Pairs = Vec<(Box<str>, Box<str>)>;
+
+impl Params {
+    /// Creates an empty set of parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the value of the parameter `name`, if present.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.inner
+            .iter()
+            .find(|(key, _)| &**key == name)
+            .map(|(_, value)| &**value)
+    }
+
+    /// Returns `true` if a parameter named `name` is present.
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Iterates over the `(name, value)` pairs, in path order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.inner.iter().map(|(key, value)| (&**key, &**value))
+    }
+
+    /// Returns the number of parameters.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns `true` if there are no parameters.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Iterates over mutable references to the values, in path order.
+    pub(crate) fn values_mut(&mut self) -> impl Iterator<Item = &mut Box<str>> {
+        self.inner.iter_mut().map(|(_, value)| value)
+    }
+
+    /// Adds a parameter, replacing the value of an existing one with the same name.
+    pub fn insert<K: Into<Box<str>>, V: Into<Box<str>>>(&mut self, name: K, value: V) {
+        let name = name.into();
+        let value = value.into();
+        match self.inner.iter_mut().find(|(key, _)| *key == name) {
+            Some((_, existing)) => *existing = value,
+            None => self.inner.push((name, value)),
+        }
+    }
+
+    /// Copies the parameters into a `HashMap`.
+    pub fn to_hashmap(&self) -> HashMap<String, String> {
+        self.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+}
+
+/// Allows `params["id"]`, panicking if the parameter is missing, like `HashMap`.
+impl Index<&str> for Params {
+    type Output = str;
+
+    fn index(&self, name: &str) -> &str {
+        self.get(name)
+            .unwrap_or_else(|| panic!("no path parameter named '{name}'"))
+    }
+}
+
+impl<K: Into<Box<str>>, V: Into<Box<str>>> FromIterator<(K, V)> for Params {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut params = Self::new();
+        for (name, value) in iter {
+            params.insert(name, value);
+        }
+        params
+    }
+}
+
+impl From<HashMap<String, String>> for Params {
+    fn from(map: HashMap<String, String>) -> Self {
+        map.into_iter().collect()
+    }
+}
@@ -0,0 +1,107 @@
+//! Defines the parsing of request bodies and query strings, memoized on the
+//! request so that middleware, validation and the handler share one parse.
+
+use crate::error::BodyError;
+use crate::path::{self, PathDecoding};
+use crate::request::HttpRequest;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+/// The parsed forms of a request's body and query string, filled on first use.
+#[derive(Debug, Default)]
+pub(crate) struct Parsed {
+    json: OnceLock<Result<Value, String>>,
+    form: OnceLock<Result<Vec<(String, String)>, String>>,
+    query: OnceLock<Result<Vec<(String, String)>, String>>,
+}
+
+impl HttpRequest {
+    /// Parses the body as JSON, whatever its `Content-Type`. The body is parsed
+    /// once: later calls, and [`HttpRequest::json`], reuse the value.
+    ///
+    /// The value is kept even if `body` is then replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BodyError::Json` if the body isn't valid JSON.
+    pub fn json_value(&self) -> Result<&Value, BodyError> {
+        self.parsed
+            .json
+            .get_or_init(|| serde_json::from_slice(&self.body).map_err(|e| e.to_string()))
+            .as_ref()
+            .map_err(|reason| BodyError::Json(reason.clone()))
+    }
+
+    /// Deserializes the JSON body into `T`, from the value parsed by
+    /// [`HttpRequest::json_value`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `BodyError::Json` if the body isn't valid JSON for `T`.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, BodyError> {
+        T::deserialize(self.json_value()?).map_err(|e| BodyError::Json(e.to_string()))
+    }
+
+    /// Parses the body as `application/x-www-form-urlencoded` name and value
+    /// pairs, in order, whatever its `Content-Type`. The body is parsed once,
+    /// like [`HttpRequest::json_value`]'s.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BodyError::Form` if the body isn't UTF-8 or has invalid
+    /// percent-encoding.
+    pub fn form_pairs(&self) -> Result<&[(String, String)], BodyError> {
+        self.parsed
+            .form
+            .get_or_init(|| {
+                let body = std::str::from_utf8(&self.body)
+                    .map_err(|_| "the body isn't valid UTF-8".to_string())?;
+                parse_pairs(body)
+            })
+            .as_deref()
+            .map_err(|reason| BodyError::Form(reason.clone()))
+    }
+
+    /// Parses the query string into name and value pairs, in order. It's
+    /// parsed once, like the body by [`HttpRequest::json_value`].
+    ///
+    /// Returns `None` if the query string has invalid percent-encoding.
+    pub fn query_pairs(&self) -> Option<&[(String, String)]> {
+        self.query_result().ok()
+    }
+
+    /// Returns the query string's pairs, or why they can't be parsed.
+    pub(crate) fn query_result(&self) -> Result<&[(String, String)], &str> {
+        self.parsed
+            .query
+            .get_or_init(|| parse_pairs(self.uri.query().unwrap_or("")))
+            .as_deref()
+            .map_err(String::as_str)
+    }
+
+    /// Returns `true` if the body has been parsed, as JSON or as a form, e.g.
+    /// to check in tests that it's parsed only once.
+    pub fn body_parsed(&self) -> bool {
+        self.parsed.json.get().is_some() || self.parsed.form.get().is_some()
+    }
+}
+
+/// Parses `name=value` pairs separated by `&`, percent-decoding both, with `+`
+/// for a space.
+fn parse_pairs(input: &str) -> Result<Vec<(String, String)>, String> {
+    let mut pairs = Vec::new();
+    for pair in input.split('&') {
+        if pair.is_empty() {
+            continue;
+        }
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let decode = |s: &str| {
+            path::percent_decode(&s.replace('+', " "), PathDecoding::Reject)
+                .map(|s| s.into_owned())
+                .ok_or_else(|| format!("invalid percent-encoding in '{pair}'"))
+        };
+        pairs.push((decode(name)?, decode(value)?));
+    }
+    Ok(pairs)
+}
@@ -0,0 +1,159 @@
+//! Utilities for normalizing and decoding request paths before route matching.
+
+use http::Uri;
+use http::uri::PathAndQuery;
+use std::borrow::Cow;
+
+/// Normalizes a request path by collapsing repeated slashes and resolving `.` and
+/// `..` segments. Nothing is percent-decoded.
+///
+/// A trailing slash is kept, since routes treat `/a` and `/a/` as different paths.
+///
+/// # Returns
+///
+/// The normalized path (borrowed when it was already normal), or `None` if a
+/// `..` segment would escape the root.
+pub fn normalize(path: &str) -> Option<Cow<'_, str>> {
+    if is_normal(path) {
+        return Some(Cow::Borrowed(path));
+    }
+
+    let mut segments: Vec<&str> = Vec::new();
+    let mut trailing_slash = false;
+    for segment in path.split('/') {
+        trailing_slash = false;
+        match segment {
+            "" => trailing_slash = true,
+            "." => trailing_slash = true,
+            ".." => {
+                segments.pop()?;
+                trailing_slash = true;
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    let mut normalized = String::with_capacity(path.len());
+    for segment in &segments {
+        normalized.push('/');
+        normalized.push_str(segment);
+    }
+    if trailing_slash || segments.is_empty() {
+        normalized.push('/');
+    }
+    Some(Cow::Owned(normalized))
+}
+
+/// Returns `true` if `path` has no empty, `.` or `..` segments (other than a
+/// single trailing slash).
+fn is_normal(path: &str) -> bool {
+    if !path.starts_with('/') {
+        return false;
+    }
+    let inner = path[1..].strip_suffix('/').unwrap_or(&path[1..]);
+    path == "/"
+        || inner
+            .split('/')
+            .all(|s| !s.is_empty() && s != "." && s != "..")
+}
+
+/// Returns a copy of `uri` with its path replaced by `path`, keeping the query string.
+pub(crate) fn replace_path(uri: &Uri, path: &str) -> Option<Uri> {
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path.to_string(),
+    };
+
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(PathAndQuery::try_from(path_and_query).ok()?);
+    Uri::from_parts(parts).ok()
+}
+
+/// How the router handles percent-encoded bytes in request paths.
+///
+/// Routes are matched against the path as received, still percent-encoded, so an
+/// encoded `/` (`%2F`) never splits a segment. Path parameter values are then
+/// percent-decoded as UTF-8 before they're passed to the handler.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PathDecoding {
+    /// Reject paths with invalid percent-encoding (e.g. `%zz` or a truncated `%4`)
+    /// or whose percent-encoded bytes aren't valid UTF-8, with a 400 Bad Request.
+    #[default]
+    Reject,
+    /// Accept every path: invalid escapes are kept as is, and invalid UTF-8 is
+    /// replaced with U+FFFD REPLACEMENT CHARACTER in decoded parameters.
+    Lossy,
+}
+
+/// Percent-decodes `input` as UTF-8, following `decoding`.
+///
+/// # Returns
+///
+/// The decoded string (borrowed when `input` has no `%`), or `None` if
+/// `decoding` is [`PathDecoding::Reject`] and `input` has invalid
+/// percent-encoding or doesn't decode to valid UTF-8.
+pub fn percent_decode(input: &str, decoding: PathDecoding) -> Option<Cow<'_, str>> {
+    if !input.contains('%') {
+        return Some(Cow::Borrowed(input));
+    }
+
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let escape = bytes
+                .get(i + 1..i + 3)
+                .and_then(|hex| Some(hex_value(hex[0])? << 4 | hex_value(hex[1])?));
+            match (escape, decoding) {
+                (Some(byte), _) => {
+                    decoded.push(byte);
+                    i += 3;
+                    continue;
+                }
+                (None, PathDecoding::Reject) => return None,
+                (None, PathDecoding::Lossy) => {}
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    match (String::from_utf8(decoded), decoding) {
+        (Ok(decoded), _) => Some(Cow::Owned(decoded)),
+        (Err(_), PathDecoding::Reject) => None,
+        (Err(e), PathDecoding::Lossy) => Some(Cow::Owned(
+            String::from_utf8_lossy(e.as_bytes()).into_owned(),
+        )),
+    }
+}
+
+/// Percent-encodes `input` for a path segment, keeping only the unreserved
+/// characters of RFC 3986, and `/` too if `keep_slash`, e.g. for catch-all
+/// parameters.
+pub(crate) fn percent_encode(input: &str, keep_slash: bool) -> Cow<'_, str> {
+    let keep = |byte: u8| {
+        byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) || (keep_slash && byte == b'/')
+    };
+    if input.bytes().all(keep) {
+        return Cow::Borrowed(input);
+    }
+    let mut encoded = String::with_capacity(input.len() * 3);
+    for byte in input.bytes() {
+        if keep(byte) {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    Cow::Owned(encoded)
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        _ => None,
+    }
+}
@@ -0,0 +1,95 @@
+//! Defines the export of a configuration as a Postman collection.
+
+use crate::config::{Config, Endpoint};
+use serde_json::{Value, json};
+
+/// The schema of the Postman collections generated, format v2.1.
+pub const POSTMAN_SCHEMA: &str =
+    "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+
+impl Config {
+    /// Generates a Postman v2.1 collection with a request for each endpoint,
+    /// which can also be imported into Insomnia.
+    ///
+    /// Requests are grouped into one folder per controller prefix (`users` for
+    /// `users::get_by_id`), in configuration order. Their URLs start with a
+    /// `{{base_url}}` collection variable set to `base_url`, and path parameters
+    /// become Postman path variables: `/users/:id` gives
+    /// `{{base_url}}/users/:id` with an `id` variable, and a catch-all `*rest`
+    /// gives a `rest` variable.
+    pub fn to_postman_collection(&self, base_url: &str) -> Value {
+        let mut folders: Vec<(&str, Vec<Value>)> = Vec::new();
+        for endpoint in &self.endpoints {
+            let folder = folder_name(endpoint);
+            let request = postman_request(endpoint);
+            match folders.iter_mut().find(|(name, _)| *name == folder) {
+                Some((_, requests)) => requests.push(request),
+                None => folders.push((folder, vec![request])),
+            }
+        }
+
+        let items: Vec<Value> = folders
+            .into_iter()
+            .map(|(name, requests)| json!({ "name": name, "item": requests }))
+            .collect();
+        json!({
+            "info": {
+                "name": "Routes",
+                "schema": POSTMAN_SCHEMA,
+            },
+            "item": items,
+            "variable": [{ "key": "base_url", "value": base_url }],
+        })
+    }
+}
+
+/// Returns the controller prefix an endpoint's request is grouped under: the
+/// controller name up to its last `::`, or the whole name if it has none.
+fn folder_name(endpoint: &Endpoint) -> &str {
+    let controller = match &endpoint.canary {
+        Some(canary) => &canary.stable,
+        None => &endpoint.controller,
+    };
+    controller
+        .rsplit_once("::")
+        .map_or(controller.as_str(), |(prefix, _)| prefix)
+}
+
+/// Builds the Postman request item for an endpoint.
+fn postman_request(endpoint: &Endpoint) -> Value {
+    let mut path = Vec::new();
+    let mut variables = Vec::new();
+    for segment in endpoint.path.split('/').filter(|s| !s.is_empty()) {
+        match segment
+            .strip_prefix(':')
+            .or_else(|| segment.strip_prefix('*'))
+        {
+            Some(name) => {
+                path.push(format!(":{name}"));
+                variables.push(json!({ "key": name, "value": "" }));
+            }
+            None => path.push(segment.to_string()),
+        }
+    }
+
+    let raw = format!("{{{{base_url}}}}/{}", path.join("/"));
+    let name = if endpoint.description.is_empty() {
+        format!("{} {}", endpoint.method, endpoint.path)
+    } else {
+        endpoint.description.clone()
+    };
+    json!({
+        "name": name,
+        "request": {
+            "method": endpoint.method.as_str(),
+            "header": [],
+            "url": {
+                "raw": raw,
+                "host": ["{{base_url}}"],
+                "path": path,
+                "variable": variables,
+            },
+            "description": endpoint.description,
+        },
+    })
+}
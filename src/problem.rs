@@ -0,0 +1,103 @@
+//! Defines `Problem`, an RFC 7807 `application/problem+json` error document.
+
+use crate::response::HttpResponse;
+use http::StatusCode;
+use http::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// The content type of a problem document.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// An RFC 7807 problem details document.
+///
+/// Serializes to a JSON object with the standard members, and any
+/// `extensions` as additional members alongside them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Problem {
+    /// A URI reference identifying the problem type. Defaults to `about:blank`.
+    #[serde(rename = "type", default = "about_blank")]
+    pub problem_type: String,
+    /// A short, human-readable summary of the problem type.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The HTTP status code of the response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// A human-readable explanation specific to this occurrence of the problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// A URI reference identifying this occurrence of the problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Additional, problem-type specific members.
+    #[serde(flatten)]
+    pub extensions: Map<String, Value>,
+}
+
+fn about_blank() -> String {
+    "about:blank".to_string()
+}
+
+impl Problem {
+    /// Creates an `about:blank` problem for `status`, titled with the status's
+    /// canonical reason phrase.
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            problem_type: about_blank(),
+            title: status.canonical_reason().map(str::to_string),
+            status: Some(status.as_u16()),
+            detail: None,
+            instance: None,
+            extensions: Map::new(),
+        }
+    }
+
+    /// Sets the problem type URI.
+    pub fn with_type<S: Into<String>>(mut self, problem_type: S) -> Self {
+        self.problem_type = problem_type.into();
+        self
+    }
+
+    /// Sets the title.
+    pub fn with_title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Sets the detail.
+    pub fn with_detail<S: Into<String>>(mut self, detail: S) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Sets the instance URI.
+    pub fn with_instance<S: Into<String>>(mut self, instance: S) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Adds an extension member.
+    pub fn with_extension<K: Into<String>, V: Into<Value>>(mut self, key: K, value: V) -> Self {
+        self.extensions.insert(key.into(), value.into());
+        self
+    }
+}
+
+impl HttpResponse {
+    /// Creates an `application/problem+json` response from `problem`.
+    ///
+    /// The response status is the problem's `status`, or 500 Internal Server
+    /// Error if it's missing or invalid.
+    pub fn problem(problem: Problem) -> Self {
+        let status = problem
+            .status
+            .and_then(|status| StatusCode::from_u16(status).ok())
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        // Serializing a map of strings and JSON values can't fail.
+        let body = serde_json::to_vec(&problem).unwrap_or_default();
+        let mut response = HttpResponse::new(status, body);
+        response.add_header(CONTENT_TYPE, PROBLEM_JSON);
+        response
+    }
+}
@@ -0,0 +1,689 @@
+//! Implements the public-key signatures of JWT validation: RSASSA-PKCS1-v1_5
+//! with SHA-256 (RFC 8017, `RS256`) and ECDSA over P-256 with SHA-256
+//! (FIPS 186-4, `ES256`), on unsigned integers of 64-bit limbs with Montgomery
+//! multiplication.
+//!
+//! Only verification is implemented, which handles public values alone, so
+//! none of it needs to run in constant time.
+//!
+//! It's compiled with the `jwt-public-key` feature only: `jsonwebtoken` can't
+//! be used in this build, and this code hasn't been audited.
+
+use crate::crypto;
+use std::fmt;
+
+/// The DER encoding of the SHA-256 `DigestInfo` prefix of an RSASSA-PKCS1-v1_5
+/// encoded message (RFC 8017, section 9.2).
+const SHA256_DIGEST_INFO: [u8; 19] = [
+    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05,
+    0x00, 0x04, 0x20,
+];
+
+/// The smallest RSA modulus accepted, in bits.
+const MIN_RSA_BITS: usize = 2048;
+/// The largest RSA modulus accepted, in bits.
+const MAX_RSA_BITS: usize = 8192;
+
+/// An RSA public key verifying `RS256` signatures.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RsaPublicKey {
+    modulus: Modulus,
+    /// The modulus' length in bytes, which is also the signatures'.
+    len: usize,
+    exponent: Vec<u64>,
+}
+
+impl RsaPublicKey {
+    /// Creates a key from its big-endian modulus `n` and public exponent `e`,
+    /// as in the `n` and `e` members of a JWK.
+    ///
+    /// # Errors
+    ///
+    /// Returns why the key is rejected: a modulus that's even or not 2048 to
+    /// 8192 bits long, or an exponent that's even, 1, or not below the
+    /// modulus.
+    pub fn new(n: &[u8], e: &[u8]) -> Result<Self, String> {
+        let n = trim_leading_zeros(n);
+        let bits = n
+            .first()
+            .map_or(0, |byte| n.len() * 8 - byte.leading_zeros() as usize);
+        if !(MIN_RSA_BITS..=MAX_RSA_BITS).contains(&bits) {
+            return Err(format!(
+                "the RSA modulus has {bits} bits instead of {MIN_RSA_BITS} to {MAX_RSA_BITS}"
+            ));
+        }
+        let modulus = Modulus::new(from_be_bytes(n, n.len().div_ceil(8)))
+            .ok_or_else(|| "the RSA modulus is even".to_string())?;
+        let e = trim_leading_zeros(e);
+        if e.len() > n.len() {
+            return Err("the RSA exponent isn't below the modulus".to_string());
+        }
+        let exponent = from_be_bytes(e, modulus.limbs());
+        if exponent[0] & 1 == 0 || is_one(&exponent) || !less_than(&exponent, &modulus.n) {
+            return Err("the RSA exponent must be odd, above 1 and below the modulus".to_string());
+        }
+        Ok(Self {
+            len: n.len(),
+            modulus,
+            exponent,
+        })
+    }
+
+    /// Returns `true` if `signature` is the RSASSA-PKCS1-v1_5 signature of
+    /// `message` with SHA-256.
+    pub(crate) fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        if signature.len() != self.len {
+            return false;
+        }
+        let s = from_be_bytes(signature, self.modulus.limbs());
+        if !less_than(&s, &self.modulus.n) {
+            return false;
+        }
+        let m = self
+            .modulus
+            .reduce(&self.modulus.pow(&self.modulus.to_mont(&s), &self.exponent));
+        let encoded = to_be_bytes(&m, self.len);
+
+        let mut expected = vec![0xff; self.len];
+        let digest_start = self.len - SHA256_DIGEST_INFO.len() - 32;
+        expected[0] = 0x00;
+        expected[1] = 0x01;
+        expected[digest_start - 1] = 0x00;
+        expected[digest_start..self.len - 32].copy_from_slice(&SHA256_DIGEST_INFO);
+        expected[self.len - 32..].copy_from_slice(&crypto::sha256(message));
+        crypto::constant_time_eq(&encoded, &expected)
+    }
+}
+
+impl fmt::Debug for RsaPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RsaPublicKey")
+            .field("bits", &(self.len * 8))
+            .finish_non_exhaustive()
+    }
+}
+
+/// A P-256 public key verifying `ES256` signatures.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EcPublicKey {
+    /// The point, in the Montgomery form of the field.
+    point: Point,
+}
+
+impl EcPublicKey {
+    /// Creates a key from the big-endian affine coordinates of its point, as
+    /// in the `x` and `y` members of a JWK.
+    ///
+    /// # Errors
+    ///
+    /// Returns why the key is rejected: coordinates that aren't 32 bytes long
+    /// or below the field's prime, or a point that isn't on the curve.
+    pub fn new(x: &[u8], y: &[u8]) -> Result<Self, String> {
+        if x.len() != 32 || y.len() != 32 {
+            return Err("the P-256 coordinates must be 32 bytes long".to_string());
+        }
+        let curve = P256::get();
+        let (x, y) = (from_be_bytes(x, 4), from_be_bytes(y, 4));
+        if !less_than(&x, &curve.p.n) || !less_than(&y, &curve.p.n) {
+            return Err("the P-256 coordinates must be below the field's prime".to_string());
+        }
+        let p = &curve.p;
+        let (x, y) = (p.to_mont(&x), p.to_mont(&y));
+        // y² = x³ - 3x + b
+        let x3 = p.mul(&p.mul(&x, &x), &x);
+        let three_x = p.add(&p.add(&x, &x), &x);
+        let rhs = p.add(&p.sub(&x3, &three_x), &curve.b);
+        if p.mul(&y, &y) != rhs {
+            return Err("the point isn't on the P-256 curve".to_string());
+        }
+        Ok(Self {
+            point: Point { x, y, z: p.one() },
+        })
+    }
+
+    /// Returns `true` if `signature` is the ECDSA signature of `message` with
+    /// SHA-256, as the 32-byte big-endian `r` and `s` of a JWS.
+    pub(crate) fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        if signature.len() != 64 {
+            return false;
+        }
+        let curve = P256::get();
+        let order = &curve.n;
+        let r = from_be_bytes(&signature[..32], 4);
+        let s = from_be_bytes(&signature[32..], 4);
+        if is_zero(&r) || is_zero(&s) || !less_than(&r, &order.n) || !less_than(&s, &order.n) {
+            return false;
+        }
+        // The digest has as many bits as the order, so it's reduced by at
+        // most one subtraction.
+        let mut e = from_be_bytes(&crypto::sha256(message), 4);
+        if !less_than(&e, &order.n) {
+            sub_in_place(&mut e, &order.n);
+        }
+
+        // w = s⁻¹ mod n, by Fermat's little theorem.
+        let mut n_minus_2 = order.n.clone();
+        sub_in_place(&mut n_minus_2, &[2, 0, 0, 0]);
+        let w = order.pow(&order.to_mont(&s), &n_minus_2);
+        let u1 = order.reduce(&order.mul(&order.to_mont(&e), &w));
+        let u2 = order.reduce(&order.mul(&order.to_mont(&r), &w));
+
+        let sum = curve.add(
+            &curve.multiply(&curve.g, &u1),
+            &curve.multiply(&self.point, &u2),
+        );
+        let Some((x, _)) = curve.to_affine(&sum) else {
+            return false;
+        };
+        let mut x = curve.p.reduce(&x);
+        if !less_than(&x, &order.n) {
+            sub_in_place(&mut x, &order.n);
+        }
+        x == r
+    }
+}
+
+impl fmt::Debug for EcPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EcPublicKey")
+            .field("curve", &"P-256")
+            .finish_non_exhaustive()
+    }
+}
+
+/// An odd modulus, with the constants of Montgomery multiplication by
+/// R = 2^(64·limbs).
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Modulus {
+    n: Vec<u64>,
+    /// -n⁻¹ mod 2^64.
+    n_inv: u64,
+    /// R² mod n, to convert into the Montgomery form.
+    r2: Vec<u64>,
+}
+
+impl Modulus {
+    /// Returns the modulus `n`, little-endian limbs, or `None` if it's even or
+    /// 1.
+    fn new(mut n: Vec<u64>) -> Option<Self> {
+        while n.len() > 1 && n.last() == Some(&0) {
+            n.pop();
+        }
+        if n[0] & 1 == 0 || is_one(&n) {
+            return None;
+        }
+        // Newton's iteration doubles the correct low bits of the inverse each
+        // step, from the 1 bit of n[0]⁻¹ = 1 mod 2.
+        let mut inv = 1u64;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(n[0].wrapping_mul(inv)));
+        }
+
+        // R² mod n, by doubling 1 modulo n 2·64·limbs times.
+        let mut r2 = vec![0; n.len()];
+        r2[0] = 1;
+        for _ in 0..2 * 64 * n.len() {
+            let carry = r2.iter_mut().fold(0, |carry, limb| {
+                let next = *limb >> 63;
+                *limb = *limb << 1 | carry;
+                next
+            });
+            if carry == 1 || !less_than(&r2, &n) {
+                sub_in_place(&mut r2, &n);
+            }
+        }
+        Some(Self {
+            n_inv: inv.wrapping_neg(),
+            n,
+            r2,
+        })
+    }
+
+    fn limbs(&self) -> usize {
+        self.n.len()
+    }
+
+    /// Returns a·b·R⁻¹ mod n, for a and b below n.
+    fn mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let k = self.limbs();
+        let mut t = vec![0u64; k + 2];
+        for &b_i in b {
+            let mut carry = 0u128;
+            for j in 0..k {
+                let sum = u128::from(t[j]) + u128::from(a[j]) * u128::from(b_i) + carry;
+                t[j] = sum as u64;
+                carry = sum >> 64;
+            }
+            let sum = u128::from(t[k]) + carry;
+            t[k] = sum as u64;
+            t[k + 1] = (sum >> 64) as u64;
+
+            let m = t[0].wrapping_mul(self.n_inv);
+            let mut carry = (u128::from(t[0]) + u128::from(m) * u128::from(self.n[0])) >> 64;
+            for j in 1..k {
+                let sum = u128::from(t[j]) + u128::from(m) * u128::from(self.n[j]) + carry;
+                t[j - 1] = sum as u64;
+                carry = sum >> 64;
+            }
+            let sum = u128::from(t[k]) + carry;
+            t[k - 1] = sum as u64;
+            t[k] = t[k + 1] + (sum >> 64) as u64;
+            t[k + 1] = 0;
+        }
+        let overflow = t[k] != 0;
+        t.truncate(k);
+        if overflow || !less_than(&t, &self.n) {
+            sub_in_place(&mut t, &self.n);
+        }
+        t
+    }
+
+    /// Returns the Montgomery form of `a`, a·R mod n.
+    fn to_mont(&self, a: &[u64]) -> Vec<u64> {
+        self.mul(a, &self.r2)
+    }
+
+    /// Returns the value of `a` in the Montgomery form, a·R⁻¹ mod n.
+    fn reduce(&self, a: &[u64]) -> Vec<u64> {
+        let mut one = vec![0; self.limbs()];
+        one[0] = 1;
+        self.mul(a, &one)
+    }
+
+    /// Returns 1 in the Montgomery form.
+    fn one(&self) -> Vec<u64> {
+        let mut one = vec![0; self.limbs()];
+        one[0] = 1;
+        self.to_mont(&one)
+    }
+
+    /// Returns a + b mod n, for a and b below n.
+    fn add(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut sum = vec![0; self.limbs()];
+        let mut carry = 0u128;
+        for (limb, (a, b)) in sum.iter_mut().zip(a.iter().zip(b)) {
+            let s = u128::from(*a) + u128::from(*b) + carry;
+            *limb = s as u64;
+            carry = s >> 64;
+        }
+        if carry == 1 || !less_than(&sum, &self.n) {
+            sub_in_place(&mut sum, &self.n);
+        }
+        sum
+    }
+
+    /// Returns a - b mod n, for a and b below n.
+    fn sub(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut difference = a.to_vec();
+        if sub_in_place(&mut difference, b) {
+            // Wraps around 2^(64·limbs) back into the range.
+            let mut carry = 0u128;
+            for (limb, n) in difference.iter_mut().zip(&self.n) {
+                let s = u128::from(*limb) + u128::from(*n) + carry;
+                *limb = s as u64;
+                carry = s >> 64;
+            }
+        }
+        difference
+    }
+
+    /// Returns base^exponent in the Montgomery form, for `base` in it.
+    fn pow(&self, base: &[u64], exponent: &[u64]) -> Vec<u64> {
+        let mut result = self.one();
+        let top = exponent
+            .iter()
+            .rposition(|&limb| limb != 0)
+            .map_or(0, |i| i * 64 + 64 - exponent[i].leading_zeros() as usize);
+        for bit in (0..top).rev() {
+            result = self.mul(&result, &result);
+            if exponent[bit / 64] >> (bit % 64) & 1 == 1 {
+                result = self.mul(&result, base);
+            }
+        }
+        result
+    }
+}
+
+/// A point of P-256 in Jacobian coordinates, in the Montgomery form of the
+/// field; z = 0 for the point at infinity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Point {
+    x: Vec<u64>,
+    y: Vec<u64>,
+    z: Vec<u64>,
+}
+
+/// The domain parameters of P-256 (FIPS 186-4, section D.1.2.3).
+struct P256 {
+    /// The field's prime.
+    p: Modulus,
+    /// The order of the base point.
+    n: Modulus,
+    /// The coefficient b, in the Montgomery form (a is -3).
+    b: Vec<u64>,
+    /// The base point.
+    g: Point,
+}
+
+impl P256 {
+    fn get() -> &'static Self {
+        static CURVE: std::sync::OnceLock<P256> = std::sync::OnceLock::new();
+        CURVE.get_or_init(|| {
+            let limbs = |hex: &str| {
+                let bytes: Vec<u8> = (0..hex.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or_default())
+                    .collect();
+                from_be_bytes(&bytes, 4)
+            };
+            let p = Modulus::new(limbs(
+                "ffffffff00000001000000000000000000000000ffffffffffffffffffffffff",
+            ))
+            .expect("the P-256 prime is odd");
+            let n = Modulus::new(limbs(
+                "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551",
+            ))
+            .expect("the P-256 order is odd");
+            let b = p.to_mont(&limbs(
+                "5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b",
+            ));
+            let g = Point {
+                x: p.to_mont(&limbs(
+                    "6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296",
+                )),
+                y: p.to_mont(&limbs(
+                    "4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5",
+                )),
+                z: p.one(),
+            };
+            P256 { p, n, b, g }
+        })
+    }
+
+    fn infinity(&self) -> Point {
+        Point {
+            x: self.p.one(),
+            y: self.p.one(),
+            z: vec![0; 4],
+        }
+    }
+
+    /// Doubles a point, with the `dbl-2001-b` formulas for a = -3.
+    fn double(&self, point: &Point) -> Point {
+        let p = &self.p;
+        if is_zero(&point.z) || is_zero(&point.y) {
+            return self.infinity();
+        }
+        let delta = p.mul(&point.z, &point.z);
+        let gamma = p.mul(&point.y, &point.y);
+        let beta = p.mul(&point.x, &gamma);
+        let t = p.mul(&p.sub(&point.x, &delta), &p.add(&point.x, &delta));
+        let alpha = p.add(&p.add(&t, &t), &t);
+        let beta4 = p.add(&p.add(&beta, &beta), &p.add(&beta, &beta));
+        let x = p.sub(&p.mul(&alpha, &alpha), &p.add(&beta4, &beta4));
+        let yz = p.add(&point.y, &point.z);
+        let z = p.sub(&p.sub(&p.mul(&yz, &yz), &gamma), &delta);
+        let gamma2 = p.mul(&gamma, &gamma);
+        let gamma2_4 = p.add(&p.add(&gamma2, &gamma2), &p.add(&gamma2, &gamma2));
+        let y = p.sub(
+            &p.mul(&alpha, &p.sub(&beta4, &x)),
+            &p.add(&gamma2_4, &gamma2_4),
+        );
+        Point { x, y, z }
+    }
+
+    /// Adds two points, with the `add-2007-bl` formulas.
+    fn add(&self, a: &Point, b: &Point) -> Point {
+        let p = &self.p;
+        if is_zero(&a.z) {
+            return b.clone();
+        }
+        if is_zero(&b.z) {
+            return a.clone();
+        }
+        let z1z1 = p.mul(&a.z, &a.z);
+        let z2z2 = p.mul(&b.z, &b.z);
+        let u1 = p.mul(&a.x, &z2z2);
+        let u2 = p.mul(&b.x, &z1z1);
+        let s1 = p.mul(&p.mul(&a.y, &b.z), &z2z2);
+        let s2 = p.mul(&p.mul(&b.y, &a.z), &z1z1);
+        let h = p.sub(&u2, &u1);
+        let s = p.sub(&s2, &s1);
+        if is_zero(&h) {
+            return if is_zero(&s) {
+                self.double(a)
+            } else {
+                self.infinity()
+            };
+        }
+        let r = p.add(&s, &s);
+        let h2 = p.add(&h, &h);
+        let i = p.mul(&h2, &h2);
+        let j = p.mul(&h, &i);
+        let v = p.mul(&u1, &i);
+        let x = p.sub(&p.sub(&p.mul(&r, &r), &j), &p.add(&v, &v));
+        let s1j = p.mul(&s1, &j);
+        let y = p.sub(&p.mul(&r, &p.sub(&v, &x)), &p.add(&s1j, &s1j));
+        let zs = p.add(&a.z, &b.z);
+        let z = p.mul(&p.sub(&p.sub(&p.mul(&zs, &zs), &z1z1), &z2z2), &h);
+        Point { x, y, z }
+    }
+
+    /// Returns scalar·point, by double-and-add.
+    fn multiply(&self, point: &Point, scalar: &[u64]) -> Point {
+        let mut result = self.infinity();
+        for bit in (0..256).rev() {
+            result = self.double(&result);
+            if scalar[bit / 64] >> (bit % 64) & 1 == 1 {
+                result = self.add(&result, point);
+            }
+        }
+        result
+    }
+
+    /// Returns the affine coordinates of a point, in the Montgomery form, or
+    /// `None` for the point at infinity.
+    fn to_affine(&self, point: &Point) -> Option<(Vec<u64>, Vec<u64>)> {
+        let p = &self.p;
+        if is_zero(&point.z) {
+            return None;
+        }
+        let mut p_minus_2 = p.n.clone();
+        sub_in_place(&mut p_minus_2, &[2, 0, 0, 0]);
+        let z_inv = p.pow(&point.z, &p_minus_2);
+        let z_inv2 = p.mul(&z_inv, &z_inv);
+        Some((
+            p.mul(&point.x, &z_inv2),
+            p.mul(&point.y, &p.mul(&z_inv2, &z_inv)),
+        ))
+    }
+}
+
+fn trim_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|&byte| byte != 0)
+        .unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+/// Converts big-endian bytes into `limbs` little-endian limbs, dropping the
+/// bytes that don't fit.
+fn from_be_bytes(bytes: &[u8], limbs: usize) -> Vec<u64> {
+    let mut value = vec![0u64; limbs];
+    for (i, &byte) in bytes.iter().rev().enumerate().take(limbs * 8) {
+        value[i / 8] |= u64::from(byte) << (8 * (i % 8));
+    }
+    value
+}
+
+/// Converts little-endian limbs into `len` big-endian bytes.
+fn to_be_bytes(value: &[u64], len: usize) -> Vec<u8> {
+    (0..len)
+        .rev()
+        .map(|i| {
+            value
+                .get(i / 8)
+                .map_or(0, |limb| (limb >> (8 * (i % 8))) as u8)
+        })
+        .collect()
+}
+
+fn is_zero(value: &[u64]) -> bool {
+    value.iter().all(|&limb| limb == 0)
+}
+
+fn is_one(value: &[u64]) -> bool {
+    value[0] == 1 && is_zero(&value[1..])
+}
+
+/// Returns `true` if a < b, for values of as many limbs.
+fn less_than(a: &[u64], b: &[u64]) -> bool {
+    for (a, b) in a.iter().zip(b).rev() {
+        if a != b {
+            return a < b;
+        }
+    }
+    false
+}
+
+/// Subtracts b from a, returning whether it borrowed past the top limb.
+fn sub_in_place(a: &mut [u64], b: &[u64]) -> bool {
+    let mut borrow = false;
+    for (i, limb) in a.iter_mut().enumerate() {
+        let (difference, borrowed) = limb.overflowing_sub(b.get(i).copied().unwrap_or(0));
+        let (difference, borrowed_again) = difference.overflowing_sub(u64::from(borrow));
+        *limb = difference;
+        borrow = borrowed || borrowed_again;
+    }
+    borrow
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limbs(value: u128, count: usize) -> Vec<u64> {
+        let mut limbs = vec![value as u64, (value >> 64) as u64];
+        limbs.resize(count, 0);
+        limbs
+    }
+
+    #[test]
+    fn montgomery_arithmetic_matches_u128() {
+        let n = (1u128 << 127) - 1;
+        let modulus = Modulus::new(limbs(n, 2)).unwrap();
+        let (a, b) = (0x1234_5678_9abc_def0_0fed_cba9_8765_4321u128, n - 3);
+        let product = modulus.reduce(&modulus.mul(
+            &modulus.to_mont(&limbs(a, 2)),
+            &modulus.to_mont(&limbs(b, 2)),
+        ));
+        // (n - 3)·a ≡ -3a (mod n)
+        let expected = n - (3 * a) % n;
+        assert_eq!(product, limbs(expected, 2));
+        assert_eq!(modulus.add(&limbs(n - 1, 2), &limbs(5, 2)), limbs(4, 2));
+        assert_eq!(modulus.sub(&limbs(1, 2), &limbs(2, 2)), limbs(n - 1, 2));
+    }
+
+    #[test]
+    fn modular_exponentiation() {
+        let modulus = Modulus::new(limbs(1_000_000_007, 1)).unwrap();
+        let power = modulus.reduce(&modulus.pow(&modulus.to_mont(&[2]), &[1_000_000_005]));
+        // 2^(p-2) is the inverse of 2 modulo the prime p.
+        assert_eq!(power, [500_000_004]);
+        assert_eq!(Modulus::new(vec![10]), None);
+        assert_eq!(Modulus::new(vec![1, 0]), None);
+    }
+
+    #[test]
+    fn byte_conversions() {
+        let bytes = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let value = from_be_bytes(&bytes, 2);
+        assert_eq!(value, [0x0203_0405_0607_0809, 1]);
+        assert_eq!(to_be_bytes(&value, 9), bytes);
+        assert_eq!(to_be_bytes(&value, 10)[0], 0);
+    }
+
+    #[test]
+    fn the_base_point_has_the_curve_order() {
+        let curve = P256::get();
+        let mut order_minus_1 = curve.n.n.clone();
+        sub_in_place(&mut order_minus_1, &[1, 0, 0, 0]);
+        let minus_g = curve.multiply(&curve.g, &order_minus_1);
+        assert!(is_zero(&curve.add(&minus_g, &curve.g).z));
+        let (x, _) = curve.to_affine(&minus_g).unwrap();
+        let (gx, _) = curve.to_affine(&curve.g).unwrap();
+        assert_eq!(x, gx);
+    }
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    const MESSAGE: &[u8] = b"public-key verification";
+    // A 2048-bit key and a signature of `MESSAGE` made with Python's
+    // cryptography package, with the exponent 65537.
+    const RSA_N: &str = "ab88f8d4489211ce5f97a4b4f8b8d4b2300e92b091ecb32f70ca929e8188505f949b3ebd7a3d778fd87614e912a918f005169c2aef8627dfa3908f9ab8599f19be120583158018bcb2a5cd3ec96b7e830a75da202550e54fb153d7c68ce0a108c01eee1a2a66d73fc954f052e4a29d5907f37229da1e3a5d899b4bfcd6c05fe4fc90b573aa80cb5b138765f048692d774f67303614472012f29171a7aed336e1e287460fac1e7099927d9ed3871d1a1406c1fe6009dcc1d7e8994fc3379bfb438965d2cc50c48d2a2dc18921743b99aabf9ec691a5a075c5c969e23a73e956ddbb96a55c120a8e5e3949d6d8309565f3b4399d4461bd31e815fbe49fd9bc109b";
+    const RSA_SIGNATURE: &str = "a8e12cee5c5916f4964ec7f03ea667fab91783d681239545a1068a08a7aff3b3d89667badfdf45cc28ce1ca2dd49a4350cc6a31558d93b771726add423baee1ff3b593bfd5c4cf8cbc86399ece776eeab6461caf0a4d783830f85ef24474dbd630cd449ced5ca6b6f35aae57ade16f1a91963e3e521906560039f72327503af0fb704e9aca9301878c1622f1ade2240d3cf8b6dfc4ffd5a8c62b0fd280e6160e9e55c6c87aeb3b6b9afc0132367f9bd890fc7f8f73eb790d9714109c29697ca9075b40033a3513c9d86bb379c39d439bb12b2edaf91f79cb39818e5adb804783b79fe546a6822efc4048fd4ade890f869d5383df24be37ec2f561a79b83afce6";
+    // A P-256 key and a signature of `MESSAGE`, also made with cryptography.
+    const EC_X: &str = "c8402dc9af17ade1cb479cd0aa954b189b68aafca89e612be877302832650388";
+    const EC_Y: &str = "3c2f2718b9f37885d981de98bf07c94f5849520904a770840f205b120de3d200";
+    const EC_SIGNATURE: &str = "7a98bf0d3efb39f47e27cc92114d0ab8031634f4717d84f3c073c0e9d13a32a7e7f15de04265641f83269e0009cd056696e3b7dbb47f9f7059736e361483ec2c";
+
+    #[test]
+    fn rsa_signatures() {
+        let key = RsaPublicKey::new(&hex(RSA_N), &[1, 0, 1]).unwrap();
+        let signature = hex(RSA_SIGNATURE);
+        assert!(key.verify(MESSAGE, &signature));
+        assert!(!key.verify(b"public-key verification!", &signature));
+        let mut tampered = signature.clone();
+        tampered[100] ^= 1;
+        assert!(!key.verify(MESSAGE, &tampered));
+        assert!(!key.verify(MESSAGE, &signature[1..]));
+        // A signature at or above the modulus.
+        assert!(!key.verify(MESSAGE, &hex(RSA_N)));
+    }
+
+    #[test]
+    fn rsa_keys_are_checked() {
+        let n = hex(RSA_N);
+        assert!(RsaPublicKey::new(&n[..128], &[1, 0, 1]).is_err());
+        let mut even = n.clone();
+        *even.last_mut().unwrap() &= 0xfe;
+        assert!(RsaPublicKey::new(&even, &[1, 0, 1]).is_err());
+        assert!(RsaPublicKey::new(&n, &[1]).is_err());
+        assert!(RsaPublicKey::new(&n, &[2]).is_err());
+        assert!(RsaPublicKey::new(&n, &n).is_err());
+        let mut padded = vec![0, 0];
+        padded.extend_from_slice(&n);
+        assert_eq!(
+            RsaPublicKey::new(&padded, &[0, 1, 0, 1]),
+            RsaPublicKey::new(&n, &[1, 0, 1])
+        );
+    }
+
+    #[test]
+    fn ecdsa_signatures() {
+        let key = EcPublicKey::new(&hex(EC_X), &hex(EC_Y)).unwrap();
+        let signature = hex(EC_SIGNATURE);
+        assert!(key.verify(MESSAGE, &signature));
+        assert!(!key.verify(b"public-key verification!", &signature));
+        let mut tampered = signature.clone();
+        tampered[40] ^= 1;
+        assert!(!key.verify(MESSAGE, &tampered));
+        assert!(!key.verify(MESSAGE, &signature[..63]));
+        assert!(!key.verify(MESSAGE, &[0; 64]));
+    }
+
+    #[test]
+    fn ec_points_must_be_on_the_curve() {
+        let (x, mut y) = (hex(EC_X), hex(EC_Y));
+        y[31] ^= 1;
+        assert!(EcPublicKey::new(&x, &y).is_err());
+        assert!(EcPublicKey::new(&x[1..], &hex(EC_Y)).is_err());
+        assert!(EcPublicKey::new(&[0xff; 32], &hex(EC_Y)).is_err());
+    }
+}
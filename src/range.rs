@@ -0,0 +1,127 @@
+//! Defines `Range` header parsing (RFC 9110, section 14) for handlers serving
+//! parts of a resource, as `HttpResponse::file_ranged` does for files.
+
+use crate::error::RangeError;
+use crate::request::HttpRequest;
+use crate::response::HttpResponse;
+use http::header::{CONTENT_RANGE, RANGE};
+
+/// A satisfiable byte range of a representation: the offsets of its first and
+/// last bytes, both included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    /// The offset of the first byte.
+    pub start: u64,
+    /// The offset of the last byte, included.
+    pub end: u64,
+}
+
+impl ByteRange {
+    /// Returns the number of bytes in the range.
+    #[allow(clippy::len_without_is_empty)] // A range has at least one byte.
+    pub fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+}
+
+impl HttpRequest {
+    /// Parses the `Range` header against a representation of `total_len`
+    /// bytes, returning the satisfiable ranges in the order they were asked
+    /// for, with their ends clamped to the last byte. `bytes=500-` asks for
+    /// the bytes from offset 500 on, and `bytes=-500` for the last 500.
+    ///
+    /// Returns `None` if the request has no `Range` header. Ranges starting
+    /// past the end, and suffix ranges of 0 bytes, can't be satisfied; they're
+    /// dropped, unless none is left.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RangeError::UnsupportedUnit` for another unit than `bytes`,
+    /// `RangeError::Malformed` for a header that isn't a list of byte ranges,
+    /// `RangeError::Overlapping` if two of the satisfiable ranges overlap, and
+    /// `RangeError::Unsatisfiable` if none can be satisfied, which calls for a
+    /// 416 Range Not Satisfiable. Servers usually ignore the header in the
+    /// other cases and send the whole representation.
+    pub fn range(&self, total_len: u64) -> Option<Result<Vec<ByteRange>, RangeError>> {
+        let value = self.headers.get(RANGE)?;
+        Some(
+            value
+                .to_str()
+                .map_err(|_| RangeError::Malformed)
+                .and_then(|value| parse_ranges(value, total_len)),
+        )
+    }
+}
+
+impl HttpResponse {
+    /// Sets the `Content-Range` header of a 206 Partial Content response for
+    /// `range` of a representation of `total` bytes, e.g. `bytes 0-1023/4096`.
+    pub fn content_range(&mut self, range: ByteRange, total: u64) -> &mut Self {
+        self.add_header(
+            CONTENT_RANGE,
+            &format!("bytes {}-{}/{total}", range.start, range.end),
+        )
+    }
+}
+
+/// Parses the value of a `Range` header; see `HttpRequest::range`.
+fn parse_ranges(value: &str, len: u64) -> Result<Vec<ByteRange>, RangeError> {
+    let (unit, specs) = value.split_once('=').ok_or(RangeError::Malformed)?;
+    let unit = unit.trim();
+    if !unit.eq_ignore_ascii_case("bytes") {
+        return Err(RangeError::UnsupportedUnit(unit.to_string()));
+    }
+
+    let mut ranges = Vec::new();
+    let mut any = false;
+    // Empty list elements, e.g. in `bytes=0-1,,5-6`, are allowed and ignored.
+    for spec in specs.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        any = true;
+        let (start, end) = spec.split_once('-').ok_or(RangeError::Malformed)?;
+        let (start, end) = (start.trim(), end.trim());
+        let range = if start.is_empty() {
+            // A suffix range: the last `end` bytes.
+            let suffix = parse_offset(end)?;
+            (suffix > 0 && len > 0).then(|| ByteRange {
+                start: len.saturating_sub(suffix),
+                end: len - 1,
+            })
+        } else {
+            let start = parse_offset(start)?;
+            let end = if end.is_empty() {
+                u64::MAX
+            } else {
+                parse_offset(end)?
+            };
+            if end < start {
+                return Err(RangeError::Malformed);
+            }
+            (start < len).then(|| ByteRange {
+                start,
+                end: end.min(len - 1),
+            })
+        };
+        ranges.extend(range);
+    }
+    if !any {
+        return Err(RangeError::Malformed);
+    }
+    if ranges.is_empty() {
+        return Err(RangeError::Unsatisfiable);
+    }
+
+    let mut sorted = ranges.clone();
+    sorted.sort_by_key(|range| range.start);
+    if sorted.windows(2).any(|pair| pair[1].start <= pair[0].end) {
+        return Err(RangeError::Overlapping);
+    }
+    Ok(ranges)
+}
+
+/// Parses a byte offset: ASCII digits only, unlike `u64::from_str`.
+fn parse_offset(value: &str) -> Result<u64, RangeError> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(RangeError::Malformed);
+    }
+    value.parse().map_err(|_| RangeError::Malformed)
+}
@@ -0,0 +1,212 @@
+//! Defines the recording of the requests a router handles and their
+//! responses, enabled with `Router::enable_recording`, e.g. to replay them
+//! later as regression tests with [`testing::replay`](crate::testing::replay).
+
+use crate::crypto;
+use crate::redact::{self, UriPreview};
+use bytes::Bytes;
+use http::{Extensions, HeaderMap, Request, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// The value recorded for the headers and query parameters listed in
+/// [`redact::debug_options`].
+pub const REDACTED: &str = "<redacted>";
+
+/// A request the router handled and its response, e.g.
+/// `{"method":"GET","uri":"/users/1","request_headers":[["accept","*/*"]],...}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    pub method: String,
+    /// The URI as received, with redacted query parameters.
+    pub uri: String,
+    /// The request headers in order, with redacted values.
+    pub request_headers: Vec<(String, String)>,
+    pub request_body: RecordedBody,
+    /// The controller of the matched route, if any.
+    pub controller: Option<String>,
+    pub status: u16,
+    /// The response headers in order, with redacted values.
+    pub response_headers: Vec<(String, String)>,
+    /// The response body, empty if it was streamed.
+    pub response_body: RecordedBody,
+    /// The time spent routing the request, in milliseconds.
+    pub duration_ms: f64,
+}
+
+/// A recorded body: its text if it's UTF-8, or else its bytes, hex-encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RecordedBody {
+    Text(String),
+    Binary { hex: String },
+}
+
+impl RecordedBody {
+    /// Returns the body for `bytes`.
+    pub fn new(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => RecordedBody::Text(text.to_string()),
+            Err(_) => RecordedBody::Binary {
+                hex: crypto::hex(bytes),
+            },
+        }
+    }
+
+    /// Returns the bytes of the body, or `None` if its hex encoding is
+    /// invalid.
+    pub fn to_bytes(&self) -> Option<Bytes> {
+        match self {
+            RecordedBody::Text(text) => Some(Bytes::from(text.clone())),
+            RecordedBody::Binary { hex } => {
+                if hex.len() % 2 != 0 {
+                    return None;
+                }
+                (0..hex.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+                    .collect::<Option<Vec<u8>>>()
+                    .map(Bytes::from)
+            }
+        }
+    }
+}
+
+/// Where recordings are sent, set with `Router::enable_recording`. It's
+/// called after each request is routed, so it should be quick, e.g. writing
+/// to a buffer or a channel.
+pub trait RecordingSink {
+    fn record(&self, recording: &Recording);
+}
+
+impl<F: Fn(&Recording)> RecordingSink for F {
+    fn record(&self, recording: &Recording) {
+        self(recording)
+    }
+}
+
+/// A `RecordingSink` writing each recording as a line of JSON (NDJSON), the
+/// format [`testing::replay`](crate::testing::replay) reads. Write errors are
+/// ignored.
+pub struct JsonFileSink {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl JsonFileSink {
+    /// Creates a sink appending to the file at `path`, created if it doesn't
+    /// exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of opening the file.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file: File = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(file))
+    }
+
+    /// Creates a sink writing to `writer`. Each line is written at once, and
+    /// `writer` is flushed after it.
+    pub fn new<W: Write + Send + 'static>(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+}
+
+impl RecordingSink for JsonFileSink {
+    fn record(&self, recording: &Recording) {
+        let Ok(mut line) = serde_json::to_vec(recording) else {
+            return;
+        };
+        line.push(b'\n');
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writer.write_all(&line).and_then(|()| writer.flush());
+    }
+}
+
+/// The controller of the matched route, in a response's extensions while it's
+/// recorded.
+#[derive(Debug, Clone)]
+pub(crate) struct RecordedController(pub(crate) Arc<str>);
+
+/// What's recorded of a request before it's routed.
+pub(crate) struct PendingRecording {
+    sink: Arc<dyn RecordingSink + Send + Sync>,
+    started: Instant,
+    method: String,
+    uri: String,
+    headers: Vec<(String, String)>,
+    body: RecordedBody,
+}
+
+impl PendingRecording {
+    pub(crate) fn new(
+        sink: &Arc<dyn RecordingSink + Send + Sync>,
+        req: &Request<Bytes>,
+        started: Instant,
+    ) -> Self {
+        let options = redact::debug_options();
+        Self {
+            sink: Arc::clone(sink),
+            started,
+            method: req.method().to_string(),
+            uri: format!(
+                "{:?}",
+                UriPreview {
+                    uri: req.uri(),
+                    options: Some(&options),
+                }
+            ),
+            headers: redacted_headers(req.headers(), &options.redacted_headers),
+            body: RecordedBody::new(req.body()),
+        }
+    }
+
+    /// Sends the recording of the request with its response, whose body is
+    /// `None` if it's streamed, to the sink.
+    pub(crate) fn finish(
+        self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: Option<&[u8]>,
+        extensions: &mut Extensions,
+        now: Instant,
+    ) {
+        let options = redact::debug_options();
+        let recording = Recording {
+            method: self.method,
+            uri: self.uri,
+            request_headers: self.headers,
+            request_body: self.body,
+            controller: extensions
+                .remove::<RecordedController>()
+                .map(|controller| controller.0.to_string()),
+            status: status.as_u16(),
+            response_headers: redacted_headers(headers, &options.redacted_headers),
+            response_body: RecordedBody::new(body.unwrap_or_default()),
+            duration_ms: now.saturating_duration_since(self.started).as_secs_f64() * 1000.0,
+        };
+        self.sink.record(&recording);
+    }
+}
+
+fn redacted_headers(
+    headers: &HeaderMap,
+    redacted: &[http::header::HeaderName],
+) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if redacted.contains(name) {
+                REDACTED.to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name.as_str().to_string(), value)
+        })
+        .collect()
+}
@@ -0,0 +1,207 @@
+//! Controls how `HttpRequest` and `HttpResponse` are printed with `{:?}`.
+//!
+//! Their `Debug` output replaces the values of sensitive headers and query
+//! parameters with `"<redacted>"` and truncates the body, so requests can be logged without
+//! leaking credentials. `HttpRequest::debug_full` and `HttpResponse::debug_full`
+//! print everything, for local debugging.
+
+use crate::body::Body;
+use bytes::Bytes;
+use http::header::{AUTHORIZATION, COOKIE, HeaderName, SET_COOKIE};
+use http::{HeaderMap, Uri};
+use std::fmt;
+use std::sync::RwLock;
+
+/// The options used by the `Debug` implementations of `HttpRequest` and
+/// `HttpResponse`, set process-wide with [`set_debug_options`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugOptions {
+    /// The headers whose values are printed as `"<redacted>"`.
+    pub redacted_headers: Vec<HeaderName>,
+    /// The query parameters whose values are printed as `<redacted>` in the
+    /// request URI, e.g. an API key accepted in the query string.
+    pub redacted_query_params: Vec<String>,
+    /// The maximum number of body bytes printed.
+    pub body_preview_len: usize,
+}
+
+impl Default for DebugOptions {
+    /// Redacts `Authorization`, `Cookie`, `Set-Cookie` and `X-Api-Key`, no
+    /// query parameters, and prints up to 128 body bytes.
+    fn default() -> Self {
+        Self {
+            redacted_headers: vec![
+                AUTHORIZATION,
+                COOKIE,
+                SET_COOKIE,
+                HeaderName::from_static("x-api-key"),
+            ],
+            redacted_query_params: Vec::new(),
+            body_preview_len: 128,
+        }
+    }
+}
+
+static OPTIONS: RwLock<Option<DebugOptions>> = RwLock::new(None);
+
+/// The headers and query parameters added with [`redact_header`] and
+/// [`redact_query_param`], kept apart from [`OPTIONS`] so that
+/// [`set_debug_options`] can't drop them.
+static ADDED: RwLock<Added> = RwLock::new(Added {
+    headers: Vec::new(),
+    query_params: Vec::new(),
+});
+
+struct Added {
+    headers: Vec<HeaderName>,
+    query_params: Vec<String>,
+}
+
+/// Sets the options used by the `Debug` implementations of `HttpRequest` and
+/// `HttpResponse` for the whole process.
+///
+/// The headers and query parameters added with [`redact_header`] and
+/// [`redact_query_param`], e.g. by a router for its API keys, stay redacted.
+pub fn set_debug_options(options: DebugOptions) {
+    *OPTIONS.write().unwrap_or_else(|e| e.into_inner()) = Some(options);
+}
+
+/// Returns the options currently used by the `Debug` implementations: those
+/// set with [`set_debug_options`], or the default ones, plus the headers and
+/// query parameters added with [`redact_header`] and [`redact_query_param`].
+pub fn debug_options() -> DebugOptions {
+    let mut options = OPTIONS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_default();
+    let added = ADDED.read().unwrap_or_else(|e| e.into_inner());
+    for name in &added.headers {
+        if !options.redacted_headers.contains(name) {
+            options.redacted_headers.push(name.clone());
+        }
+    }
+    for name in &added.query_params {
+        if !options.redacted_query_params.contains(name) {
+            options.redacted_query_params.push(name.clone());
+        }
+    }
+    options
+}
+
+/// Redacts the header `name` for the rest of the process, whatever the options
+/// set with [`set_debug_options`]. The router calls it for the header of its
+/// API keys, so a key is redacted in the output of every router once one
+/// accepts it.
+pub fn redact_header(name: HeaderName) {
+    let mut added = ADDED.write().unwrap_or_else(|e| e.into_inner());
+    if !added.headers.contains(&name) {
+        added.headers.push(name);
+    }
+}
+
+/// Redacts the query parameter `name` for the rest of the process, whatever the
+/// options set with [`set_debug_options`]. The router calls it for the query
+/// parameter of its API keys.
+pub fn redact_query_param<S: Into<String>>(name: S) {
+    let name = name.into();
+    let mut added = ADDED.write().unwrap_or_else(|e| e.into_inner());
+    if !added.query_params.contains(&name) {
+        added.query_params.push(name);
+    }
+}
+
+/// Prints a URI, redacting the values of the configured query parameters.
+pub(crate) struct UriPreview<'a> {
+    pub(crate) uri: &'a Uri,
+    pub(crate) options: Option<&'a DebugOptions>,
+}
+
+impl fmt::Debug for UriPreview<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (Some(options), Some(query)) = (self.options, self.uri.query()) else {
+            return self.uri.fmt(f);
+        };
+        if options.redacted_query_params.is_empty() {
+            return self.uri.fmt(f);
+        }
+        let uri = self.uri.to_string();
+        write!(f, "{}?", &uri[..uri.len() - query.len() - 1])?;
+        for (i, pair) in query.split('&').enumerate() {
+            if i > 0 {
+                f.write_str("&")?;
+            }
+            match pair.split_once('=') {
+                Some((name, _)) if options.redacted_query_params.iter().any(|p| p == name) => {
+                    write!(f, "{name}=<redacted>")?;
+                }
+                _ => f.write_str(pair)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Prints a header map, redacting the values of the configured headers.
+pub(crate) struct Headers<'a> {
+    pub(crate) headers: &'a HeaderMap,
+    pub(crate) options: Option<&'a DebugOptions>,
+}
+
+impl fmt::Debug for Headers<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(options) = self.options else {
+            return self.headers.fmt(f);
+        };
+        let mut map = f.debug_map();
+        for (name, value) in self.headers {
+            if options.redacted_headers.contains(name) {
+                map.entry(name, &"<redacted>");
+            } else {
+                map.entry(name, value);
+            }
+        }
+        map.finish()
+    }
+}
+
+/// Prints a response body, truncated to the configured preview length.
+pub(crate) struct BodyPreview<'a> {
+    pub(crate) body: &'a Body,
+    pub(crate) options: Option<&'a DebugOptions>,
+}
+
+impl fmt::Debug for BodyPreview<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.body {
+            Body::Full(bytes) => f
+                .debug_tuple("Full")
+                .field(&BytesPreview {
+                    bytes,
+                    options: self.options,
+                })
+                .finish(),
+            body => body.fmt(f),
+        }
+    }
+}
+
+/// Prints a request body, truncated to the configured preview length.
+pub(crate) struct BytesPreview<'a> {
+    pub(crate) bytes: &'a Bytes,
+    pub(crate) options: Option<&'a DebugOptions>,
+}
+
+impl fmt::Debug for BytesPreview<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.options {
+            Some(options) if self.bytes.len() > options.body_preview_len => write!(
+                f,
+                "{:?}... ({} bytes total)",
+                self.bytes.slice(..options.body_preview_len),
+                self.bytes.len()
+            ),
+            _ => self.bytes.fmt(f),
+        }
+    }
+}
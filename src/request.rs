@@ -1,20 +1,362 @@
 //! Defines a custom `HttpRequest` struct that wraps the standard `http::Request`
 //! and includes parsed path parameters.
 
-use http::{HeaderMap, Method, Uri};
-use std::collections::HashMap;
+use crate::error::RouterError;
+use crate::params::Params;
+use crate::parsed::Parsed;
+use crate::redact::{self, BytesPreview, DebugOptions, Headers, UriPreview};
+use bytes::Bytes;
+use http::header::{CONTENT_TYPE, HeaderName, HeaderValue};
+use http::{Extensions, HeaderMap, Method, Request, Uri};
+use serde::Serialize;
+use std::fmt;
+use std::time::{Duration, Instant};
 
 /// A representation of an incoming HTTP request.
 ///
 /// This struct is passed to `HttpHandler` implementations. It provides easy
 /// access to all parts of the request, including path parameters extracted
 //  by the router.
-#[derive(Debug)]
+///
+/// The fields are public, but [`HttpRequest::builder`] is the stable way to
+/// construct a request, e.g. in tests: fields may be added in future versions.
+///
+/// The `Debug` output redacts sensitive headers and truncates the body, as
+/// configured with [`redact::set_debug_options`]; use
+/// [`HttpRequest::debug_full`] to print everything.
 pub struct HttpRequest {
+    /// The URI used for routing, after path normalization and rewrite rules.
     pub uri: Uri,
+    /// The URI exactly as it was received, before normalization and rewriting.
+    pub original_uri: Uri,
     pub method: Method,
     pub headers: HeaderMap,
     /// Path parameters extracted from the URL (e.g., `:id` from `/users/:id`).
-    pub params: HashMap<String, String>,
-    pub body: Vec<u8>,
+    pub params: Params,
+    pub body: Bytes,
+    /// Typed data attached to the request, e.g. by middleware for the handler.
+    /// The router carries over the extensions of the `http::Request` it routes.
+    pub extensions: Extensions,
+    /// When the handler's time to respond runs out, if the route has a timeout
+    /// or the caller sent a deadline (see `Router::set_deadline_header`): the
+    /// earlier of the two. Pass it on to downstream calls so they give up in
+    /// time.
+    pub deadline: Option<Instant>,
+    /// The body and query string, parsed on first use.
+    pub(crate) parsed: Parsed,
+}
+
+impl HttpRequest {
+    /// Creates a builder for an `HttpRequest`, defaulting to a `GET /` request
+    /// with no headers, parameters or body.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use generic_http_router::HttpRequest;
+    /// use http::Method;
+    ///
+    /// let req = HttpRequest::builder()
+    ///     .method(Method::PUT)
+    ///     .uri("/users/1?notify=true")
+    ///     .header("x-request-id", "abc")
+    ///     .param("id", "1")
+    ///     .json(&serde_json::json!({ "name": "Ada" }))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(req.params.get("id"), Some("1"));
+    /// assert_eq!(req.uri.query(), Some("notify=true"));
+    /// assert_eq!(req.headers["content-type"], "application/json");
+    /// ```
+    pub fn builder() -> HttpRequestBuilder {
+        HttpRequestBuilder::new()
+    }
+
+    /// Returns a value whose `Debug` output shows the whole request, including
+    /// the values of sensitive headers and the full body. Meant for local
+    /// debugging only.
+    pub fn debug_full(&self) -> impl fmt::Debug + '_ {
+        DebugFull(self)
+    }
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter<'_>, options: Option<&DebugOptions>) -> fmt::Result {
+        f.debug_struct("HttpRequest")
+            .field(
+                "uri",
+                &UriPreview {
+                    uri: &self.uri,
+                    options,
+                },
+            )
+            .field(
+                "original_uri",
+                &UriPreview {
+                    uri: &self.original_uri,
+                    options,
+                },
+            )
+            .field("method", &self.method)
+            .field(
+                "headers",
+                &Headers {
+                    headers: &self.headers,
+                    options,
+                },
+            )
+            .field("params", &self.params)
+            .field(
+                "body",
+                &BytesPreview {
+                    bytes: &self.body,
+                    options,
+                },
+            )
+            .field("extensions", &self.extensions)
+            .field("deadline", &self.deadline)
+            .finish()
+    }
+
+    /// Returns the deadline of the handler, as `self.deadline`.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Returns the time left until the deadline (zero once it has passed), or
+    /// `None` if there's no deadline.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Returns a copy of the request for a mirror handler. Extensions can't be
+    /// cloned, so the copy has none.
+    pub(crate) fn mirror_copy(&self) -> Self {
+        Self {
+            uri: self.uri.clone(),
+            original_uri: self.original_uri.clone(),
+            method: self.method.clone(),
+            headers: self.headers.clone(),
+            params: self.params.clone(),
+            body: self.body.clone(),
+            extensions: Extensions::new(),
+            deadline: self.deadline,
+            parsed: Parsed::default(),
+        }
+    }
+
+    /// Returns the body as a byte slice.
+    pub fn body_as_slice(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Returns a copy of the body as a `Vec<u8>`.
+    pub fn body_to_vec(&self) -> Vec<u8> {
+        self.body.to_vec()
+    }
+}
+
+impl fmt::Debug for HttpRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_debug(f, Some(&redact::debug_options()))
+    }
+}
+
+/// The unredacted `Debug` view returned by [`HttpRequest::debug_full`].
+struct DebugFull<'a>(&'a HttpRequest);
+
+impl fmt::Debug for DebugFull<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_debug(f, None)
+    }
+}
+
+/// Builds an `HttpRequest` from an `http::Request`, with empty path parameters
+/// and the request's URI as both `uri` and `original_uri`.
+///
+/// The request's extensions are kept, and its HTTP version is dropped.
+impl<B: Into<Bytes>> From<Request<B>> for HttpRequest {
+    fn from(req: Request<B>) -> Self {
+        let (parts, body) = req.into_parts();
+        Self {
+            original_uri: parts.uri.clone(),
+            uri: parts.uri,
+            method: parts.method,
+            headers: parts.headers,
+            params: Params::new(),
+            body: body.into(),
+            extensions: parts.extensions,
+            deadline: None,
+            parsed: Parsed::default(),
+        }
+    }
+}
+
+/// Rebuilds an `http::Request` from an `HttpRequest`, using its (routed) `uri`.
+///
+/// The path parameters and `original_uri` are dropped, the extensions are kept,
+/// and the request has the default HTTP version.
+impl TryFrom<HttpRequest> for Request<Bytes> {
+    type Error = http::Error;
+
+    fn try_from(req: HttpRequest) -> Result<Self, Self::Error> {
+        let mut request = Request::builder()
+            .method(req.method)
+            .uri(req.uri)
+            .body(req.body)?;
+        *request.headers_mut() = req.headers;
+        *request.extensions_mut() = req.extensions;
+        Ok(request)
+    }
+}
+
+/// Rebuilds an `http::Request` with a `Vec<u8>` body from an `HttpRequest`, as
+/// the `Request<Bytes>` conversion does.
+impl TryFrom<HttpRequest> for Request<Vec<u8>> {
+    type Error = http::Error;
+
+    fn try_from(req: HttpRequest) -> Result<Self, Self::Error> {
+        Request::<Bytes>::try_from(req).map(|request| request.map(Vec::from))
+    }
+}
+
+/// A builder for an [`HttpRequest`], created with [`HttpRequest::builder`].
+///
+/// Like `http::request::Builder`, errors are deferred until [`build`] is
+/// called, so the calls can be chained.
+///
+/// [`build`]: HttpRequestBuilder::build
+#[derive(Debug)]
+pub struct HttpRequestBuilder {
+    inner: Result<HttpRequest, RouterError>,
+}
+
+impl HttpRequestBuilder {
+    /// Creates a builder for a `GET /` request.
+    pub fn new() -> Self {
+        Self {
+            inner: Ok(HttpRequest {
+                uri: Uri::from_static("/"),
+                original_uri: Uri::from_static("/"),
+                method: Method::GET,
+                headers: HeaderMap::new(),
+                params: Params::new(),
+                body: Bytes::new(),
+                extensions: Extensions::new(),
+                deadline: None,
+                parsed: Parsed::default(),
+            }),
+        }
+    }
+
+    /// Sets the method, e.g. `Method::POST` or `"POST"`.
+    pub fn method<T>(self, method: T) -> Self
+    where
+        Method: TryFrom<T>,
+        <Method as TryFrom<T>>::Error: Into<http::Error>,
+    {
+        self.and_then(|mut req| {
+            req.method = Method::try_from(method).map_err(|e| RouterError::Http(e.into()))?;
+            Ok(req)
+        })
+    }
+
+    /// Sets the URI, e.g. `"/users/1?x=2"`, as both `uri` and `original_uri`.
+    pub fn uri<T>(self, uri: T) -> Self
+    where
+        Uri: TryFrom<T>,
+        <Uri as TryFrom<T>>::Error: Into<http::Error>,
+    {
+        self.and_then(|mut req| {
+            let uri = Uri::try_from(uri).map_err(|e| RouterError::Http(e.into()))?;
+            req.original_uri = uri.clone();
+            req.uri = uri;
+            Ok(req)
+        })
+    }
+
+    /// Appends a header. Repeated calls with the same name add multiple values.
+    pub fn header<K, V>(self, name: K, value: V) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        self.and_then(|mut req| {
+            let name = HeaderName::try_from(name).map_err(|e| RouterError::Http(e.into()))?;
+            let value = HeaderValue::try_from(value).map_err(|e| RouterError::Http(e.into()))?;
+            req.headers.append(name, value);
+            Ok(req)
+        })
+    }
+
+    /// Sets a path parameter, as if the router had extracted it from the path.
+    pub fn param<K: Into<Box<str>>, V: Into<Box<str>>>(self, name: K, value: V) -> Self {
+        self.and_then(|mut req| {
+            req.params.insert(name, value);
+            Ok(req)
+        })
+    }
+
+    /// Inserts a typed extension, replacing any previous value of the same type.
+    pub fn extension<T: Send + Sync + 'static>(self, extension: T) -> Self {
+        self.and_then(|mut req| {
+            req.extensions.insert(extension);
+            Ok(req)
+        })
+    }
+
+    /// Sets the deadline.
+    pub fn deadline(self, deadline: Instant) -> Self {
+        self.and_then(|mut req| {
+            req.deadline = Some(deadline);
+            Ok(req)
+        })
+    }
+
+    /// Sets the body.
+    pub fn body<B: Into<Bytes>>(self, body: B) -> Self {
+        self.and_then(|mut req| {
+            req.body = body.into();
+            Ok(req)
+        })
+    }
+
+    /// Sets the body to `value` serialized as JSON, and the `Content-Type` to
+    /// `application/json` unless a content type was already set.
+    pub fn json<T: Serialize + ?Sized>(self, value: &T) -> Self {
+        self.and_then(|mut req| {
+            req.body = serde_json::to_vec(value)?.into();
+            req.headers
+                .entry(CONTENT_TYPE)
+                .or_insert(HeaderValue::from_static("application/json"));
+            Ok(req)
+        })
+    }
+
+    /// Builds the request.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError` if any of the given parts was invalid, e.g. an
+    /// unparsable URI or header, or a value that failed to serialize as JSON.
+    pub fn build(self) -> Result<HttpRequest, RouterError> {
+        self.inner
+    }
+
+    fn and_then<F>(self, f: F) -> Self
+    where
+        F: FnOnce(HttpRequest) -> Result<HttpRequest, RouterError>,
+    {
+        Self {
+            inner: self.inner.and_then(f),
+        }
+    }
+}
+
+impl Default for HttpRequestBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
@@ -0,0 +1,118 @@
+//! Defines the first stage of routing a request, `Router::resolve`: what the
+//! router decided about it before running any of the route's middleware or
+//! its handler, which `Router::dispatch` carries out.
+
+use crate::batch::BatchLimits;
+use crate::config::Endpoint;
+use crate::error_page::PageRequest;
+use crate::handler::HttpHandler;
+use crate::hooks::Started;
+use crate::params::Params;
+use crate::response::HttpResponse;
+use crate::shutdown::InFlight;
+use crate::table::RouteEntry;
+use http::{StatusCode, Uri};
+use std::fmt;
+
+/// A request resolved by `Router::resolve`, to pass to `Router::dispatch`.
+///
+/// The request counts as in flight, for graceful shutdown, until the
+/// resolution is dispatched or dropped.
+pub struct Resolution<'r> {
+    pub(crate) resolved: Resolved<'r>,
+    /// Whether a handler is registered for the route's controller.
+    pub(crate) has_handler: bool,
+    pub(crate) started: Option<Started>,
+    pub(crate) page_request: Option<PageRequest>,
+    pub(crate) in_flight: InFlight<'r>,
+    #[cfg(feature = "logging")]
+    pub(crate) span: tracing::Span,
+}
+
+/// What the router decided about a request.
+// Matched requests are the common case, so they aren't boxed.
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum Resolved<'r> {
+    /// The router answers the request itself, e.g. with a 404 Not Found.
+    Respond(HttpResponse),
+    /// The request is an `OPTIONS *`, for the handler set with
+    /// `Router::register_server_options`.
+    ServerOptions(&'r (dyn HttpHandler + Send + Sync)),
+    /// The request is a batch, for the endpoint enabled with
+    /// `Router::enable_batching`, with its limits.
+    Batch(&'r BatchLimits),
+    /// The request matched `entry`, with `uri` after normalization and
+    /// rewrite rules, and the decoded path parameters `params`.
+    Matched {
+        entry: &'r RouteEntry,
+        uri: Uri,
+        params: Params,
+    },
+}
+
+impl Resolution<'_> {
+    /// Returns the endpoint of the matched route, e.g. for its `metadata`,
+    /// `tags` or `auth`, or `None` if no route matched.
+    pub fn endpoint(&self) -> Option<&Endpoint> {
+        self.entry().map(|entry| &*entry.endpoint)
+    }
+
+    /// Returns the controller of the matched route, the stable one for canary
+    /// routes.
+    pub fn controller(&self) -> Option<&str> {
+        self.entry().map(|entry| &*entry.controller)
+    }
+
+    /// Returns the path pattern of the matched route, e.g. `/users/:id`.
+    pub fn pattern(&self) -> Option<&str> {
+        self.entry().map(|entry| &*entry.pattern)
+    }
+
+    /// Returns the percent-decoded path parameters of the matched route.
+    pub fn params(&self) -> Option<&Params> {
+        match &self.resolved {
+            Resolved::Matched { params, .. } => Some(params),
+            Resolved::Respond(_) | Resolved::ServerOptions(_) | Resolved::Batch(_) => None,
+        }
+    }
+
+    /// Returns the status the router answers the request with itself, without
+    /// a route: e.g. 404 Not Found, 405 Method Not Allowed, a redirect, or the
+    /// 503 Service Unavailable of maintenance mode or shutdown. Returns `None`
+    /// for a matched route, or an `OPTIONS *` request with a handler, whose
+    /// response `Router::dispatch` builds.
+    pub fn status(&self) -> Option<StatusCode> {
+        match &self.resolved {
+            Resolved::Respond(response) => Some(response.status),
+            Resolved::Matched { .. } | Resolved::ServerOptions(_) | Resolved::Batch(_) => None,
+        }
+    }
+
+    /// Returns `true` if a route matched and a handler is registered for its
+    /// controller, or for an `OPTIONS *` request with a handler set with
+    /// `Router::register_server_options`. A request to a route without one is answered following
+    /// the `MissingHandlerPolicy`, e.g. with a 501 Not Implemented, once it
+    /// passes the route's checks such as its guards.
+    pub fn has_handler(&self) -> bool {
+        self.has_handler
+    }
+
+    fn entry(&self) -> Option<&RouteEntry> {
+        match &self.resolved {
+            Resolved::Matched { entry, .. } => Some(entry),
+            Resolved::Respond(_) | Resolved::ServerOptions(_) | Resolved::Batch(_) => None,
+        }
+    }
+}
+
+impl fmt::Debug for Resolution<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Resolution")
+            .field("controller", &self.controller())
+            .field("pattern", &self.pattern())
+            .field("params", &self.params())
+            .field("status", &self.status())
+            .field("has_handler", &self.has_handler)
+            .finish()
+    }
+}
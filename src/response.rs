@@ -1,37 +1,160 @@
 //! Defines a custom `HttpResponse` struct for convenience.
 
-use http::{HeaderMap, HeaderValue, Response, StatusCode};
+use crate::body::Body;
+use crate::redact::{self, BodyPreview, DebugOptions, Headers};
+use bytes::Bytes;
+use http::header::{CONTENT_TYPE, HeaderName, VARY};
+use http::{Extensions, HeaderMap, HeaderValue, Response, StatusCode};
+use std::fmt;
+use std::io;
 
 /// A representation of an outgoing HTTP response.
 ///
 /// Handlers create and return this struct. It can be easily converted into
-/// a standard `http::Response<Vec<u8>>`.
-#[derive(Debug)]
+/// a standard `http::Response<Body>`, `http::Response<Bytes>` or
+/// `http::Response<Vec<u8>>`.
+///
+/// The `Debug` output redacts sensitive headers such as `Set-Cookie` and
+/// truncates the body, as configured with [`redact::set_debug_options`]; use
+/// [`HttpResponse::debug_full`] to print everything.
 pub struct HttpResponse {
     pub status: StatusCode,
     pub headers: HeaderMap,
-    pub body: Vec<u8>,
+    pub body: Body,
+    /// Typed data attached to the response, e.g. routing metadata for the server
+    /// adapter. Carried over into the `http::Response` the response converts into.
+    pub extensions: Extensions,
 }
 
 impl HttpResponse {
     /// Creates a new `HttpResponse`.
-    pub fn new(status: StatusCode, body: Vec<u8>) -> Self {
+    ///
+    /// The body can be anything convertible into a `Body`, such as `Bytes`, a
+    /// `Vec<u8>`, a `String` or a `&'static [u8]`, none of which are copied.
+    pub fn new<B: Into<Body>>(status: StatusCode, body: B) -> Self {
         Self {
             status,
             headers: HeaderMap::new(),
-            body,
+            body: body.into(),
+            extensions: Extensions::new(),
         }
     }
 
+    /// Creates a new `HttpResponse` with a static body, such as `b"ok"` or a canned
+    /// JSON error. The body is borrowed, so creating the response doesn't allocate.
+    pub fn new_static(status: StatusCode, body: &'static [u8]) -> Self {
+        Self::new(status, Bytes::from_static(body))
+    }
+
+    /// Creates a new `HttpResponse` with a streamed body.
+    ///
+    /// Each item of `chunks` is sent as it's produced, so the whole body never has
+    /// to be in memory. An error ends the stream.
+    pub fn streaming<I>(status: StatusCode, chunks: I) -> Self
+    where
+        I: IntoIterator<Item = Result<Bytes, io::Error>>,
+        I::IntoIter: Send + 'static,
+    {
+        Self::new(status, Body::stream(chunks))
+    }
+
     /// Creates a new `HttpResponse` with headers.
-    pub fn with_headers(status: StatusCode, headers: HeaderMap, body: Vec<u8>) -> Self {
+    pub fn with_headers<B: Into<Body>>(status: StatusCode, headers: HeaderMap, body: B) -> Self {
         Self {
             status,
             headers,
-            body,
+            body: body.into(),
+            extensions: Extensions::new(),
         }
     }
 
+    /// Creates a 200 OK response.
+    pub fn ok<B: Into<Body>>(body: B) -> Self {
+        Self::new(StatusCode::OK, body)
+    }
+
+    /// Creates a 201 Created response.
+    pub fn created<B: Into<Body>>(body: B) -> Self {
+        Self::new(StatusCode::CREATED, body)
+    }
+
+    /// Creates an empty 204 No Content response.
+    pub fn no_content() -> Self {
+        Self::new(StatusCode::NO_CONTENT, Bytes::new())
+    }
+
+    /// Creates a 400 Bad Request response with a `text/plain` message.
+    pub fn bad_request<S: Into<String>>(message: S) -> Self {
+        Self::text(StatusCode::BAD_REQUEST, message)
+    }
+
+    /// Creates an empty 404 Not Found response.
+    pub fn not_found() -> Self {
+        Self::new(StatusCode::NOT_FOUND, Bytes::new())
+    }
+
+    /// Creates a 500 Internal Server Error response with a `text/plain` message.
+    pub fn internal_error<S: Into<String>>(message: S) -> Self {
+        Self::text(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+
+    /// Creates a response with a `text/plain; charset=utf-8` body.
+    pub fn text<S: Into<String>>(status: StatusCode, text: S) -> Self {
+        let mut response = Self::new(status, text.into());
+        response.add_header(CONTENT_TYPE, "text/plain; charset=utf-8");
+        response
+    }
+
+    /// Returns a value whose `Debug` output shows the whole response, including
+    /// the values of sensitive headers and the full body. Meant for local
+    /// debugging only.
+    pub fn debug_full(&self) -> impl fmt::Debug + '_ {
+        DebugFull(self)
+    }
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter<'_>, options: Option<&DebugOptions>) -> fmt::Result {
+        f.debug_struct("HttpResponse")
+            .field("status", &self.status)
+            .field(
+                "headers",
+                &Headers {
+                    headers: &self.headers,
+                    options,
+                },
+            )
+            .field(
+                "body",
+                &BodyPreview {
+                    body: &self.body,
+                    options,
+                },
+            )
+            .field("extensions", &self.extensions)
+            .finish()
+    }
+
+    /// Returns the body as a byte slice, or `None` if the body is streamed.
+    pub fn body_as_slice(&self) -> Option<&[u8]> {
+        self.body.as_bytes().map(|bytes| &bytes[..])
+    }
+
+    /// Returns a copy of the body as a `Vec<u8>`, or `None` if the body is streamed.
+    pub fn body_to_vec(&self) -> Option<Vec<u8>> {
+        self.body.as_bytes().map(|bytes| bytes.to_vec())
+    }
+
+    /// Returns a copy of the status, headers and body, e.g. to compare with a
+    /// mirror handler's response. A streamed body can't be copied, so the copy
+    /// has an empty body instead; extensions aren't copied either.
+    #[cfg(feature = "async")]
+    pub(crate) fn buffered_copy(&self) -> Self {
+        Self::with_headers(
+            self.status,
+            self.headers.clone(),
+            self.body.as_bytes().cloned().unwrap_or_default(),
+        )
+    }
+
     /// Adds a header to the response.
     pub fn add_header<K>(&mut self, name: K, value: &str) -> &mut Self
     where
@@ -42,10 +165,71 @@ impl HttpResponse {
         }
         self
     }
+
+    /// Adds `name` to the `Vary` header, telling caches that the response
+    /// depends on that request header, e.g. `Accept-Language` for a localized
+    /// response.
+    ///
+    /// The names already listed are kept, in one `Vary` header: a name already
+    /// there (ignoring case) isn't repeated, and nothing is added to `Vary: *`.
+    pub fn add_vary(&mut self, name: HeaderName) -> &mut Self {
+        let mut names: Vec<String> = self
+            .headers
+            .get_all(VARY)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect();
+        if names
+            .iter()
+            .any(|listed| listed == "*" || listed.eq_ignore_ascii_case(name.as_str()))
+        {
+            return self;
+        }
+        names.push(name.as_str().to_string());
+        self.add_header(VARY, &names.join(", "))
+    }
+
+    /// Adds each of `names` to the `Vary` header; see [`HttpResponse::add_vary`].
+    pub fn vary_on(&mut self, names: &[HeaderName]) -> &mut Self {
+        for name in names {
+            self.add_vary(name.clone());
+        }
+        self
+    }
+}
+
+impl fmt::Debug for HttpResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_debug(f, Some(&redact::debug_options()))
+    }
+}
+
+/// The unredacted `Debug` view returned by [`HttpResponse::debug_full`].
+struct DebugFull<'a>(&'a HttpResponse);
+
+impl fmt::Debug for DebugFull<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_debug(f, None)
+    }
+}
+
+/// Builds an `HttpResponse` from an `http::Response`, keeping its status,
+/// headers, body and extensions. The HTTP version is dropped.
+impl<B: Into<Body>> From<Response<B>> for HttpResponse {
+    fn from(res: Response<B>) -> Self {
+        let (parts, body) = res.into_parts();
+        let mut response = Self::with_headers(parts.status, parts.headers, body);
+        response.extensions = parts.extensions;
+        response
+    }
 }
 
 /// Allows converting our custom `HttpResponse` into the standard `http::Response`.
-impl From<HttpResponse> for Response<Vec<u8>> {
+impl From<HttpResponse> for Response<Body> {
     fn from(res: HttpResponse) -> Self {
         let mut response = Response::builder().status(res.status);
 
@@ -53,7 +237,114 @@ impl From<HttpResponse> for Response<Vec<u8>> {
         if let Some(headers) = response.headers_mut() {
             headers.extend(res.headers);
         }
+        if let Some(extensions) = response.extensions_mut() {
+            *extensions = res.extensions;
+        }
 
         response.body(res.body).unwrap() // This unwrap is safe as we control the inputs.
     }
 }
+
+/// Allows converting our custom `HttpResponse` into an `http::Response` with a
+/// buffered body.
+///
+/// A streamed body is collected into memory without a size limit; if the stream
+/// fails, the response is replaced with an empty 500 Internal Server Error,
+/// keeping only the extensions. Use
+/// `Router::route` for a bounded conversion.
+impl From<HttpResponse> for Response<Bytes> {
+    fn from(res: HttpResponse) -> Self {
+        let response = Response::<Body>::from(res);
+        let (parts, body) = response.into_parts();
+        match body.collect(usize::MAX) {
+            Ok(bytes) => Response::from_parts(parts, bytes),
+            Err(_) => {
+                let mut response = Response::new(Bytes::new());
+                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                *response.extensions_mut() = parts.extensions;
+                response
+            }
+        }
+    }
+}
+
+/// Allows converting our custom `HttpResponse` into an `http::Response` with a
+/// `Vec<u8>` body. An owned body is moved as is, and only a borrowed (static) or
+/// shared body is copied.
+impl From<HttpResponse> for Response<Vec<u8>> {
+    fn from(res: HttpResponse) -> Self {
+        Response::<Bytes>::from(res).map(Vec::from)
+    }
+}
+
+/// Converts a value returned by a handler into an `HttpResponse`.
+///
+/// The implementations use these defaults:
+///
+/// * `HttpResponse` is returned as is.
+/// * `StatusCode` gives an empty response with that status.
+/// * `String` and `&'static str` give a 200 OK `text/plain; charset=utf-8` response.
+/// * `Vec<u8>` gives a 200 OK `application/octet-stream` response.
+/// * `serde_json::Value` gives a 200 OK `application/json` response.
+/// * `(StatusCode, String)` gives a `text/plain; charset=utf-8` response with that status.
+/// * `(StatusCode, HeaderMap, Vec<u8>)` gives a response with exactly that status,
+///   headers and body.
+pub trait IntoHttpResponse {
+    /// Performs the conversion.
+    fn into_http_response(self) -> HttpResponse;
+}
+
+impl IntoHttpResponse for HttpResponse {
+    fn into_http_response(self) -> HttpResponse {
+        self
+    }
+}
+
+impl IntoHttpResponse for StatusCode {
+    fn into_http_response(self) -> HttpResponse {
+        HttpResponse::new(self, Bytes::new())
+    }
+}
+
+impl IntoHttpResponse for String {
+    fn into_http_response(self) -> HttpResponse {
+        HttpResponse::text(StatusCode::OK, self)
+    }
+}
+
+impl IntoHttpResponse for &'static str {
+    fn into_http_response(self) -> HttpResponse {
+        let mut response = HttpResponse::new(StatusCode::OK, self);
+        response.add_header(CONTENT_TYPE, "text/plain; charset=utf-8");
+        response
+    }
+}
+
+impl IntoHttpResponse for Vec<u8> {
+    fn into_http_response(self) -> HttpResponse {
+        let mut response = HttpResponse::ok(self);
+        response.add_header(CONTENT_TYPE, "application/octet-stream");
+        response
+    }
+}
+
+impl IntoHttpResponse for serde_json::Value {
+    fn into_http_response(self) -> HttpResponse {
+        // Serializing a `Value` can't fail.
+        let mut response = HttpResponse::ok(serde_json::to_vec(&self).unwrap_or_default());
+        response.add_header(CONTENT_TYPE, "application/json");
+        response
+    }
+}
+
+impl IntoHttpResponse for (StatusCode, String) {
+    fn into_http_response(self) -> HttpResponse {
+        HttpResponse::text(self.0, self.1)
+    }
+}
+
+impl IntoHttpResponse for (StatusCode, HeaderMap, Vec<u8>) {
+    fn into_http_response(self) -> HttpResponse {
+        HttpResponse::with_headers(self.0, self.1, self.2)
+    }
+}
@@ -0,0 +1,99 @@
+//! Defines the cap on the size of response bodies, set with
+//! `Router::set_max_response_size` or a route's `max_response_size`.
+
+use crate::body::{Body, BodyStream};
+use bytes::Bytes;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+/// Recorded in the extensions of a response to a matched route for the cap
+/// check, with the route's own limit, if any, and its controller.
+pub(crate) struct RouteResponseLimit {
+    pub(crate) limit: Option<usize>,
+    pub(crate) controller: Arc<str>,
+}
+
+/// Recorded in the extensions of the 500 Internal Server Error replacing a
+/// response over the cap for `RequestSummary::detail`, and removed before
+/// the response is returned.
+pub(crate) struct OversizedResponse(pub(crate) String);
+
+/// The error ending a streamed body once it grows past the cap.
+#[derive(Debug)]
+pub(crate) struct ResponseTooLarge {
+    pub(crate) limit: usize,
+}
+
+impl fmt::Display for ResponseTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "response body exceeds the limit of {} bytes", self.limit)
+    }
+}
+
+impl std::error::Error for ResponseTooLarge {}
+
+impl ResponseTooLarge {
+    /// Returns the cap's error, if it's what ended a stream with `error`.
+    pub(crate) fn of(error: &io::Error) -> Option<&Self> {
+        error.get_ref()?.downcast_ref()
+    }
+}
+
+/// Returns the problem detail of a response over `limit`.
+pub(crate) fn detail(limit: usize) -> String {
+    format!("The response body is larger than the limit of {limit} bytes.")
+}
+
+/// Returns `body`, ended with a `ResponseTooLarge` error if it's streamed and
+/// grows past `limit` bytes, or `Err` with `body` if it's in memory and
+/// already is.
+pub(crate) fn cap(body: Body, limit: usize, controller: Option<Arc<str>>) -> Result<Body, Body> {
+    match body {
+        Body::Full(bytes) if bytes.len() > limit => Err(Body::Full(bytes)),
+        Body::Full(bytes) => Ok(Body::Full(bytes)),
+        Body::Stream(chunks) => Ok(Body::Stream(Box::new(Capped {
+            chunks,
+            limit,
+            sent: 0,
+            controller,
+        }))),
+    }
+}
+
+/// A streamed body, ended once it grows past the cap.
+struct Capped {
+    chunks: BodyStream,
+    limit: usize,
+    sent: usize,
+    /// The controller of the route, for the error log.
+    #[cfg_attr(not(feature = "logging"), allow(dead_code))]
+    controller: Option<Arc<str>>,
+}
+
+impl Iterator for Capped {
+    type Item = Result<Bytes, io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.sent > self.limit {
+            return None;
+        }
+        let chunk = match self.chunks.next()? {
+            Ok(chunk) => chunk,
+            Err(error) => return Some(Err(error)),
+        };
+        self.sent = self.sent.saturating_add(chunk.len());
+        if self.sent <= self.limit {
+            return Some(Ok(chunk));
+        }
+        #[cfg(feature = "logging")]
+        tracing::error!(
+            controller = self.controller.as_deref(),
+            limit = self.limit,
+            "streamed response body exceeds max_response_size, aborting it"
+        );
+        Some(Err(io::Error::other(ResponseTooLarge {
+            limit: self.limit,
+        })))
+    }
+}
@@ -0,0 +1,104 @@
+//! Defines URL rewrite rules that are applied to the request path before route matching.
+
+use crate::error::RouterError;
+
+/// A compiled path rewrite rule.
+///
+/// The `pattern` uses the same syntax as endpoint paths (`:name` for a single
+/// segment, `*name` for a catch-all), so the same matcher implementation serves
+/// both. The `to` template may reference any parameter captured by the pattern
+/// with the same `:name` / `*name` notation.
+#[derive(Clone)]
+pub struct RewriteRule {
+    pattern: String,
+    to: String,
+    matcher: matchit::Router<()>,
+}
+
+impl RewriteRule {
+    /// Compiles a new rewrite rule.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError` if the pattern is not a valid route pattern, or if
+    /// the `to` template references a parameter that the pattern doesn't capture.
+    pub fn new<S: Into<String>>(pattern: S, to: S) -> Result<Self, RouterError> {
+        let pattern = pattern.into();
+        let to = to.into();
+
+        let mut matcher = matchit::Router::new();
+        if let Err(e) = matcher.insert(pattern.clone(), ()) {
+            return Err(RouterError::Rewrite {
+                pattern,
+                reason: e.to_string(),
+            });
+        }
+
+        let missing = placeholders(&to)
+            .find(|name| !placeholders(&pattern).any(|captured| captured == *name))
+            .map(str::to_string);
+        if let Some(missing) = missing {
+            return Err(RouterError::Rewrite {
+                pattern,
+                reason: format!("target references unknown parameter '{missing}'"),
+            });
+        }
+
+        Ok(Self {
+            pattern,
+            to,
+            matcher,
+        })
+    }
+
+    /// Returns the pattern this rule matches against.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns the target template of this rule.
+    pub fn target(&self) -> &str {
+        &self.to
+    }
+
+    /// Applies this rule to `path`, returning the rewritten path if it matches.
+    pub fn apply(&self, path: &str) -> Option<String> {
+        let matched = self.matcher.at(path).ok()?;
+
+        let mut rewritten = String::with_capacity(self.to.len() + path.len());
+        let mut rest = self.to.as_str();
+        while let Some(start) = rest.find([':', '*']) {
+            rewritten.push_str(&rest[..start]);
+            let tail = &rest[start + 1..];
+            let end = tail.find('/').unwrap_or(tail.len());
+            // Validated in `new`, so the parameter is always present.
+            rewritten.push_str(matched.params.get(&tail[..end]).unwrap_or_default());
+            rest = &tail[end..];
+        }
+        rewritten.push_str(rest);
+
+        Some(rewritten)
+    }
+}
+
+/// Applies `rules` in order to `path`, each rule at most once, so a set of rules
+/// can be chained but can never loop.
+///
+/// Returns `None` if no rule matched.
+pub(crate) fn apply_all(rules: &[RewriteRule], path: &str) -> Option<String> {
+    let mut current: Option<String> = None;
+    for rule in rules {
+        if let Some(rewritten) = rule.apply(current.as_deref().unwrap_or(path)) {
+            current = Some(rewritten);
+        }
+    }
+    current
+}
+
+/// Iterates over the parameter names (without the `:` / `*` prefix) used in a pattern.
+fn placeholders(pattern: &str) -> impl Iterator<Item = &str> {
+    pattern.split('/').filter_map(|segment| {
+        let start = segment.find([':', '*'])?;
+        Some(&segment[start + 1..])
+    })
+}
@@ -0,0 +1,25 @@
+//! Defines the random number generator the router draws from, which tests can
+//! replace with `Router::set_rng`, e.g. with a `testing::SeededRng`.
+
+use std::hash::{BuildHasher, RandomState};
+
+/// A source of random numbers, for the random split of canary routes.
+///
+/// Security tokens, such as session IDs and CSRF tokens, always come from the
+/// operating system's random generator, so that a `testing::SeededRng` set for
+/// tests can't make them predictable if it's left in.
+pub trait Rng {
+    /// Returns a random number, uniformly distributed.
+    fn next_u64(&self) -> u64;
+}
+
+/// Draws from the standard library's randomly seeded hasher, used by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemRng;
+
+impl Rng for SystemRng {
+    fn next_u64(&self) -> u64 {
+        // Each `RandomState` has new keys, so this hashes to a new value every time.
+        RandomState::new().hash_one(())
+    }
+}
@@ -0,0 +1,99 @@
+//! Defines the endpoint and the controller of the route a request matched, in
+//! its extensions, and typed access to the route's metadata for handlers.
+
+use crate::config::Endpoint;
+use crate::error::MetadataError;
+use crate::request::HttpRequest;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+
+/// The endpoint of the route a request matched, in its extensions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteEndpoint(pub Arc<Endpoint>);
+
+/// The controller a request was routed to, in its extensions: the stable or
+/// the candidate one for canary routes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteController(pub Arc<str>);
+
+impl HttpRequest {
+    /// Returns the endpoint of the route the request matched, or `None` for a
+    /// request the router didn't route, e.g. one built with
+    /// `HttpRequest::builder`.
+    pub fn route_endpoint(&self) -> Option<&Endpoint> {
+        self.extensions
+            .get::<RouteEndpoint>()
+            .map(|endpoint| &*endpoint.0)
+    }
+
+    /// Returns the name of the controller the request was routed to, e.g.
+    /// `admin::users::list` for a handler registered with
+    /// `Router::register_prefix` dispatching on it, or `None` for a request
+    /// the router didn't route to a handler.
+    pub fn route_controller(&self) -> Option<&str> {
+        self.extensions
+            .get::<RouteController>()
+            .map(|controller| &*controller.0)
+    }
+
+    /// Deserializes the route's metadata field `key`, e.g. a page size set
+    /// with `"x-page-size": 50` in the configuration. Returns `None` if the
+    /// route has no such field, or the request didn't match a route.
+    ///
+    /// Only fields whose names start with `x-` are loaded as metadata: other
+    /// unknown fields are left out, with a `LoadWarning::UnknownField` from
+    /// `Router::new_with_report` or `Router::from_json_str_with_report`, or
+    /// an error in strict mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MetadataError::InvalidType` if the field isn't a valid `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use generic_http_router::config::Endpoint;
+    /// use generic_http_router::{HttpRequest, RouteEndpoint};
+    /// use http::Method;
+    /// use std::sync::Arc;
+    ///
+    /// let endpoint = Endpoint::new(Method::GET, "/orders", "orders::list")
+    ///     .with_metadata("x-page-size", serde_json::json!(50));
+    /// let req = HttpRequest::builder()
+    ///     .extension(RouteEndpoint(Arc::new(endpoint)))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(req.route_config::<u32>("x-page-size"), Ok(Some(50)));
+    /// assert_eq!(req.route_config::<u32>("x-missing"), Ok(None));
+    /// assert!(req.route_config::<String>("x-page-size").is_err());
+    /// ```
+    pub fn route_config<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, MetadataError> {
+        let Some(value) = self
+            .route_endpoint()
+            .and_then(|endpoint| endpoint.metadata.get(key))
+        else {
+            return Ok(None);
+        };
+        T::deserialize(value)
+            .map(Some)
+            .map_err(|e| MetadataError::InvalidType {
+                key: key.to_string(),
+                reason: e.to_string(),
+            })
+    }
+
+    /// Returns the `description` of the route the request matched, or `None`
+    /// if it didn't match a route.
+    pub fn route_description(&self) -> Option<&str> {
+        self.route_endpoint()
+            .map(|endpoint| endpoint.description.as_str())
+    }
+
+    /// Returns the `tags` of the route the request matched, or no tags if it
+    /// didn't match a route.
+    pub fn route_tags(&self) -> &[String] {
+        self.route_endpoint()
+            .map_or(&[], |endpoint| endpoint.tags.as_slice())
+    }
+}
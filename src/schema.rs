@@ -0,0 +1,566 @@
+//! Defines the validation of request and response bodies against the JSON
+//! Schema documents of endpoints' `request_schema` and `response_schema`.
+//!
+//! A subset of JSON Schema (2020-12) is supported: `type`, `enum`, `const`,
+//! the numeric, string, array and object validation keywords (`minimum`,
+//! `pattern`, `items`, `properties`, `required`, `additionalProperties`...),
+//! `allOf`, `anyOf`, `oneOf`, `not`, and `$ref` to a JSON pointer within the
+//! same document, e.g. `#/$defs/address`. Annotations such as `title`,
+//! `description` and `format` are accepted and ignored. Any other keyword,
+//! e.g. `if` or `prefixItems`, is an error when the schema is compiled, so
+//! that no constraint is silently skipped. Patterns use the syntax of the
+//! `regex` crate, which has no look-around or backreferences.
+
+use http::HeaderMap;
+use http::header::CONTENT_TYPE;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// How many `$ref`s validation follows within one value, which bounds
+/// recursive schemas such as `{ "$ref": "#" }`.
+const MAX_REF_DEPTH: usize = 32;
+
+/// The keywords that don't constrain the value, accepted and ignored.
+const ANNOTATIONS: &[&str] = &[
+    "$schema",
+    "$id",
+    "$comment",
+    "$anchor",
+    "title",
+    "description",
+    "default",
+    "examples",
+    "format",
+    "readOnly",
+    "writeOnly",
+    "deprecated",
+    "contentMediaType",
+    "contentEncoding",
+];
+
+/// A compiled JSON Schema, checked for supported keywords and valid patterns
+/// and references.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    root: Value,
+    patterns: HashMap<String, Regex>,
+}
+
+/// A part of a value that doesn't match its schema.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The JSON pointer (RFC 6901) of the offending part, empty for the whole
+    /// value, e.g. `/items/0/name`.
+    pub pointer: String,
+    /// What's wrong with it, e.g. `missing required property 'name'`.
+    pub message: String,
+}
+
+/// Whether the router validates responses against their route's
+/// `response_schema`, set with `Router::set_response_validation`.
+///
+/// Only JSON responses with a 2xx status and a buffered body are validated,
+/// unless they carry the [`SkipValidation`] extension. Validation parses every
+/// such body again, so it's meant for tests and staging rather than production.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResponseValidation {
+    /// Don't validate responses.
+    #[default]
+    Off,
+    /// Log the violations of a response, with the `logging` feature, and send
+    /// it unchanged.
+    Warn,
+    /// Replace a response that doesn't match with a 500 Internal Server Error,
+    /// listing the violations as the `errors` of a problem document if problem
+    /// responses are enabled.
+    Enforce,
+}
+
+/// Exempts a response from validation against its route's `response_schema`
+/// when inserted into its extensions, e.g. for an intentionally partial body.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SkipValidation;
+
+impl Schema {
+    /// Compiles a schema.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first problem found, such as an
+    /// unsupported keyword, an invalid pattern or an unresolved `$ref`, with
+    /// its location in the schema.
+    pub fn compile(root: Value) -> Result<Self, String> {
+        let mut patterns = HashMap::new();
+        check(&root, &root, "#", &mut patterns)?;
+        Ok(Self { root, patterns })
+    }
+
+    /// Validates a value, returning every violation found.
+    pub fn validate(&self, value: &Value) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        self.validate_at(&self.root, value, "", 0, &mut violations);
+        violations
+    }
+
+    /// Returns `true` if the schema rejects every value, as far as its
+    /// keywords alone tell, e.g. `false`, `{ "enum": [] }` or a `minimum`
+    /// above its `maximum`.
+    pub fn rejects_everything(&self) -> bool {
+        never_matches(&self.root)
+    }
+
+    /// Returns `true` if a value matches the schema.
+    pub fn is_valid(&self, value: &Value) -> bool {
+        self.validate(value).is_empty()
+    }
+
+    fn validate_at(
+        &self,
+        schema: &Value,
+        value: &Value,
+        pointer: &str,
+        depth: usize,
+        violations: &mut Vec<Violation>,
+    ) {
+        let mut violation = |message: String| {
+            violations.push(Violation {
+                pointer: pointer.to_string(),
+                message,
+            });
+        };
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => return violation("no value is allowed here".to_string()),
+            Value::Object(schema) => schema,
+            _ => return,
+        };
+
+        if let Some(Value::String(reference)) = schema.get("$ref") {
+            if depth >= MAX_REF_DEPTH {
+                violation("the schema's references nest too deeply".to_string());
+            } else if let Some(target) = resolve(&self.root, reference) {
+                self.validate_at(target, value, pointer, depth + 1, violations);
+            }
+        }
+        let mut violation = |message: String| {
+            violations.push(Violation {
+                pointer: pointer.to_string(),
+                message,
+            });
+        };
+
+        if let Some(types) = schema.get("type") {
+            let allowed: Vec<&str> = match types {
+                Value::String(name) => vec![name.as_str()],
+                Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            if !allowed.iter().any(|name| has_type(value, name)) {
+                violation(format!(
+                    "expected {}, found {}",
+                    allowed.join(" or "),
+                    type_name(value)
+                ));
+            }
+        }
+        if let Some(Value::Array(values)) = schema.get("enum")
+            && !values.contains(value)
+        {
+            violation("the value isn't one of the allowed values".to_string());
+        }
+        if let Some(constant) = schema.get("const")
+            && constant != value
+        {
+            violation(format!("expected {constant}"));
+        }
+
+        match value {
+            Value::Number(number) => {
+                // Always a finite number, as serde_json has no arbitrary precision here.
+                let number = number.as_f64().unwrap_or(f64::NAN);
+                let bound = |name: &str| schema.get(name).and_then(Value::as_f64);
+                if let Some(minimum) = bound("minimum")
+                    && number < minimum
+                {
+                    violation(format!("{number} is less than the minimum of {minimum}"));
+                }
+                if let Some(maximum) = bound("maximum")
+                    && number > maximum
+                {
+                    violation(format!("{number} is greater than the maximum of {maximum}"));
+                }
+                if let Some(minimum) = bound("exclusiveMinimum")
+                    && number <= minimum
+                {
+                    violation(format!("{number} isn't greater than {minimum}"));
+                }
+                if let Some(maximum) = bound("exclusiveMaximum")
+                    && number >= maximum
+                {
+                    violation(format!("{number} isn't less than {maximum}"));
+                }
+                if let Some(divisor) = bound("multipleOf") {
+                    let quotient = number / divisor;
+                    if (quotient - quotient.round()).abs() > 1e-9 {
+                        violation(format!("{number} isn't a multiple of {divisor}"));
+                    }
+                }
+            }
+            Value::String(string) => {
+                let length = string.chars().count() as u64;
+                let limit = |name: &str| schema.get(name).and_then(Value::as_u64);
+                if let Some(min) = limit("minLength")
+                    && length < min
+                {
+                    violation(format!("the string is shorter than {min} characters"));
+                }
+                if let Some(max) = limit("maxLength")
+                    && length > max
+                {
+                    violation(format!("the string is longer than {max} characters"));
+                }
+                if let Some(Value::String(pattern)) = schema.get("pattern")
+                    && self
+                        .patterns
+                        .get(pattern)
+                        .is_some_and(|regex| !regex.is_match(string))
+                {
+                    violation(format!("the string doesn't match the pattern {pattern}"));
+                }
+            }
+            Value::Array(items) => {
+                let count = items.len() as u64;
+                let limit = |name: &str| schema.get(name).and_then(Value::as_u64);
+                if let Some(min) = limit("minItems")
+                    && count < min
+                {
+                    violation(format!("the array has fewer than {min} items"));
+                }
+                if let Some(max) = limit("maxItems")
+                    && count > max
+                {
+                    violation(format!("the array has more than {max} items"));
+                }
+                if schema.get("uniqueItems") == Some(&Value::Bool(true))
+                    && items
+                        .iter()
+                        .enumerate()
+                        .any(|(i, item)| items[..i].contains(item))
+                {
+                    violation("the array has duplicate items".to_string());
+                }
+                if let Some(item_schema) = schema.get("items") {
+                    for (i, item) in items.iter().enumerate() {
+                        let pointer = format!("{pointer}/{i}");
+                        self.validate_at(item_schema, item, &pointer, depth, violations);
+                    }
+                }
+            }
+            Value::Object(object) => {
+                let count = object.len() as u64;
+                let limit = |name: &str| schema.get(name).and_then(Value::as_u64);
+                if let Some(Value::Array(required)) = schema.get("required") {
+                    for name in required.iter().filter_map(Value::as_str) {
+                        if !object.contains_key(name) {
+                            violation(format!("missing required property '{name}'"));
+                        }
+                    }
+                }
+                if let Some(min) = limit("minProperties")
+                    && count < min
+                {
+                    violation(format!("the object has fewer than {min} properties"));
+                }
+                if let Some(max) = limit("maxProperties")
+                    && count > max
+                {
+                    violation(format!("the object has more than {max} properties"));
+                }
+
+                let properties = schema.get("properties").and_then(Value::as_object);
+                let pattern_properties = schema.get("patternProperties").and_then(Value::as_object);
+                for (name, property) in object {
+                    let pointer = format!("{pointer}/{}", escape(name));
+                    let mut matched = false;
+                    if let Some(property_schema) = properties.and_then(|p| p.get(name)) {
+                        matched = true;
+                        self.validate_at(property_schema, property, &pointer, depth, violations);
+                    }
+                    for (pattern, property_schema) in pattern_properties.into_iter().flatten() {
+                        if self
+                            .patterns
+                            .get(pattern)
+                            .is_some_and(|regex| regex.is_match(name))
+                        {
+                            matched = true;
+                            self.validate_at(
+                                property_schema,
+                                property,
+                                &pointer,
+                                depth,
+                                violations,
+                            );
+                        }
+                    }
+                    if !matched && let Some(additional) = schema.get("additionalProperties") {
+                        if additional == &Value::Bool(false) {
+                            violations.push(Violation {
+                                pointer,
+                                message: format!("property '{name}' isn't allowed"),
+                            });
+                        } else {
+                            self.validate_at(additional, property, &pointer, depth, violations);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(Value::Array(schemas)) = schema.get("allOf") {
+            for sub_schema in schemas {
+                self.validate_at(sub_schema, value, pointer, depth, violations);
+            }
+        }
+        let matches = |sub_schema: &Value| {
+            let mut sub_violations = Vec::new();
+            self.validate_at(sub_schema, value, pointer, depth, &mut sub_violations);
+            sub_violations.is_empty()
+        };
+        let mut violation = |message: &str| {
+            violations.push(Violation {
+                pointer: pointer.to_string(),
+                message: message.to_string(),
+            });
+        };
+        if let Some(Value::Array(schemas)) = schema.get("anyOf")
+            && !schemas.iter().any(matches)
+        {
+            violation("the value doesn't match any of the schemas of anyOf");
+        }
+        if let Some(Value::Array(schemas)) = schema.get("oneOf") {
+            let count = schemas
+                .iter()
+                .filter(|sub_schema| matches(sub_schema))
+                .count();
+            if count != 1 {
+                violation(&format!(
+                    "the value matches {count} of the schemas of oneOf instead of exactly one"
+                ));
+            }
+        }
+        if let Some(sub_schema) = schema.get("not")
+            && matches(sub_schema)
+        {
+            violation("the value matches the schema of not");
+        }
+    }
+}
+
+/// Returns `true` if `schema` rejects every value, following `allOf`, `anyOf`,
+/// `oneOf` and `not` but not `$ref`s.
+fn never_matches(schema: &Value) -> bool {
+    let schema = match schema {
+        Value::Bool(allowed) => return !allowed,
+        Value::Object(schema) => schema,
+        _ => return false,
+    };
+    let empty = |keyword: &str| {
+        schema
+            .get(keyword)
+            .and_then(Value::as_array)
+            .is_some_and(Vec::is_empty)
+    };
+    let number = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+    let bounds_cross = |min: &str, max: &str| matches!((number(min), number(max)), (Some(min), Some(max)) if min > max);
+    let subschemas = |keyword: &str| schema.get(keyword).and_then(Value::as_array);
+    empty("enum")
+        || empty("type")
+        || empty("anyOf")
+        || empty("oneOf")
+        || schema.get("not").is_some_and(|not| match not {
+            Value::Bool(allowed) => *allowed,
+            Value::Object(not) => not.is_empty(),
+            _ => false,
+        })
+        || bounds_cross("minimum", "maximum")
+        || bounds_cross("minLength", "maxLength")
+        || bounds_cross("minItems", "maxItems")
+        || bounds_cross("minProperties", "maxProperties")
+        || subschemas("allOf").is_some_and(|all| all.iter().any(never_matches))
+        || subschemas("anyOf").is_some_and(|any| any.iter().all(never_matches))
+        || subschemas("oneOf").is_some_and(|one| one.iter().all(never_matches))
+}
+
+/// Returns `true` if the `Content-Type` of a request is JSON, e.g.
+/// `application/json` or `application/merge-patch+json`.
+pub(crate) fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| {
+            let mime = mime.trim().to_ascii_lowercase();
+            mime == "application/json"
+                || (mime.starts_with("application/") && mime.ends_with("+json"))
+        })
+}
+
+/// Checks that a schema, at `location` in `root`, only uses supported
+/// keywords with valid values, compiling its patterns.
+fn check(
+    root: &Value,
+    schema: &Value,
+    location: &str,
+    patterns: &mut HashMap<String, Regex>,
+) -> Result<(), String> {
+    let schema = match schema {
+        Value::Bool(_) => return Ok(()),
+        Value::Object(schema) => schema,
+        _ => return Err(format!("{location} isn't a schema")),
+    };
+    for (keyword, value) in schema {
+        let at = format!("{location}/{}", escape(keyword));
+        let invalid = |expected: &str| Err(format!("{at} must be {expected}"));
+        match (keyword.as_str(), value) {
+            (keyword, _) if ANNOTATIONS.contains(&keyword) => {}
+            ("$defs" | "definitions" | "properties", Value::Object(schemas)) => {
+                for (name, sub_schema) in schemas {
+                    check(
+                        root,
+                        sub_schema,
+                        &format!("{at}/{}", escape(name)),
+                        patterns,
+                    )?;
+                }
+            }
+            ("patternProperties", Value::Object(schemas)) => {
+                for (pattern, sub_schema) in schemas {
+                    compile_pattern(pattern, &at, patterns)?;
+                    check(
+                        root,
+                        sub_schema,
+                        &format!("{at}/{}", escape(pattern)),
+                        patterns,
+                    )?;
+                }
+            }
+            ("$defs" | "definitions" | "properties" | "patternProperties", _) => {
+                return invalid("an object of schemas");
+            }
+            ("items" | "additionalProperties" | "not", sub_schema) => {
+                check(root, sub_schema, &at, patterns)?;
+            }
+            ("allOf" | "anyOf" | "oneOf", Value::Array(schemas)) if !schemas.is_empty() => {
+                for (i, sub_schema) in schemas.iter().enumerate() {
+                    check(root, sub_schema, &format!("{at}/{i}"), patterns)?;
+                }
+            }
+            ("allOf" | "anyOf" | "oneOf", _) => return invalid("a non-empty array of schemas"),
+            ("type", Value::String(name)) if is_type_name(name) => {}
+            ("type", Value::Array(names))
+                if names
+                    .iter()
+                    .all(|name| name.as_str().is_some_and(is_type_name)) => {}
+            ("type", _) => return invalid("a type name or an array of type names"),
+            ("enum", Value::Array(_)) => {}
+            ("enum", _) => return invalid("an array"),
+            ("const", _) => {}
+            ("required", Value::Array(names)) if names.iter().all(Value::is_string) => {}
+            ("required", _) => return invalid("an array of property names"),
+            ("minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum", Value::Number(_)) => {
+            }
+            ("multipleOf", Value::Number(n)) if n.as_f64().is_some_and(|n| n > 0.0) => {}
+            ("minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum" | "multipleOf", _) => {
+                return invalid("a number");
+            }
+            (
+                "minLength" | "maxLength" | "minItems" | "maxItems" | "minProperties"
+                | "maxProperties",
+                Value::Number(n),
+            ) if n.is_u64() => {}
+            (
+                "minLength" | "maxLength" | "minItems" | "maxItems" | "minProperties"
+                | "maxProperties",
+                _,
+            ) => return invalid("a non-negative integer"),
+            ("uniqueItems", Value::Bool(_)) => {}
+            ("uniqueItems", _) => return invalid("a boolean"),
+            ("pattern", Value::String(pattern)) => compile_pattern(pattern, &at, patterns)?,
+            ("pattern", _) => return invalid("a string"),
+            ("$ref", Value::String(reference)) => {
+                if resolve(root, reference).is_none() {
+                    return Err(format!("{at} refers to '{reference}', which doesn't exist"));
+                }
+            }
+            ("$ref", _) => return invalid("a string"),
+            (keyword, _) => {
+                return Err(format!(
+                    "{location} uses the unsupported keyword '{keyword}'"
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Compiles a pattern at `location` in the schema, once per distinct pattern.
+fn compile_pattern(
+    pattern: &str,
+    location: &str,
+    patterns: &mut HashMap<String, Regex>,
+) -> Result<(), String> {
+    if !patterns.contains_key(pattern) {
+        let regex =
+            Regex::new(pattern).map_err(|e| format!("{location} has an invalid pattern: {e}"))?;
+        patterns.insert(pattern.to_string(), regex);
+    }
+    Ok(())
+}
+
+/// Resolves a `$ref` to a JSON pointer within the document, e.g. `#/$defs/id`.
+fn resolve<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    let pointer = reference.strip_prefix('#')?;
+    let pointer = crate::path::percent_decode(pointer, crate::path::PathDecoding::Reject)?;
+    root.pointer(&pointer)
+}
+
+fn is_type_name(name: &str) -> bool {
+    matches!(
+        name,
+        "null" | "boolean" | "object" | "array" | "number" | "integer" | "string"
+    )
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match (name, value) {
+        ("null", Value::Null)
+        | ("boolean", Value::Bool(_))
+        | ("object", Value::Object(_))
+        | ("array", Value::Array(_))
+        | ("number", Value::Number(_))
+        | ("string", Value::String(_)) => true,
+        ("integer", Value::Number(number)) => {
+            number.is_i64() || number.is_u64() || number.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        _ => false,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+    }
+}
+
+/// Escapes a property name for a JSON pointer.
+fn escape(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
@@ -0,0 +1,334 @@
+//! Defines cookie-based sessions: a signed session cookie identifies data kept
+//! in a `SessionStore`, which handlers read and change through a `Session`.
+
+use crate::canary;
+use crate::clock::{Clock, SystemClock};
+use crate::crypto;
+use crate::request::HttpRequest;
+use crate::response::HttpResponse;
+use http::HeaderValue;
+use http::header::SET_COOKIE;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// The data of a session, by key.
+pub type SessionData = HashMap<String, Value>;
+
+/// Where session data is kept between requests, by session ID.
+///
+/// Errors are reported as messages: a session that fails to load is replaced
+/// with a new one, and one that fails to save gets the request a 500 Internal
+/// Server Error.
+pub trait SessionStore {
+    /// Returns the data of the session `id`, or `None` if there's no such
+    /// session, e.g. because it expired.
+    fn load(&self, id: &str) -> Result<Option<SessionData>, String>;
+
+    /// Saves the data of the session `id`, to be kept for `max_age` if set.
+    fn save(&self, id: &str, data: &SessionData, max_age: Option<Duration>) -> Result<(), String>;
+
+    /// Deletes the session `id`.
+    fn destroy(&self, id: &str) -> Result<(), String>;
+}
+
+/// A `SessionStore` keeping sessions in memory, e.g. for tests or a single
+/// server instance. Expired sessions are dropped when they're next loaded.
+pub struct MemoryStore {
+    sessions: Mutex<HashMap<String, (SessionData, Option<Instant>)>>,
+    clock: Arc<dyn Clock + Send + Sync>,
+}
+
+impl MemoryStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tells the time of expiry with `clock` instead of the system's, e.g. a
+    /// `testing::MockClock` to expire sessions without waiting.
+    pub fn with_clock<C>(mut self, clock: C) -> Self
+    where
+        C: Clock + Send + Sync + 'static,
+    {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, (SessionData, Option<Instant>)>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self {
+            sessions: Mutex::default(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn load(&self, id: &str) -> Result<Option<SessionData>, String> {
+        let mut sessions = self.lock();
+        match sessions.get(id) {
+            Some((_, Some(expires))) if *expires <= self.clock.now() => {
+                sessions.remove(id);
+                Ok(None)
+            }
+            Some((data, _)) => Ok(Some(data.clone())),
+            None => Ok(None),
+        }
+    }
+
+    fn save(&self, id: &str, data: &SessionData, max_age: Option<Duration>) -> Result<(), String> {
+        // A `max_age` too large to add to the current time never expires.
+        let expires = max_age.and_then(|max_age| self.clock.now().checked_add(max_age));
+        self.lock().insert(id.to_string(), (data.clone(), expires));
+        Ok(())
+    }
+
+    fn destroy(&self, id: &str) -> Result<(), String> {
+        self.lock().remove(id);
+        Ok(())
+    }
+}
+
+/// The `SameSite` attribute of the session cookie.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    #[default]
+    Lax,
+    /// Sent with cross-site requests too; browsers require `Secure` with it.
+    None,
+}
+
+impl SameSite {
+    fn as_str(self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+/// Loads the session of each request before its handler is called and saves
+/// it afterwards, enabled with `Router::use_sessions`.
+///
+/// The session cookie holds only the session ID, signed with HMAC-SHA256 and
+/// the key given to [`SessionMiddleware::new`]: a cookie with a wrong
+/// signature is ignored, and the request gets a new, empty session. The
+/// cookie is only set when a handler changed the session, and is
+/// `HttpOnly; Path=/` with the configured name, `Secure`, `SameSite` and
+/// `Max-Age` attributes.
+pub struct SessionMiddleware {
+    key: Vec<u8>,
+    store: Arc<dyn SessionStore + Send + Sync>,
+    cookie_name: String,
+    secure: bool,
+    same_site: SameSite,
+    max_age: Option<Duration>,
+}
+
+impl SessionMiddleware {
+    /// Creates the middleware, signing cookies with `key` and keeping sessions
+    /// in `store`. The cookie is named `session`, `Secure`, `SameSite=Lax` and
+    /// without `Max-Age`, so browsers drop it when they close.
+    ///
+    /// The key should be at least 32 random bytes, kept secret and the same on
+    /// every server instance.
+    pub fn new<K: Into<Vec<u8>>>(key: K, store: Box<dyn SessionStore + Send + Sync>) -> Self {
+        Self {
+            key: key.into(),
+            store: Arc::from(store),
+            cookie_name: "session".to_string(),
+            secure: true,
+            same_site: SameSite::default(),
+            max_age: None,
+        }
+    }
+
+    /// Sets the name of the cookie.
+    pub fn with_cookie_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.cookie_name = name.into();
+        self
+    }
+
+    /// Sets whether the cookie is `Secure`, i.e. only sent over HTTPS.
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Sets the `SameSite` attribute of the cookie.
+    pub fn with_same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = same_site;
+        self
+    }
+
+    /// Sets the `Max-Age` of the cookie, which is also how long the store keeps
+    /// the session.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Returns the session of a request: the stored one its cookie identifies,
+    /// or a new, empty one.
+    pub(crate) fn load(&self, req: &HttpRequest) -> Session {
+        let id = canary::cookie(&req.headers, &self.cookie_name)
+            .and_then(|value| value.rsplit_once('.'))
+            .filter(|(id, signature)| crypto::hmac_verify(&self.key, id.as_bytes(), signature))
+            .map(|(id, _)| id.to_string());
+        let data = id.as_deref().and_then(|id| {
+            self.store
+                .load(id)
+                .inspect_err(|_error| {
+                    #[cfg(feature = "logging")]
+                    tracing::warn!(error = _error.as_str(), "failed to load the session");
+                })
+                .ok()
+                .flatten()
+        });
+        Session {
+            state: Arc::new(Mutex::new(SessionState {
+                id: id.filter(|_| data.is_some()),
+                had_cookie: data.is_some(),
+                data: data.unwrap_or_default(),
+                changed: false,
+                destroyed: false,
+            })),
+        }
+    }
+
+    /// Saves or destroys a session after its request was handled, setting the
+    /// cookie on `response` as needed. Returns the error of the store, if any.
+    pub(crate) fn finish(
+        &self,
+        session: &Session,
+        response: &mut HttpResponse,
+    ) -> Result<(), String> {
+        let mut state = session.lock();
+        if state.destroyed {
+            if let Some(id) = state.id.take() {
+                self.store.destroy(&id)?;
+            }
+            if state.had_cookie {
+                self.set_cookie(response, "", Some(Duration::ZERO));
+            }
+            return Ok(());
+        }
+        if !state.changed {
+            return Ok(());
+        }
+
+        let id = match &state.id {
+            Some(id) => id.clone(),
+            None => state.id.insert(new_session_id()?).clone(),
+        };
+        self.store.save(&id, &state.data, self.max_age)?;
+        let value = format!("{id}.{}", crypto::hmac_sign(&self.key, id.as_bytes()));
+        self.set_cookie(response, &value, self.max_age);
+        Ok(())
+    }
+
+    fn set_cookie(&self, response: &mut HttpResponse, value: &str, max_age: Option<Duration>) {
+        let mut cookie = format!("{}={value}; Path=/; HttpOnly", self.cookie_name);
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+        let _ = write!(cookie, "; SameSite={}", self.same_site.as_str());
+        if let Some(max_age) = max_age {
+            let _ = write!(cookie, "; Max-Age={}", max_age.as_secs());
+        }
+        if let Ok(value) = HeaderValue::from_str(&cookie) {
+            response.headers.append(SET_COOKIE, value);
+        }
+    }
+}
+
+/// The session of a request, available to its handler with
+/// [`HttpRequest::session`]. Changes are saved once the handler returns.
+///
+/// Values are stored as JSON, so anything serializable can be kept.
+#[derive(Clone)]
+pub struct Session {
+    state: Arc<Mutex<SessionState>>,
+}
+
+struct SessionState {
+    /// The session ID, or `None` for a new session not saved yet.
+    id: Option<String>,
+    /// Whether the request had a valid cookie for a stored session.
+    had_cookie: bool,
+    data: SessionData,
+    changed: bool,
+    destroyed: bool,
+}
+
+impl Session {
+    fn lock(&self) -> MutexGuard<'_, SessionState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the value stored under `key`, or `None` if there's none or it
+    /// doesn't deserialize into `T`.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.lock().data.get(key)?.clone();
+        serde_json::from_value(value).ok()
+    }
+
+    /// Stores `value` under `key`, replacing any previous value. A value that
+    /// fails to serialize isn't stored.
+    pub fn insert<T: Serialize>(&self, key: &str, value: T) {
+        if let Ok(value) = serde_json::to_value(value) {
+            let mut state = self.lock();
+            state.data.insert(key.to_string(), value);
+            state.changed = true;
+        }
+    }
+
+    /// Removes the value stored under `key`, returning `true` if there was one.
+    pub fn remove(&self, key: &str) -> bool {
+        let mut state = self.lock();
+        let removed = state.data.remove(key).is_some();
+        state.changed |= removed;
+        removed
+    }
+
+    /// Removes every value, keeping the session.
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.changed |= !state.data.is_empty();
+        state.data.clear();
+    }
+
+    /// Deletes the session from the store and the client's cookie, e.g. on
+    /// logout.
+    pub fn destroy(&self) {
+        let mut state = self.lock();
+        state.data.clear();
+        state.destroyed = true;
+    }
+}
+
+impl HttpRequest {
+    /// Returns the session of the request, if sessions are enabled with
+    /// `Router::use_sessions`.
+    pub fn session(&self) -> Option<&Session> {
+        self.extensions.get()
+    }
+}
+
+/// Returns a new session ID: 128 bits from the operating system's random
+/// generator, hex-encoded.
+fn new_session_id() -> Result<String, String> {
+    crypto::random_token(16).map_err(|error| format!("failed to generate a session ID: {error}"))
+}
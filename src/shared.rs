@@ -0,0 +1,155 @@
+//! Defines `SharedRouter`, a router handle whose routes and handlers can be
+//! changed while it's shared between threads.
+
+use crate::config::Endpoint;
+use crate::error::{LoadWarning, RouterError};
+use crate::handler::{Handler, IntoHandler};
+use crate::maintenance::MaintenanceMode;
+use crate::{Body, Router};
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+use http::{Method, Request, Response};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// A `Router` that can be changed through a shared reference.
+///
+/// Routing reads the current router without locking. Every change copies the
+/// router, applies the change to the copy and swaps it in, so requests being
+/// routed keep using the version they started with. Changes are serialized, and
+/// copying shares the handlers, but rebuilds or clones the routing table, so
+/// `SharedRouter` suits routes that change occasionally, such as tenants being
+/// added and removed, rather than on every request.
+pub struct SharedRouter {
+    current: ArcSwap<Router>,
+    /// Serializes writers, so concurrent changes aren't lost.
+    write: Mutex<()>,
+}
+
+impl SharedRouter {
+    /// Creates a shared handle for `router`.
+    pub fn new(router: Router) -> Self {
+        Self {
+            current: ArcSwap::from_pointee(router),
+            write: Mutex::new(()),
+        }
+    }
+
+    /// Returns the current version of the router. Later changes don't affect it.
+    pub fn load(&self) -> Arc<Router> {
+        self.current.load_full()
+    }
+
+    /// Routes a request with the current version of the router; see `Router::route`.
+    pub fn route<B: Into<Bytes>>(&self, req: Request<B>) -> Response<Bytes> {
+        self.current.load().route(req)
+    }
+
+    /// Routes a request with the current version of the router, passing streamed
+    /// bodies through; see `Router::route_streaming`.
+    pub fn route_streaming<B: Into<Bytes>>(&self, req: Request<B>) -> Response<Body> {
+        self.current.load().route_streaming(req)
+    }
+
+    /// Applies `change` to a copy of the router and makes the copy current.
+    ///
+    /// Use this to make several changes at once, so that no request sees only
+    /// some of them. If `change` panics, the router is left unchanged.
+    pub fn update<R, F>(&self, change: F) -> R
+    where
+        F: FnOnce(&mut Router) -> R,
+    {
+        let _guard = self.write.lock().unwrap_or_else(|e| e.into_inner());
+        let mut router = self.current.load().snapshot();
+        let result = change(&mut router);
+        self.current.store(Arc::new(router));
+        result
+    }
+
+    /// Adds a route; see `Router::add_route`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError` if the endpoint's path is not a valid pattern or
+    /// conflicts with a route registered for the same method. The router is left
+    /// unchanged in that case.
+    pub fn add_route(&self, endpoint: Endpoint) -> Result<(), RouterError> {
+        let _guard = self.write.lock().unwrap_or_else(|e| e.into_inner());
+        let mut router = self.current.load().snapshot();
+        router.add_route(endpoint)?;
+        self.current.store(Arc::new(router));
+        Ok(())
+    }
+
+    /// Reloads the routes and rewrite rules from a JSON configuration file,
+    /// keeping the registered handlers; see `Router::reload`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError` if the configuration can't be loaded. The router
+    /// is left unchanged in that case.
+    pub fn reload<P: AsRef<Path>>(&self, config_path: P) -> Result<Vec<LoadWarning>, RouterError> {
+        let _guard = self.write.lock().unwrap_or_else(|e| e.into_inner());
+        let mut router = self.current.load().snapshot();
+        let warnings = router.reload(config_path)?;
+        self.current.store(Arc::new(router));
+        Ok(warnings)
+    }
+
+    /// Removes the route registered for `method` with exactly the pattern `path`
+    /// (e.g. `"/users/:id"`); see `Router::remove_route`. Returns `false` if
+    /// there's no such route.
+    pub fn remove_route(&self, method: &Method, path: &str) -> bool {
+        let _guard = self.write.lock().unwrap_or_else(|e| e.into_inner());
+        let mut router = self.current.load().snapshot();
+        if !router.remove_route(method, path) {
+            return false;
+        }
+        self.current.store(Arc::new(router));
+        true
+    }
+
+    /// Registers a handler for a controller name; see `Router::register_handler`.
+    pub fn register_handler<S, H>(&self, controller_name: S, handler: H)
+    where
+        S: Into<String>,
+        H: Handler + Send + Sync + 'static,
+    {
+        self.update(|router| router.register_handler(controller_name, handler));
+    }
+
+    /// Registers a handler for the controller names starting with `prefix`;
+    /// see `Router::register_prefix`.
+    pub fn register_prefix<S, H>(&self, prefix: S, handler: H)
+    where
+        S: Into<String>,
+        H: Handler + Send + Sync + 'static,
+    {
+        self.update(|router| router.register_prefix(prefix, handler));
+    }
+
+    /// Registers an `HttpHandler`, in a `Box` or an `Arc`, for a controller
+    /// name.
+    #[deprecated(note = "use `register_handler`, which takes the handler itself")]
+    pub fn register<S: Into<String>, H: IntoHandler>(&self, controller_name: S, handler: H) {
+        self.register_handler(controller_name, handler.into_handler());
+    }
+
+    /// Removes the handler registered for `controller_name`, so its routes get a
+    /// 501 Not Implemented. Returns `false` if no handler was registered.
+    pub fn unregister(&self, controller_name: &str) -> bool {
+        self.update(|router| router.unregister(controller_name).is_some())
+    }
+
+    /// Sets the maintenance mode; see `Router::set_maintenance`. The mode is
+    /// shared by every version of the router, so this doesn't copy it.
+    pub fn set_maintenance(&self, mode: MaintenanceMode) {
+        self.current.load().set_maintenance(mode);
+    }
+}
+
+impl From<Router> for SharedRouter {
+    fn from(router: Router) -> Self {
+        Self::new(router)
+    }
+}
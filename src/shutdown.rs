@@ -0,0 +1,189 @@
+//! Defines graceful shutdown: a router told to shut down through its
+//! `ShutdownHandle` answers new requests with a 503 Service Unavailable, while
+//! the requests already in flight finish.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// The `Retry-After` of requests turned away while shutting down, by default.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(5);
+
+/// The shutdown state of a router, shared by its copies and its handles.
+#[derive(Debug)]
+pub(crate) struct Shutdown {
+    shutting_down: AtomicBool,
+    retry_after_secs: AtomicU64,
+    in_flight: AtomicUsize,
+    /// Locked to wait for, and to announce, the last in-flight request
+    /// finishing.
+    lock: Mutex<()>,
+    idle: Condvar,
+    /// Wakes the futures of `wait_idle_async` when the last in-flight request
+    /// finishes.
+    #[cfg(feature = "async")]
+    drained: tokio::sync::Notify,
+}
+
+/// A request being routed, counted in flight until it's dropped.
+pub(crate) struct InFlight<'a>(&'a Shutdown);
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self {
+            shutting_down: AtomicBool::new(false),
+            retry_after_secs: AtomicU64::new(DEFAULT_RETRY_AFTER.as_secs()),
+            in_flight: AtomicUsize::new(0),
+            lock: Mutex::new(()),
+            idle: Condvar::new(),
+            #[cfg(feature = "async")]
+            drained: tokio::sync::Notify::new(),
+        }
+    }
+}
+
+impl Shutdown {
+    /// Returns an independent state, shutting down if this one is, with no
+    /// request in flight.
+    pub(crate) fn copy(&self) -> Self {
+        let copy = Self::default();
+        copy.shutting_down
+            .store(self.is_shutting_down(), Ordering::SeqCst);
+        copy.retry_after_secs
+            .store(self.retry_after().as_secs(), Ordering::Relaxed);
+        copy
+    }
+
+    /// Counts a request in flight, until the returned guard is dropped.
+    ///
+    /// A request is counted before it checks whether the router is shutting
+    /// down, so that `wait_idle` can't miss one that got past the check.
+    pub(crate) fn enter(&self) -> InFlight<'_> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlight(self)
+    }
+
+    pub(crate) fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn retry_after(&self) -> Duration {
+        Duration::from_secs(self.retry_after_secs.load(Ordering::Relaxed))
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        if self.0.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            {
+                let _lock = self.0.lock.lock().unwrap_or_else(|e| e.into_inner());
+                self.0.idle.notify_all();
+            }
+            #[cfg(feature = "async")]
+            self.0.drained.notify_waiters();
+        }
+    }
+}
+
+/// Shuts a router down gracefully, returned by `Router::shutdown_controller`.
+///
+/// Once `begin_shutdown` is called, requests get a 503 Service Unavailable
+/// with `Connection: close` and a `Retry-After` header instead of being
+/// routed, except those to the built-in health endpoints and the paths set
+/// with `Router::set_shutdown_exempt_paths`. The readiness endpoint answers
+/// 503 from then on while the liveness endpoint still answers 200, so that a
+/// load balancer stops sending requests before the process exits.
+///
+/// A request is in flight from the moment it's routed, or resolved with
+/// `Router::resolve`, until `route`, `route_async`, `route_streaming` or
+/// `dispatch` returns; the body a streamed response sends after that isn't
+/// waited for.
+///
+/// The handle is shared by every copy of the router, such as every version of
+/// a `SharedRouter`, but not by clones.
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle {
+    state: Arc<Shutdown>,
+}
+
+impl ShutdownHandle {
+    pub(crate) fn new(state: Arc<Shutdown>) -> Self {
+        Self { state }
+    }
+
+    /// Turns new requests away from now on. This can't be undone.
+    pub fn begin_shutdown(&self) {
+        self.state.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns `true` once `begin_shutdown` has been called.
+    pub fn is_shutting_down(&self) -> bool {
+        self.state.is_shutting_down()
+    }
+
+    /// Sets the `Retry-After` of the requests turned away, in whole seconds (5
+    /// seconds by default).
+    pub fn set_retry_after(&self, retry_after: Duration) {
+        self.state
+            .retry_after_secs
+            .store(retry_after.as_secs(), Ordering::Relaxed);
+    }
+
+    /// Returns the number of requests in flight.
+    pub fn in_flight(&self) -> usize {
+        self.state.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Blocks until no request is in flight, or `timeout` elapses. Returns
+    /// `true` if no request is in flight.
+    ///
+    /// A `timeout` too large to add to the current time, e.g. `Duration::MAX`,
+    /// waits for as long as it takes.
+    pub fn wait_idle(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut lock = self.state.lock.lock().unwrap_or_else(|e| e.into_inner());
+        while self.in_flight() > 0 {
+            let Some(deadline) = deadline else {
+                lock = self
+                    .state
+                    .idle
+                    .wait(lock)
+                    .unwrap_or_else(|e| e.into_inner());
+                continue;
+            };
+            let Some(left) = deadline
+                .checked_duration_since(Instant::now())
+                .filter(|left| !left.is_zero())
+            else {
+                return false;
+            };
+            lock = match self.state.idle.wait_timeout(lock, left) {
+                Ok((lock, _)) => lock,
+                Err(e) => e.into_inner().0,
+            };
+        }
+        true
+    }
+
+    /// Waits like `wait_idle`, without blocking the thread.
+    #[cfg(feature = "async")]
+    pub async fn wait_idle_async(&self, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now().checked_add(timeout);
+        loop {
+            // Created before the count is checked, so the last request
+            // finishing in between isn't missed.
+            let drained = self.state.drained.notified();
+            if self.in_flight() == 0 {
+                return true;
+            }
+            match deadline {
+                Some(deadline) => {
+                    if tokio::time::timeout_at(deadline, drained).await.is_err() {
+                        return self.in_flight() == 0;
+                    }
+                }
+                None => drained.await,
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! Defines the verification of signed requests, such as webhooks signed by
+//! their provider with a shared secret: `SignatureGuard`, and the `signature`
+//! of endpoints.
+
+use crate::clock::{Clock, SystemClock};
+use crate::config::{Signature, SignatureAlgorithm};
+use crate::crypto;
+use crate::guard::Guard;
+use crate::request::HttpRequest;
+use crate::response::HttpResponse;
+use http::header::HeaderName;
+use http::{HeaderMap, StatusCode};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The tolerance of signature timestamps when none is configured.
+const DEFAULT_TOLERANCE: Duration = Duration::from_secs(300);
+
+/// Where the secrets of endpoint signatures are read from, by the name given
+/// as their `secret_env`. Set with `Router::set_secret_provider`, e.g. to read
+/// them from a vault.
+///
+/// Secrets are read for each signed request, so they can be rotated without
+/// reloading; providers reading them from a remote service should cache them.
+pub trait SecretProvider {
+    /// Returns the secret named `name`, or `None` if there's none.
+    fn secret(&self, name: &str) -> Option<Vec<u8>>;
+}
+
+/// The default `SecretProvider`, reading each secret from the environment
+/// variable of its name.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvSecretProvider;
+
+impl SecretProvider for EnvSecretProvider {
+    fn secret(&self, name: &str) -> Option<Vec<u8>> {
+        std::env::var_os(name)
+            .and_then(|value| value.into_string().ok())
+            .filter(|value| !value.is_empty())
+            .map(String::into_bytes)
+    }
+}
+
+/// The `signature` of a route, with its header name parsed.
+#[derive(Debug, Clone)]
+pub(crate) struct RouteSignature {
+    pub(crate) header: HeaderName,
+    pub(crate) algorithm: SignatureAlgorithm,
+    pub(crate) secret_name: String,
+    pub(crate) tolerance: Duration,
+}
+
+impl RouteSignature {
+    /// Parses the `signature` of an endpoint, returning why it's invalid.
+    pub(crate) fn new(signature: &Signature) -> Result<Self, String> {
+        let header = HeaderName::from_bytes(signature.header.as_bytes())
+            .map_err(|e| format!("invalid signature header '{}': {e}", signature.header))?;
+        Ok(Self {
+            header,
+            algorithm: signature.algorithm,
+            secret_name: signature.secret_env.clone(),
+            tolerance: signature
+                .tolerance_seconds
+                .map_or(DEFAULT_TOLERANCE, Duration::from_secs),
+        })
+    }
+}
+
+/// A guard rejecting requests without a valid signature with a 401
+/// Unauthorized, e.g. registered with `Router::register_guard` for routes
+/// whose secret isn't given by name. Endpoints can also require a signature
+/// with their `signature`.
+///
+/// # Examples
+///
+/// ```
+/// use generic_http_router::config::SignatureAlgorithm;
+/// use generic_http_router::signature::SignatureGuard;
+/// use http::header::HeaderName;
+///
+/// let guard = SignatureGuard::new(
+///     HeaderName::from_static("x-hub-signature-256"),
+///     SignatureAlgorithm::HmacSha256,
+///     "It's a Secret to Everybody",
+/// );
+/// ```
+pub struct SignatureGuard {
+    header: HeaderName,
+    algorithm: SignatureAlgorithm,
+    secret: Vec<u8>,
+    tolerance: Duration,
+    clock: Arc<dyn Clock + Send + Sync>,
+}
+
+impl SignatureGuard {
+    /// Creates a guard checking the signature in `header` with `secret`,
+    /// accepting timestamps up to 300 seconds off.
+    pub fn new<S: Into<Vec<u8>>>(
+        header: HeaderName,
+        algorithm: SignatureAlgorithm,
+        secret: S,
+    ) -> Self {
+        Self {
+            header,
+            algorithm,
+            secret: secret.into(),
+            tolerance: DEFAULT_TOLERANCE,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Sets how old or early a signature's timestamp can be.
+    pub fn with_tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets the clock timestamps are checked against, e.g. a
+    /// [`testing::MockClock`](crate::testing::MockClock) in tests.
+    pub fn with_clock<C: Clock + Send + Sync + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+}
+
+impl Guard for SignatureGuard {
+    fn check(&self, req: &HttpRequest) -> Result<(), HttpResponse> {
+        let valid = verify(
+            self.algorithm,
+            &req.headers,
+            &self.header,
+            &req.body,
+            &self.secret,
+            self.clock.system_time(),
+            self.tolerance,
+        );
+        if valid {
+            Ok(())
+        } else {
+            Err(HttpResponse::text(
+                StatusCode::UNAUTHORIZED,
+                "Invalid signature",
+            ))
+        }
+    }
+}
+
+/// Returns `true` if the `header` of a request is a valid signature of `body`
+/// with `secret`, at `now`. Digests are compared in constant time.
+pub(crate) fn verify(
+    algorithm: SignatureAlgorithm,
+    headers: &HeaderMap,
+    header: &HeaderName,
+    body: &[u8],
+    secret: &[u8],
+    now: SystemTime,
+    tolerance: Duration,
+) -> bool {
+    let Some(value) = headers.get(header).and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    match algorithm {
+        SignatureAlgorithm::HmacSha256 => {
+            let value = value.trim();
+            let digest = value
+                .get(..7)
+                .filter(|prefix| prefix.eq_ignore_ascii_case("sha256="))
+                .map_or(value, |_| &value[7..]);
+            matches(&crypto::hmac_sha256(secret, body), digest)
+        }
+        SignatureAlgorithm::StripeV1 => {
+            let mut timestamp = None;
+            let mut signatures = Vec::new();
+            for element in value.split(',') {
+                match element.trim().split_once('=') {
+                    Some(("t", t)) => timestamp = t.parse::<u64>().ok(),
+                    Some(("v1", signature)) => signatures.push(signature),
+                    _ => {}
+                }
+            }
+            let Some(timestamp) = timestamp else {
+                return false;
+            };
+            let Some(signed_at) = UNIX_EPOCH.checked_add(Duration::from_secs(timestamp)) else {
+                return false;
+            };
+            let skew = now
+                .duration_since(signed_at)
+                .unwrap_or_else(|e| e.duration());
+            if skew > tolerance {
+                return false;
+            }
+            let mut payload = format!("{timestamp}.").into_bytes();
+            payload.extend_from_slice(body);
+            let expected = crypto::hmac_sha256(secret, &payload);
+            // Every signature is checked, so that the time taken doesn't tell
+            // which one matched.
+            signatures.iter().fold(false, |valid, signature| {
+                matches(&expected, signature) | valid
+            })
+        }
+    }
+}
+
+/// Compares a digest with its hex encoding, of either case, in constant time.
+fn matches(digest: &[u8; 32], hex: &str) -> bool {
+    crypto::constant_time_eq(
+        crypto::hex(digest).as_bytes(),
+        hex.to_ascii_lowercase().as_bytes(),
+    )
+}
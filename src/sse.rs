@@ -0,0 +1,175 @@
+//! Defines helpers for Server-Sent Events (`text/event-stream`) responses.
+
+use crate::body::Body;
+use crate::response::HttpResponse;
+use bytes::Bytes;
+use http::StatusCode;
+use http::header::{CACHE_CONTROL, CONTENT_TYPE};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// A single Server-Sent Event.
+///
+/// Build one with [`SseEvent::data`], or a comment with [`SseEvent::comment`],
+/// and serialize it into the wire format with [`SseEvent::to_bytes`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    /// The event ID, sent as the `id` field.
+    pub id: Option<String>,
+    /// The event type, sent as the `event` field.
+    pub event: Option<String>,
+    /// The event data. Each line is sent as a separate `data` field.
+    pub data: String,
+    /// The reconnection time the client should use, sent as the `retry` field.
+    pub retry: Option<Duration>,
+    /// A comment, ignored by clients. Each line is sent as a separate `:` line.
+    pub comment: Option<String>,
+}
+
+impl SseEvent {
+    /// Creates an event with the given data.
+    pub fn data<S: Into<String>>(data: S) -> Self {
+        Self {
+            data: data.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Creates a comment-only event, which clients ignore.
+    pub fn comment<S: Into<String>>(comment: S) -> Self {
+        Self {
+            comment: Some(comment.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Creates an empty comment, commonly sent periodically to keep the
+    /// connection alive through proxies.
+    pub fn keep_alive() -> Self {
+        Self::comment("")
+    }
+
+    /// Sets the event type.
+    pub fn event<S: Into<String>>(mut self, event: S) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Sets the event ID.
+    pub fn id<S: Into<String>>(mut self, id: S) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Sets the reconnection time.
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Serializes the event into the `text/event-stream` wire format, including
+    /// the blank line that terminates it.
+    ///
+    /// Line breaks in the data (`\n`, `\r\n` or `\r`) start a new `data` field,
+    /// and are removed from the `id` and `event` fields, where they're not allowed.
+    pub fn to_bytes(&self) -> Bytes {
+        let mut out = String::new();
+        if let Some(comment) = &self.comment {
+            for line in lines(comment) {
+                push_field(&mut out, "", line);
+            }
+        }
+        if let Some(event) = &self.event {
+            push_field(&mut out, "event", &single_line(event));
+        }
+        if let Some(id) = &self.id {
+            push_field(&mut out, "id", &single_line(id));
+        }
+        if let Some(retry) = self.retry {
+            push_field(&mut out, "retry", &retry.as_millis().to_string());
+        }
+        // A comment on its own carries no data, and a data field would make
+        // clients dispatch an empty event.
+        let comment_only = self.comment.is_some()
+            && self.data.is_empty()
+            && self.event.is_none()
+            && self.id.is_none()
+            && self.retry.is_none();
+        if !comment_only {
+            for line in lines(&self.data) {
+                push_field(&mut out, "data", line);
+            }
+        }
+        out.push('\n');
+        Bytes::from(out)
+    }
+}
+
+/// Appends `name: value\n` to `out`. An empty name produces a comment line.
+fn push_field(out: &mut String, name: &str, value: &str) {
+    out.push_str(name);
+    out.push(':');
+    if !value.is_empty() {
+        out.push(' ');
+        out.push_str(value);
+    }
+    out.push('\n');
+}
+
+/// Splits on every line break allowed by the format: `\r\n`, `\n` and `\r`.
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    text.split('\n')
+        .flat_map(|line| line.strip_suffix('\r').unwrap_or(line).split('\r'))
+}
+
+fn single_line(text: &str) -> String {
+    text.replace(['\r', '\n'], "")
+}
+
+/// The sending half of a channel-backed SSE response, created with
+/// [`HttpResponse::sse_channel`].
+///
+/// Events are sent to the client as they are produced. The response ends when the
+/// sender is dropped.
+#[derive(Clone, Debug)]
+pub struct SseSender {
+    sender: mpsc::Sender<SseEvent>,
+}
+
+impl SseSender {
+    /// Sends an event to the client.
+    ///
+    /// # Errors
+    ///
+    /// Returns the event back if the response was dropped, e.g. because the
+    /// client disconnected.
+    pub fn send(&self, event: SseEvent) -> Result<(), SseEvent> {
+        self.sender.send(event).map_err(|e| e.0)
+    }
+}
+
+impl HttpResponse {
+    /// Creates a Server-Sent Events response streaming the given events.
+    ///
+    /// Sets `Content-Type: text/event-stream` and `Cache-Control: no-cache`.
+    pub fn sse<I>(events: I) -> Self
+    where
+        I: IntoIterator<Item = SseEvent>,
+        I::IntoIter: Send + 'static,
+    {
+        let chunks = events.into_iter().map(|event| Ok(event.to_bytes()));
+        let mut response = HttpResponse::new(StatusCode::OK, Body::stream(chunks));
+        response.add_header(CONTENT_TYPE, "text/event-stream");
+        response.add_header(CACHE_CONTROL, "no-cache");
+        response
+    }
+
+    /// Creates a Server-Sent Events response fed through a channel.
+    ///
+    /// The handler returns the response and keeps the `SseSender`, typically by
+    /// moving it into a thread or task that produces the events.
+    pub fn sse_channel() -> (SseSender, Self) {
+        let (sender, receiver) = mpsc::channel();
+        (SseSender { sender }, Self::sse(receiver))
+    }
+}
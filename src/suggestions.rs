@@ -0,0 +1,144 @@
+//! Defines the suggestions of 404 Not Found responses, enabled with
+//! `Router::enable_404_suggestions`: the route patterns closest to a path
+//! without a route, e.g. `/users/:id/orders` for `/users/123/order`.
+
+use crate::accept::parse_accept;
+use crate::config_schema::edit_distance;
+use http::HeaderMap;
+use http::header::{ACCEPT, HeaderName};
+
+/// The most suggestions a response gets.
+const MAX_SUGGESTIONS: usize = 3;
+/// The most route patterns a path is compared with, bounding the work for
+/// large routing tables.
+const MAX_PATTERNS: usize = 10_000;
+/// The longest path, in bytes, suggestions are looked for.
+const MAX_PATH_LEN: usize = 256;
+
+/// Which requests without a route get suggestions, set with
+/// `Router::set_404_suggestion_trigger`. Looking for them compares the path
+/// with every route, so it's best left to requests from developers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SuggestionTrigger {
+    /// Every request.
+    Always,
+    /// Requests with the header, whatever its value.
+    Header(HeaderName),
+    /// Requests whose `Accept` header names a JSON media type, e.g.
+    /// `application/json` or `application/problem+json`, or with the header.
+    JsonOrHeader(HeaderName),
+}
+
+impl Default for SuggestionTrigger {
+    /// Requests accepting JSON or with an `X-Debug` header.
+    fn default() -> Self {
+        Self::JsonOrHeader(HeaderName::from_static("x-debug"))
+    }
+}
+
+impl SuggestionTrigger {
+    /// Returns `true` if a request with `headers` gets suggestions.
+    pub(crate) fn applies(&self, headers: &HeaderMap) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Header(name) => headers.contains_key(name),
+            Self::JsonOrHeader(name) => {
+                headers.contains_key(name)
+                    || parse_accept(headers.get_all(ACCEPT)).iter().any(|range| {
+                        range.quality > 0.0
+                            && range.media_type == "application"
+                            && (range.subtype == "json" || range.subtype.ends_with("+json"))
+                    })
+            }
+        }
+    }
+}
+
+/// Returns the patterns closest to `path`, at most three, sorted from the
+/// closest.
+///
+/// Paths are compared segment by segment: a parameter segment matches any
+/// segment, and a literal one costs its edit distance to the segment it's
+/// matched with, or its length if it's missing or extra. Patterns further
+/// than a third of their literal characters, or without any, aren't
+/// suggested.
+pub(crate) fn suggest<'a>(path: &str, patterns: impl Iterator<Item = &'a str>) -> Vec<String> {
+    if path.len() > MAX_PATH_LEN {
+        return Vec::new();
+    }
+    let path = segments(path);
+    let mut closest: Vec<(usize, &str)> = Vec::new();
+    for pattern in patterns.take(MAX_PATTERNS) {
+        let segments = segments(pattern);
+        let literal: usize = segments
+            .iter()
+            .filter(|segment| !is_param(segment))
+            .map(|segment| segment.chars().count())
+            .sum();
+        if literal == 0 {
+            continue;
+        }
+        let max = literal.div_ceil(3);
+        if path.len().abs_diff(segments.len()) > max {
+            continue;
+        }
+        let distance = distance(&path, &segments);
+        if distance <= max && !closest.iter().any(|(_, p)| *p == pattern) {
+            closest.push((distance, pattern));
+        }
+    }
+    closest.sort();
+    closest
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, pattern)| pattern.to_string())
+        .collect()
+}
+
+/// Returns the non-empty segments of a path.
+fn segments(path: &str) -> Vec<&str> {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+/// Returns `true` if a pattern segment is a parameter, e.g. `:id`.
+fn is_param(segment: &str) -> bool {
+    segment.starts_with([':', '*', '{'])
+}
+
+/// Returns the cost of a pattern segment missing from a path, or extra.
+fn cost(segment: &str) -> usize {
+    if is_param(segment) {
+        1
+    } else {
+        segment.chars().count()
+    }
+}
+
+/// Returns the segment-wise edit distance between a path and a pattern.
+fn distance(path: &[&str], pattern: &[&str]) -> usize {
+    let mut row: Vec<usize> = std::iter::once(0)
+        .chain(pattern.iter().scan(0, |sum, segment| {
+            *sum += cost(segment);
+            Some(*sum)
+        }))
+        .collect();
+    for segment in path {
+        let mut diagonal = row[0];
+        row[0] += segment.chars().count();
+        for (j, expected) in pattern.iter().enumerate() {
+            let substitution = diagonal
+                + if is_param(expected) {
+                    0
+                } else {
+                    edit_distance(&segment.to_lowercase(), &expected.to_lowercase())
+                };
+            diagonal = row[j + 1];
+            row[j + 1] = substitution
+                .min(row[j] + cost(expected))
+                .min(diagonal + segment.chars().count());
+        }
+    }
+    row[pattern.len()]
+}
@@ -0,0 +1,954 @@
+//! Defines the routing table: a single radix tree mapping each path pattern to
+//! the routes registered for it under every method.
+
+use crate::concurrency::ConcurrencyLimit;
+use crate::config::{Auth, Canary, CircuitBreaker, Endpoint, StaticFiles};
+use crate::error::{LoadWarning, RouterError};
+use crate::handler::Handler;
+use crate::params::Params;
+#[cfg(feature = "schema")]
+use crate::schema::Schema;
+use crate::signature::RouteSignature;
+use crate::template::BodyTemplate;
+use http::Method;
+use http::header::HeaderName;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// The longest a circuit breaker may stay open, in milliseconds: a year.
+const MAX_OPEN_FOR_MS: u64 = 365 * 24 * 60 * 60 * 1000;
+
+#[cfg(test)]
+thread_local! {
+    /// The searches of the tree on this thread, for the tests of how many a
+    /// lookup makes.
+    static TREE_SEARCHES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Counts a search of the tree.
+fn count_tree_search() {
+    #[cfg(test)]
+    TREE_SEARCHES.with(|searches| searches.set(searches.get() + 1));
+}
+
+/// A single route, as stored in the routing table.
+#[derive(Clone)]
+pub(crate) struct RouteEntry {
+    /// The endpoint the route was built from, as returned by
+    /// `Resolution::endpoint`.
+    pub(crate) endpoint: Arc<Endpoint>,
+    /// The controller name the route dispatches to, shared by the routes of
+    /// the same controller.
+    pub(crate) controller: Arc<str>,
+    /// The handler the controller's factory built for the route's
+    /// `controller_args`, by the controller name and the arguments, e.g.
+    /// `static_json {"file":"plans.json"}`. Routes with the same arguments
+    /// share it.
+    pub(crate) instance: Option<Arc<str>>,
+    /// The handler the router answers the route's requests with itself, from
+    /// its `body_template` or `static_files`, if any.
+    pub(crate) builtin: Option<Arc<dyn Handler + Send + Sync>>,
+    /// The path pattern exactly as written in the configuration.
+    #[allow(dead_code)] // Retained metadata, not used for dispatch.
+    pub(crate) pattern: Arc<str>,
+    /// The locale of the route's path, if it's localized.
+    pub(crate) locale: Option<String>,
+    /// The description from the configuration.
+    #[allow(dead_code)] // Retained metadata, not used for dispatch.
+    pub(crate) description: String,
+    /// The parameter names of `pattern`, in path order.
+    pub(crate) param_names: Vec<Box<str>>,
+    /// How long responses are cached for, if at all.
+    #[cfg_attr(not(feature = "cache"), allow(dead_code))]
+    pub(crate) cache_ttl: Option<Duration>,
+    /// The handler timeout, if the route has its own.
+    pub(crate) timeout: Option<Duration>,
+    /// The limit of requests running the handler at once, if any.
+    pub(crate) concurrency: Option<Arc<ConcurrencyLimit>>,
+    /// The largest request body of the route, if limited.
+    pub(crate) max_request_size: Option<usize>,
+    /// The largest response body of the route, if it has its own limit.
+    pub(crate) max_response_size: Option<usize>,
+    /// The circuit breaker of the route's controller, if any.
+    pub(crate) circuit_breaker: Option<CircuitBreaker>,
+    /// Whether the route stays available in maintenance mode.
+    pub(crate) maintenance_exempt: bool,
+    /// The controller that gets a copy of each request, if any.
+    pub(crate) mirror_to: Option<String>,
+    /// How traffic is split between two controllers, for canary routes. Their
+    /// `controller` is the stable one.
+    pub(crate) canary: Option<Canary>,
+    /// The names of the guards checked before the handler, in order.
+    pub(crate) guards: Vec<String>,
+    /// The headers requests must have, besides the global ones.
+    pub(crate) required_headers: Vec<HeaderName>,
+    /// Whether unsafe requests must carry the CSRF token.
+    #[cfg_attr(not(feature = "csrf"), allow(dead_code))]
+    pub(crate) csrf: bool,
+    /// Whether retried requests with an `Idempotency-Key` are deduplicated.
+    #[cfg_attr(not(feature = "idempotency"), allow(dead_code))]
+    pub(crate) idempotent: bool,
+    /// Whether identical concurrent `GET` requests share one handler run.
+    #[cfg_attr(not(feature = "async"), allow(dead_code))]
+    pub(crate) coalesce: bool,
+    /// The scopes requests must be authenticated with, if the route requires
+    /// authentication.
+    #[cfg_attr(not(feature = "jwt"), allow(dead_code))]
+    pub(crate) auth_scopes: Option<Vec<String>>,
+    /// The authentication the route requires, if any.
+    pub(crate) auth: Option<Auth>,
+    /// The signature requests must carry, if any.
+    pub(crate) signature: Option<RouteSignature>,
+    /// The schema request bodies must match, if any.
+    #[cfg(feature = "schema")]
+    pub(crate) schema: Option<Arc<Schema>>,
+    /// The schema JSON responses must match with response validation, if any.
+    #[cfg(feature = "schema")]
+    pub(crate) response_schema: Option<Arc<Schema>>,
+}
+
+/// The methods registered for a path, with the `Allow` header value precomputed.
+#[derive(Clone)]
+pub(crate) struct AllowedMethods {
+    /// The methods, sorted alphabetically and without duplicates, so that the
+    /// `Allow` header value doesn't depend on the registration order.
+    pub(crate) methods: Vec<Method>,
+    /// The methods joined into an `Allow` header value.
+    pub(crate) header: String,
+    /// The methods with an implicit `HEAD`, `OPTIONS` or both added, built
+    /// the first time a 405 Method Not Allowed needs them.
+    implicit: [OnceLock<Box<AllowedMethods>>; 3],
+}
+
+impl AllowedMethods {
+    pub(crate) fn new(mut methods: Vec<Method>) -> Self {
+        methods.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        methods.dedup();
+        let header = methods
+            .iter()
+            .map(|m| m.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        Self {
+            methods,
+            header,
+            implicit: Default::default(),
+        }
+    }
+
+    /// Returns the methods with `HEAD` added if `head` and there's a `GET`,
+    /// and `OPTIONS` added if `options`, for automatic `HEAD` and `OPTIONS`
+    /// responses.
+    pub(crate) fn with_implicit(&self, head: bool, options: bool) -> &AllowedMethods {
+        let head =
+            head && self.methods.contains(&Method::GET) && !self.methods.contains(&Method::HEAD);
+        let options = options && !self.methods.contains(&Method::OPTIONS);
+        let index = match (head, options) {
+            (false, false) => return self,
+            (true, false) => 0,
+            (false, true) => 1,
+            (true, true) => 2,
+        };
+        self.implicit[index].get_or_init(|| {
+            let mut methods = self.methods.clone();
+            if head {
+                methods.push(Method::HEAD);
+            }
+            if options {
+                methods.push(Method::OPTIONS);
+            }
+            Box::new(AllowedMethods::new(methods))
+        })
+    }
+}
+
+/// All routes sharing one path shape (the pattern with parameter names ignored).
+#[derive(Clone)]
+struct PathRoutes {
+    methods: HashMap<Method, RouteEntry>,
+    allowed: AllowedMethods,
+    /// Indices of other shapes that can match some of the same paths, most
+    /// specific first. Only these need checking when `methods` lacks a method.
+    overlaps: Vec<usize>,
+    /// A matcher for this shape alone, built when other shapes overlap it or
+    /// when it couldn't be inserted into the shared tree.
+    matcher: Option<matchit::Router<()>>,
+}
+
+/// The outcome of looking up a request in the `RouteTable`.
+pub(crate) enum Lookup<'t> {
+    /// A route matched. `params` holds the parameters under the names used in the
+    /// route's own pattern.
+    Found {
+        entry: &'t RouteEntry,
+        params: Params,
+    },
+    /// The path matched, but only for other methods.
+    MethodNotAllowed(Cow<'t, AllowedMethods>),
+    /// No route matched the path.
+    NotFound,
+}
+
+/// The compiled routing table.
+///
+/// Patterns are inserted into a single `matchit::Router` using canonical,
+/// position-based parameter names, so that the same path registered with
+/// different parameter names under different methods (e.g. `GET /users/:id` and
+/// `POST /users/:user_id`) resolves to one node, and a single lookup yields every
+/// method registered for a path.
+///
+/// The endpoints it was built from are retained, since `matchit` can't remove
+/// routes: changing the routes rebuilds the table from the updated list.
+#[derive(Clone)]
+pub(crate) struct RouteTable {
+    endpoints: Vec<Endpoint>,
+    /// Whether each of `endpoints` is routed, rather than shadowed.
+    accepted: Vec<bool>,
+    tree: matchit::Router<usize>,
+    nodes: Vec<PathRoutes>,
+    /// Shapes that conflict with another shape in the shared tree. This only happens
+    /// across methods (e.g. `GET /files/:id` and `POST /files/*path`), which per-method
+    /// matching allows, so they're matched on their own after a tree miss.
+    detached: Vec<usize>,
+}
+
+impl RouteTable {
+    /// Builds the routing table from the endpoints of a configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError` if a pattern is invalid or conflicts with another
+    /// pattern registered for the same method, unless priorities resolve the
+    /// conflict.
+    pub(crate) fn new(endpoints: Vec<Endpoint>) -> Result<Self, RouterError> {
+        Self::new_with_report(endpoints).map(|(table, _)| table)
+    }
+
+    /// Builds the routing table like `new`, also returning the routes left out
+    /// because a higher-priority route shadows them.
+    pub(crate) fn new_with_report(
+        endpoints: Vec<Endpoint>,
+    ) -> Result<(Self, Vec<LoadWarning>), RouterError> {
+        for endpoint in &endpoints {
+            validate(endpoint)?;
+        }
+        let (accepted, mut warnings) = resolve_conflicts(&endpoints)?;
+
+        let mut shapes: Vec<(String, Vec<Method>, HashMap<Method, RouteEntry>)> = Vec::new();
+        let mut shape_index = HashMap::<String, usize>::new();
+        let mut controllers = HashMap::<String, Arc<str>>::new();
+
+        for endpoint in endpoints
+            .iter()
+            .zip(&accepted)
+            .filter_map(|(e, &ok)| ok.then_some(e))
+        {
+            let Endpoint {
+                method,
+                path,
+                locale,
+                controller,
+                controller_args,
+                body_template,
+                content_type,
+                static_files,
+                description,
+                canary,
+                priority: _,
+                cache_ttl_seconds,
+                timeout_ms,
+                max_concurrency,
+                max_queue,
+                max_request_size,
+                max_response_size,
+                circuit_breaker,
+                maintenance_exempt,
+                mirror_to,
+                guards,
+                csrf,
+                idempotent,
+                coalesce,
+                auth_scopes,
+                auth,
+                signature,
+                request_schema,
+                response_schema,
+                tags: _,
+                required_headers,
+                metadata: _,
+            } = endpoint.clone();
+            let required_headers = required_headers
+                .iter()
+                .map(|name| {
+                    HeaderName::from_bytes(name.as_bytes()).map_err(|e| RouterError::Endpoint {
+                        method: method.clone(),
+                        path: path.clone(),
+                        reason: format!("invalid required header '{name}': {e}"),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let signature = signature
+                .as_ref()
+                .map(RouteSignature::new)
+                .transpose()
+                .map_err(|reason| RouterError::Endpoint {
+                    method: method.clone(),
+                    path: path.clone(),
+                    reason,
+                })?;
+            #[cfg(feature = "schema")]
+            let schema = compile_schema(&method, &path, "request_schema", request_schema)?;
+            #[cfg(feature = "schema")]
+            let response_schema =
+                compile_schema(&method, &path, "response_schema", response_schema)?;
+            #[cfg(not(feature = "schema"))]
+            let _ = (request_schema, response_schema);
+
+            let template = body_template
+                .map(|template| {
+                    BodyTemplate::compile(&template, content_type.as_deref())
+                        .map(|template| Arc::new(template) as Arc<dyn Handler + Send + Sync>)
+                        .map_err(|e| format!("invalid body_template: {e}"))
+                })
+                .transpose();
+            let builtin = match static_files {
+                Some(files) => static_dir(files, &path).map(Some),
+                None => template,
+            }
+            .map_err(|reason| RouterError::Endpoint {
+                method: method.clone(),
+                path: path.clone(),
+                reason,
+            })?;
+            let instance = controller_args.map(|args| Arc::from(format!("{controller} {args}")));
+            let (canonical, param_names) = canonicalize(&path);
+            let index = *shape_index.entry(canonical.clone()).or_insert_with(|| {
+                shapes.push((canonical, Vec::new(), HashMap::new()));
+                shapes.len() - 1
+            });
+            let (_, order, methods) = &mut shapes[index];
+            order.push(method.clone());
+            methods.insert(
+                method,
+                RouteEntry {
+                    endpoint: Arc::new(endpoint.clone()),
+                    controller: Arc::clone(
+                        controllers
+                            .entry(match &canary {
+                                Some(canary) => canary.stable.clone(),
+                                None => controller,
+                            })
+                            .or_insert_with_key(|name| Arc::from(name.as_str())),
+                    ),
+                    instance,
+                    builtin,
+                    pattern: Arc::from(path),
+                    locale,
+                    description,
+                    param_names,
+                    cache_ttl: cache_ttl_seconds.map(Duration::from_secs),
+                    timeout: timeout_ms.map(Duration::from_millis),
+                    concurrency: max_concurrency
+                        .map(|max| Arc::new(ConcurrencyLimit::new(max, max_queue.unwrap_or(0)))),
+                    max_request_size: max_request_size
+                        .map(|max| usize::try_from(max).unwrap_or(usize::MAX)),
+                    max_response_size: max_response_size
+                        .map(|max| usize::try_from(max).unwrap_or(usize::MAX)),
+                    circuit_breaker,
+                    maintenance_exempt,
+                    mirror_to,
+                    canary,
+                    guards,
+                    required_headers,
+                    csrf,
+                    idempotent,
+                    coalesce,
+                    auth_scopes,
+                    auth,
+                    signature,
+                    #[cfg(feature = "schema")]
+                    schema,
+                    #[cfg(feature = "schema")]
+                    response_schema,
+                },
+            );
+        }
+
+        // Each route keeps its own parameter names, which is worth a warning
+        // when they differ for the same path.
+        for (_, order, methods) in &shapes {
+            let routes: Vec<(Method, String)> = order
+                .iter()
+                .map(|method| (method.clone(), methods[method].pattern.to_string()))
+                .collect();
+            if routes.iter().any(|(_, pattern)| *pattern != routes[0].1) {
+                warnings.push(LoadWarning::ParamNamesDiffer { routes });
+            }
+        }
+
+        let mut tree = matchit::Router::new();
+        let mut detached = Vec::new();
+        for (index, (canonical, _, _)) in shapes.iter().enumerate() {
+            if tree.insert(canonical.clone(), index).is_err() {
+                detached.push(index);
+            }
+        }
+
+        let overlaps = find_overlaps(shapes.iter().map(|(canonical, _, _)| canonical.as_str()));
+
+        let mut needs_matcher = vec![false; shapes.len()];
+        for &index in overlaps.iter().flatten().chain(&detached) {
+            needs_matcher[index] = true;
+        }
+
+        let nodes = shapes
+            .into_iter()
+            .zip(overlaps)
+            .zip(needs_matcher)
+            .map(|(((canonical, order, methods), overlaps), needs_matcher)| {
+                let matcher = needs_matcher.then(|| {
+                    let mut matcher = matchit::Router::new();
+                    // A single pattern was already validated above.
+                    let _ = matcher.insert(canonical, ());
+                    matcher
+                });
+                PathRoutes {
+                    methods,
+                    allowed: AllowedMethods::new(order),
+                    overlaps,
+                    matcher,
+                }
+            })
+            .collect();
+
+        let table = Self {
+            endpoints,
+            accepted,
+            tree,
+            nodes,
+            detached,
+        };
+        Ok((table, warnings))
+    }
+
+    /// Returns a table with `endpoint` added.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError` if the endpoint's pattern is invalid or conflicts
+    /// with another pattern registered for the same method.
+    pub(crate) fn with_endpoint(&self, endpoint: Endpoint) -> Result<Self, RouterError> {
+        let mut endpoints = self.endpoints.clone();
+        endpoints.push(endpoint);
+        Self::new(endpoints)
+    }
+
+    /// Returns a table without the route registered for `method` with exactly the
+    /// pattern `path`, or `None` if there's no such route.
+    pub(crate) fn without_route(&self, method: &Method, path: &str) -> Option<Self> {
+        let index = self
+            .endpoints
+            .iter()
+            .position(|endpoint| endpoint.method == *method && endpoint.path == path)?;
+        let mut endpoints = self.endpoints.clone();
+        endpoints.remove(index);
+        // Removing a route can't introduce a conflict.
+        Self::new(endpoints).ok()
+    }
+
+    /// Returns the endpoints of the routes in the table, in the order they were
+    /// added, without those shadowed by a higher-priority route.
+    pub(crate) fn endpoints(&self) -> impl Iterator<Item = &Endpoint> {
+        self.endpoints
+            .iter()
+            .zip(&self.accepted)
+            .filter_map(|(endpoint, &accepted)| accepted.then_some(endpoint))
+    }
+
+    /// Builds the `Allow` values of every path with the implicit `HEAD` if
+    /// `head`, and `OPTIONS` if `options`, which are otherwise built by the
+    /// first 405 Method Not Allowed of each path.
+    pub(crate) fn warm_up_allowed(&self, head: bool, options: bool) {
+        for node in &self.nodes {
+            node.allowed.with_implicit(head, options);
+        }
+    }
+
+    /// Returns every route in the table, in no particular order.
+    pub(crate) fn routes(&self) -> impl Iterator<Item = (&Method, &RouteEntry)> {
+        self.nodes.iter().flat_map(|node| node.methods.iter())
+    }
+
+    /// Looks up the route for `method` and `path`.
+    pub(crate) fn lookup<'t>(&'t self, method: &Method, path: &str) -> Lookup<'t> {
+        self.lookup_or(method, None, path)
+    }
+
+    /// Looks up the route for `method` and `path` like `lookup`, or else the
+    /// route for `fallback`, if the path has one, e.g. the `GET` route of a
+    /// `HEAD` request. The tree is searched once either way.
+    pub(crate) fn lookup_or<'t>(
+        &'t self,
+        method: &Method,
+        fallback: Option<&Method>,
+        path: &str,
+    ) -> Lookup<'t> {
+        count_tree_search();
+        let matched = match self.tree.at(path) {
+            Ok(matched) => matched,
+            Err(_) => return self.lookup_detached(method, fallback, path),
+        };
+        let node = *matched.value;
+        if let Some(found) = self.find(node, method, &matched.params, path) {
+            return found;
+        }
+        let allowed = self.allowed_at(node, path);
+        if let Some(fallback) = fallback
+            && allowed
+                .as_ref()
+                .is_some_and(|allowed| allowed.methods.contains(fallback))
+            && let Some(found) = self.find(node, fallback, &matched.params, path)
+        {
+            return found;
+        }
+        match allowed {
+            Some(allowed) => Lookup::MethodNotAllowed(allowed),
+            None => Lookup::NotFound,
+        }
+    }
+
+    /// Finds the route for `method` of the shape at `node`, which matched
+    /// `path` with `params`, or of a shape overlapping it.
+    fn find<'t>(
+        &'t self,
+        node: usize,
+        method: &Method,
+        params: &matchit::Params<'_, '_>,
+        path: &str,
+    ) -> Option<Lookup<'t>> {
+        let routes = &self.nodes[node];
+
+        // Happy path: the matched node has a route for the method.
+        if let Some(entry) = routes.methods.get(method) {
+            let params = named_params(entry, params.iter().map(|(_, v)| v));
+            return Some(Lookup::Found { entry, params });
+        }
+
+        // A less specific shape may still match this path for the method.
+        routes
+            .overlaps
+            .iter()
+            .find_map(|&other| self.match_node(other, method, path))
+    }
+
+    /// Returns the methods that have a route matching `path`.
+    pub(crate) fn allowed(&self, path: &str) -> Option<Cow<'_, AllowedMethods>> {
+        count_tree_search();
+        match self.tree.at(path) {
+            Ok(matched) => self.allowed_at(*matched.value, path),
+            Err(_) => {
+                let methods: Vec<Method> = self
+                    .detached
+                    .iter()
+                    .filter(|&&index| self.node_matches(index, path))
+                    .flat_map(|&index| self.nodes[index].allowed.methods.iter().cloned())
+                    .collect();
+                (!methods.is_empty()).then(|| Cow::Owned(AllowedMethods::new(methods)))
+            }
+        }
+    }
+
+    /// Computes the allowed methods for `path`, given it matched `node` in the tree.
+    fn allowed_at(&self, node: usize, path: &str) -> Option<Cow<'_, AllowedMethods>> {
+        let routes = &self.nodes[node];
+        let mut extra = routes
+            .overlaps
+            .iter()
+            .filter(|&&other| self.node_matches(other, path))
+            .flat_map(|&other| self.nodes[other].allowed.methods.iter())
+            .filter(|m| !routes.methods.contains_key(*m))
+            .peekable();
+
+        // Without overlapping matches, the precomputed value is exact.
+        if extra.peek().is_none() {
+            return Some(Cow::Borrowed(&routes.allowed));
+        }
+
+        let mut methods = routes.allowed.methods.clone();
+        for method in extra {
+            if !methods.contains(method) {
+                methods.push(method.clone());
+            }
+        }
+        Some(Cow::Owned(AllowedMethods::new(methods)))
+    }
+
+    /// Matches `path` against the detached shapes, after a tree miss.
+    fn lookup_detached<'t>(
+        &'t self,
+        method: &Method,
+        fallback: Option<&Method>,
+        path: &str,
+    ) -> Lookup<'t> {
+        let find = |method| {
+            self.detached
+                .iter()
+                .find_map(|&index| self.match_node(index, method, path))
+        };
+        if let Some(found) = find(method) {
+            return found;
+        }
+        let allowed = self.allowed(path);
+        if let Some(fallback) = fallback
+            && allowed
+                .as_ref()
+                .is_some_and(|allowed| allowed.methods.contains(fallback))
+            && let Some(found) = find(fallback)
+        {
+            return found;
+        }
+        match allowed {
+            Some(allowed) => Lookup::MethodNotAllowed(allowed),
+            None => Lookup::NotFound,
+        }
+    }
+
+    /// Matches `path` against one shape's own matcher, for a specific method.
+    fn match_node<'t>(&'t self, index: usize, method: &Method, path: &str) -> Option<Lookup<'t>> {
+        let routes = &self.nodes[index];
+        let entry = routes.methods.get(method)?;
+        let matched = routes.matcher.as_ref()?.at(path).ok()?;
+        let params = named_params(entry, matched.params.iter().map(|(_, v)| v));
+        Some(Lookup::Found { entry, params })
+    }
+
+    fn node_matches(&self, index: usize, path: &str) -> bool {
+        self.nodes[index]
+            .matcher
+            .as_ref()
+            .is_some_and(|matcher| matcher.at(path).is_ok())
+    }
+}
+
+/// For each pattern, finds the indices of the other patterns that can match some
+/// of the same paths, ordered most specific first.
+fn find_overlaps<'a>(patterns: impl Iterator<Item = &'a str>) -> Vec<Vec<usize>> {
+    let segments: Vec<Vec<Segment<'_>>> = patterns.map(parse_segments).collect();
+    let mut overlaps: Vec<Vec<usize>> = vec![Vec::new(); segments.len()];
+    for a in 0..segments.len() {
+        for b in (a + 1)..segments.len() {
+            if segments_overlap(&segments[a], &segments[b]) {
+                overlaps[a].push(b);
+                overlaps[b].push(a);
+            }
+        }
+    }
+    for list in &mut overlaps {
+        list.sort_by_key(|&index| specificity(&segments[index]));
+    }
+    overlaps
+}
+
+/// Pairs the parameter names of the entry's own pattern with the matched values.
+fn named_params<'p>(entry: &RouteEntry, values: impl Iterator<Item = &'p str>) -> Params {
+    entry
+        .param_names
+        .iter()
+        .map(|name| &**name)
+        .zip(values)
+        .collect()
+}
+
+/// Compiles the `request_schema` or `response_schema` (the `field`) of an
+/// endpoint, which must have been resolved from a file path with
+/// `Config::resolve_schemas` already.
+#[cfg(feature = "schema")]
+fn compile_schema(
+    method: &Method,
+    path: &str,
+    field: &str,
+    schema: Option<serde_json::Value>,
+) -> Result<Option<Arc<Schema>>, RouterError> {
+    let invalid = |reason: String| RouterError::Endpoint {
+        method: method.clone(),
+        path: path.to_string(),
+        reason: format!("invalid {field}: {reason}"),
+    };
+    match schema {
+        None => Ok(None),
+        Some(serde_json::Value::String(file)) => Err(invalid(format!(
+            "the file '{file}' wasn't loaded with Config::resolve_schemas"
+        ))),
+        Some(schema) => Schema::compile(schema)
+            .map(Arc::new)
+            .map(Some)
+            .map_err(invalid),
+    }
+}
+
+/// Builds the handler of a `static_files` route with the path `path`.
+#[cfg(feature = "files")]
+fn static_dir(files: StaticFiles, path: &str) -> Result<Arc<dyn Handler + Send + Sync>, String> {
+    Ok(Arc::new(crate::file::StaticDir::new(files, path)?))
+}
+
+#[cfg(not(feature = "files"))]
+fn static_dir(_: StaticFiles, _: &str) -> Result<Arc<dyn Handler + Send + Sync>, String> {
+    Err("static_files needs the files feature".to_string())
+}
+
+/// Checks that an endpoint has exactly one of a `controller`, a valid `canary`,
+/// a `body_template` or `static_files`, a `max_concurrency` of at least 1 and a
+/// valid `circuit_breaker`, open for at most a year.
+fn validate(endpoint: &Endpoint) -> Result<(), RouterError> {
+    let invalid = |reason: &str| RouterError::Endpoint {
+        method: endpoint.method.clone(),
+        path: endpoint.path.clone(),
+        reason: reason.to_string(),
+    };
+    if endpoint.max_concurrency == Some(0) {
+        return Err(invalid("max_concurrency must be at least 1"));
+    }
+    if let Some(breaker) = &endpoint.circuit_breaker {
+        // Written so that NaN fails it too.
+        if !(breaker.failure_threshold > 0.0 && breaker.failure_threshold <= 1.0) {
+            return Err(invalid(
+                "the circuit breaker's failure_threshold must be above 0 and up to 1",
+            ));
+        }
+        if breaker.window_ms == Some(0) || breaker.half_open_requests == Some(0) {
+            return Err(invalid(
+                "the circuit breaker's window_ms and half_open_requests must be at least 1",
+            ));
+        }
+        if breaker.open_for_ms > MAX_OPEN_FOR_MS {
+            return Err(invalid(
+                "the circuit breaker's open_for_ms must be at most a year",
+            ));
+        }
+    }
+    if endpoint.coalesce && endpoint.method != Method::GET {
+        return Err(invalid("coalesce is only for GET routes"));
+    }
+    let builtin = match (&endpoint.body_template, &endpoint.static_files) {
+        (Some(_), Some(_)) => {
+            return Err(invalid("it has both a body_template and static_files"));
+        }
+        (Some(_), None) => Some("a body_template"),
+        (None, Some(_)) => Some("static_files"),
+        (None, None) => None,
+    };
+    let reason = match (&endpoint.canary, builtin) {
+        (None, Some(builtin)) if !endpoint.controller.is_empty() => {
+            format!("both a controller and {builtin}")
+        }
+        (None, None) if endpoint.controller.is_empty() => "no controller".to_string(),
+        (None, _) => return Ok(()),
+        (Some(_), Some(builtin)) => format!("both a canary and {builtin}"),
+        (Some(_), None) if !endpoint.controller.is_empty() => {
+            "both a controller and a canary".to_string()
+        }
+        (Some(canary), None) if canary.percent > 100 => "a canary percent over 100".to_string(),
+        (Some(_), None) => return Ok(()),
+    };
+    Err(RouterError::Endpoint {
+        method: endpoint.method.clone(),
+        path: endpoint.path.clone(),
+        reason: format!("it has {reason}"),
+    })
+}
+
+/// Validates the endpoints against one tree per method, since routes only conflict
+/// within the same method, and resolves conflicts by priority.
+///
+/// Endpoints are inserted from the highest priority down (those without one
+/// last, keeping the configuration order otherwise). When one conflicts, it's
+/// left out if every route it conflicts with has a higher priority; otherwise the
+/// conflict is an error.
+///
+/// Returns whether each endpoint is kept, and a warning for each one left out.
+fn resolve_conflicts(endpoints: &[Endpoint]) -> Result<(Vec<bool>, Vec<LoadWarning>), RouterError> {
+    let mut order: Vec<usize> = (0..endpoints.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(endpoints[i].priority.map_or(i64::MIN, i64::from)));
+
+    let mut method_trees = HashMap::<&Method, (matchit::Router<()>, Vec<usize>)>::new();
+    let mut accepted = vec![false; endpoints.len()];
+    let mut warnings = Vec::new();
+
+    for index in order {
+        let endpoint = &endpoints[index];
+        let (tree, inserted) = method_trees.entry(&endpoint.method).or_default();
+        let error = match tree.insert(endpoint.path.clone(), ()) {
+            Ok(()) => {
+                inserted.push(index);
+                accepted[index] = true;
+                continue;
+            }
+            Err(error) => error,
+        };
+
+        // Find the routes this one conflicts with, pair by pair.
+        let conflicting: Vec<&Endpoint> = inserted
+            .iter()
+            .map(|&other| &endpoints[other])
+            .filter(|other| {
+                let mut pair = matchit::Router::new();
+                // Inserted routes are valid on their own.
+                let _ = pair.insert(other.path.clone(), ());
+                pair.insert(endpoint.path.clone(), ()).is_err()
+            })
+            .collect();
+        let shadowed = !conflicting.is_empty()
+            && conflicting.iter().all(
+                |other| matches!((other.priority, endpoint.priority), (Some(a), Some(b)) if a > b),
+            );
+        if !shadowed {
+            return Err(RouterError::Route {
+                method: endpoint.method.clone(),
+                path: endpoint.path.clone(),
+                conflicts_with: conflicting.first().map(|other| other.path.clone()),
+                source: error,
+            });
+        }
+        warnings.push(LoadWarning::Shadowed {
+            method: endpoint.method.clone(),
+            path: endpoint.path.clone(),
+            by: conflicting[0].path.clone(),
+        });
+    }
+
+    Ok((accepted, warnings))
+}
+
+/// Rewrites a pattern with position-based parameter names (`:p1`, `*p3`, where the
+/// number is the segment index), returning it with the original parameter names.
+fn canonicalize(pattern: &str) -> (String, Vec<Box<str>>) {
+    let mut names = Vec::new();
+    let canonical = pattern
+        .split('/')
+        .enumerate()
+        .map(|(index, segment)| match segment.find([':', '*']) {
+            Some(start) => {
+                names.push(Box::from(&segment[start + 1..]));
+                format!("{}{}p{index}", &segment[..start], &segment[start..=start])
+            }
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    (canonical, names)
+}
+
+/// A segment of a pattern, for the overlap analysis.
+enum Segment<'a> {
+    Static(&'a str),
+    /// A named parameter, possibly preceded by a static prefix (`file.:ext`).
+    Param(&'a str),
+    /// A catch-all parameter, possibly preceded by a static prefix.
+    CatchAll,
+}
+
+fn parse_segments(pattern: &str) -> Vec<Segment<'_>> {
+    pattern
+        .split('/')
+        .map(|segment| match segment.find([':', '*']) {
+            Some(start) if segment.as_bytes()[start] == b'*' => Segment::CatchAll,
+            Some(start) => Segment::Param(&segment[..start]),
+            None => Segment::Static(segment),
+        })
+        .collect()
+}
+
+/// Returns `true` if some path could match both patterns. This errs on the side
+/// of reporting an overlap, which only costs an extra check on the miss path.
+fn segments_overlap(a: &[Segment<'_>], b: &[Segment<'_>]) -> bool {
+    for (sa, sb) in a.iter().zip(b) {
+        match (sa, sb) {
+            (Segment::CatchAll, _) | (_, Segment::CatchAll) => return true,
+            (Segment::Static(x), Segment::Static(y)) if x != y => return false,
+            (Segment::Static(s), Segment::Param(prefix))
+            | (Segment::Param(prefix), Segment::Static(s))
+                if s.len() <= prefix.len() || !s.starts_with(prefix) =>
+            {
+                return false;
+            }
+            _ => {}
+        }
+    }
+    a.len() == b.len()
+}
+
+/// Orders shapes the way the radix tree prefers them: static segments over
+/// parameters over catch-alls, from the first segment on.
+fn specificity(segments: &[Segment<'_>]) -> Vec<u8> {
+    segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Static(_) => 0,
+            Segment::Param(_) => 1,
+            Segment::CatchAll => 2,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> RouteTable {
+        RouteTable::new(vec![
+            Endpoint::new(Method::GET, "/users", "users::list"),
+            Endpoint::new(Method::GET, "/users/:id", "users::get"),
+            Endpoint::new(Method::PUT, "/users/:id", "users::update"),
+            Endpoint::new(Method::GET, "/users/me", "users::me"),
+            Endpoint::new(Method::GET, "/files/*path", "files::get"),
+        ])
+        .unwrap()
+    }
+
+    /// Returns the controller `lookup_or` finds, falling back to `GET`, and
+    /// the searches of the tree it made.
+    fn lookup(table: &RouteTable, method: &Method, path: &str) -> (Option<String>, usize) {
+        let before = TREE_SEARCHES.with(std::cell::Cell::get);
+        let controller = match table.lookup_or(method, Some(&Method::GET), path) {
+            Lookup::Found { entry, .. } => Some(entry.controller.to_string()),
+            _ => None,
+        };
+        (
+            controller,
+            TREE_SEARCHES.with(std::cell::Cell::get) - before,
+        )
+    }
+
+    #[test]
+    fn a_hit_searches_the_tree_once() {
+        let table = table();
+        for (method, path, controller) in [
+            (Method::GET, "/users", "users::list"),
+            (Method::GET, "/users/42", "users::get"),
+            (Method::PUT, "/users/42", "users::update"),
+            (Method::GET, "/users/me", "users::me"),
+            (Method::GET, "/files/a/b.txt", "files::get"),
+            // The fallback is found in the same search.
+            (Method::HEAD, "/users/42", "users::get"),
+        ] {
+            assert_eq!(
+                lookup(&table, &method, path),
+                (Some(controller.to_string()), 1),
+                "{method} {path}"
+            );
+        }
+    }
+
+    #[test]
+    fn misses_and_other_methods() {
+        let table = table();
+        assert_eq!(lookup(&table, &Method::GET, "/nope").0, None);
+        assert!(matches!(
+            table.lookup(&Method::DELETE, "/users/42"),
+            Lookup::MethodNotAllowed(_)
+        ));
+    }
+}
@@ -0,0 +1,209 @@
+//! Defines the body templates of endpoints answered by the router itself,
+//! e.g. `"body_template": "{\"id\": \"{params.id}\"}"`, and the escaping of
+//! values substituted into templates, shared with the error pages.
+
+use crate::accept::parse_media_type;
+use crate::handler::HttpHandler;
+use crate::request::HttpRequest;
+use crate::response::HttpResponse;
+use http::header::{CONTENT_TYPE, HeaderName};
+use http::{HeaderValue, StatusCode};
+
+/// The `Content-Type` of templated responses without a `content_type`.
+const DEFAULT_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+/// How values are escaped when substituted into a template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Escape {
+    Html,
+    Json,
+    None,
+}
+
+impl Escape {
+    /// Returns the escaping of values in a body of the media type `subtype`,
+    /// e.g. `html` or `problem+json`.
+    pub(crate) fn for_subtype(subtype: &str) -> Self {
+        if subtype == "html" || subtype == "xhtml+xml" {
+            Self::Html
+        } else if subtype == "json" || subtype.ends_with("+json") {
+            Self::Json
+        } else {
+            Self::None
+        }
+    }
+
+    /// Appends `value` to `out`, escaped.
+    pub(crate) fn push(self, out: &mut String, value: &str) {
+        for c in value.chars() {
+            match (self, c) {
+                (Self::Html, '&') => out.push_str("&amp;"),
+                (Self::Html, '<') => out.push_str("&lt;"),
+                (Self::Html, '>') => out.push_str("&gt;"),
+                (Self::Html, '"') => out.push_str("&quot;"),
+                (Self::Html, '\'') => out.push_str("&#39;"),
+                (Self::Json, '"') => out.push_str("\\\""),
+                (Self::Json, '\\') => out.push_str("\\\\"),
+                (Self::Json, c) if c < ' ' => out.push_str(&format!("\\u{:04x}", u32::from(c))),
+                (_, c) => out.push(c),
+            }
+        }
+    }
+}
+
+/// Where a placeholder's value comes from.
+#[derive(Debug)]
+enum Source {
+    Param(String),
+    Query(String),
+    Header(HeaderName),
+}
+
+/// A piece of a compiled template.
+#[derive(Debug)]
+enum Part {
+    Literal(String),
+    /// A placeholder, with the value used when the request has none.
+    Value {
+        source: Source,
+        default: Option<String>,
+    },
+}
+
+/// A compiled body template, answering the requests of its route: literal
+/// text and the placeholders `{params.name}`, `{query.name}` and
+/// `{headers.name}`, each with an optional default after a `|`, e.g.
+/// `{query.page|1}`. Values are escaped for the `Content-Type`: JSON string
+/// escaping for JSON types, HTML escaping for HTML, and none otherwise.
+///
+/// A `{` not followed by a name and a `.`, such as the braces of a JSON
+/// object, is literal text.
+#[derive(Debug)]
+pub(crate) struct BodyTemplate {
+    parts: Vec<Part>,
+    content_type: HeaderValue,
+    escape: Escape,
+}
+
+impl BodyTemplate {
+    /// Compiles `template`, for responses with the `content_type`, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns why the template is invalid: a placeholder without its closing
+    /// `}`, of an unknown namespace or naming an invalid header, or an invalid
+    /// content type.
+    pub(crate) fn compile(template: &str, content_type: Option<&str>) -> Result<Self, String> {
+        let content_type = content_type.unwrap_or(DEFAULT_CONTENT_TYPE);
+        let media_type = parse_media_type(content_type)
+            .ok_or_else(|| format!("invalid content_type '{content_type}'"))?;
+        let header = HeaderValue::from_str(content_type)
+            .map_err(|_| format!("invalid content_type '{content_type}'"))?;
+
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            literal.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let namespace_len = rest[1..]
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len() - 1);
+            if namespace_len == 0 || !rest[1 + namespace_len..].starts_with('.') {
+                literal.push('{');
+                rest = &rest[1..];
+                continue;
+            }
+            let end = rest
+                .find('}')
+                .ok_or_else(|| format!("placeholder '{rest}' has no closing '}}'"))?;
+            let placeholder = &rest[1..end];
+            rest = &rest[end + 1..];
+
+            let (name, default) = match placeholder.split_once('|') {
+                Some((name, default)) => (name, Some(default.to_string())),
+                None => (placeholder, None),
+            };
+            let (namespace, key) = name.split_at(namespace_len);
+            let key = &key[1..];
+            let source = match namespace {
+                "params" => Source::Param(key.to_string()),
+                "query" => Source::Query(key.to_string()),
+                "headers" => Source::Header(
+                    HeaderName::from_bytes(key.as_bytes())
+                        .map_err(|_| format!("invalid header name in placeholder '{{{name}}}'"))?,
+                ),
+                _ => {
+                    return Err(format!(
+                        "unknown placeholder '{{{name}}}': use params, query or headers"
+                    ));
+                }
+            };
+            if !literal.is_empty() {
+                parts.push(Part::Literal(std::mem::take(&mut literal)));
+            }
+            parts.push(Part::Value { source, default });
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Ok(Self {
+            parts,
+            content_type: header,
+            escape: Escape::for_subtype(&media_type.subtype),
+        })
+    }
+
+    /// Returns the names of the path parameters the template uses, e.g. `id`
+    /// for `{params.id}`, with whether the placeholder has a default.
+    pub(crate) fn params(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Value {
+                source: Source::Param(name),
+                default,
+            } => Some((name.as_str(), default.is_some())),
+            _ => None,
+        })
+    }
+
+    /// Renders the template for `req`. A placeholder without a value or a
+    /// default renders as nothing.
+    fn render(&self, req: &HttpRequest) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => out.push_str(literal),
+                Part::Value { source, default } => {
+                    let value = match source {
+                        Source::Param(name) => req.params.get(name),
+                        Source::Query(name) => req.query_pairs().and_then(|pairs| {
+                            pairs
+                                .iter()
+                                .find(|(key, _)| key == name)
+                                .map(|(_, value)| value.as_str())
+                        }),
+                        Source::Header(name) => {
+                            req.headers.get(name).and_then(|value| value.to_str().ok())
+                        }
+                    };
+                    if let Some(value) = value.or(default.as_deref()) {
+                        self.escape.push(&mut out, value);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+impl HttpHandler for BodyTemplate {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let mut response = HttpResponse::new(StatusCode::OK, self.render(&req));
+        response
+            .headers
+            .insert(CONTENT_TYPE, self.content_type.clone());
+        response
+    }
+}
@@ -0,0 +1,179 @@
+//! Defines `MultiTenantRouter`, which routes each tenant's requests with its
+//! own router, falling back to a default router shared by every tenant.
+
+use crate::Router;
+use crate::shared::SharedRouter;
+use arc_swap::ArcSwap;
+use bytes::Bytes;
+use http::header::HeaderName;
+use http::{Request, Response, StatusCode};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Tells the tenant a request is for, set with
+/// `MultiTenantRouter::set_tenant_extractor`.
+///
+/// Any `Fn(&Request<Bytes>) -> Option<String>` is an extractor.
+pub trait TenantExtractor {
+    /// Returns the ID of the request's tenant, or `None` if it has none.
+    fn tenant(&self, req: &Request<Bytes>) -> Option<String>;
+}
+
+impl<F: Fn(&Request<Bytes>) -> Option<String>> TenantExtractor for F {
+    fn tenant(&self, req: &Request<Bytes>) -> Option<String> {
+        self(req)
+    }
+}
+
+/// Takes the tenant from the first segment of the path, e.g. `acme` for
+/// `/acme/users/1`. The path is routed unchanged, so routes include the
+/// segment, e.g. `/:tenant/users/:id` in the default router. Used by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PathSegmentTenant;
+
+impl TenantExtractor for PathSegmentTenant {
+    fn tenant(&self, req: &Request<Bytes>) -> Option<String> {
+        let segment = req.uri().path().trim_start_matches('/').split('/').next()?;
+        (!segment.is_empty()).then(|| segment.to_string())
+    }
+}
+
+/// Takes the tenant from a request header, e.g. `X-Tenant-Id`.
+#[derive(Debug, Clone)]
+pub struct HeaderTenant(pub HeaderName);
+
+impl Default for HeaderTenant {
+    fn default() -> Self {
+        Self(HeaderName::from_static("x-tenant-id"))
+    }
+}
+
+impl TenantExtractor for HeaderTenant {
+    fn tenant(&self, req: &Request<Bytes>) -> Option<String> {
+        let value = req.headers().get(&self.0)?.to_str().ok()?.trim();
+        (!value.is_empty()).then(|| value.to_string())
+    }
+}
+
+/// The tenant of a request routed by a `MultiTenantRouter`, in its extensions,
+/// whether or not it has a router of its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tenant(pub String);
+
+/// Routes requests with the router of their tenant, told by a
+/// `TenantExtractor`, or with the default router.
+///
+/// A request whose tenant has no route for it, i.e. would get a 404 Not Found
+/// or a 405 Method Not Allowed from the tenant's router, goes to the default
+/// router instead, so that a tenant's router only needs the routes it adds or
+/// overrides. This can be turned off with `set_fallback_to_default`.
+///
+/// Every router is a `SharedRouter`, and tenants can be added and removed
+/// while requests are routed.
+pub struct MultiTenantRouter {
+    default: SharedRouter,
+    tenants: ArcSwap<HashMap<String, Arc<SharedRouter>>>,
+    /// Serializes writers, so concurrent changes aren't lost.
+    write: Mutex<()>,
+    extractor: Box<dyn TenantExtractor + Send + Sync>,
+    fallback: bool,
+}
+
+impl MultiTenantRouter {
+    /// Creates a multi-tenant router without tenants, routing every request
+    /// with `default`.
+    pub fn new(default: Router) -> Self {
+        Self {
+            default: SharedRouter::new(default),
+            tenants: ArcSwap::default(),
+            write: Mutex::new(()),
+            extractor: Box::new(PathSegmentTenant),
+            fallback: true,
+        }
+    }
+
+    /// Sets how the tenant of a request is told, [`PathSegmentTenant`] by
+    /// default.
+    pub fn set_tenant_extractor<E>(&mut self, extractor: E)
+    where
+        E: TenantExtractor + Send + Sync + 'static,
+    {
+        self.extractor = Box::new(extractor);
+    }
+
+    /// Sets whether requests without a route in their tenant's router go to
+    /// the default router (`true` by default). When `false`, they get the
+    /// tenant router's 404 Not Found or 405 Method Not Allowed.
+    pub fn set_fallback_to_default(&mut self, fallback: bool) {
+        self.fallback = fallback;
+    }
+
+    /// Returns the default router, e.g. to change its routes.
+    pub fn default_router(&self) -> &SharedRouter {
+        &self.default
+    }
+
+    /// Returns the router of `tenant`, e.g. to change its routes, or `None` if
+    /// it has none.
+    pub fn tenant(&self, tenant: &str) -> Option<Arc<SharedRouter>> {
+        self.tenants.load().get(tenant).cloned()
+    }
+
+    /// Returns the tenants with a router, sorted.
+    pub fn tenants(&self) -> Vec<String> {
+        let mut tenants: Vec<String> = self.tenants.load().keys().cloned().collect();
+        tenants.sort();
+        tenants
+    }
+
+    /// Routes the requests of `tenant` with `router` from now on, replacing its
+    /// previous router, if any.
+    pub fn add_tenant<S: Into<String>>(&self, tenant: S, router: Router) {
+        let router = Arc::new(SharedRouter::new(router));
+        self.change(|tenants| {
+            tenants.insert(tenant.into(), router);
+        });
+    }
+
+    /// Routes the requests of `tenant` with the default router from now on.
+    /// Returns `false` if it had no router.
+    pub fn remove_tenant(&self, tenant: &str) -> bool {
+        self.change(|tenants| tenants.remove(tenant).is_some())
+    }
+
+    /// Routes a request with its tenant's router or the default one; see
+    /// `Router::route`.
+    pub fn route<B: Into<Bytes>>(&self, req: Request<B>) -> Response<Bytes> {
+        let mut req = req.map(Into::into);
+        let Some(tenant) = self.extractor.tenant(&req) else {
+            return self.default.route(req);
+        };
+        let router = self.tenants.load().get(&tenant).cloned();
+        req.extensions_mut().insert(Tenant(tenant));
+        let Some(router) = router else {
+            return self.default.route(req);
+        };
+
+        let router = router.load();
+        let resolution = router.resolve(&req);
+        if self.fallback
+            && matches!(
+                resolution.status(),
+                Some(StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED)
+            )
+        {
+            drop(resolution);
+            return self.default.route(req);
+        }
+        router.dispatch(resolution, req)
+    }
+
+    /// Applies `change` to a copy of the tenants and makes the copy current.
+    fn change<R>(&self, change: impl FnOnce(&mut HashMap<String, Arc<SharedRouter>>) -> R) -> R {
+        let _guard = self.write.lock().unwrap_or_else(|e| e.into_inner());
+        let mut tenants = HashMap::clone(&self.tenants.load());
+        let result = change(&mut tenants);
+        self.tenants.store(Arc::new(tenants));
+        result
+    }
+}
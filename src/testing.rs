@@ -0,0 +1,797 @@
+//! Defines a `Clock` and an `Rng` for tests that depend on time or
+//! randomness, e.g. the expiry of cached responses or canary splits,
+//! `render_response` for comparing whole responses, e.g. with snapshots,
+//! request fixtures read from files with [`load_fixture`] and checked with
+//! [`assert_response`], and, with the `record` feature, the replay of
+//! recorded traffic.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use generic_http_router::Router;
+//! use generic_http_router::testing::{MockClock, SeededRng};
+//! use std::time::Duration;
+//!
+//! let clock = MockClock::new();
+//! let router = Router::new("routes.json")
+//!     .unwrap()
+//!     .with_clock(clock.clone())
+//!     .with_rng(SeededRng::new(42));
+//!
+//! // ... route a request to a cached route ...
+//! clock.advance(Duration::from_secs(60));
+//! // ... the cached response has expired ...
+//! ```
+
+use crate::Router;
+use crate::clock::Clock;
+use crate::error::RouterError;
+#[cfg(feature = "record")]
+use crate::record::{REDACTED, RecordedBody, Recording};
+use crate::request::HttpRequest;
+use crate::rng::Rng;
+#[cfg(feature = "schema")]
+use crate::schema::Schema;
+use bytes::Bytes;
+#[cfg(feature = "record")]
+use http::header::DATE;
+use http::header::{HeaderName, HeaderValue};
+use http::{HeaderMap, Method, Request, Response, StatusCode, Uri};
+#[cfg(feature = "schema")]
+use regex::Regex;
+use serde_json::Value;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime};
+
+/// A clock standing still until advanced with `advance`. Its copies share the
+/// time, so a test keeps one to advance the router's.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    start: Instant,
+    system_start: SystemTime,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl MockClock {
+    /// Creates a clock stopped at the current time.
+    pub fn new() -> Self {
+        Self::at(SystemTime::now())
+    }
+
+    /// Creates a clock stopped at the wall-clock time `time`, e.g. to check
+    /// JWTs with fixed timestamps.
+    pub fn at(time: SystemTime) -> Self {
+        Self {
+            start: Instant::now(),
+            system_start: time,
+            elapsed: Arc::default(),
+        }
+    }
+
+    /// Moves the clock, and its copies, forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        *self.lock() += duration;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Duration> {
+        self.elapsed.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + *self.lock()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.system_start + *self.lock()
+    }
+}
+
+/// A random number generator returning the same sequence for the same seed
+/// (SplitMix64). It isn't suitable for anything needing unpredictable numbers.
+#[derive(Debug)]
+pub struct SeededRng {
+    state: AtomicU64,
+}
+
+impl SeededRng {
+    /// Creates a generator from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: AtomicU64::new(seed),
+        }
+    }
+}
+
+impl Rng for SeededRng {
+    fn next_u64(&self) -> u64 {
+        const GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut z = self
+            .state
+            .fetch_add(GAMMA, Ordering::Relaxed)
+            .wrapping_add(GAMMA);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Renders a response as text that doesn't depend on the order its headers
+/// were added in: the status line, the headers sorted by name, each on its
+/// own line with the values of repeated ones in order, a blank line and the
+/// body, with invalid UTF-8 replaced. E.g.
+///
+/// ```text
+/// 405 Method Not Allowed
+/// allow: GET, HEAD, POST
+///
+/// ```
+///
+/// Headers whose values change between runs, such as `Date`, need removing
+/// first.
+pub fn render_response(response: &Response<Bytes>) -> String {
+    let status = response.status();
+    let mut rendered = format!(
+        "{} {}\n",
+        status.as_str(),
+        status.canonical_reason().unwrap_or_default()
+    );
+    let mut names: Vec<&HeaderName> = response.headers().keys().collect();
+    names.sort_unstable_by(|a, b| a.as_str().cmp(b.as_str()));
+    for name in names {
+        for value in response.headers().get_all(name) {
+            rendered.push_str(name.as_str());
+            rendered.push_str(": ");
+            rendered.push_str(&String::from_utf8_lossy(value.as_bytes()));
+            rendered.push('\n');
+        }
+    }
+    rendered.push('\n');
+    rendered.push_str(&String::from_utf8_lossy(response.body()));
+    rendered
+}
+
+/// What [`replay_with`] compares besides the status of the responses.
+#[cfg(feature = "record")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayOptions {
+    /// Whether the response headers are compared.
+    pub compare_headers: bool,
+    /// The headers left out of the comparison, e.g. those holding the time.
+    pub ignored_headers: Vec<HeaderName>,
+    /// Whether the response bodies are compared.
+    pub compare_body: bool,
+}
+
+#[cfg(feature = "record")]
+impl Default for ReplayOptions {
+    /// Compares the bodies but not the headers, ignoring `Date` and
+    /// `X-Response-Time-Ms` if headers are compared.
+    fn default() -> Self {
+        Self {
+            compare_headers: false,
+            ignored_headers: vec![DATE, HeaderName::from_static("x-response-time-ms")],
+            compare_body: true,
+        }
+    }
+}
+
+/// How a replayed response differs from the recorded one.
+#[cfg(feature = "record")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    Status {
+        expected: u16,
+        actual: u16,
+    },
+    /// The values of a header, in order, differ.
+    Header {
+        name: String,
+        expected: Vec<String>,
+        actual: Vec<String>,
+    },
+    Body {
+        expected: RecordedBody,
+        actual: RecordedBody,
+    },
+}
+
+/// A recorded request whose replayed response differs.
+#[cfg(feature = "record")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayFailure {
+    /// The line of the recording in its file, from 1.
+    pub line: usize,
+    pub method: String,
+    pub uri: String,
+    pub differences: Vec<Difference>,
+}
+
+/// The outcome of [`replay`].
+#[cfg(feature = "record")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// The number of requests replayed.
+    pub replayed: usize,
+    pub failures: Vec<ReplayFailure>,
+}
+
+#[cfg(feature = "record")]
+impl ReplayReport {
+    /// Returns `true` if every replayed response matched its recording.
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Returns the number of replayed responses matching their recording.
+    pub fn passed(&self) -> usize {
+        self.replayed - self.failures.len()
+    }
+}
+
+/// Routes each request recorded in the NDJSON file at `recording_path`, e.g.
+/// written by a [`JsonFileSink`](crate::record::JsonFileSink), with
+/// `router`, and compares the responses with the recorded ones as
+/// [`ReplayOptions::default`] does.
+///
+/// # Errors
+///
+/// Returns a `RouterError` if the file can't be read, or a recording in it
+/// is malformed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use generic_http_router::Router;
+/// use generic_http_router::testing::replay;
+///
+/// let router = Router::new("routes.json").unwrap();
+/// let report = replay(&router, "recording.ndjson").unwrap();
+/// assert!(report.is_success(), "{:#?}", report.failures);
+/// ```
+#[cfg(feature = "record")]
+pub fn replay<P: AsRef<Path>>(
+    router: &Router,
+    recording_path: P,
+) -> Result<ReplayReport, RouterError> {
+    replay_with(router, recording_path, &ReplayOptions::default())
+}
+
+/// Replays the recording at `recording_path` like [`replay`], comparing what
+/// `options` says.
+///
+/// Requests are routed with `Router::route`. Redacted request headers are
+/// left out, so routes requiring credentials answer as they would without
+/// them, and redacted response headers aren't compared.
+///
+/// # Errors
+///
+/// Returns a `RouterError` as `replay` does.
+#[cfg(feature = "record")]
+pub fn replay_with<P: AsRef<Path>>(
+    router: &Router,
+    recording_path: P,
+    options: &ReplayOptions,
+) -> Result<ReplayReport, RouterError> {
+    let recording_path = recording_path.as_ref();
+    let recordings = std::fs::read_to_string(recording_path)
+        .map_err(|e| RouterError::from(e).in_file(recording_path))?;
+    let mut report = ReplayReport::default();
+    for (index, line) in recordings.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let recording: Recording =
+            serde_json::from_str(line).map_err(|e| RouterError::from(e).in_file(recording_path))?;
+        let mut request = http::Request::builder()
+            .method(recording.method.as_str())
+            .uri(recording.uri.as_str());
+        for (name, value) in &recording.request_headers {
+            if value != REDACTED {
+                request = request.header(name.as_str(), value.as_str());
+            }
+        }
+        let body = recording.request_body.to_bytes().unwrap_or_default();
+        let response = router.route(request.body(body)?);
+        report.replayed += 1;
+
+        let mut differences = Vec::new();
+        if response.status().as_u16() != recording.status {
+            differences.push(Difference::Status {
+                expected: recording.status,
+                actual: response.status().as_u16(),
+            });
+        }
+        if options.compare_headers {
+            let mut names: Vec<&str> = recording
+                .response_headers
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .chain(response.headers().keys().map(HeaderName::as_str))
+                .collect();
+            names.sort_unstable();
+            names.dedup();
+            for name in names {
+                if options.ignored_headers.iter().any(|h| h.as_str() == name) {
+                    continue;
+                }
+                let expected: Vec<String> = recording
+                    .response_headers
+                    .iter()
+                    .filter(|(n, _)| n == name)
+                    .map(|(_, value)| value.clone())
+                    .collect();
+                if expected.iter().any(|value| value == REDACTED) {
+                    continue;
+                }
+                let actual: Vec<String> = response
+                    .headers()
+                    .get_all(name)
+                    .iter()
+                    .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+                    .collect();
+                if expected != actual {
+                    differences.push(Difference::Header {
+                        name: name.to_string(),
+                        expected,
+                        actual,
+                    });
+                }
+            }
+        }
+        let actual = RecordedBody::new(response.body());
+        if options.compare_body && actual != recording.response_body {
+            differences.push(Difference::Body {
+                expected: recording.response_body,
+                actual,
+            });
+        }
+        if !differences.is_empty() {
+            report.failures.push(ReplayFailure {
+                line: index + 1,
+                method: recording.method,
+                uri: recording.uri,
+                differences,
+            });
+        }
+    }
+    Ok(report)
+}
+
+/// A request read from a fixture file with [`load_fixture`], in the format of
+/// `.http` files:
+///
+/// ```text
+/// # Comments start with `#` or `//`, before the request line.
+/// POST /users HTTP/1.1
+/// Content-Type: application/json
+/// X-Request-Id: fixture-1
+///
+/// {"name": "Ada"}
+/// ```
+///
+/// The request line has the method and the path, optionally followed by the
+/// HTTP version, which is ignored. The body is everything after the blank
+/// line ending the headers, without its trailing line breaks. A `# @body
+/// base64` comment before the request line marks the body as base64, e.g. for
+/// binary uploads, decoded ignoring whitespace. Lines may end with CRLF.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fixture {
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl Fixture {
+    /// Parses a fixture; see [`Fixture`] for the format.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RouterError::Fixture` for a missing or invalid request line,
+    /// header or base64 body.
+    pub fn parse(text: &str) -> Result<Self, RouterError> {
+        let invalid = |line: usize, reason: String| RouterError::Fixture {
+            path: None,
+            line,
+            reason,
+        };
+        let mut lines = text.split('\n').enumerate().map(|(index, line)| {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            (index + 1, line)
+        });
+
+        let mut base64 = false;
+        let (number, request_line) = loop {
+            let Some((number, line)) = lines.next() else {
+                return Err(invalid(1, "no request line".to_string()));
+            };
+            let trimmed = line.trim();
+            if let Some(comment) = trimmed
+                .strip_prefix('#')
+                .or_else(|| trimmed.strip_prefix("//"))
+            {
+                match comment.trim() {
+                    "@body base64" => base64 = true,
+                    directive if directive.starts_with('@') => {
+                        return Err(invalid(number, format!("unknown directive '{directive}'")));
+                    }
+                    _ => {}
+                }
+            } else if !trimmed.is_empty() {
+                break (number, trimmed);
+            }
+        };
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            return Err(invalid(
+                number,
+                format!("'{request_line}' isn't a method followed by a path"),
+            ));
+        };
+        if parts
+            .next()
+            .is_some_and(|version| !version.starts_with("HTTP/"))
+            || parts.next().is_some()
+        {
+            return Err(invalid(
+                number,
+                format!("'{request_line}' has more than a method, a path and a version"),
+            ));
+        }
+        let method = Method::from_bytes(method.as_bytes())
+            .map_err(|_| invalid(number, format!("invalid method '{method}'")))?;
+        let uri: Uri = target
+            .parse()
+            .map_err(|_| invalid(number, format!("invalid path '{target}'")))?;
+
+        let mut headers = HeaderMap::new();
+        let mut body_start = None;
+        for (number, line) in lines.by_ref() {
+            if line.trim().is_empty() {
+                body_start = Some(number);
+                break;
+            }
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| invalid(number, format!("'{line}' isn't a header")))?;
+            let name = HeaderName::from_bytes(name.trim().as_bytes())
+                .map_err(|_| invalid(number, format!("invalid header name '{}'", name.trim())))?;
+            let value = HeaderValue::from_str(value.trim())
+                .map_err(|_| invalid(number, format!("invalid value for the header '{name}'")))?;
+            headers.append(name, value);
+        }
+
+        // The body is the rest of the text, as written, after the blank line.
+        let body = match body_start {
+            Some(blank) => text
+                .split_inclusive('\n')
+                .skip(blank)
+                .collect::<String>()
+                .trim_end_matches(['\r', '\n'])
+                .to_string(),
+            None => String::new(),
+        };
+        let body = if base64 {
+            let encoded: String = body.split_whitespace().collect();
+            crate::batch::base64_decode(&encoded)
+                .map(Bytes::from)
+                .ok_or_else(|| {
+                    invalid(
+                        body_start.map_or(number, |line| line + 1),
+                        "the body isn't valid base64".to_string(),
+                    )
+                })?
+        } else {
+            Bytes::from(body)
+        };
+
+        Ok(Self {
+            method,
+            uri,
+            headers,
+            body,
+        })
+    }
+
+    /// Returns the fixture as a request to route, e.g. with `Router::route`.
+    pub fn request(&self) -> Request<Bytes> {
+        let mut request = Request::new(self.body.clone());
+        *request.method_mut() = self.method.clone();
+        *request.uri_mut() = self.uri.clone();
+        *request.headers_mut() = self.headers.clone();
+        request
+    }
+
+    /// Returns the fixture as the request of a handler, without path
+    /// parameters, e.g. to call an `HttpHandler` directly.
+    pub fn http_request(&self) -> HttpRequest {
+        HttpRequest::from(self.request())
+    }
+}
+
+/// Reads the request fixture at `path`; see [`Fixture`] for the format.
+///
+/// # Errors
+///
+/// Returns a `RouterError` if the file can't be read, or a
+/// `RouterError::Fixture` if it's malformed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use generic_http_router::Router;
+/// use generic_http_router::testing::{Expectations, assert_response, load_fixture};
+/// use http::StatusCode;
+///
+/// let router = Router::new("routes.json").unwrap();
+/// let fixture = load_fixture("tests/fixtures/create_user.http").unwrap();
+/// assert_response(
+///     &router,
+///     &fixture,
+///     &Expectations::status(StatusCode::CREATED)
+///         .with_header("content-type", "application/json")
+///         .with_json_subset(serde_json::json!({ "id": "*", "name": "Ada" })),
+/// );
+/// ```
+pub fn load_fixture<P: AsRef<Path>>(path: P) -> Result<Fixture, RouterError> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path).map_err(|e| RouterError::from(e).in_file(path))?;
+    Fixture::parse(&text).map_err(|e| e.in_file(path))
+}
+
+/// What a response is expected to have, checked by [`assert_response`] and
+/// [`check_response`].
+#[derive(Debug, Clone, Default)]
+pub struct Expectations {
+    /// The expected status, if checked.
+    pub status: Option<StatusCode>,
+    /// The expected headers, in order.
+    pub headers: Vec<(HeaderName, HeaderExpectation)>,
+    /// The expected body, if checked.
+    pub body: Option<BodyExpectation>,
+}
+
+/// What a response header is expected to be.
+#[derive(Debug, Clone)]
+pub enum HeaderExpectation {
+    /// The response has the header, whatever its value.
+    Present,
+    /// The response doesn't have the header.
+    Absent,
+    /// One of the header's values is exactly this one.
+    Equals(String),
+    /// One of the header's values matches the regular expression.
+    #[cfg(feature = "schema")]
+    Matches(Regex),
+}
+
+/// What a response body is expected to be.
+#[derive(Debug, Clone)]
+pub enum BodyExpectation {
+    /// The body is exactly these bytes.
+    Exact(Bytes),
+    /// The body is JSON containing this value, where objects may have more
+    /// properties than expected and the string `"*"` matches any value, e.g.
+    /// `{"id": "*", "name": "Ada"}` for a generated `id`. Arrays must have the
+    /// expected length.
+    JsonSubset(Value),
+    /// The body is JSON valid against the schema.
+    #[cfg(feature = "schema")]
+    Schema(Schema),
+}
+
+impl Expectations {
+    /// Expects the response to have `status`.
+    pub fn status(status: StatusCode) -> Self {
+        Self {
+            status: Some(status),
+            ..Self::default()
+        }
+    }
+
+    /// Expects the header `name` to have the value `value`, among others.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` isn't a valid header name.
+    pub fn with_header(self, name: &str, value: &str) -> Self {
+        self.with(name, HeaderExpectation::Equals(value.to_string()))
+    }
+
+    /// Expects the response to have the header `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` isn't a valid header name.
+    pub fn with_header_present(self, name: &str) -> Self {
+        self.with(name, HeaderExpectation::Present)
+    }
+
+    /// Expects the response not to have the header `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` isn't a valid header name.
+    pub fn without_header(self, name: &str) -> Self {
+        self.with(name, HeaderExpectation::Absent)
+    }
+
+    /// Expects a value of the header `name` to match the regular expression
+    /// `pattern`, e.g. `^max-age=\d+$`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` isn't a valid header name, or `pattern` a valid
+    /// regular expression.
+    #[cfg(feature = "schema")]
+    pub fn with_header_matching(self, name: &str, pattern: &str) -> Self {
+        let regex = Regex::new(pattern).expect("invalid header value pattern");
+        self.with(name, HeaderExpectation::Matches(regex))
+    }
+
+    /// Expects the body to be exactly `body`.
+    pub fn with_body<B: Into<Bytes>>(mut self, body: B) -> Self {
+        self.body = Some(BodyExpectation::Exact(body.into()));
+        self
+    }
+
+    /// Expects the body to be JSON containing `subset`; see
+    /// [`BodyExpectation::JsonSubset`].
+    pub fn with_json_subset(mut self, subset: Value) -> Self {
+        self.body = Some(BodyExpectation::JsonSubset(subset));
+        self
+    }
+
+    /// Expects the body to be JSON valid against `schema`.
+    #[cfg(feature = "schema")]
+    pub fn with_schema(mut self, schema: Schema) -> Self {
+        self.body = Some(BodyExpectation::Schema(schema));
+        self
+    }
+
+    fn with(mut self, name: &str, expectation: HeaderExpectation) -> Self {
+        let name = HeaderName::from_bytes(name.as_bytes()).expect("invalid header name");
+        self.headers.push((name, expectation));
+        self
+    }
+}
+
+/// Returns how `response` falls short of `expectations`, one message per
+/// unmet expectation, e.g. `/id: expected 1, got 2` for a JSON body.
+pub fn check_response(response: &Response<Bytes>, expectations: &Expectations) -> Vec<String> {
+    let mut failures = Vec::new();
+    if let Some(status) = expectations.status
+        && response.status() != status
+    {
+        failures.push(format!(
+            "status: expected {status}, got {}",
+            response.status()
+        ));
+    }
+    for (name, expectation) in &expectations.headers {
+        let values: Vec<&str> = response
+            .headers()
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect();
+        let failure = match expectation {
+            HeaderExpectation::Present if values.is_empty() => {
+                Some("expected, missing".to_string())
+            }
+            HeaderExpectation::Absent if !values.is_empty() => {
+                Some(format!("expected none, got {values:?}"))
+            }
+            HeaderExpectation::Equals(expected) if !values.contains(&expected.as_str()) => {
+                Some(format!("expected '{expected}', got {values:?}"))
+            }
+            #[cfg(feature = "schema")]
+            HeaderExpectation::Matches(regex)
+                if !values.iter().any(|value| regex.is_match(value)) =>
+            {
+                Some(format!("expected a match of '{regex}', got {values:?}"))
+            }
+            _ => None,
+        };
+        if let Some(failure) = failure {
+            failures.push(format!("header {name}: {failure}"));
+        }
+    }
+
+    let body = response.body();
+    let json = || {
+        serde_json::from_slice::<Value>(body).map_err(|e| {
+            format!(
+                "body: expected JSON, got {e}: {}",
+                String::from_utf8_lossy(body)
+            )
+        })
+    };
+    match &expectations.body {
+        None => {}
+        Some(BodyExpectation::Exact(expected)) if body != expected => {
+            failures.push(format!(
+                "body: expected {:?}, got {:?}",
+                String::from_utf8_lossy(expected),
+                String::from_utf8_lossy(body)
+            ));
+        }
+        Some(BodyExpectation::Exact(_)) => {}
+        Some(BodyExpectation::JsonSubset(expected)) => match json() {
+            Ok(actual) => json_subset(expected, &actual, "", &mut failures),
+            Err(failure) => failures.push(failure),
+        },
+        #[cfg(feature = "schema")]
+        Some(BodyExpectation::Schema(schema)) => match json() {
+            Ok(actual) => failures.extend(
+                schema
+                    .validate(&actual)
+                    .into_iter()
+                    .map(|violation| format!("body{}: {}", violation.pointer, violation.message)),
+            ),
+            Err(failure) => failures.push(failure),
+        },
+    }
+    failures
+}
+
+/// Routes `fixture` with `router` and checks the response against
+/// `expectations`, returning it.
+///
+/// # Panics
+///
+/// Panics listing every unmet expectation, and the whole response, if the
+/// response falls short of any.
+pub fn assert_response(
+    router: &Router,
+    fixture: &Fixture,
+    expectations: &Expectations,
+) -> Response<Bytes> {
+    let response = router.route(fixture.request());
+    let failures = check_response(&response, expectations);
+    assert!(
+        failures.is_empty(),
+        "{} {} got an unexpected response:\n  {}\n\n{}",
+        fixture.method,
+        fixture.uri,
+        failures.join("\n  "),
+        render_response(&response)
+    );
+    response
+}
+
+/// Checks that `actual` contains `expected` at the JSON pointer `pointer`; see
+/// [`BodyExpectation::JsonSubset`].
+fn json_subset(expected: &Value, actual: &Value, pointer: &str, failures: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::String(wildcard), _) if wildcard == "*" => {}
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, expected) in expected {
+                let pointer = format!("{pointer}/{}", key.replace('~', "~0").replace('/', "~1"));
+                match actual.get(key) {
+                    Some(actual) => json_subset(expected, actual, &pointer, failures),
+                    None => failures.push(format!("body{pointer}: expected {expected}, missing")),
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) if expected.len() == actual.len() => {
+            for (index, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                json_subset(expected, actual, &format!("{pointer}/{index}"), failures);
+            }
+        }
+        (expected, actual) if expected != actual => {
+            failures.push(format!("body{pointer}: expected {expected}, got {actual}"));
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,211 @@
+//! Defines `TraceContext`, the W3C Trace Context of a request (its
+//! `traceparent` and `tracestate` headers), for propagating distributed
+//! traces.
+
+use crate::request::HttpRequest;
+use crate::response::HttpResponse;
+use crate::rng::Rng;
+use http::{HeaderMap, HeaderValue};
+use std::fmt::Write as _;
+
+/// The header identifying the trace and the parent span of a request.
+const TRACEPARENT: &str = "traceparent";
+/// The header carrying vendor-specific trace data along with `traceparent`.
+const TRACESTATE: &str = "tracestate";
+
+/// The W3C Trace Context (Level 1) of a request: the trace it belongs to and
+/// the span that sent it, from its `traceparent` header, with the vendor data
+/// of its `tracestate` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    /// The version of the `traceparent` format, 0 for the current one.
+    pub version: u8,
+    /// The ID of the whole trace, never all zeros.
+    pub trace_id: [u8; 16],
+    /// The ID of the span that sent the request, never all zeros.
+    pub parent_id: [u8; 8],
+    /// The trace flags; bit 0 tells whether the caller may have recorded the
+    /// trace, see [`TraceContext::sampled`].
+    pub flags: u8,
+    /// The `tracestate` headers as received, combined with commas, if any.
+    pub tracestate: Option<String>,
+}
+
+impl TraceContext {
+    /// Starts a new trace, with random IDs drawn from `rng` and the sampled
+    /// flag set, e.g. for a request without a `traceparent`.
+    pub fn new<R: Rng + ?Sized>(rng: &R) -> Self {
+        let mut trace_id = [0; 16];
+        while trace_id == [0; 16] {
+            trace_id[..8].copy_from_slice(&rng.next_u64().to_be_bytes());
+            trace_id[8..].copy_from_slice(&rng.next_u64().to_be_bytes());
+        }
+        Self {
+            version: 0,
+            trace_id,
+            parent_id: span_id(rng),
+            flags: 1,
+            tracestate: None,
+        }
+    }
+
+    /// Parses a `traceparent` header value, e.g.
+    /// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`.
+    ///
+    /// Returns `None` if it's malformed: fields of the wrong length, hex digits
+    /// that aren't lowercase, all-zero IDs or the invalid version `ff`.
+    /// Following the specification, a later version than 0 is accepted with
+    /// anything after the four fields it shares with version 0, ignored.
+    pub fn parse(traceparent: &str) -> Option<Self> {
+        let value = traceparent.trim_matches([' ', '\t']);
+        let bytes = value.as_bytes();
+        if !value.is_ascii() || value.len() < 55 || [2, 35, 52].iter().any(|&i| bytes[i] != b'-') {
+            return None;
+        }
+        let version = hex::<1>(&value[..2])?[0];
+        if version == 0xff || (version == 0 && value.len() != 55) {
+            return None;
+        }
+        if version > 0 && value.len() > 55 && bytes[55] != b'-' {
+            return None;
+        }
+        let trace_id = hex::<16>(&value[3..35]).filter(|id| *id != [0; 16])?;
+        let parent_id = hex::<8>(&value[36..52]).filter(|id| *id != [0; 8])?;
+        let flags = hex::<1>(&value[53..55])?[0];
+        Some(Self {
+            version,
+            trace_id,
+            parent_id,
+            flags,
+            tracestate: None,
+        })
+    }
+
+    /// Parses the `traceparent` and `tracestate` headers of `headers`, as
+    /// [`HttpRequest::trace_context`] does.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let mut values = headers.get_all(TRACEPARENT).iter();
+        // A request with several `traceparent` headers is malformed.
+        let (Some(value), None) = (values.next(), values.next()) else {
+            return None;
+        };
+        let mut context = Self::parse(value.to_str().ok()?)?;
+        let states: Vec<&str> = headers
+            .get_all(TRACESTATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .collect();
+        if !states.is_empty() {
+            context.tracestate = Some(states.join(","));
+        }
+        Some(context)
+    }
+
+    /// Returns the context of a new span in the same trace, with a parent ID
+    /// drawn from `rng`, to pass on to downstream calls made for the request.
+    /// It has version 0, the version this crate writes.
+    pub fn child<R: Rng + ?Sized>(&self, rng: &R) -> Self {
+        Self {
+            version: 0,
+            parent_id: span_id(rng),
+            ..self.clone()
+        }
+    }
+
+    /// Returns `true` if the sampled flag is set: the caller may have recorded
+    /// the trace.
+    pub fn sampled(&self) -> bool {
+        self.flags & 1 == 1
+    }
+
+    /// Returns the trace ID as 32 lowercase hex digits.
+    pub fn trace_id_hex(&self) -> String {
+        to_hex(&self.trace_id)
+    }
+
+    /// Returns the value of the `traceparent` header for the context, in
+    /// version 0 of the format whatever the version it was parsed from.
+    pub fn traceparent(&self) -> String {
+        format!(
+            "00-{}-{}-{:02x}",
+            to_hex(&self.trace_id),
+            to_hex(&self.parent_id),
+            self.flags
+        )
+    }
+
+    /// Sets the `traceparent` and `tracestate` headers of `headers` for the
+    /// context, e.g. those of an outgoing request to propagate the trace to,
+    /// usually with the context of a [`child`](TraceContext::child) span.
+    pub fn write_headers(&self, headers: &mut HeaderMap) {
+        if let Ok(value) = HeaderValue::from_str(&self.traceparent()) {
+            headers.insert(TRACEPARENT, value);
+        }
+        headers.remove(TRACESTATE);
+        if let Some(value) = self
+            .tracestate
+            .as_deref()
+            .and_then(|state| HeaderValue::from_str(state).ok())
+        {
+            headers.insert(TRACESTATE, value);
+        }
+    }
+}
+
+impl HttpRequest {
+    /// Returns the W3C Trace Context of the request, from its `traceparent`
+    /// and `tracestate` headers, or `None` if `traceparent` is missing,
+    /// repeated or malformed; see [`TraceContext::parse`].
+    pub fn trace_context(&self) -> Option<TraceContext> {
+        TraceContext::from_headers(&self.headers)
+    }
+}
+
+impl HttpResponse {
+    /// Sets the `traceparent` and `tracestate` headers of the response for
+    /// `context`, e.g. to tell the client the trace its request joined.
+    pub fn set_trace_context(&mut self, context: &TraceContext) -> &mut Self {
+        context.write_headers(&mut self.headers);
+        self
+    }
+}
+
+/// Draws a span ID from `rng`, never all zeros.
+fn span_id<R: Rng + ?Sized>(rng: &R) -> [u8; 8] {
+    loop {
+        let id = rng.next_u64();
+        if id != 0 {
+            return id.to_be_bytes();
+        }
+    }
+}
+
+/// Decodes `N` bytes from `2 * N` lowercase hex digits.
+fn hex<const N: usize>(digits: &str) -> Option<[u8; N]> {
+    let digits = digits.as_bytes();
+    if digits.len() != 2 * N {
+        return None;
+    }
+    let value = |digit: u8| match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        _ => None,
+    };
+    let mut bytes = [0; N];
+    for (byte, pair) in bytes.iter_mut().zip(digits.chunks(2)) {
+        *byte = value(pair[0])? << 4 | value(pair[1])?;
+    }
+    Some(bytes)
+}
+
+/// Encodes `bytes` as lowercase hex digits.
+fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(2 * bytes.len()), |mut hex, b| {
+            let _ = write!(hex, "{b:02x}");
+            hex
+        })
+}
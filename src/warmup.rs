@@ -0,0 +1,64 @@
+//! Defines the report of `Router::warm_up`, which builds what the router
+//! otherwise builds on first use and checks every route with a synthetic
+//! request, before the server takes traffic.
+
+use http::Method;
+use std::time::Duration;
+
+/// What `Router::warm_up` did, and the problems it found.
+#[derive(Debug, Clone, Default)]
+pub struct WarmupReport {
+    /// The time each step took, in order: `allowed_methods`, `routes`,
+    /// `templates` and, with the `schema` feature, `schemas`.
+    pub timings: Vec<(&'static str, Duration)>,
+    /// Every route, with whether its synthetic request matched it.
+    pub routes: Vec<WarmedRoute>,
+    /// The problems found, e.g. a template using a path parameter its route
+    /// doesn't have.
+    pub problems: Vec<WarmupProblem>,
+}
+
+impl WarmupReport {
+    /// Returns `true` if no problem was found.
+    pub fn is_clean(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// A route checked by `Router::warm_up`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WarmedRoute {
+    pub method: Method,
+    /// The path pattern, as written in the configuration.
+    pub pattern: String,
+    /// The path of the synthetic request, with every parameter set to
+    /// `warmup`, e.g. `/users/warmup`.
+    pub sample_path: String,
+    /// Whether the synthetic request matched the route, rather than a more
+    /// specific one, e.g. `/users/warmup` for `/users/:id`, or none.
+    pub matched: bool,
+}
+
+/// A problem with a route found by `Router::warm_up`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WarmupProblem {
+    pub method: Method,
+    /// The path pattern, as written in the configuration.
+    pub pattern: String,
+    /// What's wrong, e.g. `the body_template uses {params.id}, which the path
+    /// doesn't have`.
+    pub problem: String,
+}
+
+/// Returns the path of the synthetic request of `pattern`, with `warmup` for
+/// each parameter, e.g. `/files/warmup` for `/files/*path`.
+pub(crate) fn sample_path(pattern: &str) -> String {
+    pattern
+        .split('/')
+        .map(|segment| match segment.find([':', '*']) {
+            Some(start) => format!("{}warmup", &segment[..start]),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,204 @@
+//! Utilities for embedding the router in a custom TCP service: parsing raw
+//! HTTP/1.1 requests and serializing responses.
+//!
+//! Only `Content-Length` delimited request bodies are supported; requests using
+//! `Transfer-Encoding` are rejected.
+
+pub use crate::error::WireError;
+use http::header::{CONTENT_LENGTH, HeaderName, HeaderValue, TRANSFER_ENCODING};
+use http::{Method, Request, Response, StatusCode, Uri, Version};
+
+/// The limits applied by [`parse_request_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WireLimits {
+    /// The maximum size of the request line and headers, in bytes.
+    pub max_header_bytes: usize,
+    /// The maximum number of headers.
+    pub max_headers: usize,
+    /// The maximum size of the body, in bytes.
+    pub max_body_bytes: usize,
+}
+
+impl Default for WireLimits {
+    /// Allows 8 KiB of headers, 64 headers and a 1 MiB body.
+    fn default() -> Self {
+        Self {
+            max_header_bytes: 8 * 1024,
+            max_headers: 64,
+            max_body_bytes: 1024 * 1024,
+        }
+    }
+}
+
+/// Parses an HTTP/1.x request from the start of `buf` with the default
+/// [`WireLimits`].
+///
+/// Returns the request and the number of bytes it took up in `buf`, so that
+/// pipelined requests can be parsed from the rest of the buffer.
+///
+/// # Errors
+///
+/// Returns [`WireError::Incomplete`] if `buf` doesn't contain a whole request
+/// yet, or another `WireError` if the request is invalid.
+pub fn parse_request(buf: &[u8]) -> Result<(Request<Vec<u8>>, usize), WireError> {
+    parse_request_with(buf, &WireLimits::default())
+}
+
+/// Parses an HTTP/1.x request from the start of `buf`, like [`parse_request`],
+/// with the given limits.
+///
+/// # Errors
+///
+/// Returns [`WireError::Incomplete`] if `buf` doesn't contain a whole request
+/// yet, or another `WireError` if the request is invalid or exceeds `limits`.
+pub fn parse_request_with(
+    buf: &[u8],
+    limits: &WireLimits,
+) -> Result<(Request<Vec<u8>>, usize), WireError> {
+    let mut headers = vec![httparse::EMPTY_HEADER; limits.max_headers];
+    let mut parsed = httparse::Request::new(&mut headers);
+    let header_len = match parsed.parse(buf) {
+        Ok(httparse::Status::Complete(len)) if len <= limits.max_header_bytes => len,
+        Ok(httparse::Status::Partial) if buf.len() <= limits.max_header_bytes => {
+            return Err(WireError::Incomplete);
+        }
+        Ok(_) => {
+            return Err(WireError::HeadersTooLarge {
+                limit: limits.max_header_bytes,
+            });
+        }
+        Err(e) => return Err(WireError::Malformed(e)),
+    };
+
+    // `parse` only completes with all of the request line present.
+    let method = Method::from_bytes(parsed.method.unwrap_or_default().as_bytes())
+        .map_err(|_| WireError::Malformed(httparse::Error::Token))?;
+    let target = parsed.path.unwrap_or_default();
+    let uri = parse_target(&method, target)?;
+    let version = match parsed.version {
+        Some(0) => Version::HTTP_10,
+        _ => Version::HTTP_11,
+    };
+
+    let mut request = Request::new(Vec::new());
+    *request.method_mut() = method;
+    *request.uri_mut() = uri;
+    *request.version_mut() = version;
+    for header in parsed.headers.iter() {
+        let name = HeaderName::from_bytes(header.name.as_bytes())
+            .map_err(|_| WireError::Malformed(httparse::Error::HeaderName))?;
+        let value = HeaderValue::from_bytes(header.value)
+            .map_err(|_| WireError::Malformed(httparse::Error::HeaderValue))?;
+        request.headers_mut().append(name, value);
+    }
+
+    let body_len = body_len(request.headers())?;
+    if body_len > limits.max_body_bytes {
+        return Err(WireError::BodyTooLarge {
+            limit: limits.max_body_bytes,
+        });
+    }
+    let end = header_len + body_len;
+    if buf.len() < end {
+        return Err(WireError::Incomplete);
+    }
+    *request.body_mut() = buf[header_len..end].to_vec();
+
+    Ok((request, end))
+}
+
+/// Parses a request target, accepting the forms RFC 9112 allows for `method`:
+/// origin-form (`/path?query`) and absolute-form (`http://host/path`) for every
+/// method, authority-form (`host:port`) only for `CONNECT`, and asterisk-form
+/// (`*`) only for `OPTIONS`.
+fn parse_target(method: &Method, target: &str) -> Result<Uri, WireError> {
+    let uri: Uri = target.parse().map_err(|_| WireError::InvalidTarget)?;
+    let valid = if *method == Method::CONNECT {
+        uri.authority().is_some() && uri.scheme().is_none() && uri.path().is_empty()
+    } else if target == "*" {
+        *method == Method::OPTIONS
+    } else if target.starts_with('/') {
+        uri.scheme().is_none() && uri.authority().is_none()
+    } else {
+        uri.scheme().is_some() && uri.authority().is_some()
+    };
+    if valid {
+        Ok(uri)
+    } else {
+        Err(WireError::InvalidTarget)
+    }
+}
+
+/// Returns the length of the body from the `Content-Length` headers, or 0 if
+/// there are none.
+fn body_len(headers: &http::HeaderMap) -> Result<usize, WireError> {
+    if headers.contains_key(TRANSFER_ENCODING) {
+        return Err(if headers.contains_key(CONTENT_LENGTH) {
+            WireError::ConflictingLength
+        } else {
+            WireError::UnsupportedTransferEncoding
+        });
+    }
+
+    let mut len = None;
+    for value in headers.get_all(CONTENT_LENGTH) {
+        // A value may also be a list of identical lengths, e.g. `42, 42`.
+        let value = value
+            .to_str()
+            .map_err(|_| WireError::InvalidContentLength)?;
+        for part in value.split(',') {
+            let part = part.trim();
+            if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(WireError::InvalidContentLength);
+            }
+            let part: usize = part.parse().map_err(|_| WireError::InvalidContentLength)?;
+            if len.is_some_and(|len| len != part) {
+                return Err(WireError::InvalidContentLength);
+            }
+            len = Some(part);
+        }
+    }
+    Ok(len.unwrap_or(0))
+}
+
+/// Serializes `res` as an HTTP/1.1 response (or HTTP/1.0, if that's its
+/// version), appending it to `out`.
+///
+/// A `Content-Length` header is added unless the response already has one or a
+/// `Transfer-Encoding` header, or its status doesn't allow a body (1xx, 204 No
+/// Content and 304 Not Modified).
+pub fn write_response(res: &Response<Vec<u8>>, out: &mut Vec<u8>) {
+    let version = if res.version() == Version::HTTP_10 {
+        "HTTP/1.0"
+    } else {
+        "HTTP/1.1"
+    };
+    let status = res.status();
+    out.extend_from_slice(version.as_bytes());
+    out.push(b' ');
+    out.extend_from_slice(status.as_str().as_bytes());
+    out.push(b' ');
+    out.extend_from_slice(status.canonical_reason().unwrap_or("").as_bytes());
+    out.extend_from_slice(b"\r\n");
+
+    for (name, value) in res.headers() {
+        out.extend_from_slice(name.as_str().as_bytes());
+        out.extend_from_slice(b": ");
+        out.extend_from_slice(value.as_bytes());
+        out.extend_from_slice(b"\r\n");
+    }
+    let bodiless = status.is_informational()
+        || status == StatusCode::NO_CONTENT
+        || status == StatusCode::NOT_MODIFIED;
+    if !bodiless
+        && !res.headers().contains_key(CONTENT_LENGTH)
+        && !res.headers().contains_key(TRANSFER_ENCODING)
+    {
+        out.extend_from_slice(format!("content-length: {}\r\n", res.body().len()).as_bytes());
+    }
+    out.extend_from_slice(b"\r\n");
+
+    if !bodiless {
+        out.extend_from_slice(res.body());
+    }
+}
@@ -0,0 +1,167 @@
+use generic_http_router::{HttpRequest, MediaRange};
+
+const BROWSER: &str =
+    "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8";
+
+fn request(accept: &[&str]) -> HttpRequest {
+    let mut builder = HttpRequest::builder();
+    for value in accept {
+        builder = builder.header("accept", *value);
+    }
+    builder.build().unwrap()
+}
+
+/// The ranges of `accept`, as `type/subtype;params` and their quality.
+fn ranges(accept: &[&str]) -> Vec<(String, f32)> {
+    request(accept)
+        .accepts()
+        .into_iter()
+        .map(
+            |MediaRange {
+                 media_type,
+                 subtype,
+                 params,
+                 quality,
+             }| {
+                let params: String = params
+                    .iter()
+                    .map(|(name, value)| format!(";{name}={value}"))
+                    .collect();
+                (format!("{media_type}/{subtype}{params}"), quality)
+            },
+        )
+        .collect()
+}
+
+fn range(range: &str, quality: f32) -> (String, f32) {
+    (range.to_string(), quality)
+}
+
+fn preferred<'a>(accept: &str, offered: &[&'a str]) -> Option<&'a str> {
+    request(&[accept]).preferred_type(offered)
+}
+
+#[test]
+fn a_browsers_accept_header() {
+    assert_eq!(
+        ranges(&[BROWSER]),
+        [
+            range("text/html", 1.0),
+            range("application/xhtml+xml", 1.0),
+            range("image/avif", 1.0),
+            range("image/webp", 1.0),
+            range("application/xml", 0.9),
+            range("*/*", 0.8),
+        ]
+    );
+    assert_eq!(
+        preferred(BROWSER, &["application/json", "text/html"]),
+        Some("text/html")
+    );
+    assert_eq!(
+        preferred(BROWSER, &["application/json", "application/xml"]),
+        Some("application/xml")
+    );
+    // Anything else is acceptable through `*/*`.
+    assert_eq!(
+        preferred(BROWSER, &["application/json"]),
+        Some("application/json")
+    );
+}
+
+#[test]
+fn ties_are_broken_by_specificity_then_order() {
+    assert_eq!(
+        ranges(&["*/*, text/*, text/html, text/html;level=1, application/json"]),
+        [
+            range("text/html;level=1", 1.0),
+            range("text/html", 1.0),
+            range("application/json", 1.0),
+            range("text/*", 1.0),
+            range("*/*", 1.0),
+        ]
+    );
+    // The most specific matching range gives a type its quality.
+    let accept = "text/*;q=0.5, text/html, */*;q=0.1";
+    assert_eq!(
+        preferred(accept, &["text/plain", "text/html"]),
+        Some("text/html")
+    );
+    assert_eq!(
+        preferred(accept, &["image/png", "text/plain"]),
+        Some("text/plain")
+    );
+    // Equal qualities go to the type offered first.
+    assert_eq!(
+        preferred(
+            "application/json, text/html",
+            &["text/html", "application/json"]
+        ),
+        Some("text/html")
+    );
+}
+
+#[test]
+fn q_0_excludes_media_types() {
+    let accept = "application/json, */*;q=0.1, text/html;q=0";
+    assert_eq!(
+        preferred(accept, &["text/html", "text/plain"]),
+        Some("text/plain")
+    );
+    assert_eq!(preferred(accept, &["text/html"]), None);
+    assert_eq!(preferred("*/*;q=0", &["text/html"]), None);
+    assert_eq!(
+        preferred("text/*;q=0, */*", &["text/csv", "image/png"]),
+        Some("image/png")
+    );
+}
+
+#[test]
+fn a_missing_header_accepts_anything() {
+    assert_eq!(ranges(&[]), [range("*/*", 1.0)]);
+    assert_eq!(
+        request(&[]).preferred_type(&["application/json", "text/html"]),
+        Some("application/json")
+    );
+    assert_eq!(request(&[]).preferred_type(&[]), None);
+}
+
+#[test]
+fn malformed_ranges_and_parameters_are_skipped() {
+    assert_eq!(
+        ranges(&[
+            "text/html;level, application/json;q=abc, bogus, image/png;charset=, */html, \
+             text/plain;q=0.5"
+        ]),
+        [
+            range("text/html", 1.0),
+            range("image/png", 1.0),
+            range("text/plain", 0.5),
+        ]
+    );
+    // Names and types are lowercased, quotes removed, and parameters after
+    // `q` left out; several headers are combined.
+    assert_eq!(
+        ranges(&[
+            "Text/HTML; Level=\"1\"; q=0.7; ext=x",
+            "application/json;q=0.9"
+        ]),
+        [
+            range("application/json", 0.9),
+            range("text/html;level=1", 0.7)
+        ]
+    );
+    // A header with nothing valid accepts nothing.
+    assert!(ranges(&["bogus"]).is_empty());
+    assert_eq!(preferred("bogus", &["text/html"]), None);
+}
+
+#[test]
+fn ranges_with_parameters_only_match_the_same_parameters() {
+    let html = &request(&["text/html;level=1"]).accepts()[0];
+    assert!(html.matches("text/html;level=1"));
+    assert!(html.matches("TEXT/html; level=1; charset=utf-8"));
+    assert!(!html.matches("text/html"));
+    assert!(!html.matches("text/html;level=2"));
+    assert!(!html.matches("not a type"));
+}
@@ -0,0 +1,161 @@
+mod common;
+
+use common::assert_snapshot;
+use generic_http_router::hooks::{Outcome, RequestSummary};
+use generic_http_router::testing::MockClock;
+use generic_http_router::{
+    AccessLogFormat, AccessLogger, HttpHandler, HttpRequest, HttpResponse, Router,
+};
+use http::{Method, Request, StatusCode, Version};
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// 2026-10-14T09:30:00.250Z.
+fn clock() -> MockClock {
+    MockClock::at(UNIX_EPOCH + Duration::from_millis(1_791_970_200_250))
+}
+
+/// A writer appending to a buffer the test reads back.
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Buffer {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+fn summary(method: Method, path: &str, status: StatusCode) -> RequestSummary {
+    RequestSummary {
+        method,
+        path: path.to_string(),
+        status,
+        elapsed: Duration::from_millis(12),
+        outcome: Outcome::Completed,
+        detail: None,
+        pattern: None,
+        bytes: None,
+        version: Version::HTTP_11,
+        request_id: None,
+        client_ip: None,
+        referer: None,
+        user_agent: None,
+        suggestions: Vec::new(),
+    }
+}
+
+/// A complete request, one whose path and headers need escaping, a streamed
+/// 404 without any of the optional fields, and a request a second and a half
+/// later, with a new timestamp.
+fn log(format: AccessLogFormat) -> String {
+    let buffer = Buffer::default();
+    let clock = clock();
+    let logger = AccessLogger::new(format, buffer.clone()).with_clock(clock.clone());
+
+    let mut complete = summary(Method::GET, "/users/42", StatusCode::OK);
+    complete.pattern = Some("/users/:id".to_string());
+    complete.bytes = Some(42);
+    complete.request_id = Some("req-1".to_string());
+    complete.client_ip = Some([10, 0, 0, 1].into());
+    complete.referer = Some("https://example.com/".to_string());
+    complete.user_agent = Some("curl/8.5.0".to_string());
+    logger.log(&complete);
+
+    let mut escaped = summary(Method::POST, "/say/\"hi\"\\\u{1}\néé", StatusCode::CREATED);
+    escaped.bytes = Some(0);
+    escaped.elapsed = Duration::from_micros(1500);
+    escaped.request_id = Some("a\"b\tc".to_string());
+    escaped.client_ip = Some("2001:db8::1".parse().unwrap());
+    escaped.user_agent = Some("Mozilla/5.0 \"quoted\"".to_string());
+    logger.log(&escaped);
+
+    let mut streamed = summary(Method::GET, "/nope", StatusCode::NOT_FOUND);
+    streamed.version = Version::HTTP_2;
+    logger.log(&streamed);
+
+    clock.advance(Duration::from_millis(1500));
+    logger.log(&summary(
+        Method::DELETE,
+        "/users/42",
+        StatusCode::NO_CONTENT,
+    ));
+    buffer.text()
+}
+
+#[test]
+fn combined_lines_match_their_golden_file() {
+    assert_snapshot(
+        "golden/access_log_combined.log",
+        &log(AccessLogFormat::Combined),
+    );
+}
+
+#[test]
+fn json_lines_match_their_golden_file() {
+    let log = log(AccessLogFormat::JsonLines);
+    assert_snapshot("golden/access_log.jsonl", &log);
+    // Each line is a JSON object, with the escaped values read back as logged.
+    let lines: Vec<serde_json::Value> = log
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[1]["path"], "/say/\"hi\"\\\u{1}\néé");
+    assert_eq!(lines[1]["request_id"], "a\"b\tc");
+}
+
+#[test]
+fn the_router_logs_through_its_response_hook() {
+    struct Hello;
+
+    impl HttpHandler for Hello {
+        fn handle(&self, _req: HttpRequest) -> HttpResponse {
+            HttpResponse::ok("hello")
+        }
+    }
+
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let logger = {
+        let lines = Arc::clone(&lines);
+        AccessLogger::with_callback(AccessLogFormat::JsonLines, move |line| {
+            lines.lock().unwrap().push(line.to_string());
+        })
+        .with_clock(clock())
+    };
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/hello", "description": "", "controller": "hello"}
+        ]}"#,
+    )
+    .unwrap()
+    .with_clock(clock());
+    router.register_handler("hello", Hello);
+    router.on_response(move |summary| logger.log(summary));
+    let req = Request::get("/hello")
+        .header("x-request-id", "req-7")
+        .body(String::new())
+        .unwrap();
+    router.route(req);
+
+    // The mock clock doesn't move while the request is routed.
+    assert_eq!(
+        *lines.lock().unwrap(),
+        [concat!(
+            r#"{"ts":"2026-10-14T09:30:00.250Z","method":"GET","path":"/hello","#,
+            r#""pattern":"/hello","status":200,"bytes":5,"duration_ms":0.000,"#,
+            r#""request_id":"req-7","client_ip":null}"#
+        )]
+    );
+}
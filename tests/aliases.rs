@@ -0,0 +1,144 @@
+use generic_http_router::config::Endpoint;
+use generic_http_router::error::RouterError;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Method, Request, StatusCode};
+
+/// Answers with its name.
+struct Named(&'static str);
+
+impl HttpHandler for Named {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok(self.0)
+    }
+}
+
+/// `GET /users/:id` uses the `users` alias and `GET /members/:id` the name it
+/// stands for, so both are the same controller.
+const CONFIG: &str = r#"{
+    "controllers": {"users": "com.acme.users_controller", "orders": "com.acme.orders"},
+    "endpoints": [
+        {"method": "GET", "path": "/users/:id", "description": "", "controller": "users::get_by_id"},
+        {"method": "GET", "path": "/members/:id", "description": "",
+         "controller": "com.acme.users_controller::get_by_id"},
+        {"method": "GET", "path": "/orders", "description": "", "controller": "orders::list"},
+        {"method": "GET", "path": "/health", "description": "", "controller": "health"}
+    ]
+}"#;
+
+fn get(router: &Router, path: &str) -> (StatusCode, String) {
+    let response = router.route(Request::get(path).body(String::new()).unwrap());
+    let body = String::from_utf8(response.body().to_vec()).unwrap();
+    (response.status(), body)
+}
+
+fn ok(body: &str) -> (StatusCode, String) {
+    (StatusCode::OK, body.to_string())
+}
+
+#[test]
+fn aliases_are_expanded_at_load_time() {
+    let router = Router::from_json_str(CONFIG).unwrap();
+    let controllers: Vec<_> = router
+        .routes()
+        .map(|endpoint| (endpoint.path.as_str(), endpoint.controller.as_str()))
+        .collect();
+    assert_eq!(
+        controllers,
+        [
+            ("/users/:id", "com.acme.users_controller::get_by_id"),
+            ("/members/:id", "com.acme.users_controller::get_by_id"),
+            ("/orders", "com.acme.orders::list"),
+            // Names without a `::` are kept as is.
+            ("/health", "health"),
+        ]
+    );
+}
+
+#[test]
+fn handlers_register_by_alias_or_expanded_name() {
+    let mut router = Router::from_json_str(CONFIG).unwrap();
+    router.register_handler("orders::list", Named("by alias"));
+    assert_eq!(get(&router, "/orders"), ok("by alias"));
+    // Both forms are the same name, so the last registration wins.
+    router.register_handler("com.acme.orders::list", Named("by expanded name"));
+    assert_eq!(get(&router, "/orders"), ok("by expanded name"));
+    router.register_handler("orders::list", Named("by alias again"));
+    assert_eq!(get(&router, "/orders"), ok("by alias again"));
+}
+
+#[test]
+fn an_alias_expanding_to_a_literal_name_is_the_same_controller() {
+    let mut router = Router::from_json_str(CONFIG).unwrap();
+    router.register_handler("users::get_by_id", Named("user"));
+    assert_eq!(get(&router, "/users/1"), ok("user"));
+    assert_eq!(get(&router, "/members/1"), ok("user"));
+
+    // A name merely starting like an alias's expansion isn't it.
+    let error = Router::from_json_str(&CONFIG.replace(
+        "com.acme.users_controller::get_by_id\"",
+        "com.acme.users_controllers::get_by_id\"",
+    ))
+    .err()
+    .unwrap();
+    assert!(
+        error
+            .to_string()
+            .contains("unknown controller alias 'com.acme.users_controllers'"),
+        "{error}"
+    );
+}
+
+#[test]
+fn unknown_alias_prefixes_are_load_errors() {
+    let json = CONFIG.replace("orders::list", "billing::list");
+    match Router::from_json_str(&json) {
+        Err(RouterError::Endpoint {
+            method,
+            path,
+            reason,
+        }) => {
+            assert_eq!((method, path.as_str()), (Method::GET, "/orders"));
+            assert_eq!(
+                reason,
+                "unknown controller alias 'billing' in 'billing::list'"
+            );
+        }
+        other => panic!("{:?}", other.map(|_| ())),
+    }
+
+    // Without aliases, any name goes.
+    let json = r#"{"endpoints": [
+        {"method": "GET", "path": "/orders", "description": "", "controller": "billing::list"}
+    ]}"#;
+    assert!(Router::from_json_str(json).is_ok());
+}
+
+#[test]
+fn canaries_mirrors_and_added_routes_are_expanded() {
+    let mut router = Router::from_json_str(&CONFIG.replace(
+        r#""controller": "orders::list"}"#,
+        r#""controller": "", "canary": {"stable": "orders::list", "candidate": "orders::list_v2",
+           "percent": 0}, "mirror_to": "orders::list_v2"}"#,
+    ))
+    .unwrap();
+    let orders = router.routes().find(|e| e.path == "/orders").unwrap();
+    let canary = orders.canary.as_ref().unwrap();
+    assert_eq!(canary.stable, "com.acme.orders::list");
+    assert_eq!(canary.candidate, "com.acme.orders::list_v2");
+    assert_eq!(
+        orders.mirror_to.as_deref(),
+        Some("com.acme.orders::list_v2")
+    );
+
+    router
+        .add_route(Endpoint::new(Method::POST, "/orders", "orders::create"))
+        .unwrap();
+    router.register_handler("com.acme.orders::create", Named("created"));
+    let response = router.route(Request::post("/orders").body(String::new()).unwrap());
+    assert_eq!(response.body(), "created");
+    assert!(
+        router
+            .add_route(Endpoint::new(Method::PUT, "/orders", "billing::put"))
+            .is_err()
+    );
+}
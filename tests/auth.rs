@@ -0,0 +1,128 @@
+use generic_http_router::redact::{self, DebugOptions};
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Request, Response, StatusCode};
+
+/// The SHA-256 hash of `partner-key`.
+const PARTNER_KEY_HASH: &str = "346e50af211b5135824bb2bb58fe0f9e6df228adcf10c58a37fbc46b57baee74";
+
+/// Answers with the subject of the caller's identity.
+struct WhoAmI;
+
+impl HttpHandler for WhoAmI {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let subject = req.identity().and_then(|identity| identity.subject());
+        HttpResponse::ok(subject.unwrap_or("nobody").to_string())
+    }
+}
+
+fn router() -> Router {
+    let config = format!(
+        r#"{{
+            "api_keys": {{
+                "header": "X-Partner-Key",
+                "query_param": "partner_key",
+                "keys": {{"partner-a": "{PARTNER_KEY_HASH}"}}
+            }},
+            "endpoints": [
+                {{"method": "GET", "path": "/partner", "description": "",
+                  "controller": "who", "auth": "api_key"}},
+                {{"method": "GET", "path": "/public", "description": "",
+                  "controller": "who"}}
+            ]
+        }}"#
+    );
+    let mut router = Router::from_json_str(&config).unwrap();
+    router.register_handler("who", WhoAmI);
+    router
+}
+
+fn get(router: &Router, uri: &str, headers: &[(&str, &str)]) -> Response<bytes::Bytes> {
+    let mut req = Request::get(uri);
+    for (name, value) in headers {
+        req = req.header(*name, *value);
+    }
+    router.route(req.body(String::new()).unwrap())
+}
+
+#[test]
+fn a_valid_key_authenticates_as_its_id() {
+    let response = get(&router(), "/partner", &[("x-partner-key", "partner-key")]);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body().as_ref(), b"partner-a");
+}
+
+#[test]
+fn an_invalid_or_missing_key_is_unauthorized() {
+    let router = router();
+    let response = get(&router, "/partner", &[("x-partner-key", "guessed")]);
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = get(&router, "/partner", &[]);
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    // A key in another header isn't read.
+    let response = get(&router, "/partner", &[("x-api-key", "partner-key")]);
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[test]
+fn routes_without_auth_dont_need_a_key() {
+    let response = get(&router(), "/public", &[]);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body().as_ref(), b"nobody");
+}
+
+#[test]
+fn a_key_can_be_sent_in_the_query() {
+    let router = router();
+    let response = get(&router, "/partner?partner_key=partner-key&page=2", &[]);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body().as_ref(), b"partner-a");
+    let response = get(&router, "/partner?partner_key=guessed", &[]);
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    // The header wins over the query.
+    let response = get(
+        &router,
+        "/partner?partner_key=partner-key",
+        &[("x-partner-key", "guessed")],
+    );
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[test]
+fn keys_are_redacted_in_debug_output_even_with_other_options() {
+    let _router = router();
+    // Replacing the options afterwards keeps the router's key header and query
+    // parameter redacted.
+    redact::set_debug_options(DebugOptions {
+        redacted_headers: Vec::new(),
+        ..DebugOptions::default()
+    });
+    let options = redact::debug_options();
+    assert!(
+        options
+            .redacted_headers
+            .iter()
+            .any(|h| h == "x-partner-key")
+    );
+    assert!(
+        options
+            .redacted_query_params
+            .iter()
+            .any(|p| p == "partner_key")
+    );
+
+    let req = HttpRequest::builder()
+        .method("GET")
+        .uri("/partner?partner_key=partner-key&page=2")
+        .header("x-partner-key", "partner-key")
+        .build()
+        .unwrap();
+    let debug = format!("{req:?}");
+    assert!(!debug.contains("=partner-key"), "{debug}");
+    assert!(!debug.contains("\"partner-key\""), "{debug}");
+    assert!(debug.contains("partner_key=<redacted>&page=2"), "{debug}");
+    assert!(
+        debug.contains("\"x-partner-key\": \"<redacted>\""),
+        "{debug}"
+    );
+    assert!(format!("{:?}", req.debug_full()).contains("partner-key"));
+}
@@ -0,0 +1,211 @@
+use generic_http_router::error::LoadWarning;
+use generic_http_router::{BatchLimits, HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Method, Request, Response, StatusCode};
+use serde_json::{Value, json};
+
+/// The SHA-256 hash of `partner-key`.
+const PARTNER_KEY_HASH: &str = "346e50af211b5135824bb2bb58fe0f9e6df228adcf10c58a37fbc46b57baee74";
+
+/// Answers with the request's body and its `X-Echo` header, as JSON.
+struct Echo;
+
+impl HttpHandler for Echo {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let mut response = HttpResponse::new(
+            StatusCode::CREATED,
+            json!({
+                "body": String::from_utf8_lossy(&req.body),
+                "echo": req.headers.get("x-echo").map(|value| value.to_str().unwrap()),
+            })
+            .to_string(),
+        );
+        response.add_header("content-type", "application/json");
+        response
+    }
+}
+
+/// Answers with the subject of the caller's identity, as plain text.
+struct WhoAmI;
+
+impl HttpHandler for WhoAmI {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let subject = req.identity().and_then(|identity| identity.subject());
+        HttpResponse::ok(subject.unwrap_or("nobody").to_string())
+    }
+}
+
+/// A router with a `POST /echo`, a `GET /partner` needing an API key, and
+/// batching at `/batch` with `limits`.
+fn router_with(limits: BatchLimits) -> Router {
+    let mut router = Router::from_json_str(&format!(
+        r#"{{
+            "api_keys": {{"header": "X-Partner-Key", "keys": {{"partner-a": "{PARTNER_KEY_HASH}"}}}},
+            "endpoints": [
+                {{"method": "POST", "path": "/echo", "description": "", "controller": "echo"}},
+                {{"method": "GET", "path": "/partner", "description": "", "controller": "who",
+                  "auth": "api_key"}}
+            ]
+        }}"#
+    ))
+    .unwrap();
+    router.register_handler("echo", Echo);
+    router.register_handler("who", WhoAmI);
+    assert!(router.enable_batching("/batch", limits).is_empty());
+    router
+}
+
+fn batch(router: &Router, body: impl Into<String>) -> Response<bytes::Bytes> {
+    router.route(
+        Request::post("/batch")
+            .header("x-partner-key", "partner-key")
+            .body(body.into())
+            .unwrap(),
+    )
+}
+
+fn json_body(response: &Response<bytes::Bytes>) -> Value {
+    serde_json::from_slice(response.body()).unwrap()
+}
+
+#[test]
+fn a_batch_gets_each_sub_response_in_order() {
+    let router = router_with(BatchLimits::default());
+    let response = batch(
+        &router,
+        json!([
+            {"method": "POST", "path": "/echo", "headers": {"x-echo": "one"}, "body": {"n": 1}},
+            {"method": "GET", "path": "/nowhere"},
+            {"method": "POST", "path": "/echo", "body_base64": "aGk="},
+            {"method": "GET", "path": "/partner", "headers": {"x-partner-key": "partner-key"}}
+        ])
+        .to_string(),
+    );
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/json");
+    let items = json_body(&response);
+    let items = items.as_array().unwrap();
+    assert_eq!(items.len(), 4);
+
+    // JSON bodies are JSON.
+    assert_eq!(items[0]["status"], 201);
+    assert_eq!(items[0]["headers"]["content-type"], "application/json");
+    assert_eq!(
+        items[0]["body"],
+        json!({"body": r#"{"n":1}"#, "echo": "one"})
+    );
+    assert_eq!(items[1]["status"], 404);
+    assert_eq!(items[2]["body"], json!({"body": "hi", "echo": null}));
+    // Others are base64-encoded.
+    assert_eq!(items[3]["status"], 200);
+    assert_eq!(items[3]["body_base64"], "cGFydG5lci1h");
+    assert!(items[3].get("body").is_none());
+}
+
+#[test]
+fn batches_over_the_limits_are_rejected() {
+    let router = router_with(BatchLimits {
+        max_requests: 2,
+        max_body_bytes: 200,
+    });
+    let sub = json!({"method": "GET", "path": "/nowhere"});
+    assert_eq!(
+        batch(&router, json!([sub, sub]).to_string()).status(),
+        StatusCode::OK
+    );
+    let response = batch(&router, json!([sub, sub, sub]).to_string());
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(
+        response.body(),
+        "Error: The batch has more than 2 requests."
+    );
+    let big = json!([{"method": "POST", "path": "/echo", "body": "x".repeat(200)}]);
+    let response = batch(&router, big.to_string());
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(
+        response.body(),
+        "Error: The batch is larger than 200 bytes."
+    );
+
+    for (body, detail) in [
+        ("{}", "The batch isn't a JSON array: "),
+        (
+            r#"[{"method": "GET", "path": "nowhere"}]"#,
+            "Request 0 of the batch has a path 'nowhere' not starting with '/'.",
+        ),
+        (
+            r#"[{"method": "GET", "path": "/", "extra": 1}]"#,
+            "Request 0 of the batch is invalid: unknown field `extra`",
+        ),
+    ] {
+        let response = batch(&router, body);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{body}");
+        let text = std::str::from_utf8(response.body()).unwrap();
+        assert!(text.starts_with(&format!("Error: {detail}")), "{text}");
+    }
+}
+
+#[test]
+fn each_sub_request_is_authenticated_on_its_own() {
+    let router = router_with(BatchLimits::default());
+    // The batch's own key isn't passed on.
+    let response = batch(
+        &router,
+        json!([
+            {"method": "GET", "path": "/partner"},
+            {"method": "GET", "path": "/partner", "headers": {"x-partner-key": "wrong"}},
+            {"method": "GET", "path": "/partner", "headers": {"x-partner-key": "partner-key"}}
+        ])
+        .to_string(),
+    );
+    let items = json_body(&response);
+    assert_eq!(items[0]["status"], 401);
+    assert_eq!(items[1]["status"], 401);
+    assert_eq!(items[2]["status"], 200);
+}
+
+#[test]
+fn batches_cant_be_nested() {
+    let router = router_with(BatchLimits::default());
+    let response = batch(
+        &router,
+        json!([{"method": "POST", "path": "/batch", "body": []}]).to_string(),
+    );
+    let items = json_body(&response);
+    assert_eq!(items[0]["status"], 400);
+    assert_eq!(
+        items[0]["body_base64"],
+        // "Error: A batch can't contain requests to the batch endpoint."
+        "RXJyb3I6IEEgYmF0Y2ggY2FuJ3QgY29udGFpbiByZXF1ZXN0cyB0byB0aGUgYmF0Y2ggZW5kcG9pbnQu"
+    );
+
+    // Only `POST` is answered.
+    let response = router.route(
+        Request::builder()
+            .method(Method::GET)
+            .uri("/batch")
+            .body(String::new())
+            .unwrap(),
+    );
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers()["allow"], "POST");
+}
+
+#[test]
+fn batching_is_off_by_default_and_shadowed_by_routes() {
+    let router = Router::from_json_str(r#"{"endpoints": []}"#).unwrap();
+    let response = router.route(Request::post("/batch").body("[]".to_string()).unwrap());
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [{"method": "POST", "path": "/batch", "description": "", "controller": "echo"}]}"#,
+    )
+    .unwrap();
+    router.register_handler("echo", Echo);
+    assert_eq!(
+        router.enable_batching("/batch", BatchLimits::default()),
+        [LoadWarning::BatchEndpointShadowed {
+            path: "/batch".to_string()
+        }]
+    );
+    assert_eq!(batch(&router, "[]").status(), StatusCode::CREATED);
+}
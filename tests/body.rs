@@ -0,0 +1,124 @@
+use bytes::Bytes;
+use generic_http_router::{Body, HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Request, Response, StatusCode};
+use std::io;
+
+/// Answers with the request body itself.
+struct Echo;
+
+impl HttpHandler for Echo {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok(req.body)
+    }
+}
+
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "POST", "path": "/echo", "description": "", "controller": "echo"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("echo", Echo);
+    router
+}
+
+#[test]
+fn a_megabyte_body_is_echoed_without_copying() {
+    let body = Bytes::from(vec![7u8; 1024 * 1024]);
+    let ptr = body.as_ptr();
+    let response = router().route(Request::post("/echo").body(body).unwrap());
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body().len(), 1024 * 1024);
+    assert_eq!(response.body().as_ptr(), ptr);
+}
+
+#[test]
+fn request_bodies_of_every_type_are_accepted() {
+    let router = router();
+    let post = || Request::post("/echo");
+    let response = router.route(post().body(Bytes::from("bytes")).unwrap());
+    assert_eq!(response.body().as_ref(), b"bytes");
+    let response = router.route(post().body(b"vec".to_vec()).unwrap());
+    assert_eq!(response.body().as_ref(), b"vec");
+    let response = router.route(post().body("string".to_string()).unwrap());
+    assert_eq!(response.body().as_ref(), b"string");
+    let response = router.route(post().body("static").unwrap());
+    assert_eq!(response.body().as_ref(), b"static");
+}
+
+#[test]
+fn response_bodies_convert_to_every_http_response_type() {
+    let vec = b"owned".to_vec();
+    let ptr = vec.as_ptr();
+    let response: Response<Vec<u8>> = HttpResponse::ok(vec).into();
+    assert_eq!(response.body(), b"owned");
+    // An owned body is moved into the `Vec`, not copied.
+    assert_eq!(response.body().as_ptr(), ptr);
+
+    let response: Response<Bytes> = HttpResponse::ok("text").into();
+    assert_eq!(response.body().as_ref(), b"text");
+
+    let response: Response<Body> = HttpResponse::ok("full").into();
+    assert_eq!(response.body().as_bytes().unwrap().as_ref(), b"full");
+    assert!(!response.body().is_stream());
+}
+
+#[test]
+fn streamed_bodies_are_collected_within_a_limit() {
+    let chunks = || {
+        Body::stream(vec![
+            Ok(Bytes::from_static(b"ab")),
+            Ok(Bytes::from_static(b"cd")),
+        ])
+    };
+    assert!(chunks().is_stream());
+    assert!(chunks().as_bytes().is_none());
+    assert_eq!(chunks().collect(4).unwrap().as_ref(), b"abcd");
+    let error = chunks().collect(3).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::OutOfMemory);
+
+    let failing = Body::stream(vec![
+        Ok(Bytes::from_static(b"ab")),
+        Err(io::Error::other("reset")),
+    ]);
+    assert_eq!(
+        failing.collect(usize::MAX).unwrap_err().to_string(),
+        "reset"
+    );
+    assert_eq!(Body::empty().collect(0).unwrap(), Bytes::new());
+
+    // Converting a failed stream into a buffered response gives a 500.
+    let failing = HttpResponse::streaming(StatusCode::OK, vec![Err(io::Error::other("reset"))]);
+    let response: Response<Bytes> = failing.into();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[test]
+fn static_bodies_are_borrowed() {
+    static BODY: &[u8] = br#"{"error":"busy"}"#;
+    let mut response = HttpResponse::new_static(StatusCode::SERVICE_UNAVAILABLE, BODY);
+    response.add_header("content-type", "application/json");
+    assert_eq!(response.body.as_bytes().unwrap().as_ptr(), BODY.as_ptr());
+
+    let response: Response<Bytes> = response.into();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()["content-type"], "application/json");
+    assert_eq!(response.body().as_ptr(), BODY.as_ptr());
+
+    // A `Vec` can't borrow, so only then is the body copied.
+    let response: Response<Vec<u8>> = HttpResponse::new_static(StatusCode::OK, BODY).into();
+    assert_eq!(response.body(), BODY);
+    assert_ne!(response.body().as_ptr(), BODY.as_ptr());
+}
+
+#[test]
+fn the_routers_own_errors_have_empty_bodies() {
+    let router = router();
+    let response = router.route(Request::get("/nope").body(Bytes::new()).unwrap());
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(response.body().is_empty());
+    let response = router.route(Request::get("/echo").body(Bytes::new()).unwrap());
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert!(response.body().is_empty());
+}
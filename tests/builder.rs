@@ -0,0 +1,137 @@
+use generic_http_router::error::RouterError;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse};
+use http::{Method, StatusCode};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, PartialEq)]
+struct RequestId(u32);
+
+#[test]
+fn defaults_to_an_empty_get_of_the_root() {
+    let req = HttpRequest::builder().build().unwrap();
+    assert_eq!(req.method, Method::GET);
+    assert_eq!(req.uri, "/");
+    assert_eq!(req.original_uri, "/");
+    assert!(req.headers.is_empty());
+    assert!(req.params.is_empty());
+    assert!(req.body.is_empty());
+    assert!(req.deadline.is_none());
+}
+
+#[test]
+fn sets_every_part() {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let req = HttpRequest::builder()
+        .method("PUT")
+        .uri("/users/1?x=2")
+        .header("x-tag", "a")
+        .header("x-tag", "b")
+        .header("accept", "text/plain")
+        .param("id", "1")
+        .param("org", "acme")
+        .extension(RequestId(9))
+        .deadline(deadline)
+        .body("payload")
+        .build()
+        .unwrap();
+    assert_eq!(req.method, Method::PUT);
+    assert_eq!(req.uri, "/users/1?x=2");
+    assert_eq!(req.original_uri, "/users/1?x=2");
+    assert_eq!(req.uri.query(), Some("x=2"));
+    let tags: Vec<_> = req.headers.get_all("x-tag").iter().collect();
+    assert_eq!(tags, ["a", "b"]);
+    assert_eq!(req.headers["accept"], "text/plain");
+    assert_eq!(req.params.get("id"), Some("1"));
+    assert_eq!(req.params.get("org"), Some("acme"));
+    assert_eq!(req.extensions.get::<RequestId>(), Some(&RequestId(9)));
+    assert_eq!(req.deadline, Some(deadline));
+    assert_eq!(req.body, "payload");
+}
+
+#[test]
+fn later_calls_replace_earlier_ones() {
+    let req = HttpRequest::builder()
+        .method(Method::POST)
+        .method(Method::DELETE)
+        .uri("/a")
+        .uri("/b")
+        .param("id", "1")
+        .param("id", "2")
+        .body("one")
+        .body("two")
+        .build()
+        .unwrap();
+    assert_eq!(req.method, Method::DELETE);
+    assert_eq!(req.uri, "/b");
+    assert_eq!(req.params.get("id"), Some("2"));
+    assert_eq!(req.params.len(), 1);
+    assert_eq!(req.body, "two");
+}
+
+#[test]
+fn json_sets_the_body_and_content_type() {
+    let req = HttpRequest::builder()
+        .method(Method::POST)
+        .json(&json!({"name": "Ada"}))
+        .build()
+        .unwrap();
+    assert_eq!(req.headers["content-type"], "application/json");
+    assert_eq!(*req.json_value().unwrap(), json!({"name": "Ada"}));
+
+    // A content type set before is kept.
+    let req = HttpRequest::builder()
+        .header("content-type", "application/merge-patch+json")
+        .json(&json!({"name": null}))
+        .build()
+        .unwrap();
+    assert_eq!(req.headers["content-type"], "application/merge-patch+json");
+    assert_eq!(req.body, r#"{"name":null}"#);
+}
+
+#[test]
+fn invalid_parts_fail_the_build() {
+    let invalid_uri = HttpRequest::builder().uri("not a uri").build();
+    assert!(matches!(invalid_uri, Err(RouterError::Http(_))));
+    let invalid_method = HttpRequest::builder().method("GE T").build();
+    assert!(matches!(invalid_method, Err(RouterError::Http(_))));
+    let invalid_name = HttpRequest::builder().header("x tag", "a").build();
+    assert!(matches!(invalid_name, Err(RouterError::Http(_))));
+    let invalid_value = HttpRequest::builder().header("x-tag", "a\nb").build();
+    assert!(matches!(invalid_value, Err(RouterError::Http(_))));
+
+    // JSON maps need string keys.
+    let map = BTreeMap::from([(vec![1u8], 1)]);
+    let unserializable = HttpRequest::builder().json(&map).build();
+    assert!(matches!(unserializable, Err(RouterError::Json { .. })));
+
+    // The first error is kept: later valid calls don't clear it.
+    let first = HttpRequest::builder()
+        .uri("not a uri")
+        .uri("/fine")
+        .body("x")
+        .build();
+    assert!(matches!(first, Err(RouterError::Http(_))));
+}
+
+#[test]
+fn built_requests_reach_handlers_as_set() {
+    struct Greet;
+
+    impl HttpHandler for Greet {
+        fn handle(&self, req: HttpRequest) -> HttpResponse {
+            let name = req.params.get("name").unwrap_or("nobody");
+            HttpResponse::ok(format!("hello {name}"))
+        }
+    }
+
+    let req = HttpRequest::builder()
+        .uri("/greet/ada")
+        .param("name", "ada")
+        .build()
+        .unwrap();
+    let response = Greet.handle(req);
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body.as_bytes().unwrap(), "hello ada");
+}
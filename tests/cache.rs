@@ -0,0 +1,134 @@
+#![cfg(feature = "cache")]
+
+use generic_http_router::testing::MockClock;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Method, Request, Response, StatusCode};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Counts its calls, answering with the request target, padded to 400 bytes.
+struct Counting(Arc<AtomicUsize>);
+
+impl HttpHandler for Counting {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        HttpResponse::ok(format!("{:<400}", req.uri.to_string()))
+    }
+}
+
+fn router(ttl: u64) -> (Router, Arc<AtomicUsize>, MockClock) {
+    let config = format!(
+        r#"{{"endpoints": [
+            {{"method": "GET", "path": "/reports/:id", "description": "",
+              "controller": "reports", "cache_ttl_seconds": {ttl}}},
+            {{"method": "POST", "path": "/reports/:id", "description": "",
+              "controller": "reports", "cache_ttl_seconds": {ttl}}}
+        ]}}"#
+    );
+    let clock = MockClock::new();
+    let mut router = Router::from_json_str(&config)
+        .unwrap()
+        .with_clock(clock.clone());
+    let calls = Arc::new(AtomicUsize::new(0));
+    router.register_handler("reports", Counting(Arc::clone(&calls)));
+    (router, calls, clock)
+}
+
+fn send(router: &Router, method: Method, target: &str) -> Response<bytes::Bytes> {
+    let req = Request::builder()
+        .method(method)
+        .uri(target)
+        .body(Vec::new())
+        .unwrap();
+    router.route(req)
+}
+
+fn x_cache(response: &Response<bytes::Bytes>) -> Option<&str> {
+    response
+        .headers()
+        .get("x-cache")
+        .and_then(|value| value.to_str().ok())
+}
+
+#[test]
+fn second_request_is_a_hit() {
+    let (router, calls, _) = router(30);
+    let first = send(&router, Method::GET, "/reports/1");
+    let second = send(&router, Method::GET, "/reports/1");
+    assert_eq!(x_cache(&first), Some("MISS"));
+    assert_eq!(x_cache(&second), Some("HIT"));
+    assert_eq!(second.body(), first.body());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    send(&router, Method::GET, "/reports/1?page=2");
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn expires_after_the_ttl() {
+    let (router, calls, clock) = router(30);
+    send(&router, Method::GET, "/reports/1");
+    clock.advance(Duration::from_secs(29));
+    assert_eq!(
+        x_cache(&send(&router, Method::GET, "/reports/1")),
+        Some("HIT")
+    );
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(
+        x_cache(&send(&router, Method::GET, "/reports/1")),
+        Some("MISS")
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn evicts_the_least_recently_used_under_the_size_cap() {
+    let (router, calls, _) = router(30);
+    // Room for two of these responses, not three.
+    router.set_cache_max_bytes(1000);
+    send(&router, Method::GET, "/reports/1");
+    send(&router, Method::GET, "/reports/2");
+    send(&router, Method::GET, "/reports/1");
+    send(&router, Method::GET, "/reports/3");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    assert_eq!(
+        x_cache(&send(&router, Method::GET, "/reports/1")),
+        Some("HIT")
+    );
+    assert_eq!(
+        x_cache(&send(&router, Method::GET, "/reports/2")),
+        Some("MISS")
+    );
+}
+
+#[test]
+fn post_is_never_cached() {
+    let (router, calls, _) = router(30);
+    send(&router, Method::POST, "/reports/1");
+    let second = send(&router, Method::POST, "/reports/1");
+    assert_eq!(x_cache(&second), None);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn invalidation_by_path_prefix() {
+    let (router, calls, _) = router(30);
+    send(&router, Method::GET, "/reports/1");
+    router.invalidate_cache("/reports");
+    assert_eq!(
+        x_cache(&send(&router, Method::GET, "/reports/1")),
+        Some("MISS")
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn ttl_past_the_end_of_instant_is_not_cached() {
+    let (router, calls, _) = router(u64::MAX);
+    let response = send(&router, Method::GET, "/reports/1");
+    assert_eq!(response.status(), StatusCode::OK);
+    send(&router, Method::GET, "/reports/1");
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
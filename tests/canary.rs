@@ -0,0 +1,178 @@
+use generic_http_router::testing::SeededRng;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::Request;
+
+struct Named(&'static str);
+
+impl HttpHandler for Named {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok(self.0)
+    }
+}
+
+fn router(seed: u64) -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/orders", "description": "",
+             "canary": {"stable": "orders::v1", "candidate": "orders::v2", "percent": 30}}
+        ]}"#,
+    )
+    .unwrap()
+    .with_rng(SeededRng::new(seed));
+    router.register_handler("orders::v1", Named("v1"));
+    router.register_handler("orders::v2", Named("v2"));
+    router
+}
+
+/// Returns the `X-Canary` header of 200 requests in a row.
+fn assignments(router: &Router) -> Vec<String> {
+    (0..200)
+        .map(|_| {
+            let response = router.route(Request::get("/orders").body(String::new()).unwrap());
+            response.headers()["x-canary"].to_str().unwrap().to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn a_seeded_random_split_is_reproducible() {
+    let first = assignments(&router(7));
+    assert_eq!(first, assignments(&router(7)));
+    assert_ne!(first, assignments(&router(8)));
+
+    let candidates = first.iter().filter(|v| *v == "candidate").count();
+    // 30% of 200, give or take what 200 draws can stray.
+    assert!((40..=80).contains(&candidates), "{candidates}");
+}
+
+/// Keys and the bucket, from 0 to 99, they hash to: a key goes to the
+/// candidate when its bucket is below the percentage.
+const KEYS: [(&str, u8); 12] = [
+    ("user-1", 8),
+    ("user-2", 41),
+    ("user-3", 30),
+    ("user-4", 75),
+    ("user-5", 64),
+    ("user-6", 97),
+    ("user-7", 86),
+    ("user-8", 31),
+    ("user-9", 20),
+    ("user-10", 80),
+    ("user-11", 91),
+    ("user-12", 2),
+];
+
+/// The configuration of `GET /orders`, split by `key` at `percent`.
+fn keyed_config(percent: u8, key: &str) -> String {
+    format!(
+        r#"{{"endpoints": [
+            {{"method": "GET", "path": "/orders", "description": "",
+             "canary": {{"stable": "orders::v1", "candidate": "orders::v2",
+                        "percent": {percent}, "key": {key}}}}}
+        ]}}"#
+    )
+}
+
+fn keyed_router(percent: u8, key: &str) -> Router {
+    let mut router = Router::from_json_str(&keyed_config(percent, key)).unwrap();
+    router.register_handler("orders::v1", Named("v1"));
+    router.register_handler("orders::v2", Named("v2"));
+    router
+}
+
+/// The `X-Canary` header and body of the response to `req`.
+fn assigned(router: &Router, req: http::request::Builder) -> (String, String) {
+    let response = router.route(req.uri("/orders").body(String::new()).unwrap());
+    let variant = response.headers()["x-canary"].to_str().unwrap().to_string();
+    let body = String::from_utf8(response.body().to_vec()).unwrap();
+    (variant, body)
+}
+
+fn by_user(router: &Router, user: &str) -> String {
+    let (variant, body) = assigned(router, Request::builder().header("x-user-id", user));
+    // The header names the controller that answered.
+    assert_eq!(body, if variant == "candidate" { "v2" } else { "v1" });
+    variant
+}
+
+#[test]
+fn a_header_keyed_split_follows_the_key_table() {
+    for percent in [5, 30, 31, 50, 90] {
+        let router = keyed_router(percent, r#"{"header": "X-User-Id"}"#);
+        for (user, bucket) in KEYS {
+            let expected = if bucket < percent {
+                "candidate"
+            } else {
+                "stable"
+            };
+            assert_eq!(by_user(&router, user), expected, "{user} at {percent}%");
+            // The same key is always assigned the same way.
+            assert_eq!(by_user(&router, user), expected, "{user} at {percent}%");
+        }
+    }
+}
+
+#[test]
+fn a_header_keyed_split_sends_about_the_percentage() {
+    let router = keyed_router(5, r#"{"header": "X-User-Id"}"#);
+    let candidates = (0..2000)
+        .filter(|i| by_user(&router, &format!("customer-{i}")) == "candidate")
+        .count();
+    // 5% of 2000 is 100.
+    assert!((60..=140).contains(&candidates), "{candidates}");
+}
+
+#[test]
+fn requests_without_the_key_go_to_the_stable_controller() {
+    let router = keyed_router(90, r#"{"header": "X-User-Id"}"#);
+    assert_eq!(assigned(&router, Request::builder()).0, "stable");
+
+    let router = keyed_router(90, r#"{"cookie": "uid"}"#);
+    assert_eq!(assigned(&router, Request::builder()).0, "stable");
+    let other_cookie = Request::builder().header("cookie", "theme=dark");
+    assert_eq!(assigned(&router, other_cookie).0, "stable");
+}
+
+#[test]
+fn a_cookie_keyed_split_hashes_the_cookie_value() {
+    let router = keyed_router(30, r#"{"cookie": "uid"}"#);
+    for (user, bucket) in KEYS {
+        let expected = if bucket < 30 { "candidate" } else { "stable" };
+        let req = Request::builder().header("cookie", format!("theme=dark; uid={user}"));
+        assert_eq!(assigned(&router, req).0, expected, "{user}");
+    }
+}
+
+#[test]
+fn zero_and_a_hundred_percent_short_circuit() {
+    for key in [r#"{"header": "X-User-Id"}"#, r#""random""#] {
+        let router = keyed_router(0, key);
+        for (user, _) in KEYS {
+            assert_eq!(by_user(&router, user), "stable", "{key}");
+        }
+        // Even without a key.
+        assert_eq!(assigned(&router, Request::builder()).0, "stable");
+
+        let router = keyed_router(100, key);
+        for (user, _) in KEYS {
+            assert_eq!(by_user(&router, user), "candidate", "{key}");
+        }
+        assert_eq!(assigned(&router, Request::builder()).0, "candidate");
+    }
+}
+
+#[test]
+fn reloading_a_new_percentage_keeps_the_handlers() {
+    let path = std::path::PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("canary_reload.json");
+    std::fs::write(&path, keyed_config(5, r#"{"header": "X-User-Id"}"#)).unwrap();
+    let mut router = Router::new(&path).unwrap();
+    router.register_handler("orders::v1", Named("v1"));
+    router.register_handler("orders::v2", Named("v2"));
+    assert_eq!(by_user(&router, "user-9"), "stable");
+
+    std::fs::write(&path, keyed_config(50, r#"{"header": "X-User-Id"}"#)).unwrap();
+    router.reload(&path).unwrap();
+    assert_eq!(by_user(&router, "user-9"), "candidate");
+    assert_eq!(by_user(&router, "user-2"), "candidate");
+    assert_eq!(by_user(&router, "user-4"), "stable");
+}
@@ -0,0 +1,303 @@
+use generic_http_router::health::HealthConfig;
+use generic_http_router::{
+    CanonicalizeConfig, HttpHandler, HttpRequest, HttpResponse, RequestContext, Router, TlsInfo,
+};
+use http::{Method, Request, Response, StatusCode};
+
+struct Ok;
+
+impl HttpHandler for Ok {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok("ok")
+    }
+}
+
+/// A router with `/users` and `/status`, canonicalizing with `config`.
+fn router_with(config: CanonicalizeConfig) -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "ANY", "path": "/users", "description": "", "controller": "ok"},
+            {"method": "GET", "path": "/status", "description": "", "controller": "ok"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("ok", Ok);
+    router.set_canonicalization(config);
+    router
+}
+
+fn request(method: Method, uri: &str, headers: &[(&str, &str)]) -> Request<String> {
+    let mut req = Request::builder().method(method).uri(uri);
+    for &(name, value) in headers {
+        req = req.header(name, value);
+    }
+    req.body("payload".to_string()).unwrap()
+}
+
+fn get(router: &Router, uri: &str, headers: &[(&str, &str)]) -> Response<bytes::Bytes> {
+    router.route(request(Method::GET, uri, headers))
+}
+
+fn assert_redirect(response: &Response<bytes::Bytes>, status: StatusCode, location: &str) {
+    assert_eq!(response.status(), status);
+    assert_eq!(response.headers()["location"], location);
+    assert!(response.body().is_empty());
+}
+
+fn https() -> CanonicalizeConfig {
+    CanonicalizeConfig {
+        https: true,
+        permanent: true,
+        ..CanonicalizeConfig::default()
+    }
+}
+
+fn host() -> CanonicalizeConfig {
+    CanonicalizeConfig {
+        host: Some("www.example.com".to_string()),
+        permanent: true,
+        ..CanonicalizeConfig::default()
+    }
+}
+
+#[test]
+fn plain_http_is_redirected_to_https() {
+    let router = router_with(https());
+    let response = get(
+        &router,
+        "/users?page=2&sort=name",
+        &[("host", "example.com"), ("x-forwarded-proto", "http")],
+    );
+    assert_redirect(
+        &response,
+        StatusCode::MOVED_PERMANENTLY,
+        "https://example.com/users?page=2&sort=name",
+    );
+
+    // `Forwarded` is read too, and wins over `X-Forwarded-Proto`.
+    let response = get(
+        &router,
+        "/users",
+        &[
+            ("host", "example.com"),
+            ("forwarded", "for=192.0.2.60;proto=http"),
+            ("x-forwarded-proto", "https"),
+        ],
+    );
+    assert_redirect(
+        &response,
+        StatusCode::MOVED_PERMANENTLY,
+        "https://example.com/users",
+    );
+
+    // So is the connection, without forwarding headers.
+    let response = router.route_with_context(
+        request(Method::GET, "/users", &[("host", "example.com")]),
+        RequestContext::new(),
+    );
+    assert_redirect(
+        &response,
+        StatusCode::MOVED_PERMANENTLY,
+        "https://example.com/users",
+    );
+    // A request whose scheme can't be told isn't redirected, so that it
+    // can't loop.
+    let response = get(&router, "/users", &[("host", "example.com")]);
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[test]
+fn the_status_depends_on_permanent_and_the_method() {
+    for (permanent, method, status) in [
+        (true, Method::GET, StatusCode::MOVED_PERMANENTLY),
+        (true, Method::HEAD, StatusCode::MOVED_PERMANENTLY),
+        (true, Method::POST, StatusCode::PERMANENT_REDIRECT),
+        (false, Method::GET, StatusCode::FOUND),
+        (false, Method::PUT, StatusCode::TEMPORARY_REDIRECT),
+    ] {
+        let router = router_with(CanonicalizeConfig {
+            permanent,
+            ..https()
+        });
+        let response = router.route(request(
+            method.clone(),
+            "/users",
+            &[("host", "example.com"), ("x-forwarded-proto", "http")],
+        ));
+        assert_eq!(response.status(), status, "{permanent} {method}");
+        assert_eq!(response.headers()["location"], "https://example.com/users");
+    }
+}
+
+#[test]
+fn another_host_is_redirected_to_the_canonical_one() {
+    let router = router_with(host());
+    let response = get(
+        &router,
+        "/users?q=1",
+        &[("host", "example.com"), ("x-forwarded-proto", "https")],
+    );
+    assert_redirect(
+        &response,
+        StatusCode::MOVED_PERMANENTLY,
+        "https://www.example.com/users?q=1",
+    );
+    // The scheme is kept without `https`.
+    let response = get(&router, "/users", &[("host", "example.com")]);
+    assert_redirect(
+        &response,
+        StatusCode::MOVED_PERMANENTLY,
+        "http://www.example.com/users",
+    );
+    // The forwarded host is the one compared.
+    let response = get(
+        &router,
+        "/users",
+        &[
+            ("host", "www.example.com"),
+            ("x-forwarded-host", "old.example.com"),
+        ],
+    );
+    assert_redirect(
+        &response,
+        StatusCode::MOVED_PERMANENTLY,
+        "http://www.example.com/users",
+    );
+}
+
+#[test]
+fn scheme_and_host_are_redirected_at_once() {
+    let router = router_with(CanonicalizeConfig {
+        https: true,
+        ..host()
+    });
+    let response = get(
+        &router,
+        "/users?q=1",
+        &[("host", "example.com"), ("x-forwarded-proto", "http")],
+    );
+    assert_redirect(
+        &response,
+        StatusCode::MOVED_PERMANENTLY,
+        "https://www.example.com/users?q=1",
+    );
+    // Only the host is wrong.
+    let response = get(
+        &router,
+        "/users",
+        &[("host", "example.com"), ("x-forwarded-proto", "https")],
+    );
+    assert_redirect(
+        &response,
+        StatusCode::MOVED_PERMANENTLY,
+        "https://www.example.com/users",
+    );
+    // Only the scheme is wrong.
+    let response = get(
+        &router,
+        "/users",
+        &[("host", "www.example.com"), ("x-forwarded-proto", "http")],
+    );
+    assert_redirect(
+        &response,
+        StatusCode::MOVED_PERMANENTLY,
+        "https://www.example.com/users",
+    );
+}
+
+#[test]
+fn exempt_paths_are_never_redirected() {
+    let mut router = router_with(CanonicalizeConfig {
+        https: true,
+        exempt_paths: vec!["/status".to_string()],
+        ..host()
+    });
+    router.enable_health_endpoints(HealthConfig::default());
+    let headers = [("host", "example.com"), ("x-forwarded-proto", "http")];
+    for path in ["/status", "/healthz", "/readyz"] {
+        assert_eq!(
+            get(&router, path, &headers).status(),
+            StatusCode::OK,
+            "{path}"
+        );
+    }
+    // Only the exact path is exempt.
+    assert_eq!(
+        get(&router, "/status/", &headers).status(),
+        StatusCode::MOVED_PERMANENTLY
+    );
+    assert_eq!(
+        get(&router, "/users", &headers).status(),
+        StatusCode::MOVED_PERMANENTLY
+    );
+}
+
+#[test]
+fn canonical_requests_are_routed() {
+    let router = router_with(CanonicalizeConfig {
+        https: true,
+        ..host()
+    });
+    for headers in [
+        &[("host", "www.example.com"), ("x-forwarded-proto", "https")][..],
+        &[("host", "WWW.Example.com"), ("x-forwarded-proto", "HTTPS")],
+        &[("host", "www.example.com"), ("forwarded", "proto=https")],
+    ] {
+        let response = get(&router, "/users", headers);
+        assert_eq!(response.status(), StatusCode::OK, "{headers:?}");
+        assert_eq!(response.body(), "ok");
+    }
+    // A TLS connection without forwarding headers.
+    let response = router.route_with_context(
+        request(Method::GET, "/users", &[("host", "www.example.com")]),
+        RequestContext::new().with_tls_info(TlsInfo::default()),
+    );
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Following a redirect never leads to another one.
+    let redirect = get(
+        &router,
+        "/users?q=1",
+        &[("host", "example.com"), ("x-forwarded-proto", "http")],
+    );
+    let location: http::Uri = redirect.headers()["location"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    let followed = get(
+        &router,
+        location.path_and_query().unwrap().as_str(),
+        &[
+            ("host", location.host().unwrap()),
+            ("x-forwarded-proto", location.scheme_str().unwrap()),
+        ],
+    );
+    assert_eq!(followed.status(), StatusCode::OK);
+}
+
+#[test]
+fn a_request_without_a_host_is_only_redirected_to_the_canonical_host() {
+    // With a canonical host, a request without a host is redirected to it.
+    let router = router_with(host());
+    let response = router.route(
+        Request::get("/users")
+            .header("x-forwarded-proto", "https")
+            .body(String::new())
+            .unwrap(),
+    );
+    assert_redirect(
+        &response,
+        StatusCode::MOVED_PERMANENTLY,
+        "https://www.example.com/users",
+    );
+    // Without one, there's nowhere to redirect to.
+    let router = router_with(https());
+    let response = router.route(
+        Request::get("/users")
+            .header("x-forwarded-proto", "http")
+            .body(String::new())
+            .unwrap(),
+    );
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,148 @@
+use generic_http_router::{BodyError, HttpRequest};
+
+fn request(content_type: Option<&str>, body: &[u8]) -> HttpRequest {
+    let mut builder = HttpRequest::builder().method("POST").uri("/notes");
+    if let Some(content_type) = content_type {
+        builder = builder.header("content-type", content_type);
+    }
+    builder.body(body.to_vec()).build().unwrap()
+}
+
+#[test]
+fn utf8_bodies() {
+    let req = request(Some("application/json"), "{\"name\":\"Zoë\"}".as_bytes());
+    assert_eq!(req.text().unwrap(), "{\"name\":\"Zoë\"}");
+    let req = request(Some("text/plain; charset=UTF-8"), b"caf\xc3\xa9");
+    assert_eq!(req.text().unwrap(), "café");
+    assert_eq!(request(None, b"plain").text().unwrap(), "plain");
+}
+
+#[test]
+fn invalid_utf8_is_an_error_unless_lossy() {
+    let req = request(Some("application/json; charset=utf-8"), b"ab\xffcd");
+    assert_eq!(
+        req.text(),
+        Err(BodyError::InvalidEncoding {
+            charset: "UTF-8",
+            offset: 2
+        })
+    );
+    assert_eq!(req.text_lossy().unwrap(), "ab\u{fffd}cd");
+}
+
+#[test]
+fn latin1_bodies() {
+    let req = request(Some("text/plain; charset=ISO-8859-1"), b"caf\xe9 \xa4");
+    assert_eq!(req.text().unwrap(), "café ¤");
+    // Text without a charset is ISO-8859-1.
+    let req = request(Some("text/csv"), b"Gr\xfc\xdfe");
+    assert_eq!(req.text().unwrap(), "Grüße");
+    let req = request(Some("text/plain; charset=latin1"), b"\xe9");
+    assert_eq!(req.text().unwrap(), "é");
+}
+
+#[test]
+fn ascii_bodies() {
+    let req = request(Some("text/plain; charset=us-ascii"), b"ok\x80");
+    assert_eq!(
+        req.text(),
+        Err(BodyError::InvalidEncoding {
+            charset: "US-ASCII",
+            offset: 2
+        })
+    );
+    assert_eq!(req.text_lossy().unwrap(), "ok\u{fffd}");
+}
+
+#[test]
+fn a_bogus_charset_is_unsupported() {
+    let req = request(Some("text/plain; charset=klingon-8"), b"Qapla'");
+    assert_eq!(
+        req.text(),
+        Err(BodyError::UnsupportedCharset("klingon-8".to_string()))
+    );
+    assert_eq!(
+        req.text_lossy(),
+        Err(BodyError::UnsupportedCharset("klingon-8".to_string()))
+    );
+}
+
+#[cfg(not(feature = "encoding"))]
+#[test]
+fn legacy_encodings_need_the_encoding_feature() {
+    let req = request(Some("text/plain; charset=windows-1252"), b"\x80");
+    assert_eq!(
+        req.text(),
+        Err(BodyError::UnsupportedCharset("windows-1252".to_string()))
+    );
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn windows_1252_bodies() {
+    let req = request(
+        Some("text/plain; charset=windows-1252"),
+        b"\x93quoted\x94 \x80 5 \x85 caf\xe9 \x81",
+    );
+    assert_eq!(
+        req.text().unwrap(),
+        "\u{201c}quoted\u{201d} € 5 … café \u{81}"
+    );
+    let req = request(Some("text/plain; charset=cp1252"), b"\x9f");
+    assert_eq!(req.text().unwrap(), "Ÿ");
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn iso_8859_15_bodies() {
+    let req = request(
+        Some("text/plain; charset=ISO-8859-15"),
+        b"\xa4 \xa6 \xbd \xe9",
+    );
+    assert_eq!(req.text().unwrap(), "€ Š œ é");
+    let req = request(Some("text/plain; charset=latin-9"), b"\xbe");
+    assert_eq!(req.text().unwrap(), "Ÿ");
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn utf16_bodies() {
+    // "hé😀" in both byte orders, the emoji as a surrogate pair.
+    let le = b"h\x00\xe9\x00\x3d\xd8\x00\xde";
+    let be = b"\x00h\x00\xe9\xd8\x3d\xde\x00";
+    let req = request(Some("text/plain; charset=utf-16le"), le);
+    assert_eq!(req.text().unwrap(), "hé😀");
+    let req = request(Some("text/plain; charset=UTF-16BE"), be);
+    assert_eq!(req.text().unwrap(), "hé😀");
+
+    // UTF-16 has the byte order of its byte order mark, or else big-endian.
+    let mut marked = b"\xff\xfe".to_vec();
+    marked.extend_from_slice(le);
+    let req = request(Some("application/json; charset=utf-16"), &marked);
+    assert_eq!(req.text().unwrap(), "hé😀");
+    let req = request(Some("application/json; charset=utf-16"), be);
+    assert_eq!(req.text().unwrap(), "hé😀");
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn invalid_utf16() {
+    // An unpaired high surrogate, then a trailing odd byte.
+    let req = request(Some("text/plain; charset=utf-16le"), b"a\x00\x3d\xd8b\x00c");
+    assert_eq!(
+        req.text(),
+        Err(BodyError::InvalidEncoding {
+            charset: "UTF-16LE",
+            offset: 2
+        })
+    );
+    assert_eq!(req.text_lossy().unwrap(), "a\u{fffd}b\u{fffd}");
+    let req = request(Some("text/plain; charset=utf-16"), b"\xfe\xff\x00ab");
+    assert_eq!(
+        req.text(),
+        Err(BodyError::InvalidEncoding {
+            charset: "UTF-16",
+            offset: 4
+        })
+    );
+}
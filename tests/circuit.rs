@@ -0,0 +1,67 @@
+use generic_http_router::circuit::CircuitState;
+use generic_http_router::testing::MockClock;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router, RouterError};
+use http::{Request, StatusCode};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Fails with a 500 while `failing` is set.
+struct Flaky(Arc<AtomicBool>);
+
+impl HttpHandler for Flaky {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        if self.0.load(Ordering::SeqCst) {
+            HttpResponse::internal_error("down")
+        } else {
+            HttpResponse::ok("up")
+        }
+    }
+}
+
+fn config(open_for_ms: u64) -> String {
+    format!(
+        r#"{{"endpoints": [
+            {{"method": "GET", "path": "/payments", "description": "",
+             "controller": "payments::list",
+             "circuit_breaker": {{"failure_threshold": 0.5, "min_requests": 2,
+                                 "open_for_ms": {open_for_ms}}}}}
+        ]}}"#
+    )
+}
+
+fn get(router: &Router) -> StatusCode {
+    router
+        .route(Request::get("/payments").body(String::new()).unwrap())
+        .status()
+}
+
+#[test]
+fn circuit_opens_and_recovers_after_open_for_ms() {
+    let failing = Arc::new(AtomicBool::new(true));
+    let clock = MockClock::new();
+    let mut router = Router::from_json_str(&config(30_000)).unwrap();
+    router.register_handler("payments::list", Flaky(Arc::clone(&failing)));
+    router.set_clock(clock.clone());
+
+    assert_eq!(get(&router), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(get(&router), StatusCode::INTERNAL_SERVER_ERROR);
+    let rejected = router.route(Request::get("/payments").body(String::new()).unwrap());
+    assert_eq!(rejected.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(rejected.headers().contains_key("retry-after"));
+    assert_eq!(router.stats().circuits[0].state, CircuitState::Open);
+
+    failing.store(false, Ordering::SeqCst);
+    clock.advance(Duration::from_secs(29));
+    assert_eq!(get(&router), StatusCode::SERVICE_UNAVAILABLE);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(get(&router), StatusCode::OK);
+    assert_eq!(router.stats().circuits[0].state, CircuitState::Closed);
+}
+
+#[test]
+fn open_for_ms_past_a_year_is_rejected() {
+    let error = Router::from_json_str(&config(u64::MAX)).err().unwrap();
+    assert!(matches!(error, RouterError::Endpoint { .. }), "{error}");
+    assert!(Router::from_json_str(&config(365 * 24 * 60 * 60 * 1000)).is_ok());
+}
@@ -0,0 +1,107 @@
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Method, Request, StatusCode};
+
+/// Answers with a value that mustn't show up in the router's `Debug` output.
+struct Secret(&'static str);
+
+impl HttpHandler for Secret {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let id = req.params.get("id").unwrap_or("-");
+        HttpResponse::ok(format!("{} {id}", self.0))
+    }
+}
+
+/// A template with `users::list` and `users::get` registered, and
+/// `users::create` left for each copy to register.
+fn template() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/users", "description": "", "controller": "users::list"},
+            {"method": "GET", "path": "/users/:id", "description": "", "controller": "users::get"},
+            {"method": "POST", "path": "/users", "description": "", "controller": "users::create"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("users::list", Secret("hunter2"));
+    router.register_handler("users::get", Secret("hunter2"));
+    router
+}
+
+const REQUESTS: [(Method, &str); 6] = [
+    (Method::GET, "/users"),
+    (Method::GET, "/users/7"),
+    (Method::HEAD, "/users/7"),
+    (Method::POST, "/users"),
+    (Method::DELETE, "/users"),
+    (Method::GET, "/orders"),
+];
+
+fn send(router: &Router, method: &Method, path: &str) -> http::Response<bytes::Bytes> {
+    router.route(
+        Request::builder()
+            .method(method.clone())
+            .uri(path)
+            .body(String::new())
+            .unwrap(),
+    )
+}
+
+#[test]
+fn a_clone_routes_identically() {
+    let router = template();
+    let clone = router.clone();
+    for (method, path) in &REQUESTS {
+        let (original, copy) = (send(&router, method, path), send(&clone, method, path));
+        assert_eq!(original.status(), copy.status(), "{method} {path}");
+        assert_eq!(original.headers(), copy.headers(), "{method} {path}");
+        assert_eq!(original.body(), copy.body(), "{method} {path}");
+    }
+    assert_eq!(send(&clone, &Method::GET, "/users/7").body(), "hunter2 7");
+    assert_eq!(
+        send(&clone, &Method::DELETE, "/users").status(),
+        StatusCode::METHOD_NOT_ALLOWED
+    );
+}
+
+#[test]
+fn registering_on_a_clone_leaves_the_original_alone() {
+    let router = template();
+    let mut clone = router.clone();
+    clone.register_handler("users::create", Secret("created"));
+    clone.register_handler("users::get", Secret("replaced"));
+    assert_eq!(send(&clone, &Method::POST, "/users").body(), "created -");
+    assert_eq!(send(&clone, &Method::GET, "/users/7").body(), "replaced 7");
+
+    assert_eq!(
+        send(&router, &Method::POST, "/users").status(),
+        StatusCode::NOT_IMPLEMENTED
+    );
+    assert_eq!(send(&router, &Method::GET, "/users/7").body(), "hunter2 7");
+
+    // And the other way round.
+    let mut router = router;
+    router.register_handler("users::list", Secret("relisted"));
+    assert_eq!(send(&clone, &Method::GET, "/users").body(), "hunter2 -");
+}
+
+#[test]
+fn debug_summarizes_routes_and_handler_names() {
+    let router = template();
+    assert_eq!(
+        format!("{router:?}"),
+        "Router { routes: {\"GET\": 2, \"POST\": 1}, rewrites: 0, \
+         handlers: [\"users::get\", \"users::list\"], prefix_handlers: [], factories: [], \
+         guards: [], api_keys: None, missing_handler_policy: NotImplemented, \
+         maintenance: Off, .. }"
+    );
+    // The handlers' internals aren't shown.
+    assert!(!format!("{router:#?}").contains("hunter2"));
+
+    // A clone shows what it registered.
+    let mut clone = router.clone();
+    clone.register_handler("users::create", Secret("created"));
+    assert!(
+        format!("{clone:?}")
+            .contains("handlers: [\"users::create\", \"users::get\", \"users::list\"]")
+    );
+}
@@ -0,0 +1,110 @@
+#![cfg(feature = "async")]
+
+use generic_http_router::async_handler::AsyncFn;
+use generic_http_router::{CoalesceOptions, HttpRequest, HttpResponse, Router};
+use http::Request;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// A router whose `GET /report` coalesces its requests. Each run of the
+/// handler is counted and waits for a permit of `release`; the first one
+/// panics if `panic_first`.
+fn router(release: &Arc<Semaphore>, runs: &Arc<AtomicUsize>, panic_first: bool) -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/report", "description": "", "controller": "report",
+             "coalesce": true}
+        ]}"#,
+    )
+    .unwrap();
+    let (release, runs) = (Arc::clone(release), Arc::clone(runs));
+    router.register_handler(
+        "report",
+        AsyncFn(move |_req: HttpRequest| {
+            let (release, runs) = (Arc::clone(&release), Arc::clone(&runs));
+            async move {
+                let run = runs.fetch_add(1, Ordering::SeqCst) + 1;
+                release.acquire().await.unwrap().forget();
+                if panic_first && run == 1 {
+                    panic!("the first run fails");
+                }
+                HttpResponse::ok(format!("run {run}"))
+            }
+        }),
+    );
+    router
+}
+
+fn spawn_get(router: &Arc<Router>) -> tokio::task::JoinHandle<String> {
+    let router = Arc::clone(router);
+    tokio::spawn(async move {
+        let req = Request::get("/report").body(String::new()).unwrap();
+        let response = router.route_async(req).await;
+        String::from_utf8(response.body().to_vec()).unwrap()
+    })
+}
+
+/// Waits for the handler to have run `count` times.
+async fn wait_for_runs(runs: &AtomicUsize, count: usize) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while runs.load(Ordering::SeqCst) < count {
+            tokio::task::yield_now().await;
+        }
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn waiting_requests_get_the_response_once_it_lands() {
+    let (release, runs) = (Arc::new(Semaphore::new(0)), Arc::new(AtomicUsize::new(0)));
+    let router = Arc::new(router(&release, &runs, false));
+    let leader = spawn_get(&router);
+    wait_for_runs(&runs, 1).await;
+    let followers: Vec<_> = (0..4).map(|_| spawn_get(&router)).collect();
+    // Lets the followers join the flight.
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    release.add_permits(1);
+    assert_eq!(leader.await.unwrap(), "run 1");
+    for follower in followers {
+        assert_eq!(follower.await.unwrap(), "run 1");
+    }
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn a_waiting_request_runs_the_handler_of_an_abandoned_flight() {
+    let (release, runs) = (Arc::new(Semaphore::new(0)), Arc::new(AtomicUsize::new(0)));
+    let router = Arc::new(router(&release, &runs, true));
+    let leader = spawn_get(&router);
+    wait_for_runs(&runs, 1).await;
+    let follower = spawn_get(&router);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // The leader's handler panics, and the follower is woken to run it.
+    release.add_permits(2);
+    assert!(leader.await.unwrap_err().is_panic());
+    assert_eq!(follower.await.unwrap(), "run 2");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn waiting_without_a_limit() {
+    let (release, runs) = (Arc::new(Semaphore::new(0)), Arc::new(AtomicUsize::new(0)));
+    let mut router = router(&release, &runs, false);
+    router.set_coalescing(CoalesceOptions {
+        max_wait: Duration::MAX,
+        ..CoalesceOptions::default()
+    });
+    let router = Arc::new(router);
+    let leader = spawn_get(&router);
+    wait_for_runs(&runs, 1).await;
+    let follower = spawn_get(&router);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    release.add_permits(1);
+    assert_eq!(leader.await.unwrap(), "run 1");
+    assert_eq!(follower.await.unwrap(), "run 1");
+}
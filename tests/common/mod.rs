@@ -0,0 +1,18 @@
+//! Helpers shared by the integration tests.
+
+use std::path::PathBuf;
+
+/// Compares `rendered` with the golden file `file`, relative to `tests`, or
+/// writes it with `UPDATE_SNAPSHOTS` set. Review the diff of the files
+/// rewritten after an intended change.
+pub fn assert_snapshot(file: &str, rendered: &str) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", file].iter().collect();
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, rendered).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("{}: {e}; run with UPDATE_SNAPSHOTS=1", path.display()));
+    assert_eq!(rendered, expected, "the output differs from {file}");
+}
@@ -0,0 +1,172 @@
+use generic_http_router::compiled::MatchOutcome;
+use generic_http_router::config::Config;
+use generic_http_router::testing::SeededRng;
+use generic_http_router::{CompiledRoutes, Rng, Router};
+use http::{Method, Request, StatusCode};
+
+const CONFIG: &str = r#"{
+    "endpoints": [
+        {"method": "GET", "path": "/", "description": "", "controller": "home"},
+        {"method": "GET", "path": "/users", "description": "", "controller": "users::list"},
+        {"method": "POST", "path": "/users", "description": "", "controller": "users::create"},
+        {"method": "GET", "path": "/users/:id", "description": "", "controller": "users::get"},
+        {"method": "GET", "path": "/users/me", "description": "", "controller": "users::me"},
+        {"method": "DELETE", "path": "/users/:id", "description": "", "controller": "users::delete"},
+        {"method": "GET", "path": "/users/:id/orders/:order", "description": "", "controller": "orders::get"},
+        {"method": "PUT", "path": "/files/*path", "description": "", "controller": "files::put"},
+        {"method": "GET", "path": "/v2/*rest", "description": "", "controller": "v2"}
+    ],
+    "rewrites": [
+        {"match": "/legacy/*rest", "to": "/v2/*rest"}
+    ]
+}"#;
+
+/// The segments generated paths are made of: route segments, parameter
+/// values, dot segments and percent-encodings, valid or not.
+const SEGMENTS: [&str; 16] = [
+    "", "users", "me", "7", "orders", "42", "files", "a%20b", "%C3%A9", "%2F", "%zz", "%FF", "..",
+    ".", "legacy", "v2",
+];
+
+const METHODS: [Method; 5] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::DELETE,
+    Method::PATCH,
+];
+
+/// Generates a path of up to 5 segments of `SEGMENTS`.
+fn arbitrary_path(rng: &SeededRng) -> String {
+    let mut path = String::new();
+    for _ in 0..1 + rng.next_u64() % 5 {
+        path.push('/');
+        path.push_str(SEGMENTS[rng.next_u64() as usize % SEGMENTS.len()]);
+    }
+    path
+}
+
+fn sample_paths(seed: u64, count: usize) -> Vec<String> {
+    let rng = SeededRng::new(seed);
+    (0..count).map(|_| arbitrary_path(&rng)).collect()
+}
+
+/// `CompiledRoutes::resolve` is how a `Router` with the default settings
+/// resolves a request, for any generated method and path.
+#[test]
+fn compiled_routes_resolve_as_the_router_does() {
+    let router = Router::from_json_str(CONFIG).unwrap();
+    let compiled = CompiledRoutes::new(&serde_json::from_str::<Config>(CONFIG).unwrap()).unwrap();
+    let rng = SeededRng::new(131);
+    let mut outcomes = [0; 4];
+    for _ in 0..2000 {
+        let method = &METHODS[rng.next_u64() as usize % METHODS.len()];
+        let path = arbitrary_path(&rng);
+        let req = Request::builder()
+            .method(method.clone())
+            .uri(&path)
+            .body(())
+            .unwrap();
+        let resolution = router.resolve(&req);
+        match compiled.resolve(method, &path) {
+            MatchOutcome::Matched {
+                pattern,
+                controller,
+                params,
+            } => {
+                outcomes[0] += 1;
+                assert_eq!(resolution.pattern(), Some(&*pattern), "{method} {path}");
+                assert_eq!(
+                    resolution.controller(),
+                    Some(&*controller),
+                    "{method} {path}"
+                );
+                let routed: Vec<(String, String)> = resolution
+                    .params()
+                    .unwrap()
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect();
+                assert_eq!(routed, params, "{method} {path}");
+            }
+            MatchOutcome::MethodNotAllowed { .. } => {
+                outcomes[1] += 1;
+                let status = resolution.status();
+                assert_eq!(
+                    status,
+                    Some(StatusCode::METHOD_NOT_ALLOWED),
+                    "{method} {path}"
+                );
+            }
+            MatchOutcome::NotFound => {
+                outcomes[2] += 1;
+                let status = resolution.status();
+                assert_eq!(status, Some(StatusCode::NOT_FOUND), "{method} {path}");
+            }
+            MatchOutcome::InvalidPath => {
+                outcomes[3] += 1;
+                let status = resolution.status();
+                assert_eq!(status, Some(StatusCode::BAD_REQUEST), "{method} {path}");
+            }
+            other => panic!("{method} {path}: {other:?}"),
+        }
+    }
+    // Every outcome was generated.
+    assert!(outcomes.iter().all(|&count| count > 0), "{outcomes:?}");
+}
+
+/// `diff` reports exactly the generated requests resolved differently, and
+/// nothing for a configuration compared with itself.
+#[test]
+fn diff_reports_exactly_the_changed_resolutions() {
+    let before = serde_json::from_str::<Config>(CONFIG).unwrap();
+    let mut after = before.clone();
+    // Drop `GET /users/me`, so `/users/me` falls back to `/users/:id`, and
+    // retarget the delete.
+    after
+        .endpoints
+        .retain(|endpoint| endpoint.path != "/users/me");
+    for endpoint in &mut after.endpoints {
+        if endpoint.method == Method::DELETE {
+            endpoint.controller = "users::archive".to_string();
+        }
+    }
+    let (before, after) = (
+        CompiledRoutes::new(&before).unwrap(),
+        CompiledRoutes::new(&after).unwrap(),
+    );
+    let paths = sample_paths(131, 500);
+    assert!(before.diff(&before, &paths).is_empty());
+
+    let changes = before.diff(&after, &paths);
+    assert!(!changes.is_empty());
+    let mut expected = Vec::new();
+    for path in &paths {
+        for method in [Method::DELETE, Method::GET, Method::POST, Method::PUT] {
+            if before.resolve(&method, path) != after.resolve(&method, path) {
+                expected.push((method, path.clone()));
+            }
+        }
+    }
+    let changed: Vec<_> = changes
+        .iter()
+        .map(|change| (change.method.clone(), change.path.clone()))
+        .collect();
+    assert_eq!(changed, expected);
+    for change in &changes {
+        assert_eq!(change.before, before.resolve(&change.method, &change.path));
+        assert_eq!(change.after, after.resolve(&change.method, &change.path));
+        assert!(matches!(change.method, Method::GET | Method::DELETE));
+    }
+
+    // Compared the other way, the changes are the same, before and after
+    // swapped.
+    let reversed = after.diff(&before, &paths);
+    assert_eq!(reversed.len(), changes.len());
+    for (reversed, change) in reversed.iter().zip(&changes) {
+        assert_eq!(
+            (&reversed.before, &reversed.after),
+            (&change.after, &change.before)
+        );
+    }
+}
@@ -0,0 +1,204 @@
+#[cfg(feature = "async")]
+use generic_http_router::DeadlineFormat;
+#[cfg(feature = "async")]
+use generic_http_router::async_handler::AsyncFn;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+#[cfg(feature = "async")]
+use http::header::HeaderName;
+use http::{Request, StatusCode};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "async")]
+use std::time::Duration;
+#[cfg(feature = "async")]
+use tokio::sync::Barrier;
+
+/// A router whose `GET /slow` runs at most `max` handlers and queues
+/// `max_queue` requests, with deadlines in `X-Request-Deadline-Ms`. Each
+/// handler waits on `started`, then `release`.
+#[cfg(feature = "async")]
+fn router(max: u32, max_queue: u32, started: &Arc<Barrier>, release: &Arc<Barrier>) -> Arc<Router> {
+    let mut router = Router::from_json_str(&format!(
+        r#"{{"endpoints": [
+            {{"method": "GET", "path": "/slow", "description": "", "controller": "slow",
+             "max_concurrency": {max}, "max_queue": {max_queue}}}
+        ]}}"#
+    ))
+    .unwrap();
+    router.set_deadline_header(
+        HeaderName::from_static("x-request-deadline-ms"),
+        DeadlineFormat::RemainingMillis,
+    );
+    let (started, release) = (Arc::clone(started), Arc::clone(release));
+    router.register_handler(
+        "slow",
+        AsyncFn(move |_req: HttpRequest| {
+            let (started, release) = (Arc::clone(&started), Arc::clone(&release));
+            async move {
+                started.wait().await;
+                release.wait().await;
+                HttpResponse::ok("done")
+            }
+        }),
+    );
+    Arc::new(router)
+}
+
+#[cfg(feature = "async")]
+fn spawn_get(router: &Arc<Router>) -> tokio::task::JoinHandle<StatusCode> {
+    let router = Arc::clone(router);
+    tokio::spawn(async move {
+        let req = Request::get("/slow").body(String::new()).unwrap();
+        router.route_async(req).await.status()
+    })
+}
+
+/// Waits until the route has `queued` requests waiting.
+#[cfg(feature = "async")]
+async fn wait_until_queued(router: &Router, queued: usize) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while router.stats().routes[0].queued < queued {
+            tokio::task::yield_now().await;
+        }
+    })
+    .await
+    .unwrap();
+}
+
+#[cfg(feature = "async")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn requests_past_the_limit_are_rejected_and_permits_recycled() {
+    let (started, release) = (Arc::new(Barrier::new(3)), Arc::new(Barrier::new(3)));
+    let router = router(2, 0, &started, &release);
+    let running = [spawn_get(&router), spawn_get(&router)];
+    started.wait().await;
+    assert_eq!(router.stats().routes[0].in_flight, 2);
+
+    let req = Request::get("/slow").body(String::new()).unwrap();
+    let response = router.route_async(req).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()["retry-after"], "1");
+
+    release.wait().await;
+    for request in running {
+        assert_eq!(request.await.unwrap(), StatusCode::OK);
+    }
+    assert_eq!(router.stats().routes[0].in_flight, 0);
+
+    // The permits are back: two more requests run at once.
+    let running = [spawn_get(&router), spawn_get(&router)];
+    started.wait().await;
+    release.wait().await;
+    for request in running {
+        assert_eq!(request.await.unwrap(), StatusCode::OK);
+    }
+}
+
+#[cfg(feature = "async")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn queued_requests_get_the_released_permit() {
+    let (started, release) = (Arc::new(Barrier::new(2)), Arc::new(Barrier::new(2)));
+    let router = router(1, 1, &started, &release);
+    let first = spawn_get(&router);
+    started.wait().await;
+    let second = spawn_get(&router);
+    wait_until_queued(&router, 1).await;
+
+    // The queue is full.
+    let req = Request::get("/slow").body(String::new()).unwrap();
+    assert_eq!(
+        router.route_async(req).await.status(),
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+
+    // Releasing the first handler starts the queued one, without polling.
+    release.wait().await;
+    assert_eq!(first.await.unwrap(), StatusCode::OK);
+    started.wait().await;
+    let stats = &router.stats().routes[0];
+    assert_eq!((stats.in_flight, stats.queued), (1, 0));
+    release.wait().await;
+    assert_eq!(second.await.unwrap(), StatusCode::OK);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn queued_requests_leave_at_their_deadline() {
+    let (started, release) = (Arc::new(Barrier::new(2)), Arc::new(Barrier::new(2)));
+    let router = router(1, 1, &started, &release);
+    let first = spawn_get(&router);
+    started.wait().await;
+
+    let req = Request::get("/slow")
+        .header("x-request-deadline-ms", "20")
+        .body(String::new())
+        .unwrap();
+    assert_eq!(
+        router.route_async(req).await.status(),
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+    assert_eq!(router.stats().routes[0].queued, 0);
+
+    release.wait().await;
+    assert_eq!(first.await.unwrap(), StatusCode::OK);
+}
+
+/// Panics while `panicking` is set, and answers otherwise.
+struct Flaky(Arc<AtomicBool>);
+
+impl HttpHandler for Flaky {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        if self.0.load(Ordering::SeqCst) {
+            panic!("the handler failed");
+        }
+        HttpResponse::ok("done")
+    }
+}
+
+/// A router whose `GET /flaky` runs one handler at a time, without a queue.
+fn flaky_router(panicking: &Arc<AtomicBool>) -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/flaky", "description": "", "controller": "flaky",
+             "max_concurrency": 1, "max_queue": 0}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("flaky", Flaky(Arc::clone(panicking)));
+    router
+}
+
+#[test]
+fn a_panicking_handler_releases_its_permit() {
+    let panicking = Arc::new(AtomicBool::new(true));
+    let router = flaky_router(&panicking);
+    let get = || router.route(Request::get("/flaky").body(String::new()).unwrap());
+    assert!(panic::catch_unwind(AssertUnwindSafe(get)).is_err());
+    let stats = router.stats();
+    assert_eq!((stats.in_flight, stats.routes[0].in_flight), (0, 0));
+
+    panicking.store(false, Ordering::SeqCst);
+    assert_eq!(get().status(), StatusCode::OK);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn a_panicking_async_handler_releases_its_permit() {
+    let panicking = Arc::new(AtomicBool::new(true));
+    let router = Arc::new(flaky_router(&panicking));
+    let task = {
+        let router = Arc::clone(&router);
+        tokio::spawn(async move {
+            let req = Request::get("/flaky").body(String::new()).unwrap();
+            router.route_async(req).await
+        })
+    };
+    assert!(task.await.unwrap_err().is_panic());
+    let stats = router.stats();
+    assert_eq!((stats.in_flight, stats.routes[0].in_flight), (0, 0));
+
+    panicking.store(false, Ordering::SeqCst);
+    let req = Request::get("/flaky").body(String::new()).unwrap();
+    assert_eq!(router.route_async(req).await.status(), StatusCode::OK);
+}
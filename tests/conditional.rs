@@ -0,0 +1,193 @@
+#![cfg(feature = "files")]
+
+use generic_http_router::conditional::not_modified_since;
+use generic_http_router::{HttpRequest, HttpResponse, Router};
+use http::{Method, Request, StatusCode};
+use std::fs::File;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// `Sun, 06 Nov 1994 08:49:37 GMT`.
+const NOV_6_1994: u64 = 784_111_777;
+
+/// The same date in each of the three formats of RFC 9110.
+const FORMATS: [&str; 3] = [
+    // IMF-fixdate.
+    "Sun, 06 Nov 1994 08:49:37 GMT",
+    // The obsolete RFC 850 format.
+    "Sunday, 06-Nov-94 08:49:37 GMT",
+    // ANSI C's `asctime()` format.
+    "Sun Nov  6 08:49:37 1994",
+];
+
+fn at(seconds: u64, millis: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(seconds) + Duration::from_millis(millis)
+}
+
+fn request(method: Method, headers: &[(&str, &str)]) -> HttpRequest {
+    let mut builder = HttpRequest::builder().method(method);
+    for (name, value) in headers {
+        builder = builder.header(*name, *value);
+    }
+    builder.build().unwrap()
+}
+
+fn get(headers: &[(&str, &str)]) -> HttpRequest {
+    request(Method::GET, headers)
+}
+
+/// Writes a temp file named `name`, last modified half a second after
+/// `NOV_6_1994`, and returns its path.
+fn temp_file(dir: &str, name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, "contents").unwrap();
+    File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(at(NOV_6_1994, 500))
+        .unwrap();
+    path
+}
+
+#[test]
+fn each_http_date_format_is_parsed() {
+    for date in FORMATS {
+        let req = get(&[("if-modified-since", date), ("if-unmodified-since", date)]);
+        assert_eq!(req.if_modified_since(), Some(at(NOV_6_1994, 0)), "{date}");
+        assert_eq!(req.if_unmodified_since(), Some(at(NOV_6_1994, 0)), "{date}");
+    }
+    // Surrounding whitespace is ignored.
+    let req = get(&[("if-modified-since", " Sun, 06 Nov 1994 08:49:37 GMT ")]);
+    assert_eq!(req.if_modified_since(), Some(at(NOV_6_1994, 0)));
+}
+
+#[test]
+fn invalid_dates_are_ignored() {
+    for date in [
+        "",
+        "yesterday",
+        "784111777",
+        "Sun, 06 Nov 1994 08:49:37",
+        "Sun, 06 Nov 1994 08:49:37 PST",
+        "Sun, 32 Nov 1994 08:49:37 GMT",
+        "Sun, 06 Foo 1994 08:49:37 GMT",
+        "Sun, 06 Nov 1994 25:49:37 GMT",
+        "1994-11-06T08:49:37Z",
+    ] {
+        let req = get(&[("if-modified-since", date)]);
+        assert_eq!(req.if_modified_since(), None, "{date:?}");
+    }
+    assert_eq!(get(&[]).if_modified_since(), None);
+    assert_eq!(get(&[]).if_unmodified_since(), None);
+}
+
+#[test]
+fn last_modified_is_an_imf_fixdate_in_whole_seconds() {
+    let mut response = HttpResponse::ok("");
+    response.set_last_modified(at(NOV_6_1994, 999));
+    assert_eq!(
+        response.headers["last-modified"],
+        "Sun, 06 Nov 1994 08:49:37 GMT"
+    );
+    // The formatted date parses back.
+    let req = get(&[(
+        "if-modified-since",
+        response.headers["last-modified"].to_str().unwrap(),
+    )]);
+    assert_eq!(req.if_modified_since(), Some(at(NOV_6_1994, 0)));
+}
+
+#[test]
+fn not_modified_since_compares_whole_seconds() {
+    let since = [("if-modified-since", FORMATS[0])];
+    // Modified at, or within the second of, the date the client has.
+    assert!(not_modified_since(&get(&since), at(NOV_6_1994, 0)));
+    assert!(not_modified_since(&get(&since), at(NOV_6_1994, 999)));
+    // Modified earlier.
+    assert!(not_modified_since(&get(&since), at(NOV_6_1994 - 3600, 0)));
+    // Modified in a later second.
+    assert!(!not_modified_since(&get(&since), at(NOV_6_1994 + 1, 0)));
+    // Without a valid date.
+    assert!(!not_modified_since(&get(&[]), at(NOV_6_1994, 0)));
+    assert!(!not_modified_since(
+        &get(&[("if-modified-since", "soon")]),
+        at(NOV_6_1994, 0)
+    ));
+}
+
+#[test]
+fn not_modified_since_only_applies_to_get_and_head_without_if_none_match() {
+    let since = [("if-modified-since", FORMATS[0])];
+    let modified = at(NOV_6_1994, 0);
+    assert!(not_modified_since(&request(Method::HEAD, &since), modified));
+    assert!(!not_modified_since(
+        &request(Method::POST, &since),
+        modified
+    ));
+    assert!(!not_modified_since(&request(Method::PUT, &since), modified));
+    let with_etag = [
+        ("if-modified-since", FORMATS[0]),
+        ("if-none-match", "\"x\""),
+    ];
+    assert!(!not_modified_since(&get(&with_etag), modified));
+}
+
+#[test]
+fn file_responses_answer_304_and_412() {
+    let path = temp_file("conditional", "file.txt");
+    for date in FORMATS {
+        let response =
+            HttpResponse::file_ranged(&path, &get(&[("if-modified-since", date)])).unwrap();
+        assert_eq!(response.status, StatusCode::NOT_MODIFIED, "{date}");
+        assert_eq!(response.headers["last-modified"], FORMATS[0]);
+        assert!(response.body.as_bytes().unwrap().is_empty());
+    }
+
+    // Modified after the client's copy.
+    let earlier = [("if-modified-since", "Sun, 06 Nov 1994 08:49:36 GMT")];
+    let response = HttpResponse::file_ranged(&path, &get(&earlier)).unwrap();
+    assert_eq!(response.status, StatusCode::OK);
+
+    // Modified since the date the client requires.
+    let unmodified = [("if-unmodified-since", "Sun, 06 Nov 1994 08:49:36 GMT")];
+    let response = HttpResponse::file_ranged(&path, &get(&unmodified)).unwrap();
+    assert_eq!(response.status, StatusCode::PRECONDITION_FAILED);
+    assert!(response.body.as_bytes().unwrap().is_empty());
+    let unmodified = [("if-unmodified-since", FORMATS[2])];
+    let response = HttpResponse::file_ranged(&path, &get(&unmodified)).unwrap();
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[test]
+fn static_files_answer_304_and_412() {
+    let path = temp_file("conditional_static", "app.js");
+    let dir = path.parent().unwrap().to_str().unwrap();
+    let router = Router::from_json_str(&format!(
+        r#"{{"endpoints": [
+            {{"method": "GET", "path": "/assets/*file", "description": "", "controller": "",
+              "static_files": {{"dir": {dir:?}, "etag": false}}}}
+        ]}}"#
+    ))
+    .unwrap();
+    let send = |name: &str, value: &str| {
+        router.route(
+            Request::get("/assets/app.js")
+                .header(name, value)
+                .body(String::new())
+                .unwrap(),
+        )
+    };
+
+    let response = send("if-modified-since", FORMATS[1]);
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()["last-modified"], FORMATS[0]);
+    assert!(response.body().is_empty());
+    let response = send("if-unmodified-since", "Sat, 05 Nov 1994 08:49:37 GMT");
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    let response = send("if-modified-since", "Sat, 05 Nov 1994 08:49:37 GMT");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), "contents");
+}
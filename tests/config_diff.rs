@@ -0,0 +1,109 @@
+use generic_http_router::config::{Config, ConfigDiff, Endpoint};
+use http::Method;
+use serde_json::json;
+
+fn fixture(name: &str) -> Config {
+    let path = format!("{}/tests/fixtures/{name}.json", env!("CARGO_MANIFEST_DIR"));
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// The method, path and controller of each endpoint.
+fn routes(endpoints: &[Endpoint]) -> Vec<(Method, &str, &str)> {
+    endpoints
+        .iter()
+        .map(|e| (e.method.clone(), e.path.as_str(), e.controller.as_str()))
+        .collect()
+}
+
+#[test]
+fn each_category_is_reported() {
+    let diff = fixture("diff_before").diff(&fixture("diff_after"));
+    assert_eq!(
+        routes(&diff.added),
+        [(Method::POST, "/users", "users::create")]
+    );
+    assert_eq!(
+        routes(&diff.removed),
+        [(Method::DELETE, "/users/:id", "users::delete")]
+    );
+    let modified: Vec<_> = diff
+        .modified
+        .iter()
+        .map(|(old, new)| (old.method.clone(), old.path.as_str(), new.path.as_str()))
+        .collect();
+    assert_eq!(
+        modified,
+        [
+            (Method::GET, "/users/:id", "/users/:id"),
+            (Method::GET, "/orders", "/orders"),
+        ]
+    );
+    // The endpoints are as they were and as they are.
+    let (old, new) = &diff.modified[1];
+    assert_eq!((old.timeout_ms, new.timeout_ms), (Some(500), Some(250)));
+}
+
+#[test]
+fn a_renamed_controller_is_a_modification() {
+    let diff = fixture("diff_before").diff(&fixture("diff_after"));
+    let (old, new) = &diff.modified[0];
+    assert_eq!(old.controller, "users::get");
+    assert_eq!(new.controller, "users::get_v2");
+}
+
+#[test]
+fn order_only_changes_are_not_modifications() {
+    let before = fixture("diff_before");
+    let mut reordered = before.clone();
+    reordered.endpoints.reverse();
+    let diff = before.diff(&reordered);
+    assert!(diff.is_empty(), "{diff}");
+    assert_eq!(diff, ConfigDiff::default());
+    assert_eq!(diff.to_string(), "No changes\n");
+    // `GET /users` moved in the fixtures and isn't reported either.
+    let diff = before.diff(&fixture("diff_after"));
+    assert!(diff.modified.iter().all(|(old, _)| old.path != "/users"));
+}
+
+#[test]
+fn compared_the_other_way_added_and_removed_swap() {
+    let forward = fixture("diff_before").diff(&fixture("diff_after"));
+    let backward = fixture("diff_after").diff(&fixture("diff_before"));
+    assert_eq!(backward.added, forward.removed);
+    assert_eq!(backward.removed, forward.added);
+    // Modifications follow the order of the configuration diffed from.
+    let swapped: Vec<_> = forward
+        .modified
+        .iter()
+        .rev()
+        .map(|(old, new)| (new.clone(), old.clone()))
+        .collect();
+    assert_eq!(backward.modified, swapped);
+}
+
+#[test]
+fn display_summarizes_one_line_per_endpoint() {
+    let diff = fixture("diff_before").diff(&fixture("diff_after"));
+    assert_eq!(
+        diff.to_string(),
+        "+ POST /users (users::create)\n\
+         - DELETE /users/:id (users::delete)\n\
+         ~ GET /users/:id: controller\n\
+         ~ GET /orders: timeout_ms, tags\n"
+    );
+}
+
+#[test]
+fn serializes_for_tooling() {
+    let diff = fixture("diff_before").diff(&fixture("diff_after"));
+    let value = serde_json::to_value(&diff).unwrap();
+    assert_eq!(value["added"][0]["method"], "POST");
+    assert_eq!(value["added"][0]["controller"], "users::create");
+    assert_eq!(value["removed"][0]["path"], "/users/:id");
+    assert_eq!(value["modified"][0][0]["controller"], json!("users::get"));
+    assert_eq!(
+        value["modified"][0][1]["controller"],
+        json!("users::get_v2")
+    );
+    assert_eq!(value["modified"].as_array().unwrap().len(), 2);
+}
@@ -0,0 +1,22 @@
+mod common;
+
+use common::assert_snapshot;
+use generic_http_router::config::Config;
+
+/// `routes.schema.json` is `Config::schema()`, so editors validating against
+/// the committed file see every field. Run with `UPDATE_SNAPSHOTS=1` to
+/// regenerate it after changing the format.
+#[test]
+fn the_committed_schema_is_up_to_date() {
+    let rendered = serde_json::to_string_pretty(&Config::schema()).unwrap() + "\n";
+    assert_snapshot("../routes.schema.json", &rendered);
+}
+
+#[test]
+fn the_schema_describes_the_endpoint_fields() {
+    let schema = Config::schema();
+    let endpoint = &schema["$defs"]["endpoint"]["properties"];
+    for field in ["method", "path", "controller", "max_request_size"] {
+        assert!(endpoint.get(field).is_some(), "{field}");
+    }
+}
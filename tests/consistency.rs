@@ -0,0 +1,157 @@
+use generic_http_router::config::Config;
+use generic_http_router::{
+    HttpHandler, HttpRequest, HttpResponse, LoadWarning, Router, RouterError,
+};
+use http::{Method, Request, StatusCode};
+use std::path::PathBuf;
+
+/// A configuration with `users::update` on two endpoints, the second with
+/// the extra fields `second`.
+fn config(second: &str) -> String {
+    format!(
+        r#"{{"endpoints": [
+            {{"method": "GET", "path": "/health", "description": "", "controller": "health"}},
+            {{"method": "PUT", "path": "/users/:id", "description": "",
+              "controller": "users::update", "auth": "api_key",
+              "required_headers": ["X-Tenant"], "max_request_size": 1024}},
+            {{"method": "PATCH", "path": "/users/:id", "description": "",
+              "controller": "users::update"{second}}}
+        ]}}"#
+    )
+}
+
+const SAME: &str =
+    r#", "auth": "api_key", "required_headers": ["x-tenant"], "max_request_size": 1024"#;
+
+fn validate(json: &str, strict: bool) -> Result<Vec<LoadWarning>, RouterError> {
+    serde_json::from_str::<Config>(json)
+        .unwrap()
+        .validate(strict)
+}
+
+/// The index, method and path of each endpoint listed in a warning.
+type Listed = [(usize, Method, String)];
+
+fn inconsistent(warnings: &[LoadWarning]) -> (&str, &[&'static str], &Listed) {
+    match warnings {
+        [
+            LoadWarning::InconsistentController {
+                controller,
+                fields,
+                endpoints,
+            },
+        ] => (controller, fields, endpoints),
+        _ => panic!("expected one InconsistentController warning, got {warnings:?}"),
+    }
+}
+
+fn endpoints() -> Vec<(usize, Method, String)> {
+    vec![
+        (1, Method::PUT, "/users/:id".to_string()),
+        (2, Method::PATCH, "/users/:id".to_string()),
+    ]
+}
+
+#[test]
+fn consistent_duplicates_are_fine() {
+    assert!(validate(&config(SAME), false).unwrap().is_empty());
+    assert!(validate(&config(SAME), true).unwrap().is_empty());
+}
+
+#[test]
+fn divergent_auth_is_a_warning_listing_every_endpoint() {
+    let second = r#", "required_headers": ["X-Tenant"], "max_request_size": 1024"#;
+    let warnings = validate(&config(second), false).unwrap();
+    let (controller, fields, listed) = inconsistent(&warnings);
+    assert_eq!(controller, "users::update");
+    assert_eq!(fields, ["auth"]);
+    assert_eq!(listed, endpoints());
+    assert_eq!(
+        warnings[0].to_string(),
+        "Controller 'users::update' has endpoints with different auth: \
+         endpoint 1 (PUT /users/:id), endpoint 2 (PATCH /users/:id)"
+    );
+}
+
+#[test]
+fn divergent_required_headers_and_body_size_are_listed() {
+    let second = r#", "auth": "api_key", "max_request_size": 1048576"#;
+    let warnings = validate(&config(second), false).unwrap();
+    let (_, fields, listed) = inconsistent(&warnings);
+    assert_eq!(fields, ["required_headers", "max_request_size"]);
+    assert_eq!(listed, endpoints());
+}
+
+#[test]
+fn strict_mode_makes_it_an_error() {
+    let error = validate(&config(""), true).unwrap_err();
+    let RouterError::InconsistentController {
+        controller,
+        fields,
+        endpoints: listed,
+    } = &error
+    else {
+        panic!("expected InconsistentController, got {error:?}");
+    };
+    assert_eq!(controller, "users::update");
+    assert_eq!(fields, &["auth", "required_headers", "max_request_size"]);
+    assert_eq!(*listed, endpoints());
+    assert_eq!(error.code(), "config.controller");
+}
+
+#[test]
+fn loading_warns_and_a_strict_reload_fails() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let (consistent, divergent) = (dir.join("consistent.json"), dir.join("divergent.json"));
+    std::fs::write(&consistent, config(SAME)).unwrap();
+    std::fs::write(&divergent, config("")).unwrap();
+
+    let (_, warnings) = Router::new_with_report(&divergent).unwrap();
+    assert_eq!(inconsistent(&warnings).0, "users::update");
+
+    let mut router = Router::new(&consistent).unwrap();
+    router.set_strict_config(true);
+    assert!(matches!(
+        router.reload(&divergent),
+        Err(RouterError::InconsistentController { .. })
+    ));
+    assert!(router.reload(&consistent).unwrap().is_empty());
+}
+
+struct Echo;
+
+impl HttpHandler for Echo {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok(format!("{} bytes", req.body.len()))
+    }
+}
+
+#[test]
+fn bodies_over_max_request_size_are_rejected() {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "POST", "path": "/small", "description": "", "controller": "echo",
+             "max_request_size": 4},
+            {"method": "POST", "path": "/any", "description": "", "controller": "echo"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("echo", Echo);
+    router.use_problem_responses(true);
+    let post =
+        |path: &str, body: &str| router.route(Request::post(path).body(body.to_string()).unwrap());
+
+    let response = post("/small", "1234");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body().as_ref(), b"4 bytes");
+
+    let response = post("/small", "12345");
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let problem: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(
+        problem["detail"],
+        "The request body is larger than 4 bytes."
+    );
+
+    assert_eq!(post("/any", &"x".repeat(100_000)).status(), StatusCode::OK);
+}
@@ -0,0 +1,164 @@
+use generic_http_router::{
+    HttpHandler, HttpRequest, HttpResponse, RequestContext, Router, TlsInfo,
+};
+use http::Request;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The name of the listener a request was received on, in the context's
+/// extensions.
+#[derive(Debug, Clone, PartialEq)]
+struct Listener(&'static str);
+
+/// Answers with what the context says about the request.
+struct Whoami;
+
+impl HttpHandler for Whoami {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let Some(ctx) = req.context() else {
+            return HttpResponse::internal_error("no context");
+        };
+        let addr = |addr: Option<SocketAddr>| addr.map_or("-".to_string(), |a| a.to_string());
+        let tls = ctx.tls_info.as_ref();
+        HttpResponse::ok(format!(
+            "remote={} local={} identity={} alpn={} listener={}",
+            addr(ctx.remote_addr),
+            addr(ctx.local_addr),
+            tls.and_then(|tls| tls.peer_identity.as_deref())
+                .unwrap_or("-"),
+            tls.and_then(|tls| tls.alpn_protocol.as_deref())
+                .unwrap_or("-"),
+            ctx.extensions.get::<Listener>().map_or("-", |l| l.0),
+        ))
+    }
+}
+
+/// Records when the context says the request was received.
+struct ReceivedAt(Arc<Mutex<Option<Instant>>>);
+
+impl HttpHandler for ReceivedAt {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        *self.0.lock().unwrap() = req.context().map(|ctx| ctx.received_at);
+        HttpResponse::no_content()
+    }
+}
+
+fn router() -> (Router, Arc<Mutex<Option<Instant>>>) {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/whoami", "description": "", "controller": "whoami"},
+            {"method": "GET", "path": "/received", "description": "", "controller": "received"}
+        ]}"#,
+    )
+    .unwrap();
+    let received = Arc::default();
+    router.register_handler("whoami", Whoami);
+    router.register_handler("received", ReceivedAt(Arc::clone(&received)));
+    (router, received)
+}
+
+fn get(path: &str) -> Request<String> {
+    Request::get(path).body(String::new()).unwrap()
+}
+
+fn peer() -> SocketAddr {
+    "203.0.113.7:51234".parse().unwrap()
+}
+
+#[test]
+fn handlers_read_the_context_the_request_was_routed_with() {
+    let (router, _) = router();
+    let mut ctx = RequestContext::new()
+        .with_remote_addr(peer())
+        .with_local_addr("10.0.0.1:443".parse().unwrap())
+        .with_tls_info(TlsInfo {
+            server_name: Some("api.example.com".to_string()),
+            alpn_protocol: Some("h2".to_string()),
+            peer_identity: Some("CN=billing".to_string()),
+            peer_certificates: vec![vec![0x30, 0x82]],
+        });
+    ctx.extensions.insert(Listener("public"));
+    let response = router.route_with_context(get("/whoami"), ctx);
+    assert_eq!(
+        response.body(),
+        "remote=203.0.113.7:51234 local=10.0.0.1:443 identity=CN=billing alpn=h2 listener=public"
+    );
+}
+
+#[test]
+fn route_gives_a_default_context() {
+    let (router, received) = router();
+    assert_eq!(
+        router.route(get("/whoami")).body(),
+        "remote=- local=- identity=- alpn=- listener=-"
+    );
+
+    let before = Instant::now();
+    router.route(get("/received"));
+    let received_at = received.lock().unwrap().unwrap();
+    assert!(before <= received_at && received_at <= Instant::now());
+}
+
+#[test]
+fn received_at_is_kept() {
+    let (router, received) = router();
+    let accepted = Instant::now() - Duration::from_secs(5);
+    router.route_with_context(
+        get("/received"),
+        RequestContext::new().with_received_at(accepted),
+    );
+    assert_eq!(*received.lock().unwrap(), Some(accepted));
+}
+
+#[test]
+fn a_context_in_the_extensions_is_used_by_every_entry_point() {
+    let (router, _) = router();
+    let mut req = get("/whoami");
+    req.extensions_mut()
+        .insert(RequestContext::new().with_remote_addr(peer()));
+    let response = router.route_streaming(req);
+    let body = response.into_body().collect(usize::MAX).unwrap();
+    assert_eq!(
+        body,
+        "remote=203.0.113.7:51234 local=- identity=- alpn=- listener=-"
+    );
+}
+
+#[test]
+fn the_remote_address_is_the_summarys_client_ip() {
+    let (mut router, _) = router();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&seen);
+    router.on_response(move |summary| sink.lock().unwrap().push(summary.client_ip));
+    router.route_with_context(
+        get("/whoami"),
+        RequestContext::new().with_remote_addr(peer()),
+    );
+    router.route(get("/whoami"));
+    assert_eq!(*seen.lock().unwrap(), [Some(peer().ip()), None]);
+}
+
+#[test]
+fn requests_built_by_hand_have_no_context() {
+    let req = HttpRequest::builder().uri("/whoami").build().unwrap();
+    assert!(req.context().is_none());
+    assert_eq!(
+        Whoami.handle(req).body.as_bytes().unwrap().as_ref(),
+        b"no context"
+    );
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn route_async_passes_the_context_on() {
+    let (router, _) = router();
+    let mut req = get("/whoami");
+    req.extensions_mut()
+        .insert(RequestContext::new().with_remote_addr(peer()));
+    let response = router.route_async(req).await;
+    assert_eq!(
+        response.body(),
+        "remote=203.0.113.7:51234 local=- identity=- alpn=- listener=-"
+    );
+}
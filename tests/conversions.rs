@@ -0,0 +1,115 @@
+use generic_http_router::{HttpRequest, HttpResponse};
+use http::header::{ACCEPT, CONTENT_TYPE, HeaderValue};
+use http::{HeaderMap, Method, Request, Response, StatusCode};
+
+/// A marker extension, to check extensions survive the conversions.
+#[derive(Clone, Debug, PartialEq)]
+struct TraceId(u64);
+
+/// Headers with a repeated name, in a known order.
+fn headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.append(ACCEPT, HeaderValue::from_static("text/html"));
+    headers.append(ACCEPT, HeaderValue::from_static("application/json"));
+    headers.append("x-forwarded-for", HeaderValue::from_static("10.0.0.1"));
+    headers.append("x-forwarded-for", HeaderValue::from_static("10.0.0.2"));
+    headers
+}
+
+const URI: &str = "https://example.com/users/7?fields=name";
+const BODY: &[u8] = b"{\"name\":\"Ada\"}";
+
+fn request() -> Request<Vec<u8>> {
+    let mut req = Request::builder()
+        .method(Method::PATCH)
+        .uri(URI)
+        .body(BODY.to_vec())
+        .unwrap();
+    *req.headers_mut() = headers();
+    req.extensions_mut().insert(TraceId(42));
+    req
+}
+
+/// Every header value, in order, including the repeated ones.
+fn all_headers(headers: &HeaderMap) -> Vec<(String, Vec<u8>)> {
+    headers
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.as_bytes().to_vec()))
+        .collect()
+}
+
+#[test]
+fn request_from_http_request() {
+    let req = HttpRequest::from(request());
+    assert_eq!(req.method, Method::PATCH);
+    assert_eq!(req.uri, URI);
+    assert_eq!(req.original_uri, URI);
+    assert_eq!(all_headers(&req.headers), all_headers(&headers()));
+    assert_eq!(req.headers.get_all(ACCEPT).iter().count(), 2);
+    assert_eq!(req.body, BODY);
+    assert!(req.params.is_empty());
+    assert_eq!(req.extensions.get::<TraceId>(), Some(&TraceId(42)));
+}
+
+#[test]
+fn request_round_trip() {
+    let round_tripped = Request::<Vec<u8>>::try_from(HttpRequest::from(request())).unwrap();
+    assert_eq!(round_tripped.method(), Method::PATCH);
+    assert_eq!(round_tripped.uri(), URI);
+    assert_eq!(
+        all_headers(round_tripped.headers()),
+        all_headers(&headers())
+    );
+    assert_eq!(round_tripped.body(), BODY);
+    assert_eq!(
+        round_tripped.extensions().get::<TraceId>(),
+        Some(&TraceId(42))
+    );
+
+    // Through `Request<Bytes>`, the same.
+    let round_tripped = Request::<bytes::Bytes>::try_from(HttpRequest::from(request())).unwrap();
+    assert_eq!(round_tripped.method(), Method::PATCH);
+    assert_eq!(round_tripped.uri(), URI);
+    assert_eq!(
+        all_headers(round_tripped.headers()),
+        all_headers(&headers())
+    );
+    assert_eq!(round_tripped.body(), BODY);
+}
+
+#[test]
+fn request_round_trip_uses_the_routed_uri() {
+    let mut req = HttpRequest::from(request());
+    req.uri = "/v2/users/7".parse().unwrap();
+    let rebuilt = Request::<Vec<u8>>::try_from(req).unwrap();
+    assert_eq!(rebuilt.uri(), "/v2/users/7");
+}
+
+#[test]
+fn response_round_trip() {
+    let mut original = Response::builder()
+        .status(StatusCode::CREATED)
+        .body(b"made".to_vec())
+        .unwrap();
+    *original.headers_mut() = headers();
+    original.extensions_mut().insert(TraceId(7));
+
+    let response = HttpResponse::from(original);
+    assert_eq!(response.status, StatusCode::CREATED);
+    assert_eq!(all_headers(&response.headers), all_headers(&headers()));
+    assert_eq!(response.body.as_bytes().unwrap(), "made");
+    assert_eq!(response.extensions.get::<TraceId>(), Some(&TraceId(7)));
+
+    let round_tripped = Response::<Vec<u8>>::from(response);
+    assert_eq!(round_tripped.status(), StatusCode::CREATED);
+    assert_eq!(
+        all_headers(round_tripped.headers()),
+        all_headers(&headers())
+    );
+    assert_eq!(round_tripped.body(), b"made");
+    assert_eq!(
+        round_tripped.extensions().get::<TraceId>(),
+        Some(&TraceId(7))
+    );
+}
@@ -0,0 +1,139 @@
+#![cfg(feature = "csrf")]
+
+use generic_http_router::{CsrfMiddleware, HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Request, Response, StatusCode, header};
+
+/// Answers with the request's CSRF token, or `none`.
+struct Form;
+
+impl HttpHandler for Form {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok(req.csrf_token().unwrap_or("none").to_string())
+    }
+}
+
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/form", "description": "", "controller": "form"},
+            {"method": "POST", "path": "/form", "description": "", "controller": "form"},
+            {"method": "DELETE", "path": "/form", "description": "", "controller": "form"},
+            {"method": "POST", "path": "/webhook", "description": "", "controller": "form",
+             "csrf": false}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("form", Form);
+    router.use_csrf(CsrfMiddleware::new());
+    router
+}
+
+fn send(router: &Router, req: http::request::Builder, body: &str) -> Response<bytes::Bytes> {
+    router.route(req.body(body.to_string()).unwrap())
+}
+
+/// Gets the form, returning the token of the issued cookie.
+fn issue(router: &Router) -> String {
+    let response = send(router, Request::get("/form"), "");
+    assert_eq!(response.status(), StatusCode::OK);
+    let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+    let token = cookie
+        .strip_prefix("csrf_token=")
+        .and_then(|rest| rest.split(';').next())
+        .unwrap()
+        .to_string();
+    assert_eq!(response.body().as_ref(), token.as_bytes());
+    token
+}
+
+#[test]
+fn safe_requests_get_a_token_cookie() {
+    let router = router();
+    let response = send(&router, Request::get("/form"), "");
+    let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+    assert!(
+        cookie.contains("; Path=/; SameSite=Strict; Secure"),
+        "{cookie}"
+    );
+    assert!(!cookie.contains("HttpOnly"));
+    let token = issue(&router);
+    assert_eq!(token.len(), 64);
+    assert!(token.bytes().all(|b| b.is_ascii_hexdigit()));
+    assert_ne!(token, issue(&router));
+
+    // A request with the cookie keeps its token.
+    let cookie = format!("csrf_token={token}");
+    let response = send(
+        &router,
+        Request::get("/form").header(header::COOKIE, &cookie),
+        "",
+    );
+    assert!(!response.headers().contains_key(header::SET_COOKIE));
+    assert_eq!(response.body().as_ref(), token.as_bytes());
+}
+
+#[test]
+fn a_valid_token_is_accepted() {
+    let router = router();
+    let token = issue(&router);
+    let cookie = format!("csrf_token={token}");
+
+    let req = Request::post("/form")
+        .header(header::COOKIE, &cookie)
+        .header("x-csrf-token", &token);
+    let response = send(&router, req, "");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body().as_ref(), token.as_bytes());
+
+    let req = Request::post("/form")
+        .header(header::COOKIE, &cookie)
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded");
+    let response = send(&router, req, &format!("name=ada&_csrf={token}"));
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[test]
+fn a_missing_token_is_rejected() {
+    let router = router();
+    let token = issue(&router);
+    let cookie = format!("csrf_token={token}");
+
+    // Without the header or form field.
+    let req = Request::post("/form").header(header::COOKIE, &cookie);
+    assert_eq!(send(&router, req, "").status(), StatusCode::FORBIDDEN);
+    // Without the cookie.
+    let req = Request::delete("/form").header("x-csrf-token", &token);
+    assert_eq!(send(&router, req, "").status(), StatusCode::FORBIDDEN);
+    // The form field only counts in form bodies.
+    let req = Request::post("/form")
+        .header(header::COOKIE, &cookie)
+        .header(header::CONTENT_TYPE, "text/plain");
+    let response = send(&router, req, &format!("_csrf={token}"));
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn a_mismatched_token_is_rejected() {
+    let router = router();
+    let token = issue(&router);
+    let other = issue(&router);
+    let req = Request::post("/form")
+        .header(header::COOKIE, format!("csrf_token={token}"))
+        .header("x-csrf-token", &other);
+    let response = send(&router, req, "");
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // A forged cookie repeating the header isn't a token.
+    let req = Request::post("/form")
+        .header(header::COOKIE, "csrf_token=forged")
+        .header("x-csrf-token", "forged");
+    assert_eq!(send(&router, req, "").status(), StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn opted_out_routes_are_not_checked() {
+    let router = router();
+    let response = send(&router, Request::post("/webhook"), "{}");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body().as_ref(), b"none");
+}
@@ -0,0 +1,167 @@
+use generic_http_router::testing::MockClock;
+use generic_http_router::{
+    Deadline, DeadlineFormat, HttpHandler, HttpRequest, HttpResponse, Router,
+};
+use http::header::HeaderName;
+use http::{Request, StatusCode};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+/// What a handler saw: `HttpRequest::deadline` and the `Deadline` the caller
+/// sent.
+type Seen = Arc<Mutex<Option<(Option<Instant>, Option<Deadline>)>>>;
+
+struct Record(Seen);
+
+impl HttpHandler for Record {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let sent = req.extensions.get::<Deadline>().copied();
+        *self.0.lock().unwrap() = Some((req.deadline(), sent));
+        HttpResponse::ok("ok")
+    }
+}
+
+/// A router reading `X-Request-Deadline-Ms` in `format`, with a `GET /timed`
+/// route whose `timeout_ms` is 1000 and a `GET /untimed` one without.
+fn router(format: DeadlineFormat, seen: &Seen) -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/timed", "description": "", "controller": "record",
+             "timeout_ms": 1000},
+            {"method": "GET", "path": "/untimed", "description": "", "controller": "record"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("record", Record(Arc::clone(seen)));
+    router.set_deadline_header(HeaderName::from_static("x-request-deadline-ms"), format);
+    router
+}
+
+/// Routes `GET path` with the deadline header `value`, returning the deadlines
+/// the handler saw and when the request was sent.
+fn get(
+    router: &Router,
+    seen: &Seen,
+    path: &str,
+    value: Option<&str>,
+) -> (Instant, Option<Instant>, Option<Deadline>) {
+    let mut req = Request::get(path);
+    if let Some(value) = value {
+        req = req.header("x-request-deadline-ms", value);
+    }
+    let sent = Instant::now();
+    let response = router.route(req.body(String::new()).unwrap());
+    assert_eq!(response.status(), StatusCode::OK);
+    let (deadline, header) = seen.lock().unwrap().take().unwrap();
+    (sent, deadline, header)
+}
+
+/// Returns `true` if `instant` is within 500 ms of `expected`, which absorbs
+/// the time taken routing.
+fn near(instant: Instant, expected: Instant) -> bool {
+    let distance = if instant > expected {
+        instant - expected
+    } else {
+        expected - instant
+    };
+    distance < Duration::from_millis(500)
+}
+
+#[test]
+fn the_earlier_header_deadline_wins() {
+    let seen = Seen::default();
+    let router = router(DeadlineFormat::RemainingMillis, &seen);
+    let (sent, deadline, header) = get(&router, &seen, "/timed", Some("100"));
+    let deadline = deadline.unwrap();
+    assert_eq!(Some(Deadline(deadline)), header);
+    assert!(near(deadline, sent + Duration::from_millis(100)));
+}
+
+#[test]
+fn the_earlier_route_timeout_wins() {
+    let seen = Seen::default();
+    let router = router(DeadlineFormat::RemainingMillis, &seen);
+    let (sent, deadline, header) = get(&router, &seen, "/timed", Some("60000"));
+    let deadline = deadline.unwrap();
+    assert!(near(deadline, sent + Duration::from_millis(1000)));
+    // The sent deadline is still available as it was sent.
+    let Deadline(sent_deadline) = header.unwrap();
+    assert!(sent_deadline > deadline);
+}
+
+#[test]
+fn either_alone_is_the_deadline() {
+    let seen = Seen::default();
+    let router = router(DeadlineFormat::RemainingMillis, &seen);
+    let (sent, deadline, header) = get(&router, &seen, "/untimed", Some("300"));
+    assert!(near(deadline.unwrap(), sent + Duration::from_millis(300)));
+    assert!(header.is_some());
+
+    let (sent, deadline, header) = get(&router, &seen, "/timed", None);
+    assert!(near(deadline.unwrap(), sent + Duration::from_millis(1000)));
+    assert_eq!(header, None);
+
+    assert_eq!(get(&router, &seen, "/untimed", None).1, None);
+}
+
+#[test]
+fn unix_millis_deadlines() {
+    // The Unix time is compared with the router's clock, so a mock clock at a
+    // fixed time makes the time left exact.
+    let now = UNIX_EPOCH + Duration::from_millis(1_700_000_000_000);
+    let seen = Seen::default();
+    let mut router = router(DeadlineFormat::UnixMillis, &seen);
+    router.set_clock(MockClock::at(now));
+    let (sent, deadline, _) = get(&router, &seen, "/timed", Some("1700000000200"));
+    assert!(near(deadline.unwrap(), sent + Duration::from_millis(200)));
+
+    // A deadline already passed leaves no time.
+    let (sent, deadline, _) = get(&router, &seen, "/timed", Some("1699999999000"));
+    assert!(near(deadline.unwrap(), sent));
+}
+
+#[test]
+fn invalid_deadlines_are_ignored() {
+    let seen = Seen::default();
+    let router = router(DeadlineFormat::UnixMillis, &seen);
+    for value in ["soon", "-5", "1.5"] {
+        let (sent, deadline, header) = get(&router, &seen, "/timed", Some(value));
+        assert_eq!(header, None, "{value}");
+        assert!(near(deadline.unwrap(), sent + Duration::from_millis(1000)));
+    }
+}
+
+#[test]
+fn the_largest_deadline_leaves_the_route_timeout() {
+    let seen = Seen::default();
+    for format in [DeadlineFormat::RemainingMillis, DeadlineFormat::UnixMillis] {
+        let router = router(format, &seen);
+        let (sent, deadline, _) = get(&router, &seen, "/timed", Some(&u64::MAX.to_string()));
+        assert!(near(deadline.unwrap(), sent + Duration::from_millis(1000)));
+    }
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn route_async_enforces_the_sent_deadline() {
+    use generic_http_router::async_handler::AsyncFn;
+
+    let seen = Seen::default();
+    let mut router = router(DeadlineFormat::RemainingMillis, &seen);
+    router.register_handler(
+        "record",
+        AsyncFn(|_req: HttpRequest| async {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+            HttpResponse::ok("too late")
+        }),
+    );
+    let req = Request::get("/timed")
+        .header("x-request-deadline-ms", "20")
+        .body(String::new())
+        .unwrap();
+    let started = Instant::now();
+    let response = router.route_async(req).await;
+    assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    // Well before the route's own timeout.
+    assert!(started.elapsed() < Duration::from_millis(900));
+}
@@ -0,0 +1,163 @@
+use generic_http_router::error::RouterError;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::header::{HeaderMap, HeaderValue, SERVER, SET_COOKIE};
+use http::{Method, Request, Response, StatusCode};
+use std::path::PathBuf;
+
+const CONFIG: &str = r#"{
+    "default_response_headers": {
+        "X-Content-Type-Options": "nosniff",
+        "Strict-Transport-Security": "max-age=63072000",
+        "Server": "router",
+        "Content-Type": "application/octet-stream"
+    },
+    "endpoints": [
+        {"method": "GET", "path": "/plain", "description": "", "controller": "plain"},
+        {"method": "GET", "path": "/branded", "description": "", "controller": "branded"},
+        {"method": "GET", "path": "/missing", "description": "", "controller": "missing"},
+        {"method": "GET", "path": "/template", "description": "", "controller": "",
+         "body_template": "hello", "content_type": "text/html"}
+    ]
+}"#;
+
+/// Answers without headers of its own.
+struct Plain;
+
+impl HttpHandler for Plain {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok("plain")
+    }
+}
+
+/// Sets its own `Server` header, and two cookies.
+struct Branded;
+
+impl HttpHandler for Branded {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        let mut response = HttpResponse::ok("branded");
+        response.add_header(SERVER, "branded/1.0");
+        response.headers.append(SET_COOKIE, "a=1".parse().unwrap());
+        response.headers.append(SET_COOKIE, "b=2".parse().unwrap());
+        response
+    }
+}
+
+fn router() -> Router {
+    let mut router = Router::from_json_str(CONFIG).unwrap();
+    router.register_handler("plain", Plain);
+    router.register_handler("branded", Branded);
+    router
+}
+
+fn send(router: &Router, method: Method, path: &str) -> Response<bytes::Bytes> {
+    router.route(
+        Request::builder()
+            .method(method)
+            .uri(path)
+            .body(String::new())
+            .unwrap(),
+    )
+}
+
+fn values<'a>(response: &'a Response<bytes::Bytes>, name: &str) -> Vec<&'a str> {
+    response
+        .headers()
+        .get_all(name)
+        .iter()
+        .map(|value| value.to_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn every_default_is_added_to_a_handlers_response() {
+    let response = send(&router(), Method::GET, "/plain");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(values(&response, "x-content-type-options"), ["nosniff"]);
+    assert_eq!(
+        values(&response, "strict-transport-security"),
+        ["max-age=63072000"]
+    );
+    assert_eq!(values(&response, "server"), ["router"]);
+    // `HttpResponse::ok` doesn't set a `Content-Type`, so the default's used.
+    assert_eq!(
+        values(&response, "content-type"),
+        ["application/octet-stream"]
+    );
+}
+
+#[test]
+fn headers_the_handler_or_route_set_win() {
+    let router = router();
+    let response = send(&router, Method::GET, "/branded");
+    assert_eq!(values(&response, "server"), ["branded/1.0"]);
+    assert_eq!(values(&response, "set-cookie"), ["a=1", "b=2"]);
+    assert_eq!(values(&response, "x-content-type-options"), ["nosniff"]);
+
+    let response = send(&router, Method::GET, "/template");
+    assert_eq!(response.body(), "hello");
+    assert_eq!(values(&response, "content-type"), ["text/html"]);
+    assert_eq!(values(&response, "server"), ["router"]);
+}
+
+#[test]
+fn error_responses_get_the_defaults_too() {
+    let router = router();
+    for (method, path, status) in [
+        (Method::GET, "/nowhere", StatusCode::NOT_FOUND),
+        (Method::DELETE, "/plain", StatusCode::METHOD_NOT_ALLOWED),
+        (Method::GET, "/missing", StatusCode::NOT_IMPLEMENTED),
+        (Method::HEAD, "/nowhere", StatusCode::NOT_FOUND),
+    ] {
+        let response = send(&router, method.clone(), path);
+        assert_eq!(response.status(), status, "{method} {path}");
+        assert_eq!(
+            values(&response, "x-content-type-options"),
+            ["nosniff"],
+            "{method} {path}"
+        );
+        assert_eq!(values(&response, "server"), ["router"], "{method} {path}");
+    }
+}
+
+#[test]
+fn set_default_headers_replaces_the_configured_ones() {
+    let mut router = router();
+    let mut headers = HeaderMap::new();
+    headers.append("x-frame-options", HeaderValue::from_static("DENY"));
+    headers.append("link", HeaderValue::from_static("</a.css>; rel=preload"));
+    headers.append("link", HeaderValue::from_static("</b.js>; rel=preload"));
+    router.set_default_headers(headers);
+
+    let response = send(&router, Method::GET, "/nowhere");
+    assert_eq!(values(&response, "x-frame-options"), ["DENY"]);
+    // Every value of a header.
+    assert_eq!(
+        values(&response, "link"),
+        ["</a.css>; rel=preload", "</b.js>; rel=preload"]
+    );
+    assert!(values(&response, "server").is_empty());
+    assert!(values(&response, "x-content-type-options").is_empty());
+
+    // Until the next reload.
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("default_headers.json");
+    std::fs::write(&path, CONFIG).unwrap();
+    router.reload(&path).unwrap();
+    let response = send(&router, Method::GET, "/nowhere");
+    assert_eq!(values(&response, "server"), ["router"]);
+    assert!(values(&response, "x-frame-options").is_empty());
+}
+
+#[test]
+fn invalid_headers_fail_at_load_time() {
+    for (name, value) in [("Bad Name", "ok"), ("X-Ok", "line\\nbreak")] {
+        let config =
+            format!(r#"{{"default_response_headers": {{"{name}": "{value}"}}, "endpoints": []}}"#);
+        match Router::from_json_str(&config) {
+            Err(error @ RouterError::Header { .. }) => {
+                assert_eq!(error.code(), "config.header");
+                assert!(error.to_string().contains(name), "{error}");
+            }
+            other => panic!("{name}: {:?}", other.map(|_| ())),
+        }
+    }
+}
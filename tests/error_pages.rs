@@ -0,0 +1,269 @@
+use generic_http_router::error::RouterError;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Method, Request, Response, StatusCode};
+use std::path::PathBuf;
+
+/// Answers with its own 404.
+struct OwnNotFound;
+
+impl HttpHandler for OwnNotFound {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::new(StatusCode::NOT_FOUND, "no such user")
+    }
+}
+
+/// An HTML and a JSON page for 404, a text page by default, and `GET /users`,
+/// `POST /uploads` taking at most 4 bytes and `GET /orders` without a
+/// handler.
+const CONFIG: &str = r#"{
+    "error_pages": {
+        "404": [
+            {"content_type": "text/html; charset=utf-8",
+             "body": "<h1>{{status}}</h1><p>{{path}}</p><p>{{request_id}}</p>"},
+            {"content_type": "application/json",
+             "body": "{\"status\": {{status}}, \"path\": \"{{path}}\", \"request_id\": \"{{request_id}}\"}"}
+        ],
+        "default": {"content_type": "text/plain", "body": "{{status}} at {{path}} {{unknown}}"}
+    },
+    "endpoints": [
+        {"method": "GET", "path": "/users", "description": "", "controller": "users"},
+        {"method": "POST", "path": "/uploads", "description": "", "controller": "users",
+         "max_request_size": 4},
+        {"method": "GET", "path": "/orders", "description": "", "controller": "orders"}
+    ]
+}"#;
+
+fn router() -> Router {
+    let mut router = Router::from_json_str(CONFIG).unwrap();
+    router.register_handler("users", OwnNotFound);
+    router
+}
+
+fn send(
+    router: &Router,
+    method: Method,
+    path: &str,
+    headers: &[(&str, &str)],
+) -> Response<bytes::Bytes> {
+    let mut req = Request::builder().method(method).uri(path);
+    for &(name, value) in headers {
+        req = req.header(name, value);
+    }
+    router.route(req.body("too large".to_string()).unwrap())
+}
+
+fn body(response: &Response<bytes::Bytes>) -> String {
+    String::from_utf8(response.body().to_vec()).unwrap()
+}
+
+#[test]
+fn placeholders_are_substituted_and_escaped() {
+    let router = router();
+    let response = send(
+        &router,
+        Method::GET,
+        "/missing",
+        &[("accept", "text/html"), ("x-request-id", "<req & \"1\">")],
+    );
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(
+        response.headers()["content-type"],
+        "text/html; charset=utf-8"
+    );
+    assert_eq!(
+        body(&response),
+        "<h1>404</h1><p>/missing</p><p>&lt;req &amp; &quot;1&quot;&gt;</p>"
+    );
+
+    // JSON values are escaped as strings, and a missing request ID is empty.
+    let response = send(
+        &router,
+        Method::GET,
+        "/missing",
+        &[("accept", "application/json")],
+    );
+    assert_eq!(response.headers()["content-type"], "application/json");
+    let page: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(
+        page,
+        serde_json::json!({"status": 404, "path": "/missing", "request_id": ""})
+    );
+    let response = send(
+        &router,
+        Method::GET,
+        "/missing",
+        &[("accept", "application/json"), ("x-request-id", "a\"b\\c")],
+    );
+    let page: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(page["request_id"], "a\"b\\c");
+
+    // Unknown placeholders are left as they are.
+    let response = send(&router, Method::DELETE, "/users", &[]);
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers()["content-type"], "text/plain");
+    assert_eq!(body(&response), "405 at /users {{unknown}}");
+}
+
+#[test]
+fn the_variant_is_picked_by_accept() {
+    let router = router();
+    for (accept, content_type) in [
+        ("text/html", "text/html; charset=utf-8"),
+        ("application/json", "application/json"),
+        ("text/html;q=0.5, application/json", "application/json"),
+        (
+            "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
+            "text/html; charset=utf-8",
+        ),
+        ("application/*", "application/json"),
+        // Neither is accepted: the first one is used.
+        ("image/png", "text/html; charset=utf-8"),
+    ] {
+        let response = send(&router, Method::GET, "/missing", &[("accept", accept)]);
+        assert_eq!(response.headers()["content-type"], content_type, "{accept}");
+        assert_eq!(response.headers()["vary"], "accept", "{accept}");
+    }
+    // Without `Accept`, the first one too.
+    let response = send(&router, Method::GET, "/missing", &[]);
+    assert_eq!(
+        response.headers()["content-type"],
+        "text/html; charset=utf-8"
+    );
+
+    // A single page doesn't vary.
+    let response = send(
+        &router,
+        Method::DELETE,
+        "/users",
+        &[("accept", "application/json")],
+    );
+    assert_eq!(response.headers()["content-type"], "text/plain");
+    assert!(response.headers().get("vary").is_none());
+}
+
+#[test]
+fn only_built_in_errors_use_the_pages() {
+    let router = router();
+    // A status without a page uses the default one.
+    let response = send(&router, Method::POST, "/uploads", &[]);
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(body(&response), "413 at /uploads {{unknown}}");
+    let response = send(&router, Method::GET, "/orders", &[]);
+    assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+    assert_eq!(body(&response), "501 at /orders {{unknown}}");
+
+    // A handler's own error response is left as it is.
+    let response = send(&router, Method::GET, "/users", &[("accept", "text/html")]);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(body(&response), "no such user");
+    assert!(response.headers().get("vary").is_none());
+}
+
+#[test]
+fn without_a_default_other_statuses_are_unchanged() {
+    let router = Router::from_json_str(
+        r#"{"error_pages": {"404": {"content_type": "text/plain", "body": "gone"}},
+            "endpoints": [
+                {"method": "GET", "path": "/users", "description": "", "controller": "users"}
+            ]}"#,
+    )
+    .unwrap();
+    assert_eq!(body(&send(&router, Method::GET, "/missing", &[])), "gone");
+    let response = send(&router, Method::DELETE, "/users", &[]);
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert!(response.body().is_empty());
+}
+
+/// Writes a configuration with `error_pages` to a temp directory of its own,
+/// named `name`, and returns its path.
+fn config_in_dir(name: &str, error_pages: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("error_pages_{name}"));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("routes.json");
+    std::fs::write(
+        &path,
+        format!(r#"{{"error_pages": {error_pages}, "endpoints": []}}"#),
+    )
+    .unwrap();
+    path
+}
+
+#[test]
+fn template_files_are_read_relative_to_the_config() {
+    let path = config_in_dir(
+        "file",
+        r#"{"404": {"content_type": "text/html", "file": "pages/404.html"}}"#,
+    );
+    let pages = path.parent().unwrap().join("pages");
+    std::fs::create_dir_all(&pages).unwrap();
+    std::fs::write(pages.join("404.html"), "<p>No {{path}} here</p>").unwrap();
+    let router = Router::new(&path).unwrap();
+    // The file is read once, when loading.
+    std::fs::remove_file(pages.join("404.html")).unwrap();
+    assert_eq!(
+        body(&send(&router, Method::GET, "/missing", &[])),
+        "<p>No /missing here</p>"
+    );
+}
+
+#[test]
+fn a_missing_template_file_is_a_load_error() {
+    let path = config_in_dir(
+        "missing_file",
+        r#"{"500": {"content_type": "text/html", "file": "pages/nowhere.html"}}"#,
+    );
+    match Router::new(&path) {
+        Err(error @ RouterError::ErrorPage { .. }) => {
+            assert_eq!(error.code(), "config.error_page");
+            let RouterError::ErrorPage { key, reason } = error else {
+                unreachable!()
+            };
+            assert_eq!(key, "500");
+            assert!(
+                reason.starts_with("can't read 'pages/nowhere.html'"),
+                "{reason}"
+            );
+        }
+        other => panic!("{:?}", other.err()),
+    }
+}
+
+#[test]
+fn invalid_pages_are_load_errors() {
+    for (error_pages, key, reason) in [
+        (
+            r#"{"200": {"content_type": "text/plain", "body": ""}}"#,
+            "200",
+            "expected an error status code or \"default\"",
+        ),
+        (
+            r#"{"oops": {"content_type": "text/plain", "body": ""}}"#,
+            "oops",
+            "expected an error status code or \"default\"",
+        ),
+        (
+            r#"{"404": {"content_type": "text/plain"}}"#,
+            "404",
+            "exactly one of body and file must be set",
+        ),
+        (
+            r#"{"404": {"content_type": "text/plain", "body": "", "file": "x"}}"#,
+            "404",
+            "exactly one of body and file must be set",
+        ),
+        (
+            r#"{"404": {"content_type": "not a type", "body": ""}}"#,
+            "404",
+            "invalid content_type 'not a type'",
+        ),
+        (r#"{"404": []}"#, "404", "no page is given"),
+    ] {
+        let json = format!(r#"{{"error_pages": {error_pages}, "endpoints": []}}"#);
+        match Router::from_json_str(&json) {
+            Err(RouterError::ErrorPage { key: k, reason: r }) => {
+                assert_eq!((k.as_str(), r.as_str()), (key, reason), "{error_pages}");
+            }
+            other => panic!("{error_pages}: {:?}", other.err()),
+        }
+    }
+}
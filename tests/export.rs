@@ -0,0 +1,110 @@
+use generic_http_router::HttpResponse;
+use http::StatusCode;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Order {
+    id: u32,
+    customer: String,
+    note: Option<String>,
+    paid: bool,
+    total: f64,
+}
+
+fn orders() -> Vec<Order> {
+    vec![
+        Order {
+            id: 1,
+            customer: "Ada".to_string(),
+            note: None,
+            paid: true,
+            total: 9.5,
+        },
+        Order {
+            id: 2,
+            customer: "Lovelace, Ada".to_string(),
+            note: Some("said \"hi\"\nthen left".to_string()),
+            paid: false,
+            total: 0.0,
+        },
+    ]
+}
+
+fn body(response: HttpResponse) -> String {
+    String::from_utf8(response.body.collect(usize::MAX).unwrap().to_vec()).unwrap()
+}
+
+#[test]
+fn ndjson_has_one_document_per_line() {
+    let response = HttpResponse::ndjson(StatusCode::OK, orders());
+    assert_eq!(response.headers["content-type"], "application/x-ndjson");
+    assert!(response.body.is_stream());
+    assert_eq!(
+        body(response),
+        "{\"id\":1,\"customer\":\"Ada\",\"note\":null,\"paid\":true,\"total\":9.5}\n\
+         {\"id\":2,\"customer\":\"Lovelace, Ada\",\"note\":\"said \\\"hi\\\"\\nthen left\",\"paid\":false,\"total\":0.0}\n"
+    );
+}
+
+#[test]
+fn ndjson_of_no_items_is_empty() {
+    assert_eq!(
+        body(HttpResponse::ndjson(StatusCode::OK, Vec::<Order>::new())),
+        ""
+    );
+}
+
+#[cfg(feature = "csv")]
+mod csv {
+    use super::*;
+
+    #[test]
+    fn header_record_then_one_record_per_item() {
+        let response = HttpResponse::csv(StatusCode::OK, orders());
+        assert_eq!(response.headers["content-type"], "text/csv; charset=utf-8");
+        assert_eq!(
+            body(response),
+            "id,customer,note,paid,total\r\n\
+             1,Ada,,true,9.5\r\n\
+             2,\"Lovelace, Ada\",\"said \"\"hi\"\"\nthen left\",false,0.0\r\n"
+        );
+    }
+
+    #[test]
+    fn no_items_give_an_empty_body() {
+        assert_eq!(
+            body(HttpResponse::csv(StatusCode::OK, Vec::<Order>::new())),
+            ""
+        );
+    }
+
+    #[test]
+    fn carriage_returns_are_quoted() {
+        #[derive(Serialize)]
+        struct Line {
+            text: &'static str,
+        }
+        assert_eq!(
+            body(HttpResponse::csv(StatusCode::OK, [Line { text: "a\rb" }])),
+            "text\r\n\"a\rb\"\r\n"
+        );
+    }
+
+    #[test]
+    fn nested_fields_end_the_stream() {
+        #[derive(Serialize)]
+        struct Nested {
+            tags: Vec<&'static str>,
+        }
+        let response = HttpResponse::csv(StatusCode::OK, [Nested { tags: vec!["a"] }]);
+        let error = response.body.collect(usize::MAX).unwrap_err();
+        assert!(error.to_string().contains("`tags`"), "{error}");
+    }
+
+    #[test]
+    fn items_must_be_structs() {
+        let response = HttpResponse::csv(StatusCode::OK, [1, 2]);
+        let error = response.body.collect(usize::MAX).unwrap_err();
+        assert!(error.to_string().contains("must be a struct"), "{error}");
+    }
+}
@@ -0,0 +1,147 @@
+use generic_http_router::guard::Guard;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Request, Response, StatusCode};
+
+/// Set by the middleware in front of the router, for the handler to read.
+#[derive(Clone, Debug, PartialEq)]
+struct User(String);
+
+/// Set by the handler, for the server adapter to read.
+#[derive(Clone, Debug, PartialEq)]
+struct CacheHint(u32);
+
+/// Greets the user its middleware authenticated, and hints the response may
+/// be cached for a minute.
+struct Greet;
+
+impl HttpHandler for Greet {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let Some(User(name)) = req.extensions.get::<User>() else {
+            return HttpResponse::new(StatusCode::UNAUTHORIZED, "no user");
+        };
+        let mut response = HttpResponse::ok(format!("hello {name}"));
+        response.extensions.insert(CacheHint(60));
+        response
+    }
+}
+
+/// Lets only the `root` user through.
+struct Admin;
+
+impl Guard for Admin {
+    fn check(&self, req: &HttpRequest) -> Result<(), HttpResponse> {
+        match req.extensions.get::<User>() {
+            Some(User(name)) if name == "root" => Ok(()),
+            _ => Err(HttpResponse::new(StatusCode::FORBIDDEN, "not root")),
+        }
+    }
+}
+
+/// A router with `GET /me`, and an `admin` guard on `GET /admin` that
+/// checks the middleware's `User`.
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/me", "description": "", "controller": "greet"},
+            {"method": "GET", "path": "/admin", "description": "", "controller": "greet",
+             "guards": ["admin"]}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("greet", Greet);
+    router.register_guard("admin", Box::new(Admin));
+    router
+}
+
+/// A `before` middleware in front of the router: authenticates the request
+/// from its `x-user` header, as a server layer would.
+fn authenticate(mut req: Request<String>) -> Request<String> {
+    let user = req
+        .headers()
+        .get("x-user")
+        .map(|value| User(value.to_str().unwrap().to_string()));
+    if let Some(user) = user {
+        req.extensions_mut().insert(user);
+    }
+    req
+}
+
+fn get(path: &str, user: Option<&str>) -> Request<String> {
+    let mut req = Request::get(path);
+    if let Some(user) = user {
+        req = req.header("x-user", user);
+    }
+    authenticate(req.body(String::new()).unwrap())
+}
+
+#[test]
+fn the_handler_reads_what_the_middleware_inserted() {
+    let router = router();
+    let response = router.route(get("/me", Some("ada")));
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), "hello ada");
+    assert_eq!(
+        response.extensions().get::<CacheHint>(),
+        Some(&CacheHint(60))
+    );
+
+    let response = router.route(get("/me", None));
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.extensions().get::<CacheHint>(), None);
+}
+
+#[test]
+fn guards_read_the_extensions_too() {
+    let router = router();
+    assert_eq!(
+        router.route(get("/admin", Some("root"))).status(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        router.route(get("/admin", Some("ada"))).status(),
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        router.route(get("/admin", None)).status(),
+        StatusCode::FORBIDDEN
+    );
+}
+
+#[test]
+fn response_extensions_survive_every_conversion() {
+    let router = router();
+
+    let response = router.route_streaming(get("/me", Some("ada")));
+    assert_eq!(
+        response.extensions().get::<CacheHint>(),
+        Some(&CacheHint(60))
+    );
+
+    let req = get("/me", Some("ada"));
+    let resolution = router.resolve(&req);
+    let response: Response<bytes::Bytes> = router.dispatch(resolution, req);
+    assert_eq!(
+        response.extensions().get::<CacheHint>(),
+        Some(&CacheHint(60))
+    );
+
+    let mut response = HttpResponse::ok("x");
+    response.extensions.insert(CacheHint(5));
+    let response = Response::<Vec<u8>>::from(response);
+    assert_eq!(
+        response.extensions().get::<CacheHint>(),
+        Some(&CacheHint(5))
+    );
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn route_async_carries_the_extensions() {
+    let router = router();
+    let response = router.route_async(get("/me", Some("ada"))).await;
+    assert_eq!(response.body(), "hello ada");
+    assert_eq!(
+        response.extensions().get::<CacheHint>(),
+        Some(&CacheHint(60))
+    );
+}
@@ -0,0 +1,258 @@
+#![cfg(feature = "extractors")]
+
+use generic_http_router::error::ExtractError;
+use generic_http_router::extract::{FromRequest, Headers, Json, Path, Query, RawBody};
+use generic_http_router::{HttpRequest, Router};
+use http::{Method, Request, Response, StatusCode};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Filters {
+    status: Option<String>,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct CreateUser {
+    name: String,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct OrderPath {
+    id: u64,
+    order: String,
+}
+
+/// The example server's routes, with handlers taking extractors.
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/health", "description": "", "controller": "health"},
+            {"method": "GET", "path": "/users", "description": "", "controller": "users::list"},
+            {"method": "GET", "path": "/users/:id", "description": "", "controller": "users::get"},
+            {"method": "POST", "path": "/users", "description": "", "controller": "users::create"},
+            {"method": "GET", "path": "/users/:id/orders/:order", "description": "",
+             "controller": "orders::get"},
+            {"method": "PUT", "path": "/users/:id/orders/:order", "description": "",
+             "controller": "orders::put"},
+            {"method": "POST", "path": "/echo", "description": "", "controller": "echo"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_extracting("health", || "ok");
+    router.register_extracting("users::list", |Query(filters): Query<Filters>| {
+        format!("{:?} {:?}", filters.status, filters.limit)
+    });
+    router.register_extracting("users::get", |Path(id): Path<u64>| format!("user {id}"));
+    router.register_extracting("users::create", |Json(user): Json<CreateUser>| {
+        (StatusCode::CREATED, format!("created {}", user.name))
+    });
+    router.register_extracting("orders::get", |Path((id, order)): Path<(u64, String)>| {
+        format!("user {id} order {order}")
+    });
+    router.register_extracting(
+        "orders::put",
+        |Path(path): Path<OrderPath>,
+         Query(filters): Query<Filters>,
+         Headers(headers): Headers,
+         Json(body): Json<Value>| {
+            format!(
+                "{} {} {:?} {} {}",
+                path.id,
+                path.order,
+                filters.limit,
+                headers["x-trace"].to_str().unwrap(),
+                body["note"]
+            )
+        },
+    );
+    router.register_extracting(
+        "echo",
+        |RawBody(body): RawBody, json: Option<Json<Value>>| {
+            format!("{} bytes, json: {}", body.len(), json.is_some())
+        },
+    );
+    router
+}
+
+fn send(
+    router: &Router,
+    method: Method,
+    uri: &str,
+    content_type: Option<&str>,
+    body: &str,
+) -> Response<bytes::Bytes> {
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("x-trace", "t-1");
+    if let Some(content_type) = content_type {
+        builder = builder.header("content-type", content_type);
+    }
+    router.route(builder.body(body.to_string()).unwrap())
+}
+
+fn get(router: &Router, uri: &str) -> Response<bytes::Bytes> {
+    send(router, Method::GET, uri, None, "")
+}
+
+fn text(response: &Response<bytes::Bytes>) -> &str {
+    std::str::from_utf8(response.body()).unwrap()
+}
+
+#[test]
+fn handlers_get_the_extracted_arguments() {
+    let router = router();
+    assert_eq!(text(&get(&router, "/health")), "ok");
+    assert_eq!(text(&get(&router, "/users/42")), "user 42");
+    assert_eq!(
+        text(&get(&router, "/users?status=active&limit=10")),
+        "Some(\"active\") Some(10)"
+    );
+    assert_eq!(text(&get(&router, "/users")), "None None");
+    assert_eq!(
+        text(&get(&router, "/users/7/orders/a-1")),
+        "user 7 order a-1"
+    );
+
+    let response = send(
+        &router,
+        Method::POST,
+        "/users",
+        Some("application/json"),
+        r#"{"name": "ada"}"#,
+    );
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(text(&response), "created ada");
+}
+
+#[test]
+fn four_extractors() {
+    let response = send(
+        &router(),
+        Method::PUT,
+        "/users/7/orders/a-1?limit=3",
+        Some("application/vnd.api+json; charset=utf-8"),
+        r#"{"note": "gift"}"#,
+    );
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(text(&response), "7 a-1 Some(3) t-1 \"gift\"");
+}
+
+#[test]
+fn invalid_paths_and_queries_are_bad_requests() {
+    let router = router();
+    let response = get(&router, "/users/ada");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        text(&response),
+        "Invalid path parameters: invalid value 'ada'"
+    );
+    let response = get(&router, "/users?limit=many");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(
+        text(&response).starts_with("Invalid query string: "),
+        "{}",
+        text(&response)
+    );
+}
+
+#[test]
+fn json_bodies_must_be_json() {
+    let router = router();
+    for content_type in [None, Some("text/plain"), Some("json"), Some("+json")] {
+        let response = send(
+            &router,
+            Method::POST,
+            "/users",
+            content_type,
+            r#"{"name": "ada"}"#,
+        );
+        assert_eq!(
+            response.status(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "{content_type:?}"
+        );
+        assert_eq!(
+            text(&response),
+            "Expected a request body of type application/json"
+        );
+    }
+    for body in ["{", r#"{"title": "ada"}"#, ""] {
+        let response = send(
+            &router,
+            Method::POST,
+            "/users",
+            Some("application/json"),
+            body,
+        );
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{body}");
+        assert!(text(&response).starts_with("Invalid JSON body: "), "{body}");
+    }
+}
+
+#[test]
+fn optional_extractors_dont_reject() {
+    let router = router();
+    let response = send(
+        &router,
+        Method::POST,
+        "/echo",
+        Some("application/json"),
+        "[1]",
+    );
+    assert_eq!(text(&response), "3 bytes, json: true");
+    let response = send(&router, Method::POST, "/echo", None, "[1]");
+    assert_eq!(text(&response), "3 bytes, json: false");
+}
+
+#[test]
+fn rejections_become_problems_when_enabled() {
+    let mut router = router();
+    router.use_problem_responses(true);
+    let response = get(&router, "/users/ada");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/problem+json"
+    );
+    let problem: Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(problem["status"], 400);
+    assert_eq!(
+        problem["detail"],
+        "Invalid path parameters: invalid value 'ada'"
+    );
+
+    let response = send(&router, Method::POST, "/users", None, "{}");
+    let problem: Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(problem["status"], 415);
+}
+
+#[test]
+fn extractors_can_be_used_on_their_own() {
+    let req = HttpRequest::builder()
+        .uri("/search?q=rust&limit=5")
+        .param("id", "9")
+        .json(&json!({"name": "ada"}))
+        .build()
+        .unwrap();
+    assert_eq!(Path::<u64>::from_request(&req).unwrap(), Path(9));
+    assert_eq!(
+        Query::<Filters>::from_request(&req).unwrap(),
+        Query(Filters {
+            status: None,
+            limit: Some(5)
+        })
+    );
+    let Json(user) = Json::<CreateUser>::from_request(&req).unwrap();
+    assert_eq!(user.name, "ada");
+    assert_eq!(RawBody::from_request(&req).unwrap().0, r#"{"name":"ada"}"#);
+    // A tuple needs as many parameters as it has fields.
+    assert!(matches!(
+        Path::<(u64, u64)>::from_request(&req),
+        Err(ExtractError::Path(_))
+    ));
+    let error = Json::<Value>::from_request(&HttpRequest::builder().build().unwrap()).unwrap_err();
+    assert_eq!(error.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
@@ -0,0 +1,191 @@
+use generic_http_router::config::Endpoint;
+use generic_http_router::error::RouterError;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, IntoHttpResponse, Router};
+use http::{Method, Request, StatusCode};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Answers with the JSON document it was built with.
+struct StaticJson(Value);
+
+impl HttpHandler for StaticJson {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        self.0.clone().into_http_response()
+    }
+}
+
+struct Named(&'static str);
+
+impl HttpHandler for Named {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok(self.0)
+    }
+}
+
+/// `GET /plans` and `GET /regions` share the `static_json` controller with
+/// different arguments, `GET /pricing` has the same ones as `/plans`, and
+/// `GET /health` names a controller as a string.
+const CONFIG: &str = r#"{"endpoints": [
+    {"method": "GET", "path": "/plans", "description": "",
+     "controller": {"name": "static_json", "args": {"body": ["free", "pro"]}}},
+    {"method": "GET", "path": "/regions", "description": "",
+     "controller": {"name": "static_json", "args": {"body": ["eu", "us"]}}},
+    {"method": "GET", "path": "/pricing", "description": "",
+     "controller": {"name": "static_json", "args": {"body": ["free", "pro"]}}},
+    {"method": "GET", "path": "/health", "description": "", "controller": "health"}
+]}"#;
+
+/// Registers the `static_json` factory, answering with its `body` argument
+/// and failing without one, and returns the count of handlers it built.
+fn register_static_json(router: &mut Router) -> Result<Arc<AtomicUsize>, RouterError> {
+    let built = Arc::new(AtomicUsize::new(0));
+    let count = Arc::clone(&built);
+    router.register_factory(
+        "static_json",
+        Box::new(move |args| {
+            count.fetch_add(1, Ordering::SeqCst);
+            let body = args.get("body").ok_or_else(|| RouterError::Io {
+                path: None,
+                source: std::io::Error::other("missing `body` argument"),
+            })?;
+            Ok(Box::new(StaticJson(body.clone())))
+        }),
+    )?;
+    Ok(built)
+}
+
+fn get(router: &Router, path: &str) -> (StatusCode, String) {
+    let response = router.route(Request::get(path).body(String::new()).unwrap());
+    let body = String::from_utf8(response.body().to_vec()).unwrap();
+    (response.status(), body)
+}
+
+#[test]
+fn endpoints_sharing_a_factory_get_their_own_handlers() {
+    let mut router = Router::from_json_str(CONFIG).unwrap();
+    let built = register_static_json(&mut router).unwrap();
+    router.register_handler("health", Named("ok"));
+    assert_eq!(
+        get(&router, "/plans"),
+        (StatusCode::OK, r#"["free","pro"]"#.into())
+    );
+    assert_eq!(
+        get(&router, "/regions"),
+        (StatusCode::OK, r#"["eu","us"]"#.into())
+    );
+    assert_eq!(
+        get(&router, "/pricing"),
+        (StatusCode::OK, r#"["free","pro"]"#.into())
+    );
+    // Routes with the same arguments share a handler.
+    assert_eq!(built.load(Ordering::SeqCst), 2);
+    // The string form still names a registered handler.
+    assert_eq!(get(&router, "/health"), (StatusCode::OK, "ok".into()));
+    assert!(router.validate_handlers().is_ok());
+
+    // The controller is the name, and the arguments are kept.
+    let plans = router.routes().find(|e| e.path == "/plans").unwrap();
+    assert_eq!(plans.controller, "static_json");
+    assert_eq!(
+        plans.controller_args,
+        Some(json!({"body": ["free", "pro"]}))
+    );
+}
+
+#[test]
+fn added_routes_get_a_handler_from_the_factory() {
+    let mut router = Router::from_json_str(CONFIG).unwrap();
+    let built = register_static_json(&mut router).unwrap();
+    let mut endpoint = Endpoint::new(Method::GET, "/currencies", "static_json");
+    endpoint.controller_args = Some(json!({"body": ["EUR"]}));
+    router.add_route(endpoint).unwrap();
+    assert_eq!(get(&router, "/currencies").1, r#"["EUR"]"#);
+    // The existing handlers are kept.
+    assert_eq!(built.load(Ordering::SeqCst), 3);
+    assert_eq!(get(&router, "/plans").1, r#"["free","pro"]"#);
+}
+
+#[test]
+fn a_missing_factory_fails_validation() {
+    let mut router = Router::from_json_str(CONFIG).unwrap();
+    router.register_handler("health", Named("ok"));
+    // A handler registered under the name doesn't serve routes with
+    // arguments.
+    router.register_handler("static_json", Named("plain"));
+    match router.validate_handlers() {
+        Err(RouterError::MissingHandler {
+            method,
+            path,
+            controller,
+        }) => {
+            assert_eq!(method, Method::GET);
+            assert_eq!(path, "/plans");
+            assert_eq!(controller, "static_json");
+        }
+        other => panic!("{other:?}"),
+    }
+    assert_eq!(get(&router, "/plans").0, StatusCode::NOT_IMPLEMENTED);
+}
+
+#[test]
+fn factory_errors_name_the_endpoint() {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/plans", "description": "",
+             "controller": {"name": "static_json", "args": {"body": []}}},
+            {"method": "POST", "path": "/broken", "description": "",
+             "controller": {"name": "static_json", "args": {"file": "plans.json"}}}
+        ]}"#,
+    )
+    .unwrap();
+    let error = register_static_json(&mut router).unwrap_err();
+    assert_eq!(error.code(), "config.endpoint");
+    match &error {
+        RouterError::Endpoint {
+            method,
+            path,
+            reason,
+        } => {
+            assert_eq!((method, path.as_str()), (&Method::POST, "/broken"));
+            assert!(
+                reason.starts_with("controller 'static_json' failed to build its handler: "),
+                "{reason}"
+            );
+            assert!(reason.contains("missing `body` argument"), "{reason}");
+        }
+        other => panic!("{other:?}"),
+    }
+    // No handler is registered.
+    assert_eq!(get(&router, "/plans").0, StatusCode::NOT_IMPLEMENTED);
+
+    // Nor is a route whose handler can't be built added.
+    let mut router = Router::from_json_str(CONFIG).unwrap();
+    register_static_json(&mut router).unwrap();
+    let mut endpoint = Endpoint::new(Method::GET, "/broken", "static_json");
+    endpoint.controller_args = Some(json!({}));
+    assert!(matches!(
+        router.add_route(endpoint),
+        Err(RouterError::Endpoint { path, .. }) if path == "/broken"
+    ));
+    assert_eq!(get(&router, "/broken").0, StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn a_controller_object_needs_a_name() {
+    match Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/plans", "description": "",
+             "controller": {"args": {}}}
+        ]}"#,
+    ) {
+        Err(RouterError::Json { source: e, .. }) => {
+            assert!(
+                e.to_string()
+                    .contains("a controller object needs a string `name`"),
+                "{e}"
+            );
+        }
+        other => panic!("{:?}", other.err()),
+    }
+}
@@ -0,0 +1,357 @@
+#![cfg(feature = "files")]
+
+use generic_http_router::{HttpRequest, HttpResponse, Router};
+use http::{Request, Response, StatusCode};
+use std::io;
+use std::path::PathBuf;
+
+/// The contents of the temp file: 100 bytes, `0123456789` ten times.
+fn contents() -> Vec<u8> {
+    (0..100u8).map(|i| b'0' + i % 10).collect()
+}
+
+/// Writes the contents to a temp file named `name`, and returns its path.
+fn temp_file(name: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, contents()).unwrap();
+    path
+}
+
+fn with_range(range: &str) -> HttpRequest {
+    HttpRequest::builder()
+        .header("range", range)
+        .build()
+        .unwrap()
+}
+
+fn header<'a>(response: &'a HttpResponse, name: &str) -> Option<&'a str> {
+    response
+        .headers
+        .get(name)
+        .map(|value| value.to_str().unwrap())
+}
+
+fn body(response: HttpResponse) -> Vec<u8> {
+    response.body.collect(usize::MAX).unwrap().to_vec()
+}
+
+#[test]
+fn full_fetch() {
+    let path = temp_file("full_fetch.txt");
+    let response = HttpResponse::file(&path).unwrap();
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        header(&response, "content-type"),
+        Some("text/plain; charset=utf-8")
+    );
+    assert_eq!(header(&response, "content-length"), Some("100"));
+    assert_eq!(header(&response, "accept-ranges"), Some("bytes"));
+    assert!(
+        header(&response, "last-modified")
+            .unwrap()
+            .ends_with(" GMT")
+    );
+    assert_eq!(header(&response, "content-disposition"), None);
+    assert_eq!(body(response), contents());
+
+    // Without a `Range` header, `file_ranged` is a full fetch too.
+    let response =
+        HttpResponse::file_ranged(&path, &HttpRequest::builder().build().unwrap()).unwrap();
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(header(&response, "content-range"), None);
+    assert_eq!(body(response), contents());
+}
+
+#[test]
+fn middle_range() {
+    let path = temp_file("middle_range.bin");
+    let response = HttpResponse::file_ranged(&path, &with_range("bytes=10-24")).unwrap();
+    assert_eq!(response.status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        header(&response, "content-type"),
+        Some("application/octet-stream")
+    );
+    assert_eq!(header(&response, "content-range"), Some("bytes 10-24/100"));
+    assert_eq!(header(&response, "content-length"), Some("15"));
+    assert_eq!(body(response), &contents()[10..25]);
+}
+
+#[test]
+fn suffix_range() {
+    let path = temp_file("suffix_range.bin");
+    let response = HttpResponse::file_ranged(&path, &with_range("bytes=-7")).unwrap();
+    assert_eq!(response.status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(header(&response, "content-range"), Some("bytes 93-99/100"));
+    assert_eq!(header(&response, "content-length"), Some("7"));
+    assert_eq!(body(response), &contents()[93..]);
+}
+
+#[test]
+fn invalid_ranges() {
+    let path = temp_file("invalid_ranges.bin");
+
+    // A range past the end of the file can't be satisfied.
+    let response = HttpResponse::file_ranged(&path, &with_range("bytes=100-")).unwrap();
+    assert_eq!(response.status, StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(header(&response, "content-range"), Some("bytes */100"));
+    assert!(body(response).is_empty());
+
+    // A malformed range, or one in another unit, is ignored.
+    for range in ["bytes=9-3", "bytes=abc", "lines=0-9"] {
+        let response = HttpResponse::file_ranged(&path, &with_range(range)).unwrap();
+        assert_eq!(response.status, StatusCode::OK, "{range}");
+        assert_eq!(header(&response, "content-range"), None, "{range}");
+        assert_eq!(body(response), contents(), "{range}");
+    }
+}
+
+#[test]
+fn attachment_names_are_escaped() {
+    let path = temp_file("attachment.csv");
+    let disposition = |name| {
+        let response = HttpResponse::file_attachment(&path, name).unwrap();
+        assert_eq!(
+            header(&response, "content-type"),
+            Some("text/csv; charset=utf-8")
+        );
+        header(&response, "content-disposition")
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(
+        disposition("report.csv"),
+        r#"attachment; filename="report.csv""#
+    );
+    assert_eq!(
+        disposition(r#"say "hi"\now.csv"#),
+        r#"attachment; filename="say \"hi\"\\now.csv""#
+    );
+    assert_eq!(
+        disposition("résumé 1.csv"),
+        r#"attachment; filename="r_sum_ 1.csv"; filename*=UTF-8''r%C3%A9sum%C3%A9%201.csv"#
+    );
+}
+
+#[test]
+fn missing_files_and_directories_are_errors() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let missing = dir.join("no_such_file.txt");
+    let error = HttpResponse::file(&missing).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    let error = HttpResponse::file_ranged(&missing, &with_range("bytes=0-9")).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    let error = HttpResponse::file(&dir).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
+
+/// Creates the directory `name` with `app.js`, its `app.js.gz` sibling,
+/// `plain.css`, and a `sub dir` with a hidden file, a file with a weird name
+/// and a subdirectory, and returns its path.
+fn static_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    let sub = dir.join("sub dir");
+    std::fs::create_dir_all(sub.join("nested")).unwrap();
+    std::fs::write(dir.join("app.js"), "plain js").unwrap();
+    std::fs::write(dir.join("app.js.gz"), "gzipped js").unwrap();
+    std::fs::write(dir.join("plain.css"), "css").unwrap();
+    std::fs::write(sub.join(".hidden"), "secret").unwrap();
+    std::fs::write(sub.join(r#"<b>&"x'.txt"#), "weird").unwrap();
+    std::fs::write(sub.join("z.txt"), "z").unwrap();
+    dir
+}
+
+/// A router serving `dir` at `/assets/*file`, with the `static_files`
+/// options `options`, e.g. `"etag": false`.
+fn static_router(dir: &std::path::Path, options: &str) -> Router {
+    let options = match options {
+        "" => String::new(),
+        options => format!(", {options}"),
+    };
+    Router::from_json_str(&format!(
+        r#"{{"endpoints": [
+            {{"method": "GET", "path": "/assets/*file", "description": "", "controller": "",
+              "static_files": {{"dir": {:?}{options}}}}}
+        ]}}"#,
+        dir.to_str().unwrap()
+    ))
+    .unwrap()
+}
+
+fn fetch(router: &Router, path: &str, headers: &[(&str, &str)]) -> Response<bytes::Bytes> {
+    let mut req = Request::get(path);
+    for &(name, value) in headers {
+        req = req.header(name, value);
+    }
+    router.route(req.body(String::new()).unwrap())
+}
+
+#[test]
+fn static_files_answer_304_to_a_matching_etag_or_date() {
+    let dir = static_dir("static_304");
+    let router = static_router(&dir, "");
+    let response = fetch(&router, "/assets/plain.css", &[]);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), "css");
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+    let last_modified = response.headers()["last-modified"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    // From the size and the modification time.
+    assert!(etag.starts_with('"') && etag.ends_with("-3\""), "{etag}");
+
+    for if_none_match in [
+        etag.as_str(),
+        &format!("W/{etag}"),
+        &format!("\"x\", {etag}"),
+        "*",
+    ] {
+        let response = fetch(
+            &router,
+            "/assets/plain.css",
+            &[("if-none-match", if_none_match)],
+        );
+        assert_eq!(
+            response.status(),
+            StatusCode::NOT_MODIFIED,
+            "{if_none_match}"
+        );
+        assert!(response.body().is_empty());
+        assert_eq!(response.headers()["etag"], etag.as_str());
+        assert_eq!(response.headers()["last-modified"], last_modified.as_str());
+    }
+    let response = fetch(
+        &router,
+        "/assets/plain.css",
+        &[("if-none-match", "\"other\"")],
+    );
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = fetch(
+        &router,
+        "/assets/plain.css",
+        &[("if-modified-since", &last_modified)],
+    );
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    let response = fetch(
+        &router,
+        "/assets/plain.css",
+        &[("if-modified-since", "Sat, 05 Nov 1994 08:49:37 GMT")],
+    );
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[test]
+fn etag_and_last_modified_can_be_turned_off() {
+    let dir = static_dir("static_validators_off");
+    let router = static_router(&dir, r#""etag": false, "last_modified": false"#);
+    let response = fetch(&router, "/assets/plain.css", &[("if-none-match", "*")]);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get("etag").is_none());
+    assert!(response.headers().get("last-modified").is_none());
+    let response = fetch(
+        &router,
+        "/assets/plain.css",
+        &[("if-modified-since", "Fri, 01 Jan 2100 00:00:00 GMT")],
+    );
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[test]
+fn gzip_clients_get_the_precompressed_sibling() {
+    let dir = static_dir("static_gz");
+    let router = static_router(&dir, "");
+    let gzip = [("accept-encoding", "br, gzip;q=0.8")];
+    let response = fetch(&router, "/assets/app.js", &gzip);
+    assert_eq!(response.body(), "gzipped js");
+    assert_eq!(response.headers()["content-encoding"], "gzip");
+    // The type is the original file's.
+    assert_eq!(
+        response.headers()["content-type"],
+        "text/javascript; charset=utf-8"
+    );
+    // The validators are the sibling's too.
+    let gzipped_etag = response.headers()["etag"].clone();
+    let response = fetch(&router, "/assets/app.js", &[]);
+    assert_ne!(response.headers()["etag"], gzipped_etag);
+
+    for accept_encoding in [
+        &[][..],
+        &[("accept-encoding", "gzip;q=0")],
+        &[("accept-encoding", "br")],
+    ] {
+        let response = fetch(&router, "/assets/app.js", accept_encoding);
+        assert_eq!(response.body(), "plain js", "{accept_encoding:?}");
+        assert!(response.headers().get("content-encoding").is_none());
+    }
+    // `*` accepts gzip.
+    let response = fetch(&router, "/assets/app.js", &[("accept-encoding", "*")]);
+    assert_eq!(response.body(), "gzipped js");
+    // A file without a sibling is served as is.
+    let response = fetch(&router, "/assets/plain.css", &gzip);
+    assert_eq!(response.body(), "css");
+    assert!(response.headers().get("content-encoding").is_none());
+
+    let router = static_router(&dir, r#""precompressed": false"#);
+    let response = fetch(&router, "/assets/app.js", &gzip);
+    assert_eq!(response.body(), "plain js");
+    assert!(response.headers().get("vary").is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn the_precompressed_sibling_cant_leave_the_directory() {
+    let dir = static_dir("static_gz_escape");
+    let outside = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("static_gz_escape.gz");
+    std::fs::write(&outside, "outside").unwrap();
+    std::fs::write(dir.join("linked.js"), "linked").unwrap();
+    std::os::unix::fs::symlink(&outside, dir.join("linked.js.gz")).unwrap();
+    let router = static_router(&dir, "");
+    let response = fetch(&router, "/assets/linked.js", &[("accept-encoding", "gzip")]);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), "linked");
+    assert!(response.headers().get("content-encoding").is_none());
+    // Nor can `..` reach a sibling.
+    let response = fetch(
+        &router,
+        "/assets/../static_gz_escape",
+        &[("accept-encoding", "gzip")],
+    );
+    assert_ne!(response.status(), StatusCode::OK);
+}
+
+#[test]
+fn directories_are_listed_when_enabled() {
+    let dir = static_dir("static_listing");
+    let router = static_router(&dir, "");
+    assert_eq!(
+        fetch(&router, "/assets/sub%20dir", &[]).status(),
+        StatusCode::NOT_FOUND
+    );
+
+    let router = static_router(&dir, r#""directory_listing": true"#);
+    let response = fetch(&router, "/assets/sub%20dir", &[]);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["content-type"],
+        "text/html; charset=utf-8"
+    );
+    let html = std::str::from_utf8(response.body()).unwrap();
+    // Names are escaped for HTML, links percent-encoded, and the hidden file
+    // left out.
+    assert_eq!(
+        html,
+        concat!(
+            "<!DOCTYPE html>\n",
+            "<html><head><meta charset=\"utf-8\"><title>Index of /assets/sub%20dir/</title></head>\n",
+            "<body><h1>Index of /assets/sub%20dir/</h1>\n<ul>\n",
+            "<li><a href=\"/assets/sub%20dir/%3Cb%3E%26%22x%27.txt\">&lt;b&gt;&amp;&quot;x&#39;.txt</a></li>\n",
+            "<li><a href=\"/assets/sub%20dir/nested/\">nested/</a></li>\n",
+            "<li><a href=\"/assets/sub%20dir/z.txt\">z.txt</a></li>\n",
+            "</ul></body></html>\n",
+        )
+    );
+    // Files are still served.
+    assert_eq!(fetch(&router, "/assets/sub%20dir/z.txt", &[]).body(), "z");
+}
@@ -0,0 +1,177 @@
+use generic_http_router::config::Endpoint;
+use generic_http_router::{RouteFilter, Router};
+use http::{Method, Request, StatusCode};
+use std::path::PathBuf;
+
+/// `GET /users/:id` and `GET /users/:name` conflict, but are only loaded
+/// together without a filter.
+const CONFIG: &str = r#"{"endpoints": [
+    {"method": "GET", "path": "/users", "description": "", "controller": "users::list",
+     "tags": ["public"]},
+    {"method": "DELETE", "path": "/users", "description": "", "controller": "users::purge",
+     "tags": ["admin"]},
+    {"method": "GET", "path": "/users/:id", "description": "", "controller": "users::get",
+     "tags": ["public"]},
+    {"method": "GET", "path": "/users/:name", "description": "", "controller": "users::by_name",
+     "tags": ["admin", "legacy"]},
+    {"method": "POST", "path": "/jobs/poll", "description": "", "controller": "jobs::poll",
+     "tags": ["worker", "deprecated"]},
+    {"method": "GET", "path": "/internal/metrics", "description": "", "controller": "metrics"}
+]}"#;
+
+fn config(name: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("filter_{name}.json"));
+    std::fs::write(&path, CONFIG).unwrap();
+    path
+}
+
+fn routes(router: &Router) -> Vec<(Method, &str)> {
+    router
+        .routes()
+        .map(|endpoint| (endpoint.method.clone(), endpoint.path.as_str()))
+        .collect()
+}
+
+#[test]
+fn the_include_filter_loads_only_tagged_endpoints() {
+    let router =
+        Router::new_filtered(config("include"), RouteFilter::new().include_tag("public")).unwrap();
+    assert_eq!(
+        routes(&router),
+        [(Method::GET, "/users"), (Method::GET, "/users/:id")]
+    );
+
+    // The `DELETE` route that isn't loaded isn't allowed either.
+    let response = router.route(Request::delete("/users").body(String::new()).unwrap());
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers()["allow"], "GET, HEAD");
+    let response = router.route(Request::post("/jobs/poll").body(String::new()).unwrap());
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Several included tags load the endpoints with any of them.
+    let router = Router::new_filtered(
+        config("include_several"),
+        RouteFilter::new()
+            .include_tag("admin")
+            .include_tag("worker"),
+    )
+    .unwrap();
+    assert_eq!(
+        routes(&router),
+        [
+            (Method::DELETE, "/users"),
+            (Method::GET, "/users/:name"),
+            (Method::POST, "/jobs/poll"),
+        ]
+    );
+}
+
+#[test]
+fn the_exclude_filter_leaves_tagged_endpoints_out() {
+    let router = Router::new_filtered(
+        config("exclude"),
+        RouteFilter::new()
+            .exclude_tag("admin")
+            .exclude_tag("deprecated"),
+    )
+    .unwrap();
+    assert_eq!(
+        routes(&router),
+        [
+            (Method::GET, "/users"),
+            (Method::GET, "/users/:id"),
+            (Method::GET, "/internal/metrics"),
+        ]
+    );
+
+    // Exclusion wins over inclusion.
+    let router = Router::new_filtered(
+        config("include_exclude"),
+        RouteFilter::new()
+            .include_tag("admin")
+            .exclude_tag("legacy"),
+    )
+    .unwrap();
+    assert_eq!(routes(&router), [(Method::DELETE, "/users")]);
+}
+
+#[test]
+fn the_predicate_filters_on_any_field() {
+    let filter = RouteFilter::new()
+        .exclude_tag("admin")
+        .with_predicate(|endpoint: &Endpoint| !endpoint.path.starts_with("/internal"));
+    assert!(!filter.matches(&Endpoint::new(Method::GET, "/internal/x", "x")));
+    assert!(filter.matches(&Endpoint::new(Method::GET, "/x", "x")));
+
+    let router = Router::new_filtered(config("predicate"), filter).unwrap();
+    assert_eq!(
+        routes(&router),
+        [
+            (Method::GET, "/users"),
+            (Method::GET, "/users/:id"),
+            (Method::POST, "/jobs/poll"),
+        ]
+    );
+    let router = Router::new_filtered(
+        config("predicate_only"),
+        RouteFilter::new().with_predicate(|endpoint| endpoint.method == Method::POST),
+    )
+    .unwrap();
+    assert_eq!(routes(&router), [(Method::POST, "/jobs/poll")]);
+}
+
+#[test]
+fn a_conflict_only_fails_when_both_endpoints_load() {
+    let error = Router::new(config("unfiltered")).err().unwrap();
+    assert_eq!(error.code(), "route.conflict");
+    assert!(
+        Router::new_filtered(config("public"), RouteFilter::new().include_tag("public")).is_ok()
+    );
+    assert!(Router::new_filtered(config("admin"), RouteFilter::new().include_tag("admin")).is_ok());
+    assert!(Router::new_filtered(config("everything"), RouteFilter::new()).is_err());
+}
+
+#[test]
+fn reloading_applies_the_filter_again() {
+    let path = config("reload");
+    let mut router = Router::new_filtered(&path, RouteFilter::new().include_tag("worker")).unwrap();
+    std::fs::write(
+        &path,
+        CONFIG.replace(r#""tags": ["public"]}"#, r#""tags": ["public", "worker"]}"#),
+    )
+    .unwrap();
+    router.reload(&path).unwrap();
+    assert_eq!(
+        routes(&router),
+        [
+            (Method::GET, "/users"),
+            (Method::GET, "/users/:id"),
+            (Method::POST, "/jobs/poll"),
+        ]
+    );
+}
+
+#[test]
+fn routes_expose_their_tags() {
+    let router =
+        Router::new_filtered(config("tags"), RouteFilter::new().exclude_tag("public")).unwrap();
+    let tags: Vec<_> = router
+        .routes()
+        .map(|endpoint| (endpoint.path.as_str(), endpoint.tags.clone()))
+        .collect();
+    assert_eq!(
+        tags,
+        [
+            ("/users", vec!["admin".to_string()]),
+            (
+                "/users/:name",
+                vec!["admin".to_string(), "legacy".to_string()]
+            ),
+            (
+                "/jobs/poll",
+                vec!["worker".to_string(), "deprecated".to_string()]
+            ),
+            ("/internal/metrics", vec![]),
+        ]
+    );
+}
@@ -0,0 +1,94 @@
+//! Routes the inputs the `route` fuzz target found panics with through its
+//! configuration, `fuzz/routes.json`, which also keeps it valid.
+
+#![cfg(all(feature = "schema", feature = "cache"))]
+
+use generic_http_router::signature::SecretProvider;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Request, StatusCode};
+
+struct Echo;
+
+impl HttpHandler for Echo {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok("echo")
+    }
+}
+
+struct Flaky;
+
+impl HttpHandler for Flaky {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        match req.body.first() {
+            Some(b'!') => HttpResponse::internal_error("failed"),
+            _ => HttpResponse::ok("ok"),
+        }
+    }
+}
+
+struct Secret;
+
+impl SecretProvider for Secret {
+    fn secret(&self, _name: &str) -> Option<Vec<u8>> {
+        Some(b"fuzz".to_vec())
+    }
+}
+
+fn router() -> Router {
+    let mut router = Router::from_json_str(include_str!("../fuzz/routes.json")).unwrap();
+    router.register_handler("echo", Echo);
+    router.register_handler("flaky", Flaky);
+    router.set_secret_provider(Secret);
+    router.set_cache_max_bytes(64 * 1024);
+    router
+}
+
+fn send(router: &Router, req: http::request::Builder, body: &str) -> StatusCode {
+    router.route(req.body(body.to_string()).unwrap()).status()
+}
+
+#[test]
+fn stripe_timestamp_at_the_end_of_time() {
+    let req =
+        Request::post("/hooks/stripe").header("stripe-signature", "t=18446744073709551615,v1=00");
+    assert_eq!(send(&router(), req, "{}"), StatusCode::UNAUTHORIZED);
+}
+
+#[test]
+fn cache_ttl_at_the_end_of_time() {
+    let router = router();
+    assert_eq!(
+        send(&router, Request::get("/cached-forever"), ""),
+        StatusCode::OK
+    );
+    assert_eq!(
+        send(&router, Request::get("/cached-forever"), ""),
+        StatusCode::OK
+    );
+}
+
+#[test]
+fn distinct_cached_targets_stay_within_max_bytes() {
+    let router = router();
+    for nonce in 0..2000 {
+        let req = Request::get(format!("/cached/{nonce}?nonce={nonce}"));
+        assert_eq!(send(&router, req, ""), StatusCode::OK);
+    }
+}
+
+#[test]
+fn tripped_circuit_breaker() {
+    let router = router();
+    assert_eq!(
+        send(&router, Request::post("/flaky"), "!"),
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+    assert_eq!(
+        send(&router, Request::post("/flaky"), "!"),
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+    assert_eq!(
+        send(&router, Request::post("/flaky"), ""),
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+}
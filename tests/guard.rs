@@ -0,0 +1,192 @@
+use generic_http_router::error::RouterError;
+use generic_http_router::guard::{Guard, RequireContentType, RequireHeader};
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::header::HeaderName;
+use http::{Method, Request, Response, StatusCode};
+use std::sync::{Arc, Mutex};
+
+/// Records that it ran, in the shared log, and rejects requests with a
+/// `x-reject` header naming it with a 403 Forbidden.
+struct Logged {
+    name: &'static str,
+    log: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Guard for Logged {
+    fn check(&self, req: &HttpRequest) -> Result<(), HttpResponse> {
+        self.log.lock().unwrap().push(self.name);
+        let rejected = req
+            .headers
+            .get_all("x-reject")
+            .iter()
+            .any(|value| value == self.name);
+        match rejected {
+            true => Err(HttpResponse::text(
+                StatusCode::FORBIDDEN,
+                format!("rejected by {}", self.name),
+            )),
+            false => Ok(()),
+        }
+    }
+}
+
+/// Answers `created`, logging that it ran.
+struct Create(Arc<Mutex<Vec<&'static str>>>);
+
+impl HttpHandler for Create {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        self.0.lock().unwrap().push("handler");
+        HttpResponse::created("created")
+    }
+}
+
+fn router() -> (Router, Arc<Mutex<Vec<&'static str>>>) {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "POST", "path": "/users", "description": "", "controller": "create",
+             "guards": ["require_json", "require_tenant"]},
+            {"method": "POST", "path": "/ordered", "description": "", "controller": "create",
+             "guards": ["first", "second", "third"]},
+            {"method": "POST", "path": "/open", "description": "", "controller": "create"}
+        ]}"#,
+    )
+    .unwrap();
+    let log = Arc::new(Mutex::new(Vec::new()));
+    router.register_handler("create", Create(Arc::clone(&log)));
+    router.register_guard(
+        "require_json",
+        Box::new(RequireContentType::new("application/json")),
+    );
+    router.register_guard(
+        "require_tenant",
+        Box::new(RequireHeader::new(HeaderName::from_static("x-tenant"))),
+    );
+    for name in ["first", "second", "third"] {
+        router.register_guard(
+            name,
+            Box::new(Logged {
+                name,
+                log: Arc::clone(&log),
+            }),
+        );
+    }
+    (router, log)
+}
+
+fn post(router: &Router, path: &str, headers: &[(&str, &str)]) -> Response<bytes::Bytes> {
+    let mut builder = Request::builder().method(Method::POST).uri(path);
+    for (name, value) in headers {
+        builder = builder.header(*name, *value);
+    }
+    router.route(builder.body("{}".to_string()).unwrap())
+}
+
+fn text(response: &Response<bytes::Bytes>) -> &str {
+    std::str::from_utf8(response.body()).unwrap()
+}
+
+#[test]
+fn requests_passing_every_guard_reach_the_handler() {
+    let (router, log) = router();
+    router.validate().unwrap();
+    let response = post(
+        &router,
+        "/users",
+        &[
+            ("content-type", "Application/JSON; charset=utf-8"),
+            ("x-tenant", "acme"),
+        ],
+    );
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(*log.lock().unwrap(), ["handler"]);
+    // Routes without guards aren't checked.
+    assert_eq!(post(&router, "/open", &[]).status(), StatusCode::CREATED);
+}
+
+#[test]
+fn a_failing_guard_answers_instead_of_the_handler() {
+    let (router, log) = router();
+    let response = post(&router, "/users", &[("x-tenant", "acme")]);
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(
+        text(&response),
+        "Expected a request body of type application/json"
+    );
+    let response = post(&router, "/users", &[("content-type", "application/json")]);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(text(&response), "Missing required header x-tenant");
+    let response = post(
+        &router,
+        "/users",
+        &[("content-type", "application/jsonl"), ("x-tenant", "acme")],
+    );
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert!(log.lock().unwrap().is_empty());
+}
+
+#[test]
+fn guards_run_in_order_and_the_first_failure_wins() {
+    let (router, log) = router();
+    let response = post(&router, "/ordered", &[]);
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(
+        std::mem::take(&mut *log.lock().unwrap()),
+        ["first", "second", "third", "handler"]
+    );
+
+    // The guards after the failing one don't run.
+    let response = post(
+        &router,
+        "/ordered",
+        &[("x-reject", "third"), ("x-reject", "second")],
+    );
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(text(&response), "rejected by second");
+    assert_eq!(*log.lock().unwrap(), ["first", "second"]);
+}
+
+#[test]
+fn unknown_guards_fail_validation() {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/internal", "description": "", "controller": "create",
+             "guards": ["require_json", "internal_only"]}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_guard(
+        "require_json",
+        Box::new(RequireContentType::new("application/json")),
+    );
+    match router.validate() {
+        Err(RouterError::Endpoint {
+            method,
+            path,
+            reason,
+        }) => {
+            assert_eq!(method, Method::GET);
+            assert_eq!(path, "/internal");
+            assert_eq!(reason, "unknown guard 'internal_only'");
+        }
+        other => panic!("{other:?}"),
+    }
+
+    // Requests get a 500 until it's registered.
+    let send = |router: &Router| {
+        router.route(
+            Request::get("/internal")
+                .header("content-type", "application/json")
+                .body(String::new())
+                .unwrap(),
+        )
+    };
+    let response = send(&router);
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(!text(&response).contains("internal_only"));
+    router.register_guard(
+        "internal_only",
+        Box::new(RequireHeader::new(HeaderName::from_static("x-internal"))),
+    );
+    router.validate().unwrap();
+    assert_eq!(send(&router).status(), StatusCode::BAD_REQUEST);
+}
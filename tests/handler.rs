@@ -0,0 +1,128 @@
+use generic_http_router::{Handler, HandlerFuture, HttpHandler, HttpRequest, HttpResponse};
+
+struct Hello;
+
+impl HttpHandler for Hello {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok(format!("Hello from {}", req.uri.path()))
+    }
+}
+
+/// Answers asynchronously, implementing `Handler` directly.
+struct Later;
+
+impl Handler for Later {
+    fn call(&self, req: HttpRequest) -> HandlerFuture<'_> {
+        HandlerFuture::Pending(Box::pin(async move {
+            HttpResponse::ok(format!("Hello later from {}", req.uri.path()))
+        }))
+    }
+}
+
+fn request(path: &str) -> HttpRequest {
+    HttpRequest::builder().uri(path).build().unwrap()
+}
+
+#[tokio::test]
+async fn boxed_handlers_are_called_through_the_trait_object() {
+    let handlers: Vec<Box<dyn Handler + Send + Sync>> = vec![Box::new(Hello), Box::new(Later)];
+    let HandlerFuture::Ready(response) = handlers[0].call(request("/now")) else {
+        panic!("an HttpHandler answers right away");
+    };
+    assert_eq!(response.body_as_slice(), Some(&b"Hello from /now"[..]));
+    let HandlerFuture::Pending(future) = handlers[1].call(request("/later")) else {
+        panic!("an async handler answers with a future");
+    };
+    assert_eq!(
+        future.await.body_as_slice(),
+        Some(&b"Hello later from /later"[..])
+    );
+}
+
+mod registry {
+    use generic_http_router::maintenance::MaintenanceMode;
+    use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+    use http::{Request, StatusCode};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts its calls, answering with the running count.
+    #[derive(Default)]
+    struct Counting(AtomicUsize);
+
+    impl HttpHandler for Counting {
+        fn handle(&self, _req: HttpRequest) -> HttpResponse {
+            HttpResponse::ok((self.0.fetch_add(1, Ordering::SeqCst) + 1).to_string())
+        }
+    }
+
+    fn router() -> Router {
+        Router::from_json_str(
+            r#"{"endpoints": [
+                {"method": "GET", "path": "/legacy/users/:id", "description": "",
+                 "controller": "legacy::get_user"},
+                {"method": "GET", "path": "/users/:id", "description": "",
+                 "controller": "users::get_by_id"}
+            ]}"#,
+        )
+        .unwrap()
+    }
+
+    fn get(router: &Router, path: &str) -> String {
+        let response = router.route(Request::get(path).body(String::new()).unwrap());
+        assert_eq!(response.status(), StatusCode::OK, "{path}");
+        String::from_utf8(response.body().to_vec()).unwrap()
+    }
+
+    #[test]
+    fn register_shared_dispatches_both_names_to_one_instance() {
+        let mut router = router();
+        let handler = Arc::new(Counting::default());
+        router.register_shared(&["legacy::get_user", "users::get_by_id"], handler.clone());
+        assert_eq!(get(&router, "/legacy/users/1"), "1");
+        assert_eq!(get(&router, "/users/1"), "2");
+        assert_eq!(get(&router, "/legacy/users/2"), "3");
+        assert_eq!(handler.0.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn register_accepts_boxes_and_arcs() {
+        let mut router = router();
+        let shared: Arc<dyn HttpHandler + Send + Sync> = Arc::new(Counting::default());
+        router.register("legacy::get_user", Arc::clone(&shared));
+        router.register("users::get_by_id", shared);
+        // The `Arc` is shared, not copied.
+        assert_eq!(get(&router, "/legacy/users/1"), "1");
+        assert_eq!(get(&router, "/users/1"), "2");
+
+        // Each `Box` is its own instance.
+        router.register("legacy::get_user", Box::new(Counting::default()));
+        router.register(
+            "users::get_by_id",
+            Box::new(Counting::default()) as Box<dyn HttpHandler + Send + Sync>,
+        );
+        assert_eq!(get(&router, "/legacy/users/1"), "1");
+        assert_eq!(get(&router, "/users/1"), "1");
+    }
+
+    #[test]
+    fn a_cloned_router_shares_its_handlers() {
+        let mut router = router();
+        let handler = Arc::new(Counting::default());
+        router.register_shared(&["legacy::get_user", "users::get_by_id"], handler.clone());
+        let mut snapshot = router.clone();
+        assert_eq!(get(&router, "/users/1"), "1");
+        assert_eq!(get(&snapshot, "/users/1"), "2");
+
+        // Registering a handler or entering maintenance on the clone leaves the
+        // original alone.
+        snapshot.register_handler("users::get_by_id", Counting::default());
+        assert_eq!(get(&snapshot, "/users/1"), "1");
+        assert_eq!(get(&router, "/users/1"), "3");
+        snapshot.set_maintenance(MaintenanceMode::All);
+        assert_eq!(router.maintenance(), MaintenanceMode::Off);
+        assert_eq!(get(&router, "/legacy/users/1"), "4");
+        assert_eq!(handler.0.load(Ordering::SeqCst), 4);
+    }
+}
@@ -0,0 +1,152 @@
+use bytes::Bytes;
+use generic_http_router::maintenance::MaintenanceMode;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::header::CONTENT_LENGTH;
+use http::{Method, Request, Response, StatusCode};
+
+/// Answers with its body, whatever the method, and an `X-Method` header
+/// telling the method it saw.
+struct Named(&'static str);
+
+impl HttpHandler for Named {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let mut response = HttpResponse::ok(self.0);
+        response.add_header("x-method", req.method.as_str());
+        response
+    }
+}
+
+/// Answers with a body and a `Content-Length` of its own.
+struct WithLength;
+
+impl HttpHandler for WithLength {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        let mut response = HttpResponse::ok("0123456789");
+        response.add_header(CONTENT_LENGTH, "10");
+        response
+    }
+}
+
+/// Streams two chunks.
+struct Streamed;
+
+impl HttpHandler for Streamed {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::streaming(
+            StatusCode::OK,
+            [
+                Ok(Bytes::from_static(b"one")),
+                Ok(Bytes::from_static(b"two")),
+            ],
+        )
+    }
+}
+
+/// A router with `GET /auto`, `GET` and an explicit `HEAD` on `/explicit`, a
+/// `GET /length` setting its `Content-Length` and a streamed `GET /stream`.
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/auto", "description": "", "controller": "auto"},
+            {"method": "GET", "path": "/explicit", "description": "", "controller": "get"},
+            {"method": "HEAD", "path": "/explicit", "description": "", "controller": "head"},
+            {"method": "GET", "path": "/length", "description": "", "controller": "length"},
+            {"method": "GET", "path": "/stream", "description": "", "controller": "stream"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("auto", Named("auto body"));
+    router.register_handler("get", Named("get body"));
+    router.register_handler("head", Named("the head handler's body"));
+    router.register_handler("length", WithLength);
+    router.register_handler("stream", Streamed);
+    router
+}
+
+fn head(router: &Router, path: &str) -> Response<Bytes> {
+    router.route(Request::head(path).body(String::new()).unwrap())
+}
+
+/// Checks `response` has no body, and the `Content-Length` of `body`.
+fn assert_stripped(response: &Response<Bytes>, body: &str) {
+    assert!(response.body().is_empty(), "{:?}", response.body());
+    assert_eq!(response.headers()[CONTENT_LENGTH], body.len().to_string());
+}
+
+#[test]
+fn an_explicit_head_route_has_its_body_stripped() {
+    let router = router();
+    let response = head(&router, "/explicit");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-method"], "HEAD");
+    assert_stripped(&response, "the head handler's body");
+}
+
+#[test]
+fn auto_head_strips_the_get_body() {
+    let router = router();
+    let response = head(&router, "/auto");
+    assert_eq!(response.status(), StatusCode::OK);
+    // The `GET` handler sees the `HEAD` method.
+    assert_eq!(response.headers()["x-method"], "HEAD");
+    assert_stripped(&response, "auto body");
+
+    // The `GET` response is unchanged.
+    let response = router.route(Request::get("/auto").body(String::new()).unwrap());
+    assert_eq!(response.body(), "auto body");
+
+    // A handler's own `Content-Length` is kept.
+    assert_stripped(&head(&router, "/length"), "0123456789");
+
+    // Without auto-HEAD, there's no route.
+    let mut router = self::router();
+    router.set_auto_head(false);
+    let response = head(&router, "/auto");
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert!(response.body().is_empty());
+}
+
+#[test]
+fn error_responses_have_their_body_stripped() {
+    let mut router = router();
+    router.use_problem_responses(true);
+    // The router's own 404 and 405, with a problem body.
+    let not_found = router.route(Request::get("/nowhere").body(String::new()).unwrap());
+    let response = head(&router, "/nowhere");
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_stripped(&response, std::str::from_utf8(not_found.body()).unwrap());
+
+    router.set_auto_head(false);
+    let not_allowed = router.route(
+        Request::builder()
+            .method(Method::DELETE)
+            .uri("/auto")
+            .body(String::new())
+            .unwrap(),
+    );
+    let response = head(&router, "/auto");
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_stripped(&response, std::str::from_utf8(not_allowed.body()).unwrap());
+
+    // Maintenance.
+    router.set_maintenance_response(None, "Back soon");
+    router.set_maintenance(MaintenanceMode::All);
+    let response = head(&router, "/explicit");
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_stripped(&response, "Back soon");
+}
+
+#[test]
+fn streamed_bodies_are_dropped_for_head() {
+    let router = router();
+    let response = router.route_streaming(Request::head("/stream").body(String::new()).unwrap());
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body().as_bytes().map(|body| body.len()), Some(0));
+    // The length of a stream isn't known without reading it, which the
+    // router doesn't, even when it buffers bodies.
+    assert!(response.headers().get(CONTENT_LENGTH).is_none());
+
+    let response = head(&router, "/stream");
+    assert!(response.body().is_empty());
+    assert!(response.headers().get(CONTENT_LENGTH).is_none());
+}
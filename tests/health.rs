@@ -0,0 +1,199 @@
+use generic_http_router::error::LoadWarning;
+use generic_http_router::health::HealthConfig;
+use generic_http_router::maintenance::MaintenanceMode;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Method, Request, Response, StatusCode};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+struct Named(&'static str);
+
+impl HttpHandler for Named {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok(self.0)
+    }
+}
+
+fn router_with(endpoints: &str) -> Router {
+    let mut router = Router::from_json_str(&format!(r#"{{"endpoints": [{endpoints}]}}"#)).unwrap();
+    router.register_handler("named", Named("configured"));
+    router
+}
+
+fn send(router: &Router, method: Method, path: &str) -> Response<bytes::Bytes> {
+    router.route(
+        Request::builder()
+            .method(method)
+            .uri(path)
+            .body(String::new())
+            .unwrap(),
+    )
+}
+
+fn json_body(response: &Response<bytes::Bytes>) -> Value {
+    assert_eq!(response.headers()["content-type"], "application/json");
+    serde_json::from_slice(response.body()).unwrap()
+}
+
+#[test]
+fn liveness_is_always_ok() {
+    let mut router = router_with("");
+    assert!(
+        router
+            .enable_health_endpoints(HealthConfig::default())
+            .is_empty()
+    );
+    router.add_readiness_check("db", Box::new(|| Err("down".to_string())));
+    let response = send(&router, Method::GET, "/healthz");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), "ok");
+    let response = send(&router, Method::HEAD, "/healthz");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.body().is_empty());
+    // Only `GET` and `HEAD` are answered.
+    assert_eq!(
+        send(&router, Method::POST, "/healthz").status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[test]
+fn readiness_with_every_check_passing() {
+    let mut router = router_with("");
+    router.enable_health_endpoints(HealthConfig::default());
+    router.add_readiness_check("db", Box::new(|| Ok(())));
+    router.add_readiness_check("cache", Box::new(|| Ok(())));
+    let response = send(&router, Method::GET, "/readyz");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        json_body(&response),
+        json!({"status": "ok", "checks": [
+            {"name": "db", "status": "ok"},
+            {"name": "cache", "status": "ok"}
+        ]})
+    );
+
+    // Without checks, the service is ready.
+    let mut router = router_with("");
+    router.enable_health_endpoints(HealthConfig::default());
+    let response = send(&router, Method::GET, "/readyz");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_body(&response), json!({"status": "ok", "checks": []}));
+}
+
+#[test]
+fn readiness_with_a_failing_check() {
+    let mut router = router_with("");
+    router.enable_health_endpoints(HealthConfig::default());
+    let db_up = Arc::new(AtomicBool::new(false));
+    let db = Arc::clone(&db_up);
+    router.add_readiness_check(
+        "db",
+        Box::new(move || {
+            if db.load(Ordering::SeqCst) {
+                Ok(())
+            } else {
+                Err("connection refused".to_string())
+            }
+        }),
+    );
+    router.add_readiness_check("cache", Box::new(|| Ok(())));
+
+    let response = send(&router, Method::GET, "/readyz");
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(
+        json_body(&response),
+        json!({"status": "unavailable", "checks": [
+            {"name": "db", "status": "error", "error": "connection refused"},
+            {"name": "cache", "status": "ok"}
+        ]})
+    );
+
+    // The checks run on every request.
+    db_up.store(true, Ordering::SeqCst);
+    assert_eq!(
+        send(&router, Method::GET, "/readyz").status(),
+        StatusCode::OK
+    );
+}
+
+#[test]
+fn readiness_fails_while_shutting_down() {
+    let mut router = router_with("");
+    router.enable_health_endpoints(HealthConfig::default());
+    router.add_readiness_check("db", Box::new(|| Ok(())));
+    router.shutdown_controller().begin_shutdown();
+    let response = send(&router, Method::GET, "/readyz");
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(json_body(&response)["status"], "shutting_down");
+    // Liveness still answers.
+    assert_eq!(
+        send(&router, Method::GET, "/healthz").status(),
+        StatusCode::OK
+    );
+}
+
+#[test]
+fn health_endpoints_bypass_maintenance() {
+    let mut router = router_with(
+        r#"{"method": "GET", "path": "/users", "description": "", "controller": "named"}"#,
+    );
+    router.enable_health_endpoints(HealthConfig::default());
+    router.set_maintenance(MaintenanceMode::All);
+    assert_eq!(
+        send(&router, Method::GET, "/users").status(),
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+    assert_eq!(
+        send(&router, Method::GET, "/healthz").status(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        send(&router, Method::GET, "/readyz").status(),
+        StatusCode::OK
+    );
+}
+
+#[test]
+fn custom_paths() {
+    let mut router = router_with("");
+    router.enable_health_endpoints(HealthConfig {
+        liveness_path: "/live".to_string(),
+        readiness_path: "/ready".to_string(),
+    });
+    assert_eq!(send(&router, Method::GET, "/live").body(), "ok");
+    assert_eq!(
+        send(&router, Method::GET, "/ready").status(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        send(&router, Method::GET, "/healthz").status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[test]
+fn configured_routes_win_over_the_built_ins_with_a_warning() {
+    let mut router = router_with(
+        r#"{"method": "GET", "path": "/healthz", "description": "", "controller": "named"},
+           {"method": "POST", "path": "/:any", "description": "", "controller": "named"}"#,
+    );
+    let warnings = router.enable_health_endpoints(HealthConfig::default());
+    // `/readyz` is matched by `POST /:any`: even a route for another method
+    // shadows a built-in endpoint.
+    assert_eq!(
+        warnings,
+        [
+            LoadWarning::HealthEndpointShadowed {
+                path: "/healthz".to_string()
+            },
+            LoadWarning::HealthEndpointShadowed {
+                path: "/readyz".to_string()
+            },
+        ]
+    );
+    assert_eq!(send(&router, Method::GET, "/healthz").body(), "configured");
+    let response = send(&router, Method::GET, "/readyz");
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
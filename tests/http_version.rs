@@ -0,0 +1,28 @@
+//! The router's public types are those of `http` 0.2, as the README says
+//! under "HTTP Crate Version".
+
+use bytes::Bytes;
+use generic_http_router::{HttpRequest, HttpResponse, Router};
+
+/// Not `Clone`: `http` 1 only stores `Clone` values in `Extensions`.
+struct Tag;
+
+#[test]
+fn requests_and_responses_are_http_0_2_types() {
+    let mut req: http::Request<Vec<u8>> = http::Request::get("/ping").body(Vec::new()).unwrap();
+    req.extensions_mut().insert(Tag);
+    let req = HttpRequest::from(req);
+    assert_eq!(req.method, http::Method::GET);
+    assert!(req.extensions.get::<Tag>().is_some());
+
+    let response: http::Response<Bytes> = HttpResponse::ok("pong").into();
+    assert_eq!(response.status(), http::StatusCode::OK);
+}
+
+#[test]
+fn the_router_takes_and_returns_http_0_2_types() {
+    let router = Router::from_json_str(r#"{"endpoints": []}"#).unwrap();
+    let req: http::Request<String> = http::Request::get("/ping").body(String::new()).unwrap();
+    let response: http::Response<Bytes> = router.route(req);
+    assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+}
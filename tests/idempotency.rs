@@ -0,0 +1,173 @@
+#![cfg(feature = "idempotency")]
+
+#[cfg(feature = "session")]
+use generic_http_router::SessionMiddleware;
+use generic_http_router::idempotency::MemoryStore;
+#[cfg(feature = "session")]
+use generic_http_router::session::MemoryStore as SessionMemoryStore;
+use generic_http_router::testing::MockClock;
+use generic_http_router::{
+    ApiKeyProvider, HttpHandler, HttpRequest, HttpResponse, IdempotencyMiddleware, Router,
+};
+use http::header::HeaderName;
+#[cfg(feature = "session")]
+use http::header::SET_COOKIE;
+use http::{Request, Response, StatusCode};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Places an order, remembering it in the caller's session.
+struct PlaceOrder(Arc<AtomicUsize>);
+
+impl HttpHandler for PlaceOrder {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let number = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+        #[cfg(not(feature = "session"))]
+        let _ = req;
+        #[cfg(feature = "session")]
+        if let Some(session) = req.session() {
+            session.insert("order", number);
+        }
+        HttpResponse::ok(format!("order {number}"))
+    }
+}
+
+fn router(calls: &Arc<AtomicUsize>) -> Router {
+    router_with(
+        calls,
+        IdempotencyMiddleware::new(Box::new(MemoryStore::new())),
+    )
+}
+
+fn router_with(calls: &Arc<AtomicUsize>, idempotency: IdempotencyMiddleware) -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "POST", "path": "/orders", "description": "",
+             "controller": "orders::place", "idempotent": true},
+            {"method": "POST", "path": "/keyed/orders", "description": "",
+             "controller": "orders::place", "idempotent": true, "auth": "api_key"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("orders::place", PlaceOrder(Arc::clone(calls)));
+    router.use_idempotency(idempotency);
+    #[cfg(feature = "session")]
+    router.use_sessions(SessionMiddleware::new(
+        [7; 32],
+        Box::new(SessionMemoryStore::new()),
+    ));
+    router.set_api_keys(
+        ApiKeyProvider::new(HeaderName::from_static("x-api-key"))
+            .with_key("alice", "alice-key")
+            .with_key("bob", "bob-key"),
+    );
+    router
+}
+
+fn post(router: &Router, path: &str, headers: &[(&str, &str)]) -> Response<bytes::Bytes> {
+    post_body(router, path, headers, "{}")
+}
+
+fn post_body(
+    router: &Router,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: &str,
+) -> Response<bytes::Bytes> {
+    let mut req = Request::post(path).header("Idempotency-Key", "order-1");
+    for (name, value) in headers {
+        req = req.header(*name, *value);
+    }
+    router.route(req.body(body.to_string()).unwrap())
+}
+
+#[cfg(feature = "session")]
+#[test]
+fn a_retry_gets_the_first_response_without_its_session_cookie() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let router = router(&calls);
+    let first = post(&router, "/orders", &[]);
+    assert_eq!(first.status(), StatusCode::OK);
+    assert!(first.headers().contains_key(SET_COOKIE));
+
+    let retry = post(&router, "/orders", &[]);
+    assert_eq!(retry.status(), StatusCode::OK);
+    assert_eq!(retry.body().as_ref(), b"order 1");
+    assert_eq!(retry.headers()["idempotent-replayed"], "true");
+    assert!(!retry.headers().contains_key(SET_COOKIE));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn keys_are_scoped_to_the_caller() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let router = router(&calls);
+    let alice = post(&router, "/keyed/orders", &[("x-api-key", "alice-key")]);
+    assert_eq!(alice.body().as_ref(), b"order 1");
+    let bob = post(&router, "/keyed/orders", &[("x-api-key", "bob-key")]);
+    assert_eq!(bob.body().as_ref(), b"order 2");
+    assert!(!bob.headers().contains_key("idempotent-replayed"));
+
+    let retry = post(&router, "/keyed/orders", &[("x-api-key", "alice-key")]);
+    assert_eq!(retry.body().as_ref(), b"order 1");
+    assert_eq!(retry.headers()["idempotent-replayed"], "true");
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn keys_are_scoped_to_the_authorization_header() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let router = router(&calls);
+    let first = post(&router, "/orders", &[("authorization", "Bearer first")]);
+    assert_eq!(first.body().as_ref(), b"order 1");
+    let second = post(&router, "/orders", &[("authorization", "Bearer second")]);
+    assert_eq!(second.body().as_ref(), b"order 2");
+}
+
+#[test]
+fn a_retry_with_another_body_is_rejected() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let router = router(&calls);
+    let first = post_body(&router, "/orders", &[], r#"{"amount": 10}"#);
+    assert_eq!(first.status(), StatusCode::OK);
+
+    let other = post_body(&router, "/orders", &[], r#"{"amount": 1000}"#);
+    assert_eq!(other.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    // The first response is still replayed for the first body.
+    let retry = post_body(&router, "/orders", &[], r#"{"amount": 10}"#);
+    assert_eq!(retry.body().as_ref(), b"order 1");
+}
+
+#[test]
+fn a_record_expires_after_its_ttl() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let idempotency =
+        IdempotencyMiddleware::new(Box::new(MemoryStore::new())).with_ttl(Duration::from_secs(60));
+    let mut router = router_with(&calls, idempotency);
+    let clock = MockClock::new();
+    router.set_clock(clock.clone());
+
+    assert_eq!(post(&router, "/orders", &[]).body().as_ref(), b"order 1");
+    clock.advance(Duration::from_secs(59));
+    let retry = post(&router, "/orders", &[]);
+    assert_eq!(retry.body().as_ref(), b"order 1");
+    assert_eq!(retry.headers()["idempotent-replayed"], "true");
+
+    clock.advance(Duration::from_secs(1));
+    let expired = post(&router, "/orders", &[]);
+    assert_eq!(expired.body().as_ref(), b"order 2");
+    assert!(!expired.headers().contains_key("idempotent-replayed"));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn a_ttl_too_large_to_add_keeps_records() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let idempotency =
+        IdempotencyMiddleware::new(Box::new(MemoryStore::new())).with_ttl(Duration::MAX);
+    let router = router_with(&calls, idempotency);
+    assert_eq!(post(&router, "/orders", &[]).body().as_ref(), b"order 1");
+    assert_eq!(post(&router, "/orders", &[]).body().as_ref(), b"order 1");
+}
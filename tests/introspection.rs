@@ -0,0 +1,200 @@
+use generic_http_router::error::LoadWarning;
+use generic_http_router::guard::Guard;
+use generic_http_router::introspection::INTROSPECTION_VERSION;
+use generic_http_router::maintenance::MaintenanceMode;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, IntrospectionConfig, Router};
+use http::{Method, Request, Response, StatusCode};
+use serde_json::{Value, json};
+
+struct Listed;
+
+impl HttpHandler for Listed {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok("ok")
+    }
+}
+
+/// Lets through requests with `X-Admin-Token: open-sesame`.
+struct Admin;
+
+impl Guard for Admin {
+    fn check(&self, req: &HttpRequest) -> Result<(), HttpResponse> {
+        match req.headers.get("x-admin-token") {
+            Some(token) if token == "open-sesame" => Ok(()),
+            _ => Err(HttpResponse::text(StatusCode::FORBIDDEN, "admins only")),
+        }
+    }
+}
+
+const ENDPOINTS: &str = r#"
+    {"method": "POST", "path": "/users", "description": "Creates a user", "controller": "ok",
+     "auth": "api_key"},
+    {"method": "GET", "path": "/users/:id", "description": "Gets a user", "controller": "ok",
+     "tags": ["users"]},
+    {"method": "GET", "path": "/v1/users/:id", "description": "Gets a user, the old way",
+     "controller": "ok", "tags": ["deprecated", "users"]},
+    {"method": "GET", "path": "/debug/cache", "description": "Dumps the cache", "controller": "ok",
+     "tags": ["internal"]}"#;
+
+fn router_with(endpoints: &str) -> Router {
+    let mut router = Router::from_json_str(&format!(
+        r#"{{"api_keys": {{"keys": {{}}}}, "endpoints": [{endpoints}]}}"#
+    ))
+    .unwrap();
+    router.register_handler("ok", Listed);
+    router
+}
+
+fn send(
+    router: &Router,
+    method: Method,
+    path: &str,
+    headers: &[(&str, &str)],
+) -> Response<bytes::Bytes> {
+    let mut req = Request::builder().method(method).uri(path);
+    for &(name, value) in headers {
+        req = req.header(name, value);
+    }
+    router.route(req.body(String::new()).unwrap())
+}
+
+fn listing(response: &Response<bytes::Bytes>) -> Value {
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/json");
+    serde_json::from_slice(response.body()).unwrap()
+}
+
+#[test]
+fn the_listing_is_off_by_default() {
+    let router = router_with(ENDPOINTS);
+    assert_eq!(
+        send(&router, Method::GET, "/_routes", &[]).status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[test]
+fn the_listing_is_versioned_and_sorted_without_internal_routes() {
+    let mut router = router_with(ENDPOINTS);
+    assert!(
+        router
+            .enable_introspection(IntrospectionConfig::default())
+            .is_empty()
+    );
+    assert_eq!(INTROSPECTION_VERSION, 1);
+    let listing = listing(&send(&router, Method::GET, "/_routes", &[]));
+    assert_eq!(
+        listing,
+        json!({"version": 1, "routes": [
+            {"method": "POST", "path": "/users", "description": "Creates a user",
+             "deprecated": false, "auth": "api_key", "auth_scopes": null, "tags": []},
+            {"method": "GET", "path": "/users/:id", "description": "Gets a user",
+             "deprecated": false, "auth": null, "auth_scopes": null, "tags": ["users"]},
+            {"method": "GET", "path": "/v1/users/:id", "description": "Gets a user, the old way",
+             "deprecated": true, "auth": null, "auth_scopes": null,
+             "tags": ["deprecated", "users"]}
+        ]})
+    );
+
+    // `HEAD` is answered too, without the body; other methods aren't.
+    let response = send(&router, Method::HEAD, "/_routes", &[]);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.body().is_empty());
+    assert_eq!(
+        send(&router, Method::POST, "/_routes", &[]).status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[test]
+fn the_path_and_excluded_tags_are_configurable() {
+    let mut router = router_with(ENDPOINTS);
+    router.enable_introspection(IntrospectionConfig {
+        path: "/meta/routes".to_string(),
+        excluded_tags: vec!["users".to_string()],
+        guard: None,
+    });
+    assert_eq!(
+        send(&router, Method::GET, "/_routes", &[]).status(),
+        StatusCode::NOT_FOUND
+    );
+    let listing = listing(&send(&router, Method::GET, "/meta/routes", &[]));
+    let paths: Vec<&str> = listing["routes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|route| route["path"].as_str().unwrap())
+        .collect();
+    // `internal` routes are listed once it isn't excluded.
+    assert_eq!(paths, ["/debug/cache", "/users"]);
+}
+
+#[test]
+fn a_guard_protects_the_listing() {
+    let mut router = router_with(ENDPOINTS);
+    router.register_guard("admin", Box::new(Admin));
+    router.enable_introspection(IntrospectionConfig {
+        guard: Some("admin".to_string()),
+        ..IntrospectionConfig::default()
+    });
+    let response = send(&router, Method::GET, "/_routes", &[]);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(response.body(), "admins only");
+    let response = send(
+        &router,
+        Method::GET,
+        "/_routes",
+        &[("x-admin-token", "wrong")],
+    );
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = send(
+        &router,
+        Method::GET,
+        "/_routes",
+        &[("x-admin-token", "open-sesame")],
+    );
+    assert_eq!(listing(&response)["routes"].as_array().unwrap().len(), 3);
+
+    // An unregistered guard never lets a request through.
+    let mut router = router_with(ENDPOINTS);
+    router.enable_introspection(IntrospectionConfig {
+        guard: Some("missing".to_string()),
+        ..IntrospectionConfig::default()
+    });
+    let response = send(
+        &router,
+        Method::GET,
+        "/_routes",
+        &[("x-admin-token", "open-sesame")],
+    );
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[test]
+fn the_listing_bypasses_maintenance() {
+    let mut router = router_with(ENDPOINTS);
+    router.enable_introspection(IntrospectionConfig::default());
+    router.set_maintenance(MaintenanceMode::All);
+    assert_eq!(
+        send(&router, Method::GET, "/users/1", &[]).status(),
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+    assert_eq!(
+        send(&router, Method::GET, "/_routes", &[]).status(),
+        StatusCode::OK
+    );
+}
+
+#[test]
+fn a_configured_route_shadows_the_listing_with_a_warning() {
+    let mut router = router_with(
+        r#"{"method": "GET", "path": "/_routes", "description": "", "controller": "ok"}"#,
+    );
+    assert_eq!(
+        router.enable_introspection(IntrospectionConfig::default()),
+        [LoadWarning::IntrospectionEndpointShadowed {
+            path: "/_routes".to_string()
+        }]
+    );
+    assert_eq!(send(&router, Method::GET, "/_routes", &[]).body(), "ok");
+}
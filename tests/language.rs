@@ -0,0 +1,110 @@
+use generic_http_router::HttpRequest;
+use generic_http_router::language::LanguageTag;
+
+fn request(accept_language: &[&str]) -> HttpRequest {
+    let mut builder = HttpRequest::builder();
+    for value in accept_language {
+        builder = builder.header("accept-language", *value);
+    }
+    builder.build().unwrap()
+}
+
+fn ranges(accept_language: &[&str]) -> Vec<(String, f32)> {
+    request(accept_language)
+        .accept_languages()
+        .into_iter()
+        .map(|LanguageTag { tag, quality }| (tag, quality))
+        .collect()
+}
+
+fn negotiate<'a>(accept_language: &str, supported: &[&'a str]) -> Option<&'a str> {
+    request(&[accept_language]).negotiate_language(supported)
+}
+
+fn range(tag: &str, quality: f32) -> (String, f32) {
+    (tag.to_string(), quality)
+}
+
+#[test]
+fn ranges_are_sorted_by_decreasing_quality() {
+    assert_eq!(
+        ranges(&["fr;q=0.5, en-GB, de;q=0.8, en;q=0.9, *;q=0.1"]),
+        [
+            range("en-GB", 1.0),
+            range("en", 0.9),
+            range("de", 0.8),
+            range("fr", 0.5),
+            range("*", 0.1),
+        ]
+    );
+    // Ties keep their order, across several headers too.
+    assert_eq!(
+        ranges(&["pt;q=0.5, es", "it;q=0.5, ca"]),
+        [
+            range("es", 1.0),
+            range("ca", 1.0),
+            range("pt", 0.5),
+            range("it", 0.5),
+        ]
+    );
+    assert_eq!(ranges(&[" en-gb ; Q=0.700 "]), [range("en-gb", 0.7)]);
+    assert!(ranges(&[]).is_empty());
+}
+
+#[test]
+fn malformed_ranges_are_skipped() {
+    assert_eq!(
+        ranges(&["en_US, 12, de;q=2, fr;q=abc, it;q=0.1234, nl;q, toolongtag, , es;q=0.3, pt"]),
+        [range("pt", 1.0), range("es", 0.3)]
+    );
+    // Other parameters are ignored.
+    assert_eq!(ranges(&["en;level=1;q=0.5"]), [range("en", 0.5)]);
+}
+
+#[test]
+fn the_best_exact_match_is_picked() {
+    let supported = ["en", "de", "fr"];
+    assert_eq!(negotiate("de;q=0.8, fr;q=0.9", &supported), Some("fr"));
+    assert_eq!(negotiate("it, de;q=0.2", &supported), Some("de"));
+    // Ignoring case, and answering with the supported spelling.
+    assert_eq!(negotiate("DE", &supported), Some("de"));
+    assert_eq!(negotiate("it, es", &supported), None);
+    assert_eq!(request(&[]).negotiate_language(&supported), None);
+}
+
+#[test]
+fn region_subtags_fall_back_to_the_primary_subtag() {
+    // `en-GB` isn't supported, `en` is.
+    assert_eq!(negotiate("en-GB", &["de", "en"]), Some("en"));
+    assert_eq!(negotiate("zh-Hant-TW", &["zh-Hant", "zh"]), Some("zh-Hant"));
+    // The exact tag wins over the fallback.
+    assert_eq!(negotiate("en-GB", &["en", "en-GB"]), Some("en-GB"));
+    // A range doesn't pick a more specific tag: `en` isn't `en-GB`.
+    assert_eq!(negotiate("en", &["en-GB", "en-US"]), None);
+    // A better quality fallback wins over a worse exact match.
+    assert_eq!(negotiate("en-GB, de;q=0.5", &["de", "en"]), Some("en"));
+}
+
+#[test]
+fn the_wildcard_picks_the_first_acceptable_language() {
+    let supported = ["de", "en", "fr"];
+    assert_eq!(negotiate("*", &supported), Some("de"));
+    // Tags ranked higher are looked up first.
+    assert_eq!(negotiate("fr, *;q=0.5", &supported), Some("fr"));
+    assert_eq!(negotiate("it, *;q=0.5", &supported), Some("de"));
+    // Excluded languages aren't picked by the wildcard.
+    assert_eq!(negotiate("*, de;q=0", &supported), Some("en"));
+    assert_eq!(negotiate("*", &[]), None);
+}
+
+#[test]
+fn languages_with_q_0_are_never_picked() {
+    assert_eq!(negotiate("en;q=0", &["en"]), None);
+    // Nor are their more specific tags.
+    assert_eq!(negotiate("en-GB, en;q=0", &["en-GB", "en"]), None);
+    assert_eq!(
+        negotiate("en-GB, en;q=0, de;q=0.1", &["en", "de"]),
+        Some("de")
+    );
+    assert_eq!(negotiate("*;q=0", &["en"]), None);
+}
@@ -0,0 +1,178 @@
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Limits, Router};
+use http::{Request, Response, StatusCode};
+
+struct Ok;
+
+impl HttpHandler for Ok {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok("ok")
+    }
+}
+
+fn router(limits: Limits) -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/items", "description": "", "controller": "ok"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("ok", Ok);
+    router.set_limits(limits);
+    router.use_problem_responses(true);
+    router
+}
+
+fn get(router: &Router, uri: &str, headers: &[(String, String)]) -> Response<bytes::Bytes> {
+    let mut req = Request::get(uri);
+    for (name, value) in headers {
+        req = req.header(name.as_str(), value.as_str());
+    }
+    router.route(req.body(String::new()).unwrap())
+}
+
+fn headers(count: usize) -> Vec<(String, String)> {
+    (0..count)
+        .map(|i| (format!("x-h{i}"), "1".to_string()))
+        .collect()
+}
+
+fn assert_problem(response: &Response<bytes::Bytes>, status: StatusCode, detail: &str) {
+    assert_eq!(response.status(), status);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/problem+json"
+    );
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(body["status"], status.as_u16());
+    assert_eq!(body["detail"], detail);
+}
+
+#[test]
+fn header_count_at_and_over_the_limit() {
+    let router = router(Limits {
+        max_headers: 3,
+        ..Limits::default()
+    });
+    assert_eq!(get(&router, "/items", &headers(3)).status(), StatusCode::OK);
+    assert_problem(
+        &get(&router, "/items", &headers(4)),
+        StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+        "The request has too many headers.",
+    );
+    // Each value of a repeated header counts.
+    let repeated = vec![("x-tag".to_string(), "a".to_string()); 4];
+    assert_eq!(
+        get(&router, "/items", &repeated).status(),
+        StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+    );
+}
+
+#[test]
+fn header_size_at_and_over_the_limit() {
+    let router = router(Limits {
+        max_header_bytes: 16,
+        ..Limits::default()
+    });
+    // "x-long" is 6 bytes, so a 10-byte value is exactly at the limit.
+    let at = vec![("x-long".to_string(), "a".repeat(10))];
+    assert_eq!(get(&router, "/items", &at).status(), StatusCode::OK);
+    let over = vec![("x-long".to_string(), "a".repeat(11))];
+    assert_problem(
+        &get(&router, "/items", &over),
+        StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+        "A request header is too large.",
+    );
+}
+
+#[test]
+fn uri_length_at_and_over_the_limit() {
+    let router = router(Limits {
+        max_uri_bytes: 20,
+        ..Limits::default()
+    });
+    // "/items?q=" is 9 bytes.
+    let at = format!("/items?q={}", "a".repeat(11));
+    assert_eq!(get(&router, &at, &[]).status(), StatusCode::OK);
+    let over = format!("/items?q={}", "a".repeat(12));
+    assert_problem(
+        &get(&router, &over, &[]),
+        StatusCode::URI_TOO_LONG,
+        "The request URI is too long.",
+    );
+    // The scheme and authority of an absolute URI count.
+    let absolute = "http://example.com/items";
+    assert_eq!(
+        get(&router, absolute, &[]).status(),
+        StatusCode::URI_TOO_LONG
+    );
+}
+
+#[test]
+fn query_param_count_at_and_over_the_limit() {
+    let router = router(Limits {
+        max_query_params: 3,
+        ..Limits::default()
+    });
+    assert_eq!(
+        get(&router, "/items?a=1&b=2&c=3", &[]).status(),
+        StatusCode::OK
+    );
+    assert_problem(
+        &get(&router, "/items?a=1&b=2&c=3&d=4", &[]),
+        StatusCode::URI_TOO_LONG,
+        "The request URI has too many query parameters.",
+    );
+    // Empty pairs aren't parameters.
+    assert_eq!(
+        get(&router, "/items?a=1&&b=2&c=3&", &[]).status(),
+        StatusCode::OK
+    );
+}
+
+#[test]
+fn limits_are_checked_before_matching() {
+    let mut router = router(Limits {
+        max_headers: 1,
+        ..Limits::default()
+    });
+    let response = get(&router, "/nowhere", &headers(2));
+    assert_eq!(
+        response.status(),
+        StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+    );
+
+    // Without problem responses, the error has an empty body.
+    router.use_problem_responses(false);
+    let response = get(&router, "/items", &headers(2));
+    assert_eq!(
+        response.status(),
+        StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+    );
+    assert!(response.body().is_empty());
+}
+
+#[test]
+fn limits_from_the_config() {
+    let mut router = Router::from_json_str(
+        r#"{"limits": {"max_query_params": 1},
+            "endpoints": [
+            {"method": "GET", "path": "/items", "description": "", "controller": "ok"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("ok", Ok);
+    assert_eq!(get(&router, "/items?a=1", &[]).status(), StatusCode::OK);
+    assert_eq!(
+        get(&router, "/items?a=1&b=2", &[]).status(),
+        StatusCode::URI_TOO_LONG
+    );
+    // The other limits keep their defaults.
+    assert_eq!(
+        get(&router, "/items", &headers(100)).status(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        get(&router, "/items", &headers(101)).status(),
+        StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+    );
+}
@@ -0,0 +1,121 @@
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router, UrlError};
+use http::{Request, StatusCode};
+
+/// Answers with the locale of the route and its `plan` parameter.
+struct Pricing;
+
+impl HttpHandler for Pricing {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let plan = req.params.get("plan").unwrap_or("-").to_string();
+        HttpResponse::ok(format!("{} {plan}", req.locale().unwrap_or("none")))
+    }
+}
+
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": {"en": "/pricing", "de": "/preise"},
+             "description": "", "controller": "pricing"},
+            {"method": "GET", "path": {"en": "/pricing/:plan", "de": "/preise/:plan"},
+             "description": "", "controller": "pricing::plan"},
+            {"method": "GET", "path": "/docs/*page", "description": "", "controller": "docs"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("pricing", Pricing);
+    router.register_handler("pricing::plan", Pricing);
+    router
+}
+
+fn get(router: &Router, path: &str) -> (StatusCode, String) {
+    let response = router.route(Request::get(path).body(String::new()).unwrap());
+    let body = String::from_utf8(response.body().to_vec()).unwrap();
+    (response.status(), body)
+}
+
+#[test]
+fn every_localized_path_dispatches_to_the_controller_with_its_locale() {
+    let router = router();
+    assert_eq!(
+        get(&router, "/pricing"),
+        (StatusCode::OK, "en -".to_string())
+    );
+    assert_eq!(
+        get(&router, "/preise"),
+        (StatusCode::OK, "de -".to_string())
+    );
+    assert_eq!(
+        get(&router, "/preise/team"),
+        (StatusCode::OK, "de team".to_string())
+    );
+}
+
+#[test]
+fn url_for_each_locale() {
+    let router = router();
+    assert_eq!(
+        router.url_for("pricing", Some("en"), &[]).unwrap(),
+        "/pricing"
+    );
+    assert_eq!(
+        router.url_for("pricing", Some("de"), &[]).unwrap(),
+        "/preise"
+    );
+    assert_eq!(
+        router
+            .url_for("pricing::plan", Some("de"), &[("plan", "team")])
+            .unwrap(),
+        "/preise/team"
+    );
+    // Unlocalized paths are for every locale.
+    assert_eq!(
+        router
+            .url_for("docs", Some("de"), &[("page", "guide/intro md")])
+            .unwrap(),
+        "/docs/guide/intro%20md"
+    );
+    assert_eq!(
+        router.url_for("docs", None, &[("page", "a/b")]).unwrap(),
+        "/docs/a/b"
+    );
+}
+
+#[test]
+fn url_for_encodes_parameter_values() {
+    let router = router();
+    let url = router
+        .url_for("pricing::plan", Some("en"), &[("plan", "a/b?c")])
+        .unwrap();
+    assert_eq!(url, "/pricing/a%2Fb%3Fc");
+    assert_eq!(get(&router, &url), (StatusCode::OK, "en a/b?c".to_string()));
+}
+
+#[test]
+fn url_for_a_missing_locale() {
+    let router = router();
+    assert_eq!(
+        router.url_for("pricing", Some("fr"), &[]),
+        Err(UrlError::MissingLocale {
+            controller: "pricing".to_string(),
+            locale: Some("fr".to_string()),
+        })
+    );
+    let error = router.url_for("pricing", None, &[]).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "controller 'pricing' has no route for locale 'none'"
+    );
+}
+
+#[test]
+fn url_for_errors() {
+    let router = router();
+    assert_eq!(
+        router.url_for("checkout", Some("en"), &[]),
+        Err(UrlError::UnknownController("checkout".to_string()))
+    );
+    assert_eq!(
+        router.url_for("pricing::plan", Some("en"), &[("tier", "team")]),
+        Err(UrlError::MissingParam("plan".to_string()))
+    );
+}
@@ -0,0 +1,142 @@
+use generic_http_router::maintenance::MaintenanceMode;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Request, Response, StatusCode};
+use std::time::Duration;
+
+/// Answers with its name.
+struct Named(&'static str);
+
+impl HttpHandler for Named {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok(self.0)
+    }
+}
+
+/// A router with `/health` exempt from maintenance, `/billing/invoices`,
+/// `/billing-report` and `/users`.
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/health", "description": "", "controller": "health",
+             "maintenance_exempt": true},
+            {"method": "GET", "path": "/billing/invoices", "description": "", "controller": "invoices"},
+            {"method": "GET", "path": "/billing-report", "description": "", "controller": "report"},
+            {"method": "GET", "path": "/users", "description": "", "controller": "users"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("health", Named("health"));
+    router.register_handler("invoices", Named("invoices"));
+    router.register_handler("report", Named("report"));
+    router.register_handler("users", Named("users"));
+    router
+}
+
+fn get(router: &Router, path: &str) -> Response<bytes::Bytes> {
+    router.route(Request::get(path).body(String::new()).unwrap())
+}
+
+fn status(router: &Router, path: &str) -> StatusCode {
+    get(router, path).status()
+}
+
+#[test]
+fn global_maintenance_spares_exempt_routes() {
+    let router = router();
+    router.set_maintenance_response(Some(Duration::from_secs(120)), "Back soon");
+    router.set_maintenance(MaintenanceMode::All);
+    assert_eq!(router.maintenance(), MaintenanceMode::All);
+
+    for path in ["/billing/invoices", "/billing-report", "/users"] {
+        let response = get(&router, path);
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE, "{path}");
+        assert_eq!(response.headers()["retry-after"], "120", "{path}");
+        assert_eq!(response.body(), "Back soon", "{path}");
+    }
+    let response = get(&router, "/health");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), "health");
+    assert!(response.headers().get("retry-after").is_none());
+
+    // Paths without a route still 404.
+    assert_eq!(status(&router, "/nowhere"), StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn prefix_maintenance_only_affects_matching_paths() {
+    let router = router();
+    router.set_maintenance(MaintenanceMode::Prefixes(vec!["/billing/".to_string()]));
+    assert_eq!(
+        status(&router, "/billing/invoices"),
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+    // The prefix is matched as given: `/billing-report` doesn't start with
+    // `/billing/`.
+    assert_eq!(status(&router, "/billing-report"), StatusCode::OK);
+    assert_eq!(status(&router, "/users"), StatusCode::OK);
+    assert_eq!(status(&router, "/health"), StatusCode::OK);
+
+    router.set_maintenance(MaintenanceMode::Prefixes(vec![
+        "/billing".to_string(),
+        "/users".to_string(),
+    ]));
+    for path in ["/billing/invoices", "/billing-report", "/users"] {
+        assert_eq!(
+            status(&router, path),
+            StatusCode::SERVICE_UNAVAILABLE,
+            "{path}"
+        );
+    }
+}
+
+#[test]
+fn the_default_response_is_a_bare_503() {
+    let mut router = router();
+    router.set_maintenance(MaintenanceMode::All);
+    let response = get(&router, "/users");
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(response.headers().get("retry-after").is_none());
+    assert!(response.body().is_empty());
+
+    router.use_problem_responses(true);
+    let response = get(&router, "/users");
+    let problem: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(problem["status"], 503);
+    assert_eq!(problem["detail"], "The service is undergoing maintenance.");
+}
+
+#[test]
+fn switching_maintenance_off_restores_routing() {
+    let router = router();
+    router.set_maintenance(MaintenanceMode::All);
+    assert_eq!(status(&router, "/users"), StatusCode::SERVICE_UNAVAILABLE);
+    router.set_maintenance(MaintenanceMode::Off);
+    assert_eq!(router.maintenance(), MaintenanceMode::Off);
+    for path in ["/billing/invoices", "/billing-report", "/users", "/health"] {
+        assert_eq!(status(&router, path), StatusCode::OK, "{path}");
+    }
+}
+
+#[cfg(feature = "shared")]
+#[test]
+fn the_shared_handle_toggles_every_version() {
+    use generic_http_router::SharedRouter;
+    use generic_http_router::config::Endpoint;
+    use http::Method;
+
+    let shared = SharedRouter::new(router());
+    shared.set_maintenance(MaintenanceMode::All);
+    let response = shared.route(Request::get("/users").body(String::new()).unwrap());
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    // A new version of the router, after adding a route, stays in maintenance
+    // until it's switched off.
+    shared
+        .add_route(Endpoint::new(Method::GET, "/orders", "users"))
+        .unwrap();
+    let response = shared.route(Request::get("/orders").body(String::new()).unwrap());
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    shared.set_maintenance(MaintenanceMode::Off);
+    let response = shared.route(Request::get("/orders").body(String::new()).unwrap());
+    assert_eq!(response.status(), StatusCode::OK);
+}
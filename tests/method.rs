@@ -0,0 +1,153 @@
+use generic_http_router::method::TracePolicy;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Method, Request, Response, StatusCode};
+
+/// Answers with its name and the request's method.
+struct Named(&'static str);
+
+impl HttpHandler for Named {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok(format!("{} {}", self.0, req.method))
+    }
+}
+
+/// A router with `ANY /any`, explicit `TRACE` and `CONNECT` routes next to a
+/// `GET` on `/explicit`, and a `TRACE`-only `/trace-only`.
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "ANY", "path": "/any", "description": "", "controller": "any"},
+            {"method": "GET", "path": "/explicit", "description": "", "controller": "explicit"},
+            {"method": "TRACE", "path": "/explicit", "description": "", "controller": "explicit"},
+            {"method": "CONNECT", "path": "/explicit", "description": "", "controller": "explicit"},
+            {"method": "TRACE", "path": "/trace-only", "description": "", "controller": "explicit"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("any", Named("any"));
+    router.register_handler("explicit", Named("explicit"));
+    router
+}
+
+fn send(router: &Router, method: Method, path: &str) -> Response<bytes::Bytes> {
+    router.route(
+        Request::builder()
+            .method(method)
+            .uri(path)
+            .body(String::new())
+            .unwrap(),
+    )
+}
+
+fn allow(response: &Response<bytes::Bytes>) -> &str {
+    response.headers()["allow"].to_str().unwrap()
+}
+
+#[test]
+fn any_stands_for_every_method_but_trace_and_connect() {
+    let router = router();
+    for method in [
+        Method::GET,
+        Method::POST,
+        Method::PUT,
+        Method::DELETE,
+        Method::PATCH,
+    ] {
+        let response = send(&router, method.clone(), "/any");
+        assert_eq!(response.status(), StatusCode::OK, "{method}");
+        assert_eq!(response.body(), format!("any {method}").as_str());
+    }
+    let patterns: Vec<String> = router
+        .routes()
+        .filter(|endpoint| endpoint.path == "/any")
+        .map(|endpoint| endpoint.method.to_string())
+        .collect();
+    assert_eq!(
+        patterns,
+        ["GET", "HEAD", "POST", "PUT", "DELETE", "PATCH", "OPTIONS"]
+    );
+}
+
+#[test]
+fn trace_is_rejected_by_default() {
+    let router = router();
+    // An `ANY` route doesn't cover `TRACE`.
+    let response = send(&router, Method::TRACE, "/any");
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert!(!allow(&response).contains("TRACE"), "{}", allow(&response));
+
+    // Nor does an explicit `TRACE` route get it.
+    let response = send(&router, Method::TRACE, "/explicit");
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(allow(&response), "CONNECT, GET, HEAD");
+
+    // `TRACE` routes are left out of the other methods' `Allow` header.
+    let response = send(&router, Method::DELETE, "/explicit");
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(allow(&response), "CONNECT, GET, HEAD");
+
+    // A path with only a `TRACE` route doesn't exist.
+    assert_eq!(
+        send(&router, Method::TRACE, "/trace-only").status(),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        send(&router, Method::GET, "/trace-only").status(),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        send(&router, Method::TRACE, "/nowhere").status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[test]
+fn trace_is_routed_when_allowed() {
+    let mut router = router();
+    router.set_trace_policy(TracePolicy::Allow);
+    let response = send(&router, Method::TRACE, "/explicit");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), "explicit TRACE");
+    let response = send(&router, Method::TRACE, "/trace-only");
+    assert_eq!(response.body(), "explicit TRACE");
+    let response = send(&router, Method::DELETE, "/explicit");
+    assert_eq!(allow(&response), "CONNECT, GET, HEAD, TRACE");
+
+    // `ANY` still doesn't cover it.
+    let response = send(&router, Method::TRACE, "/any");
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert!(!allow(&response).contains("TRACE"), "{}", allow(&response));
+}
+
+#[test]
+fn connect_is_not_implemented_without_a_connect_route() {
+    for policy in [TracePolicy::Reject, TracePolicy::Allow] {
+        let mut router = router();
+        router.set_trace_policy(policy);
+        for path in ["/any", "/trace-only", "/nowhere"] {
+            let response = send(&router, Method::CONNECT, path);
+            assert_eq!(
+                response.status(),
+                StatusCode::NOT_IMPLEMENTED,
+                "{policy:?} {path}"
+            );
+        }
+        // An explicit `CONNECT` route gets it.
+        let response = send(&router, Method::CONNECT, "/explicit");
+        assert_eq!(response.status(), StatusCode::OK, "{policy:?}");
+        assert_eq!(response.body(), "explicit CONNECT");
+    }
+}
+
+#[test]
+fn other_methods_are_unaffected() {
+    let router = router();
+    assert_eq!(
+        send(&router, Method::GET, "/explicit").body(),
+        "explicit GET"
+    );
+    let response = send(&router, Method::HEAD, "/explicit");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.body().is_empty());
+    assert_eq!(send(&router, Method::OPTIONS, "/any").body(), "any OPTIONS");
+}
@@ -0,0 +1,210 @@
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Request, StatusCode};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Answers with its version and the `id` parameter, counting its calls.
+struct Users {
+    version: &'static str,
+    calls: Arc<AtomicUsize>,
+}
+
+impl HttpHandler for Users {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let id = req.params.get("id").unwrap_or("-");
+        let body = String::from_utf8_lossy(&req.body);
+        HttpResponse::ok(format!("{} {id} {body}", self.version))
+    }
+}
+
+struct Panics;
+
+impl HttpHandler for Panics {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        panic!("the mirror handler failed");
+    }
+}
+
+/// What the `on_mirror_result` callback saw: the request's method, URI and
+/// body, and the primary and mirror response bodies.
+type Seen = (String, String, String, String, String);
+
+fn body(response: &HttpResponse) -> String {
+    String::from_utf8(response.body.as_bytes().unwrap().to_vec()).unwrap()
+}
+
+/// A router mirroring `/users/:id` to `users_v2`, `/panics/:id` to a
+/// panicking handler and `/orphans/:id` to an unregistered controller, with
+/// the primary and mirror call counts.
+fn router() -> (Router, Arc<AtomicUsize>, Arc<AtomicUsize>) {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "POST", "path": "/users/:id", "description": "", "controller": "users",
+             "mirror_to": "users_v2"},
+            {"method": "POST", "path": "/panics/:id", "description": "", "controller": "users",
+             "mirror_to": "panics"},
+            {"method": "POST", "path": "/orphans/:id", "description": "", "controller": "users",
+             "mirror_to": "missing"}
+        ]}"#,
+    )
+    .unwrap();
+    let (primary, mirror) = (Arc::default(), Arc::default());
+    router.register_handler(
+        "users",
+        Users {
+            version: "v1",
+            calls: Arc::clone(&primary),
+        },
+    );
+    router.register_handler(
+        "users_v2",
+        Users {
+            version: "v2",
+            calls: Arc::clone(&mirror),
+        },
+    );
+    router.register_handler("panics", Panics);
+    (router, primary, mirror)
+}
+
+fn post(path: &str, body: &str) -> Request<String> {
+    Request::post(path).body(body.to_string()).unwrap()
+}
+
+/// Records what the callback sees in `seen`.
+fn record(router: &mut Router) -> Arc<Mutex<Vec<Seen>>> {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&seen);
+    router.on_mirror_result(move |req, primary, mirror| {
+        sink.lock().unwrap().push((
+            req.method.to_string(),
+            req.uri.to_string(),
+            String::from_utf8(req.body.to_vec()).unwrap(),
+            body(primary),
+            body(mirror),
+        ));
+    });
+    seen
+}
+
+#[test]
+fn sync_routing_doesnt_mirror_by_default() {
+    let (mut router, primary, mirror) = router();
+    let seen = record(&mut router);
+    let response = router.route(post("/users/7", "ada"));
+    assert_eq!(response.body(), "v1 7 ada");
+    assert_eq!(primary.load(Ordering::SeqCst), 1);
+    assert_eq!(mirror.load(Ordering::SeqCst), 0);
+    assert!(seen.lock().unwrap().is_empty());
+}
+
+#[test]
+fn sync_mirroring_calls_the_mirror_with_a_copy_of_the_request() {
+    let (mut router, primary, mirror) = router();
+    router.set_sync_mirroring(true);
+    let seen = record(&mut router);
+    let response = router.route(post("/users/7", "ada"));
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), "v1 7 ada");
+    assert_eq!(primary.load(Ordering::SeqCst), 1);
+    assert_eq!(mirror.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        *seen.lock().unwrap(),
+        [(
+            "POST".to_string(),
+            "/users/7".to_string(),
+            "ada".to_string(),
+            "v1 7 ada".to_string(),
+            "v2 7 ada".to_string(),
+        )]
+    );
+
+    // `route_streaming` mirrors too.
+    router.route_streaming(post("/users/8", "bob"));
+    assert_eq!(mirror.load(Ordering::SeqCst), 2);
+    assert_eq!(seen.lock().unwrap()[1].4, "v2 8 bob");
+}
+
+#[test]
+fn a_failing_mirror_never_affects_the_primary_response() {
+    let (mut router, primary, _) = router();
+    router.set_sync_mirroring(true);
+    let seen = record(&mut router);
+
+    // A panicking mirror handler: the callback isn't called.
+    let response = router.route(post("/panics/7", "ada"));
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), "v1 7 ada");
+    // An unregistered mirror controller.
+    let response = router.route(post("/orphans/7", "ada"));
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), "v1 7 ada");
+    assert_eq!(primary.load(Ordering::SeqCst), 2);
+    assert!(seen.lock().unwrap().is_empty());
+
+    // A panicking callback.
+    router.on_mirror_result(|_, _, _| panic!("the callback failed"));
+    let response = router.route(post("/users/7", "ada"));
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), "v1 7 ada");
+}
+
+#[cfg(feature = "async")]
+mod spawned {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    /// A mirror handler that answers once released.
+    struct Held(Mutex<mpsc::Receiver<()>>);
+
+    impl HttpHandler for Held {
+        fn handle(&self, _req: HttpRequest) -> HttpResponse {
+            self.0.lock().unwrap().recv().unwrap();
+            HttpResponse::ok("held")
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn route_async_mirrors_without_waiting_for_the_mirror() {
+        let (mut router, _, _) = router();
+        let (release, held) = mpsc::channel();
+        router.register_handler("users_v2", Held(Mutex::new(held)));
+        let (report, mut reports) = unbounded_channel();
+        router.on_mirror_result(move |req, primary, mirror| {
+            let _ = report.send((req.uri.to_string(), body(primary), body(mirror)));
+        });
+
+        // The primary response is returned while the mirror handler is held.
+        let response = router.route_async(post("/users/7", "ada")).await;
+        assert_eq!(response.body(), "v1 7 ada");
+        assert!(reports.try_recv().is_err());
+
+        release.send(()).unwrap();
+        let reported = tokio::time::timeout(Duration::from_secs(10), reports.recv())
+            .await
+            .unwrap();
+        assert_eq!(
+            reported,
+            Some((
+                "/users/7".to_string(),
+                "v1 7 ada".to_string(),
+                "held".to_string()
+            ))
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn a_panicking_spawned_mirror_only_ends_its_task() {
+        let (router, primary, _) = router();
+        let response = router.route_async(post("/panics/7", "ada")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), "v1 7 ada");
+        assert_eq!(primary.load(Ordering::SeqCst), 1);
+        // The router keeps routing.
+        let response = router.route_async(post("/users/8", "bob")).await;
+        assert_eq!(response.body(), "v1 8 bob");
+    }
+}
@@ -0,0 +1,137 @@
+use generic_http_router::handler::MissingHandlerPolicy;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, RequestSummary, Router};
+use http::{Request, Response, StatusCode};
+use std::sync::{Arc, Mutex};
+
+/// Answers with a 503 and the path it was called for.
+struct Fallback;
+
+impl HttpHandler for Fallback {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        HttpResponse::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("fallback {}", req.uri.path()),
+        )
+    }
+}
+
+/// A router with `GET /orders/:id`, whose `orders::internal_get` controller
+/// has no handler, recording the summaries of the requests it routes.
+fn router(policy: MissingHandlerPolicy) -> (Router, Arc<Mutex<Vec<RequestSummary>>>) {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/orders/:id", "description": "",
+             "controller": "orders::internal_get"}
+        ]}"#,
+    )
+    .unwrap();
+    router.set_missing_handler_policy(policy);
+    let summaries = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&summaries);
+    router.on_response(move |summary| sink.lock().unwrap().push(summary.clone()));
+    (router, summaries)
+}
+
+fn get(router: &Router) -> Response<bytes::Bytes> {
+    router.route(Request::get("/orders/7").body(String::new()).unwrap())
+}
+
+/// Checks the controller name is in the summary's detail and nowhere in the
+/// response.
+fn assert_name_only_in_summary(
+    response: &Response<bytes::Bytes>,
+    summaries: &Mutex<Vec<RequestSummary>>,
+) {
+    let body = String::from_utf8_lossy(response.body());
+    assert!(!body.contains("orders::internal_get"), "{body}");
+    for value in response.headers().values() {
+        assert!(!value.to_str().unwrap().contains("internal_get"));
+    }
+    let summaries = summaries.lock().unwrap();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(
+        summaries[0].detail.as_deref(),
+        Some("No handler is registered for controller 'orders::internal_get'.")
+    );
+    assert_eq!(summaries[0].status, response.status());
+}
+
+#[test]
+fn not_implemented_is_the_default_with_a_generic_body() {
+    assert_eq!(
+        MissingHandlerPolicy::default(),
+        MissingHandlerPolicy::NotImplemented
+    );
+    let (router, summaries) = router(MissingHandlerPolicy::default());
+    let response = get(&router);
+    assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+    assert_eq!(
+        response.body(),
+        "Error: No handler is registered for this route."
+    );
+    assert_name_only_in_summary(&response, &summaries);
+}
+
+#[test]
+fn not_found_pretends_the_route_doesnt_exist() {
+    let (mut router, summaries) = router(MissingHandlerPolicy::NotFound);
+    let response = get(&router);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(response.body().is_empty());
+    assert_name_only_in_summary(&response, &summaries);
+
+    // The same as a path without a route.
+    let unrouted = router.route(Request::get("/nowhere").body(String::new()).unwrap());
+    assert_eq!(unrouted.status(), StatusCode::NOT_FOUND);
+    assert_eq!(unrouted.body(), response.body());
+
+    router.use_problem_responses(true);
+    let response = get(&router);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/problem+json"
+    );
+    assert!(!String::from_utf8_lossy(response.body()).contains("internal_get"));
+}
+
+#[test]
+fn fallback_calls_the_fallback_handler() {
+    let (mut router, summaries) = router(MissingHandlerPolicy::Fallback);
+    router.set_fallback_handler(Box::new(Fallback));
+    let response = get(&router);
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.body(), "fallback /orders/7");
+    assert_name_only_in_summary(&response, &summaries);
+}
+
+#[test]
+fn fallback_without_a_fallback_handler_is_not_implemented() {
+    let (router, summaries) = router(MissingHandlerPolicy::Fallback);
+    let response = get(&router);
+    assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+    assert_name_only_in_summary(&response, &summaries);
+}
+
+#[test]
+fn registered_handlers_are_unaffected_by_the_policy() {
+    struct Found;
+
+    impl HttpHandler for Found {
+        fn handle(&self, _req: HttpRequest) -> HttpResponse {
+            HttpResponse::ok("found")
+        }
+    }
+
+    for policy in [
+        MissingHandlerPolicy::NotImplemented,
+        MissingHandlerPolicy::NotFound,
+        MissingHandlerPolicy::Fallback,
+    ] {
+        let (mut router, summaries) = router(policy);
+        router.set_fallback_handler(Box::new(Fallback));
+        router.register_handler("orders::internal_get", Found);
+        let response = get(&router);
+        assert_eq!(response.body(), "found", "{policy:?}");
+        assert_eq!(summaries.lock().unwrap()[0].detail, None, "{policy:?}");
+    }
+}
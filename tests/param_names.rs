@@ -0,0 +1,123 @@
+//! Routes for one path that name its parameter differently, `GET /users/:id`
+//! and `DELETE /users/:user_id`: loading warns, and each route keeps its own
+//! name when matching, in `url_for` and in the exports, while the path shares
+//! one `Allow` value.
+
+use generic_http_router::config::Config;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, LoadWarning, Router, UrlError};
+use http::{Method, Request, StatusCode};
+
+const CONFIG: &str = r#"{"endpoints": [
+    {"method": "GET", "path": "/users/:id", "description": "", "controller": "users::show"},
+    {"method": "DELETE", "path": "/users/:user_id", "description": "", "controller": "users::delete"}
+]}"#;
+
+/// Answers with the names and values of the path parameters.
+struct Params;
+
+impl HttpHandler for Params {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let id = req.params.get("id").unwrap_or("-");
+        let user_id = req.params.get("user_id").unwrap_or("-");
+        HttpResponse::ok(format!("id={id} user_id={user_id}"))
+    }
+}
+
+fn router() -> (Router, Vec<LoadWarning>) {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("param_names.json");
+    std::fs::write(&path, CONFIG).unwrap();
+    let (mut router, warnings) = Router::new_with_report(&path).unwrap();
+    router.register_handler("users::show", Params);
+    router.register_handler("users::delete", Params);
+    (router, warnings)
+}
+
+fn send(router: &Router, method: Method, path: &str) -> http::Response<bytes::Bytes> {
+    let req = Request::builder().method(method).uri(path);
+    router.route(req.body(String::new()).unwrap())
+}
+
+#[test]
+fn loading_warns_with_both_spellings() {
+    let (_, warnings) = router();
+    let routes = warnings
+        .iter()
+        .find_map(|warning| match warning {
+            LoadWarning::ParamNamesDiffer { routes } => Some(routes.clone()),
+            _ => None,
+        })
+        .expect("a ParamNamesDiffer warning");
+    assert_eq!(
+        routes,
+        vec![
+            (Method::GET, "/users/:id".to_string()),
+            (Method::DELETE, "/users/:user_id".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn each_handler_gets_its_own_routes_names() {
+    let (router, _) = router();
+    let response = send(&router, Method::GET, "/users/7");
+    assert_eq!(response.body().as_ref(), b"id=7 user_id=-");
+    let response = send(&router, Method::DELETE, "/users/7");
+    assert_eq!(response.body().as_ref(), b"id=- user_id=7");
+}
+
+#[test]
+fn the_path_has_one_allow_value() {
+    let (router, _) = router();
+    let response = send(&router, Method::PUT, "/users/7");
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers()["allow"], "DELETE, GET, HEAD");
+}
+
+#[test]
+fn url_for_takes_each_routes_own_names() {
+    let (router, _) = router();
+    assert_eq!(
+        router.url_for("users::show", None, &[("id", "7")]).unwrap(),
+        "/users/7"
+    );
+    assert_eq!(
+        router
+            .url_for("users::delete", None, &[("user_id", "7")])
+            .unwrap(),
+        "/users/7"
+    );
+    // The other route's spelling isn't accepted.
+    assert_eq!(
+        router.url_for("users::delete", None, &[("id", "7")]),
+        Err(UrlError::MissingParam("user_id".to_string()))
+    );
+}
+
+#[test]
+fn exports_list_each_routes_own_names() {
+    let (router, _) = router();
+    let listed: Vec<(Method, &str)> = router
+        .routes()
+        .map(|endpoint| (endpoint.method.clone(), endpoint.path.as_str()))
+        .collect();
+    assert_eq!(
+        listed,
+        vec![
+            (Method::GET, "/users/:id"),
+            (Method::DELETE, "/users/:user_id"),
+        ]
+    );
+
+    let config: Config = serde_json::from_str(CONFIG).unwrap();
+    let collection = config.to_postman_collection("http://localhost");
+    let requests = collection["item"][0]["item"].as_array().unwrap();
+    let variables: Vec<&str> = requests
+        .iter()
+        .map(|request| {
+            request["request"]["url"]["variable"][0]["key"]
+                .as_str()
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(variables, ["id", "user_id"]);
+}
@@ -0,0 +1,74 @@
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Params, Router};
+use http::Request;
+use std::collections::HashMap;
+
+/// Answers with the request's parameters, in path order.
+struct Echo;
+
+impl HttpHandler for Echo {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let params: Vec<String> = req.params.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        HttpResponse::ok(format!("{} {}", req.params.len(), params.join("&")))
+    }
+}
+
+fn get(path: &str) -> String {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/health", "description": "", "controller": "echo"},
+            {"method": "GET", "path": "/users/:user/orders/:order", "description": "", "controller": "echo"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("echo", Echo);
+    let response = router.route(Request::get(path).body(String::new()).unwrap());
+    String::from_utf8(response.body().to_vec()).unwrap()
+}
+
+#[test]
+fn routes_pass_their_parameters_in_path_order() {
+    assert_eq!(get("/health"), "0 ");
+    assert_eq!(get("/users/ada/orders/7"), "2 user=ada&order=7");
+}
+
+#[test]
+fn params_work_like_a_small_map() {
+    let mut params = Params::new();
+    assert!(params.is_empty());
+    assert_eq!(params.get("id"), None);
+
+    params.insert("user", "ada");
+    params.insert("order", "7");
+    params.insert("user", "grace");
+    assert_eq!(params.len(), 2);
+    assert_eq!(params.get("user"), Some("grace"));
+    assert_eq!(&params["order"], "7");
+    assert!(params.contains_key("order"));
+    assert!(!params.contains_key("Order"));
+    // Replacing a value keeps its position.
+    assert_eq!(
+        params.iter().collect::<Vec<_>>(),
+        [("user", "grace"), ("order", "7")]
+    );
+
+    let map = params.to_hashmap();
+    assert_eq!(map.len(), 2);
+    assert_eq!(map["user"], "grace");
+    let mut from_map = Params::from(map);
+    from_map.insert("extra", "");
+    assert_eq!(from_map.get("order"), Some("7"));
+    assert_eq!(from_map.get("extra"), Some(""));
+
+    let collected: Params = [("a", "1"), ("b", "2"), ("a", "3")].into_iter().collect();
+    assert_eq!(
+        collected.iter().collect::<Vec<_>>(),
+        [("a", "3"), ("b", "2")]
+    );
+    assert_eq!(Params::from(HashMap::new()), Params::default());
+}
+
+#[test]
+#[should_panic(expected = "no path parameter named 'id'")]
+fn indexing_a_missing_parameter_panics() {
+    let _ = &Params::new()["id"];
+}
@@ -0,0 +1,126 @@
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Request, StatusCode};
+use serde::Deserialize;
+
+fn request(body: &str) -> HttpRequest {
+    HttpRequest::builder()
+        .method("POST")
+        .uri("/orders?page=2")
+        .header("content-type", "application/json")
+        .body(body.as_bytes().to_vec())
+        .build()
+        .unwrap()
+}
+
+/// Answers whether the body was parsed before the handler, or after it
+/// reads it if `read`.
+struct Parsed {
+    read: bool,
+}
+
+impl HttpHandler for Parsed {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let before = req.body_parsed();
+        if self.read {
+            let _ = req.json_value();
+        }
+        HttpResponse::ok(format!("{before} {}", req.body_parsed()))
+    }
+}
+
+fn route(config: &str, read: bool) -> (StatusCode, String) {
+    let mut router = Router::from_json_str(config).unwrap();
+    router.register_handler("orders::create", Parsed { read });
+    let req = Request::post("/orders")
+        .header("content-type", "application/json")
+        .body(r#"{"item":"book"}"#)
+        .unwrap();
+    let response = router.route(req);
+    let body = String::from_utf8(response.body().to_vec()).unwrap();
+    (response.status(), body)
+}
+
+const CONFIG: &str = r#"{"endpoints": [
+    {"method": "POST", "path": "/orders", "description": "", "controller": "orders::create"}
+]}"#;
+
+#[test]
+fn the_body_isnt_parsed_unless_read() {
+    assert_eq!(
+        route(CONFIG, false),
+        (StatusCode::OK, "false false".to_string())
+    );
+    assert_eq!(
+        route(CONFIG, true),
+        (StatusCode::OK, "false true".to_string())
+    );
+
+    let req = request("not json");
+    assert!(!req.body_parsed());
+    // Reading the headers, the query or the raw body parses nothing.
+    let _ = (req.headers.len(), req.query_pairs(), req.body.len());
+    assert!(!req.body_parsed());
+}
+
+#[test]
+fn json_is_parsed_once_for_every_consumer() {
+    #[derive(Deserialize)]
+    struct Order {
+        item: String,
+    }
+
+    let req = request(r#"{"item":"book"}"#);
+    let first: *const _ = req.json_value().unwrap();
+    assert!(req.body_parsed());
+    let order: Order = req.json().unwrap();
+    assert_eq!(order.item, "book");
+    let again: *const _ = req.json_value().unwrap();
+    assert_eq!(first, again);
+}
+
+#[test]
+fn a_failed_parse_is_kept_too() {
+    let mut req = request("{");
+    let error = req.json_value().unwrap_err();
+    assert!(req.body_parsed());
+    // The result is kept even if the body is replaced.
+    req.body = bytes::Bytes::from_static(b"{}");
+    assert_eq!(req.json_value().unwrap_err(), error);
+}
+
+#[test]
+fn form_and_query_pairs_are_parsed_once() {
+    let req = HttpRequest::builder()
+        .method("POST")
+        .uri("/search?q=a+b&page=2")
+        .body(b"name=Zo%C3%AB&tag=".to_vec())
+        .build()
+        .unwrap();
+    let form = req.form_pairs().unwrap();
+    assert!(req.body_parsed());
+    assert_eq!(
+        form,
+        [
+            ("name".to_string(), "Zoë".to_string()),
+            ("tag".to_string(), String::new())
+        ]
+    );
+    assert!(std::ptr::eq(form, req.form_pairs().unwrap()));
+    let query = req.query_pairs().unwrap();
+    assert_eq!(query[0], ("q".to_string(), "a b".to_string()));
+    assert!(std::ptr::eq(query, req.query_pairs().unwrap()));
+}
+
+/// A request schema parses the body before the handler, which reuses it.
+#[cfg(feature = "schema")]
+#[test]
+fn request_schemas_share_the_parse_with_the_handler() {
+    let config = r#"{"endpoints": [
+        {"method": "POST", "path": "/orders", "description": "", "controller": "orders::create",
+         "request_schema": {"type": "object", "required": ["item"]}}
+    ]}"#;
+    assert_eq!(
+        route(config, true),
+        (StatusCode::OK, "true true".to_string())
+    );
+}
@@ -0,0 +1,212 @@
+use generic_http_router::path::{self, PathDecoding};
+use generic_http_router::testing::SeededRng;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Rng, Router};
+use http::{Method, Request, StatusCode, Uri};
+use std::borrow::Cow;
+
+struct Echo;
+
+impl HttpHandler for Echo {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let id = req.params.get("id").unwrap_or_default().to_string();
+        HttpResponse::ok(format!("{} {id}", req.uri.path()))
+    }
+}
+
+fn get(path: &str) -> (StatusCode, String) {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/", "description": "", "controller": "echo"},
+            {"method": "GET", "path": "/a", "description": "", "controller": "echo"},
+            {"method": "GET", "path": "/a/", "description": "", "controller": "echo"},
+            {"method": "GET", "path": "/users/:id", "description": "", "controller": "echo"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("echo", Echo);
+    let response = router.route(Request::get(path).body(String::new()).unwrap());
+    let body = String::from_utf8(response.body().to_vec()).unwrap();
+    (response.status(), body)
+}
+
+#[test]
+fn normal_paths_are_borrowed() {
+    for normal in ["/", "/a", "/a/", "/a/b.txt", "/a/..b/.c"] {
+        assert!(matches!(path::normalize(normal), Some(Cow::Borrowed(p)) if p == normal));
+    }
+}
+
+#[test]
+fn duplicate_slashes_are_collapsed() {
+    assert_eq!(path::normalize("//a").as_deref(), Some("/a"));
+    assert_eq!(path::normalize("/a//b").as_deref(), Some("/a/b"));
+    assert_eq!(path::normalize("/a//").as_deref(), Some("/a/"));
+    assert_eq!(path::normalize("//").as_deref(), Some("/"));
+}
+
+#[test]
+fn dot_segments_are_resolved() {
+    assert_eq!(path::normalize("/a/./b").as_deref(), Some("/a/b"));
+    assert_eq!(path::normalize("/a/b/..").as_deref(), Some("/a/"));
+    assert_eq!(path::normalize("/a/b/../c").as_deref(), Some("/a/c"));
+    assert_eq!(path::normalize("/a/..").as_deref(), Some("/"));
+    assert_eq!(path::normalize("/.").as_deref(), Some("/"));
+}
+
+#[test]
+fn dot_dot_escaping_the_root_is_rejected() {
+    assert_eq!(path::normalize("/.."), None);
+    assert_eq!(path::normalize("/a/../.."), None);
+    assert_eq!(path::normalize("/../a"), None);
+}
+
+#[test]
+fn requests_are_routed_by_the_normalized_path() {
+    assert_eq!(get("//a"), (StatusCode::OK, "/a ".to_string()));
+    assert_eq!(get("/a/./"), (StatusCode::OK, "/a/ ".to_string()));
+    assert_eq!(
+        get("/x/../users/7"),
+        (StatusCode::OK, "/users/7 7".to_string())
+    );
+    assert_eq!(get("/a/.."), (StatusCode::OK, "/ ".to_string()));
+    assert_eq!(get("/..").0, StatusCode::BAD_REQUEST);
+    assert_eq!(get("/users/../..").0, StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn percent_decoding() {
+    let decode = |input| path::percent_decode(input, PathDecoding::Reject);
+    assert!(matches!(decode("plain"), Some(Cow::Borrowed("plain"))));
+    assert_eq!(decode("a%20b%2Fc").as_deref(), Some("a b/c"));
+    assert_eq!(decode("%C3%A9").as_deref(), Some("é"));
+    assert_eq!(decode("%zz"), None);
+    assert_eq!(decode("%4"), None);
+    assert_eq!(decode("%FF"), None);
+
+    let lossy = |input| path::percent_decode(input, PathDecoding::Lossy);
+    assert_eq!(lossy("%zz%4").as_deref(), Some("%zz%4"));
+    assert_eq!(lossy("a%FFb").as_deref(), Some("a\u{FFFD}b"));
+}
+
+#[test]
+fn encoded_slashes_do_not_split_segments() {
+    assert_eq!(
+        get("/users/a%2Fb"),
+        (StatusCode::OK, "/users/a%2Fb a/b".to_string())
+    );
+    assert_eq!(get("/users/%zz").0, StatusCode::BAD_REQUEST);
+}
+
+/// The pieces a generated request target is made of, weighted towards the
+/// ones that percent-encoding, dot segments and UTF-8 decoding trip over.
+const TARGET_PIECES: [&[u8]; 24] = [
+    b"%",
+    b"%%",
+    b"%4",
+    b"%zz",
+    b"%C3",
+    b"%A9",
+    b"%C3%A9",
+    b"%FF",
+    b"%ED%A0%80",
+    b"%F0%9F",
+    b"%2F",
+    b"%2e",
+    b"%00",
+    b"/",
+    b"//",
+    b".",
+    b"..",
+    b"a",
+    b"users",
+    b"files",
+    b"~",
+    b"?",
+    b"&id=",
+    b"#",
+];
+
+/// Generates a request target of up to 12 pieces of `TARGET_PIECES`, with
+/// now and then any byte.
+fn arbitrary_target(rng: &SeededRng) -> Vec<u8> {
+    let mut target = vec![b'/'];
+    for _ in 0..rng.next_u64() % 12 {
+        let draw = rng.next_u64();
+        if draw.is_multiple_of(32) {
+            target.push((draw >> 8) as u8);
+        } else {
+            target.extend_from_slice(TARGET_PIECES[(draw >> 8) as usize % TARGET_PIECES.len()]);
+        }
+    }
+    target
+}
+
+/// Generates a method, a known one or an extension token of up to 8 bytes.
+fn arbitrary_method(rng: &SeededRng) -> Method {
+    const KNOWN: [Method; 5] = [
+        Method::GET,
+        Method::POST,
+        Method::HEAD,
+        Method::OPTIONS,
+        Method::DELETE,
+    ];
+    let draw = rng.next_u64();
+    if draw.is_multiple_of(2) {
+        return KNOWN[(draw >> 8) as usize % KNOWN.len()].clone();
+    }
+    let token: Vec<u8> = (0..1 + (draw >> 8) % 8)
+        .map(|_| b"ABCXYZ-!~"[rng.next_u64() as usize % 9])
+        .collect();
+    Method::from_bytes(&token).unwrap()
+}
+
+#[test]
+fn arbitrary_targets_never_panic() {
+    for decoding in [PathDecoding::Reject, PathDecoding::Lossy] {
+        let mut router = Router::from_json_str(
+            r#"{"endpoints": [
+                {"method": "GET", "path": "/a", "description": "", "controller": "echo"},
+                {"method": "GET", "path": "/users/:id", "description": "", "controller": "echo"},
+                {"method": "GET", "path": "/files/*path", "description": "", "controller": "echo"}
+            ]}"#,
+        )
+        .unwrap();
+        router.register_handler("echo", Echo);
+        router.set_path_decoding(decoding);
+        let rng = SeededRng::new(118);
+        let mut statuses = std::collections::BTreeSet::new();
+        for _ in 0..5000 {
+            let target = arbitrary_target(&rng);
+            if let Ok(text) = std::str::from_utf8(&target) {
+                let _ = path::normalize(text);
+                let _ = path::percent_decode(text, decoding);
+            }
+            let Ok(uri) = Uri::from_maybe_shared(bytes::Bytes::from(target)) else {
+                continue;
+            };
+            let method = arbitrary_method(&rng);
+            let req = Request::builder()
+                .method(method.clone())
+                .uri(uri.clone())
+                .body(String::new())
+                .unwrap();
+            let status = router.route(req).status();
+            assert!(
+                matches!(status.as_u16(), 200 | 204 | 400 | 404 | 405),
+                "{method} {uri}: {status}"
+            );
+            if decoding == PathDecoding::Lossy && status == StatusCode::BAD_REQUEST {
+                // Only dot segments escaping the root are rejected when lossy.
+                assert!(uri.path().contains(".."), "{method} {uri}");
+            }
+            statuses.insert(status.as_u16());
+        }
+        // The targets reach the handler, and the router's own answers.
+        for expected in [200, 404, 405] {
+            assert!(statuses.contains(&expected), "{statuses:?}");
+        }
+        if decoding == PathDecoding::Reject {
+            assert!(statuses.contains(&400), "{statuses:?}");
+        }
+    }
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use common::assert_snapshot;
+use generic_http_router::config::Config;
+use generic_http_router::postman::POSTMAN_SCHEMA;
+use serde_json::Value;
+
+/// Two controller prefixes, a controller without one, path parameters, a
+/// catch-all, a canary route and an endpoint without a description.
+const CONFIG: &str = r#"{"endpoints": [
+    {"method": "GET", "path": "/users", "description": "List users", "controller": "users::list"},
+    {"method": "GET", "path": "/users/:id", "description": "Get a user", "controller": "users::get"},
+    {"method": "POST", "path": "/orders", "description": "Create an order",
+     "canary": {"stable": "orders::create", "candidate": "orders::create_v2", "percent": 5}},
+    {"method": "GET", "path": "/users/:id/orders/:order", "description": "",
+     "controller": "orders::get"},
+    {"method": "GET", "path": "/files/*path", "description": "Download a file", "controller": "files"},
+    {"method": "DELETE", "path": "/users/:id", "description": "Delete a user", "controller": "users::delete"}
+]}"#;
+
+fn collection() -> Value {
+    let config: Config = serde_json::from_str(CONFIG).unwrap();
+    config.to_postman_collection("http://localhost:3000")
+}
+
+#[test]
+fn the_collection_matches_its_golden_file() {
+    let rendered = serde_json::to_string_pretty(&collection()).unwrap() + "\n";
+    assert_snapshot("golden/postman_collection.json", &rendered);
+}
+
+/// The parts of the Postman v2.1 collection schema an import relies on.
+#[test]
+fn the_collection_has_the_v2_1_structure() {
+    let collection = collection();
+    assert_eq!(collection["info"]["schema"], POSTMAN_SCHEMA);
+    assert!(collection["info"]["name"].is_string());
+    assert_eq!(
+        collection["variable"],
+        serde_json::json!([{"key": "base_url", "value": "http://localhost:3000"}])
+    );
+
+    let folders = collection["item"].as_array().unwrap();
+    let names: Vec<_> = folders
+        .iter()
+        .map(|f| f["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["users", "orders", "files"]);
+    let mut requests = 0;
+    for folder in folders {
+        for item in folder["item"].as_array().unwrap() {
+            requests += 1;
+            assert!(item["name"].is_string());
+            let request = &item["request"];
+            assert!(request["method"].is_string());
+            assert!(request["header"].is_array());
+            assert!(request["description"].is_string());
+            let url = &request["url"];
+            assert_eq!(url["host"], serde_json::json!(["{{base_url}}"]));
+            let path: Vec<_> = url["path"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|segment| segment.as_str().unwrap())
+                .collect();
+            assert_eq!(url["raw"], format!("{{{{base_url}}}}/{}", path.join("/")));
+            // Each `:name` segment has a variable, in path order.
+            let variables: Vec<_> = url["variable"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|variable| {
+                    assert_eq!(variable["value"], "");
+                    variable["key"].as_str().unwrap()
+                })
+                .collect();
+            let params: Vec<_> = path.iter().filter_map(|s| s.strip_prefix(':')).collect();
+            assert_eq!(variables, params);
+        }
+    }
+    assert_eq!(requests, 6);
+}
@@ -0,0 +1,125 @@
+use generic_http_router::error::RouterError;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Method, Request, StatusCode};
+
+/// Answers with its name and the controller the request was routed to.
+struct Dispatcher(&'static str);
+
+impl HttpHandler for Dispatcher {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok(format!(
+            "{} {}",
+            self.0,
+            req.route_controller().unwrap_or("none")
+        ))
+    }
+}
+
+fn router() -> Router {
+    Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/admin/users", "description": "", "controller": "admin::users::list"},
+            {"method": "DELETE", "path": "/admin/users/:id", "description": "", "controller": "admin::users::delete"},
+            {"method": "GET", "path": "/admin/audit", "description": "", "controller": "admin::audit::export"},
+            {"method": "GET", "path": "/admin/billing", "description": "", "controller": "admin::billing::report"},
+            {"method": "GET", "path": "/public", "description": "", "controller": "public::index"}
+        ]}"#,
+    )
+    .unwrap()
+}
+
+fn send(router: &Router, method: Method, path: &str) -> (StatusCode, String) {
+    let response = router.route(
+        Request::builder()
+            .method(method)
+            .uri(path)
+            .body(String::new())
+            .unwrap(),
+    );
+    let body = String::from_utf8(response.body().to_vec()).unwrap();
+    (response.status(), body)
+}
+
+#[test]
+fn a_prefix_covers_every_controller_starting_with_it() {
+    let mut router = router();
+    router.register_prefix("admin::", Dispatcher("admin"));
+    for (method, path, controller) in [
+        (Method::GET, "/admin/users", "admin::users::list"),
+        (Method::DELETE, "/admin/users/7", "admin::users::delete"),
+        (Method::GET, "/admin/audit", "admin::audit::export"),
+    ] {
+        assert_eq!(
+            send(&router, method, path),
+            (StatusCode::OK, format!("admin {controller}"))
+        );
+    }
+    // Other controllers aren't covered.
+    assert_eq!(
+        send(&router, Method::GET, "/public").0,
+        StatusCode::NOT_IMPLEMENTED
+    );
+}
+
+#[test]
+fn an_exact_registration_beats_a_prefix() {
+    let mut router = router();
+    router.register_handler("admin::audit::export", Dispatcher("exact"));
+    router.register_prefix("admin::", Dispatcher("admin"));
+    assert_eq!(
+        send(&router, Method::GET, "/admin/audit").1,
+        "exact admin::audit::export"
+    );
+    assert_eq!(
+        send(&router, Method::GET, "/admin/users").1,
+        "admin admin::users::list"
+    );
+}
+
+#[test]
+fn the_longest_prefix_wins() {
+    let mut router = router();
+    router.register_prefix("admin::users::", Dispatcher("users"));
+    router.register_prefix("admin::", Dispatcher("admin"));
+    assert_eq!(
+        send(&router, Method::GET, "/admin/users").1,
+        "users admin::users::list"
+    );
+    assert_eq!(
+        send(&router, Method::GET, "/admin/billing").1,
+        "admin admin::billing::report"
+    );
+
+    // Registering a prefix again replaces its handler.
+    router.register_prefix("admin::", Dispatcher("replaced"));
+    assert_eq!(
+        send(&router, Method::GET, "/admin/billing").1,
+        "replaced admin::billing::report"
+    );
+    assert_eq!(
+        send(&router, Method::GET, "/admin/users").1,
+        "users admin::users::list"
+    );
+}
+
+#[test]
+fn validation_counts_prefix_covered_controllers() {
+    let mut router = router();
+    router.register_handler("public::index", Dispatcher("public"));
+    assert!(matches!(
+        router.validate_handlers(),
+        Err(RouterError::MissingHandler { controller, .. }) if controller.starts_with("admin::")
+    ));
+
+    router.register_prefix("admin::users::", Dispatcher("users"));
+    match router.validate_handlers() {
+        Err(RouterError::MissingHandler { controller, .. }) => assert!(
+            ["admin::audit::export", "admin::billing::report"].contains(&controller.as_str()),
+            "{controller}"
+        ),
+        other => panic!("{other:?}"),
+    }
+
+    router.register_prefix("admin::", Dispatcher("admin"));
+    assert!(router.validate_handlers().is_ok());
+}
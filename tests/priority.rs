@@ -0,0 +1,168 @@
+use generic_http_router::error::{LoadWarning, RouterError};
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Method, Request, StatusCode};
+use std::path::PathBuf;
+
+/// Answers with its name and the path's parameters.
+struct Named(&'static str);
+
+impl HttpHandler for Named {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let params: Vec<String> = req
+            .params
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect();
+        HttpResponse::ok(format!("{} {}", self.0, params.join(",")))
+    }
+}
+
+/// Writes a configuration with the endpoints `{method, path, controller,
+/// priority}` to a temp file named `name`, and returns its path.
+fn config(name: &str, endpoints: &[(&str, &str, &str, Option<i32>)]) -> PathBuf {
+    let endpoints: Vec<_> = endpoints
+        .iter()
+        .map(|&(method, path, controller, priority)| {
+            let mut endpoint = serde_json::json!({
+                "method": method, "path": path, "description": "", "controller": controller
+            });
+            if let Some(priority) = priority {
+                endpoint["priority"] = priority.into();
+            }
+            endpoint
+        })
+        .collect();
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("priority_{name}.json"));
+    std::fs::write(
+        &path,
+        serde_json::json!({ "endpoints": endpoints }).to_string(),
+    )
+    .unwrap();
+    path
+}
+
+fn get(router: &Router, path: &str) -> (StatusCode, String) {
+    let response = router.route(Request::get(path).body(String::new()).unwrap());
+    let body = String::from_utf8(response.body().to_vec()).unwrap();
+    (response.status(), body)
+}
+
+#[test]
+fn the_higher_priority_route_wins_and_the_other_is_reported() {
+    let path = config(
+        "ordered",
+        &[
+            ("GET", "/files/:name", "by_name", Some(1)),
+            ("GET", "/files/:id", "by_id", Some(10)),
+            ("GET", "/files/latest", "latest", None),
+        ],
+    );
+    let (mut router, warnings) = Router::new_with_report(&path).unwrap();
+    assert_eq!(
+        warnings,
+        [LoadWarning::Shadowed {
+            method: Method::GET,
+            path: "/files/:name".to_string(),
+            by: "/files/:id".to_string(),
+        }]
+    );
+    router.register_handler("by_id", Named("by_id"));
+    router.register_handler("by_name", Named("by_name"));
+    router.register_handler("latest", Named("latest"));
+    assert_eq!(
+        get(&router, "/files/7"),
+        (StatusCode::OK, "by_id id=7".into())
+    );
+    // The literal doesn't conflict with the parameter, and is kept.
+    assert_eq!(
+        get(&router, "/files/latest"),
+        (StatusCode::OK, "latest ".into())
+    );
+    // The shadowed route is left out of the routes.
+    let patterns: Vec<_> = router.routes().map(|e| e.path.as_str()).collect();
+    assert_eq!(patterns, ["/files/:id", "/files/latest"]);
+
+    // `Router::new` loads the same routes, without the report.
+    let router = Router::new(&path).unwrap();
+    assert_eq!(router.routes().count(), 2);
+}
+
+#[test]
+fn a_route_shadowed_by_several_is_reported_once() {
+    let path = config(
+        "several",
+        &[
+            ("GET", "/a/:x", "first", Some(5)),
+            ("GET", "/a/:y", "second", Some(3)),
+            ("GET", "/a/:z", "third", Some(1)),
+            // Another method doesn't conflict.
+            ("POST", "/a/:x", "post", None),
+        ],
+    );
+    let (_, warnings) = Router::new_with_report(path).unwrap();
+    let shadowed: Vec<_> = warnings
+        .iter()
+        .map(|warning| match warning {
+            LoadWarning::Shadowed { method, path, by } => {
+                (method.clone(), path.as_str(), by.as_str())
+            }
+            other => panic!("{other:?}"),
+        })
+        .collect();
+    assert_eq!(
+        shadowed,
+        [
+            (Method::GET, "/a/:y", "/a/:x"),
+            (Method::GET, "/a/:z", "/a/:x"),
+        ]
+    );
+}
+
+#[test]
+fn conflicts_without_priorities_are_errors() {
+    for (name, priorities) in [
+        ("neither", [None, None]),
+        ("first_only", [Some(5), None]),
+        ("second_only", [None, Some(5)]),
+    ] {
+        let path = config(
+            name,
+            &[
+                ("GET", "/users/:id", "by_id", priorities[0]),
+                ("GET", "/users/:name", "by_name", priorities[1]),
+            ],
+        );
+        match Router::new_with_report(path) {
+            Err(RouterError::Route {
+                method,
+                conflicts_with,
+                ..
+            }) => {
+                assert_eq!(method, Method::GET, "{name}");
+                assert!(conflicts_with.is_some(), "{name}");
+            }
+            other => panic!("{name}: {:?}", other.map(|(_, warnings)| warnings)),
+        }
+    }
+}
+
+#[test]
+fn equal_priorities_are_errors() {
+    let path = config(
+        "equal",
+        &[
+            ("GET", "/users/:id", "by_id", Some(3)),
+            ("GET", "/users/:name", "by_name", Some(3)),
+        ],
+    );
+    let error = Router::new_with_report(path).err().unwrap();
+    assert!(
+        matches!(
+            &error,
+            RouterError::Route { path, conflicts_with: Some(with), .. }
+                if path == "/users/:name" && with == "/users/:id"
+        ),
+        "{error}"
+    );
+    assert_eq!(error.code(), "route.conflict");
+}
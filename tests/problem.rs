@@ -0,0 +1,156 @@
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Problem, Router};
+use http::{Request, Response, StatusCode};
+use serde_json::{Value, json};
+
+struct Echo;
+
+impl HttpHandler for Echo {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok(req.body)
+    }
+}
+
+/// A router with problem responses, `GET /items`, `POST /items` limited to
+/// 8 bytes of body, and `PUT /orphan` whose controller has no handler.
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/items", "description": "", "controller": "echo"},
+            {"method": "POST", "path": "/items", "description": "", "controller": "echo",
+             "max_request_size": 8},
+            {"method": "PUT", "path": "/orphan", "description": "", "controller": "missing"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("echo", Echo);
+    router.use_problem_responses(true);
+    router
+}
+
+fn send(router: &Router, method: &str, uri: &str, body: &str) -> Response<bytes::Bytes> {
+    router.route(
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(body.to_string())
+            .unwrap(),
+    )
+}
+
+/// Checks that `response` is a problem document and returns it.
+fn problem(response: &Response<bytes::Bytes>) -> Value {
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/problem+json"
+    );
+    let document: Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(document["type"], "about:blank");
+    assert_eq!(document["status"], response.status().as_u16());
+    assert_eq!(
+        document["title"],
+        response.status().canonical_reason().unwrap()
+    );
+    document
+}
+
+#[test]
+fn problem_documents_have_the_rfc_7807_shape() {
+    let response = HttpResponse::problem(
+        Problem::new(StatusCode::CONFLICT)
+            .with_type("https://example.com/problems/out-of-stock")
+            .with_title("Out of stock")
+            .with_detail("Item 7 is out of stock.")
+            .with_instance("/orders/42")
+            .with_extension("item", 7),
+    );
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.headers["content-type"], "application/problem+json");
+    let document: Value = serde_json::from_slice(response.body.as_bytes().unwrap()).unwrap();
+    assert_eq!(
+        document,
+        json!({
+            "type": "https://example.com/problems/out-of-stock",
+            "title": "Out of stock",
+            "status": 409,
+            "detail": "Item 7 is out of stock.",
+            "instance": "/orders/42",
+            "item": 7
+        })
+    );
+
+    // Unset members are left out, and a missing status is a 500.
+    let mut bare = Problem::new(StatusCode::NOT_FOUND);
+    bare.status = None;
+    bare.title = None;
+    let response = HttpResponse::problem(bare);
+    assert_eq!(response.status, StatusCode::INTERNAL_SERVER_ERROR);
+    let document: Value = serde_json::from_slice(response.body.as_bytes().unwrap()).unwrap();
+    assert_eq!(document, json!({"type": "about:blank"}));
+}
+
+#[test]
+fn not_found_is_a_problem() {
+    let response = send(&router(), "GET", "/nowhere", "");
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let document = problem(&response);
+    assert!(document.get("allowed").is_none());
+}
+
+#[test]
+fn method_not_allowed_lists_the_allowed_methods() {
+    let response = send(&router(), "DELETE", "/items", "");
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers()["allow"], "GET, HEAD, POST");
+    let document = problem(&response);
+    assert_eq!(document["allowed"], json!(["GET", "HEAD", "POST"]));
+}
+
+#[test]
+fn missing_handler_is_a_problem() {
+    let response = send(&router(), "PUT", "/orphan", "");
+    assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+    let document = problem(&response);
+    assert_eq!(
+        document["detail"],
+        "No handler is registered for this route."
+    );
+}
+
+#[test]
+fn too_large_body_is_a_problem() {
+    let router = router();
+    let response = send(&router, "POST", "/items", "123456789");
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let document = problem(&response);
+    assert_eq!(
+        document["detail"],
+        "The request body is larger than 8 bytes."
+    );
+
+    // A body within the limit reaches the handler.
+    let response = send(&router, "POST", "/items", "12345678");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), "12345678");
+}
+
+#[test]
+fn without_problem_responses_errors_are_plain() {
+    let mut router = router();
+    router.use_problem_responses(false);
+    for (method, uri, status) in [
+        ("GET", "/nowhere", StatusCode::NOT_FOUND),
+        ("DELETE", "/items", StatusCode::METHOD_NOT_ALLOWED),
+        ("PUT", "/orphan", StatusCode::NOT_IMPLEMENTED),
+    ] {
+        let response = send(&router, method, uri, "");
+        assert_eq!(response.status(), status);
+        assert_ne!(
+            response
+                .headers()
+                .get("content-type")
+                .map(|value| value.to_str().unwrap()),
+            Some("application/problem+json"),
+            "{method} {uri}"
+        );
+    }
+}
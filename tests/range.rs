@@ -0,0 +1,88 @@
+use generic_http_router::error::RangeError;
+use generic_http_router::range::ByteRange;
+use generic_http_router::{HttpRequest, HttpResponse};
+
+fn range(header: &str, len: u64) -> Result<Vec<(u64, u64)>, RangeError> {
+    let req = HttpRequest::builder()
+        .header("range", header)
+        .build()
+        .unwrap();
+    req.range(len)
+        .unwrap()
+        .map(|ranges| ranges.iter().map(|r| (r.start, r.end)).collect())
+}
+
+#[test]
+fn no_header() {
+    assert_eq!(HttpRequest::builder().build().unwrap().range(100), None);
+}
+
+#[test]
+fn single_ranges() {
+    assert_eq!(range("bytes=0-9", 100), Ok(vec![(0, 9)]));
+    assert_eq!(range("bytes=90-", 100), Ok(vec![(90, 99)]));
+    assert_eq!(range("bytes=-10", 100), Ok(vec![(90, 99)]));
+    assert_eq!(range("bytes=-500", 100), Ok(vec![(0, 99)]));
+    assert_eq!(range("bytes=50-500", 100), Ok(vec![(50, 99)]));
+    assert_eq!(range("Bytes = 0-0", 100), Ok(vec![(0, 0)]));
+}
+
+#[test]
+fn multiple_ranges_keep_their_order() {
+    assert_eq!(
+        range("bytes=50-59, 0-9,,-5", 100),
+        Ok(vec![(50, 59), (0, 9), (95, 99)])
+    );
+    assert_eq!(range("bytes=0-9,10-19", 100), Ok(vec![(0, 9), (10, 19)]));
+}
+
+#[test]
+fn unsatisfiable_ranges_are_dropped() {
+    assert_eq!(range("bytes=0-9,200-300", 100), Ok(vec![(0, 9)]));
+    assert_eq!(range("bytes=100-", 100), Err(RangeError::Unsatisfiable));
+    assert_eq!(range("bytes=-0", 100), Err(RangeError::Unsatisfiable));
+    assert_eq!(range("bytes=-10", 0), Err(RangeError::Unsatisfiable));
+    assert_eq!(range("bytes=0-", 0), Err(RangeError::Unsatisfiable));
+}
+
+#[test]
+fn invalid_headers() {
+    assert_eq!(
+        range("items=0-9", 100),
+        Err(RangeError::UnsupportedUnit("items".to_string()))
+    );
+    for malformed in [
+        "bytes",
+        "bytes=",
+        "bytes=,",
+        "bytes=5-3",
+        "bytes=a-",
+        "bytes=+1-2",
+        "bytes=1",
+        "bytes=--1",
+        "bytes=0-99999999999999999999999",
+    ] {
+        assert_eq!(
+            range(malformed, 100),
+            Err(RangeError::Malformed),
+            "{malformed}"
+        );
+    }
+    assert_eq!(
+        range("bytes=0-99,50-149", 200),
+        Err(RangeError::Overlapping)
+    );
+    assert_eq!(range("bytes=50-60,-60", 100), Err(RangeError::Overlapping));
+}
+
+#[test]
+fn content_range() {
+    let range = ByteRange {
+        start: 0,
+        end: 1023,
+    };
+    assert_eq!(range.len(), 1024);
+    let mut response = HttpResponse::ok("");
+    response.content_range(range, 4096);
+    assert_eq!(response.headers["content-range"], "bytes 0-1023/4096");
+}
@@ -0,0 +1,267 @@
+#![cfg(feature = "record")]
+
+use generic_http_router::record::{JsonFileSink, RecordedBody, Recording};
+use generic_http_router::testing::{Difference, ReplayOptions, replay, replay_with};
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Request, StatusCode};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Answers with its greeting and the `id` parameter.
+struct Users(&'static str);
+
+impl HttpHandler for Users {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let id = req.params.get("id").unwrap_or("-");
+        let mut response = HttpResponse::ok(format!("{} {id}", self.0));
+        response.add_header("x-version", self.0);
+        response
+    }
+}
+
+/// Answers with a 201 and the length of the body it's sent.
+struct Create;
+
+impl HttpHandler for Create {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        HttpResponse::new(StatusCode::CREATED, format!("{} bytes", req.body.len()))
+    }
+}
+
+fn router_with(greeting: &'static str) -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/users/:id", "description": "", "controller": "users::get"},
+            {"method": "POST", "path": "/users", "description": "", "controller": "users::create"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("users::get", Users(greeting));
+    router.register_handler("users::create", Create);
+    router
+}
+
+/// Records the requests `router` routes in memory.
+fn record_in_memory(router: &mut Router) -> Arc<Mutex<Vec<Recording>>> {
+    let recordings = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&recordings);
+    router.enable_recording(Box::new(move |recording: &Recording| {
+        sink.lock().unwrap().push(recording.clone());
+    }));
+    recordings
+}
+
+/// Returns a path in the temp directory for the recording `name`, removing
+/// any previous one.
+fn recording_path(name: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("record_{name}.ndjson"));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// Routes a `GET` and a `POST` with `router`.
+fn send_two(router: &Router) {
+    router.route(
+        Request::get("/users/7?page=2")
+            .header("accept", "text/plain")
+            .header("authorization", "Bearer secret")
+            .body(String::new())
+            .unwrap(),
+    );
+    router.route(
+        Request::post("/users")
+            .header("content-type", "application/json")
+            .body(r#"{"name": "Ada"}"#.to_string())
+            .unwrap(),
+    );
+}
+
+/// Records two requests to a JSON file sink at `name`, with the router
+/// greeting with "hello".
+fn record_two(name: &str) -> PathBuf {
+    let path = recording_path(name);
+    let mut router = router_with("hello");
+    router.enable_recording(Box::new(JsonFileSink::create(&path).unwrap()));
+    send_two(&router);
+    path
+}
+
+#[test]
+fn requests_and_responses_are_recorded() {
+    let mut router = router_with("hello");
+    let recordings = record_in_memory(&mut router);
+    send_two(&router);
+    router.route(Request::get("/nowhere").body(vec![0xff, 0xfe]).unwrap());
+
+    let recordings = recordings.lock().unwrap();
+    assert_eq!(recordings.len(), 3);
+    let get = &recordings[0];
+    assert_eq!(get.method, "GET");
+    assert_eq!(get.uri, "/users/7?page=2");
+    assert_eq!(
+        get.request_headers,
+        [
+            ("accept".to_string(), "text/plain".to_string()),
+            ("authorization".to_string(), "<redacted>".to_string()),
+        ]
+    );
+    assert_eq!(get.request_body, RecordedBody::Text(String::new()));
+    assert_eq!(get.controller.as_deref(), Some("users::get"));
+    assert_eq!(get.status, 200);
+    assert!(
+        get.response_headers
+            .contains(&("x-version".to_string(), "hello".to_string()))
+    );
+    assert_eq!(get.response_body, RecordedBody::Text("hello 7".to_string()));
+    assert!(get.duration_ms >= 0.0);
+
+    let post = &recordings[1];
+    assert_eq!(
+        (post.method.as_str(), post.status),
+        ("POST", StatusCode::CREATED.as_u16())
+    );
+    assert_eq!(
+        post.request_body,
+        RecordedBody::Text(r#"{"name": "Ada"}"#.to_string())
+    );
+    assert_eq!(post.controller.as_deref(), Some("users::create"));
+    assert_eq!(
+        post.response_body,
+        RecordedBody::Text("15 bytes".to_string())
+    );
+
+    // A request without a route, with a binary body.
+    let unrouted = &recordings[2];
+    assert_eq!(unrouted.controller, None);
+    assert_eq!(unrouted.status, 404);
+    assert_eq!(
+        unrouted.request_body,
+        RecordedBody::Binary {
+            hex: "fffe".to_string()
+        }
+    );
+}
+
+#[test]
+fn recorded_bodies_round_trip() {
+    for bytes in [&b""[..], b"text", &[0, 0xff, 0x10]] {
+        assert_eq!(
+            RecordedBody::new(bytes).to_bytes().unwrap(),
+            bytes,
+            "{bytes:?}"
+        );
+    }
+    for hex in ["f", "zz"] {
+        let body = RecordedBody::Binary {
+            hex: hex.to_string(),
+        };
+        assert_eq!(body.to_bytes(), None, "{hex}");
+    }
+}
+
+#[test]
+fn the_file_sink_writes_a_line_per_request() {
+    let path = record_two("lines");
+    let recordings = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<Recording> = recordings
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].uri, "/users/7?page=2");
+    assert_eq!(lines[1].method, "POST");
+    // Secrets never reach the file.
+    assert!(!recordings.contains("secret"), "{recordings}");
+
+    // The file is appended to.
+    let mut router = router_with("hello");
+    router.enable_recording(Box::new(JsonFileSink::create(&path).unwrap()));
+    send_two(&router);
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 4);
+}
+
+#[test]
+fn replaying_against_an_unchanged_router_passes() {
+    let path = record_two("unchanged");
+    let report = replay(&router_with("hello"), &path).unwrap();
+    assert_eq!(report.replayed, 2);
+    assert_eq!(report.passed(), 2);
+    assert!(report.is_success(), "{:#?}", report.failures);
+
+    // With the headers compared too.
+    let options = ReplayOptions {
+        compare_headers: true,
+        ..ReplayOptions::default()
+    };
+    let report = replay_with(&router_with("hello"), &path, &options).unwrap();
+    assert!(report.is_success(), "{:#?}", report.failures);
+}
+
+#[test]
+fn replaying_against_a_modified_handler_reports_the_differences() {
+    let path = record_two("modified");
+    let router = router_with("hi");
+    let report = replay(&router, &path).unwrap();
+    assert_eq!(report.replayed, 2);
+    assert_eq!(report.passed(), 1);
+    assert_eq!(report.failures.len(), 1);
+    let failure = &report.failures[0];
+    assert_eq!(
+        (failure.line, failure.method.as_str(), failure.uri.as_str()),
+        (1, "GET", "/users/7?page=2")
+    );
+    // The headers aren't compared by default.
+    assert_eq!(
+        failure.differences,
+        [Difference::Body {
+            expected: RecordedBody::Text("hello 7".to_string()),
+            actual: RecordedBody::Text("hi 7".to_string()),
+        }]
+    );
+
+    let options = ReplayOptions {
+        compare_headers: true,
+        compare_body: false,
+        ..ReplayOptions::default()
+    };
+    let report = replay_with(&router, &path, &options).unwrap();
+    assert_eq!(
+        report.failures[0].differences,
+        [Difference::Header {
+            name: "x-version".to_string(),
+            expected: vec!["hello".to_string()],
+            actual: vec!["hi".to_string()],
+        }]
+    );
+    // Ignored headers aren't compared.
+    let options = ReplayOptions {
+        ignored_headers: vec![http::HeaderName::from_static("x-version")],
+        ..options
+    };
+    assert!(replay_with(&router, &path, &options).unwrap().is_success());
+}
+
+#[test]
+fn replaying_reports_a_different_status() {
+    let path = record_two("status");
+    let mut router = router_with("hello");
+    router.register_handler("users::create", Users("hello"));
+    let report = replay(&router, &path).unwrap();
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(report.failures[0].line, 2);
+    assert_eq!(
+        report.failures[0].differences[0],
+        Difference::Status {
+            expected: 201,
+            actual: 200
+        }
+    );
+}
+
+#[test]
+fn a_malformed_recording_is_an_error() {
+    let path = recording_path("malformed");
+    std::fs::write(&path, "{\"method\": \"GET\"}\n").unwrap();
+    assert!(replay(&router_with("hello"), &path).is_err());
+    assert!(replay(&router_with("hello"), recording_path("nowhere")).is_err());
+}
@@ -0,0 +1,139 @@
+use generic_http_router::redact::{self, DebugOptions};
+use generic_http_router::{HttpRequest, HttpResponse};
+use http::StatusCode;
+use http::header::{HeaderName, HeaderValue, SET_COOKIE};
+use std::sync::{Mutex, MutexGuard};
+
+/// The debug options are process-wide: tests that print with them, or change
+/// them, take turns.
+static OPTIONS: Mutex<()> = Mutex::new(());
+
+fn options_lock() -> MutexGuard<'static, ()> {
+    OPTIONS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn request() -> HttpRequest {
+    HttpRequest::builder()
+        .method("POST")
+        .uri("/login?token=abc123&page=2")
+        .header("authorization", "Bearer secret-token")
+        .header("cookie", "sid=secret-session")
+        .header("x-api-key", "secret-key")
+        .header("x-request-id", "req-7")
+        .body(vec![b'a'; 200])
+        .build()
+        .unwrap()
+}
+
+fn response() -> HttpResponse {
+    let mut response = HttpResponse::new(StatusCode::OK, vec![b'b'; 200]);
+    response
+        .headers
+        .append(SET_COOKIE, HeaderValue::from_static("sid=secret-1"));
+    response
+        .headers
+        .append(SET_COOKIE, HeaderValue::from_static("csrf=secret-2"));
+    response
+        .headers
+        .append("x-request-id", HeaderValue::from_static("req-7"));
+    response
+}
+
+#[test]
+fn default_options() {
+    let _lock = options_lock();
+    assert_eq!(
+        DebugOptions::default().redacted_headers,
+        ["authorization", "cookie", "set-cookie", "x-api-key"].map(HeaderName::from_static)
+    );
+    assert_eq!(DebugOptions::default().body_preview_len, 128);
+
+    let debug = format!("{:?}", request());
+    for secret in ["secret-token", "secret-session", "secret-key"] {
+        assert!(!debug.contains(secret), "{debug}");
+    }
+    assert!(
+        debug.contains(r#""authorization": "<redacted>""#),
+        "{debug}"
+    );
+    assert!(debug.contains(r#""cookie": "<redacted>""#), "{debug}");
+    assert!(debug.contains(r#""x-api-key": "<redacted>""#), "{debug}");
+    assert!(debug.contains(r#""x-request-id": "req-7""#), "{debug}");
+    // No query parameters are redacted by default.
+    assert!(debug.contains("token=abc123&page=2"), "{debug}");
+    assert!(debug.contains(&format!("{}\"... (200 bytes total)", "a".repeat(128))));
+    assert!(!debug.contains(&"a".repeat(129)), "{debug}");
+
+    let debug = format!("{:?}", response());
+    assert!(
+        !debug.contains("secret-1") && !debug.contains("secret-2"),
+        "{debug}"
+    );
+    // Each `Set-Cookie` value is redacted.
+    assert_eq!(debug.matches(r#""set-cookie": "<redacted>""#).count(), 2);
+    assert!(debug.contains(r#""x-request-id": "req-7""#), "{debug}");
+    assert!(debug.contains("(200 bytes total)"), "{debug}");
+}
+
+#[test]
+fn debug_full_prints_everything() {
+    let _lock = options_lock();
+    let debug = format!("{:?}", request().debug_full());
+    for secret in ["secret-token", "secret-session", "secret-key", "abc123"] {
+        assert!(debug.contains(secret), "{debug}");
+    }
+    assert!(debug.contains(&"a".repeat(200)), "{debug}");
+    assert!(!debug.contains("<redacted>") && !debug.contains("bytes total"));
+
+    let debug = format!("{:?}", response().debug_full());
+    assert!(
+        debug.contains("secret-1") && debug.contains("secret-2"),
+        "{debug}"
+    );
+    assert!(debug.contains(&"b".repeat(200)), "{debug}");
+}
+
+#[test]
+fn custom_options() {
+    let _lock = options_lock();
+    redact::set_debug_options(DebugOptions {
+        redacted_headers: vec![HeaderName::from_static("x-request-id")],
+        redacted_query_params: vec!["token".to_string()],
+        body_preview_len: 4,
+    });
+    let request = format!("{:?}", request());
+    let response = format!("{:?}", response());
+    redact::set_debug_options(DebugOptions::default());
+
+    assert!(
+        request.contains(r#""x-request-id": "<redacted>""#),
+        "{request}"
+    );
+    // Only the configured headers are redacted.
+    assert!(request.contains("secret-token"), "{request}");
+    assert!(request.contains("token=<redacted>&page=2"), "{request}");
+    assert!(!request.contains("abc123"), "{request}");
+    assert!(
+        request.contains(r#"b"aaaa"... (200 bytes total)"#),
+        "{request}"
+    );
+
+    assert!(
+        response.contains(r#""x-request-id": "<redacted>""#),
+        "{response}"
+    );
+    assert!(response.contains("secret-1"), "{response}");
+    assert!(
+        response.contains(r#"b"bbbb"... (200 bytes total)"#),
+        "{response}"
+    );
+}
+
+#[test]
+fn short_bodies_are_printed_whole() {
+    let _lock = options_lock();
+    let req = HttpRequest::builder().body("short").build().unwrap();
+    let debug = format!("{req:?}");
+    assert!(debug.contains(r#"b"short""#), "{debug}");
+    assert!(!debug.contains("bytes total"), "{debug}");
+}
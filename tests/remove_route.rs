@@ -0,0 +1,146 @@
+use generic_http_router::config::Endpoint;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Method, Request, StatusCode};
+
+/// Answers with its name.
+struct Named(&'static str);
+
+impl HttpHandler for Named {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok(self.0)
+    }
+}
+
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/users/:id", "description": "", "controller": "get"},
+            {"method": "DELETE", "path": "/users/:id", "description": "", "controller": "delete"},
+            {"method": "GET", "path": "/items", "description": "", "controller": "items"},
+            {"method": "PATCH", "path": "/*rest", "description": "", "controller": "patch"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("get", Named("get"));
+    router.register_handler("delete", Named("delete"));
+    router.register_handler("items", Named("items"));
+    router.register_handler("patch", Named("patch"));
+    router
+}
+
+/// The status, `Allow` header and body of the response to `method path`.
+fn send(router: &Router, method: Method, path: &str) -> (StatusCode, Option<String>, String) {
+    let response = router.route(
+        Request::builder()
+            .method(method)
+            .uri(path)
+            .body(String::new())
+            .unwrap(),
+    );
+    let allow = response
+        .headers()
+        .get("allow")
+        .map(|value| value.to_str().unwrap().to_string());
+    let body = String::from_utf8(response.body().to_vec()).unwrap();
+    (response.status(), allow, body)
+}
+
+#[test]
+fn a_removed_route_405s_while_other_methods_remain() {
+    let mut router = router();
+    assert!(router.remove_route(&Method::DELETE, "/users/:id"));
+    assert_eq!(
+        send(&router, Method::DELETE, "/users/7"),
+        (
+            StatusCode::METHOD_NOT_ALLOWED,
+            Some("GET, HEAD, PATCH".to_string()),
+            String::new()
+        )
+    );
+    assert_eq!(send(&router, Method::GET, "/users/7").2, "get");
+}
+
+#[test]
+fn a_path_without_routes_left_404s() {
+    let mut router = router();
+    assert!(router.remove_route(&Method::GET, "/items"));
+    // `PATCH /*rest` still matches every path, so `/items` 405s for the other
+    // methods; with it removed too, `/items` 404s.
+    assert_eq!(
+        send(&router, Method::GET, "/items").0,
+        StatusCode::METHOD_NOT_ALLOWED
+    );
+    assert!(router.remove_route(&Method::PATCH, "/*rest"));
+    assert_eq!(
+        send(&router, Method::GET, "/items").0,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        send(&router, Method::PATCH, "/items").0,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[test]
+fn removing_the_last_route_of_a_method_drops_it_from_every_allow() {
+    let mut router = router();
+    assert_eq!(
+        send(&router, Method::PUT, "/items").1.as_deref(),
+        Some("GET, HEAD, PATCH")
+    );
+    assert!(router.remove_route(&Method::PATCH, "/*rest"));
+    assert_eq!(
+        send(&router, Method::PUT, "/items").1.as_deref(),
+        Some("GET, HEAD")
+    );
+    assert_eq!(
+        send(&router, Method::PUT, "/users/7").1.as_deref(),
+        Some("DELETE, GET, HEAD")
+    );
+    assert_eq!(
+        send(&router, Method::PATCH, "/items").0,
+        StatusCode::METHOD_NOT_ALLOWED
+    );
+}
+
+#[test]
+fn only_an_exact_route_is_removed() {
+    let mut router = router();
+    // Another parameter name, another method, or a path instead of a pattern.
+    assert!(!router.remove_route(&Method::GET, "/users/:user"));
+    assert!(!router.remove_route(&Method::PUT, "/users/:id"));
+    assert!(!router.remove_route(&Method::GET, "/users/7"));
+    assert!(!router.remove_route(&Method::GET, "/nowhere"));
+    assert_eq!(send(&router, Method::GET, "/users/7").2, "get");
+
+    assert!(router.remove_route(&Method::GET, "/users/:id"));
+    assert!(!router.remove_route(&Method::GET, "/users/:id"));
+}
+
+#[test]
+fn a_removed_route_can_be_added_again() {
+    let mut router = router();
+    assert!(router.remove_route(&Method::GET, "/items"));
+    router
+        .add_route(Endpoint::new(Method::GET, "/items", "items"))
+        .unwrap();
+    assert_eq!(send(&router, Method::GET, "/items").2, "items");
+}
+
+#[test]
+fn an_unregistered_handler_501s_until_registered_again() {
+    let mut router = router();
+    let handler = router.unregister("get");
+    assert!(handler.is_some());
+    assert!(router.unregister("get").is_none());
+    assert!(router.unregister("nobody").is_none());
+    assert_eq!(
+        send(&router, Method::GET, "/users/7").0,
+        StatusCode::NOT_IMPLEMENTED
+    );
+    // The route's other methods are unaffected.
+    assert_eq!(send(&router, Method::DELETE, "/users/7").2, "delete");
+
+    router.register_handler("get", Named("get again"));
+    assert_eq!(send(&router, Method::GET, "/users/7").2, "get again");
+}
@@ -0,0 +1,122 @@
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Request, Response, StatusCode};
+use serde_json::{Value, json};
+
+struct Ok;
+
+impl HttpHandler for Ok {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok("handled")
+    }
+}
+
+/// Every route requires `X-Tenant-Id`, and `/internal/jobs` `X-Internal-Auth`
+/// too.
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"required_headers": ["X-Tenant-Id"], "endpoints": [
+            {"method": "GET", "path": "/users", "description": "", "controller": "ok"},
+            {"method": "GET", "path": "/internal/jobs", "description": "", "controller": "ok",
+             "required_headers": ["X-Internal-Auth", "x-tenant-id"]}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("ok", Ok);
+    router
+}
+
+fn get(router: &Router, path: &str, headers: &[&str]) -> Response<bytes::Bytes> {
+    let mut builder = Request::get(path);
+    for name in headers {
+        builder = builder.header(*name, "1");
+    }
+    router.route(builder.body(String::new()).unwrap())
+}
+
+fn problem(response: &Response<bytes::Bytes>) -> Value {
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/problem+json"
+    );
+    serde_json::from_slice(response.body()).unwrap()
+}
+
+#[test]
+fn requests_with_the_required_headers_reach_the_handler() {
+    let router = router();
+    assert_eq!(get(&router, "/users", &["X-Tenant-Id"]).body(), "handled");
+    // Names are matched ignoring case.
+    assert_eq!(
+        get(
+            &router,
+            "/internal/jobs",
+            &["x-INTERNAL-auth", "X-TENANT-ID"]
+        )
+        .body(),
+        "handled"
+    );
+}
+
+#[test]
+fn only_the_missing_headers_are_reported() {
+    let router = router();
+    let response = get(&router, "/internal/jobs", &["X-Tenant-Id"]);
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = problem(&response);
+    assert_eq!(body["missing_headers"], json!(["x-internal-auth"]));
+    assert_eq!(
+        body["detail"],
+        "The request is missing the required headers: x-internal-auth."
+    );
+
+    // A header required globally and by the route is listed once.
+    let response = get(&router, "/internal/jobs", &[]);
+    assert_eq!(
+        problem(&response)["missing_headers"],
+        json!(["x-internal-auth", "x-tenant-id"])
+    );
+}
+
+#[test]
+fn route_requirements_add_to_the_global_ones() {
+    let router = router();
+    // The route's requirement doesn't replace the global one.
+    let response = get(&router, "/internal/jobs", &["X-Internal-Auth"]);
+    assert_eq!(
+        problem(&response)["missing_headers"],
+        json!(["x-tenant-id"])
+    );
+    // And applies to its route only.
+    let response = get(&router, "/users", &[]);
+    assert_eq!(
+        problem(&response)["missing_headers"],
+        json!(["x-tenant-id"])
+    );
+}
+
+#[test]
+fn the_status_is_configurable() {
+    let mut router = router();
+    router.set_required_header_status(StatusCode::FORBIDDEN);
+    let response = get(&router, "/users", &[]);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(problem(&response)["status"], 403);
+}
+
+#[test]
+fn invalid_names_fail_at_load_time() {
+    let error = Router::from_json_str(r#"{"required_headers": ["X Tenant"], "endpoints": []}"#)
+        .err()
+        .unwrap();
+    assert_eq!(error.code(), "config.header");
+
+    let error = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/users", "description": "", "controller": "ok",
+             "required_headers": ["X:Tenant"]}
+        ]}"#,
+    )
+    .err()
+    .unwrap();
+    assert!(error.to_string().contains("X:Tenant"), "{error}");
+}
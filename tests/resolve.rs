@@ -0,0 +1,131 @@
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Method, Request, Response, StatusCode};
+
+/// Answers with its method and the `id` parameter, if any.
+struct Echo;
+
+impl HttpHandler for Echo {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let id = req.params.get("id").unwrap_or("-");
+        HttpResponse::ok(format!("{} {id}", req.method))
+    }
+}
+
+/// A router with `GET`, `POST` and `DELETE` on `/users/:id`, `GET /items`
+/// and `PUT /orphan` whose controller has no handler.
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/users/:id", "description": "", "controller": "echo"},
+            {"method": "POST", "path": "/users/:id", "description": "", "controller": "echo"},
+            {"method": "DELETE", "path": "/users/:id", "description": "", "controller": "echo"},
+            {"method": "GET", "path": "/items", "description": "", "controller": "echo"},
+            {"method": "PUT", "path": "/orphan", "description": "", "controller": "missing"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("echo", Echo);
+    router.use_problem_responses(true);
+    router
+}
+
+const METHODS: [Method; 6] = [
+    Method::GET,
+    Method::POST,
+    Method::DELETE,
+    Method::PUT,
+    Method::HEAD,
+    Method::OPTIONS,
+];
+
+const PATHS: [&str; 4] = ["/users/7", "/items", "/orphan", "/nowhere"];
+
+fn request(method: &Method, path: &str) -> Request<String> {
+    Request::builder()
+        .method(method.clone())
+        .uri(path)
+        .body(String::new())
+        .unwrap()
+}
+
+/// The status, headers and body of a response, to compare responses by.
+fn parts<B: AsRef<[u8]>>(response: &Response<B>) -> (StatusCode, Vec<(String, String)>, Vec<u8>) {
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_str().unwrap().to_string()))
+        .collect();
+    (
+        response.status(),
+        headers,
+        response.body().as_ref().to_vec(),
+    )
+}
+
+#[test]
+fn resolve_then_dispatch_is_route() {
+    let router = router();
+    for method in &METHODS {
+        for path in PATHS {
+            let routed = router.route(request(method, path));
+            let req = request(method, path);
+            let resolution = router.resolve(&req);
+            let dispatched = router.dispatch(resolution, req);
+            assert_eq!(parts(&routed), parts(&dispatched), "{method} {path}");
+        }
+    }
+}
+
+#[test]
+fn route_streaming_is_route() {
+    let router = router();
+    for method in &METHODS {
+        for path in PATHS {
+            let routed = router.route(request(method, path));
+            let streamed = router.route_streaming(request(method, path));
+            let (head, body) = streamed.into_parts();
+            let body = body.collect(usize::MAX).unwrap();
+            let streamed = Response::from_parts(head, body);
+            assert_eq!(parts(&routed), parts(&streamed), "{method} {path}");
+        }
+    }
+}
+
+#[test]
+fn the_matrix_answers_as_expected() {
+    let router = router();
+    let status = |method: Method, path| router.route(request(&method, path)).status();
+
+    assert_eq!(status(Method::GET, "/users/7"), StatusCode::OK);
+    assert_eq!(status(Method::POST, "/users/7"), StatusCode::OK);
+    assert_eq!(status(Method::DELETE, "/users/7"), StatusCode::OK);
+    assert_eq!(status(Method::HEAD, "/users/7"), StatusCode::OK);
+    assert_eq!(
+        status(Method::PUT, "/users/7"),
+        StatusCode::METHOD_NOT_ALLOWED
+    );
+    assert_eq!(
+        status(Method::POST, "/items"),
+        StatusCode::METHOD_NOT_ALLOWED
+    );
+    assert_eq!(status(Method::PUT, "/orphan"), StatusCode::NOT_IMPLEMENTED);
+    assert_eq!(status(Method::GET, "/nowhere"), StatusCode::NOT_FOUND);
+
+    let response = router.route(request(&Method::PUT, "/users/7"));
+    assert_eq!(response.headers()["allow"], "DELETE, GET, HEAD, POST");
+    let response = router.route(request(&Method::HEAD, "/users/7"));
+    assert!(response.body().is_empty());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn route_async_is_route() {
+    let router = router();
+    for method in &METHODS {
+        for path in PATHS {
+            let routed = router.route(request(method, path));
+            let awaited = router.route_async(request(method, path)).await;
+            assert_eq!(parts(&routed), parts(&awaited), "{method} {path}");
+        }
+    }
+}
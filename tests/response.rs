@@ -0,0 +1,128 @@
+use generic_http_router::{HttpResponse, IntoHttpResponse};
+use http::header::{CONTENT_TYPE, ETAG, HeaderValue};
+use http::{HeaderMap, StatusCode};
+use serde_json::json;
+
+/// The status, `Content-Type` and body of a response.
+fn parts(response: HttpResponse) -> (StatusCode, Option<String>, Vec<u8>) {
+    let content_type = response
+        .headers
+        .get(CONTENT_TYPE)
+        .map(|value| value.to_str().unwrap().to_string());
+    let body = response.body.collect(usize::MAX).unwrap().to_vec();
+    (response.status, content_type, body)
+}
+
+fn text(status: StatusCode, body: &str) -> (StatusCode, Option<String>, Vec<u8>) {
+    (
+        status,
+        Some("text/plain; charset=utf-8".to_string()),
+        body.into(),
+    )
+}
+
+#[test]
+fn shorthand_constructors() {
+    assert_eq!(
+        parts(HttpResponse::ok("hi")),
+        (StatusCode::OK, None, b"hi".to_vec())
+    );
+    assert_eq!(
+        parts(HttpResponse::created("made")),
+        (StatusCode::CREATED, None, b"made".to_vec())
+    );
+    assert_eq!(
+        parts(HttpResponse::no_content()),
+        (StatusCode::NO_CONTENT, None, Vec::new())
+    );
+    assert_eq!(
+        parts(HttpResponse::bad_request("no name")),
+        text(StatusCode::BAD_REQUEST, "no name")
+    );
+    assert_eq!(
+        parts(HttpResponse::not_found()),
+        (StatusCode::NOT_FOUND, None, Vec::new())
+    );
+    assert_eq!(
+        parts(HttpResponse::internal_error("oops")),
+        text(StatusCode::INTERNAL_SERVER_ERROR, "oops")
+    );
+}
+
+#[test]
+fn http_response_is_returned_as_is() {
+    let mut response = HttpResponse::created("made");
+    response.add_header(ETAG, "\"1\"");
+    let converted = response.into_http_response();
+    assert_eq!(converted.status, StatusCode::CREATED);
+    assert_eq!(converted.headers.len(), 1);
+    assert_eq!(converted.headers[ETAG], "\"1\"");
+    assert_eq!(converted.body.as_bytes().unwrap(), "made");
+}
+
+#[test]
+fn status_code_is_an_empty_response() {
+    assert_eq!(
+        parts(StatusCode::ACCEPTED.into_http_response()),
+        (StatusCode::ACCEPTED, None, Vec::new())
+    );
+}
+
+#[test]
+fn strings_are_plain_text() {
+    assert_eq!(
+        parts("hello".to_string().into_http_response()),
+        text(StatusCode::OK, "hello")
+    );
+    assert_eq!(
+        parts("hello".into_http_response()),
+        text(StatusCode::OK, "hello")
+    );
+}
+
+#[test]
+fn bytes_are_octet_streams() {
+    assert_eq!(
+        parts(vec![0, 159, 255].into_http_response()),
+        (
+            StatusCode::OK,
+            Some("application/octet-stream".to_string()),
+            vec![0, 159, 255]
+        )
+    );
+}
+
+#[test]
+fn json_values_are_json() {
+    let (status, content_type, body) = parts(json!({"id": 7, "tags": ["a"]}).into_http_response());
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type.as_deref(), Some("application/json"));
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+        json!({"id": 7, "tags": ["a"]})
+    );
+}
+
+#[test]
+fn status_and_string_is_plain_text_with_that_status() {
+    assert_eq!(
+        parts((StatusCode::CONFLICT, "taken".to_string()).into_http_response()),
+        text(StatusCode::CONFLICT, "taken")
+    );
+}
+
+#[test]
+fn status_headers_and_body_are_kept_exactly() {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("image/png"));
+    headers.append("x-tag", HeaderValue::from_static("a"));
+    headers.append("x-tag", HeaderValue::from_static("b"));
+    let response = (StatusCode::IM_A_TEAPOT, headers.clone(), b"png".to_vec()).into_http_response();
+    assert_eq!(response.status, StatusCode::IM_A_TEAPOT);
+    assert_eq!(response.headers, headers);
+    assert_eq!(response.body.as_bytes().unwrap(), "png");
+
+    // Without headers, nothing is added.
+    let response = (StatusCode::OK, HeaderMap::new(), Vec::new()).into_http_response();
+    assert!(response.headers.is_empty());
+}
@@ -0,0 +1,150 @@
+use bytes::Bytes;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, RequestSummary, Router};
+use http::{Request, Response, StatusCode};
+use std::sync::{Arc, Mutex};
+
+/// Answers with a body of `len` bytes.
+struct Sized(usize);
+
+impl HttpHandler for Sized {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok("x".repeat(self.0))
+    }
+}
+
+/// Streams `chunks` chunks of 4 bytes.
+struct Streamed(usize);
+
+impl HttpHandler for Streamed {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::streaming(
+            StatusCode::OK,
+            (0..self.0).map(|_| Ok(Bytes::from_static(b"abcd"))),
+        )
+    }
+}
+
+/// A router capping responses at 10 bytes, with routes answering 9, 10 and
+/// 11 bytes, a `/large` route with a limit of its own, and streams of 8 and
+/// 12 bytes, recording the summaries of the requests it routes.
+fn router() -> (Router, Arc<Mutex<Vec<RequestSummary>>>) {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/under", "description": "", "controller": "9"},
+            {"method": "GET", "path": "/at", "description": "", "controller": "10"},
+            {"method": "GET", "path": "/over", "description": "", "controller": "11"},
+            {"method": "GET", "path": "/large", "description": "", "controller": "11",
+             "max_response_size": 20},
+            {"method": "GET", "path": "/stream/short", "description": "", "controller": "stream-8"},
+            {"method": "GET", "path": "/stream/long", "description": "", "controller": "stream-12"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("9", Sized(9));
+    router.register_handler("10", Sized(10));
+    router.register_handler("11", Sized(11));
+    router.register_handler("stream-8", Streamed(2));
+    router.register_handler("stream-12", Streamed(3));
+    router.use_problem_responses(true);
+    router.set_max_response_size(Some(10));
+    let summaries = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&summaries);
+    router.on_response(move |summary| sink.lock().unwrap().push(summary.clone()));
+    (router, summaries)
+}
+
+fn get(router: &Router, path: &str) -> Response<Bytes> {
+    router.route(Request::get(path).body(String::new()).unwrap())
+}
+
+/// Checks `response` is the 500 replacing a body over `limit` bytes.
+fn assert_oversized(response: &Response<Bytes>, limit: usize) {
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/problem+json"
+    );
+    let problem: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(
+        problem["detail"],
+        format!("The response body is larger than the limit of {limit} bytes.")
+    );
+}
+
+#[test]
+fn bodies_up_to_the_cap_are_sent() {
+    let (router, summaries) = router();
+    assert_eq!(get(&router, "/under").body().len(), 9);
+    assert_eq!(get(&router, "/at").body().len(), 10);
+    assert!(
+        summaries
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|summary| summary.status == StatusCode::OK && summary.detail.is_none())
+    );
+}
+
+#[test]
+fn bodies_over_the_cap_are_replaced() {
+    let (router, summaries) = router();
+    assert_oversized(&get(&router, "/over"), 10);
+    let summary = summaries.lock().unwrap().pop().unwrap();
+    assert_eq!(summary.status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(
+        summary.detail.as_deref(),
+        Some("The response body is larger than the limit of 10 bytes.")
+    );
+    assert_eq!(summary.pattern.as_deref(), Some("/over"));
+
+    // A route's own limit replaces the router's.
+    assert_eq!(get(&router, "/large").status(), StatusCode::OK);
+}
+
+#[test]
+fn the_cap_is_checked_after_the_transformers() {
+    let (mut router, _) = router();
+    router.add_response_transformer(Box::new(
+        |_: &RequestSummary, mut response: HttpResponse| {
+            if let Some(body) = response.body.as_bytes() {
+                let mut body = body.to_vec();
+                body.push(b'!');
+                response.body = body.into();
+            }
+            response
+        },
+    ));
+    assert_eq!(get(&router, "/under").body().len(), 10);
+    assert_oversized(&get(&router, "/at"), 10);
+}
+
+#[test]
+fn the_cap_is_off_by_default() {
+    let (mut router, _) = router();
+    router.set_max_response_size(None);
+    assert_eq!(get(&router, "/over").body().len(), 11);
+    assert_eq!(get(&router, "/stream/long").body().len(), 12);
+}
+
+#[test]
+fn streams_over_the_cap_are_aborted() {
+    let (router, summaries) = router();
+    assert_eq!(get(&router, "/stream/short").body(), "abcdabcd");
+
+    // Buffered by `route`, it gets the same 500.
+    assert_oversized(&get(&router, "/stream/long"), 10);
+    assert_eq!(
+        summaries.lock().unwrap().last().unwrap().detail.as_deref(),
+        Some("The response body is larger than the limit of 10 bytes.")
+    );
+
+    // Streamed, the head is already sent, and the stream is cut short.
+    let response =
+        router.route_streaming(Request::get("/stream/long").body(String::new()).unwrap());
+    assert_eq!(response.status(), StatusCode::OK);
+    let error = response.into_body().collect(usize::MAX).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "response body exceeds the limit of 10 bytes"
+    );
+}
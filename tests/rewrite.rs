@@ -0,0 +1,125 @@
+use generic_http_router::rewrite::RewriteRule;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Request, StatusCode};
+
+/// Answers with the path it was routed by and its parameters.
+struct Echo;
+
+impl HttpHandler for Echo {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let mut params: Vec<String> = req.params.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        params.sort();
+        HttpResponse::ok(format!("{} {}", req.uri.path(), params.join("&")))
+    }
+}
+
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{
+            "rewrites": [
+                {"match": "/legacy/:id", "to": "/old/:id"},
+                {"match": "/old/:id", "to": "/users/:id"},
+                {"match": "/docs/*rest", "to": "/static/*rest"},
+                {"match": "/users/:id", "to": "/accounts/:id"}
+            ],
+            "endpoints": [
+                {"method": "GET", "path": "/users/:id", "description": "", "controller": "echo"},
+                {"method": "GET", "path": "/accounts/:id", "description": "", "controller": "echo"},
+                {"method": "GET", "path": "/static/*path", "description": "", "controller": "echo"},
+                {"method": "GET", "path": "/health", "description": "", "controller": "echo"}
+            ]
+        }"#,
+    )
+    .unwrap();
+    router.register_handler("echo", Echo);
+    router
+}
+
+fn get(router: &Router, path: &str) -> (StatusCode, String) {
+    let response = router.route(Request::get(path).body(String::new()).unwrap());
+    let body = String::from_utf8(response.body().to_vec()).unwrap();
+    (response.status(), body)
+}
+
+#[test]
+fn rules_chain_in_order() {
+    let router = router();
+    assert_eq!(
+        get(&router, "/legacy/7"),
+        (StatusCode::OK, "/accounts/7 id=7".to_string())
+    );
+    assert_eq!(
+        get(&router, "/old/7"),
+        (StatusCode::OK, "/accounts/7 id=7".to_string())
+    );
+}
+
+#[test]
+fn each_rule_applies_at_most_once() {
+    let rules = [
+        RewriteRule::new("/a/:x", "/b/:x").unwrap(),
+        RewriteRule::new("/b/:x", "/a/:x").unwrap(),
+    ];
+    assert_eq!(rules[0].apply("/a/1").as_deref(), Some("/b/1"));
+    assert_eq!(rules[1].apply("/b/1").as_deref(), Some("/a/1"));
+    let mut router = Router::from_json_str(
+        r#"{
+            "rewrites": [
+                {"match": "/a/:x", "to": "/b/:x"},
+                {"match": "/b/:x", "to": "/a/:x"}
+            ],
+            "endpoints": [
+                {"method": "GET", "path": "/a/:x", "description": "", "controller": "echo"}
+            ]
+        }"#,
+    )
+    .unwrap();
+    router.register_handler("echo", Echo);
+    assert_eq!(
+        get(&router, "/a/1"),
+        (StatusCode::OK, "/a/1 x=1".to_string())
+    );
+}
+
+#[test]
+fn catch_all_parameters_are_carried_over() {
+    assert_eq!(
+        get(&router(), "/docs/guide/intro.html"),
+        (
+            StatusCode::OK,
+            "/static/guide/intro.html path=guide/intro.html".to_string()
+        )
+    );
+}
+
+#[test]
+fn paths_no_rule_matches_are_routed_as_is() {
+    let router = router();
+    assert_eq!(
+        get(&router, "/health"),
+        (StatusCode::OK, "/health ".to_string())
+    );
+    assert_eq!(get(&router, "/legacy").0, StatusCode::NOT_FOUND);
+    assert_eq!(
+        RewriteRule::new("/old/:id", "/users/:id")
+            .unwrap()
+            .apply("/older/1"),
+        None
+    );
+}
+
+#[test]
+fn targets_may_only_use_captured_parameters() {
+    let error = RewriteRule::new("/old/:id", "/users/:name").err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "config.rewrite: rule '/old/:id': target references unknown parameter 'name'"
+    );
+    assert!(RewriteRule::new("/old/*rest/edit", "/users/*rest").is_err());
+    assert!(
+        Router::from_json_str(
+            r#"{"rewrites": [{"match": "/a/:x", "to": "/b/:y"}], "endpoints": []}"#
+        )
+        .is_err()
+    );
+}
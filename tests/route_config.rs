@@ -0,0 +1,155 @@
+use generic_http_router::error::{LoadWarning, MetadataError};
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::Request;
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+
+/// Keeps the requests it handles.
+#[derive(Clone, Default)]
+struct Keep(Arc<Mutex<Vec<HttpRequest>>>);
+
+impl HttpHandler for Keep {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        self.0.lock().unwrap().push(req);
+        HttpResponse::ok("ok")
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Paging {
+    default_size: u32,
+    max_size: u32,
+}
+
+/// Routes a `GET` to `path` with a router whose `/orders` route has metadata,
+/// and returns the request its handler got.
+fn routed(path: &str) -> HttpRequest {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/orders", "description": "Lists orders", "controller": "keep",
+             "tags": ["orders", "public"],
+             "x-paging": {"default_size": 20, "max_size": 100},
+             "x-beta": true, "x-owner": "billing"},
+            {"method": "GET", "path": "/plain", "description": "", "controller": "keep"}
+        ]}"#,
+    )
+    .unwrap();
+    let keep = Keep::default();
+    router.register_handler("keep", keep.clone());
+    router.route(Request::get(path).body(String::new()).unwrap());
+    keep.0.lock().unwrap().pop().unwrap()
+}
+
+#[test]
+fn structured_metadata_is_deserialized() {
+    let req = routed("/orders");
+    assert_eq!(
+        req.route_config::<Paging>("x-paging"),
+        Ok(Some(Paging {
+            default_size: 20,
+            max_size: 100
+        }))
+    );
+    assert_eq!(
+        req.route_config::<serde_json::Value>("x-paging").unwrap(),
+        Some(serde_json::json!({"default_size": 20, "max_size": 100}))
+    );
+}
+
+#[test]
+fn scalar_metadata_is_deserialized() {
+    let req = routed("/orders");
+    assert_eq!(req.route_config::<bool>("x-beta"), Ok(Some(true)));
+    assert_eq!(
+        req.route_config::<String>("x-owner"),
+        Ok(Some("billing".to_string()))
+    );
+}
+
+#[test]
+fn an_absent_key_is_none() {
+    let req = routed("/orders");
+    assert_eq!(req.route_config::<bool>("x-missing"), Ok(None));
+    let req = routed("/plain");
+    assert_eq!(req.route_config::<bool>("x-beta"), Ok(None));
+    // Nor does a request the router didn't route have any.
+    let req = HttpRequest::builder().build().unwrap();
+    assert_eq!(req.route_config::<bool>("x-beta"), Ok(None));
+}
+
+#[test]
+fn a_type_mismatch_names_the_key_and_the_problem() {
+    let req = routed("/orders");
+    let error = req.route_config::<u32>("x-owner").unwrap_err();
+    assert_eq!(
+        error,
+        MetadataError::InvalidType {
+            key: "x-owner".to_string(),
+            reason: r#"invalid type: string "billing", expected u32"#.to_string(),
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        r#"route metadata 'x-owner' has the wrong type: invalid type: string "billing", expected u32"#
+    );
+    let error = req.route_config::<Paging>("x-beta").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "route metadata 'x-beta' has the wrong type: invalid type: boolean `true`, expected struct Paging"
+    );
+    // A struct missing a field.
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Strict {
+        default_size: u32,
+        min_size: u32,
+    }
+    let error = req.route_config::<Strict>("x-paging").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "route metadata 'x-paging' has the wrong type: missing field `min_size`"
+    );
+}
+
+#[test]
+fn the_description_and_tags_are_available() {
+    let req = routed("/orders");
+    assert_eq!(req.route_description(), Some("Lists orders"));
+    assert_eq!(req.route_tags(), ["orders", "public"]);
+    let req = routed("/plain");
+    assert_eq!(req.route_description(), Some(""));
+    assert!(req.route_tags().is_empty());
+    let req = HttpRequest::builder().build().unwrap();
+    assert_eq!(req.route_description(), None);
+    assert!(req.route_tags().is_empty());
+}
+
+#[test]
+fn only_x_fields_are_metadata() {
+    let (mut router, warnings) = Router::from_json_str_with_report(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/orders", "description": "", "controller": "keep",
+             "x-owner": "billing", "owner": "billing"}
+        ]}"#,
+    )
+    .unwrap();
+    // The field is reported, and left out.
+    assert_eq!(
+        warnings,
+        [LoadWarning::UnknownField {
+            index: 0,
+            path: "/orders".to_string(),
+            field: "owner".to_string(),
+            suggestion: None,
+        }]
+    );
+    let keep = Keep::default();
+    router.register_handler("keep", keep.clone());
+    router.route(Request::get("/orders").body(String::new()).unwrap());
+    let req = keep.0.lock().unwrap().pop().unwrap();
+    assert_eq!(req.route_config::<String>("owner"), Ok(None));
+    assert_eq!(
+        req.route_config::<String>("x-owner"),
+        Ok(Some("billing".to_string()))
+    );
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::assert_snapshot;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+
+struct Hello;
+
+impl HttpHandler for Hello {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok("ok")
+    }
+}
+
+/// Routes listed out of order, several methods on a path, a canary route with
+/// one of its controllers registered, and a controller without a handler.
+const CONFIG: &str = r#"{"endpoints": [
+    {"method": "POST", "path": "/users", "description": "", "controller": "users::create"},
+    {"method": "GET", "path": "/users/:id", "description": "", "controller": "users::get"},
+    {"method": "DELETE", "path": "/users/:id", "description": "", "controller": "users::delete"},
+    {"method": "GET", "path": "/users", "description": "", "controller": "users::list"},
+    {"method": "POST", "path": "/orders", "description": "",
+     "canary": {"stable": "orders::create", "candidate": "orders::create_v2", "percent": 5}},
+    {"method": "GET", "path": "/files/*path", "description": "", "controller": "files::get"},
+    {"method": "GET", "path": "/", "description": "", "controller": "home"}
+]}"#;
+
+#[test]
+fn the_summary_is_an_aligned_sorted_table() {
+    let mut router = Router::from_json_str(CONFIG).unwrap();
+    for controller in [
+        "users::create",
+        "users::get",
+        "users::list",
+        "orders::create",
+        "files::get",
+        "home",
+    ] {
+        router.register_handler(controller, Hello);
+    }
+    assert_snapshot("golden/routes_summary.txt", &router.routes_summary());
+}
+
+#[test]
+fn registering_a_handler_updates_the_summary() {
+    let mut router = Router::from_json_str(CONFIG).unwrap();
+    assert!(!router.routes_summary().contains(" registered"));
+    router.register_handler("users::delete", Hello);
+    let line = router
+        .routes_summary()
+        .lines()
+        .find(|line| line.starts_with("DELETE"))
+        .unwrap()
+        .to_string();
+    assert!(line.ends_with("  registered"), "{line}");
+}
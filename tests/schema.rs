@@ -0,0 +1,417 @@
+#![cfg(feature = "schema")]
+
+use generic_http_router::schema::{Schema, Violation};
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Request, StatusCode};
+use serde_json::{Value, json};
+
+fn schema(schema: Value) -> Schema {
+    Schema::compile(schema).unwrap()
+}
+
+fn violations(schema: &Schema, value: Value) -> Vec<(String, String)> {
+    schema
+        .validate(&value)
+        .into_iter()
+        .map(|Violation { pointer, message }| (pointer, message))
+        .collect()
+}
+
+fn violation(pointer: &str, message: &str) -> (String, String) {
+    (pointer.to_string(), message.to_string())
+}
+
+#[test]
+fn types() {
+    let integer = schema(json!({"type": "integer"}));
+    assert!(integer.is_valid(&json!(3)));
+    assert!(!integer.is_valid(&json!(3.5)));
+    assert!(!integer.is_valid(&json!("3")));
+    let nullable = schema(json!({"type": ["string", "null"]}));
+    assert!(nullable.is_valid(&json!(null)));
+    assert!(nullable.is_valid(&json!("a")));
+    assert_eq!(
+        violations(&nullable, json!(1)),
+        [violation("", "expected string or null, found number")]
+    );
+    assert!(schema(json!(true)).is_valid(&json!({"any": "thing"})));
+    assert!(!schema(json!(false)).is_valid(&json!(null)));
+}
+
+#[test]
+fn enum_and_const() {
+    let status = schema(json!({"enum": ["open", "closed", 1]}));
+    assert!(status.is_valid(&json!("open")));
+    assert!(status.is_valid(&json!(1)));
+    assert!(!status.is_valid(&json!("pending")));
+    let version = schema(json!({"const": {"v": 2}}));
+    assert!(version.is_valid(&json!({"v": 2})));
+    assert!(!version.is_valid(&json!({"v": 3})));
+}
+
+#[test]
+fn numbers() {
+    let percent = schema(json!({"minimum": 0, "maximum": 100, "multipleOf": 5}));
+    assert!(percent.is_valid(&json!(0)));
+    assert!(percent.is_valid(&json!(100)));
+    assert_eq!(
+        violations(&percent, json!(-5)),
+        [violation("", "-5 is less than the minimum of 0")]
+    );
+    assert_eq!(
+        violations(&percent, json!(7)),
+        [violation("", "7 isn't a multiple of 5")]
+    );
+    let open = schema(json!({"exclusiveMinimum": 0, "exclusiveMaximum": 1}));
+    assert!(open.is_valid(&json!(0.5)));
+    assert!(!open.is_valid(&json!(0)));
+    assert!(!open.is_valid(&json!(1)));
+    // Numeric keywords only apply to numbers.
+    assert!(percent.is_valid(&json!("-5")));
+}
+
+#[test]
+fn strings() {
+    let code = schema(json!({"minLength": 2, "maxLength": 3, "pattern": "^[A-Z]+$"}));
+    assert!(code.is_valid(&json!("EUR")));
+    assert!(!code.is_valid(&json!("E")));
+    assert!(!code.is_valid(&json!("EURO")));
+    assert_eq!(
+        violations(&code, json!("eu")),
+        [violation(
+            "",
+            "the string doesn't match the pattern ^[A-Z]+$"
+        )]
+    );
+}
+
+#[test]
+fn string_lengths_count_characters() {
+    let short = schema(json!({"maxLength": 2}));
+    assert!(short.is_valid(&json!("éé")));
+    assert!(!short.is_valid(&json!("ééé")));
+}
+
+#[test]
+fn arrays() {
+    let tags = schema(json!({
+        "type": "array",
+        "items": {"type": "string"},
+        "minItems": 1,
+        "maxItems": 3,
+        "uniqueItems": true
+    }));
+    assert!(tags.is_valid(&json!(["a", "b"])));
+    assert!(!tags.is_valid(&json!([])));
+    assert!(!tags.is_valid(&json!(["a", "b", "c", "d"])));
+    assert_eq!(
+        violations(&tags, json!(["a", "a"])),
+        [violation("", "the array has duplicate items")]
+    );
+    assert_eq!(
+        violations(&tags, json!(["a", 2])),
+        [violation("/1", "expected string, found number")]
+    );
+}
+
+#[test]
+fn objects() {
+    let user = schema(json!({
+        "type": "object",
+        "properties": {
+            "name": {"type": "string"},
+            "address": {
+                "type": "object",
+                "properties": {"city": {"type": "string"}},
+                "required": ["city"]
+            }
+        },
+        "patternProperties": {"^x-": {"type": "string"}},
+        "required": ["name"],
+        "additionalProperties": false
+    }));
+    assert!(user.is_valid(&json!({"name": "Ada", "x-team": "eng"})));
+    assert_eq!(
+        violations(&user, json!({"address": {}})),
+        [
+            violation("", "missing required property 'name'"),
+            violation("/address", "missing required property 'city'"),
+        ]
+    );
+    assert_eq!(
+        violations(&user, json!({"name": "Ada", "age": 36, "x-n": 1})),
+        [
+            violation("/age", "property 'age' isn't allowed"),
+            violation("/x-n", "expected string, found number"),
+        ]
+    );
+    let sized = schema(json!({"minProperties": 1, "maxProperties": 1}));
+    assert!(sized.is_valid(&json!({"a": 1})));
+    assert!(!sized.is_valid(&json!({})));
+    assert!(!sized.is_valid(&json!({"a": 1, "b": 2})));
+}
+
+#[test]
+fn pointers_escape_property_names() {
+    let schema = schema(json!({"properties": {"a/b~c": {"type": "string"}}}));
+    assert_eq!(
+        violations(&schema, json!({"a/b~c": 1})),
+        [violation("/a~1b~0c", "expected string, found number")]
+    );
+}
+
+#[test]
+fn combinators() {
+    let all = schema(json!({"allOf": [{"minimum": 1}, {"maximum": 2}]}));
+    assert!(all.is_valid(&json!(1)));
+    assert!(!all.is_valid(&json!(3)));
+    let any = schema(json!({"anyOf": [{"type": "string"}, {"type": "integer"}]}));
+    assert!(any.is_valid(&json!(1)));
+    assert_eq!(
+        violations(&any, json!(true)),
+        [violation(
+            "",
+            "the value doesn't match any of the schemas of anyOf"
+        )]
+    );
+    let one = schema(json!({"oneOf": [{"minimum": 0}, {"maximum": 0}]}));
+    assert!(one.is_valid(&json!(1)));
+    assert_eq!(
+        violations(&one, json!(0)),
+        [violation(
+            "",
+            "the value matches 2 of the schemas of oneOf instead of exactly one"
+        )]
+    );
+    let not = schema(json!({"not": {"type": "null"}}));
+    assert!(not.is_valid(&json!(0)));
+    assert!(!not.is_valid(&json!(null)));
+}
+
+#[test]
+fn references() {
+    let order = schema(json!({
+        "$defs": {"address": {"type": "object", "required": ["city"]}},
+        "properties": {
+            "billing": {"$ref": "#/$defs/address"},
+            "shipping": {"$ref": "#/$defs/address"}
+        }
+    }));
+    assert!(order.is_valid(&json!({"billing": {"city": "Paris"}})));
+    assert_eq!(
+        violations(&order, json!({"shipping": {}})),
+        [violation("/shipping", "missing required property 'city'")]
+    );
+}
+
+#[test]
+fn recursive_references_are_bounded() {
+    let tree = schema(json!({
+        "type": "object",
+        "properties": {"children": {"type": "array", "items": {"$ref": "#"}}}
+    }));
+    assert!(tree.is_valid(&json!({"children": [{"children": [{}]}]})));
+    let mut deep = json!({});
+    for _ in 0..40 {
+        deep = json!({"children": [deep]});
+    }
+    assert_eq!(
+        tree.validate(&deep)[0].message,
+        "the schema's references nest too deeply"
+    );
+    let looping = schema(json!({"$ref": "#"}));
+    assert!(!looping.is_valid(&json!(1)));
+}
+
+#[test]
+fn unsupported_or_invalid_schemas_are_rejected() {
+    assert!(Schema::compile(json!({"if": {"type": "string"}})).is_err());
+    assert!(Schema::compile(json!({"prefixItems": []})).is_err());
+    assert!(Schema::compile(json!({"pattern": "(?=a)"})).is_err());
+    assert!(Schema::compile(json!({"$ref": "#/$defs/missing"})).is_err());
+    assert!(Schema::compile(json!({"type": "decimal"})).is_err());
+    assert!(Schema::compile(json!({"title": "User", "format": "email"})).is_ok());
+}
+
+#[test]
+fn schemas_rejecting_everything() {
+    assert!(schema(json!(false)).rejects_everything());
+    assert!(schema(json!({"enum": []})).rejects_everything());
+    assert!(schema(json!({"minimum": 2, "maximum": 1})).rejects_everything());
+    assert!(schema(json!({"not": true})).rejects_everything());
+    assert!(!schema(json!({"minimum": 1, "maximum": 1})).rejects_everything());
+}
+
+struct Created;
+
+impl HttpHandler for Created {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::created("{}")
+    }
+}
+
+#[test]
+fn request_bodies_are_validated_before_the_handler() {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "POST", "path": "/users", "description": "", "controller": "users",
+             "request_schema": {"type": "object", "required": ["name"]}}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("users", Created);
+    let post = |content_type: &str, body: &str| {
+        let req = Request::post("/users")
+            .header("content-type", content_type)
+            .body(body.to_string())
+            .unwrap();
+        router.route(req)
+    };
+    assert_eq!(
+        post("application/json", r#"{"name": "Ada"}"#).status(),
+        StatusCode::CREATED
+    );
+    let invalid = post("application/json", "{}");
+    assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+    let problem: Value = serde_json::from_slice(invalid.body()).unwrap();
+    assert_eq!(
+        problem["errors"],
+        json!([{"pointer": "", "message": "missing required property 'name'"}])
+    );
+    assert_eq!(
+        post("application/json", "{").status(),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        post("text/plain", r#"{"name": "Ada"}"#).status(),
+        StatusCode::UNSUPPORTED_MEDIA_TYPE
+    );
+}
+
+mod responses {
+    use super::*;
+    use generic_http_router::schema::{ResponseValidation, SkipValidation};
+
+    /// Answers for the `case` parameter: a `valid` user, an `invalid` one, or
+    /// an invalid one as `text`, with a 404 status, `skip`ped or as broken
+    /// JSON.
+    struct Users;
+
+    impl HttpHandler for Users {
+        fn handle(&self, req: HttpRequest) -> HttpResponse {
+            let json = |status, body: &str| {
+                let mut response = HttpResponse::text(status, body.to_string());
+                response.add_header("content-type", "application/json; charset=utf-8");
+                response
+            };
+            match req.params.get("case").unwrap() {
+                "valid" => json(StatusCode::OK, r#"{"id": 1, "name": "Ada"}"#),
+                "invalid" => json(StatusCode::OK, r#"{"id": "one"}"#),
+                "text" => HttpResponse::text(StatusCode::OK, r#"{"id": "one"}"#.to_string()),
+                "not_found" => json(StatusCode::NOT_FOUND, r#"{"error": "no such user"}"#),
+                "skip" => {
+                    let mut response = json(StatusCode::OK, r#"{"id": "one"}"#);
+                    response.extensions.insert(SkipValidation);
+                    response
+                }
+                _ => json(StatusCode::OK, "{"),
+            }
+        }
+    }
+
+    fn router_with(mode: Option<ResponseValidation>) -> Router {
+        let mut router = Router::from_json_str(
+            r#"{"endpoints": [
+                {"method": "GET", "path": "/users/:case", "description": "", "controller": "users",
+                 "response_schema": {"type": "object", "required": ["id", "name"],
+                                     "properties": {"id": {"type": "integer"}}}}
+            ]}"#,
+        )
+        .unwrap();
+        router.register_handler("users", Users);
+        if let Some(mode) = mode {
+            router.set_response_validation(mode);
+        }
+        router
+    }
+
+    fn get(router: &Router, case: &str) -> (StatusCode, String) {
+        let response = router.route(
+            Request::get(format!("/users/{case}"))
+                .body(String::new())
+                .unwrap(),
+        );
+        let body = String::from_utf8(response.body().to_vec()).unwrap();
+        (response.status(), body)
+    }
+
+    #[test]
+    fn validation_is_off_by_default() {
+        let router = router_with(None);
+        assert_eq!(
+            get(&router, "invalid"),
+            (StatusCode::OK, r#"{"id": "one"}"#.to_string())
+        );
+        let router = router_with(Some(ResponseValidation::Off));
+        assert_eq!(get(&router, "invalid").0, StatusCode::OK);
+        assert_eq!(get(&router, "broken").0, StatusCode::OK);
+    }
+
+    #[test]
+    fn warn_mode_sends_the_response_unchanged() {
+        let router = router_with(Some(ResponseValidation::Warn));
+        for case in ["valid", "invalid", "broken"] {
+            assert_eq!(get(&router, case).0, StatusCode::OK, "{case}");
+        }
+        assert_eq!(get(&router, "invalid").1, r#"{"id": "one"}"#);
+    }
+
+    #[test]
+    fn enforce_mode_replaces_invalid_responses_with_a_500() {
+        let mut router = router_with(Some(ResponseValidation::Enforce));
+        assert_eq!(
+            get(&router, "valid"),
+            (StatusCode::OK, r#"{"id": 1, "name": "Ada"}"#.to_string())
+        );
+        let (status, body) = get(&router, "invalid");
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        // The violations aren't sent without problem responses.
+        assert!(!body.contains("one"), "{body}");
+        assert_eq!(get(&router, "broken").0, StatusCode::INTERNAL_SERVER_ERROR);
+
+        router.use_problem_responses(true);
+        let (status, body) = get(&router, "invalid");
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        let problem: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            problem["detail"],
+            "The response doesn't match the schema of the route."
+        );
+        assert_eq!(
+            problem["errors"],
+            json!([
+                {"pointer": "", "message": "missing required property 'name'"},
+                {"pointer": "/id", "message": "expected integer, found string"}
+            ])
+        );
+    }
+
+    #[test]
+    fn only_2xx_json_responses_are_validated() {
+        let router = router_with(Some(ResponseValidation::Enforce));
+        assert_eq!(
+            get(&router, "text"),
+            (StatusCode::OK, r#"{"id": "one"}"#.to_string())
+        );
+        assert_eq!(get(&router, "not_found").0, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn skip_validation_exempts_a_response() {
+        let router = router_with(Some(ResponseValidation::Enforce));
+        assert_eq!(
+            get(&router, "skip"),
+            (StatusCode::OK, r#"{"id": "one"}"#.to_string())
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Method, Request, Response, StatusCode};
+
+/// Answers with its name and the request's path.
+struct Named(&'static str);
+
+impl HttpHandler for Named {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok(format!("{} {}", self.0, req.uri.path()))
+    }
+}
+
+/// A router with `GET` and `DELETE` routes on `/users/:id`, a `POST /users`,
+/// and an `OPTIONS /:any` that `OPTIONS *` must never match.
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/users/:id", "description": "", "controller": "route"},
+            {"method": "DELETE", "path": "/users/:id", "description": "", "controller": "route"},
+            {"method": "POST", "path": "/users", "description": "", "controller": "route"},
+            {"method": "OPTIONS", "path": "/:any", "description": "", "controller": "route"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("route", Named("route"));
+    router
+}
+
+fn send(router: &Router, method: Method, target: &str) -> Response<bytes::Bytes> {
+    router.route(
+        Request::builder()
+            .method(method)
+            .uri(target)
+            .body(String::new())
+            .unwrap(),
+    )
+}
+
+#[test]
+fn auto_options_lists_every_method_with_a_route() {
+    let mut router = router();
+    router.set_auto_options(true);
+    let response = send(&router, Method::OPTIONS, "*");
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(
+        response.headers()["allow"],
+        "DELETE, GET, HEAD, OPTIONS, POST"
+    );
+    assert!(response.body().is_empty());
+}
+
+#[test]
+fn a_registered_handler_answers_options_star() {
+    let mut router = router();
+    router.register_server_options(Box::new(Named("server")));
+    let response = send(&router, Method::OPTIONS, "*");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), "server *");
+
+    // It takes precedence over the automatic response.
+    router.set_auto_options(true);
+    assert_eq!(send(&router, Method::OPTIONS, "*").body(), "server *");
+}
+
+#[test]
+fn options_star_is_not_implemented_by_default() {
+    let router = router();
+    let response = send(&router, Method::OPTIONS, "*");
+    assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+    // A path is still matched against the routes.
+    assert_eq!(
+        send(&router, Method::OPTIONS, "/star").body(),
+        "route /star"
+    );
+}
+
+#[test]
+fn only_options_may_target_the_server() {
+    let mut router = router();
+    router.set_auto_options(true);
+    router.register_server_options(Box::new(Named("server")));
+    for method in [Method::GET, Method::DELETE] {
+        let response = send(&router, method.clone(), "*");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{method}");
+    }
+}
@@ -0,0 +1,210 @@
+#![cfg(feature = "session")]
+
+use generic_http_router::session::MemoryStore;
+use generic_http_router::testing::MockClock;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router, SessionMiddleware};
+use http::{Request, Response, StatusCode, header};
+use std::time::Duration;
+
+const KEY: &[u8] = b"a session key of at least 32 bytes";
+
+/// `POST /login` stores the user in the session, `GET /me` reads it and
+/// `POST /logout` destroys the session.
+struct Account;
+
+impl HttpHandler for Account {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let session = req.session().expect("sessions are enabled");
+        match req.uri.path() {
+            "/login" => {
+                session.insert("user", "ada");
+                HttpResponse::ok("logged in")
+            }
+            "/logout" => {
+                session.destroy();
+                HttpResponse::ok("logged out")
+            }
+            _ => match session.get::<String>("user") {
+                Some(user) => HttpResponse::ok(user),
+                None => HttpResponse::ok("anonymous"),
+            },
+        }
+    }
+}
+
+fn router(sessions: SessionMiddleware) -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "POST", "path": "/login", "description": "", "controller": "account"},
+            {"method": "POST", "path": "/logout", "description": "", "controller": "account"},
+            {"method": "GET", "path": "/me", "description": "", "controller": "account"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("account", Account);
+    router.use_sessions(sessions);
+    router
+}
+
+fn sessions() -> SessionMiddleware {
+    SessionMiddleware::new(KEY, Box::new(MemoryStore::new()))
+}
+
+fn send(router: &Router, method: &str, path: &str, cookie: Option<&str>) -> Response<bytes::Bytes> {
+    let mut req = Request::builder().method(method).uri(path);
+    if let Some(cookie) = cookie {
+        req = req.header(header::COOKIE, cookie);
+    }
+    router.route(req.body(String::new()).unwrap())
+}
+
+/// Returns the `name=value` part of the response's `Set-Cookie`.
+fn set_cookie(response: &Response<bytes::Bytes>) -> Option<String> {
+    let value = response
+        .headers()
+        .get(header::SET_COOKIE)?
+        .to_str()
+        .unwrap();
+    Some(value.split(';').next().unwrap().to_string())
+}
+
+fn body(response: &Response<bytes::Bytes>) -> &str {
+    std::str::from_utf8(response.body()).unwrap()
+}
+
+/// Returns a session cookie signed with another key.
+fn cookie_signed_with_another_key() -> String {
+    let other = router(SessionMiddleware::new(
+        b"another key of at least 32 bytes!".to_vec(),
+        Box::new(MemoryStore::new()),
+    ));
+    set_cookie(&send(&other, "POST", "/login", None)).unwrap()
+}
+
+#[test]
+fn a_changed_session_is_created_with_a_signed_cookie() {
+    let router = router(sessions().with_max_age(Duration::from_secs(600)));
+    let response = send(&router, "POST", "/login", None);
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let header = response.headers()[header::SET_COOKIE].to_str().unwrap();
+    assert!(header.starts_with("session="), "{header}");
+    assert!(header.contains("; HttpOnly"));
+    assert!(header.contains("; Secure"));
+    assert!(header.contains("; SameSite=Lax"));
+    assert!(header.contains("; Max-Age=600"));
+    let value = set_cookie(&response).unwrap();
+    let (id, signature) = value["session=".len()..].split_once('.').unwrap();
+    assert_eq!(id.len(), 32);
+    assert_eq!(signature.len(), 64);
+}
+
+#[test]
+fn an_unchanged_session_sets_no_cookie() {
+    let router = router(sessions());
+    let response = send(&router, "GET", "/me", None);
+    assert_eq!(body(&response), "anonymous");
+    assert_eq!(set_cookie(&response), None);
+}
+
+#[test]
+fn new_sessions_get_distinct_ids() {
+    let router = router(sessions());
+    let first = set_cookie(&send(&router, "POST", "/login", None)).unwrap();
+    let second = set_cookie(&send(&router, "POST", "/login", None)).unwrap();
+    assert_ne!(first, second);
+}
+
+#[test]
+fn a_session_round_trips_across_requests() {
+    let router = router(sessions());
+    let cookie = set_cookie(&send(&router, "POST", "/login", None)).unwrap();
+    let response = send(&router, "GET", "/me", Some(&cookie));
+    assert_eq!(body(&response), "ada");
+    assert_eq!(set_cookie(&response), None);
+}
+
+#[test]
+fn a_tampered_cookie_is_ignored() {
+    let router = router(sessions());
+    let cookie = set_cookie(&send(&router, "POST", "/login", None)).unwrap();
+    let (id, signature) = cookie.split_once('.').unwrap();
+
+    let mut forged_id = id.to_string();
+    let last = forged_id.pop().unwrap();
+    forged_id.push(if last == '0' { '1' } else { '0' });
+    let forged = format!("{forged_id}.{signature}");
+    assert_eq!(
+        body(&send(&router, "GET", "/me", Some(&forged))),
+        "anonymous"
+    );
+
+    let unsigned = id.to_string();
+    assert_eq!(
+        body(&send(&router, "GET", "/me", Some(&unsigned))),
+        "anonymous"
+    );
+
+    let other = cookie_signed_with_another_key();
+    assert_eq!(
+        body(&send(&router, "GET", "/me", Some(&other))),
+        "anonymous"
+    );
+}
+
+#[test]
+fn destroying_a_session_clears_its_cookie() {
+    let router = router(sessions());
+    let cookie = set_cookie(&send(&router, "POST", "/login", None)).unwrap();
+
+    let response = send(&router, "POST", "/logout", Some(&cookie));
+    let header = response.headers()[header::SET_COOKIE].to_str().unwrap();
+    assert!(header.starts_with("session=;"), "{header}");
+    assert!(header.contains("; Max-Age=0"));
+    // The stored session is gone too.
+    assert_eq!(
+        body(&send(&router, "GET", "/me", Some(&cookie))),
+        "anonymous"
+    );
+}
+
+#[test]
+fn cookie_attributes_are_configurable() {
+    let sessions = sessions()
+        .with_cookie_name("sid")
+        .secure(false)
+        .with_same_site(generic_http_router::session::SameSite::Strict);
+    let router = router(sessions);
+    let response = send(&router, "POST", "/login", None);
+    let header = response.headers()[header::SET_COOKIE].to_str().unwrap();
+    assert!(header.starts_with("sid="));
+    assert!(!header.contains("Secure"));
+    assert!(header.contains("; SameSite=Strict"));
+}
+
+#[test]
+fn a_session_expires_after_its_max_age() {
+    let clock = MockClock::new();
+    let store = MemoryStore::new().with_clock(clock.clone());
+    let router =
+        router(SessionMiddleware::new(KEY, Box::new(store)).with_max_age(Duration::from_secs(600)));
+    let cookie = set_cookie(&send(&router, "POST", "/login", None)).unwrap();
+
+    clock.advance(Duration::from_secs(599));
+    assert_eq!(body(&send(&router, "GET", "/me", Some(&cookie))), "ada");
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(
+        body(&send(&router, "GET", "/me", Some(&cookie))),
+        "anonymous"
+    );
+}
+
+#[test]
+fn a_max_age_too_large_for_a_deadline_never_expires() {
+    let clock = MockClock::new();
+    let store = MemoryStore::new().with_clock(clock.clone());
+    let router = router(SessionMiddleware::new(KEY, Box::new(store)).with_max_age(Duration::MAX));
+    let cookie = set_cookie(&send(&router, "POST", "/login", None)).unwrap();
+    clock.advance(Duration::from_secs(100 * 365 * 24 * 60 * 60));
+    assert_eq!(body(&send(&router, "GET", "/me", Some(&cookie))), "ada");
+}
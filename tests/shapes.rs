@@ -0,0 +1,95 @@
+//! Routes sharing a path shape under different parameter names, e.g.
+//! `GET /users/:id` and `POST /users/:user_id`.
+
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Method, Request, Response, StatusCode};
+
+/// Answers with the route's parameters, sorted.
+struct Params;
+
+impl HttpHandler for Params {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let mut params: Vec<String> = req.params.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        params.sort();
+        HttpResponse::ok(params.join("&"))
+    }
+}
+
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/users/:id", "description": "", "controller": "params"},
+            {"method": "POST", "path": "/users/:user_id", "description": "", "controller": "params"},
+            {"method": "DELETE", "path": "/users/:uid", "description": "", "controller": "params"},
+            {"method": "GET", "path": "/users/:id/orders/:order", "description": "", "controller": "params"},
+            {"method": "PUT", "path": "/users/:user/orders/:n", "description": "", "controller": "params"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("params", Params);
+    router.set_auto_options(true);
+    router
+}
+
+fn send(router: &Router, method: Method, path: &str) -> Response<bytes::Bytes> {
+    let req = Request::builder().method(method).uri(path);
+    router.route(req.body(String::new()).unwrap())
+}
+
+fn body(response: &Response<bytes::Bytes>) -> &str {
+    std::str::from_utf8(response.body()).unwrap()
+}
+
+#[test]
+fn each_route_gets_its_own_parameter_names() {
+    let router = router();
+    assert_eq!(body(&send(&router, Method::GET, "/users/7")), "id=7");
+    assert_eq!(body(&send(&router, Method::POST, "/users/7")), "user_id=7");
+    assert_eq!(body(&send(&router, Method::DELETE, "/users/7")), "uid=7");
+    assert_eq!(
+        body(&send(&router, Method::GET, "/users/7/orders/3")),
+        "id=7&order=3"
+    );
+    assert_eq!(
+        body(&send(&router, Method::PUT, "/users/7/orders/3")),
+        "n=3&user=7"
+    );
+}
+
+#[test]
+fn allow_lists_every_method_of_the_shape_once() {
+    let router = router();
+    let response = send(&router, Method::PATCH, "/users/7");
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(
+        response.headers()["allow"],
+        "DELETE, GET, HEAD, OPTIONS, POST"
+    );
+
+    let response = send(&router, Method::POST, "/users/7/orders/3");
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers()["allow"], "GET, HEAD, OPTIONS, PUT");
+}
+
+#[test]
+fn options_lists_every_method_of_the_shape() {
+    let response = send(&router(), Method::OPTIONS, "/users/7");
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(
+        response.headers()["allow"],
+        "DELETE, GET, HEAD, OPTIONS, POST"
+    );
+}
+
+#[test]
+fn other_shapes_are_not_found() {
+    let router = router();
+    assert_eq!(
+        send(&router, Method::GET, "/users").status(),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        send(&router, Method::GET, "/users/7/orders").status(),
+        StatusCode::NOT_FOUND
+    );
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "shared")]
+
+use generic_http_router::config::Endpoint;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router, SharedRouter};
+use http::{Method, Request, StatusCode};
+use std::sync::Barrier;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+/// Answers with its name.
+struct Named(String);
+
+impl HttpHandler for Named {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok(self.0.clone())
+    }
+}
+
+const TENANTS: usize = 200;
+const READERS: usize = 8;
+
+fn get(shared: &SharedRouter, path: &str) -> (StatusCode, String) {
+    let response = shared.route(Request::get(path).body(String::new()).unwrap());
+    let body = String::from_utf8(response.body().to_vec()).unwrap();
+    (response.status(), body)
+}
+
+/// One writer adds, registers, unregisters and removes tenant routes while
+/// eight readers route continuously: every response comes from a whole
+/// version of the router, and the route that never changes always answers.
+#[test]
+fn one_writer_and_eight_readers() {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/stable", "description": "", "controller": "stable"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("stable", Named("stable".to_string()));
+    let shared = SharedRouter::new(router);
+    let start = Barrier::new(READERS + 1);
+    let done = AtomicBool::new(false);
+    let tenant_hits = AtomicUsize::new(0);
+
+    thread::scope(|scope| {
+        for reader in 0..READERS {
+            let (shared, start, done, tenant_hits) = (&shared, &start, &done, &tenant_hits);
+            scope.spawn(move || {
+                start.wait();
+                let mut n = reader;
+                loop {
+                    // Read while the writer runs, and once more after it's done.
+                    let finished = done.load(Ordering::Acquire);
+                    assert_eq!(
+                        get(shared, "/stable"),
+                        (StatusCode::OK, "stable".to_string())
+                    );
+                    let tenant = format!("t{}", n % TENANTS);
+                    let (status, body) = get(shared, &format!("/tenants/{tenant}"));
+                    match status {
+                        StatusCode::OK => {
+                            assert_eq!(body, tenant);
+                            tenant_hits.fetch_add(1, Ordering::Relaxed);
+                        }
+                        StatusCode::NOT_FOUND | StatusCode::NOT_IMPLEMENTED => {}
+                        other => panic!("/tenants/{tenant}: {other}"),
+                    }
+                    n += READERS;
+                    if finished {
+                        break;
+                    }
+                }
+            });
+        }
+
+        start.wait();
+        for i in 0..TENANTS {
+            let tenant = format!("t{i}");
+            let path = format!("/tenants/{tenant}");
+            shared
+                .add_route(Endpoint::new(Method::GET, path.clone(), tenant.clone()))
+                .unwrap();
+            shared.register_handler(tenant.clone(), Named(tenant.clone()));
+            if i % 2 == 0 {
+                // Retire every other tenant again: its route first answers 501,
+                // then 404.
+                assert!(shared.unregister(&tenant));
+                assert!(shared.remove_route(&Method::GET, &path));
+            }
+        }
+        done.store(true, Ordering::Release);
+    });
+
+    for i in 0..TENANTS {
+        let (status, body) = get(&shared, &format!("/tenants/t{i}"));
+        if i % 2 == 0 {
+            assert_eq!(status, StatusCode::NOT_FOUND, "t{i}");
+        } else {
+            assert_eq!((status, body), (StatusCode::OK, format!("t{i}")));
+        }
+    }
+    // The readers' last pass ran after the writer was done, when half of the
+    // tenants answer.
+    assert!(tenant_hits.load(Ordering::Relaxed) > 0);
+}
@@ -0,0 +1,158 @@
+#[cfg(feature = "async")]
+use generic_http_router::async_handler::AsyncFn;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Request, StatusCode, header};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn config() -> Router {
+    Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/slow", "description": "", "controller": "slow"}
+        ]}"#,
+    )
+    .unwrap()
+}
+
+/// Waits on `started`, then `release`, blocking the thread.
+struct Blocking {
+    started: Arc<std::sync::Barrier>,
+    release: Arc<std::sync::Barrier>,
+}
+
+impl HttpHandler for Blocking {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        self.started.wait();
+        self.release.wait();
+        HttpResponse::ok("done")
+    }
+}
+
+#[test]
+fn a_request_in_flight_finishes_and_new_ones_are_turned_away() {
+    let (started, release) = (
+        Arc::new(std::sync::Barrier::new(2)),
+        Arc::new(std::sync::Barrier::new(2)),
+    );
+    let mut router = config();
+    router.register_handler(
+        "slow",
+        Blocking {
+            started: Arc::clone(&started),
+            release: Arc::clone(&release),
+        },
+    );
+    let router = Arc::new(router);
+    let handle = router.shutdown_controller();
+    let running = {
+        let router = Arc::clone(&router);
+        std::thread::spawn(move || {
+            let req = Request::get("/slow").body(String::new()).unwrap();
+            router.route(req).status()
+        })
+    };
+    started.wait();
+
+    handle.begin_shutdown();
+    handle.set_retry_after(Duration::from_secs(30));
+    let response = router.route(Request::get("/slow").body(String::new()).unwrap());
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()[header::CONNECTION], "close");
+    assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+    assert!(!handle.wait_idle(Duration::from_millis(20)));
+
+    let drained = {
+        let handle = handle.clone();
+        std::thread::spawn(move || handle.wait_idle(Duration::MAX))
+    };
+    release.wait();
+    assert_eq!(running.join().unwrap(), StatusCode::OK);
+    assert!(drained.join().unwrap());
+    assert_eq!(handle.in_flight(), 0);
+}
+
+#[test]
+fn waiting_without_a_deadline() {
+    let router = config();
+    let handle = router.shutdown_controller();
+    assert!(handle.wait_idle(Duration::MAX));
+    handle.begin_shutdown();
+    assert!(handle.wait_idle(Duration::MAX));
+}
+
+/// A router whose `GET /slow` handler waits on `started`, then `release`.
+#[cfg(feature = "async")]
+fn router(started: &Arc<tokio::sync::Barrier>, release: &Arc<tokio::sync::Barrier>) -> Arc<Router> {
+    let mut router = config();
+    let (started, release) = (Arc::clone(started), Arc::clone(release));
+    router.register_handler(
+        "slow",
+        AsyncFn(move |_req: HttpRequest| {
+            let (started, release) = (Arc::clone(&started), Arc::clone(&release));
+            async move {
+                started.wait().await;
+                release.wait().await;
+                HttpResponse::ok("done")
+            }
+        }),
+    );
+    Arc::new(router)
+}
+
+#[cfg(feature = "async")]
+fn spawn_get(router: &Arc<Router>) -> tokio::task::JoinHandle<StatusCode> {
+    let router = Arc::clone(router);
+    tokio::spawn(async move {
+        let req = Request::get("/slow").body(String::new()).unwrap();
+        router.route_async(req).await.status()
+    })
+}
+
+#[cfg(feature = "async")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn draining_ends_when_the_last_request_finishes() {
+    let (started, release) = (
+        Arc::new(tokio::sync::Barrier::new(2)),
+        Arc::new(tokio::sync::Barrier::new(2)),
+    );
+    let router = router(&started, &release);
+    let handle = router.shutdown_controller();
+    let running = spawn_get(&router);
+    started.wait().await;
+
+    handle.begin_shutdown();
+    assert_eq!(handle.in_flight(), 1);
+    let req = Request::get("/slow").body(String::new()).unwrap();
+    let response = router.route_async(req).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()[header::CONNECTION], "close");
+    assert!(response.headers().contains_key(header::RETRY_AFTER));
+
+    let drained = {
+        let handle = handle.clone();
+        tokio::spawn(async move { handle.wait_idle_async(Duration::MAX).await })
+    };
+    release.wait().await;
+    assert_eq!(running.await.unwrap(), StatusCode::OK);
+    assert!(drained.await.unwrap());
+    assert_eq!(handle.in_flight(), 0);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn draining_times_out_while_requests_run() {
+    let (started, release) = (
+        Arc::new(tokio::sync::Barrier::new(2)),
+        Arc::new(tokio::sync::Barrier::new(2)),
+    );
+    let router = router(&started, &release);
+    let handle = router.shutdown_controller();
+    let running = spawn_get(&router);
+    started.wait().await;
+
+    handle.begin_shutdown();
+    assert!(!handle.wait_idle_async(Duration::from_millis(20)).await);
+    release.wait().await;
+    assert_eq!(running.await.unwrap(), StatusCode::OK);
+    assert!(handle.wait_idle_async(Duration::ZERO).await);
+}
@@ -0,0 +1,125 @@
+use generic_http_router::signature::SecretProvider;
+use generic_http_router::testing::MockClock;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Request, StatusCode};
+use std::time::{Duration, UNIX_EPOCH};
+
+const BODY: &str = r#"{"id":"evt_1","type":"charge.succeeded"}"#;
+
+struct Received;
+
+impl HttpHandler for Received {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::ok("received")
+    }
+}
+
+struct Secrets;
+
+impl SecretProvider for Secrets {
+    fn secret(&self, name: &str) -> Option<Vec<u8>> {
+        match name {
+            "GITHUB_SECRET" => Some(b"It's a Secret to Everybody".to_vec()),
+            "STRIPE_SECRET" => Some(b"whsec_test_secret".to_vec()),
+            _ => None,
+        }
+    }
+}
+
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "POST", "path": "/github", "description": "",
+             "controller": "hooks::received",
+             "signature": {"header": "X-Hub-Signature-256",
+                           "algorithm": "hmac-sha256", "secret_env": "GITHUB_SECRET"}},
+            {"method": "POST", "path": "/stripe", "description": "",
+             "controller": "hooks::received",
+             "signature": {"header": "Stripe-Signature",
+                           "algorithm": "stripe-v1", "secret_env": "STRIPE_SECRET"}}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("hooks::received", Received);
+    router.set_secret_provider(Secrets);
+    // The timestamp of Stripe's documented example header.
+    router.set_clock(MockClock::at(UNIX_EPOCH + Duration::from_secs(1492774577)));
+    router
+}
+
+fn post(router: &Router, path: &str, header: (&str, &str), body: &str) -> StatusCode {
+    let req = Request::post(path)
+        .header(header.0, header.1)
+        .body(body.to_string())
+        .unwrap();
+    router.route(req).status()
+}
+
+#[test]
+fn github_signature_with_the_sha256_prefix() {
+    let router = router();
+    let valid = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+    let header = ("X-Hub-Signature-256", valid);
+    assert_eq!(
+        post(&router, "/github", header, "Hello, World!"),
+        StatusCode::OK
+    );
+    assert_eq!(
+        post(&router, "/github", header, "Hello, World?"),
+        StatusCode::UNAUTHORIZED
+    );
+    let unprefixed = &valid["sha256=".len()..].to_ascii_uppercase();
+    let header = ("X-Hub-Signature-256", unprefixed.as_str());
+    assert_eq!(
+        post(&router, "/github", header, "Hello, World!"),
+        StatusCode::OK
+    );
+}
+
+#[test]
+fn stripe_signature_within_the_tolerance() {
+    let router = router();
+    let valid = "t=1492774577,v1=4ee5e8d695c9619e003fd480d3d51700d924e29697337d379a6ef6e06e703ab4";
+    assert_eq!(
+        post(&router, "/stripe", ("Stripe-Signature", valid), BODY),
+        StatusCode::OK
+    );
+    let rotated =
+        "t=1492774577,v1=00,v1=4ee5e8d695c9619e003fd480d3d51700d924e29697337d379a6ef6e06e703ab4";
+    assert_eq!(
+        post(&router, "/stripe", ("Stripe-Signature", rotated), BODY),
+        StatusCode::OK
+    );
+    let tampered =
+        "t=1492774578,v1=4ee5e8d695c9619e003fd480d3d51700d924e29697337d379a6ef6e06e703ab4";
+    assert_eq!(
+        post(&router, "/stripe", ("Stripe-Signature", tampered), BODY),
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[test]
+fn stripe_signature_past_the_tolerance() {
+    let router = router();
+    let old = "t=1492770000,v1=4ee5e8d695c9619e003fd480d3d51700d924e29697337d379a6ef6e06e703ab4";
+    assert_eq!(
+        post(&router, "/stripe", ("Stripe-Signature", old), BODY),
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[test]
+fn stripe_timestamp_past_the_end_of_time_is_rejected() {
+    let router = router();
+    let header = ("Stripe-Signature", "t=18446744073709551615,v1=00");
+    assert_eq!(
+        post(&router, "/stripe", header, BODY),
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[test]
+fn missing_signature() {
+    let req = Request::post("/github").body(BODY.to_string()).unwrap();
+    assert_eq!(router().route(req).status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,215 @@
+//! Snapshots of whole responses, rendered with `testing::render_response`, to
+//! a matrix of requests against one configuration. The snapshots are the
+//! files of `tests/snapshots`; run with `UPDATE_SNAPSHOTS=1` to rewrite them
+//! after an intended change, and review their diff.
+
+mod common;
+
+use common::assert_snapshot;
+use generic_http_router::testing::render_response;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::Request;
+use std::path::PathBuf;
+
+/// Params, wildcards, several methods on a path, a rewrite, default headers,
+/// the unregistered controller `users::delete`, and `GET`, `POST` and `DELETE`
+/// on `/teams` for the `Allow` header.
+const CONFIG: &str = r#"{
+    "default_response_headers": {"X-Content-Type-Options": "nosniff"},
+    "rewrites": [{"match": "/members/:id", "to": "/users/:id"}],
+    "endpoints": [
+        {"method": "GET", "path": "/users", "description": "", "controller": "users::list"},
+        {"method": "POST", "path": "/users", "description": "", "controller": "users::create"},
+        {"method": "GET", "path": "/users/:id", "description": "", "controller": "users::get"},
+        {"method": "PUT", "path": "/users/:id", "description": "", "controller": "users::get"},
+        {"method": "DELETE", "path": "/users/:id", "description": "", "controller": "users::delete"},
+        {"method": "GET", "path": "/users/:id/orders/:order", "description": "",
+         "controller": "users::get"},
+        {"method": "GET", "path": "/files/*path", "description": "", "controller": "files::get"},
+        {"method": "DELETE", "path": "/teams", "description": "", "controller": "teams"},
+        {"method": "POST", "path": "/teams", "description": "", "controller": "teams"},
+        {"method": "GET", "path": "/teams", "description": "", "controller": "teams"}
+    ]
+}"#;
+
+/// Answers with the method, the path parameters sorted by name and the query
+/// of the request, and its body on the next line.
+struct Echo;
+
+impl HttpHandler for Echo {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let mut params: Vec<_> = req.params.iter().collect();
+        params.sort_unstable();
+        let params: Vec<_> = params
+            .into_iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect();
+        let mut echo = format!(
+            "{} [{}] {}\n",
+            req.method,
+            params.join(" "),
+            req.uri.query().unwrap_or("-")
+        );
+        echo.push_str(&String::from_utf8_lossy(&req.body));
+        HttpResponse::ok(echo)
+    }
+}
+
+struct Create;
+
+impl HttpHandler for Create {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        HttpResponse::created(r#"{"id":43}"#)
+    }
+}
+
+fn router() -> Router {
+    let mut router = Router::from_json_str(CONFIG).unwrap();
+    router.register_handler("users::list", Echo);
+    router.register_handler("users::get", Echo);
+    router.register_handler("users::create", Create);
+    router.register_handler("files::get", Echo);
+    router.register_handler("teams", Echo);
+    router
+}
+
+/// The name of a snapshot, with the method, target, headers and body of its
+/// request.
+type Case = (
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static [(&'static str, &'static str)],
+    &'static str,
+);
+
+const MATRIX: &[Case] = &[
+    ("list_users", "GET", "/users", &[], ""),
+    (
+        "list_users_with_query",
+        "GET",
+        "/users?limit=2&sort=name",
+        &[],
+        "",
+    ),
+    (
+        "create_user",
+        "POST",
+        "/users",
+        &[("content-type", "application/json")],
+        r#"{"name":"Ada"}"#,
+    ),
+    ("delete_users_not_allowed", "DELETE", "/users", &[], ""),
+    ("get_user", "GET", "/users/42", &[], ""),
+    ("get_user_encoded", "GET", "/users/a%20b", &[], ""),
+    ("head_user", "HEAD", "/users/42", &[], ""),
+    ("put_user", "PUT", "/users/42", &[], "new name"),
+    (
+        "delete_user_without_handler",
+        "DELETE",
+        "/users/42",
+        &[],
+        "",
+    ),
+    ("patch_user_not_allowed", "PATCH", "/users/42", &[], ""),
+    ("options_user", "OPTIONS", "/users/42", &[], ""),
+    ("unknown_method", "PURGE", "/users/42", &[], ""),
+    ("get_user_trailing_slash", "GET", "/users/42/", &[], ""),
+    ("get_user_order", "GET", "/users/42/orders/7", &[], ""),
+    (
+        "get_user_orders_not_found",
+        "GET",
+        "/users/42/orders",
+        &[],
+        "",
+    ),
+    ("rewritten_member", "GET", "/members/42", &[], ""),
+    ("get_file", "GET", "/files/readme.md", &[], ""),
+    (
+        "get_nested_file",
+        "GET",
+        "/files/docs/guide/intro.md",
+        &[],
+        "",
+    ),
+    ("head_file", "HEAD", "/files/readme.md", &[], ""),
+    ("post_file_not_allowed", "POST", "/files/readme.md", &[], ""),
+    ("files_root_not_found", "GET", "/files", &[], ""),
+    ("not_found", "GET", "/nope", &[], ""),
+    (
+        "not_found_for_json",
+        "GET",
+        "/nope",
+        &[("accept", "application/json")],
+        "",
+    ),
+    ("root_not_found", "GET", "/", &[], ""),
+    ("duplicate_slashes", "GET", "//users//42", &[], ""),
+    ("patch_teams_not_allowed", "PATCH", "/teams", &[], ""),
+];
+
+/// The cases routed with automatic `OPTIONS` responses enabled, listing
+/// `OPTIONS` in the `Allow` header.
+const AUTO_OPTIONS_MATRIX: &[Case] = &[
+    (
+        "patch_teams_not_allowed_auto_options",
+        "PATCH",
+        "/teams",
+        &[],
+        "",
+    ),
+    ("options_teams_auto_options", "OPTIONS", "/teams", &[], ""),
+];
+
+/// Routes each case of `cases` with `router`, and compares the response with
+/// its snapshot.
+fn assert_snapshots(router: &Router, cases: &[Case]) {
+    for &(name, method, target, headers, body) in cases {
+        let mut req = Request::builder().method(method).uri(target);
+        for &(header, value) in headers {
+            req = req.header(header, value);
+        }
+        let response = router.route(req.body(body.to_string()).unwrap());
+        assert_snapshot(
+            &format!("snapshots/{name}.txt"),
+            &render_response(&response),
+        );
+    }
+}
+
+#[test]
+fn responses_match_their_snapshots() {
+    assert_snapshots(&router(), MATRIX);
+}
+
+#[test]
+fn responses_with_auto_options_match_their_snapshots() {
+    let mut router = router();
+    router.set_auto_options(true);
+    assert_snapshots(&router, AUTO_OPTIONS_MATRIX);
+    // `allowed_methods` lists the same methods as the `Allow` header.
+    let methods: Vec<String> = router
+        .allowed_methods("/teams")
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(methods, ["DELETE", "GET", "HEAD", "OPTIONS", "POST"]);
+}
+
+#[test]
+fn every_snapshot_is_in_the_matrix() {
+    let dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "snapshots"]
+        .iter()
+        .collect();
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_stem().unwrap().to_str().unwrap();
+        assert!(
+            MATRIX
+                .iter()
+                .chain(AUTO_OPTIONS_MATRIX)
+                .any(|&(snapshot, ..)| snapshot == name),
+            "the snapshot {name} isn't taken any more"
+        );
+    }
+}
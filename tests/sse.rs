@@ -0,0 +1,84 @@
+use generic_http_router::{HttpResponse, SseEvent};
+use std::thread;
+use std::time::Duration;
+
+fn framed(event: SseEvent) -> String {
+    String::from_utf8(event.to_bytes().to_vec()).unwrap()
+}
+
+#[test]
+fn events_end_with_a_blank_line() {
+    assert_eq!(framed(SseEvent::data("hello")), "data: hello\n\n");
+    assert_eq!(
+        framed(
+            SseEvent::data("50%")
+                .event("progress")
+                .id("7")
+                .retry(Duration::from_secs(3))
+        ),
+        "event: progress\nid: 7\nretry: 3000\ndata: 50%\n\n"
+    );
+    assert_eq!(framed(SseEvent::data("")), "data:\n\n");
+}
+
+#[test]
+fn every_line_of_the_data_is_a_data_field() {
+    assert_eq!(
+        framed(SseEvent::data("one\ntwo\r\nthree\rfour")),
+        "data: one\ndata: two\ndata: three\ndata: four\n\n"
+    );
+    assert_eq!(
+        framed(SseEvent::data("trailing\n")),
+        "data: trailing\ndata:\n\n"
+    );
+}
+
+#[test]
+fn line_breaks_are_dropped_from_ids_and_event_types() {
+    assert_eq!(
+        framed(SseEvent::data("x").event("a\nb").id("1\r\n2")),
+        "event: ab\nid: 12\ndata: x\n\n"
+    );
+}
+
+#[test]
+fn comments_and_keep_alives() {
+    assert_eq!(framed(SseEvent::keep_alive()), ":\n\n");
+    assert_eq!(
+        framed(SseEvent::comment("two\nlines")),
+        ": two\n: lines\n\n"
+    );
+    // A comment with data is sent before it.
+    let mut event = SseEvent::data("x");
+    event.comment = Some("note".to_string());
+    assert_eq!(framed(event), ": note\ndata: x\n\n");
+}
+
+#[test]
+fn sse_responses_stream_their_events() {
+    let response = HttpResponse::sse(vec![SseEvent::data("a"), SseEvent::data("b").id("2")]);
+    assert_eq!(response.headers["content-type"], "text/event-stream");
+    assert_eq!(response.headers["cache-control"], "no-cache");
+    assert!(response.body.is_stream());
+    let body = response.body.collect(usize::MAX).unwrap();
+    assert_eq!(body, "data: a\n\nid: 2\ndata: b\n\n");
+}
+
+#[test]
+fn a_channel_feeds_the_response_until_the_sender_is_dropped() {
+    let (sender, response) = HttpResponse::sse_channel();
+    let producer = thread::spawn(move || {
+        for n in 0..3 {
+            sender.send(SseEvent::data(n.to_string())).unwrap();
+        }
+    });
+    let body = response.body.collect(usize::MAX).unwrap();
+    producer.join().unwrap();
+    assert_eq!(body, "data: 0\n\ndata: 1\n\ndata: 2\n\n");
+
+    // Once the response is dropped, sending gives the event back.
+    let (sender, response) = HttpResponse::sse_channel();
+    drop(response);
+    let event = SseEvent::data("late");
+    assert_eq!(sender.send(event.clone()), Err(event));
+}
@@ -0,0 +1,110 @@
+use bytes::Bytes;
+use generic_http_router::{Body, HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Request, StatusCode};
+use std::io;
+
+const CHUNKS: usize = 1000;
+
+/// Streams `chunk 0\n` to `chunk 999\n`, failing instead of sending the chunk
+/// `fail_at`, if any.
+struct Numbers {
+    fail_at: Option<usize>,
+}
+
+impl HttpHandler for Numbers {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        let fail_at = self.fail_at;
+        HttpResponse::streaming(
+            StatusCode::OK,
+            (0..CHUNKS).map(move |n| {
+                if Some(n) == fail_at {
+                    Err(io::Error::other(format!("failed at {n}")))
+                } else {
+                    Ok(Bytes::from(format!("chunk {n}\n")))
+                }
+            }),
+        )
+    }
+}
+
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/numbers", "description": "", "controller": "numbers"},
+            {"method": "GET", "path": "/failing", "description": "", "controller": "failing"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("numbers", Numbers { fail_at: None });
+    router.register_handler("failing", Numbers { fail_at: Some(500) });
+    router
+}
+
+fn get(path: &str) -> Request<Bytes> {
+    Request::get(path).body(Bytes::new()).unwrap()
+}
+
+fn expected(chunks: usize) -> String {
+    (0..chunks).map(|n| format!("chunk {n}\n")).collect()
+}
+
+#[test]
+fn streamed_chunks_arrive_in_order() {
+    let response = router().route_streaming(get("/numbers"));
+    assert_eq!(response.status(), StatusCode::OK);
+    let Body::Stream(chunks) = response.into_body() else {
+        panic!("the body isn't streamed");
+    };
+    let chunks: Vec<Bytes> = chunks.map(Result::unwrap).collect();
+    assert_eq!(chunks.len(), CHUNKS);
+    for (n, chunk) in chunks.iter().enumerate() {
+        assert_eq!(chunk, format!("chunk {n}\n").as_bytes());
+    }
+}
+
+#[test]
+fn an_error_mid_stream_reaches_the_server() {
+    let response = router().route_streaming(get("/failing"));
+    // The head is sent before the stream fails.
+    assert_eq!(response.status(), StatusCode::OK);
+    let Body::Stream(chunks) = response.into_body() else {
+        panic!("the body isn't streamed");
+    };
+    let chunks: Vec<io::Result<Bytes>> = chunks.collect();
+    assert_eq!(chunks.len(), CHUNKS);
+    assert!(chunks[..500].iter().all(Result::is_ok));
+    assert_eq!(
+        chunks[500].as_ref().unwrap_err().to_string(),
+        "failed at 500"
+    );
+}
+
+#[test]
+fn route_buffers_streams() {
+    let router = router();
+    let response = router.route(get("/numbers"));
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), expected(CHUNKS).as_bytes());
+
+    // A stream that fails can't be buffered.
+    let response = router.route(get("/failing"));
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(response.body().is_empty());
+}
+
+#[test]
+fn route_buffers_streams_up_to_a_limit() {
+    let mut router = router();
+    let size = expected(CHUNKS).len();
+    router.set_max_buffered_body_size(size);
+    assert_eq!(router.route(get("/numbers")).status(), StatusCode::OK);
+    router.set_max_buffered_body_size(size - 1);
+    let response = router.route(get("/numbers"));
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(response.body().is_empty());
+
+    // Streaming isn't limited.
+    let response = router.route_streaming(get("/numbers"));
+    let body = response.into_body().collect(usize::MAX).unwrap();
+    assert_eq!(body.len(), size);
+}
@@ -0,0 +1,125 @@
+use generic_http_router::{RequestSummary, Router, SuggestionTrigger};
+use http::header::HeaderName;
+use http::{Request, Response, StatusCode};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+/// A router with a few routes, problem responses, and suggestions if
+/// `enabled`, recording the summaries of the requests it routes.
+fn router(enabled: bool) -> (Router, Arc<Mutex<Vec<RequestSummary>>>) {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/users/:id/orders", "description": "", "controller": "c"},
+            {"method": "GET", "path": "/users/:id", "description": "", "controller": "c"},
+            {"method": "GET", "path": "/users/:id/order-history", "description": "", "controller": "c"},
+            {"method": "POST", "path": "/users/:id/orders", "description": "", "controller": "c"},
+            {"method": "GET", "path": "/products/:sku", "description": "", "controller": "c"},
+            {"method": "GET", "path": "/:a/:b/:c/:d/:e", "description": "", "controller": "c"}
+        ]}"#,
+    )
+    .unwrap();
+    router.use_problem_responses(true);
+    router.enable_404_suggestions(enabled);
+    let summaries = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&summaries);
+    router.on_response(move |summary| sink.lock().unwrap().push(summary.clone()));
+    (router, summaries)
+}
+
+fn get(router: &Router, path: &str, headers: &[(&str, &str)]) -> Response<bytes::Bytes> {
+    let mut req = Request::get(path);
+    for &(name, value) in headers {
+        req = req.header(name, value);
+    }
+    router.route(req.body(String::new()).unwrap())
+}
+
+/// Returns the `suggestions` of a 404 problem document, if it has any.
+fn suggestions(response: &Response<bytes::Bytes>) -> Option<Vec<String>> {
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let problem: Value = serde_json::from_slice(response.body()).unwrap();
+    serde_json::from_value(problem.get("suggestions")?.clone()).ok()
+}
+
+const JSON: &[(&str, &str)] = &[("accept", "application/json")];
+
+#[test]
+fn a_near_miss_suggests_the_closest_routes() {
+    let (router, summaries) = router(true);
+    let response = get(&router, "/users/123/order", JSON);
+    // Each pattern is suggested once, whatever its methods, and
+    // `/users/:id/order-history` is too far.
+    assert_eq!(suggestions(&response).unwrap(), ["/users/:id/orders"]);
+    assert_eq!(
+        summaries.lock().unwrap()[0].suggestions,
+        ["/users/:id/orders"]
+    );
+
+    let response = get(&router, "/prodcts/abc", JSON);
+    assert_eq!(suggestions(&response).unwrap(), ["/products/:sku"]);
+    // `/:a/:b/:c/:d/:e` has no literal segments, so it's never suggested.
+    let response = get(&router, "/a/b/c/d", JSON);
+    assert_eq!(suggestions(&response), None);
+}
+
+#[test]
+fn an_unrelated_path_gets_none() {
+    let (router, summaries) = router(true);
+    for path in ["/invoices/2024/03", "/a/b/c/d/e/f"] {
+        let response = get(&router, path, JSON);
+        assert_eq!(suggestions(&response), None, "{path}");
+    }
+    assert!(
+        summaries
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|summary| summary.suggestions.is_empty())
+    );
+    // Nor does a path too long to compare.
+    let long = format!("/users/1/order{}", "s".repeat(300));
+    assert_eq!(suggestions(&get(&router, &long, JSON)), None);
+}
+
+#[test]
+fn suggestions_are_off_by_default() {
+    let (router, summaries) = router(false);
+    let response = get(&router, "/users/123/order/x", &[("x-debug", "1")]);
+    assert_eq!(suggestions(&response), None);
+    let response = get(&router, "/users/123/order/x", JSON);
+    assert_eq!(suggestions(&response), None);
+    assert!(summaries.lock().unwrap()[1].suggestions.is_empty());
+}
+
+#[test]
+fn only_triggering_requests_get_suggestions() {
+    let (mut router, _) = router(true);
+    // By default, requests accepting JSON or with `X-Debug`.
+    for headers in [
+        JSON,
+        &[("accept", "application/problem+json")],
+        &[("accept", "text/html, application/json;q=0.5")],
+        &[("x-debug", "")],
+    ] {
+        let response = get(&router, "/users/123/order/x", headers);
+        assert!(suggestions(&response).is_some(), "{headers:?}");
+    }
+    for headers in [
+        &[][..],
+        &[("accept", "text/html")],
+        &[("accept", "application/json;q=0")],
+        &[("accept", "*/*")],
+    ] {
+        let response = get(&router, "/users/123/order/x", headers);
+        assert_eq!(suggestions(&response), None, "{headers:?}");
+    }
+
+    router.set_404_suggestion_trigger(SuggestionTrigger::Header(HeaderName::from_static(
+        "x-suggest",
+    )));
+    assert_eq!(suggestions(&get(&router, "/users/123/order/x", JSON)), None);
+    assert!(suggestions(&get(&router, "/users/123/order/x", &[("x-suggest", "1")])).is_some());
+
+    router.set_404_suggestion_trigger(SuggestionTrigger::Always);
+    assert!(suggestions(&get(&router, "/users/123/order/x", &[])).is_some());
+}
@@ -0,0 +1,119 @@
+use generic_http_router::Router;
+use generic_http_router::error::RouterError;
+use http::header::CONTENT_TYPE;
+use http::{Method, Request, Response};
+
+/// A router with a JSON, an HTML and a plain text template.
+fn router() -> Router {
+    Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/users/:id", "description": "", "controller": "",
+             "body_template": "{\"id\": \"{params.id}\", \"page\": {query.page|1}, \"host\": \"{headers.host}\"}",
+             "content_type": "application/json"},
+            {"method": "GET", "path": "/greeting/:name", "description": "", "controller": "",
+             "body_template": "<h1>Hello, {params.name}!</h1>", "content_type": "text/html; charset=utf-8"},
+            {"method": "GET", "path": "/echo/:word", "description": "", "controller": "",
+             "body_template": "{params.word} {query.lang|en} {query.missing}"}
+        ]}"#,
+    )
+    .unwrap()
+}
+
+fn get(router: &Router, target: &str) -> Response<bytes::Bytes> {
+    router.route(
+        Request::get(target)
+            .header("host", "api.example.com")
+            .body(String::new())
+            .unwrap(),
+    )
+}
+
+fn body(response: &Response<bytes::Bytes>) -> &str {
+    std::str::from_utf8(response.body()).unwrap()
+}
+
+#[test]
+fn json_templates_escape_values_as_json_strings() {
+    let router = router();
+    let response = get(&router, "/users/a%22b%5Cc?page=3");
+    assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+    assert_eq!(
+        body(&response),
+        r#"{"id": "a\"b\\c", "page": 3, "host": "api.example.com"}"#
+    );
+    let value: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(value["id"], r#"a"b\c"#);
+}
+
+#[test]
+fn a_missing_query_value_takes_its_default() {
+    let router = router();
+    let response = get(&router, "/users/7");
+    assert_eq!(
+        body(&response),
+        r#"{"id": "7", "page": 1, "host": "api.example.com"}"#
+    );
+    // A placeholder without a default renders as nothing.
+    let response = get(&router, "/echo/hi");
+    assert_eq!(
+        response.headers()[CONTENT_TYPE],
+        "text/plain; charset=utf-8"
+    );
+    assert_eq!(body(&response), "hi en ");
+    assert_eq!(body(&get(&router, "/echo/hi?lang=fr")), "hi fr ");
+}
+
+#[test]
+fn html_templates_escape_values_for_html() {
+    let router = router();
+    let response = get(&router, "/greeting/%3Cb%3E%26'%22");
+    assert_eq!(response.headers()[CONTENT_TYPE], "text/html; charset=utf-8");
+    assert_eq!(
+        body(&response),
+        "<h1>Hello, &lt;b&gt;&amp;&#39;&quot;!</h1>"
+    );
+    // Plain text isn't escaped.
+    assert_eq!(body(&get(&router, "/echo/%3Cb%3E")), "<b> en ");
+}
+
+fn load_error(template: &str) -> (Method, String, String) {
+    let config = serde_json::json!({"endpoints": [
+        {"method": "GET", "path": "/t", "description": "", "controller": "",
+         "body_template": template}
+    ]});
+    match Router::from_json_str(&config.to_string()) {
+        Err(RouterError::Endpoint {
+            method,
+            path,
+            reason,
+        }) => (method, path, reason),
+        other => panic!("{:?}", other.err()),
+    }
+}
+
+#[test]
+fn invalid_placeholders_are_load_errors() {
+    let (method, path, reason) = load_error("{cookies.session}");
+    assert_eq!((method, path.as_str()), (Method::GET, "/t"));
+    assert_eq!(
+        reason,
+        "invalid body_template: unknown placeholder '{cookies.session}': use params, query or headers"
+    );
+    let (_, _, reason) = load_error("{query.page");
+    assert_eq!(
+        reason,
+        "invalid body_template: placeholder '{query.page' has no closing '}'"
+    );
+    let (_, _, reason) = load_error("{headers.bad name}");
+    assert_eq!(
+        reason,
+        "invalid body_template: invalid header name in placeholder '{headers.bad name}'"
+    );
+    // Braces without a namespace are literal text.
+    let router = Router::from_json_str(
+        r#"{"endpoints": [{"method": "GET", "path": "/t", "description": "", "controller": "",
+            "body_template": "{} {\"a\": {}} {.x}"}]}"#,
+    )
+    .unwrap();
+    assert_eq!(body(&get(&router, "/t")), r#"{} {"a": {}} {.x}"#);
+}
@@ -0,0 +1,197 @@
+#![cfg(feature = "shared")]
+
+use generic_http_router::config::Endpoint;
+use generic_http_router::{
+    HeaderTenant, HttpHandler, HttpRequest, HttpResponse, MultiTenantRouter, Router, Tenant,
+};
+use http::{Method, Request, StatusCode};
+use std::sync::Arc;
+
+/// Answers with its name and the tenant the request was routed for.
+struct Named(&'static str);
+
+impl HttpHandler for Named {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let tenant = req
+            .extensions
+            .get::<Tenant>()
+            .map_or("-", |tenant| tenant.0.as_str());
+        HttpResponse::ok(format!("{} {tenant}", self.0))
+    }
+}
+
+/// A router with the endpoints `{method, path, controller}`, each controller
+/// answering with its name.
+fn router_with(endpoints: &[(&str, &str, &'static str)]) -> Router {
+    let config: Vec<_> = endpoints
+        .iter()
+        .map(|&(method, path, controller)| {
+            serde_json::json!({
+                "method": method, "path": path, "description": "", "controller": controller
+            })
+        })
+        .collect();
+    let mut router =
+        Router::from_json_str(&serde_json::json!({ "endpoints": config }).to_string()).unwrap();
+    for &(_, _, controller) in endpoints {
+        router.register_handler(controller, Named(controller));
+    }
+    router
+}
+
+/// The default router has `/:tenant/users` and `/:tenant/orders`, and the
+/// tenant `acme` overrides `/:tenant/users`.
+fn multi_tenant() -> MultiTenantRouter {
+    let router = MultiTenantRouter::new(router_with(&[
+        ("GET", "/:tenant/users", "default_users"),
+        ("GET", "/:tenant/orders", "default_orders"),
+    ]));
+    router.add_tenant(
+        "acme",
+        router_with(&[("GET", "/:tenant/users", "acme_users")]),
+    );
+    router
+}
+
+fn get(router: &MultiTenantRouter, path: &str) -> (StatusCode, String) {
+    send(router, Method::GET, path)
+}
+
+fn send(router: &MultiTenantRouter, method: Method, path: &str) -> (StatusCode, String) {
+    let response = router.route(
+        Request::builder()
+            .method(method)
+            .uri(path)
+            .body(String::new())
+            .unwrap(),
+    );
+    let body = String::from_utf8(response.body().to_vec()).unwrap();
+    (response.status(), body)
+}
+
+#[test]
+fn a_tenant_route_shadows_the_default_one() {
+    let router = multi_tenant();
+    assert_eq!(
+        get(&router, "/acme/users"),
+        (StatusCode::OK, "acme_users acme".into())
+    );
+    // Routes the tenant doesn't have fall back to the default router.
+    assert_eq!(
+        get(&router, "/acme/orders"),
+        (StatusCode::OK, "default_orders acme".into())
+    );
+    // So do methods it has no route for.
+    let mut router = multi_tenant();
+    router
+        .default_router()
+        .add_route(Endpoint::new(
+            Method::POST,
+            "/:tenant/users",
+            "default_users",
+        ))
+        .unwrap();
+    assert_eq!(
+        send(&router, Method::POST, "/acme/users"),
+        (StatusCode::OK, "default_users acme".into())
+    );
+    // Without a tenant, the default router is used.
+    router.set_tenant_extractor(|_: &Request<bytes::Bytes>| None);
+    assert_eq!(
+        get(&router, "/acme/users"),
+        (StatusCode::OK, "default_users -".into())
+    );
+}
+
+#[test]
+fn an_unknown_tenant_uses_the_default_router() {
+    let router = multi_tenant();
+    assert_eq!(
+        get(&router, "/globex/users"),
+        (StatusCode::OK, "default_users globex".into())
+    );
+    assert_eq!(get(&router, "/").0, StatusCode::NOT_FOUND);
+    assert_eq!(get(&router, "/globex/nowhere").0, StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn without_fallback_the_tenant_404_is_returned() {
+    let mut router = multi_tenant();
+    router.set_fallback_to_default(false);
+    assert_eq!(
+        get(&router, "/acme/users"),
+        (StatusCode::OK, "acme_users acme".into())
+    );
+    assert_eq!(get(&router, "/acme/orders").0, StatusCode::NOT_FOUND);
+    assert_eq!(
+        send(&router, Method::DELETE, "/acme/users").0,
+        StatusCode::METHOD_NOT_ALLOWED
+    );
+    // Tenants without a router still use the default one.
+    assert_eq!(
+        get(&router, "/globex/orders"),
+        (StatusCode::OK, "default_orders globex".into())
+    );
+}
+
+#[test]
+fn the_tenant_can_come_from_a_header() {
+    let mut router = MultiTenantRouter::new(router_with(&[("GET", "/users", "default_users")]));
+    router.add_tenant("acme", router_with(&[("GET", "/users", "acme_users")]));
+    router.set_tenant_extractor(HeaderTenant::default());
+    let route = |tenant: Option<&str>| {
+        let mut req = Request::get("/users");
+        if let Some(tenant) = tenant {
+            req = req.header("x-tenant-id", tenant);
+        }
+        let response = router.route(req.body(String::new()).unwrap());
+        String::from_utf8(response.body().to_vec()).unwrap()
+    };
+    assert_eq!(route(Some("acme")), "acme_users acme");
+    assert_eq!(route(Some(" acme ")), "acme_users acme");
+    assert_eq!(route(Some("globex")), "default_users globex");
+    assert_eq!(route(Some("")), "default_users -");
+    assert_eq!(route(None), "default_users -");
+}
+
+#[test]
+fn tenants_are_added_and_removed_at_runtime() {
+    let router = Arc::new(multi_tenant());
+    assert_eq!(router.tenants(), ["acme"]);
+    router.add_tenant(
+        "globex",
+        router_with(&[("GET", "/:tenant/orders", "globex_orders")]),
+    );
+    assert_eq!(router.tenants(), ["acme", "globex"]);
+    assert_eq!(get(&router, "/globex/orders").1, "globex_orders globex");
+
+    // A tenant's routes can be changed through its shared handle.
+    router
+        .tenant("globex")
+        .unwrap()
+        .add_route(Endpoint::new(
+            Method::GET,
+            "/:tenant/users",
+            "globex_orders",
+        ))
+        .unwrap();
+    assert_eq!(get(&router, "/globex/users").1, "globex_orders globex");
+
+    // From another thread too.
+    let other = Arc::clone(&router);
+    std::thread::spawn(move || assert!(other.remove_tenant("acme")))
+        .join()
+        .unwrap();
+    assert!(!router.remove_tenant("acme"));
+    assert!(router.tenant("acme").is_none());
+    assert_eq!(router.tenants(), ["globex"]);
+    assert_eq!(get(&router, "/acme/users").1, "default_users acme");
+
+    // Adding a tenant again replaces its router.
+    router.add_tenant(
+        "globex",
+        router_with(&[("GET", "/:tenant/orders", "globex_v2")]),
+    );
+    assert_eq!(get(&router, "/globex/orders").1, "globex_v2 globex");
+    assert_eq!(get(&router, "/globex/users").1, "default_users globex");
+}
@@ -0,0 +1,285 @@
+use generic_http_router::error::RouterError;
+use generic_http_router::testing::{
+    Expectations, Fixture, assert_response, check_response, load_fixture,
+};
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+use http::{Method, StatusCode};
+use serde_json::{Value, json};
+use std::path::Path;
+
+fn fixture_path(name: &str) -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+#[test]
+fn a_fixture_file_is_parsed() {
+    let fixture = load_fixture(fixture_path("create_user.http")).unwrap();
+    assert_eq!(fixture.method, Method::POST);
+    assert_eq!(fixture.uri, "/users?notify=true");
+    assert_eq!(fixture.headers["content-type"], "application/json");
+    assert_eq!(fixture.headers["x-request-id"], "fixture-1");
+    // The body keeps its line breaks, but not the trailing ones.
+    assert_eq!(
+        fixture.body,
+        "{\"name\": \"Ada\",\n \"roles\": [\"admin\"]}"
+    );
+
+    let req = fixture.http_request();
+    assert_eq!(req.uri.query(), Some("notify=true"));
+    assert_eq!(req.body, fixture.body);
+}
+
+#[test]
+fn crlf_line_endings_are_accepted() {
+    let fixture = Fixture::parse(
+        "GET /users/1 HTTP/1.1\r\nAccept: application/json\r\nX-Empty:\r\n\r\nline one\r\nline two\r\n",
+    )
+    .unwrap();
+    assert_eq!(fixture.method, Method::GET);
+    assert_eq!(fixture.headers["accept"], "application/json");
+    assert_eq!(fixture.headers["x-empty"], "");
+    assert_eq!(fixture.body, "line one\r\nline two");
+}
+
+#[test]
+fn a_fixture_may_have_no_body() {
+    for text in [
+        "DELETE /users/1",
+        "DELETE /users/1\nX-A: 1\n",
+        "DELETE /users/1\n\n\n",
+    ] {
+        let fixture = Fixture::parse(text).unwrap();
+        assert_eq!(fixture.method, Method::DELETE, "{text:?}");
+        assert!(fixture.body.is_empty(), "{text:?}");
+    }
+}
+
+#[test]
+fn a_base64_body_is_decoded() {
+    let fixture = load_fixture(fixture_path("upload.http")).unwrap();
+    assert_eq!(fixture.method, Method::PUT);
+    assert_eq!(fixture.uri, "/uploads/avatar.png");
+    assert_eq!(&fixture.body[..], b"\x89PNG\r\n\x1a\n\0\0\0\r");
+}
+
+#[test]
+fn malformed_fixtures_are_errors() {
+    for (text, line, reason) in [
+        ("# only a comment\n", 1, "no request line"),
+        ("GET\n", 1, "'GET' isn't a method followed by a path"),
+        (
+            "GET /a HTTP/1.1 extra\n",
+            1,
+            "'GET /a HTTP/1.1 extra' has more than a method, a path and a version",
+        ),
+        (
+            "# @body gzip\nGET /a\n",
+            1,
+            "unknown directive '@body gzip'",
+        ),
+        ("GET /a\nX-A: 1\nno colon\n", 3, "'no colon' isn't a header"),
+        (
+            "# @body base64\nPUT /a\n\n!!!\n",
+            4,
+            "the body isn't valid base64",
+        ),
+    ] {
+        match Fixture::parse(text) {
+            Err(RouterError::Fixture {
+                path: None,
+                line: actual_line,
+                reason: actual_reason,
+            }) => assert_eq!(
+                (actual_line, actual_reason.as_str()),
+                (line, reason),
+                "{text:?}"
+            ),
+            other => panic!("{text:?}: {other:?}"),
+        }
+    }
+
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("malformed.http");
+    std::fs::write(&path, "GET\n").unwrap();
+    let error = load_fixture(&path).unwrap_err();
+    assert_eq!(error.code(), "fixture.parse");
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "fixture.parse: {}: line 1: 'GET' isn't a method followed by a path",
+            path.display()
+        )
+    );
+    assert!(matches!(
+        load_fixture(fixture_path("missing.http")),
+        Err(RouterError::Io { path: Some(_), .. })
+    ));
+}
+
+/// Creates a user from the request's JSON body, with an `id` standing for a
+/// generated one.
+struct CreateUser;
+
+impl HttpHandler for CreateUser {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let mut user: Value = serde_json::from_slice(&req.body).unwrap();
+        user["id"] = json!("user-1");
+        user["notified"] = json!(req.uri.query() == Some("notify=true"));
+        let mut response = HttpResponse::new(StatusCode::CREATED, user.to_string());
+        response.add_header("content-type", "application/json");
+        response.add_header("cache-control", "max-age=60");
+        response
+    }
+}
+
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "POST", "path": "/users", "description": "", "controller": "create"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("create", CreateUser);
+    router
+}
+
+#[test]
+fn status_and_header_expectations() {
+    let router = router();
+    let fixture = load_fixture(fixture_path("create_user.http")).unwrap();
+    assert_response(
+        &router,
+        &fixture,
+        &Expectations::status(StatusCode::CREATED)
+            .with_header("content-type", "application/json")
+            .with_header_present("cache-control")
+            .without_header("set-cookie"),
+    );
+
+    let response = router.route(fixture.request());
+    let failures = check_response(
+        &response,
+        &Expectations::status(StatusCode::OK)
+            .with_header("content-type", "text/plain")
+            .with_header_present("etag")
+            .without_header("cache-control"),
+    );
+    assert_eq!(
+        failures,
+        [
+            "status: expected 200 OK, got 201 Created",
+            r#"header content-type: expected 'text/plain', got ["application/json"]"#,
+            "header etag: expected, missing",
+            r#"header cache-control: expected none, got ["max-age=60"]"#,
+        ]
+    );
+}
+
+#[test]
+fn exact_body_expectations() {
+    let router = router();
+    let fixture = Fixture::parse("POST /users\n\n{\"name\": \"Bo\"}").unwrap();
+    let body = r#"{"id":"user-1","name":"Bo","notified":false}"#;
+    assert_response(&router, &fixture, &Expectations::default().with_body(body));
+    let response = router.route(fixture.request());
+    assert_eq!(
+        check_response(&response, &Expectations::default().with_body("{}")),
+        [format!(r#"body: expected "{{}}", got {body:?}"#)]
+    );
+}
+
+#[test]
+fn json_subset_expectations_allow_wildcards() {
+    let router = router();
+    let fixture = load_fixture(fixture_path("create_user.http")).unwrap();
+    assert_response(
+        &router,
+        &fixture,
+        &Expectations::status(StatusCode::CREATED)
+            .with_json_subset(json!({"id": "*", "name": "Ada", "roles": ["admin"]})),
+    );
+    // Extra properties are allowed, but not extra array items.
+    let response = router.route(fixture.request());
+    let failures = check_response(
+        &response,
+        &Expectations::default().with_json_subset(json!({
+            "id": 1,
+            "name": "Bo",
+            "roles": ["admin", "*"],
+            "email": "*",
+            "notified": true
+        })),
+    );
+    assert_eq!(
+        failures,
+        [
+            r#"body/email: expected "*", missing"#,
+            r#"body/id: expected 1, got "user-1""#,
+            r#"body/name: expected "Bo", got "Ada""#,
+            r#"body/roles: expected ["admin","*"], got ["admin"]"#,
+        ]
+    );
+
+    let fixture = Fixture::parse("POST /nowhere").unwrap();
+    let response = router.route(fixture.request());
+    let failures = check_response(
+        &response,
+        &Expectations::default().with_json_subset(json!({})),
+    );
+    assert_eq!(failures.len(), 1);
+    assert!(
+        failures[0].starts_with("body: expected JSON, got "),
+        "{failures:?}"
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "POST /users?notify=true got an unexpected response:\n  status: expected 200 OK, got 201 Created"
+)]
+fn assert_response_panics_listing_the_failures() {
+    let fixture = load_fixture(fixture_path("create_user.http")).unwrap();
+    assert_response(&router(), &fixture, &Expectations::status(StatusCode::OK));
+}
+
+#[cfg(feature = "schema")]
+#[test]
+fn regex_and_schema_expectations() {
+    use generic_http_router::schema::Schema;
+
+    let router = router();
+    let fixture = load_fixture(fixture_path("create_user.http")).unwrap();
+    let schema = Schema::compile(json!({
+        "type": "object",
+        "required": ["id", "name"],
+        "properties": {"id": {"type": "string"}, "name": {"type": "string"}}
+    }))
+    .unwrap();
+    assert_response(
+        &router,
+        &fixture,
+        &Expectations::status(StatusCode::CREATED)
+            .with_header_matching("cache-control", r"^max-age=\d+$")
+            .with_schema(schema),
+    );
+
+    let strict = Schema::compile(json!({
+        "type": "object",
+        "properties": {"id": {"type": "integer"}}
+    }))
+    .unwrap();
+    let response = router.route(fixture.request());
+    let failures = check_response(
+        &response,
+        &Expectations::default()
+            .with_header_matching("content-type", "^text/")
+            .with_schema(strict),
+    );
+    assert_eq!(failures.len(), 2, "{failures:?}");
+    assert_eq!(
+        failures[0],
+        r#"header content-type: expected a match of '^text/', got ["application/json"]"#
+    );
+    assert!(failures[1].starts_with("body/id: "), "{failures:?}");
+}
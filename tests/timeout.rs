@@ -0,0 +1,102 @@
+#![cfg(feature = "async")]
+
+use generic_http_router::async_handler::AsyncFn;
+use generic_http_router::{HttpRequest, HttpResponse, Outcome, Router};
+use http::{Request, StatusCode, header};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A router with a `GET /slow` handler that never finishes in time, and a
+/// `GET /fast` one answering at once, both with a `timeout_ms` of 50.
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/slow", "description": "", "controller": "slow",
+             "timeout_ms": 50},
+            {"method": "GET", "path": "/fast", "description": "", "controller": "fast",
+             "timeout_ms": 50},
+            {"method": "GET", "path": "/default", "description": "", "controller": "slow"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler(
+        "slow",
+        AsyncFn(|_req: HttpRequest| async {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+            HttpResponse::ok("too late")
+        }),
+    );
+    router.register_handler(
+        "fast",
+        AsyncFn(|req: HttpRequest| async move {
+            assert!(req.deadline().is_some());
+            HttpResponse::ok("fast")
+        }),
+    );
+    router
+}
+
+async fn get(router: &Router, path: &str) -> http::Response<bytes::Bytes> {
+    router
+        .route_async(Request::get(path).body(String::new()).unwrap())
+        .await
+}
+
+#[tokio::test]
+async fn a_slow_handler_gets_a_504() {
+    let mut router = router();
+    let outcomes = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&outcomes);
+    router.on_response(move |summary| seen.lock().unwrap().push(summary.outcome));
+
+    let response = get(&router, "/slow").await;
+    assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "application/problem+json"
+    );
+    let problem: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(problem["status"], 504);
+    assert_eq!(*outcomes.lock().unwrap(), [Outcome::TimedOut]);
+}
+
+#[tokio::test]
+async fn a_fast_handler_is_unaffected() {
+    let mut router = router();
+    let outcomes = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&outcomes);
+    router.on_response(move |summary| seen.lock().unwrap().push(summary.outcome));
+
+    let response = get(&router, "/fast").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body().as_ref(), b"fast");
+    assert_eq!(*outcomes.lock().unwrap(), [Outcome::Completed]);
+}
+
+#[tokio::test]
+async fn the_default_timeout_applies_to_routes_without_one() {
+    let mut router = router();
+    router.set_default_timeout(Some(Duration::from_millis(50)));
+    assert_eq!(
+        get(&router, "/default").await.status(),
+        StatusCode::GATEWAY_TIMEOUT
+    );
+}
+
+#[tokio::test]
+async fn a_timeout_too_large_for_a_deadline_is_no_timeout() {
+    let mut router = router();
+    router.set_default_timeout(Some(Duration::MAX));
+    let response = get(&router, "/fast").await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    router.register_handler(
+        "slow",
+        AsyncFn(|req: HttpRequest| async move {
+            assert_eq!(req.deadline(), None);
+            HttpResponse::ok("no deadline")
+        }),
+    );
+    let response = get(&router, "/default").await;
+    assert_eq!(response.body().as_ref(), b"no deadline");
+}
@@ -0,0 +1,144 @@
+use generic_http_router::testing::MockClock;
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, RequestSummary, Router};
+use http::{Request, Response, StatusCode};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Takes the number of microseconds of its `micros` parameter, by advancing
+/// the router's clock.
+struct Work(MockClock);
+
+impl HttpHandler for Work {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let micros = req.params.get("micros").unwrap().parse().unwrap();
+        self.0.advance(Duration::from_micros(micros));
+        HttpResponse::ok("done")
+    }
+}
+
+/// Sets its own `X-Response-Time-Ms`.
+struct Timed;
+
+impl HttpHandler for Timed {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        let mut response = HttpResponse::ok("timed");
+        response.add_header("x-response-time-ms", "upstream");
+        response
+    }
+}
+
+fn router() -> (Router, MockClock) {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/work/:micros", "description": "", "controller": "work"},
+            {"method": "GET", "path": "/timed", "description": "", "controller": "timed"}
+        ]}"#,
+    )
+    .unwrap();
+    let clock = MockClock::new();
+    router.set_clock(clock.clone());
+    router.register_handler("work", Work(clock.clone()));
+    router.register_handler("timed", Timed);
+    (router, clock)
+}
+
+fn get(router: &Router, path: &str) -> Response<bytes::Bytes> {
+    router.route(Request::get(path).body(String::new()).unwrap())
+}
+
+fn timing(response: &Response<bytes::Bytes>) -> Option<&str> {
+    response
+        .headers()
+        .get("x-response-time-ms")
+        .map(|value| value.to_str().unwrap())
+}
+
+/// Records the summaries of requests taking at least `threshold`.
+fn record_slow(router: &mut Router, threshold: Duration) -> Arc<Mutex<Vec<RequestSummary>>> {
+    let slow = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&slow);
+    router.on_slow_request(threshold, move |summary| {
+        sink.lock().unwrap().push(summary.clone())
+    });
+    slow
+}
+
+#[test]
+fn the_timing_header_is_off_by_default() {
+    let (router, _) = router();
+    assert_eq!(timing(&get(&router, "/work/1500")), None);
+}
+
+#[test]
+fn the_timing_header_has_fractional_milliseconds() {
+    let (mut router, _) = router();
+    router.enable_timing_header(true);
+    assert_eq!(timing(&get(&router, "/work/12345")), Some("12.345"));
+    assert_eq!(timing(&get(&router, "/work/7")), Some("0.007"));
+    assert_eq!(timing(&get(&router, "/work/2000000")), Some("2000.000"));
+    // Built-in error responses are timed too.
+    let response = get(&router, "/nowhere");
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(timing(&response), Some("0.000"));
+
+    router.enable_timing_header(false);
+    assert_eq!(timing(&get(&router, "/work/7")), None);
+}
+
+#[test]
+fn a_handlers_timing_header_is_kept() {
+    let (mut router, _) = router();
+    router.enable_timing_header(true);
+    let response = get(&router, "/timed");
+    let values: Vec<_> = response
+        .headers()
+        .get_all("x-response-time-ms")
+        .iter()
+        .collect();
+    assert_eq!(values, ["upstream"]);
+}
+
+#[test]
+fn slow_requests_are_reported_with_their_summary() {
+    let (mut router, _) = router();
+    let slow = record_slow(&mut router, Duration::from_millis(100));
+    get(&router, "/work/99999");
+    assert!(slow.lock().unwrap().is_empty());
+    // At the threshold, and over it.
+    get(&router, "/work/100000");
+    get(&router, "/work/250000");
+    let slow = slow.lock().unwrap();
+    let reported: Vec<_> = slow
+        .iter()
+        .map(|summary| (summary.path.as_str(), summary.elapsed, summary.status))
+        .collect();
+    assert_eq!(
+        reported,
+        [
+            ("/work/100000", Duration::from_millis(100), StatusCode::OK),
+            ("/work/250000", Duration::from_millis(250), StatusCode::OK),
+        ]
+    );
+    assert_eq!(slow[0].pattern.as_deref(), Some("/work/:micros"));
+}
+
+#[test]
+fn built_in_error_responses_are_measured() {
+    let (mut router, _) = router();
+    let slow = record_slow(&mut router, Duration::ZERO);
+    get(&router, "/nowhere");
+    let slow = slow.lock().unwrap();
+    assert_eq!(slow.len(), 1);
+    assert_eq!(slow[0].status, StatusCode::NOT_FOUND);
+    assert_eq!(slow[0].elapsed, Duration::ZERO);
+}
+
+#[test]
+fn the_header_and_the_callback_measure_the_same_time() {
+    let (mut router, _) = router();
+    router.enable_timing_header(true);
+    let slow = record_slow(&mut router, Duration::ZERO);
+    let response = get(&router, "/work/4321");
+    assert_eq!(timing(&response), Some("4.321"));
+    assert_eq!(slow.lock().unwrap()[0].elapsed, Duration::from_micros(4321));
+}
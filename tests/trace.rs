@@ -0,0 +1,282 @@
+use generic_http_router::testing::SeededRng;
+use generic_http_router::{HttpRequest, HttpResponse, TraceContext};
+use http::HeaderMap;
+
+const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+fn request(headers: &[(&str, &str)]) -> HttpRequest {
+    let mut builder = HttpRequest::builder();
+    for (name, value) in headers {
+        builder = builder.header(*name, *value);
+    }
+    builder.build().unwrap()
+}
+
+fn traceparent_of(headers: &HeaderMap) -> Vec<&str> {
+    headers
+        .get_all("traceparent")
+        .iter()
+        .map(|value| value.to_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn a_valid_header_round_trips() {
+    let context = TraceContext::parse(TRACEPARENT).unwrap();
+    assert_eq!(
+        context,
+        TraceContext {
+            version: 0,
+            trace_id: [
+                0x4b, 0xf9, 0x2f, 0x35, 0x77, 0xb3, 0x4d, 0xa6, 0xa3, 0xce, 0x92, 0x9d, 0x0e, 0x0e,
+                0x47, 0x36
+            ],
+            parent_id: [0x00, 0xf0, 0x67, 0xaa, 0x0b, 0xa9, 0x02, 0xb7],
+            flags: 1,
+            tracestate: None,
+        }
+    );
+    assert!(context.sampled());
+    assert_eq!(context.trace_id_hex(), "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_eq!(context.traceparent(), TRACEPARENT);
+
+    let unsampled = TraceContext::parse(&TRACEPARENT.replace("-01", "-00")).unwrap();
+    assert!(!unsampled.sampled());
+    assert_eq!(unsampled.traceparent(), TRACEPARENT.replace("-01", "-00"));
+    // Surrounding spaces and tabs are ignored.
+    assert_eq!(
+        TraceContext::parse(&format!(" \t{TRACEPARENT} ")).unwrap(),
+        context
+    );
+}
+
+#[test]
+fn requests_carry_their_context_with_the_tracestate() {
+    let req = request(&[
+        ("traceparent", TRACEPARENT),
+        ("tracestate", "congo=t61rcWkgMzE"),
+        ("tracestate", " rojo=00f067aa0ba902b7 "),
+        ("tracestate", ""),
+    ]);
+    let context = req.trace_context().unwrap();
+    assert_eq!(context.trace_id_hex(), "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_eq!(
+        context.tracestate.as_deref(),
+        Some("congo=t61rcWkgMzE,rojo=00f067aa0ba902b7")
+    );
+    assert_eq!(request(&[]).trace_context(), None);
+    // A `tracestate` alone isn't a context.
+    assert_eq!(request(&[("tracestate", "congo=1")]).trace_context(), None);
+    // Nor are several `traceparent` headers.
+    assert_eq!(
+        request(&[("traceparent", TRACEPARENT), ("traceparent", TRACEPARENT)]).trace_context(),
+        None
+    );
+}
+
+#[test]
+fn malformed_headers_are_ignored() {
+    for traceparent in [
+        "",
+        "00",
+        // Uppercase hex digits.
+        "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+        // All-zero IDs.
+        "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+        // Fields of the wrong length.
+        "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-1",
+        "0-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        // Not hex.
+        "00-4bf92f3577b34da6a3ce929d0e0e473g-00f067aa0ba902b7-01",
+        "zz-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        // Other separators.
+        "00_4bf92f3577b34da6a3ce929d0e0e4736_00f067aa0ba902b7_01",
+        // Version 0 with more fields.
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+        // The invalid version.
+        "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01é",
+    ] {
+        assert_eq!(TraceContext::parse(traceparent), None, "{traceparent:?}");
+        assert_eq!(
+            request(&[("traceparent", traceparent)]).trace_context(),
+            None,
+            "{traceparent:?}"
+        );
+    }
+}
+
+#[test]
+fn later_versions_are_read_as_version_0() {
+    let context =
+        TraceContext::parse("cc-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+    assert_eq!(context.version, 0xcc);
+    assert_eq!(context.traceparent(), TRACEPARENT);
+
+    // With fields added after a `-`, ignored.
+    let context = TraceContext::parse(
+        "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-09-what-the-future-holds",
+    )
+    .unwrap();
+    assert_eq!(context.version, 1);
+    assert_eq!(context.flags, 9);
+    assert_eq!(
+        context.traceparent(),
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-09"
+    );
+    // But not run on from the flags.
+    assert_eq!(
+        TraceContext::parse("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01x"),
+        None
+    );
+}
+
+#[test]
+fn a_child_span_stays_in_the_trace() {
+    let parent = request(&[("traceparent", TRACEPARENT), ("tracestate", "congo=1")])
+        .trace_context()
+        .unwrap();
+    let rng = SeededRng::new(7);
+    let child = parent.child(&rng);
+    assert_eq!(child.trace_id, parent.trace_id);
+    assert_eq!(child.flags, parent.flags);
+    assert_eq!(child.tracestate, parent.tracestate);
+    assert_ne!(child.parent_id, parent.parent_id);
+    assert_ne!(child.parent_id, [0; 8]);
+    // Each child gets its own span, and the same seed the same ones.
+    let sibling = parent.child(&rng);
+    assert_ne!(sibling.parent_id, child.parent_id);
+    assert_eq!(parent.child(&SeededRng::new(7)), child);
+
+    // A later version's child is version 0.
+    let future = TraceContext::parse(&TRACEPARENT.replacen("00", "05", 1)).unwrap();
+    assert_eq!(future.child(&rng).version, 0);
+}
+
+#[test]
+fn contexts_are_written_to_outgoing_headers() {
+    let mut context = TraceContext::new(&SeededRng::new(1));
+    assert_eq!(context.version, 0);
+    assert!(context.sampled());
+    assert_ne!(context.trace_id, [0; 16]);
+
+    let mut headers = HeaderMap::new();
+    headers.insert("traceparent", "stale".parse().unwrap());
+    headers.insert("tracestate", "stale=1".parse().unwrap());
+    context.write_headers(&mut headers);
+    assert_eq!(traceparent_of(&headers), [context.traceparent().as_str()]);
+    assert!(headers.get("tracestate").is_none());
+    assert_eq!(TraceContext::from_headers(&headers).unwrap(), context);
+
+    context.tracestate = Some("congo=1".to_string());
+    let mut response = HttpResponse::ok("");
+    response.set_trace_context(&context);
+    assert_eq!(
+        traceparent_of(&response.headers),
+        [context.traceparent().as_str()]
+    );
+    assert_eq!(response.headers["tracestate"], "congo=1");
+}
+
+#[cfg(feature = "logging")]
+mod logging {
+    use super::TRACEPARENT;
+    use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, Router};
+    use http::Request;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// Records the `trace_id` fields of the `request` spans.
+    #[derive(Default)]
+    struct TraceIds {
+        next: AtomicU64,
+        requests: Mutex<Vec<u64>>,
+        trace_ids: Arc<Mutex<Vec<(u64, String)>>>,
+    }
+
+    struct TraceId<'a>(&'a mut Option<String>);
+
+    impl Visit for TraceId<'_> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "trace_id" {
+                *self.0 = Some(value.to_string());
+            }
+        }
+
+        fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+    }
+
+    impl Subscriber for TraceIds {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let id = self.next.fetch_add(1, Ordering::SeqCst) + 1;
+            if span.metadata().name() == "request" {
+                self.requests.lock().unwrap().push(id);
+            }
+            Id::from_u64(id)
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            let mut trace_id = None;
+            values.record(&mut TraceId(&mut trace_id));
+            if let Some(trace_id) = trace_id {
+                self.trace_ids
+                    .lock()
+                    .unwrap()
+                    .push((span.into_u64(), trace_id));
+            }
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    struct Ok;
+
+    impl HttpHandler for Ok {
+        fn handle(&self, _req: HttpRequest) -> HttpResponse {
+            HttpResponse::ok("ok")
+        }
+    }
+
+    #[test]
+    fn the_request_span_records_the_trace_id() {
+        let mut router = Router::from_json_str(
+            r#"{"endpoints": [
+                {"method": "GET", "path": "/", "description": "", "controller": "ok"}
+            ]}"#,
+        )
+        .unwrap();
+        router.register_handler("ok", Ok);
+        let subscriber = Arc::new(TraceIds::default());
+        tracing::subscriber::with_default(Arc::clone(&subscriber), || {
+            let traced = Request::get("/")
+                .header("traceparent", TRACEPARENT)
+                .body(String::new())
+                .unwrap();
+            router.route(traced);
+            router.route(Request::get("/").body(String::new()).unwrap());
+        });
+        let requests = subscriber.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        // Only the request with a `traceparent` has a trace ID.
+        assert_eq!(
+            *subscriber.trace_ids.lock().unwrap(),
+            [(requests[0], "4bf92f3577b34da6a3ce929d0e0e4736".to_string())]
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use generic_http_router::{HttpHandler, HttpRequest, HttpResponse, RequestSummary, Router};
+use http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use http::{HeaderMap, HeaderValue, Request, Response, StatusCode};
+use std::sync::{Arc, Mutex};
+
+/// Answers with a body of the given content type, with a `Content-Length`.
+struct Page(&'static str, &'static str);
+
+impl HttpHandler for Page {
+    fn handle(&self, _req: HttpRequest) -> HttpResponse {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static(self.0));
+        headers.insert(CONTENT_LENGTH, HeaderValue::from(self.1.len()));
+        HttpResponse::with_headers(StatusCode::OK, headers, self.1)
+    }
+}
+
+const BANNER: &str = "<div>Compliance banner</div>";
+
+/// Appends the banner to HTML bodies only.
+fn banner(_summary: &RequestSummary, mut response: HttpResponse) -> HttpResponse {
+    let html = response
+        .headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"));
+    if html && let Some(body) = response.body.as_bytes() {
+        let mut body = body.to_vec();
+        body.extend_from_slice(BANNER.as_bytes());
+        response.body = body.into();
+    }
+    response
+}
+
+/// Adds the compliance header to every response.
+fn compliance(_summary: &RequestSummary, mut response: HttpResponse) -> HttpResponse {
+    response.add_header("x-compliance", "reviewed");
+    response
+}
+
+fn router() -> Router {
+    let mut router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/page", "description": "", "controller": "page"},
+            {"method": "GET", "path": "/data", "description": "", "controller": "data"}
+        ]}"#,
+    )
+    .unwrap();
+    router.register_handler("page", Page("text/html; charset=utf-8", "<p>Hi</p>"));
+    router.register_handler("data", Page("application/json", "{}"));
+    router
+}
+
+fn get(router: &Router, path: &str) -> Response<bytes::Bytes> {
+    router.route(Request::get(path).body(String::new()).unwrap())
+}
+
+#[test]
+fn a_transformer_appends_to_html_bodies_only() {
+    let mut router = router();
+    router.add_response_transformer(Box::new(banner));
+    let response = get(&router, "/page");
+    let expected = format!("<p>Hi</p>{BANNER}");
+    assert_eq!(response.body(), expected.as_str());
+    // The length is updated after the transformers.
+    assert_eq!(
+        response.headers()[CONTENT_LENGTH],
+        expected.len().to_string()
+    );
+
+    let response = get(&router, "/data");
+    assert_eq!(response.body(), "{}");
+    assert_eq!(response.headers()[CONTENT_LENGTH], "2");
+
+    // `HEAD` responses keep the length of the `GET` response, without a body.
+    let response = router.route(Request::head("/page").body(String::new()).unwrap());
+    assert!(response.body().is_empty());
+    assert_eq!(
+        response.headers()[CONTENT_LENGTH],
+        "<p>Hi</p>".len().to_string()
+    );
+}
+
+#[test]
+fn a_transformer_adds_a_header_to_every_response() {
+    let mut router = router();
+    router.add_response_transformer(Box::new(compliance));
+    for (path, status) in [
+        ("/page", StatusCode::OK),
+        ("/data", StatusCode::OK),
+        // The router's own responses too.
+        ("/nowhere", StatusCode::NOT_FOUND),
+    ] {
+        let response = get(&router, path);
+        assert_eq!(response.status(), status, "{path}");
+        assert_eq!(response.headers()["x-compliance"], "reviewed", "{path}");
+    }
+    let response = router.route(Request::post("/page").body(String::new()).unwrap());
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers()["x-compliance"], "reviewed");
+}
+
+#[test]
+fn transformers_run_in_the_order_they_were_added() {
+    let mut router = router();
+    let order = Arc::new(Mutex::new(Vec::new()));
+    for name in ["first", "second"] {
+        let order = Arc::clone(&order);
+        router.add_response_transformer(Box::new(
+            move |_: &RequestSummary, mut response: HttpResponse| {
+                order.lock().unwrap().push(name);
+                response
+                    .headers
+                    .append("x-order", HeaderValue::from_static(name));
+                response
+            },
+        ));
+    }
+    // Each one gets the response of the previous one.
+    router.add_response_transformer(Box::new(banner));
+    router.add_response_transformer(Box::new(compliance));
+    let response = get(&router, "/page");
+    assert_eq!(*order.lock().unwrap(), ["first", "second"]);
+    let values: Vec<_> = response.headers().get_all("x-order").iter().collect();
+    assert_eq!(values, ["first", "second"]);
+    assert_eq!(response.body(), format!("<p>Hi</p>{BANNER}").as_str());
+    assert_eq!(response.headers()["x-compliance"], "reviewed");
+}
+
+#[test]
+fn a_later_transformer_sees_the_changes_of_an_earlier_one() {
+    let mut router = router();
+    // The first one turns JSON into HTML, so the banner is appended to it.
+    router.add_response_transformer(Box::new(
+        |_: &RequestSummary, mut response: HttpResponse| {
+            response
+                .headers
+                .insert(CONTENT_TYPE, HeaderValue::from_static("text/html"));
+            response
+        },
+    ));
+    router.add_response_transformer(Box::new(banner));
+    assert_eq!(
+        get(&router, "/data").body(),
+        format!("{{}}{BANNER}").as_str()
+    );
+
+    // In the other order, it isn't.
+    let mut router = self::router();
+    router.add_response_transformer(Box::new(banner));
+    router.add_response_transformer(Box::new(
+        |_: &RequestSummary, mut response: HttpResponse| {
+            response
+                .headers
+                .insert(CONTENT_TYPE, HeaderValue::from_static("text/html"));
+            response
+        },
+    ));
+    assert_eq!(get(&router, "/data").body(), "{}");
+}
+
+#[test]
+fn transformers_can_change_the_status_and_see_the_summary() {
+    let mut router = router();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&seen);
+    router.add_response_transformer(Box::new(
+        move |summary: &RequestSummary, mut response: HttpResponse| {
+            sink.lock().unwrap().push((
+                summary.path.clone(),
+                summary.pattern.clone(),
+                summary.status,
+            ));
+            if response.status == StatusCode::NOT_FOUND {
+                response.status = StatusCode::GONE;
+            }
+            response
+        },
+    ));
+    let statuses = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&statuses);
+    router.on_response(move |summary| sink.lock().unwrap().push(summary.status));
+
+    assert_eq!(get(&router, "/nowhere").status(), StatusCode::GONE);
+    assert_eq!(get(&router, "/page").status(), StatusCode::OK);
+    assert_eq!(
+        *seen.lock().unwrap(),
+        [
+            ("/nowhere".to_string(), None, StatusCode::NOT_FOUND),
+            (
+                "/page".to_string(),
+                Some("/page".to_string()),
+                StatusCode::OK
+            ),
+        ]
+    );
+    // The `on_response` hook sees the transformed response.
+    assert_eq!(
+        *statuses.lock().unwrap(),
+        [StatusCode::GONE, StatusCode::OK]
+    );
+}
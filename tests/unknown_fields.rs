@@ -0,0 +1,111 @@
+use generic_http_router::Router;
+use generic_http_router::error::{LoadWarning, RouterError};
+use serde_json::json;
+use std::path::PathBuf;
+
+/// The second endpoint has a misspelled `guards`, and both have `x-` fields.
+const TYPO: &str = r#"{"endpoints": [
+    {"method": "GET", "path": "/users", "description": "", "controller": "users::list",
+     "x-owner": "identity", "x-slo": {"p99_ms": 250}},
+    {"method": "POST", "path": "/users", "description": "", "controller": "users::create",
+     "gaurds": ["require_json"], "x-owner": "identity"}
+]}"#;
+
+/// Writes `json` to a temp file named `name`, and returns its path.
+fn config(name: &str, json: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("unknown_{name}.json"));
+    std::fs::write(&path, json).unwrap();
+    path
+}
+
+fn typo_warning() -> LoadWarning {
+    LoadWarning::UnknownField {
+        index: 1,
+        path: "/users".to_string(),
+        field: "gaurds".to_string(),
+        suggestion: Some("guards".to_string()),
+    }
+}
+
+#[test]
+fn unknown_endpoint_fields_are_warnings() {
+    let (router, warnings) = Router::new_with_report(config("warn", TYPO)).unwrap();
+    assert_eq!(warnings, [typo_warning()]);
+    assert_eq!(
+        warnings[0].to_string(),
+        "Unknown field 'gaurds' of endpoint 1 (/users) is ignored, did you mean 'guards'?"
+    );
+    // The endpoint is loaded, without the field.
+    let create = router
+        .routes()
+        .find(|endpoint| endpoint.controller == "users::create")
+        .unwrap();
+    assert!(create.guards.is_empty());
+    assert!(!create.metadata.contains_key("gaurds"));
+
+    // A field unlike any known one has no suggestion.
+    let json = TYPO.replace("gaurds", "zzzzzzzz");
+    let (_, warnings) = Router::new_with_report(config("unlike", &json)).unwrap();
+    assert!(matches!(
+        &warnings[..],
+        [LoadWarning::UnknownField { field, suggestion: None, .. }] if field == "zzzzzzzz"
+    ));
+}
+
+#[test]
+fn strict_config_makes_them_errors() {
+    let path = config("strict", TYPO);
+    let (mut router, _) = Router::new_with_report(&path).unwrap();
+    assert_eq!(router.reload(&path).unwrap(), [typo_warning()]);
+
+    router.set_strict_config(true);
+    let error = router.reload(&path).unwrap_err();
+    assert_eq!(error.code(), "config.unknown_field");
+    assert!(
+        matches!(
+            &error,
+            RouterError::UnknownField { pointer, field, suggestion }
+                if pointer == "/endpoints/1"
+                && field == "gaurds"
+                && suggestion.as_deref() == Some("guards")
+        ),
+        "{error}"
+    );
+    // The routes loaded before are kept.
+    assert_eq!(router.routes().count(), 2);
+
+    // A configuration without unknown fields reloads.
+    let fixed = config("strict_fixed", &TYPO.replace("gaurds", "guards"));
+    assert!(router.reload(fixed).unwrap().is_empty());
+}
+
+#[test]
+fn x_fields_are_exempt_and_kept_as_metadata() {
+    let json = TYPO.replace(r#""gaurds": ["require_json"], "#, "");
+    let (router, warnings) = Router::new_with_report(config("x", &json)).unwrap();
+    assert!(warnings.is_empty(), "{warnings:?}");
+    let list = router
+        .routes()
+        .find(|endpoint| endpoint.controller == "users::list")
+        .unwrap();
+    assert_eq!(list.metadata["x-owner"], "identity");
+    assert_eq!(list.metadata["x-slo"], json!({"p99_ms": 250}));
+
+    // Even in strict mode.
+    let mut router = router;
+    router.set_strict_config(true);
+    assert!(router.reload(config("x_strict", &json)).unwrap().is_empty());
+}
+
+#[test]
+fn unknown_fields_outside_endpoints_are_always_errors() {
+    let json = r#"{"endpoints": [], "rewrits": []}"#;
+    let error = Router::new_with_report(config("top", json)).err().unwrap();
+    assert_eq!(error.code(), "config.unknown_field");
+    assert!(
+        error.to_string().contains(
+            "field 'rewrits' at the top level of the configuration, did you mean 'rewrites'?"
+        ),
+        "{error}"
+    );
+}
@@ -0,0 +1,101 @@
+use generic_http_router::HttpResponse;
+use http::header::{ACCEPT, ACCEPT_ENCODING, ACCEPT_LANGUAGE, COOKIE, VARY};
+
+/// The `Vary` header values of `response`.
+fn vary(response: &HttpResponse) -> Vec<&str> {
+    response
+        .headers
+        .get_all(VARY)
+        .iter()
+        .map(|value| value.to_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn add_vary_starts_and_extends_the_header() {
+    let mut response = HttpResponse::ok("");
+    response.add_vary(ACCEPT_LANGUAGE);
+    assert_eq!(vary(&response), ["accept-language"]);
+    response.add_vary(COOKIE);
+    assert_eq!(vary(&response), ["accept-language, cookie"]);
+}
+
+#[test]
+fn compression_merges_with_a_handlers_vary() {
+    let mut response = HttpResponse::ok("");
+    response.add_header(VARY, "Accept");
+    response.add_vary(ACCEPT_ENCODING);
+    assert_eq!(vary(&response), ["Accept, accept-encoding"]);
+    // Adding it again, or `Accept` in another case, changes nothing.
+    response.add_vary(ACCEPT_ENCODING).add_vary(ACCEPT);
+    assert_eq!(vary(&response), ["Accept, accept-encoding"]);
+}
+
+#[test]
+fn several_vary_headers_are_merged_into_one() {
+    let mut response = HttpResponse::ok("");
+    response
+        .headers
+        .append(VARY, "Accept,  Cookie".parse().unwrap());
+    response
+        .headers
+        .append(VARY, "ACCEPT-LANGUAGE, ".parse().unwrap());
+    response.add_vary(ACCEPT_ENCODING);
+    assert_eq!(
+        vary(&response),
+        ["Accept, Cookie, ACCEPT-LANGUAGE, accept-encoding"]
+    );
+}
+
+#[test]
+fn nothing_is_added_to_vary_star() {
+    let mut response = HttpResponse::ok("");
+    response.add_header(VARY, "*");
+    response.add_vary(ACCEPT_ENCODING);
+    assert_eq!(vary(&response), ["*"]);
+}
+
+#[test]
+fn vary_on_adds_each_name_once() {
+    let mut response = HttpResponse::ok("");
+    response.add_header(VARY, "accept-encoding");
+    response.vary_on(&[ACCEPT, ACCEPT_ENCODING, ACCEPT_LANGUAGE, ACCEPT]);
+    assert_eq!(
+        vary(&response),
+        ["accept-encoding, accept, accept-language"]
+    );
+    response.vary_on(&[]);
+    assert_eq!(vary(&response).len(), 1);
+}
+
+#[cfg(feature = "files")]
+#[test]
+fn precompressed_static_files_vary_on_accept_encoding() {
+    use generic_http_router::Router;
+    use http::Request;
+    use std::path::PathBuf;
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("vary_static");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("app.js"), "plain").unwrap();
+    std::fs::write(dir.join("app.js.gz"), "gzipped").unwrap();
+    let router = Router::from_json_str(&format!(
+        r#"{{"endpoints": [
+            {{"method": "GET", "path": "/assets/*file", "description": "", "controller": "",
+              "static_files": {{"dir": {:?}}}}}
+        ]}}"#,
+        dir.to_str().unwrap()
+    ))
+    .unwrap();
+
+    for accept_encoding in ["gzip", "identity"] {
+        let response = router.route(
+            Request::get("/assets/app.js")
+                .header("accept-encoding", accept_encoding)
+                .body(String::new())
+                .unwrap(),
+        );
+        let vary: Vec<_> = response.headers().get_all(VARY).iter().collect();
+        assert_eq!(vary, ["accept-encoding"], "{accept_encoding}");
+    }
+}
@@ -0,0 +1,131 @@
+use generic_http_router::{Router, WarmedRoute, WarmupProblem};
+use http::{Method, Request, StatusCode};
+
+const CONFIG: &str = r#"{"endpoints": [
+    {"method": "GET", "path": "/users/:id", "description": "", "controller": "",
+     "body_template": "{\"id\": \"{params.id}\"}", "content_type": "application/json"},
+    {"method": "POST", "path": "/users", "description": "", "controller": "",
+     "body_template": "created"},
+    {"method": "GET", "path": "/users/warmup", "description": "", "controller": "",
+     "body_template": "literal"},
+    {"method": "GET", "path": "/files/*path", "description": "", "controller": "",
+     "body_template": "{params.path}"},
+    {"method": "GET", "path": "/orders/:order", "description": "", "controller": "",
+     "body_template": "{\"id\": \"{params.id}\", \"page\": \"{params.page|1}\"}",
+     "content_type": "application/json"}
+]}"#;
+
+fn route(method: Method, pattern: &str, sample_path: &str, matched: bool) -> WarmedRoute {
+    WarmedRoute {
+        method,
+        pattern: pattern.to_string(),
+        sample_path: sample_path.to_string(),
+        matched,
+    }
+}
+
+#[test]
+fn the_report_lists_every_route() {
+    let router = Router::from_json_str(CONFIG).unwrap();
+    let report = router.warm_up();
+    assert_eq!(
+        report.routes,
+        [
+            route(Method::GET, "/files/*path", "/files/warmup", true),
+            route(Method::GET, "/orders/:order", "/orders/warmup", true),
+            route(Method::POST, "/users", "/users", true),
+            route(Method::GET, "/users/:id", "/users/warmup", false),
+            route(Method::GET, "/users/warmup", "/users/warmup", true),
+        ]
+    );
+    let steps: Vec<&str> = report.timings.iter().map(|(step, _)| *step).collect();
+    #[cfg(not(feature = "schema"))]
+    assert_eq!(steps, ["allowed_methods", "routes", "templates"]);
+    #[cfg(feature = "schema")]
+    assert_eq!(steps, ["allowed_methods", "routes", "templates", "schemas"]);
+
+    // The router still routes as before.
+    let response = router.route(Request::get("/users/7").body(String::new()).unwrap());
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), r#"{"id": "7"}"#);
+}
+
+#[test]
+fn a_template_using_a_missing_param_is_a_problem() {
+    let router = Router::from_json_str(CONFIG).unwrap();
+    let report = router.warm_up();
+    assert!(!report.is_clean());
+    // Even with a default.
+    assert_eq!(
+        report.problems,
+        [
+            WarmupProblem {
+                method: Method::GET,
+                pattern: "/orders/:order".to_string(),
+                problem: "the body_template uses {params.id}, which the path doesn't have"
+                    .to_string(),
+            },
+            WarmupProblem {
+                method: Method::GET,
+                pattern: "/orders/:order".to_string(),
+                problem: "the body_template uses {params.page}, which the path doesn't have"
+                    .to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn a_clean_config_has_no_problems() {
+    let router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "GET", "path": "/users/:id", "description": "", "controller": "users"},
+            {"method": "DELETE", "path": "/users/:id", "description": "", "controller": "users"}
+        ]}"#,
+    )
+    .unwrap();
+    let report = router.warm_up();
+    assert!(report.is_clean(), "{:?}", report.problems);
+    assert_eq!(report.routes.len(), 2);
+    assert!(report.routes.iter().all(|route| route.matched));
+}
+
+#[cfg(feature = "schema")]
+#[test]
+fn a_schema_rejecting_everything_is_a_problem() {
+    let router = Router::from_json_str(
+        r#"{"endpoints": [
+            {"method": "POST", "path": "/users", "description": "", "controller": "users",
+             "request_schema": {"type": "object"}},
+            {"method": "POST", "path": "/orders", "description": "", "controller": "orders",
+             "request_schema": {"type": "integer", "minimum": 10, "maximum": 1}},
+            {"method": "GET", "path": "/orders", "description": "", "controller": "orders",
+             "response_schema": {"enum": []}}
+        ]}"#,
+    )
+    .unwrap();
+    let problems: Vec<(String, String)> = router
+        .warm_up()
+        .problems
+        .into_iter()
+        .map(|problem| {
+            (
+                format!("{} {}", problem.method, problem.pattern),
+                problem.problem,
+            )
+        })
+        .collect();
+    assert_eq!(
+        problems,
+        [
+            (
+                "GET /orders".to_string(),
+                "the response_schema rejects every value".to_string()
+            ),
+            (
+                "POST /orders".to_string(),
+                "the request_schema rejects every value".to_string()
+            ),
+        ]
+    );
+}
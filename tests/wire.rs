@@ -0,0 +1,154 @@
+#![cfg(feature = "wire")]
+
+use generic_http_router::wire::{self, WireError, WireLimits};
+use http::{Method, Response, StatusCode, Version};
+
+#[test]
+fn parses_a_request_with_a_body() {
+    let buf = b"POST /users?page=2 HTTP/1.1\r\nHost: example.com\r\nContent-Length: 5\r\n\r\nhello";
+    let (req, len) = wire::parse_request(buf).unwrap();
+    assert_eq!(len, buf.len());
+    assert_eq!(req.method(), Method::POST);
+    assert_eq!(req.uri(), "/users?page=2");
+    assert_eq!(req.version(), Version::HTTP_11);
+    assert_eq!(req.headers()["host"], "example.com");
+    assert_eq!(req.body(), b"hello");
+}
+
+#[test]
+fn parses_pipelined_requests() {
+    let buf = b"GET /a HTTP/1.1\r\n\r\nGET /b HTTP/1.0\r\n\r\n";
+    let (first, len) = wire::parse_request(buf).unwrap();
+    assert_eq!(first.uri(), "/a");
+    let (second, rest) = wire::parse_request(&buf[len..]).unwrap();
+    assert_eq!(second.uri(), "/b");
+    assert_eq!(second.version(), Version::HTTP_10);
+    assert_eq!(len + rest, buf.len());
+}
+
+#[test]
+fn incomplete_requests() {
+    assert_eq!(
+        wire::parse_request(b"GET /a HTTP/1.1\r\nHost: x").unwrap_err(),
+        WireError::Incomplete
+    );
+    assert_eq!(
+        wire::parse_request(b"POST /a HTTP/1.1\r\nContent-Length: 5\r\n\r\nhel").unwrap_err(),
+        WireError::Incomplete
+    );
+}
+
+#[test]
+fn request_targets_by_method() {
+    let parse = |line: &str| wire::parse_request(format!("{line}\r\n\r\n").as_bytes());
+    assert!(parse("GET http://example.com/a HTTP/1.1").is_ok());
+    assert!(parse("CONNECT example.com:443 HTTP/1.1").is_ok());
+    assert!(parse("OPTIONS * HTTP/1.1").is_ok());
+    assert_eq!(
+        parse("GET * HTTP/1.1").unwrap_err(),
+        WireError::InvalidTarget
+    );
+    assert_eq!(
+        parse("GET example.com:443 HTTP/1.1").unwrap_err(),
+        WireError::InvalidTarget
+    );
+    assert_eq!(
+        parse("CONNECT /a HTTP/1.1").unwrap_err(),
+        WireError::InvalidTarget
+    );
+}
+
+#[test]
+fn content_length_headers() {
+    let parse = |headers: &str| {
+        wire::parse_request(format!("POST /a HTTP/1.1\r\n{headers}\r\n\r\nabcd").as_bytes())
+            .map(|(req, _)| req.into_body())
+    };
+    assert_eq!(parse("Content-Length: 4, 4").unwrap(), b"abcd");
+    assert_eq!(
+        parse("Content-Length: 4\r\nContent-Length: 4").unwrap(),
+        b"abcd"
+    );
+    assert_eq!(
+        parse("Content-Length: 4\r\nContent-Length: 3").unwrap_err(),
+        WireError::InvalidContentLength
+    );
+    assert_eq!(
+        parse("Content-Length: +4").unwrap_err(),
+        WireError::InvalidContentLength
+    );
+    assert_eq!(
+        parse("Content-Length: 99999999999999999999999").unwrap_err(),
+        WireError::InvalidContentLength
+    );
+    assert_eq!(
+        parse("Transfer-Encoding: chunked").unwrap_err(),
+        WireError::UnsupportedTransferEncoding
+    );
+    assert_eq!(
+        parse("Transfer-Encoding: chunked\r\nContent-Length: 4").unwrap_err(),
+        WireError::ConflictingLength
+    );
+}
+
+#[test]
+fn limits() {
+    let limits = WireLimits {
+        max_header_bytes: 40,
+        max_headers: 1,
+        max_body_bytes: 3,
+    };
+    assert_eq!(
+        wire::parse_request_with(
+            b"GET /a-very-long-path-indeed-longer-than-the-limit HTTP/1.1\r\n",
+            &limits
+        )
+        .unwrap_err(),
+        WireError::HeadersTooLarge { limit: 40 }
+    );
+    assert!(matches!(
+        wire::parse_request_with(b"GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\n\r\n", &limits),
+        Err(WireError::Malformed(httparse::Error::TooManyHeaders))
+    ));
+    assert_eq!(
+        wire::parse_request_with(b"POST / HTTP/1.1\r\nContent-Length: 4\r\n\r\n", &limits)
+            .unwrap_err(),
+        WireError::BodyTooLarge { limit: 3 }
+    );
+}
+
+#[test]
+fn malformed_requests() {
+    assert!(matches!(
+        wire::parse_request(b"GET /a HTTP/1.1\r\nBad Header: x\r\n\r\n"),
+        Err(WireError::Malformed(_))
+    ));
+    assert!(matches!(
+        wire::parse_request(b"GET /a HTTP/1.1\r\nA: 1\r\n folded\r\n\r\n"),
+        Err(WireError::Malformed(_))
+    ));
+}
+
+#[test]
+fn writes_responses() {
+    let res = Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "text/plain")
+        .body(b"hi".to_vec())
+        .unwrap();
+    let mut out = Vec::new();
+    wire::write_response(&res, &mut out);
+    assert_eq!(
+        out,
+        b"HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: 2\r\n\r\nhi"
+    );
+
+    let res = Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .version(Version::HTTP_10)
+        .body(b"ignored".to_vec())
+        .unwrap();
+    let mut out = Vec::new();
+    wire::write_response(&res, &mut out);
+    assert_eq!(out, b"HTTP/1.0 204 No Content\r\n\r\n");
+}